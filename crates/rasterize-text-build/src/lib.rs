@@ -0,0 +1,250 @@
+//! Render fixed strings at build time with [`rasterize_text`], and emit their pixels as Rust consts, so that firmware
+//! (ex. for a microcontroller with a small display) can ship pre-rendered labels without fonts or a rasterizer.
+//!
+//! # Examples
+//!
+//! The labels are rendered by the build script (`build.rs`) of the firmware, which has `rasterize-text-build` as a
+//! build dependency, and are written to a file in `OUT_DIR`.
+//!
+//! ```rust,no_run
+//! use rasterize_text::{read_font_file, Options};
+//! use rasterize_text_build::{write_labels, Label, PixelFormat};
+//!
+//! let font    = read_font_file(&"assets/fonts/Label.ttf")?;
+//! let options = Options { size: 16.0, ..Default::default() };
+//! let labels  = [
+//!     Label::new("TEMPERATURE", "Temperature", PixelFormat::Mono),
+//!     Label::new("HUMIDITY", "Humidity", PixelFormat::Mono),
+//! ];
+//! let out = std::path::PathBuf::from(std::env::var("OUT_DIR")?).join("labels.rs");
+//! write_labels(&labels, &font, &options, &out)?;
+//! println!("cargo:rerun-if-changed=assets/fonts/Label.ttf");
+//! # Ok::<(), color_eyre::eyre::Report>(())
+//! ```
+//!
+//! The firmware then includes the file, and draws the pixels of each label with its size and stride (the number of
+//! bytes in each row):
+//!
+//! ```rust,ignore
+//! include!(concat!(env!("OUT_DIR"), "/labels.rs"));
+//!
+//! display.draw_mono(TEMPERATURE_WIDTH, TEMPERATURE_HEIGHT, TEMPERATURE_STRIDE, &TEMPERATURE);
+//! ```
+
+use image::{ImageBuffer, Rgba};
+use rasterize_text::{rasterize_with_options, Options, RasterizeError};
+use rusttype::Font;
+use std::fmt::{Display, Formatter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// The number of bytes on each line of a generated array.
+const BYTES_PER_LINE: usize = 16;
+
+#[derive(Debug, thiserror::Error)]
+pub enum BuildError {
+    #[error(
+        "Invalid label name: {0:?}. Names must be uppercase Rust identifiers (ex. 'HELLO_WORLD')."
+    )]
+    InvalidNameError(String),
+    #[error("Unknown pixel format: {0:?}. Formats are 'rgba8', 'rgb565', 'alpha8' and 'mono'.")]
+    UnknownPixelFormatError(String),
+    #[error("The label name {0:?} is used more than once.")]
+    DuplicateNameError(String),
+    #[error("Failed to write the generated code to {1:?}.")]
+    WriteError(#[source] std::io::Error, PathBuf),
+    #[error(transparent)]
+    RasterizeError(#[from] RasterizeError),
+}
+
+/// How the pixels of a [`Label`] are stored, row by row from the top of the image.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum PixelFormat {
+    /// 4 bytes for each pixel, with the colors premultiplied by their alpha like the images of [`rasterize_text`].
+    Rgba8,
+    /// 2 bytes for each pixel, in 16 bit RGB 5:6:5 (little endian) over a black background, for color displays.
+    Rgb565,
+    /// 1 byte for each pixel, which is the alpha (coverage) of the text, for drawing the text in any color.
+    #[default]
+    Alpha8,
+    /// 1 bit for each pixel, which is set where the alpha of the text is at least half, for monochrome displays.
+    /// The first pixel of each byte is its most significant bit, and each row starts on a new byte.
+    Mono,
+}
+
+impl PixelFormat {
+    /// Returns the number of bytes in each row of an image that is `width` pixels wide.
+    pub fn stride(&self, width: u32) -> usize {
+        let width = width as usize;
+        match self {
+            PixelFormat::Rgba8 => width * 4,
+            PixelFormat::Rgb565 => width * 2,
+            PixelFormat::Alpha8 => width,
+            PixelFormat::Mono => width.div_ceil(8),
+        }
+    }
+
+    /// Returns the bytes of the pixels of an image of rasterized text in the format.
+    pub fn encode(&self, image: &ImageBuffer<Rgba<u8>, Vec<u8>>) -> Vec<u8> {
+        let stride = self.stride(image.width());
+        let mut bytes = vec![0; stride * image.height() as usize];
+        for (x, y, Rgba([r, g, b, a])) in image.enumerate_pixels() {
+            let (x, row) = (x as usize, y as usize * stride);
+            match self {
+                PixelFormat::Rgba8 => bytes[row + x * 4..][..4].copy_from_slice(&[*r, *g, *b, *a]),
+                PixelFormat::Rgb565 => {
+                    let rgb565 =
+                        (u16::from(*r) >> 3) << 11 | (u16::from(*g) >> 2) << 5 | u16::from(*b) >> 3;
+                    bytes[row + x * 2..][..2].copy_from_slice(&rgb565.to_le_bytes());
+                }
+                PixelFormat::Alpha8 => bytes[row + x] = *a,
+                PixelFormat::Mono => {
+                    if *a >= 128 {
+                        bytes[row + x / 8] |= 0x80 >> (x % 8);
+                    }
+                }
+            }
+        }
+        bytes
+    }
+}
+
+impl Display for PixelFormat {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        let lowercase = format!("{:?}", self).to_lowercase();
+        write!(f, "{lowercase}")
+    }
+}
+
+impl FromStr for PixelFormat {
+    type Err = BuildError;
+
+    /// Returns a [`PixelFormat`] converted from a [`str`].
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use rasterize_text_build::PixelFormat;
+    /// use std::str::FromStr;
+    ///
+    /// assert_eq!(PixelFormat::from_str("rgb565")?, PixelFormat::Rgb565);
+    /// # Ok::<(), color_eyre::eyre::Report>(())
+    /// ```
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        let format = match format {
+            "rgba8" => PixelFormat::Rgba8,
+            "rgb565" => PixelFormat::Rgb565,
+            "alpha8" => PixelFormat::Alpha8,
+            "mono" => PixelFormat::Mono,
+            _ => Err(BuildError::UnknownPixelFormatError(format.to_string()))?,
+        };
+
+        Ok(format)
+    }
+}
+
+/// A fixed string that is rendered at build time, by [`generate_labels`] and [`write_labels`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Label {
+    /// The name of the consts of the label (ex. `HELLO` for `HELLO`, `HELLO_WIDTH`, `HELLO_HEIGHT` and `HELLO_STRIDE`),
+    /// which is an uppercase Rust identifier.
+    pub name: String,
+    /// The text of the label.
+    pub text: String,
+    /// How the pixels of the label are stored.
+    pub format: PixelFormat,
+}
+
+impl Label {
+    /// Returns a label with a name, text and pixel format.
+    pub fn new(name: impl Into<String>, text: impl Into<String>, format: PixelFormat) -> Self {
+        Label {
+            name: name.into(),
+            text: text.into(),
+            format,
+        }
+    }
+}
+
+/// Rasterize labels with a [`Font`], as configured by [`Options`], and return Rust code with the consts of their pixels.
+///
+/// Each label has four consts: its pixels as an array of bytes in its [`PixelFormat`] (ex. `HELLO: [u8; 312]`), and
+/// its `_WIDTH` and `_HEIGHT` in pixels and `_STRIDE` in bytes. The consts don't depend on any crate, so the code can
+/// be included in `no_std` firmware.
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{read_font_bytes, Options, EN_FONT};
+/// use rasterize_text_build::{generate_labels, Label, PixelFormat};
+///
+/// let font    = read_font_bytes(EN_FONT)?;
+/// let options = Options { size: 12.0, ..Default::default() };
+/// let labels  = [Label::new("OK", "OK", PixelFormat::Mono), Label::new("CANCEL", "Cancel", PixelFormat::Alpha8)];
+///
+/// let code = generate_labels(&labels, &font, &options)?;
+/// assert!(code.contains("pub const OK_WIDTH: u32 = "));
+/// assert!(code.contains("pub const CANCEL: [u8; "));
+///
+/// // Names must be usable as the names of consts
+/// let invalid = [Label::new("ok", "OK", PixelFormat::Mono)];
+/// assert!(generate_labels(&invalid, &font, &options).is_err());
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+pub fn generate_labels(
+    labels: &[Label],
+    font: &Font,
+    options: &Options,
+) -> Result<String, BuildError> {
+    let mut code = String::from("// Generated by rasterize-text-build. Do not edit.\n");
+    for (i, label) in labels.iter().enumerate() {
+        let name = &label.name;
+        let valid = name.starts_with(|c: char| c.is_ascii_uppercase())
+            && name
+                .chars()
+                .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_');
+        if !valid {
+            return Err(BuildError::InvalidNameError(name.clone()));
+        }
+        if labels[..i].iter().any(|other| other.name == *name) {
+            return Err(BuildError::DuplicateNameError(name.clone()));
+        }
+
+        let image = rasterize_with_options(&label.text, font, options)?;
+        let bytes = label.format.encode(&image);
+        // Writing to a String can't fail
+        let _ = writeln!(code);
+        let _ = writeln!(code, "/// {:?} in {:?} pixels.", label.text, label.format);
+        let _ = writeln!(code, "pub const {name}_WIDTH: u32 = {};", image.width());
+        let _ = writeln!(code, "pub const {name}_HEIGHT: u32 = {};", image.height());
+        let _ = writeln!(
+            code,
+            "pub const {name}_STRIDE: usize = {};",
+            label.format.stride(image.width())
+        );
+        let _ = writeln!(code, "pub const {name}: [u8; {}] = [", bytes.len());
+        for line in bytes.chunks(BYTES_PER_LINE) {
+            let line: Vec<String> = line.iter().map(|byte| format!("0x{byte:02x},")).collect();
+            let _ = writeln!(code, "    {}", line.join(" "));
+        }
+        let _ = writeln!(code, "];");
+    }
+    Ok(code)
+}
+
+/// Rasterize labels with [`generate_labels`], and write the code to a file (ex. in `OUT_DIR` from a build script).
+///
+/// The file is only written if its code changed, so that the crates that include it aren't rebuilt needlessly.
+pub fn write_labels(
+    labels: &[Label],
+    font: &Font,
+    options: &Options,
+    path: impl AsRef<Path>,
+) -> Result<(), BuildError> {
+    let path = path.as_ref();
+    let code = generate_labels(labels, font, options)?;
+    if std::fs::read_to_string(path).is_ok_and(|existing| existing == code) {
+        return Ok(());
+    }
+    std::fs::write(path, code).map_err(|e| BuildError::WriteError(e, path.to_path_buf()))
+}
@@ -0,0 +1,59 @@
+use chrono::{Datelike, Local};
+use color_eyre::eyre::{Report, Result};
+use image::{ImageBuffer, Rgba};
+use rasterize_text::{Options, WeekStart};
+use rusttype::Font;
+
+type Image = ImageBuffer<Rgba<u8>, Vec<u8>>;
+
+/// Render a month of a calendar, with today highlighted.
+/// ---
+/// The current month is rendered, unless a month is provided with `--month`. Today is drawn white on black,
+/// like a cell of an e-ink display, when it is in the month.
+#[derive(Debug, clap::Args)]
+pub struct Calendar {
+    /// The month to render, instead of the current month.
+    #[clap(help = "The month to render as YYYY-MM (ex. '2024-02'). If no month is provided, the current month is used.")]
+    #[clap(long, value_parser = parse_month)]
+    #[clap(required = false)]
+    pub month: Option<(i32, u8)>,
+
+    /// The day that weeks start on.
+    #[clap(help = "The day that weeks start on, which is the first column: monday or sunday.")]
+    #[clap(long)]
+    #[clap(default_value_t = WeekStart::default())]
+    pub week_start: WeekStart,
+
+    /// Don't highlight today.
+    #[clap(help = "Don't highlight today.")]
+    #[clap(long)]
+    pub no_today: bool,
+}
+
+impl Calendar {
+    /// Render the month as a calendar with a [`Font`], as configured by [`Options`].
+    pub fn render(&self, font: &Font, options: &Options) -> Result<Image, Report> {
+        let today = Local::now().date_naive();
+        let (year, month) = self.month.unwrap_or((today.year(), today.month() as u8));
+        let is_this_month = (year, month) == (today.year(), today.month() as u8);
+        let calendar = rasterize_text::Calendar {
+            year,
+            month,
+            week_start: self.week_start,
+            today: (is_this_month && !self.no_today).then_some(today.day() as u8),
+            ..Default::default()
+        };
+        let image = rasterize_text::rasterize_calendar(&calendar, font, options)?;
+        Ok(image)
+    }
+}
+
+/// Returns a year and month converted from a [`str`] of the form `YYYY-MM`.
+fn parse_month(month: &str) -> Result<(i32, u8), String> {
+    let error = || format!("Invalid month: {month:?}. Expected YYYY-MM (ex. '2024-02').");
+    let (year, month) = month.rsplit_once('-').ok_or_else(error)?;
+    match (year.parse::<i32>(), month.parse::<u8>()) {
+        (Ok(year), Ok(month)) if (1..=12).contains(&month) => Ok((year, month)),
+        _ => Err(error()),
+    }
+}
@@ -0,0 +1,80 @@
+use color_eyre::eyre::{Report, Result};
+use image::{ImageBuffer, Rgba};
+use rasterize_text::{ChatStyle, Color, Options, Span};
+use rusttype::Font;
+
+type Image = ImageBuffer<Rgba<u8>, Vec<u8>>;
+
+/// Render the text as a chat message, with an avatar, a name and a timestamp.
+/// ---
+/// The message is laid out like a message in Discord or Slack, with the avatar drawn as a circle on the left,
+/// and the name and timestamp on a line above the text. The text is wrapped at `--wrap-width`, and can be styled
+/// with `--ansi` or `--math`.
+#[derive(Debug, clap::Args)]
+pub struct Chat {
+    /// Path to the avatar image.
+    #[clap(help = "Path to a PNG or JPEG avatar image, which is scaled and cropped to a circle.")]
+    #[clap(long)]
+    #[clap(required = true)]
+    pub avatar: std::path::PathBuf,
+
+    /// Name of the sender.
+    #[clap(help = "Name of the sender of the message, drawn in bold above the text.")]
+    #[clap(long)]
+    #[clap(required = true)]
+    pub name: String,
+
+    /// Timestamp of the message.
+    #[clap(help = "Timestamp of the message, drawn after the name (ex. 'Today at 9:41').")]
+    #[clap(long)]
+    #[clap(default_value = "")]
+    pub timestamp: String,
+
+    /// Width and height of the avatar in pixels.
+    #[clap(help = "Width and height of the avatar in pixels.")]
+    #[clap(long)]
+    #[clap(default_value_t = ChatStyle::default().avatar_size)]
+    pub avatar_size: u32,
+
+    /// Space around the message in pixels.
+    #[clap(help = "Space around the message in pixels.")]
+    #[clap(long)]
+    #[clap(default_value_t = ChatStyle::default().padding)]
+    pub padding: f32,
+
+    /// Color of the name.
+    #[clap(help = "Color of the name as a CSS color or a space delimited RGBA value. If no color is provided, the text color is used.")]
+    #[clap(long)]
+    #[clap(required = false)]
+    pub name_color: Option<Color>,
+
+    /// Color of the timestamp.
+    #[clap(help = "Color of the timestamp as a CSS color or a space delimited RGBA value.")]
+    #[clap(long)]
+    #[clap(default_value_t = ChatStyle::default().timestamp_color)]
+    pub timestamp_color: Color,
+}
+
+impl Chat {
+    /// Render spans of text as a chat message with a [`Font`], as configured by [`Options`].
+    pub fn render(&self, spans: &[Span], font: &Font, options: &Options) -> Result<Image, Report> {
+        let avatar = image::open(&self.avatar)?;
+        let style = ChatStyle {
+            avatar_size: self.avatar_size,
+            padding: self.padding,
+            name_color: self.name_color,
+            timestamp_color: self.timestamp_color,
+            ..Default::default()
+        };
+        let image = rasterize_text::chat_message(
+            &avatar,
+            &self.name,
+            &self.timestamp,
+            spans,
+            font,
+            &style,
+            options,
+        )?;
+        Ok(image)
+    }
+}
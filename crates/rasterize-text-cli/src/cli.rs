@@ -1,6 +1,17 @@
-use crate::Verbosity;
-use clap::Parser;
-use rasterize_text::Color;
+use crate::{
+    Calendar, Chat, Clock, Compare, Coverage, Exec, Fonts, Icon, Specimen, Tail, Verbosity,
+    Waterfall,
+};
+#[cfg(feature = "template")]
+use crate::{Merge, Template};
+use clap::{Parser, Subcommand, ValueEnum};
+use regex::Regex;
+use std::ops::Range;
+use rasterize_text::{
+    Align, Anchor, Blending, Color, ColorTag, DiffLayout, Direction, Effect, EngraveOptions, Fit,
+    Justification, LanguageTag, LastLine, LicensePolicy, LineNumbers, Options, PaperSize,
+    PixelSnap, PlotOptions, Selection, Side, TabStops, TextTransform, TofuStyle, Whitespace,
+};
 
 /// The command-line interface (CLI).
 /// ---
@@ -16,21 +27,170 @@ use rasterize_text::Color;
 #[clap(after_help = "This is long message after help.")]
 #[clap(trailing_var_arg = true)]
 #[clap(arg_required_else_help = true)]
+#[clap(subcommand_negates_reqs = true)]
 pub struct Cli {
-    /// Single-line of text to render.
-    #[clap(help = "Single-line of text to render.")]
+    /// Render something other than the text.
+    #[clap(subcommand)]
+    pub command: Option<Command>,
+
+    /// Text to render.
+    #[clap(help = "Text to render. Newlines start a new line.")]
     #[clap(short = 't', long)]
     #[clap(required = true)]
-    pub text: String,
+    pub text: Option<String>,
+
+    /// Interpret the text as a math expression.
+    #[clap(help = "Interpret the text as a math expression, with superscripts (x^2), subscripts (x_0), fractions (1/2) and groups in braces (e^{-x}).")]
+    #[clap(long)]
+    pub math: bool,
+
+    /// Interpret ANSI escape sequences in the text.
+    #[clap(help = "Interpret ANSI escape sequences in the text (ex. the output of 'ls --color=always'), drawing their foreground and background colors, bold and underline.")]
+    #[clap(long, conflicts_with = "math")]
+    pub ansi: bool,
+
+    /// Hide spoilers marked up in the text behind bars.
+    #[clap(help = "Hide spoilers in the text, surrounded by '||' (ex. 'The butler ||did it||') or by <redact> and </redact>, behind solid bars in the color of the text.")]
+    #[clap(long, conflicts_with_all = ["math", "ansi"])]
+    pub spoilers: bool,
+
+    /// Draw spoilers over faint bars.
+    #[clap(help = "Draw the text of --spoilers over faint bars instead of hiding it, for the revealed layer of a preview.")]
+    #[clap(long, requires = "spoilers")]
+    pub reveal_spoilers: bool,
+
+    /// Style URLs, @mentions and #hashtags in the text.
+    #[clap(help = "Style URLs (underlined), @mentions and #hashtags in the text in --auto-style-color, like a social network post.")]
+    #[clap(long, conflicts_with_all = ["math", "ansi", "spoilers"])]
+    pub auto_style: bool,
+
+    /// Style the parts of the text that match a regular expression.
+    #[clap(help = "Style the parts of the text that match a regular expression (ex. 'TICKET-\\d+') in --auto-style-color, as with --auto-style. If the expression has a capture group, only its first group is styled. Can be repeated.")]
+    #[clap(long, conflicts_with_all = ["math", "ansi", "spoilers"])]
+    pub auto_style_pattern: Vec<Regex>,
+
+    /// Color of the text that is styled with --auto-style.
+    #[clap(help = "Color of the text that is styled by --auto-style and --auto-style-pattern, as a CSS color or a space delimited RGBA value.")]
+    #[clap(long)]
+    #[clap(default_value_t = Color { r: 29, g: 155, b: 240, a: 255 })]
+    pub auto_style_color: Color,
+
+    /// Render math surrounded by '$' with LaTeX.
+    #[cfg(feature = "latex")]
+    #[clap(help = "Render math surrounded by '$' (ex. 'Area: $\\pi r^2$') with LaTeX, using an external TeX installation (latex and dvipng).")]
+    #[clap(long, conflicts_with = "cache_dir")]
+    pub latex: bool,
 
     /// Output png file.
-    #[clap(help = "Output PNG file path.")]
+    #[clap(help = "Output PNG file path, or the path of the exported outlines with --export. For merge, a pattern of the paths of the rows, or of the URLs of their objects in an S3 or GCS bucket with the object_store feature (ex. 's3://conference/badges/{name}.png').")]
     #[clap(short = 'o', long)]
     #[clap(required = true)]
-    pub output: std::path::PathBuf,
+    pub output: Option<std::path::PathBuf>,
+
+    /// How the color space of the output png file is declared.
+    #[clap(help = "How the color space (sRGB) of the output PNG file is declared: srgb (an sRGB chunk), icc (an embedded sRGB ICC profile, for print workflows) or none. Both srgb and icc also write a cICP chunk.")]
+    #[clap(long)]
+    #[clap(default_value_t = ColorTag::default())]
+    pub color_tag: ColorTag,
+
+    /// Embed the render parameters in the output png file.
+    #[clap(help = "Embed the render parameters (a hash of the text, the font name, size and color, and the version of rasterize-text) in the output PNG file as text chunks, so that it describes how it was rendered.")]
+    #[clap(long)]
+    pub embed_metadata: bool,
+
+    /// Embed the text in the output png file as its description.
+    #[clap(help = "Embed the text, its language and direction in the output PNG file as its description (an iTXt chunk), so that publishing tools can use it as the alt text of the image.")]
+    #[clap(long)]
+    pub embed_alt_text: bool,
+
+    /// Write the text, its language and direction to a JSON file.
+    #[clap(help = "Write the text, its language and direction to a sidecar JSON file (ex. 'output.json'), with the fields text, lang and dir, so that publishing tools can use it as the alt text of the image.")]
+    #[clap(long)]
+    pub alt_text_file: Option<std::path::PathBuf>,
+
+    /// Directory of rendered images to read the output from.
+    #[clap(help = "Directory of rendered PNG files, keyed by a hash of the text, fonts and options, which the output is copied from if the same text was rendered before (and which it is stored in otherwise). Text is cached with its --math, --ansi, --spoilers and --auto-style spans, but the other outputs (ex. --export or --bubble) and the subcommands aren't cached.")]
+    #[clap(long, conflicts_with_all = ["rgba16", "export", "bubble", "nine_patch", "background_image", "diff", "hershey_font"])]
+    #[clap(required = false)]
+    pub cache_dir: Option<std::path::PathBuf>,
+
+    /// Print a hash of the output image.
+    #[clap(help = "Print a hash of the pixels of the output image, which is the same whenever the same image is rendered (ex. to skip uploading unchanged images).")]
+    #[clap(long, conflicts_with = "rgba16")]
+    pub print_hash: bool,
+
+    /// List the vendored fonts.
+    #[clap(help = "List the fonts that are provided within the application (vendored), with their versions, licenses and checksums, and exit. The same as the 'fonts list' subcommand.")]
+    #[clap(long, exclusive = true)]
+    pub list_fonts: bool,
+
+    /// Write a subset of the font with the glyphs of the text.
+    #[clap(help = "Write a subset of the font with only the glyphs of the text to this TrueType font file (ex. 'title.ttf'), to serve as a webfont that matches the image.")]
+    #[clap(long)]
+    #[clap(required = false)]
+    pub subset_font: Option<std::path::PathBuf>,
 
-    /// Text color as a space delimited rgba value.
-    #[clap(help = "Text color as a space delimited RGBA value.")]
+    /// Save the output png file with 16 bits per channel.
+    #[clap(help = "Save the output PNG file with 16 bits per channel, for compositing and color grading without banding. Applies to plain, math and ANSI text.")]
+    #[clap(long, conflicts_with_all = ["bubble", "nine_patch", "background_image"])]
+    pub rgba16: bool,
+
+    /// Export the outlines of the glyphs for engraving or plotting.
+    #[clap(help = "Export the outlines of the glyphs to the output file for engraving or plotting, instead of rasterizing them. Applies to plain, math and ANSI text.")]
+    #[clap(long, value_enum, conflicts_with_all = ["bubble", "nine_patch", "background_image", "rgba16", "print_hash"])]
+    #[clap(required = false)]
+    pub export: Option<ExportFormat>,
+
+    /// Tolerance of exported outlines in pixels.
+    #[clap(help = "The most distance in pixels between the curves of exported outlines and the straight lines that they are flattened to.")]
+    #[clap(long)]
+    #[clap(default_value_t = EngraveOptions::default().tolerance)]
+    pub tolerance: f64,
+
+    /// Size of a pixel of exported outlines in millimeters.
+    #[clap(help = "The size of a pixel of exported outlines in millimeters.")]
+    #[clap(long)]
+    #[clap(default_value_t = EngraveOptions::default().scale)]
+    pub mm_per_pixel: f64,
+
+    /// Height that the tool moves between outlines in G-code.
+    #[clap(help = "The height in millimeters that the tool moves between outlines in G-code, above the surface at 0.")]
+    #[clap(long, allow_hyphen_values = true)]
+    #[clap(default_value_t = EngraveOptions::default().safe_z)]
+    pub safe_z: f64,
+
+    /// Height that the tool cuts at in G-code.
+    #[clap(help = "The height in millimeters that the tool cuts at in G-code, which is negative to cut below the surface (ex. -0.5).")]
+    #[clap(long, allow_hyphen_values = true)]
+    #[clap(default_value_t = EngraveOptions::default().cut_z)]
+    pub cut_z: f64,
+
+    /// Speed that the tool cuts at in G-code.
+    #[clap(help = "The speed in millimeters per minute that the tool cuts at in G-code.")]
+    #[clap(long)]
+    #[clap(default_value_t = EngraveOptions::default().feed_rate)]
+    pub feed_rate: f64,
+
+    /// Paper that HPGL is scaled to fit.
+    #[clap(help = "The paper that HPGL is scaled to fit within the margin of, and centered on: a4, a3 or letter (landscape). If no paper is provided, HPGL is scaled by --mm-per-pixel.")]
+    #[clap(long)]
+    #[clap(required = false)]
+    pub paper: Option<PaperSize>,
+
+    /// Margin around the edges of the paper in HPGL.
+    #[clap(help = "The margin around the edges of the paper in millimeters, for HPGL.")]
+    #[clap(long)]
+    #[clap(default_value_t = PlotOptions::default().margin)]
+    pub paper_margin: f64,
+
+    /// Pen that HPGL is plotted with.
+    #[clap(help = "The number of the pen that HPGL is plotted with.")]
+    #[clap(long)]
+    #[clap(default_value_t = PlotOptions::default().pen)]
+    pub pen: u8,
+
+    /// Text color as a CSS color or a space delimited rgba value.
+    #[clap(help = "Text color as a CSS color (ex. 'red', '#ff000080', 'hsl(200 50% 40% / 0.5)') or a space delimited RGBA value.")]
     #[clap(short = 'c', long)]
     #[clap(default_value_t = Color::default())]
     pub color: Color,
@@ -41,12 +201,481 @@ pub struct Cli {
     #[clap(default_value_t = 50.0)]
     pub size: f32,
 
+    /// Maximum line width in pixels, beyond which text wraps onto the next line.
+    #[clap(help = "Maximum line width in pixels, beyond which text wraps onto the next line. If no width is provided, text is only broken at newlines.")]
+    #[clap(short = 'w', long)]
+    #[clap(required = false)]
+    pub wrap_width: Option<f32>,
+
+    /// Horizontal alignment of lines.
+    #[clap(help = "Horizontal alignment of lines within the wrap width (or the width of the longest line): left, center, right, or justify (stretch lines to fill the wrap width).")]
+    #[clap(short = 'a', long)]
+    #[clap(default_value_t = Align::default())]
+    pub align: Align,
+
+    /// Maximum space added between words when justifying, as a fraction of the font size.
+    #[clap(help = "Maximum space added between words when justifying, as a fraction of the font size.")]
+    #[clap(long)]
+    #[clap(default_value_t = Justification::default().max_word_stretch)]
+    pub max_word_stretch: f32,
+
+    /// Maximum space added between letters when justifying, as a fraction of the font size.
+    #[clap(help = "Maximum space added between letters when justifying, as a fraction of the font size.")]
+    #[clap(long)]
+    #[clap(default_value_t = Justification::default().max_letter_stretch)]
+    pub max_letter_stretch: f32,
+
+    /// Alignment of the last line of each paragraph when justifying.
+    #[clap(help = "Alignment of the last line of each paragraph when justifying: left, center, right, or justify.")]
+    #[clap(long)]
+    #[clap(default_value_t = LastLine::default())]
+    pub last_line: LastLine,
+
+    /// Indent of the first line of each paragraph in pixels.
+    #[clap(help = "Indent of the first line of each paragraph in pixels.")]
+    #[clap(long)]
+    #[clap(default_value_t = 0.0)]
+    pub first_line_indent: f32,
+
+    /// Indent of every line of a paragraph except the first in pixels.
+    #[clap(help = "Indent of every line of a paragraph except the first in pixels.")]
+    #[clap(long)]
+    #[clap(default_value_t = 0.0)]
+    pub hanging_indent: f32,
+
+    /// Extra vertical space between paragraphs in pixels.
+    #[clap(help = "Extra vertical space between paragraphs in pixels.")]
+    #[clap(long)]
+    #[clap(default_value_t = 0.0)]
+    pub paragraph_spacing: f32,
+
+    /// Extra horizontal space between characters in pixels.
+    #[clap(help = "Extra horizontal space between characters in pixels (tracking), which is negative to tighten them (ex. '4' for spaced out small capitals, '-2' for tight headlines).")]
+    #[clap(long, allow_hyphen_values = true)]
+    #[clap(default_value_t = 0.0)]
+    pub letter_spacing: f32,
+
+    #[clap(help = "Distance in pixels from the top of the image to the first baseline, so that separately rendered text lines up.")]
+    #[clap(long)]
+    #[clap(required = false)]
+    pub fixed_ascent: Option<f32>,
+
+    #[clap(help = "Snap baselines down to multiples of this many pixels from the top of the image.")]
+    #[clap(long)]
+    #[clap(required = false)]
+    pub baseline_grid: Option<f32>,
+
+    /// Column stops that tabs advance to.
+    #[clap(help = "Column stops that tabs advance to, as increasing numbers separated by commas in character cells, or in pixels if they end with 'px' (ex. '8' for a stop every 8 characters, '80,160,280px'). If no stops are provided, tabs are drawn as a glyph of the font.")]
+    #[clap(long)]
+    #[clap(required = false)]
+    pub tab_stops: Option<TabStops>,
+
+    /// Number the lines of the text.
+    #[clap(help = "Number the lines of the text in a gutter to the left of the text. Lines that wrap are not numbered again.")]
+    #[clap(long)]
+    pub line_numbers: bool,
+
+    /// Number of the first line.
+    #[clap(help = "Number of the first line, with --line-numbers.")]
+    #[clap(long)]
+    #[clap(default_value_t = LineNumbers::default().start)]
+    pub line_number_start: usize,
+
+    /// Color of the line numbers.
+    #[clap(help = "Color of the line numbers, with --line-numbers, as a CSS color or a space delimited RGBA value.")]
+    #[clap(long)]
+    #[clap(default_value_t = LineNumbers::default().color)]
+    pub line_number_color: Color,
+
+    /// Color of the rule between the line numbers and the text.
+    #[clap(help = "Color of the rule between the line numbers and the text, with --line-numbers, as a CSS color or a space delimited RGBA value. If no color is provided, the color of the line numbers is used.")]
+    #[clap(long)]
+    #[clap(required = false)]
+    pub gutter_rule_color: Option<Color>,
+
+    /// Don't draw a rule between the line numbers and the text.
+    #[clap(help = "Don't draw a rule between the line numbers and the text.")]
+    #[clap(long, conflicts_with = "gutter_rule_color")]
+    pub no_gutter_rule: bool,
+
+    /// How whitespace is trimmed and collapsed.
+    #[clap(help = "How whitespace is trimmed and collapsed before the text is laid out, like HTML: preserve, trim (remove the whitespace around the text), collapse (also replace each run of whitespace with a space), or collapsespaces (like collapse, but keep the line breaks).")]
+    #[clap(long)]
+    #[clap(default_value_t = Whitespace::default())]
+    pub whitespace: Whitespace,
+
+    /// How the case of the text is changed.
+    #[clap(help = "How the case of the text is changed before it is laid out: none, uppercase, lowercase, titlecase (the first letter of each word), or smallcaps. Case is mapped with the rules of --lang (ex. the dotted and dotless i of Turkish).")]
+    #[clap(long)]
+    #[clap(default_value_t = TextTransform::default())]
+    pub text_transform: TextTransform,
+
+    /// Draw digits at the same width.
+    #[clap(help = "Draw digits at the same width (tabular figures, with the tnum feature of the font), so that the digits of numbers in rows line up.")]
+    #[clap(long)]
+    pub tabular_figures: bool,
+
+    /// Replace emoji shortcodes with their emoji.
+    #[cfg(feature = "emoji")]
+    #[clap(help = "Replace emoji shortcodes (ex. :rocket:, :+1:) with their emoji before the text is laid out, like chat apps. Shortcodes that aren't emoji are drawn as is.")]
+    #[clap(long)]
+    pub emoji_shortcodes: bool,
+
+    /// Draw emoji with the images of a directory of Twemoji assets.
+    #[cfg(feature = "twemoji")]
+    #[clap(help = "Draw emoji with the images of a directory of Twemoji assets (ex. the assets/72x72 directory of Twemoji), whose files are named by codepoints (ex. 1f680.png). The images span the height of the line, and emoji without an image are drawn with the fonts.")]
+    #[clap(long)]
+    pub twemoji: Option<std::path::PathBuf>,
+
+    /// Replace every character of the text with a mask glyph.
+    #[clap(help = "Replace every character of the text (except line breaks) with --mask-glyph, to hide secrets (ex. passwords or API keys).")]
+    #[clap(long)]
+    pub mask: bool,
+
+    /// Glyph that the characters of the text are replaced with.
+    #[clap(help = "Glyph that the characters of the text are replaced with, with --mask.")]
+    #[clap(long)]
+    #[clap(default_value_t = '•')]
+    pub mask_glyph: char,
+
+    /// Blur the parts of the text that match a regular expression.
+    #[clap(help = "Blur the parts of the text that match a regular expression (ex. 'token=\\S+'), to hide secrets. The characters are replaced with '•' before they are blurred, so the image doesn't depend on the secret. Can be repeated.")]
+    #[clap(long, conflicts_with_all = ["mask", "math", "ansi"])]
+    pub blur: Vec<Regex>,
+
+    /// Blur byte ranges of the text.
+    #[clap(help = "Blur a byte range of the text, as START..END (ex. '10..17'), to hide secrets. Can be repeated.")]
+    #[clap(long, conflicts_with_all = ["mask", "math", "ansi"])]
+    #[clap(value_parser = parse_range)]
+    pub blur_range: Vec<Range<usize>>,
+
+    /// Select byte ranges of the text.
+    #[clap(help = "Select a byte range of the text, as START..END (ex. '10..17'), which is drawn over the selection color like in a text editor. Can be repeated.")]
+    #[clap(long, conflicts_with_all = ["math", "ansi"])]
+    #[clap(value_parser = parse_range)]
+    pub select: Vec<Range<usize>>,
+
+    /// Color behind selected text.
+    #[clap(help = "Color behind selected text, as a CSS color or a space delimited RGBA value.")]
+    #[clap(long)]
+    #[clap(default_value_t = Selection::default().color)]
+    pub selection_color: Color,
+
+    /// Filters applied to the image after the text is drawn.
+    #[clap(help = "Filter applied to the image after the text is drawn: blur:SIGMA, pixelate:SIZE, invert or desaturate (ex. 'pixelate:8'). Followed by @START..END (ex. 'invert@0..5'), only the glyphs of the byte range of the text are filtered. Can be repeated to apply several filters in order.")]
+    #[clap(long)]
+    #[clap(value_parser = parse_effect)]
+    pub effect: Vec<Effect>,
+
+    /// Procedural background that the text is drawn over.
+    #[clap(help = "Procedural background that the text is drawn over: solid, noise (grain), paper (a subtle texture) or gradient. If no background is provided, the background is transparent.")]
+    #[clap(long)]
+    #[clap(value_enum)]
+    #[clap(required = false)]
+    pub background: Option<BackgroundKind>,
+
+    /// Color of the background.
+    #[clap(help = "Color of the background, or the start color of a gradient, as a CSS color or a space delimited RGBA value.")]
+    #[clap(long)]
+    #[clap(default_value_t = Color { r: 255, g: 255, b: 255, a: 255 })]
+    pub background_color: Color,
+
+    /// End color of a gradient background.
+    #[clap(help = "End color of a gradient background, as a CSS color or a space delimited RGBA value.")]
+    #[clap(long)]
+    #[clap(default_value_t = Color { r: 200, g: 200, b: 200, a: 255 })]
+    pub gradient_end_color: Color,
+
+    /// Angle of a gradient background in degrees.
+    #[clap(help = "Angle of a gradient background in degrees, clockwise from left-to-right (ex. 90 is top-to-bottom).")]
+    #[clap(long)]
+    #[clap(default_value_t = 90.0)]
+    pub gradient_angle: f32,
+
+    /// Strength of the grain of a noise background.
+    #[clap(help = "Strength of the grain of a noise background, as the most that each pixel is lightened or darkened (0 to 1).")]
+    #[clap(long)]
+    #[clap(default_value_t = 0.1)]
+    pub noise_amount: f32,
+
+    /// Seed of a noise or paper background.
+    #[clap(help = "Seed of a noise or paper background. The same seed always draws the same background.")]
+    #[clap(long)]
+    #[clap(default_value_t = 0)]
+    pub background_seed: u64,
+
+    /// Radius of the corners of the image in pixels.
+    #[clap(help = "Radius of the corners of the image in pixels, which are cut out so that they are transparent.")]
+    #[clap(long)]
+    #[clap(default_value_t = 0.0)]
+    pub corner_radius: f32,
+
+    /// Width of the border around the image in pixels.
+    #[clap(help = "Width of the border around the image in pixels. The image is made larger by the width of the border on each side.")]
+    #[clap(long)]
+    #[clap(default_value_t = 0.0)]
+    pub border_width: f32,
+
+    /// Color of the border around the image.
+    #[clap(help = "Color of the border around the image, as a CSS color or a space delimited RGBA value.")]
+    #[clap(long)]
+    #[clap(default_value_t = Color { r: 0, g: 0, b: 0, a: 255 })]
+    pub border_color: Color,
+
+    /// Background image that the text is placed on, like a quote card.
+    #[clap(help = "PNG or JPEG image that the text is placed on, like a quote card. The text wraps within the margins of the image, unless a wrap width is provided.")]
+    #[clap(long, conflicts_with_all = ["math", "ansi"])]
+    #[clap(required = false)]
+    pub background_image: Option<std::path::PathBuf>,
+
+    /// How the background image is fitted to the size of the card.
+    #[clap(help = "How the background image is fitted to the size of the card: cover (scale and crop), contain (scale to fit within) or tile (repeat at its own size).")]
+    #[clap(long)]
+    #[clap(default_value_t = Fit::default())]
+    pub fit: Fit,
+
+    /// Where the text is placed on the background image.
+    #[clap(help = "Where the text is placed on the background image: topleft, top, topright, left, center, right, bottomleft, bottom or bottomright.")]
+    #[clap(long)]
+    #[clap(default_value_t = Anchor::default())]
+    pub anchor: Anchor,
+
+    /// Space between the text and the edges of the background image in pixels.
+    #[clap(help = "Space between the text and the edges of the background image in pixels.")]
+    #[clap(long)]
+    #[clap(default_value_t = 0.0)]
+    pub margin: f32,
+
+    /// Width of the card in pixels.
+    #[clap(help = "Width of the card in pixels. If no width is provided, the card is the width of the background image.")]
+    #[clap(long, requires = "background_image")]
+    #[clap(required = false)]
+    pub card_width: Option<u32>,
+
+    /// Height of the card in pixels.
+    #[clap(help = "Height of the card in pixels. If no height is provided, the card is the height of the background image.")]
+    #[clap(long, requires = "background_image")]
+    #[clap(required = false)]
+    pub card_height: Option<u32>,
+
+    /// Draw the text in a speech bubble.
+    #[clap(help = "Draw the text in a speech bubble that is sized to the wrapped text, for chat messages and comic balloons.")]
+    #[clap(long, conflicts_with_all = ["math", "ansi", "background_image"])]
+    pub bubble: bool,
+
+    /// Fill color of the speech bubble.
+    #[clap(help = "Fill color of the speech bubble, as a CSS color or a space delimited RGBA value.")]
+    #[clap(long)]
+    #[clap(default_value_t = Color { r: 255, g: 255, b: 255, a: 255 })]
+    pub bubble_color: Color,
+
+    /// Radius of the corners of the speech bubble in pixels.
+    #[clap(help = "Radius of the corners of the speech bubble in pixels.")]
+    #[clap(long)]
+    #[clap(default_value_t = 16.0)]
+    pub bubble_radius: f32,
+
+    /// Width of the outline of the speech bubble in pixels.
+    #[clap(help = "Width of the outline of the speech bubble in pixels. If the width is 0, the bubble has no outline.")]
+    #[clap(long)]
+    #[clap(default_value_t = 0.0)]
+    pub bubble_outline_width: f32,
+
+    /// Color of the outline of the speech bubble.
+    #[clap(help = "Color of the outline of the speech bubble, as a CSS color or a space delimited RGBA value.")]
+    #[clap(long)]
+    #[clap(default_value_t = Color { r: 0, g: 0, b: 0, a: 255 })]
+    pub bubble_outline_color: Color,
+
+    /// Space between the text and the edges of the speech bubble or nine-patch in pixels.
+    #[clap(help = "Space between the text and the edges of the speech bubble or nine-patch image in pixels.")]
+    #[clap(long)]
+    #[clap(default_value_t = 12.0)]
+    pub bubble_padding: f32,
+
+    /// Side of the speech bubble that its tail points away from.
+    #[clap(help = "Side of the speech bubble that its tail points away from: top, right, bottom or left. If no side is provided, the bubble has no tail.")]
+    #[clap(long, requires = "bubble")]
+    #[clap(required = false)]
+    pub tail: Option<Side>,
+
+    /// Position of the tail along its side.
+    #[clap(help = "Position of the tail along the straight part of its side, from the start (0) to the end (1), where the sides start at the top and left of the bubble.")]
+    #[clap(long)]
+    #[clap(default_value_t = 0.0)]
+    pub tail_position: f32,
+
+    /// Width and length of the tail in pixels.
+    #[clap(help = "Width and length of the tail of the speech bubble in pixels.")]
+    #[clap(long)]
+    #[clap(default_value_t = 16.0)]
+    pub tail_size: f32,
+
+    /// Nine-patch image that the text is framed by.
+    #[clap(help = "PNG or JPEG nine-patch image that the text is framed by, which is stretched to the size of the wrapped text.")]
+    #[clap(long, conflicts_with_all = ["math", "ansi", "background_image", "bubble"])]
+    #[clap(required = false)]
+    pub nine_patch: Option<std::path::PathBuf>,
+
+    /// Widths of the edges of the nine-patch image in pixels.
+    #[clap(help = "Widths of the edges of the nine-patch image in pixels, which are not stretched across, as one width for every edge or LEFT,TOP,RIGHT,BOTTOM (ex. '8' or '12,8,12,16').")]
+    #[clap(long)]
+    #[clap(value_parser = parse_insets)]
+    #[clap(default_value = "8")]
+    pub nine_patch_insets: [u32; 4],
+
+    /// Whether glyphs are snapped to whole pixels.
+    #[clap(help = "Whether glyphs are snapped to whole pixels: off, positions (round the position of each glyph), advances (also round the space between glyphs), or quarters (round the position of each glyph to a quarter of a pixel).")]
+    #[clap(long)]
+    #[clap(default_value_t = PixelSnap::default())]
+    pub pixel_snap: PixelSnap,
+
+    /// The color space that glyphs are blended in.
+    #[clap(help = "The color space that glyphs are blended in: srgb, or linear (linear light, which keeps thin strokes from looking thinner in light or colored text).")]
+    #[clap(long)]
+    #[clap(default_value_t = Blending::default())]
+    pub blending: Blending,
+
+    /// Coverage below which the pixels of glyphs are left transparent.
+    #[clap(help = "Coverage below which the pixels of glyphs are left transparent, from 0 to 1 (ex. 0.05), which removes faint fringes around glyphs.")]
+    #[clap(long)]
+    #[clap(default_value_t = Options::default().coverage_threshold)]
+    pub coverage_threshold: f32,
+
+    /// The least alpha of the pixels of glyphs that are drawn.
+    #[clap(help = "The least alpha of the pixels of glyphs that are drawn, as a fraction of the alpha of the text color (ex. 0.25), which makes the edges of glyphs more solid.")]
+    #[clap(long)]
+    #[clap(default_value_t = Options::default().min_alpha)]
+    pub min_alpha: f32,
+
+    /// The most alpha of the pixels of glyphs.
+    #[clap(help = "The most alpha of the pixels of glyphs and underlines, as a fraction of the alpha of the text color (ex. 0.9). If no maximum is provided, the alpha isn't clamped.")]
+    #[clap(long)]
+    #[clap(required = false)]
+    pub max_alpha: Option<f32>,
+
+    /// Draw only the outlines of glyphs, with a stroke width in pixels.
+    #[clap(help = "Draw only the outlines of glyphs (hollow text), with a stroke width in pixels (ex. 2), for stencils and plotters. The stroke is drawn inside of the outlines.")]
+    #[clap(long)]
+    #[clap(required = false)]
+    pub stroke_width: Option<f32>,
+
+    /// Color of the strokes of hollow glyphs.
+    #[clap(help = "Color of the strokes of hollow glyphs, with --stroke-width, as a CSS color or a space delimited RGBA value. If no color is provided, the text color is used.")]
+    #[clap(long, requires = "stroke_width")]
+    #[clap(required = false)]
+    pub stroke_color: Option<Color>,
+
+    /// Index of the palette of color glyphs.
+    #[clap(help = "Index of the palette (CPAL table) that the color glyphs (COLR table) of the fonts are drawn in (ex. 1 for a palette for dark backgrounds). If the font doesn't have the palette, the first palette is used.")]
+    #[clap(long)]
+    #[clap(default_value_t = 0)]
+    pub palette: u16,
+
+    /// Color that replaces an entry of the palette of color glyphs.
+    #[clap(help = "Color that replaces an entry of the palette of color glyphs, as INDEX=COLOR (ex. '2=#ff6600'), where the color is a CSS color or a space delimited RGBA value. Can be repeated.")]
+    #[clap(long, value_parser = parse_palette_color)]
+    pub palette_color: Vec<(u16, Color)>,
+
+    /// Draw color glyphs in the color of the text.
+    #[clap(help = "Draw the color glyphs of the fonts (and glyphs that are SVG documents, with the svg feature) in the color of the text like other glyphs, from their outlines.")]
+    #[clap(long, conflicts_with_all = ["palette", "palette_color"])]
+    pub monochrome: bool,
+
+    /// Draw characters that no font supports as boxes with their codepoints.
+    #[clap(help = "Draw characters that no font supports as boxes with their codepoints (tofu) instead of the missing glyph of the font, to debug missing glyphs: outline or filled.")]
+    #[clap(long)]
+    #[clap(required = false)]
+    pub tofu: Option<TofuStyle>,
+
+    /// Don't draw the codepoints in the boxes of missing characters.
+    #[clap(help = "Don't draw the codepoints in the boxes of characters that no font supports.")]
+    #[clap(long, requires = "tofu")]
+    pub no_tofu_codepoint: bool,
+
+    /// Make conditions that are recovered from errors.
+    #[clap(help = "Make conditions that are recovered from errors (missing glyphs, an empty image, a font without kerning, and clipped glyphs), for CI pipelines that must not produce broken images.")]
+    #[clap(long)]
+    pub strict: bool,
+
+    /// What happens when a font has restricted embedding permissions.
+    #[clap(help = "What happens when the text is drawn with a font whose embedding permissions are restricted (the fsType of its OS/2 table): ignore, warn (log a warning) or deny (exit with an error), for asset pipelines that must stay compliant.")]
+    #[clap(long)]
+    #[clap(default_value_t = LicensePolicy::default())]
+    pub license_policy: LicensePolicy,
+
+    /// Maximum width of the image in pixels.
+    #[clap(help = "Maximum width of the image in pixels. Larger images are an error.")]
+    #[clap(long)]
+    #[clap(default_value_t = Options::default().max_width)]
+    pub max_width: u32,
+
+    /// Maximum height of the image in pixels.
+    #[clap(help = "Maximum height of the image in pixels. Larger images are an error.")]
+    #[clap(long)]
+    #[clap(default_value_t = Options::default().max_height)]
+    pub max_height: u32,
+
+    /// Maximum number of pixels in the image.
+    #[clap(help = "Maximum number of pixels in the image, where each pixel uses 4 bytes. Larger images are an error.")]
+    #[clap(long)]
+    #[clap(default_value_t = Options::default().max_pixels)]
+    pub max_pixels: u64,
+
+    /// Maximum number of glyphs of the text.
+    #[clap(help = "Maximum number of glyphs of the text, which bounds the time that drawing it takes. More glyphs are an error.")]
+    #[clap(long)]
+    #[clap(default_value_t = Options::default().max_glyphs)]
+    pub max_glyphs: usize,
+
+    /// Base direction of the text.
+    #[clap(help = "Base direction of the text: auto (detect from the first strong directional character), ltr, or rtl.")]
+    #[clap(short = 'd', long)]
+    #[clap(default_value_t = Direction::default())]
+    pub direction: Direction,
+
+    /// Language of the text as a BCP 47 language tag.
+    #[clap(help = "Language of the text as a BCP 47 language tag (ex. 'en', 'sr', 'tr-TR'), used for language-sensitive shaping.")]
+    #[clap(short = 'l', long)]
+    #[clap(required = false)]
+    pub lang: Option<LanguageTag>,
+
     /// Path to a font file in ttf format. If no file is provided, DejaVu Sans is used.
     #[clap(help = "Path to a ttf font file. If no file is provided, DejaVu Sans is used.")]
     #[clap(short = 'f', long)]
     #[clap(required = false)]
     pub font: Option<std::path::PathBuf>,
 
+    /// Paths to fallback font files in ttf format, for characters that are missing from the main font.
+    #[clap(help = "Path to a ttf font file to use for characters that are missing from the main font. Can be repeated to provide several fallback fonts, which are tried in order.")]
+    #[clap(long)]
+    #[clap(required = false)]
+    pub fallback_font: Vec<std::path::PathBuf>,
+
+    /// Path to a single-stroke Hershey font file in jhf format.
+    #[clap(help = "Path to a single-stroke Hershey font file in jhf format (ex. futural.jhf), whose glyphs are drawn with a pen rather than filled, for engraving and pen plotting. Text is laid out left to right, and broken at newlines.")]
+    #[clap(long, conflicts_with_all = ["bubble", "nine_patch", "background_image", "rgba16", "math", "ansi"])]
+    #[clap(required = false)]
+    pub hershey_font: Option<std::path::PathBuf>,
+
+    /// Path to a file with an older version of the text, to draw the differences from.
+    #[clap(help = "Path to a file with an older version of the text. The differences between it and the text are drawn, with insertions in green and deletions in red and struck through, like in code review.")]
+    #[clap(long, conflicts_with_all = ["bubble", "nine_patch", "background_image", "rgba16", "export", "hershey_font", "math", "ansi"])]
+    #[clap(required = false)]
+    pub diff: Option<std::path::PathBuf>,
+
+    /// How the old and new text of a diff are arranged.
+    #[clap(help = "How the old and new text of a diff are arranged: unified (deletions followed by insertions) or sidebyside (the old text on the left, and the new text on the right).")]
+    #[clap(long)]
+    #[clap(default_value_t = DiffLayout::default())]
+    pub diff_layout: DiffLayout,
+
+    /// Don't strike through deleted text in a diff.
+    #[clap(help = "Don't strike through deleted text in a diff.")]
+    #[clap(long)]
+    pub no_strikethrough: bool,
+
     /// Set the logging [`Verbosity`] level.
     #[clap(help = "Set the logging verbosity level.")]
     #[clap(short = 'v', long)]
@@ -55,3 +684,101 @@ pub struct Cli {
     #[clap(default_value_t = Verbosity::default())]
     pub verbosity: Verbosity,
 }
+
+/// The kinds of procedural backgrounds that text can be drawn over.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum BackgroundKind {
+    Solid,
+    Noise,
+    Paper,
+    Gradient,
+}
+
+/// The vector formats that the outlines of glyphs can be exported to.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum ExportFormat {
+    /// DXF polylines, for CAD and laser cutting software.
+    Dxf,
+    /// G-code, for engraving with a CNC machine.
+    Gcode,
+    /// HPGL, for pen plotters.
+    Hpgl,
+}
+
+/// The subcommands of the command-line interface, which render something other than the text.
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Run a command, and render its output as a screenshot of a terminal.
+    Exec(Exec),
+    /// Render the text as a chat message, with an avatar, a name and a timestamp.
+    Chat(Chat),
+    /// Follow a log file, and render its last lines to the output file whenever it changes.
+    Tail(Tail),
+    /// Render the time as a digital clock.
+    Clock(Clock),
+    /// Render a month of a calendar, with today highlighted.
+    Calendar(Calendar),
+    /// Render a single glyph of the font, for icon fonts.
+    Icon(Icon),
+    /// Render a specimen of the font, for cataloging font libraries.
+    Specimen(Specimen),
+    /// Render a chart of the glyphs of the font in a range of Unicode characters, for font QA.
+    Coverage(Coverage),
+    /// Render a text at a list of sizes stacked vertically, for font selection and hinting evaluation.
+    Waterfall(Waterfall),
+    /// Render a text twice, and compare the renderings with their differences highlighted.
+    Compare(Compare),
+    /// Render a TOML or JSON template of texts on a canvas, with its placeholders filled in by variables.
+    #[cfg(feature = "template")]
+    Template(Template),
+    /// Render a template once for each row of a CSV or JSON file, to the output path filled in with the row.
+    #[cfg(feature = "template")]
+    Merge(Merge),
+    /// List the fonts that are provided within the application, or export one of them as a TTF file.
+    Fonts(Fonts),
+}
+
+/// Returns an [`Effect`] converted from a [`str`] of the form `FILTER` or `FILTER@START..END`.
+fn parse_effect(effect: &str) -> Result<Effect, String> {
+    let (filter, range) = match effect.split_once('@') {
+        Some((filter, range)) => (filter, Some(parse_range(range)?)),
+        None => (effect, None),
+    };
+    let filter = filter.parse().map_err(|error| format!("{error}"))?;
+    Ok(Effect { filter, range })
+}
+
+/// Returns the insets of a nine-patch image converted from a [`str`] of the form `WIDTH` or `LEFT,TOP,RIGHT,BOTTOM`.
+fn parse_insets(insets: &str) -> Result<[u32; 4], String> {
+    let error = || format!("Invalid insets: {insets:?}. Expected WIDTH or LEFT,TOP,RIGHT,BOTTOM (ex. '8', '12,8,12,16').");
+    let widths = insets
+        .split(',')
+        .map(|width| width.trim().parse().map_err(|_| error()))
+        .collect::<Result<Vec<u32>, _>>()?;
+    match widths[..] {
+        [width] => Ok([width; 4]),
+        [left, top, right, bottom] => Ok([left, top, right, bottom]),
+        _ => Err(error()),
+    }
+}
+
+/// Returns an entry of a palette and its color converted from a [`str`] of the form `INDEX=COLOR`.
+fn parse_palette_color(entry: &str) -> Result<(u16, Color), String> {
+    let error = || format!("Invalid palette color: {entry:?}. Expected INDEX=COLOR (ex. '2=#ff6600').");
+    let (index, color) = entry.split_once('=').ok_or_else(error)?;
+    let index = index.trim().parse().map_err(|_| error())?;
+    let color = color.parse().map_err(|error| format!("{error}"))?;
+    Ok((index, color))
+}
+
+/// Returns a byte range converted from a [`str`] of the form `START..END`.
+fn parse_range(range: &str) -> Result<Range<usize>, String> {
+    let error = || format!("Invalid byte range: {range:?}. Expected START..END (ex. '10..17').");
+    let (start, end) = range.split_once("..").ok_or_else(error)?;
+    let start = start.parse().map_err(|_| error())?;
+    let end = end.parse().map_err(|_| error())?;
+    match start <= end {
+        true => Ok(start..end),
+        false => Err(error()),
+    }
+}
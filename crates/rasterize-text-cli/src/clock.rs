@@ -0,0 +1,64 @@
+use chrono::{Local, Timelike};
+use color_eyre::eyre::{Report, Result};
+use image::{ImageBuffer, Rgba};
+use rasterize_text::Options;
+use rusttype::Font;
+
+type Image = ImageBuffer<Rgba<u8>, Vec<u8>>;
+
+/// Render the time as a digital clock.
+/// ---
+/// The current local time is rendered, unless a time is provided with `--time`. The clock is drawn at `--size`,
+/// and can be given a background and rounded corners for a dashboard widget (ex. `--background solid --corner-radius 16`).
+#[derive(Debug, clap::Args)]
+pub struct Clock {
+    /// The time to render, instead of the current time.
+    #[clap(help = "The time to render as HH:MM or HH:MM:SS, on a 24-hour clock (ex. '21:41'). If no time is provided, the current local time is used.")]
+    #[clap(long, value_parser = parse_time)]
+    #[clap(required = false)]
+    pub time: Option<(u8, u8, u8)>,
+
+    /// Draw the seconds.
+    #[clap(help = "Draw the seconds after the minutes.")]
+    #[clap(long)]
+    pub seconds: bool,
+
+    /// Draw a 12-hour clock.
+    #[clap(help = "Draw the hour from 1 to 12, followed by AM or PM.")]
+    #[clap(long)]
+    pub twelve_hour: bool,
+}
+
+impl Clock {
+    /// Render the time as a digital clock with a [`Font`], as configured by [`Options`].
+    pub fn render(&self, font: &Font, options: &Options) -> Result<Image, Report> {
+        let (hour, minute, second) = self.time.unwrap_or_else(|| {
+            let now = Local::now();
+            (now.hour() as u8, now.minute() as u8, now.second() as u8)
+        });
+        let clock = rasterize_text::Clock {
+            hour,
+            minute,
+            second: self.seconds.then_some(second),
+            twelve_hour: self.twelve_hour,
+        };
+        let image = rasterize_text::rasterize_clock(&clock, font, options)?;
+        Ok(image)
+    }
+}
+
+/// Returns a time converted from a [`str`] of the form `HH:MM` or `HH:MM:SS`.
+fn parse_time(time: &str) -> Result<(u8, u8, u8), String> {
+    let error = || format!("Invalid time: {time:?}. Expected HH:MM or HH:MM:SS (ex. '21:41').");
+    let parts = time
+        .split(':')
+        .map(|part| part.parse::<u8>().map_err(|_| error()))
+        .collect::<Result<Vec<_>, _>>()?;
+    match parts[..] {
+        [hour, minute] if hour < 24 && minute < 60 => Ok((hour, minute, 0)),
+        [hour, minute, second] if hour < 24 && minute < 60 && second < 60 => {
+            Ok((hour, minute, second))
+        }
+        _ => Err(error()),
+    }
+}
@@ -0,0 +1,126 @@
+use color_eyre::eyre::{Report, Result};
+use image::{ImageBuffer, Rgba};
+use rasterize_text::{Blending, CompareStyle, Options, PixelSnap};
+use rusttype::Font;
+use std::path::PathBuf;
+
+type Image = ImageBuffer<Rgba<u8>, Vec<u8>>;
+
+/// Render a text twice, and compare the renderings with their differences highlighted.
+/// ---
+/// The text is rendered as configured by the options, and compared with a reference image (ex. rendered by another
+/// version of rasterize-text) or with the text rendered with other options. The output has both renderings side by
+/// side, followed by the pixels that are different in magenta over a faded copy of the second rendering. With the
+/// `pango` feature, the text can also be compared with the text rendered by Pango.
+#[derive(Debug, clap::Args)]
+pub struct Compare {
+    /// Text to render and compare.
+    #[clap(help = "Text to render and compare.")]
+    #[clap(required = true)]
+    pub text: String,
+
+    /// Reference image to compare the text with.
+    #[clap(help = "PNG file to compare the rendered text with, instead of rendering it again with other options (ex. the output of another version of rasterize-text).")]
+    #[clap(long, conflicts_with_all = ["other_font", "other_size", "other_pixel_snap", "other_blending"])]
+    #[clap(required = false)]
+    pub reference: Option<PathBuf>,
+
+    /// Compare the text with the text rendered by Pango.
+    #[cfg(feature = "pango")]
+    #[clap(help = "Compare the ink of the text with the text rendered by an external Pango installation (pango-view), and log the differences of their sizes, to validate shaping (ex. of complex scripts).")]
+    #[clap(long, conflicts_with_all = ["reference", "other_font", "other_size", "other_pixel_snap", "other_blending"])]
+    pub pango: bool,
+
+    /// Font of the second rendering.
+    #[clap(help = "Font file of the second rendering, instead of the font.")]
+    #[clap(long)]
+    #[clap(required = false)]
+    pub other_font: Option<PathBuf>,
+
+    /// Size of the second rendering.
+    #[clap(help = "Font size of the second rendering in pixels, instead of the size.")]
+    #[clap(long)]
+    #[clap(required = false)]
+    pub other_size: Option<f32>,
+
+    /// Pixel snapping of the second rendering.
+    #[clap(help = "How glyphs are snapped to the pixel grid in the second rendering, instead of the pixel snapping: off, positions, advances or quarters.")]
+    #[clap(long)]
+    #[clap(required = false)]
+    pub other_pixel_snap: Option<PixelSnap>,
+
+    /// Blending of the second rendering.
+    #[clap(help = "The color space that glyphs are blended in for the second rendering, instead of the blending: srgb or linear.")]
+    #[clap(long)]
+    #[clap(required = false)]
+    pub other_blending: Option<Blending>,
+
+    /// Largest difference of a channel that pixels can have and still be the same.
+    #[clap(help = "The largest difference of a channel (from 0 to 255) that pixels can have and still be the same, to ignore small differences.")]
+    #[clap(long)]
+    #[clap(default_value_t = 0)]
+    pub threshold: u8,
+}
+
+impl Compare {
+    /// Render the text with a [`Font`], as configured by [`Options`], and compare it with the reference image or
+    /// with the text rendered with the other options.
+    pub fn render(&self, font: &Font, options: &Options) -> Result<Image, Report> {
+        let text = self.text.as_str();
+        #[cfg(feature = "pango")]
+        if self.pango {
+            let style = CompareStyle {
+                threshold: self.threshold,
+                ..Default::default()
+            };
+            let result = rasterize_text::compare_with_pango(text, font, options, &style)?;
+            log::info!(
+                "The ink is {}px wider and {}px taller than Pango's, and {} pixels are different.",
+                result.width_delta,
+                result.height_delta,
+                result.comparison.changed_pixels
+            );
+            return Ok(result.comparison.image);
+        }
+        let before = rasterize_text::rasterize_with_options(text, font, options)?;
+        let after = match &self.reference {
+            Some(path) => premultiply(image::open(path)?.to_rgba8()),
+            None => {
+                let other_font = match &self.other_font {
+                    Some(path) => Some(rasterize_text::read_font_file(path)?),
+                    None => None,
+                };
+                let other_options = Options {
+                    size: self.other_size.unwrap_or(options.size),
+                    pixel_snap: self.other_pixel_snap.unwrap_or(options.pixel_snap),
+                    blending: self.other_blending.unwrap_or(options.blending),
+                    ..options.clone()
+                };
+                let other_font = other_font.as_ref().unwrap_or(font);
+                rasterize_text::rasterize_with_options(text, other_font, &other_options)?
+            }
+        };
+
+        let style = CompareStyle {
+            threshold: self.threshold,
+            ..Default::default()
+        };
+        let comparison = rasterize_text::compare_images(&before, &after, &style)?;
+        match comparison.is_identical() {
+            true => log::info!("The renderings are identical."),
+            false => log::warn!("{} pixels are different.", comparison.changed_pixels),
+        }
+        Ok(comparison.image)
+    }
+}
+
+/// Returns an image that is read from a PNG file with its colors premultiplied by their alpha, like rasterized text.
+pub fn premultiply(mut image: Image) -> Image {
+    for pixel in image.pixels_mut() {
+        let alpha = u32::from(pixel[3]);
+        for c in 0..3 {
+            pixel[c] = ((u32::from(pixel[c]) * alpha + 127) / 255) as u8;
+        }
+    }
+    image
+}
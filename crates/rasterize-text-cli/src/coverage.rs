@@ -0,0 +1,62 @@
+use color_eyre::eyre::{Report, Result};
+use image::{ImageBuffer, Rgba};
+use rasterize_text::{CoverageChart, Options};
+use rusttype::Font;
+use std::ops::RangeInclusive;
+
+type Image = ImageBuffer<Rgba<u8>, Vec<u8>>;
+
+/// Render a chart of the glyphs of the font in a range of Unicode characters, for font QA.
+/// ---
+/// The characters are laid out on a grid of 16 columns, like the charts of the Unicode Standard, with the codepoint of
+/// each character below its glyph. The cells of characters that the font doesn't support are shaded, and the number
+/// of supported characters is logged. The font is chosen with `--font`.
+#[derive(Debug, clap::Args)]
+pub struct Coverage {
+    /// The range of characters to render.
+    #[clap(help = "The inclusive range of characters to render as START..END, which is usually a Unicode block (ex. 'U+0370..U+03FF' for Greek and Coptic).")]
+    #[clap(value_parser = parse_range)]
+    #[clap(default_value = "U+0020..U+007F")]
+    pub range: RangeInclusive<char>,
+
+    /// Don't draw lines between the cells.
+    #[clap(help = "Don't draw lines between the cells.")]
+    #[clap(long)]
+    pub no_lines: bool,
+}
+
+impl Coverage {
+    /// Render the chart of the range with a [`Font`], as configured by [`Options`].
+    pub fn render(&self, font: &Font, options: &Options) -> Result<Image, Report> {
+        let chart = CoverageChart {
+            range: self.range.clone(),
+            line_color: match self.no_lines {
+                true => None,
+                false => CoverageChart::default().line_color,
+            },
+            ..Default::default()
+        };
+        log::info!(
+            "The font supports {} of {} characters.",
+            chart.supported(font).len(),
+            self.range.clone().count()
+        );
+        let image = rasterize_text::rasterize_coverage(&chart, font, options)?;
+        Ok(image)
+    }
+}
+
+/// Returns a range of characters converted from a [`str`] of the form `START..END`, where each character is a
+/// hexadecimal codepoint with an optional `U+` prefix.
+fn parse_range(range: &str) -> Result<RangeInclusive<char>, String> {
+    let error = || format!("Invalid range: {range:?}. Expected START..END (ex. 'U+0370..U+03FF').");
+    let codepoint = |codepoint: &str| {
+        let hex = codepoint.strip_prefix("U+").unwrap_or(codepoint);
+        u32::from_str_radix(hex, 16).ok().and_then(char::from_u32)
+    };
+    let (start, end) = range.split_once("..").ok_or_else(error)?;
+    match (codepoint(start), codepoint(end)) {
+        (Some(start), Some(end)) if start <= end => Ok(start..=end),
+        _ => Err(error()),
+    }
+}
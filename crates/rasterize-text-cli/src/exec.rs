@@ -0,0 +1,232 @@
+use color_eyre::eyre::{eyre, Report, Result};
+use image::{ImageBuffer, Rgba};
+use rasterize_text::{Color, GridLayout, Options};
+use rusttype::Font;
+use std::io::Read;
+use std::process::Command;
+
+type Image = ImageBuffer<Rgba<u8>, Vec<u8>>;
+
+/// Colors of the close, minimize and maximize buttons of the window chrome.
+const BUTTONS: [Color; 3] = [
+    Color { r: 255, g: 95, b: 86, a: 255 },
+    Color { r: 255, g: 189, b: 46, a: 255 },
+    Color { r: 39, g: 201, b: 63, a: 255 },
+];
+
+/// Run a command, and render its output as a screenshot of a terminal.
+/// ---
+/// The output of the command (stdout and stderr, in the order they were written) is interpreted as
+/// ANSI escape sequences, and laid out on a grid of character cells like a terminal.
+/// Commands usually only color their output when it is written to a terminal, so they might need to be told to
+/// color it anyway (ex. `ls --color=always`). The `CLICOLOR_FORCE` and `FORCE_COLOR` environment variables are set
+/// for commands that support them, and `COLUMNS` is set to the width of the terminal.
+///
+/// Every character is placed in a cell as wide as a space of the font, so a monospace font (`--font`) looks best.
+#[derive(Debug, clap::Args)]
+pub struct Exec {
+    /// The command to run, and its arguments.
+    #[clap(help = "The command to run and its arguments, after '--' (ex. 'rasterize-text -o ls.png exec -- ls --color=always').")]
+    #[clap(last = true)]
+    #[clap(required = true)]
+    pub command: Vec<String>,
+
+    /// Width of the terminal in character cells.
+    #[clap(help = "Width of the terminal in character cells. Longer lines wrap onto the next row.")]
+    #[clap(long)]
+    #[clap(default_value_t = 80)]
+    pub cols: usize,
+
+    /// Height of the terminal in character cells.
+    #[clap(help = "Height of the terminal in character cells. Rows past the bottom are not drawn. If no height is provided, the terminal fits all of the output.")]
+    #[clap(long)]
+    #[clap(required = false)]
+    pub rows: Option<usize>,
+
+    /// Space around the text in pixels.
+    #[clap(help = "Space around the text in pixels.")]
+    #[clap(long)]
+    #[clap(default_value_t = 16)]
+    pub padding: u32,
+
+    /// Background color of the terminal.
+    #[clap(help = "Background color of the terminal as a CSS color (ex. '#1e1e1e') or a space delimited RGBA value.")]
+    #[clap(short = 'b', long)]
+    #[clap(default_value_t = Color { r: 255, g: 255, b: 255, a: 255 })]
+    pub background: Color,
+
+    /// Draw a window around the terminal.
+    #[clap(help = "Draw a window around the terminal, with rounded corners and a title bar with buttons and the title.")]
+    #[clap(long)]
+    pub chrome: bool,
+
+    /// Title of the window.
+    #[clap(help = "Title of the window, when drawn with --chrome. If no title is provided, the command is used.")]
+    #[clap(long)]
+    #[clap(required = false)]
+    pub title: Option<String>,
+}
+
+impl Exec {
+    /// Run the command, and return its output.
+    ///
+    /// The output is returned even if the command fails, since the screenshot of a failing command is often the point.
+    pub fn run(&self) -> Result<String, Report> {
+        let (program, args) = self
+            .command
+            .split_first()
+            .ok_or_else(|| eyre!("No command was provided to run."))?;
+
+        // Stdout and stderr share a pipe, so that their output is interleaved in the order it was written
+        let (mut reader, writer) = std::io::pipe()?;
+        let mut command = Command::new(program);
+        command
+            .args(args)
+            .env("CLICOLOR_FORCE", "1")
+            .env("FORCE_COLOR", "1")
+            .env("COLUMNS", self.cols.to_string())
+            .stdout(writer.try_clone()?)
+            .stderr(writer);
+        let mut child = command
+            .spawn()
+            .map_err(|e| eyre!("Failed to run {program:?}: {e}"))?;
+        // The command holds a copy of the writer, which has to be closed for the output to end
+        drop(command);
+
+        let mut output = Vec::new();
+        reader.read_to_end(&mut output)?;
+        let status = child.wait()?;
+        if !status.success() {
+            log::warn!("The command {program:?} exited with {status}");
+        }
+        Ok(String::from_utf8_lossy(&output).into_owned())
+    }
+
+    /// Run the command, and render its output as a screenshot of a terminal with a [`Font`], as configured by [`Options`].
+    pub fn screenshot(&self, font: &Font, options: &Options) -> Result<Image, Report> {
+        let output = self.run()?;
+        let spans = rasterize_text::parse_ansi(&output);
+        let text: String = spans.iter().map(|span| span.text.as_str()).collect();
+
+        let mut grid = GridLayout::new(self.cols, 1, font, options.size);
+        grid.rows = self.rows.unwrap_or(grid.rows_for(&text, options).max(1));
+        let terminal = rasterize_text::rasterize_grid_spans(&spans, font, &grid, options)?;
+
+        // The title bar is one and a half rows tall, with the title at 80% of the font size
+        let title_bar = match self.chrome {
+            true => (grid.cell_h * 1.5).round() as u32,
+            false => 0,
+        };
+        // The padding is provided by the user, so a padding that is too large is an error rather than an overflow
+        let pad = |length: u32, extra: u32| {
+            self.padding
+                .checked_mul(2)
+                .and_then(|padding| length.checked_add(padding)?.checked_add(extra))
+                .ok_or_else(|| eyre!("The padding of {} pixels is too large.", self.padding))
+        };
+        let width = pad(terminal.width(), 0)?;
+        let height = pad(terminal.height(), title_bar)?;
+        let mut image = Image::from_pixel(width, height, to_rgba(self.background));
+        over(
+            &mut image,
+            &terminal,
+            self.padding,
+            self.padding + title_bar,
+        );
+        if !self.chrome {
+            return Ok(image);
+        }
+
+        let radius = grid.cell_h / 4.0;
+        let center_y = title_bar as f32 / 2.0;
+        for (i, color) in BUTTONS.iter().enumerate() {
+            let center_x = self.padding as f32 + radius + i as f32 * radius * 3.0;
+            circle(&mut image, (center_x, center_y), radius, *color);
+        }
+        let title = self.title.clone().unwrap_or_else(|| self.command.join(" "));
+        let title_options = Options {
+            size: options.size * 0.8,
+            color: Color {
+                r: 128,
+                g: 128,
+                b: 128,
+                a: 255,
+            },
+            max_width: options.max_width,
+            max_height: options.max_height,
+            max_pixels: options.max_pixels,
+            max_glyphs: options.max_glyphs,
+            fallback_fonts: options.fallback_fonts.clone(),
+            ..Default::default()
+        };
+        let title = rasterize_text::rasterize_with_options(title.as_str(), font, &title_options)?;
+        let x = width.saturating_sub(title.width()) / 2;
+        let y = title_bar.saturating_sub(title.height()) / 2;
+        over(&mut image, &title, x, y);
+
+        round_corners(&mut image, grid.cell_h / 2.0);
+        Ok(image)
+    }
+}
+
+pub(crate) fn to_rgba(color: Color) -> Rgba<u8> {
+    Rgba([color.r, color.g, color.b, color.a])
+}
+
+/// Blend the pixels of rasterized text over an image at `x` and `y`.
+///
+/// The color of rasterized text is already multiplied by its coverage, so it is added to the uncovered part of the image.
+pub(crate) fn over(image: &mut Image, text: &Image, x: u32, y: u32) {
+    for (i, j, pixel) in text.enumerate_pixels() {
+        let Some(below) = image.get_pixel_mut_checked(x + i, y + j) else {
+            continue;
+        };
+        let uncovered = 1.0 - f32::from(pixel[3]) / 255.0;
+        for c in 0..4 {
+            below[c] = (f32::from(pixel[c]) + f32::from(below[c]) * uncovered).round() as u8;
+        }
+    }
+}
+
+/// Draw an antialiased circle in a color, centered at `center` with a radius in pixels.
+fn circle(image: &mut Image, center: (f32, f32), radius: f32, color: Color) {
+    let min_x = (center.0 - radius).floor().max(0.0) as u32;
+    let min_y = (center.1 - radius).floor().max(0.0) as u32;
+    let max_x = ((center.0 + radius).ceil() as u32).min(image.width());
+    let max_y = ((center.1 + radius).ceil() as u32).min(image.height());
+    for y in min_y..max_y {
+        for x in min_x..max_x {
+            let distance = (x as f32 + 0.5 - center.0).hypot(y as f32 + 0.5 - center.1);
+            let coverage = (radius + 0.5 - distance).clamp(0.0, 1.0) * f32::from(color.a) / 255.0;
+            let pixel = image.get_pixel_mut(x, y);
+            let color = to_rgba(color);
+            for c in 0..4 {
+                let blended =
+                    f32::from(color[c]) * coverage + f32::from(pixel[c]) * (1.0 - coverage);
+                pixel[c] = blended.round() as u8;
+            }
+        }
+    }
+}
+
+/// Make the corners of an image transparent outside of a quarter circle with a radius in pixels.
+fn round_corners(image: &mut Image, radius: f32) {
+    let (width, height) = image.dimensions();
+    let size = (radius.ceil() as u32).min(width / 2).min(height / 2);
+    for y in 0..size {
+        for x in 0..size {
+            let distance = (radius - x as f32 - 0.5).hypot(radius - y as f32 - 0.5);
+            let coverage = (radius + 0.5 - distance).clamp(0.0, 1.0);
+            // Pixels are scaled by their coverage in the same way as rasterized text
+            for (x, y) in [
+                (x, y),
+                (width - 1 - x, y),
+                (x, height - 1 - y),
+                (width - 1 - x, height - 1 - y),
+            ] {
+                let pixel = image.get_pixel_mut(x, y);
+                pixel.0 = pixel.0.map(|c| (f32::from(c) * coverage).round() as u8);
+            }
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use color_eyre::eyre::{Report, Result};
+use rasterize_text::VendoredFont;
+use std::path::PathBuf;
+
+/// List the fonts that are provided within the application, or export one of them.
+/// ---
+/// The vendored fonts are embedded in the application, so that text can be rendered without any font files. They can
+/// be listed with their versions, licenses and checksums, and exported as TTF files to use them elsewhere (ex. in a
+/// document that must match the rendered text).
+#[derive(Debug, clap::Args)]
+pub struct Fonts {
+    /// What to do with the vendored fonts.
+    #[clap(subcommand)]
+    pub command: FontsCommand,
+}
+
+/// What to do with the vendored fonts.
+#[derive(Debug, clap::Subcommand)]
+pub enum FontsCommand {
+    /// List the vendored fonts, with their names, versions, licenses and checksums.
+    List,
+    /// Write the TTF file of a vendored font.
+    Export(Export),
+}
+
+/// Write the TTF file of a vendored font.
+#[derive(Debug, clap::Args)]
+pub struct Export {
+    /// Name of the font to export.
+    #[clap(help = "Name of the vendored font to export, as listed by 'fonts list' (ex. 'DejaVu Sans Bold' or 'dejavu-sans-bold').")]
+    #[clap(value_parser = parse_font)]
+    pub name: VendoredFont,
+
+    /// Path of the exported font file.
+    #[clap(help = "Path of the exported TTF file (ex. 'DejaVuSans-Bold.ttf').")]
+    pub path: PathBuf,
+}
+
+impl Fonts {
+    /// List the vendored fonts, or export one of them.
+    pub fn run(&self) -> Result<(), Report> {
+        match &self.command {
+            FontsCommand::List => list(),
+            FontsCommand::Export(export) => {
+                verify(export.name);
+                std::fs::write(&export.path, export.name.bytes())?;
+                log::info!("Exported {} to {:?}.", export.name, export.path);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Print the name, version, license and checksum of each vendored font, separated by tabs.
+pub fn list() {
+    for vendored in VendoredFont::ALL {
+        verify(*vendored);
+        println!(
+            "{}\t{}\t{}\t{:032x}",
+            vendored.name(),
+            vendored.version(),
+            vendored.license(),
+            vendored.checksum()
+        );
+    }
+}
+
+/// Log a warning if the font file of a vendored font doesn't match its checksum.
+fn verify(vendored: VendoredFont) {
+    if !vendored.verify() {
+        log::warn!("The font file of {vendored} doesn't match its checksum.");
+    }
+}
+
+/// Returns a [`VendoredFont`] converted from its name, ignoring case, spaces, hyphens and underscores.
+fn parse_font(name: &str) -> Result<VendoredFont, String> {
+    let normalize = |name: &str| {
+        name.chars()
+            .filter(|c| !matches!(c, ' ' | '-' | '_'))
+            .collect::<String>()
+            .to_lowercase()
+    };
+    VendoredFont::ALL
+        .iter()
+        .find(|vendored| normalize(vendored.name()) == normalize(name))
+        .copied()
+        .ok_or_else(|| {
+            let names: Vec<_> = VendoredFont::ALL.iter().map(|v| v.name()).collect();
+            format!(
+                "Unknown vendored font: {name:?}. Expected one of: {}.",
+                names.join(", ")
+            )
+        })
+}
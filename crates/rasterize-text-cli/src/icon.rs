@@ -0,0 +1,72 @@
+use color_eyre::eyre::{eyre, Report, Result};
+use image::{ImageBuffer, Rgba};
+use rasterize_text::Options;
+use rusttype::{Font, GlyphId};
+
+type Image = ImageBuffer<Rgba<u8>, Vec<u8>>;
+
+/// Render a single glyph of the font, for icon fonts.
+/// ---
+/// The glyph is drawn at `--size` in `--color`, and the image is cropped to its pixels. This is intended for icon fonts
+/// (ex. Font Awesome, Material Symbols or Weather Icons), which are chosen with `--font`. Icons can be chosen by name
+/// with `--name` (ex. 'home'), which is resolved through the ligatures and glyph names of the font,
+/// or by their PostScript name with `--glyph-name` (ex. 'uni2600').
+#[derive(Debug, clap::Args)]
+pub struct Icon {
+    /// The icon to render.
+    #[clap(help = "The icon to render, as a character (ex. '☀') or a Unicode codepoint (ex. 'U+F185'), or as a glyph id with --id (ex. '42'), as a name with --name (ex. 'home'), or as a PostScript glyph name with --glyph-name (ex. 'uni2600').")]
+    #[clap(required = true)]
+    pub icon: String,
+
+    /// Interpret the icon as the id of a glyph in the font.
+    #[clap(help = "Interpret the icon as the id of a glyph in the font, instead of a character.")]
+    #[clap(long)]
+    pub id: bool,
+
+    /// Interpret the icon as the name of an icon in the font.
+    #[clap(help = "Interpret the icon as the name of an icon in the font (ex. 'home'), which is resolved through its ligatures or glyph names.")]
+    #[clap(long)]
+    #[clap(conflicts_with = "id")]
+    pub name: bool,
+
+    /// Interpret the icon as the PostScript name of a glyph in the font.
+    #[clap(help = "Interpret the icon as the PostScript name of a glyph in the font (ex. 'uni2600'), from its post or CFF table. Similar names are listed if it is not found.")]
+    #[clap(long)]
+    #[clap(conflicts_with_all = ["id", "name"])]
+    pub glyph_name: bool,
+}
+
+impl Icon {
+    /// Render the glyph of the icon with a [`Font`], as configured by [`Options`].
+    pub fn render(&self, font: &Font, options: &Options) -> Result<Image, Report> {
+        let invalid = || eyre!("Invalid icon: {:?}.", self.icon);
+        if self.name {
+            let image = rasterize_text::rasterize_icon(font, &self.icon, options)?;
+            return Ok(image);
+        }
+        if self.glyph_name {
+            let image = rasterize_text::rasterize_named_glyph(font, &self.icon, options)?;
+            return Ok(image);
+        }
+        let image = match self.id {
+            true => {
+                let id = self.icon.parse::<u16>().map_err(|_| invalid())?;
+                rasterize_text::rasterize_glyph_with_options(font, GlyphId(id), options)?
+            }
+            false => {
+                let mut chars = self.icon.chars();
+                let character = match (chars.next(), chars.next()) {
+                    (Some(character), None) => character,
+                    _ => self
+                        .icon
+                        .strip_prefix("U+")
+                        .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                        .and_then(char::from_u32)
+                        .ok_or_else(invalid)?,
+                };
+                rasterize_text::rasterize_glyph_with_options(font, character, options)?
+            }
+        };
+        Ok(image)
+    }
+}
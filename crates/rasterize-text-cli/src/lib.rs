@@ -1,8 +1,38 @@
 #![doc = include_str!("../../../README.md")]
 
+pub mod calendar;
+pub mod chat;
 pub mod cli;
+pub mod clock;
+pub mod compare;
+pub mod coverage;
+pub mod exec;
+pub mod fonts;
+pub mod icon;
+#[cfg(feature = "template")]
+pub mod merge;
+pub mod specimen;
+pub mod tail;
+#[cfg(feature = "template")]
+pub mod template;
 pub mod verbosity;
+pub mod waterfall;
 
 #[doc(inline)]
-pub use crate::cli::Cli;
+pub use crate::cli::{BackgroundKind, Cli, Command, ExportFormat};
+pub use crate::calendar::Calendar;
+pub use crate::chat::Chat;
+pub use crate::clock::Clock;
+pub use crate::compare::Compare;
+pub use crate::coverage::Coverage;
+pub use crate::exec::Exec;
+pub use crate::fonts::Fonts;
+pub use crate::icon::Icon;
+#[cfg(feature = "template")]
+pub use crate::merge::{Merge, Progress};
+pub use crate::specimen::Specimen;
+pub use crate::tail::Tail;
+#[cfg(feature = "template")]
+pub use crate::template::Template;
 pub use crate::verbosity::Verbosity;
+pub use crate::waterfall::Waterfall;
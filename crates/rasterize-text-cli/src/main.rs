@@ -1,9 +1,13 @@
 use chrono::Local; // Display log time in logging message.
 use clap::Parser; // Parse command-line arguments rfom the user.
-use color_eyre::eyre::{Report, Result}; // Handle errors with backtracking.
+use color_eyre::eyre::{eyre, Report, Result}; // Handle errors with backtracking.
 use env_logger::Builder; // Build a custom log message based on a Verbosity level.
-use rasterize_text_cli::Cli; // The command-line interface for the rasterize-text crate.
+use image::{ImageBuffer, Rgba}; // The pixels of rasterized text.
+use indicatif::MultiProgress; // Draw progress bars on the standard error.
+use indicatif_log_bridge::LogWrapper; // Write logging messages above the progress bars.
+use rasterize_text_cli::{BackgroundKind, Cli, Command, ExportFormat}; // The command-line interface for the rasterize-text crate.
 use std::io::Write; // Use the writeln macro for the loggin messages.
+use std::path::Path; // The path of the output png file.
 
 fn main() -> Result<(), Report> {
     // Parse arguments from the CLI
@@ -11,8 +15,8 @@ fn main() -> Result<(), Report> {
     // initialize color_eyre crate for colorized logs
     color_eyre::install()?;
 
-    // Customize logging message format
-    Builder::new()
+    // Customize logging message format, and hide the progress bars while a message is written
+    let logger = Builder::new()
         .format(|buf, record| {
             writeln!(
                 buf,
@@ -22,18 +26,438 @@ fn main() -> Result<(), Report> {
                 record.args()
             )
         })
-        .filter(None, args.verbosity.to_levelfilter())
-        .init();
+        .filter(None, args.verbosity.clone().to_levelfilter())
+        .build();
+    let level = logger.filter();
+    let bars = MultiProgress::new();
+    LogWrapper::new(bars.clone(), logger).try_init()?;
+    log::set_max_level(level);
+
+    // The vendored fonts are listed or exported instead of rendering anything
+    if args.list_fonts {
+        rasterize_text_cli::fonts::list();
+        return Ok(());
+    }
+    if let Some(Command::Fonts(fonts)) = &args.command {
+        fonts.run()?;
+        return Ok(());
+    }
+    // The output is required by the CLI, except for the vendored fonts
+    let output = args.output.as_deref().ok_or_else(|| {
+        eyre!("The following required argument was not provided: --output <OUTPUT>")
+    })?;
 
     // Convert input text to str to allow for unicode normalization
-    let text = args.text.as_str();
+    let text = args.text.as_deref().unwrap_or_default();
     // Read font
     let font = match &args.font {
         Some(path) => rasterize_text::read_font_file(path)?,
         None => rasterize_text::read_font_bytes(rasterize_text::EN_FONT)?,
     };
-    let image = rasterize_text::rasterize(&text, &font, args.size, &args.color);
-    image.save(args.output)?;
+    let fallback_fonts = args
+        .fallback_font
+        .iter()
+        .map(rasterize_text::read_font_file)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // Secrets are blurred where the patterns match the text, and within the byte ranges
+    let blur: Vec<_> = args
+        .blur
+        .iter()
+        .flat_map(|regex| regex.find_iter(text).map(|found| found.range()))
+        .chain(args.blur_range.iter().cloned())
+        .collect();
+
+    // Configure the layout and aesthetics
+    let options = rasterize_text::Options {
+        size: args.size,
+        color: args.color,
+        direction: args.direction,
+        lang: args.lang.clone(),
+        fallback_fonts,
+        wrap_width: args.wrap_width,
+        align: args.align,
+        justification: rasterize_text::Justification {
+            max_word_stretch: args.max_word_stretch,
+            max_letter_stretch: args.max_letter_stretch,
+            last_line: args.last_line,
+        },
+        first_line_indent: args.first_line_indent,
+        hanging_indent: args.hanging_indent,
+        paragraph_spacing: args.paragraph_spacing,
+        letter_spacing: args.letter_spacing,
+        fixed_ascent: args.fixed_ascent,
+        baseline_grid: args.baseline_grid,
+        tab_stops: args.tab_stops.clone(),
+        line_numbers: args.line_numbers.then(|| rasterize_text::LineNumbers {
+            start: args.line_number_start,
+            color: args.line_number_color,
+            rule_color: match args.no_gutter_rule {
+                true => None,
+                false => Some(args.gutter_rule_color.unwrap_or(args.line_number_color)),
+            },
+        }),
+        whitespace: args.whitespace,
+        text_transform: args.text_transform,
+        tabular_figures: args.tabular_figures,
+        #[cfg(feature = "emoji")]
+        emoji_shortcodes: args.emoji_shortcodes,
+        #[cfg(not(feature = "emoji"))]
+        emoji_shortcodes: false,
+        mask: match (args.mask, blur.is_empty()) {
+            (true, _) => Some(rasterize_text::Mask::All(args.mask_glyph)),
+            (false, false) => Some(rasterize_text::Mask::Blur(blur)),
+            (false, true) => None,
+        },
+        reveal_spoilers: args.reveal_spoilers,
+        effects: args.effect.clone(),
+        selection: (!args.select.is_empty()).then_some(rasterize_text::Selection {
+            ranges: args.select.clone(),
+            color: args.selection_color,
+        }),
+        background: args.background.map(|kind| {
+            let color = args.background_color;
+            let seed = args.background_seed;
+            match kind {
+                BackgroundKind::Solid => rasterize_text::Background::Solid(color),
+                BackgroundKind::Noise => rasterize_text::Background::Noise {
+                    color,
+                    amount: args.noise_amount,
+                    seed,
+                },
+                BackgroundKind::Paper => rasterize_text::Background::Paper { color, seed },
+                BackgroundKind::Gradient => rasterize_text::Background::Gradient {
+                    start: color,
+                    end: args.gradient_end_color,
+                    angle: args.gradient_angle,
+                },
+            }
+        }),
+        corner_radius: args.corner_radius,
+        border: (args.border_width > 0.0).then_some(rasterize_text::Border {
+            width: args.border_width,
+            color: args.border_color,
+        }),
+        pixel_snap: args.pixel_snap,
+        blending: args.blending,
+        coverage_threshold: args.coverage_threshold,
+        min_alpha: args.min_alpha,
+        max_alpha: args.max_alpha,
+        stroke_width: args.stroke_width,
+        stroke_color: args.stroke_color,
+        color_palette: rasterize_text::ColorPalette {
+            index: args.palette,
+            overrides: args.palette_color.clone(),
+            monochrome: args.monochrome,
+        },
+        #[cfg(feature = "twemoji")]
+        twemoji: args.twemoji.clone(),
+        #[cfg(not(feature = "twemoji"))]
+        twemoji: None,
+        tofu: args.tofu.map(|style| rasterize_text::Tofu {
+            style,
+            codepoint: !args.no_tofu_codepoint,
+        }),
+        strict: args.strict,
+        license_policy: args.license_policy,
+        max_width: args.max_width,
+        max_height: args.max_height,
+        max_pixels: args.max_pixels,
+        max_glyphs: args.max_glyphs,
+    };
+    let png_options = rasterize_text::PngOptions {
+        color_tag: args.color_tag,
+        metadata: args
+            .embed_metadata
+            .then(|| rasterize_text::RenderMetadata::new(text, &font, &options)),
+        alt_text: args
+            .embed_alt_text
+            .then(|| rasterize_text::AltText::new(text, &options)),
+    };
+    // The alt text is written next to the image, for publishing tools that don't read PNG chunks
+    if let Some(path) = &args.alt_text_file {
+        std::fs::write(path, rasterize_text::AltText::new(text, &options).to_json())?;
+    }
+    // A webfont with the glyphs of the text is written next to the image
+    if let Some(path) = &args.subset_font {
+        std::fs::write(path, rasterize_text::subset_font(&font, text)?)?;
+    }
+    // Only the text output is cached, so the images of the subcommands would be rendered without it
+    if args.cache_dir.is_some() && args.command.is_some() {
+        Err(eyre!("The images of the subcommands aren't cached, so --cache-dir is only used without a subcommand."))?;
+    }
+    if let Some(Command::Exec(exec)) = &args.command {
+        let image = exec.screenshot(&font, &options)?;
+        save(&image, output, png_options, args.print_hash)?;
+        return Ok(());
+    }
+    if let Some(Command::Tail(tail)) = &args.command {
+        // The image is saved next to the output file, and then moved over it, so that it is replaced at once
+        let mut partial = output.as_os_str().to_os_string();
+        partial.push(".partial");
+        tail.follow(&font, &options, |image| {
+            save(
+                image,
+                Path::new(&partial),
+                png_options.clone(),
+                args.print_hash,
+            )?;
+            std::fs::rename(&partial, output)?;
+            Ok(())
+        })?;
+        return Ok(());
+    }
+    if let Some(Command::Clock(clock)) = &args.command {
+        let image = clock.render(&font, &options)?;
+        save(&image, output, png_options, args.print_hash)?;
+        return Ok(());
+    }
+    if let Some(Command::Calendar(calendar)) = &args.command {
+        let image = calendar.render(&font, &options)?;
+        save(&image, output, png_options, args.print_hash)?;
+        return Ok(());
+    }
+    if let Some(Command::Icon(icon)) = &args.command {
+        let image = icon.render(&font, &options)?;
+        save(&image, output, png_options, args.print_hash)?;
+        return Ok(());
+    }
+    if let Some(Command::Specimen(specimen)) = &args.command {
+        let image = specimen.render(&font, &options)?;
+        save(&image, output, png_options, args.print_hash)?;
+        return Ok(());
+    }
+    if let Some(Command::Coverage(coverage)) = &args.command {
+        let image = coverage.render(&font, &options)?;
+        save(&image, output, png_options, args.print_hash)?;
+        return Ok(());
+    }
+    if let Some(Command::Waterfall(waterfall)) = &args.command {
+        let image = waterfall.render(&font, &options)?;
+        save(&image, output, png_options, args.print_hash)?;
+        return Ok(());
+    }
+    if let Some(Command::Compare(compare)) = &args.command {
+        let image = compare.render(&font, &options)?;
+        save(&image, output, png_options, args.print_hash)?;
+        return Ok(());
+    }
+    #[cfg(feature = "template")]
+    if let Some(Command::Merge(merge)) = &args.command {
+        // Outputs are written to their sink as they are encoded, so their hashes aren't printed
+        if args.print_hash {
+            Err(eyre!("The hashes of the outputs of a merge can't be printed, so --print-hash is only used by the other commands. Use --manifest to record the hashes of the outputs."))?;
+        }
+        let (sink, pattern) = rasterize_text_cli::merge::output_sink(&output.to_string_lossy())?;
+        let bar = match merge.progress {
+            true => bars.add(rasterize_text_cli::Progress::bar()),
+            false => indicatif::ProgressBar::hidden(),
+        };
+        merge.render(
+            sink.as_ref(),
+            &pattern,
+            &font,
+            &options,
+            &png_options,
+            |progress| progress.draw(&bar),
+        )?;
+        return Ok(());
+    }
+    #[cfg(feature = "template")]
+    if let Some(Command::Template(template)) = &args.command {
+        let image = template.render(&font, &options)?;
+        save(&image, output, png_options, args.print_hash)?;
+        return Ok(());
+    }
+    if let Some(Command::Chat(chat)) = &args.command {
+        let spans = spans(&args, text)?;
+        let image = chat.render(&spans, &font, &options)?;
+        save(&image, output, png_options, args.print_hash)?;
+        return Ok(());
+    }
+    #[cfg(feature = "latex")]
+    if args.latex {
+        let image = rasterize_text::rasterize_with_latex(text, &font, &options)?;
+        save(&image, output, png_options, args.print_hash)?;
+        return Ok(());
+    }
+    if let Some(path) = &args.background_image {
+        let background = image::open(path)?;
+        let card = rasterize_text::Card {
+            size: Some((
+                args.card_width.unwrap_or(background.width()),
+                args.card_height.unwrap_or(background.height()),
+            )),
+            fit: args.fit,
+            anchor: args.anchor,
+            margin: args.margin,
+            background,
+        };
+        let image = rasterize_text::rasterize_card(text, &font, &card, &options)?;
+        save(&image, output, png_options, args.print_hash)?;
+        return Ok(());
+    }
+    if args.bubble || args.nine_patch.is_some() {
+        let frame = match &args.nine_patch {
+            Some(path) => rasterize_text::BubbleFrame::NinePatch {
+                image: image::open(path)?.to_rgba8(),
+                insets: args.nine_patch_insets,
+            },
+            None => rasterize_text::BubbleFrame::Speech {
+                fill: args.bubble_color,
+                radius: args.bubble_radius,
+                outline: (args.bubble_outline_width > 0.0).then_some(rasterize_text::Border {
+                    width: args.bubble_outline_width,
+                    color: args.bubble_outline_color,
+                }),
+                tail: args.tail.map(|side| rasterize_text::Tail {
+                    side,
+                    position: args.tail_position,
+                    size: args.tail_size,
+                }),
+            },
+        };
+        let bubble = rasterize_text::Bubble {
+            frame,
+            padding: args.bubble_padding,
+        };
+        let image = rasterize_text::rasterize_bubble(text, &font, &bubble, &options)?;
+        save(&image, output, png_options, args.print_hash)?;
+        return Ok(());
+    }
+    let hershey_font = match &args.hershey_font {
+        Some(path) => Some(rasterize_text::HersheyFont::from_file(path)?),
+        None => None,
+    };
+    if let Some(format) = args.export {
+        let spans = spans(&args, text)?;
+        let outlines = match &hershey_font {
+            Some(hershey_font) => rasterize_text::outline_hershey(text, hershey_font, &options),
+            None => rasterize_text::outline_spans(&spans, &font, &options)?,
+        };
+        let engrave_options = rasterize_text::EngraveOptions {
+            tolerance: args.tolerance,
+            scale: args.mm_per_pixel,
+            safe_z: args.safe_z,
+            cut_z: args.cut_z,
+            feed_rate: args.feed_rate,
+        };
+        let file = std::io::BufWriter::new(std::fs::File::create(output)?);
+        match format {
+            ExportFormat::Dxf => rasterize_text::write_dxf(&outlines, &engrave_options, file)?,
+            ExportFormat::Gcode => rasterize_text::write_gcode(&outlines, &engrave_options, file)?,
+            ExportFormat::Hpgl => {
+                let plot_options = rasterize_text::PlotOptions {
+                    paper: args.paper,
+                    margin: args.paper_margin,
+                    pen: args.pen,
+                };
+                rasterize_text::write_hpgl(&outlines, &engrave_options, &plot_options, file)?
+            }
+        }
+        return Ok(());
+    }
+    if let Some(hershey_font) = &hershey_font {
+        let image = rasterize_text::rasterize_hershey(text, hershey_font, &options)?;
+        save(&image, output, png_options, args.print_hash)?;
+        return Ok(());
+    }
+    if let Some(path) = &args.diff {
+        let old = std::fs::read_to_string(path)?;
+        let mut style = rasterize_text::DiffStyle {
+            layout: args.diff_layout,
+            ..Default::default()
+        };
+        style.deleted.strikethrough = !args.no_strikethrough;
+        let image = rasterize_text::rasterize_diff(&old, text, &font, &style, &options)?;
+        save(&image, output, png_options, args.print_hash)?;
+        return Ok(());
+    }
+    if args.rgba16 {
+        let spans = spans(&args, text)?;
+        let image = rasterize_text::rasterize_rgba16_spans(&spans, &font, &options)?;
+        rasterize_text::save_png16(&image, output, png_options)?;
+        return Ok(());
+    }
+    let spans = spans(&args, text)?;
+    // Text is copied from the cache as it was encoded, if it was rendered with the same inputs before
+    if let Some(directory) = &args.cache_dir {
+        let cache = rasterize_text::DiskCache::new(directory);
+        let cached =
+            rasterize_text::rasterize_spans_cached(&spans, &font, &options, png_options, &cache)?;
+        warn(&cached.warnings);
+        std::fs::write(output, &cached.png)?;
+        if args.print_hash {
+            // The PNG stores colors apart from alpha, so they are premultiplied again like the rendered pixels
+            let image = image::load_from_memory(&cached.png)?.to_rgba8();
+            print_content_hash(&rasterize_text_cli::compare::premultiply(image));
+        }
+        return Ok(());
+    }
+    let rasterized = rasterize_text::rasterize_spans_with_metadata(&spans, &font, &options)?;
+    warn(&rasterized.warnings);
+    save(&rasterized.image, output, png_options, args.print_hash)?;
+
+    Ok(())
+}
 
+/// Report characters that the main font doesn't support.
+fn warn(warnings: &[rasterize_text::CoverageWarning]) {
+    warnings.iter().for_each(|warning| log::warn!("{warning}"));
+}
+
+/// Save an image to the output PNG file, and print a hash of its pixels if requested.
+fn save(
+    image: &ImageBuffer<Rgba<u8>, Vec<u8>>,
+    output: &Path,
+    png_options: rasterize_text::PngOptions,
+    print_hash: bool,
+) -> Result<(), Report> {
+    rasterize_text::save_png(image, output, png_options)?;
+    if print_hash {
+        print_content_hash(image);
+    }
     Ok(())
 }
+
+/// Print a hash of the pixels of an image (see [`rasterize_text::content_hash`]).
+fn print_content_hash(image: &ImageBuffer<Rgba<u8>, Vec<u8>>) {
+    println!("{:032x}", rasterize_text::content_hash(image));
+}
+
+/// Returns the spans of the text, parsed as math, ANSI escape codes or spoilers, or styled automatically as requested.
+fn spans(args: &Cli, text: &str) -> Result<Vec<rasterize_text::Span>, Report> {
+    // URLs, mentions, hashtags and the patterns are styled in the color of links
+    let link = rasterize_text::Style {
+        color: Some(args.auto_style_color),
+        ..Default::default()
+    };
+    let mut auto_styles = Vec::new();
+    if args.auto_style {
+        auto_styles.extend([
+            rasterize_text::AutoStyle::urls(rasterize_text::Style {
+                underline: true,
+                ..link
+            }),
+            rasterize_text::AutoStyle::mentions(link),
+            rasterize_text::AutoStyle::hashtags(link),
+        ]);
+    }
+    auto_styles.extend(
+        args.auto_style_pattern
+            .iter()
+            .map(|pattern| rasterize_text::AutoStyle {
+                pattern: pattern.clone(),
+                style: link,
+            }),
+    );
+
+    let spans = match (args.math, args.ansi) {
+        (true, _) => rasterize_text::parse_math(text)?,
+        (_, true) => rasterize_text::parse_ansi(text),
+        _ if args.spoilers => rasterize_text::parse_spoilers(text),
+        _ if !auto_styles.is_empty() => rasterize_text::auto_style(text, &auto_styles),
+        _ => vec![rasterize_text::Span::from(text)],
+    };
+    Ok(spans)
+}
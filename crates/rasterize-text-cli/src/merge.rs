@@ -0,0 +1,576 @@
+use crate::template::read_template;
+use color_eyre::eyre::{eyre, Report, Result};
+use image::{ImageBuffer, Rgba};
+use indicatif::{FormattedDuration, ProgressBar, ProgressStyle};
+use rasterize_text::{DirectorySink, Options, OutputSink, PngOptions};
+use rusttype::Font;
+use serde::de::{Deserializer, IgnoredAny, SeqAccess, Visitor};
+use serde_json::{Map, Value};
+use siphasher::sip128::{Hasher128, SipHasher13};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::hash::Hasher;
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+type Image = ImageBuffer<Rgba<u8>, Vec<u8>>;
+
+/// Render a template once for each row of a CSV or JSON file, like a mail merge.
+/// ---
+/// The data is a CSV file with a header row of variable names, or a JSON file with an array of objects (by its
+/// extension), and each row fills in the placeholders of the template (see the `template` subcommand). The output
+/// path is a pattern with placeholders of the same variables (ex. 'badges/{name}.png'), whose directories are created
+/// as needed, and which must be different for every row. With the `object_store` feature, the output can also be the
+/// URL of an S3 or GCS bucket (ex. 's3://conference/badges/{name}.png'), whose credentials are read from the
+/// environment (ex. AWS_ACCESS_KEY_ID). Rows are rendered in parallel, ex. to generate the badges of
+/// a conference in one command. The data is read once to count its rows, and again one row at a time as the jobs take
+/// them, and each job holds one row and its image at a time, so the memory that a merge uses is bounded by its number
+/// of jobs rather than its number of rows.
+///
+/// Rows that fail are collected rather than stopping the merge, and are listed when it ends (and written to a file
+/// with --errors), so that they can be fixed and rendered again.
+///
+/// Large jobs can record each output in a manifest as it is saved, with a hash of its contents and a hash of the
+/// template and the variables of its row, and be resumed after they are interrupted: the rows whose outputs are in the
+/// manifest, and whose outputs, template and variables haven't changed since, are skipped.
+#[derive(Debug, clap::Args)]
+pub struct Merge {
+    /// Template file to render.
+    #[clap(help = "TOML or JSON file of the template (ex. 'card.toml'), which is read as JSON if its extension is '.json'.")]
+    #[clap(long)]
+    #[clap(required = true)]
+    pub template: PathBuf,
+
+    /// Data file with a row of variables for each output.
+    #[clap(help = "CSV file with a header row of variable names (ex. 'people.csv'), or JSON file with an array of objects of variables if its extension is '.json'.")]
+    #[clap(long)]
+    #[clap(required = true)]
+    pub data: PathBuf,
+
+    /// Number of rows that are rendered at the same time.
+    #[clap(help = "Number of rows that are rendered at the same time. If no number is provided, it is the number of CPUs.")]
+    #[clap(long)]
+    #[clap(required = false)]
+    pub jobs: Option<usize>,
+
+    /// Number of failed rows after which no more rows are rendered.
+    #[clap(help = "Number of rows that can fail before no more rows are rendered (ex. 1 to stop at the first failure). If no number is provided, every row is rendered.")]
+    #[clap(long)]
+    #[clap(required = false)]
+    pub max_failures: Option<usize>,
+
+    /// CSV file that the failed rows are written to.
+    #[clap(help = "CSV file that the rows that failed are written to, with their numbers, output paths and errors (ex. 'failed.csv'). It is only written if a row failed.")]
+    #[clap(long)]
+    #[clap(required = false)]
+    pub errors: Option<PathBuf>,
+
+    /// Manifest of the outputs that are saved.
+    #[clap(help = "File that each output is recorded in as it is saved, with a hash of its contents and a hash of the template and the variables of its row (ex. 'badges.manifest'), so that the job can be resumed with --resume. It is replaced unless the job is resumed.")]
+    #[clap(long)]
+    #[clap(required = false)]
+    pub manifest: Option<PathBuf>,
+
+    /// Skip the rows whose outputs are in the manifest.
+    #[clap(help = "Skip the rows whose outputs are recorded in the manifest and have the same contents, template and variables, to resume a job that was interrupted.")]
+    #[clap(long, requires = "manifest")]
+    pub resume: bool,
+
+    /// Draw a progress bar.
+    #[clap(help = "Draw a progress bar of the rows on the standard error, with the number of rows that failed and an estimate of the time that is left.")]
+    #[clap(long)]
+    pub progress: bool,
+}
+
+/// The progress of a [`Merge`], which is reported after each row.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Progress {
+    /// Number of rows of the data.
+    pub total: usize,
+    /// Number of rows that were rendered and saved.
+    pub rendered: usize,
+    /// Number of rows whose outputs were already saved, when the merge is resumed.
+    pub skipped: usize,
+    /// Number of rows that failed to render or save.
+    pub failed: usize,
+    /// Time since the rows started rendering.
+    pub elapsed: Duration,
+    /// Whether the merge ended, because every row is done or too many rows failed. It is reported once, after the
+    /// last row.
+    pub finished: bool,
+}
+
+impl Progress {
+    /// Returns the number of rows that are done, whether they were rendered, skipped or failed.
+    pub fn done(&self) -> usize {
+        self.rendered + self.skipped + self.failed
+    }
+
+    /// Returns an estimate of the time until every row is done, at the rate that rows were rendered so far, or
+    /// [`None`] before the first row is rendered. Skipped rows take no time, so they aren't part of the rate.
+    pub fn eta(&self) -> Option<Duration> {
+        let rendered = self.rendered + self.failed;
+        let remaining = self.total.saturating_sub(self.done());
+        (rendered > 0).then(|| self.elapsed.mul_f64(remaining as f64 / rendered as f64))
+    }
+
+    /// Returns a progress bar of the rows (ex. `[#####     ] 50/100 rows, 2 failed, ETA 00:00:12`), which is drawn
+    /// on the standard error and updated with [`Progress::draw`].
+    pub fn bar() -> ProgressBar {
+        let style = ProgressStyle::with_template("[{bar:30}] {pos}/{len} rows{msg}")
+            .expect("the template of the progress bar is valid")
+            .progress_chars("# ");
+        ProgressBar::new(0).with_style(style)
+    }
+
+    /// Update a progress bar (see [`Progress::bar`]) with the rows that are done, the number of rows that failed and
+    /// an estimate of the time that is left, and finish it when the merge ends.
+    pub fn draw(&self, bar: &ProgressBar) {
+        bar.set_length(self.total as u64);
+        bar.set_position(self.done() as u64);
+        let mut message = String::new();
+        if self.failed > 0 {
+            message.push_str(&format!(", {} failed", self.failed));
+        }
+        if let Some(eta) = self.eta().filter(|_| !self.finished) {
+            message.push_str(&format!(", ETA {}", FormattedDuration(eta)));
+        }
+        bar.set_message(message);
+        if self.finished {
+            bar.finish();
+        }
+    }
+}
+
+impl Merge {
+    /// Render the template for each row of the data with a [`Font`], as configured by [`Options`], and write each
+    /// image as a PNG (see [`PngOptions`]) to an [`OutputSink`], by the key pattern (see [`output_sink`]) filled in
+    /// with the variables of its row.
+    ///
+    /// The [`Progress`] is reported to a function after each row, one row at a time.
+    pub fn render<P>(
+        &self,
+        sink: &dyn OutputSink,
+        pattern: &str,
+        font: &Font,
+        options: &Options,
+        png_options: &PngOptions,
+        on_progress: P,
+    ) -> Result<(), Report>
+    where
+        P: Fn(&Progress) + Sync,
+    {
+        let template = read_template(&self.template)?;
+        let source = std::fs::read(&self.template)?;
+        // The rows are counted first, which also checks that the whole data can be read before any row is rendered
+        let mut total = 0;
+        read_rows(&self.data, |_| {
+            total += 1;
+            true
+        })?;
+
+        // The outputs that were saved before are skipped when resuming, and the manifest is appended to
+        let completed = match (&self.manifest, self.resume) {
+            (Some(manifest), true) => read_manifest(manifest)?,
+            _ => HashMap::new(),
+        };
+        let manifest = match &self.manifest {
+            Some(path) => {
+                let mut file = OpenOptions::new();
+                match self.resume {
+                    true => file.append(true),
+                    false => file.write(true).truncate(true),
+                };
+                Some(Mutex::new(file.create(true).open(path)?))
+            }
+            None => None,
+        };
+        let started = Instant::now();
+        let progress = Mutex::new(Progress {
+            total,
+            ..Default::default()
+        });
+        let advance = |update: fn(&mut Progress)| {
+            let mut progress = progress.lock().expect("a rendering thread panicked");
+            update(&mut progress);
+            progress.elapsed = started.elapsed();
+            on_progress(&progress);
+        };
+
+        let jobs = self
+            .jobs
+            .or_else(|| std::thread::available_parallelism().ok().map(usize::from))
+            .unwrap_or(1)
+            .clamp(1, total.max(1));
+        let stopped = || {
+            let failed = progress.lock().expect("a rendering thread panicked").failed;
+            self.max_failures.is_some_and(|max| failed >= max)
+        };
+        // Rows are handed to the jobs through a queue of one row per job, so only a few rows are read ahead
+        let (rows, queue) = std::sync::mpsc::sync_channel(jobs);
+        let queue = Mutex::new(queue);
+        let errors = Mutex::new(Vec::new());
+        let fail = |i: usize, key: Option<String>, error: Report| {
+            errors
+                .lock()
+                .expect("a rendering thread panicked")
+                .push((i, key, error));
+            advance(|progress| progress.failed += 1);
+        };
+        let read = std::thread::scope(|scope| {
+            for _ in 0..jobs {
+                scope.spawn(|| loop {
+                    let next = queue.lock().expect("a rendering thread panicked").recv();
+                    let Ok((i, row, key)) = next else {
+                        break;
+                    };
+                    // The rows that are already queued when too many rows failed are dropped
+                    if stopped() {
+                        continue;
+                    }
+                    let input = match input_hash(&source, &row) {
+                        Ok(input) => input,
+                        Err(error) => {
+                            fail(i, Some(key), error);
+                            continue;
+                        }
+                    };
+                    // Sinks that can't be read are written again, since their outputs can't be checked
+                    let saved = |hash: &u128| {
+                        sink.read(&key)
+                            .is_ok_and(|bytes| hash_bytes(&bytes) == *hash)
+                    };
+                    let unchanged = completed
+                        .get(&key)
+                        .is_some_and(|(hash, recorded)| *recorded == input && saved(hash));
+                    if unchanged {
+                        advance(|progress| progress.skipped += 1);
+                        continue;
+                    }
+                    let rendered = render_row(&template, &row, font, options)
+                        .and_then(|image| save_row(&image, &key, sink, png_options))
+                        .and_then(|bytes| match &manifest {
+                            Some(manifest) => record(manifest, &key, &bytes, input),
+                            None => Ok(()),
+                        });
+                    match rendered {
+                        Ok(()) => advance(|progress| progress.rendered += 1),
+                        Err(error) => fail(i, Some(key), error),
+                    }
+                });
+            }
+
+            // Rows with the same output path would overwrite each other, so only the first of them is rendered
+            let mut keys = HashMap::new();
+            let mut i = 0;
+            let read = read_rows(&self.data, |row| {
+                if stopped() {
+                    return false;
+                }
+                match output_path(pattern, &row) {
+                    Ok(key) => match keys.insert(hash_bytes(key.as_bytes()), i) {
+                        Some(first) => fail(i, Some(key), eyre!("The output path is the same as the output path of row {}. Add placeholders of the variables of the rows to the output (ex. 'badges/{{name}}.png').", first + 1)),
+                        None => rows.send((i, row, key)).expect("a rendering thread panicked"),
+                    },
+                    Err(error) => fail(i, None, error),
+                }
+                i += 1;
+                true
+            });
+            // The jobs stop once the queue is empty and no more rows are read
+            drop(rows);
+            read
+        });
+        read?;
+
+        let mut errors = errors.into_inner().expect("a rendering thread panicked");
+        errors.sort_by_key(|(i, _, _)| *i);
+        let mut progress = progress.into_inner().expect("a rendering thread panicked");
+        progress.elapsed = started.elapsed();
+        progress.finished = true;
+        on_progress(&progress);
+        log::info!("Rendered {} of {} rows.", progress.rendered, progress.total);
+        if progress.skipped > 0 {
+            log::info!(
+                "Skipped {} rows whose outputs were already saved.",
+                progress.skipped
+            );
+        }
+        // Rows are numbered from 1, as in a spreadsheet without its header row
+        for (i, _, error) in &errors {
+            log::error!("Failed to render row {}: {error:#}", i + 1);
+        }
+        if let Some(path) = &self.errors {
+            if !errors.is_empty() {
+                write_errors(path, &errors)?;
+            }
+        }
+        let not_rendered = progress.total - progress.done();
+        if not_rendered > 0 {
+            log::warn!(
+                "Stopped after {} failed rows, so {not_rendered} rows weren't rendered.",
+                progress.failed
+            );
+        }
+        match errors.into_iter().next() {
+            Some((i, _, error)) => Err(error.wrap_err(format!(
+                "Failed to render {} of {} rows, the first of which is row {}.",
+                progress.failed,
+                progress.total,
+                i + 1
+            ))),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Returns the template rendered with the variables of a row.
+fn render_row(
+    template: &rasterize_text::Template,
+    row: &Map<String, Value>,
+    font: &Font,
+    options: &Options,
+) -> Result<Image, Report> {
+    let image = rasterize_text::render_template_with_data(template, row, font, options)?;
+    Ok(image)
+}
+
+/// Write the image of a row to its key of a sink as a PNG, and return the bytes of the PNG.
+fn save_row(
+    image: &Image,
+    key: &str,
+    sink: &dyn OutputSink,
+    png_options: &PngOptions,
+) -> Result<Vec<u8>, Report> {
+    let mut bytes = Vec::new();
+    rasterize_text::write_png(image, &mut bytes, png_options.clone())?;
+    sink.write(key, &bytes)
+        .map_err(|error| eyre!("Failed to write the output {key:?}: {error}"))?;
+    Ok(bytes)
+}
+
+/// Write the rows that failed to a CSV file, with a header row and the number, output path and error of each row.
+///
+/// Rows whose output path couldn't be filled in have an empty output path.
+fn write_errors(path: &Path, errors: &[(usize, Option<String>, Report)]) -> Result<(), Report> {
+    let mut writer = csv::Writer::from_path(path)?;
+    writer.write_record(["row", "output", "error"])?;
+    for (i, key, error) in errors {
+        let row = (i + 1).to_string();
+        let error = format!("{error:#}");
+        writer.write_record([&row, key.as_deref().unwrap_or_default(), &error])?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Returns a hash of the contents of an output, as the 128 bit SipHash-1-3 (with keys of zero) of its bytes.
+fn hash_bytes(bytes: &[u8]) -> u128 {
+    SipHasher13::new().hash(bytes).as_u128()
+}
+
+/// Returns a hash of what the output of a row is rendered from, which is the template file and the variables of the
+/// row, as the 128 bit SipHash-1-3 (with keys of zero) of their bytes.
+fn input_hash(template: &[u8], row: &Map<String, Value>) -> Result<u128, Report> {
+    let mut hasher = SipHasher13::new();
+    // Each input is prefixed with its length, so that the bytes of the template can't be moved to the row
+    for bytes in [template, &serde_json::to_vec(row)?] {
+        hasher.write(&(bytes.len() as u64).to_le_bytes());
+        hasher.write(bytes);
+    }
+    Ok(hasher.finish128().as_u128())
+}
+
+/// Record an output that was saved in the manifest, as a line with the hash of its contents, the hash of its inputs
+/// (see [`input_hash`]) and its key, with two spaces before the key (like the output of `sha256sum`).
+fn record(manifest: &Mutex<File>, key: &str, bytes: &[u8], input: u128) -> Result<(), Report> {
+    let hash = hash_bytes(bytes);
+    let mut manifest = manifest.lock().expect("a rendering thread panicked");
+    writeln!(manifest, "{hash:032x} {input:032x}  {key}")?;
+    manifest.flush()?;
+    Ok(())
+}
+
+/// Returns the keys of the outputs of a manifest, and the hashes of their contents and their inputs.
+///
+/// A manifest that doesn't exist has no outputs, and lines that aren't complete (ex. from a job that was killed
+/// while it wrote the manifest) are left out.
+fn read_manifest(path: &Path) -> Result<HashMap<String, (u128, u128)>, Report> {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(error) => Err(error)?,
+    };
+    let outputs = contents
+        .lines()
+        .filter_map(|line| {
+            let hash = u128::from_str_radix(line.get(..32)?, 16).ok()?;
+            let line = line.get(32..)?.strip_prefix(' ')?;
+            let input = u128::from_str_radix(line.get(..32)?, 16).ok()?;
+            let output = line.get(32..)?.strip_prefix("  ")?;
+            Some((output.to_string(), (hash, input)))
+        })
+        .collect();
+    Ok(outputs)
+}
+
+/// Returns the [`OutputSink`] of an output, and the pattern of the keys of the outputs within it.
+///
+/// The output is a path (ex. `../badges/{name}.png`), whose leading `/` and `..` are the directory of a
+/// [`DirectorySink`] and whose other parts are the pattern. With the `object_store` feature, it can also be the URL of
+/// an S3 or GCS bucket (ex. `s3://conference/badges/{name}.png` or `gs://conference/badges/{name}.png`), whose
+/// credentials and region are read from the environment, and whose path is the pattern.
+pub fn output_sink(output: &str) -> Result<(Box<dyn OutputSink>, String), Report> {
+    #[cfg(feature = "object_store")]
+    if let Some((scheme, path)) = output.split_once("://") {
+        let (bucket, pattern) = path.split_once('/').unwrap_or((path, ""));
+        let store: std::sync::Arc<dyn object_store::ObjectStore> = match scheme {
+            "s3" => std::sync::Arc::new(
+                object_store::aws::AmazonS3Builder::from_env()
+                    .with_bucket_name(bucket)
+                    .build()?,
+            ),
+            "gs" => std::sync::Arc::new(
+                object_store::gcp::GoogleCloudStorageBuilder::from_env()
+                    .with_bucket_name(bucket)
+                    .build()?,
+            ),
+            _ => Err(eyre!(
+                "Unsupported output URL: {output:?}. Expected an s3:// or gs:// URL, or a path."
+            ))?,
+        };
+        let sink = rasterize_text::ObjectStoreSink::new(store, "")?;
+        return Ok((Box::new(sink), pattern.to_string()));
+    }
+    #[cfg(not(feature = "object_store"))]
+    if output.contains("://") {
+        Err(eyre!("Unsupported output URL: {output:?}. Buckets are only supported with the object_store feature."))?;
+    }
+
+    // The directory is where the output leaves the current directory, so that the pattern is a relative key
+    let mut components = Path::new(output).components().peekable();
+    let mut directory = PathBuf::from(".");
+    while let Some(component) = components.next_if(|c| !matches!(c, Component::Normal(_))) {
+        directory.push(component);
+    }
+    let mut parts = Vec::new();
+    for component in components {
+        match component {
+            Component::Normal(part) => parts.push(part.to_string_lossy()),
+            _ => Err(eyre!("Invalid output: {output:?}. Only the start of the output can leave its directory (ex. '../badges/{{name}}.png')."))?,
+        }
+    }
+    Ok((Box::new(DirectorySink::new(directory)), parts.join("/")))
+}
+
+/// Returns an output path with its placeholders (ex. `{name}`) replaced by the variables of a row.
+///
+/// The pattern is read once, so placeholders in the values aren't replaced, and placeholders of variables that the
+/// row doesn't have are kept. Path separators in the values are replaced by `_`, and values that are empty, `.` or
+/// `..` are rejected, so that a value can't write outside of the directory of the pattern.
+fn output_path(pattern: &str, row: &Map<String, Value>) -> Result<String, Report> {
+    let mut path = String::new();
+    let mut rest = pattern;
+    while let Some(start) = rest.find('{') {
+        path.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some((name, value)) = rest[1..]
+            .find('}')
+            .and_then(|end| row.get_key_value(&rest[1..end + 1]))
+        else {
+            path.push('{');
+            rest = &rest[1..];
+            continue;
+        };
+        let value = match value {
+            Value::String(value) => value.clone(),
+            value => value.to_string(),
+        };
+        let value = value.replace(['/', '\\'], "_");
+        if matches!(value.as_str(), "" | "." | "..") {
+            Err(eyre!("Invalid output: the variable {name:?} is {value:?}, which can't be part of a path."))?;
+        }
+        path.push_str(&value);
+        rest = &rest[name.len() + 2..];
+    }
+    path.push_str(rest);
+    Ok(path)
+}
+
+/// Read the rows of a data file one at a time, and pass each of them to a function until it returns `false`.
+///
+/// The data is a JSON array of objects of variables if its extension is `.json`, and otherwise a CSV file with a
+/// header row of variable names, whose fields in double quotes can have commas, newlines and doubled quotes (`""`).
+fn read_rows<F>(path: &Path, each: F) -> Result<(), Report>
+where
+    F: FnMut(Map<String, Value>) -> bool,
+{
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("json") => json_rows(path, each),
+        _ => csv_rows(path, each),
+    }
+}
+
+/// Read the rows of a JSON array of objects (see [`read_rows`]).
+fn json_rows<F>(path: &Path, each: F) -> Result<(), Report>
+where
+    F: FnMut(Map<String, Value>) -> bool,
+{
+    let reader = std::io::BufReader::new(File::open(path)?);
+    let mut deserializer = serde_json::Deserializer::from_reader(reader);
+    deserializer
+        .deserialize_seq(Rows(each))
+        .and_then(|()| deserializer.end())
+        .map_err(|error| {
+            eyre!("Invalid data: {path:?}. Expected a JSON array of objects ({error}).")
+        })
+}
+
+/// Read the rows of a CSV file, as variables named by its header row (see [`read_rows`]).
+fn csv_rows<F>(path: &Path, mut each: F) -> Result<(), Report>
+where
+    F: FnMut(Map<String, Value>) -> bool,
+{
+    let mut reader = csv::ReaderBuilder::new()
+        .trim(csv::Trim::Headers)
+        .from_path(path)?;
+    let header = reader.headers()?.clone();
+    for record in reader.records() {
+        let record = record.map_err(|error| eyre!("Invalid CSV data: {error}"))?;
+        let row = header
+            .iter()
+            .zip(&record)
+            .map(|(name, value)| (name.to_string(), Value::String(value.to_string())))
+            .collect();
+        if !each(row) {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Passes the objects of a JSON array to a function as they are deserialized, rather than collecting them.
+struct Rows<F>(F);
+
+impl<'de, F> Visitor<'de> for Rows<F>
+where
+    F: FnMut(Map<String, Value>) -> bool,
+{
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("an array of objects of variables")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(mut self, mut seq: A) -> Result<(), A::Error> {
+        while let Some(row) = seq.next_element()? {
+            if !(self.0)(row) {
+                // The rest of the array is skipped, since it must be read to the end
+                while seq.next_element::<IgnoredAny>()?.is_some() {}
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,157 @@
+use crate::exec::{over, to_rgba};
+use color_eyre::eyre::{Report, Result};
+use image::{ImageBuffer, Rgba};
+use rasterize_text::{Color, Options, Span, Style};
+use rusttype::Font;
+use std::path::PathBuf;
+
+type Image = ImageBuffer<Rgba<u8>, Vec<u8>>;
+
+/// The characters of a specimen, above the pangram.
+const CHARACTERS: [&str; 4] = [
+    "ABCDEFGHIJKLMNOPQRSTUVWXYZ",
+    "abcdefghijklmnopqrstuvwxyz",
+    "0123456789",
+    "!\"#$%&'()*+,-./:;<=>?@[\\]^_`{|}~",
+];
+/// Color of the labels of the sizes and weights, which are drawn smaller than the text.
+const LABEL_COLOR: Color = Color {
+    r: 128,
+    g: 128,
+    b: 128,
+    a: 255,
+};
+
+/// Render a specimen of the font, for cataloging font libraries.
+/// ---
+/// The specimen has the name of the font, its alphabet, numerals and punctuation at `--size`, and a pangram at
+/// several sizes, followed by the pangram in each of the weights of the font that are provided with `--weight`
+/// (or in a synthetic bold if none are). The font is chosen with `--font`.
+#[derive(Debug, clap::Args)]
+pub struct Specimen {
+    /// Pangram that is rendered at several sizes.
+    #[clap(help = "Pangram that is rendered at each of the sizes, and in each of the weights.")]
+    #[clap(long)]
+    #[clap(default_value_t = String::from("The quick brown fox jumps over the lazy dog."))]
+    pub pangram: String,
+
+    /// Sizes of the pangram in pixels.
+    #[clap(help = "Comma delimited sizes of the pangram in pixels (ex. '12,24,48').")]
+    #[clap(long, value_delimiter = ',')]
+    #[clap(default_values_t = [12.0, 18.0, 24.0, 36.0, 48.0, 72.0])]
+    pub sizes: Vec<f32>,
+
+    /// Font files of other weights of the font.
+    #[clap(help = "Font file of another weight or style of the font family (ex. 'DejaVuSans-Bold.ttf'), whose pangram is rendered below the sizes. May be repeated.")]
+    #[clap(long)]
+    #[clap(required = false)]
+    pub weight: Vec<PathBuf>,
+
+    /// Space around the specimen in pixels.
+    #[clap(help = "Space around the specimen and between its sections in pixels.")]
+    #[clap(long)]
+    #[clap(default_value_t = 32)]
+    pub padding: u32,
+
+    /// Background color of the specimen.
+    #[clap(help = "Background color of the specimen as a CSS color (ex. '#f5f5f5') or a space delimited RGBA value.")]
+    #[clap(short = 'b', long)]
+    #[clap(default_value_t = Color { r: 255, g: 255, b: 255, a: 255 })]
+    pub background: Color,
+}
+
+impl Specimen {
+    /// Render a specimen of a [`Font`], as configured by [`Options`].
+    pub fn render(&self, font: &Font, options: &Options) -> Result<Image, Report> {
+        let options = Options {
+            wrap_width: None,
+            ..options.clone()
+        };
+        let label_options = Options {
+            size: (options.size / 2.0).max(8.0),
+            color: LABEL_COLOR,
+            ..options.clone()
+        };
+        let text = |text: &str, style: Style, font: &Font, options: &Options| {
+            let span = Span {
+                text: text.to_string(),
+                style,
+                ruby: None,
+                image: None,
+            };
+            rasterize_text::rasterize_spans(&[span], font, options)
+        };
+        let label = |label: &str| text(label, Style::default(), font, &label_options);
+        let bold = Style {
+            bold: true,
+            ..Default::default()
+        };
+
+        // Each section is a list of rows, which are drawn below each other
+        let mut sections: Vec<Vec<Image>> = Vec::new();
+        let name = font_name(font, &options).unwrap_or_else(|| String::from("Unknown font"));
+        let title = Options {
+            size: options.size * 1.5,
+            ..options.clone()
+        };
+        sections.push(vec![text(&name, bold, font, &title)?]);
+        sections.push(
+            CHARACTERS
+                .iter()
+                .map(|characters| text(characters, Style::default(), font, &options))
+                .collect::<Result<_, _>>()?,
+        );
+
+        let mut sizes = Vec::new();
+        for size in &self.sizes {
+            sizes.push(label(&format!("{size}px"))?);
+            let options = Options {
+                size: *size,
+                ..options.clone()
+            };
+            sizes.push(text(&self.pangram, Style::default(), font, &options)?);
+        }
+        sections.push(sizes);
+
+        let mut weights = Vec::new();
+        if self.weight.is_empty() {
+            weights.push(label("Bold (synthetic)")?);
+            weights.push(text(&self.pangram, bold, font, &options)?);
+        }
+        for path in &self.weight {
+            let weight = rasterize_text::read_font_file(path)?;
+            let name = font_name(&weight, &options).unwrap_or_else(|| path.display().to_string());
+            weights.push(label(&name)?);
+            weights.push(text(&self.pangram, Style::default(), &weight, &options)?);
+        }
+        sections.push(weights);
+
+        // Rows are separated by a quarter of the padding, and sections by the padding
+        let gap = self.padding / 4;
+        let rows = sections.iter().flatten();
+        let width = rows
+            .clone()
+            .map(|row| row.width())
+            .max()
+            .unwrap_or_default()
+            + 2 * self.padding;
+        let height = rows.map(|row| row.height() + gap).sum::<u32>()
+            + (sections.len() as u32 + 1) * self.padding
+            - sections.len() as u32 * gap;
+        let mut image = Image::from_pixel(width, height, to_rgba(self.background));
+        let mut y = self.padding;
+        for section in &sections {
+            for row in section {
+                over(&mut image, row, self.padding, y);
+                y += row.height() + gap;
+            }
+            y += self.padding - gap;
+        }
+        Ok(image)
+    }
+}
+
+/// Returns the full name of a font (ex. `DejaVu Sans Bold`), or its family name, if it has one.
+fn font_name(font: &Font, options: &Options) -> Option<String> {
+    rasterize_text::RenderMetadata::new("", font, options).font_name
+}
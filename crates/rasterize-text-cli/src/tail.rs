@@ -0,0 +1,150 @@
+use crate::exec::{over, to_rgba};
+use color_eyre::eyre::{eyre, Report, Result};
+use image::{ImageBuffer, Rgba};
+use rasterize_text::{Color, GridLayout, Options};
+use rusttype::Font;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+type Image = ImageBuffer<Rgba<u8>, Vec<u8>>;
+
+/// Number of bytes that are read at a time from the end of the log file, while looking for the last lines.
+const CHUNK_SIZE: u64 = 64 * 1024;
+
+/// Follow a log file, and render its last lines to the output file whenever it changes.
+/// ---
+/// The last lines of the file are interpreted as ANSI escape sequences, and laid out on a grid of character cells
+/// like a terminal, for dashboards and status screens that can only display images. Lines that wrap take up several
+/// rows, so fewer lines are drawn to keep the newest line at the bottom. Colors that were set before the first line
+/// that is drawn are not carried over.
+///
+/// The output file is replaced at once, so that a partly written image is never displayed.
+#[derive(Debug, clap::Args)]
+pub struct Tail {
+    /// Path to the log file.
+    #[clap(help = "Path to the log file to follow (ex. 'rasterize-text -o status.png tail /var/log/app.log').")]
+    #[clap(required = true)]
+    pub file: PathBuf,
+
+    /// Number of lines to render.
+    #[clap(help = "Number of lines at the end of the file to render, which is the height of the terminal in character cells.")]
+    #[clap(short = 'n', long)]
+    #[clap(default_value_t = 20)]
+    pub lines: usize,
+
+    /// Width of the terminal in character cells.
+    #[clap(help = "Width of the terminal in character cells. Longer lines wrap onto the next row.")]
+    #[clap(long)]
+    #[clap(default_value_t = 100)]
+    pub cols: usize,
+
+    /// Seconds between checks of the file for changes.
+    #[clap(help = "Seconds between checks of the file for changes. The image is only rendered again when the file has changed.")]
+    #[clap(long)]
+    #[clap(default_value_t = 2.0)]
+    pub interval: f64,
+
+    /// Render the image once, and exit.
+    #[clap(help = "Render the image once, and exit, instead of following the file (ex. when run by cron).")]
+    #[clap(long)]
+    pub once: bool,
+
+    /// Space around the text in pixels.
+    #[clap(help = "Space around the text in pixels.")]
+    #[clap(long)]
+    #[clap(default_value_t = 16)]
+    pub padding: u32,
+
+    /// Background color of the terminal.
+    #[clap(help = "Background color of the terminal as a CSS color (ex. '#1e1e1e') or a space delimited RGBA value.")]
+    #[clap(short = 'b', long)]
+    #[clap(default_value_t = Color { r: 255, g: 255, b: 255, a: 255 })]
+    pub background: Color,
+}
+
+impl Tail {
+    /// Render the last lines of the file with a [`Font`], as configured by [`Options`], and save them with a function
+    /// whenever the file changes, until the file can't be read (or once, with `--once`).
+    pub fn follow<S>(&self, font: &Font, options: &Options, mut save: S) -> Result<(), Report>
+    where
+        S: FnMut(&Image) -> Result<(), Report>,
+    {
+        let mut last: Option<(u64, SystemTime)> = None;
+        loop {
+            // The length and modification time of the file change when lines are written to it
+            let metadata = std::fs::metadata(&self.file)?;
+            let version = (metadata.len(), metadata.modified()?);
+            if last != Some(version) {
+                log::info!("Rendering {:?}", self.file);
+                save(&self.render(font, options)?)?;
+                last = Some(version);
+            }
+            if self.once {
+                return Ok(());
+            }
+            std::thread::sleep(Duration::from_secs_f64(self.interval.max(0.0)));
+        }
+    }
+
+    /// Render the last lines of the file as a terminal with a [`Font`], as configured by [`Options`].
+    pub fn render(&self, font: &Font, options: &Options) -> Result<Image, Report> {
+        let grid = GridLayout::new(self.cols, self.lines.max(1), font, options.size);
+        let mut lines = self.read()?;
+        let mut text = lines.join("\n");
+        // Drop the oldest lines until the newest line is on the last row
+        while lines.len() > 1 && grid.rows_for(&strip_ansi(&text), options) > grid.rows {
+            lines.remove(0);
+            text = lines.join("\n");
+        }
+        let spans = rasterize_text::parse_ansi(&text);
+        let terminal = rasterize_text::rasterize_grid_spans(&spans, font, &grid, options)?;
+
+        // The padding is provided by the user, so a padding that is too large is an error rather than an overflow
+        let pad = |length: u32| {
+            self.padding
+                .checked_mul(2)
+                .and_then(|padding| length.checked_add(padding))
+                .ok_or_else(|| eyre!("The padding of {} pixels is too large.", self.padding))
+        };
+        let width = pad(terminal.width())?;
+        let height = pad(terminal.height())?;
+        let mut image = Image::from_pixel(width, height, to_rgba(self.background));
+        over(&mut image, &terminal, self.padding, self.padding);
+        Ok(image)
+    }
+
+    /// Read the last lines of the file, without reading all of it.
+    fn read(&self) -> Result<Vec<String>, Report> {
+        let mut file = std::fs::File::open(&self.file)?;
+        let length = file.metadata()?.len();
+        // Chunks are read backwards from the end, until there are more line breaks than lines
+        let mut start = length;
+        let mut bytes = Vec::new();
+        while start > 0 && bytes.iter().filter(|b| **b == b'\n').count() <= self.lines {
+            let size = CHUNK_SIZE.min(start);
+            start -= size;
+            let mut chunk = vec![0; size as usize];
+            file.seek(SeekFrom::Start(start))?;
+            file.read_exact(&mut chunk)?;
+            chunk.append(&mut bytes);
+            bytes = chunk;
+        }
+        let text = String::from_utf8_lossy(&bytes);
+        let lines: Vec<&str> = text.trim_end_matches('\n').split('\n').collect();
+        // The first line is incomplete if the file wasn't read from its start, but there are more lines than are kept
+        let first = lines.len().saturating_sub(self.lines);
+        Ok(lines[first..]
+            .iter()
+            .map(|line| line.trim_end_matches('\r').to_string())
+            .collect())
+    }
+}
+
+/// Returns text without its ANSI escape sequences, as it is laid out on the grid.
+fn strip_ansi(text: &str) -> String {
+    rasterize_text::parse_ansi(text)
+        .iter()
+        .map(|span| span.text.as_str())
+        .collect()
+}
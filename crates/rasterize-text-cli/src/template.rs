@@ -0,0 +1,81 @@
+use color_eyre::eyre::{eyre, Report, Result};
+use image::{ImageBuffer, Rgba};
+use rasterize_text::Options;
+use rusttype::Font;
+use std::path::{Path, PathBuf};
+
+type Image = ImageBuffer<Rgba<u8>, Vec<u8>>;
+
+/// Render a template of texts on a canvas, with its placeholders filled in by variables.
+/// ---
+/// The template is a TOML or JSON file (by its extension) that describes the size and background of the canvas, and
+/// the texts on it with their positions and styles (ex. a social card). Placeholders in braces (ex. '{title}') are
+/// replaced by the values of `--var`, so that a card can be edited without code changes. The font and options are used
+/// for the texts that don't have their own, and paths in the template are relative to the template file. With the
+/// `tera` feature, templates with `engine = "tera"` can have conditionals and loops over the arrays of `--data`.
+#[derive(Debug, clap::Args)]
+pub struct Template {
+    /// Template file to render.
+    #[clap(help = "TOML or JSON file of the template (ex. 'card.toml'), which is read as JSON if its extension is '.json'.")]
+    #[clap(required = true)]
+    pub template: PathBuf,
+
+    /// Variables of the placeholders of the template.
+    #[clap(help = "Value of a placeholder of the template, as NAME=VALUE (ex. 'title=Release 1.2'). Can be repeated.")]
+    #[clap(long = "var", value_parser = parse_var)]
+    pub vars: Vec<(String, String)>,
+
+    /// JSON file of the variables of the template.
+    #[clap(help = "JSON file with an object of variables for the template (ex. arrays of rows for the loops of a Tera template). Variables of --var replace the variables of the same names.")]
+    #[clap(long)]
+    #[clap(required = false)]
+    pub data: Option<PathBuf>,
+}
+
+impl Template {
+    /// Render the template with a [`Font`], as configured by [`Options`].
+    pub fn render(&self, font: &Font, options: &Options) -> Result<Image, Report> {
+        let template = read_template(&self.template)?;
+        let mut data = match &self.data {
+            Some(path) => match serde_json::from_str(&std::fs::read_to_string(path)?)? {
+                serde_json::Value::Object(data) => data,
+                _ => Err(eyre!(
+                    "Invalid data: {path:?}. Expected a JSON object of variables."
+                ))?,
+            },
+            None => serde_json::Map::new(),
+        };
+        for (name, value) in &self.vars {
+            data.insert(name.clone(), serde_json::Value::String(value.clone()));
+        }
+        let image = rasterize_text::render_template_with_data(&template, &data, font, options)?;
+        Ok(image)
+    }
+}
+
+/// Returns a template read from a TOML or JSON file (by its extension), with its paths relative to the file.
+pub fn read_template(path: &Path) -> Result<rasterize_text::Template, Report> {
+    let contents = std::fs::read_to_string(path)?;
+    let mut template = match path.extension().and_then(|e| e.to_str()) {
+        Some("json") => rasterize_text::Template::from_json(&contents)?,
+        _ => rasterize_text::Template::from_toml(&contents)?,
+    };
+    // Paths are relative to the template file, rather than to the working directory
+    let directory = path.parent().unwrap_or(path);
+    let paths = template
+        .fonts
+        .values_mut()
+        .chain(template.background_image.iter_mut());
+    for path in paths.filter(|path| path.is_relative()) {
+        *path = directory.join(&path);
+    }
+    Ok(template)
+}
+
+/// Returns a variable converted from a [`str`] of the form `NAME=VALUE`.
+fn parse_var(var: &str) -> Result<(String, String), String> {
+    let (name, value) = var.split_once('=').ok_or_else(|| {
+        format!("Invalid variable: {var:?}. Expected NAME=VALUE (ex. 'title=Release 1.2').")
+    })?;
+    Ok((name.trim().to_string(), value.to_string()))
+}
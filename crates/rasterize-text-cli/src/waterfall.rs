@@ -0,0 +1,58 @@
+use color_eyre::eyre::{Report, Result};
+use image::{ImageBuffer, Rgba};
+use rasterize_text::Options;
+use rusttype::Font;
+use std::path::PathBuf;
+
+type Image = ImageBuffer<Rgba<u8>, Vec<u8>>;
+
+/// Render a text at a list of sizes stacked vertically, for font selection and hinting evaluation.
+/// ---
+/// The text is rendered in the font of `--font` at each of the sizes, with the size to the left of each row. Other
+/// fonts that are provided with `--compare` are rendered side by side with it, on the same baselines.
+#[derive(Debug, clap::Args)]
+pub struct Waterfall {
+    /// Text to render at each size.
+    #[clap(help = "Text to render at each size, which has the letters that tell fonts apart by default.")]
+    #[clap(default_value = "Hamburgefonstiv")]
+    pub text: String,
+
+    /// Sizes of the text in pixels.
+    #[clap(help = "Comma delimited sizes of the text in pixels, from the top row to the bottom row (ex. '10,12,16,24').")]
+    #[clap(long, value_delimiter = ',')]
+    #[clap(default_values_t = rasterize_text::Waterfall::default().sizes)]
+    pub sizes: Vec<f32>,
+
+    /// Font files to compare with the font.
+    #[clap(help = "Font file to compare with the font, which is rendered in a column to its right. May be repeated.")]
+    #[clap(long)]
+    #[clap(required = false)]
+    pub compare: Vec<PathBuf>,
+
+    /// Don't label the sizes of the rows.
+    #[clap(help = "Don't label the sizes of the rows.")]
+    #[clap(long)]
+    pub no_labels: bool,
+}
+
+impl Waterfall {
+    /// Render the text at each size with a [`Font`] and the fonts it is compared with, as configured by [`Options`].
+    pub fn render(&self, font: &Font, options: &Options) -> Result<Image, Report> {
+        let others = self
+            .compare
+            .iter()
+            .map(rasterize_text::read_font_file)
+            .collect::<Result<Vec<_>, _>>()?;
+        let fonts: Vec<&Font> = std::iter::once(font).chain(others.iter()).collect();
+        let waterfall = rasterize_text::Waterfall {
+            sizes: self.sizes.clone(),
+            label_color: match self.no_labels {
+                true => None,
+                false => rasterize_text::Waterfall::default().label_color,
+            },
+            ..Default::default()
+        };
+        let image = rasterize_text::rasterize_waterfall(&self.text, &fonts, &waterfall, options)?;
+        Ok(image)
+    }
+}
@@ -0,0 +1,143 @@
+//! Render fixed strings at compile time with [`rasterize_text`], and embed their pixels in the binary, so that `no_std`
+//! firmware can draw a handful of static labels without fonts, a rasterizer or a build script.
+//!
+//! For many labels, or labels that are shared between crates, see [`rasterize_text_build`], which writes them to a
+//! file from a build script.
+
+use proc_macro::TokenStream;
+use proc_macro2::Literal;
+use quote::quote;
+use rasterize_text::{rasterize_with_options, read_font_bytes, read_font_file, Options, EN_FONT};
+use rasterize_text_build::PixelFormat;
+use std::path::PathBuf;
+use syn::parse::{Parse, ParseStream};
+use syn::{parse_macro_input, Ident, Lit, LitStr, Token};
+
+/// The arguments of [`rasterized!`]: the text, followed by optional `key = value` pairs.
+struct Args {
+    text: LitStr,
+    size: Option<f32>,
+    font: Option<LitStr>,
+    format: Option<Ident>,
+}
+
+impl Parse for Args {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut args = Args {
+            text: input.parse()?,
+            size: None,
+            font: None,
+            format: None,
+        };
+        while !input.is_empty() {
+            input.parse::<Token![,]>()?;
+            // Trailing commas are allowed
+            if input.is_empty() {
+                break;
+            }
+            let key: Ident = input.parse()?;
+            input.parse::<Token![=]>()?;
+            match key.to_string().as_str() {
+                "size" => {
+                    args.size = Some(match input.parse()? {
+                        Lit::Int(size) => size.base10_parse()?,
+                        Lit::Float(size) => size.base10_parse()?,
+                        lit => Err(syn::Error::new(
+                            lit.span(),
+                            "The size must be a number of pixels.",
+                        ))?,
+                    })
+                }
+                "font" => args.font = Some(input.parse()?),
+                "format" => args.format = Some(input.parse()?),
+                _ => Err(syn::Error::new(
+                    key.span(),
+                    format!("Unknown argument: {key}. Arguments are 'size', 'font' and 'format'."),
+                ))?,
+            }
+        }
+        Ok(args)
+    }
+}
+
+/// Rasterize a string at compile time, and expand to its dimensions and pixels: a tuple of
+/// `(width: u32, height: u32, stride: usize, pixels: &'static [u8])`, where the stride is the number of bytes in each
+/// row of the pixels.
+///
+/// The text is followed by optional arguments:
+/// - `size`: the height of the text in pixels (ex. `size = 24`), which defaults to the size of [`Options`].
+/// - `font`: the path of a font file, relative to the `Cargo.toml` of the crate (ex. `font = "assets/Label.ttf"`),
+///   which defaults to DejaVu Sans ([`EN_FONT`]). The crate is rebuilt when the font changes.
+/// - `format`: the [`PixelFormat`] of the pixels (ex. `format = Mono`), which defaults to `Alpha8`.
+///
+/// Fonts that can't be read, and text that can't be rasterized, are compile errors.
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text_macros::rasterized;
+///
+/// const HELLO: (u32, u32, usize, &[u8]) = rasterized!("Hello", size = 24, format = Mono);
+///
+/// let (width, height, stride, pixels) = HELLO;
+/// assert!(width > 0 && height > 0);
+/// assert_eq!(stride, (width as usize).div_ceil(8));
+/// assert_eq!(pixels.len(), stride * height as usize);
+/// ```
+#[proc_macro]
+pub fn rasterized(input: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(input as Args);
+    expand(args)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// Returns the code of the dimensions and pixels of rasterized text.
+fn expand(args: Args) -> syn::Result<proc_macro2::TokenStream> {
+    let format = match &args.format {
+        Some(format) => format
+            .to_string()
+            .to_lowercase()
+            .parse::<PixelFormat>()
+            .map_err(|e| syn::Error::new(format.span(), e))?,
+        None => PixelFormat::default(),
+    };
+
+    // Fonts are read relative to the crate that calls the macro, and are included so that it is rebuilt when they change
+    let (font, include) = match &args.font {
+        Some(font) => {
+            let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+            let path = PathBuf::from(manifest_dir).join(font.value());
+            let include = path.to_string_lossy().into_owned();
+            (
+                read_font_file(&path).map_err(|e| syn::Error::new(font.span(), e))?,
+                Some(quote! { const _: &[u8] = include_bytes!(#include); }),
+            )
+        }
+        None => (
+            read_font_bytes(EN_FONT).map_err(|e| syn::Error::new(args.text.span(), e))?,
+            None,
+        ),
+    };
+
+    let mut options = Options::default();
+    if let Some(size) = args.size {
+        options.size = size;
+    }
+    let image = rasterize_with_options(args.text.value(), &font, &options)
+        .map_err(|e| syn::Error::new(args.text.span(), e))?;
+
+    let width = Literal::u32_suffixed(image.width());
+    let height = Literal::u32_suffixed(image.height());
+    let stride = Literal::usize_suffixed(format.stride(image.width()));
+    let pixels = format.encode(&image);
+    let len = Literal::usize_unsuffixed(pixels.len());
+    let pixels = pixels.into_iter().map(Literal::u8_unsuffixed);
+    Ok(quote! {
+        {
+            #include
+            const PIXELS: [u8; #len] = [#(#pixels),*];
+            (#width, #height, #stride, &PIXELS as &[u8])
+        }
+    })
+}
@@ -0,0 +1,17 @@
+//! Parse arbitrary strings as colors.
+//!
+//! ```bash
+//! cargo +nightly fuzz run color
+//! ```
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rasterize_text::Color;
+use std::str::FromStr;
+
+fuzz_target!(|color: &str| {
+    if let Ok(parsed) = Color::from_str(color) {
+        // Every color that is parsed can be written and parsed again
+        assert_eq!(Color::from_str(&parsed.to_string()).ok(), Some(parsed));
+    }
+});
@@ -0,0 +1,21 @@
+//! Read arbitrary bytes as a font, and rasterize text with it if it is accepted.
+//!
+//! The fuzzer is most effective when it mutates real fonts, so start the corpus with the vendored fonts:
+//!
+//! ```bash
+//! mkdir -p corpus/font && cp ../../../assets/fonts/*/*.ttf corpus/font
+//! cargo +nightly fuzz run font
+//! ```
+//!
+//! Fonts that rustybuzz 0.18 panics on while shaping (ex. a coverage range that ends before it starts, which
+//! subtracts with overflow) are rejected by `read_font_bytes`, so any panic here is a bug that should be guarded.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rasterize_text::{rasterize, read_font_bytes, Color};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(font) = read_font_bytes(data) {
+        let _ = rasterize(&"Hello, World! ÅΩ 안녕 مرحبا", &font, 20.0, &Color::default());
+    }
+});
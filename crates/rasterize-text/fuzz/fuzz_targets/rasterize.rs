@@ -0,0 +1,27 @@
+//! Rasterize arbitrary UTF-8 text, wrapped and justified.
+//!
+//! ```bash
+//! cargo +nightly fuzz run rasterize
+//! ```
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rasterize_text::{rasterize_with_options, read_font_bytes, Align, Options, EN_FONT, KR_FONT};
+use std::sync::OnceLock;
+
+fn options() -> &'static Options {
+    static OPTIONS: OnceLock<Options> = OnceLock::new();
+    OPTIONS.get_or_init(|| Options {
+        size: 20.0,
+        fallback_fonts: vec![read_font_bytes(KR_FONT).expect("vendored font is valid")],
+        wrap_width: Some(200.0),
+        align: Align::Justify,
+        ..Default::default()
+    })
+}
+
+fuzz_target!(|text: &str| {
+    static FONT: OnceLock<rusttype::Font<'static>> = OnceLock::new();
+    let font = FONT.get_or_init(|| read_font_bytes(EN_FONT).expect("vendored font is valid"));
+    let _ = rasterize_with_options(&text, font, options());
+});
@@ -0,0 +1,177 @@
+//! Interpret the ANSI escape sequences of terminal output as styled spans.
+
+use crate::{Color, Span, Style};
+
+/// Parse text with ANSI escape sequences (ex. the output of `ls --color=always`) into [`Span`]s,
+/// for rasterizing with [`rasterize_spans`](crate::rasterize_spans) or [`rasterize_grid_spans`](crate::rasterize_grid_spans).
+///
+/// The Select Graphic Rendition (SGR) sequences `ESC [ ... m` set the style of the text that follows:
+///
+/// - `0` resets the style, and an empty sequence (`ESC [ m`) does the same.
+/// - `1` is bold, and `22` is normal intensity.
+/// - `4` is underlined, and `24` is not underlined.
+/// - `9` is struck through, and `29` is not struck through.
+/// - `30`–`37` and `90`–`97` set the foreground color from the 16 standard colors, and `39` resets it.
+/// - `40`–`47` and `100`–`107` set the background color from the 16 standard colors, and `49` resets it.
+/// - `38;5;n` and `48;5;n` set the foreground and background color from the 256 color palette,
+///   and `38;2;r;g;b` and `48;2;r;g;b` set them to a 24-bit color.
+///
+/// Other SGR parameters (ex. italic) are ignored. Other escape sequences (ex. moving the cursor, or
+/// setting the window title) are removed from the text. The colors are those of xterm.
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{Color, parse_ansi, rasterize_spans, EN_FONT, read_font_bytes};
+///
+/// let spans = parse_ansi("\x1b[1;34msrc\x1b[0m  Cargo.toml  \x1b[4;31;47merror\x1b[m");
+/// assert_eq!(spans.len(), 3);
+/// assert_eq!(spans[0].text, "src");
+/// assert!(spans[0].style.bold);
+/// assert_eq!(spans[0].style.color, Some(Color { r: 0, g: 0, b: 238, a: 255 }));
+/// assert_eq!(spans[1].text, "  Cargo.toml  ");
+/// assert_eq!(spans[1].style, Default::default());
+/// assert!(spans[2].style.underline);
+/// assert_eq!(spans[2].style.background, Some(Color { r: 229, g: 229, b: 229, a: 255 }));
+///
+/// let font  = read_font_bytes(EN_FONT)?;
+/// let image = rasterize_spans(&spans, &font, &Default::default())?;
+/// image.save("rasterize_ansi.png")?;
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+pub fn parse_ansi(text: &str) -> Vec<Span> {
+    let mut spans: Vec<Span> = Vec::new();
+    let mut style = Style::default();
+    let mut rest = text;
+    while let Some(start) = rest.find('\x1b') {
+        push(&mut spans, &rest[..start], style);
+        let sequence = &rest[start + 1..];
+        rest = match sequence.chars().next() {
+            // Control Sequence Introducer, which ends at a final byte in '@'..='~'
+            Some('[') => {
+                let end = sequence[1..]
+                    .find(|c| ('@'..='~').contains(&c))
+                    .map_or(sequence.len(), |end| end + 2);
+                if sequence[..end].ends_with('m') {
+                    select_graphic_rendition(&mut style, &sequence[1..end - 1]);
+                }
+                &sequence[end..]
+            }
+            // Operating System Command (ex. a title or hyperlink), which ends at a bell or a String Terminator
+            Some(']') => {
+                let bell = sequence.find('\x07').map(|end| end + 1);
+                let terminator = sequence.find("\x1b\\").map(|end| end + 2);
+                let end = match (bell, terminator) {
+                    (Some(bell), Some(terminator)) => bell.min(terminator),
+                    (end, None) | (None, end) => end.unwrap_or(sequence.len()),
+                };
+                &sequence[end..]
+            }
+            // Other escape sequences are a single character
+            Some(c) => &sequence[c.len_utf8()..],
+            None => sequence,
+        };
+    }
+    push(&mut spans, rest, style);
+    spans
+}
+
+/// Add text to the last span if it has the same style, and otherwise start a new span.
+fn push(spans: &mut Vec<Span>, text: &str, style: Style) {
+    if text.is_empty() {
+        return;
+    }
+    match spans.last_mut() {
+        Some(span) if span.style == style => span.text.push_str(text),
+        _ => spans.push(Span {
+            text: text.to_string(),
+            style,
+            ruby: None,
+            image: None,
+        }),
+    }
+}
+
+/// Apply the parameters of an SGR sequence, separated by ';', to a style.
+fn select_graphic_rendition(style: &mut Style, parameters: &str) {
+    // Missing parameters are zero, which resets the style, and invalid parameters are ignored
+    let mut parameters = parameters
+        .split([';', ':'])
+        .map(|parameter| match parameter {
+            "" => 0,
+            _ => parameter.parse::<u16>().unwrap_or(u16::MAX),
+        });
+    while let Some(parameter) = parameters.next() {
+        match parameter {
+            0 => *style = Style::default(),
+            1 => style.bold = true,
+            22 => style.bold = false,
+            4 => style.underline = true,
+            24 => style.underline = false,
+            9 => style.strikethrough = true,
+            29 => style.strikethrough = false,
+            30..=37 => style.color = Some(palette(parameter as u8 - 30)),
+            90..=97 => style.color = Some(palette(parameter as u8 - 90 + 8)),
+            39 => style.color = None,
+            40..=47 => style.background = Some(palette(parameter as u8 - 40)),
+            100..=107 => style.background = Some(palette(parameter as u8 - 100 + 8)),
+            49 => style.background = None,
+            38 => style.color = extended(&mut parameters).or(style.color),
+            48 => style.background = extended(&mut parameters).or(style.background),
+            _ => {}
+        }
+    }
+}
+
+/// Read an extended color, from the 256 color palette (`5;n`) or a 24-bit color (`2;r;g;b`).
+fn extended(parameters: &mut impl Iterator<Item = u16>) -> Option<Color> {
+    let mut component = || u8::try_from(parameters.next()?).ok();
+    match component()? {
+        5 => component().map(palette),
+        2 => {
+            let (r, g, b) = (component()?, component()?, component()?);
+            Some(Color { r, g, b, a: 255 })
+        }
+        _ => None,
+    }
+}
+
+/// Returns a color of the 256 color palette of xterm.
+///
+/// The first 16 colors are the standard and bright colors, followed by a 6×6×6 color cube and 24 shades of grey.
+fn palette(index: u8) -> Color {
+    const STANDARD: [(u8, u8, u8); 16] = [
+        (0, 0, 0),
+        (205, 0, 0),
+        (0, 205, 0),
+        (205, 205, 0),
+        (0, 0, 238),
+        (205, 0, 205),
+        (0, 205, 205),
+        (229, 229, 229),
+        (127, 127, 127),
+        (255, 0, 0),
+        (0, 255, 0),
+        (255, 255, 0),
+        (92, 92, 255),
+        (255, 0, 255),
+        (0, 255, 255),
+        (255, 255, 255),
+    ];
+    let level = |value: u8| match value {
+        0 => 0,
+        _ => 55 + value * 40,
+    };
+    let (r, g, b) = match index {
+        0..=15 => STANDARD[index as usize],
+        16..=231 => {
+            let cube = index - 16;
+            (level(cube / 36), level(cube / 6 % 6), level(cube % 6))
+        }
+        232..=255 => {
+            let grey = 8 + (index - 232) * 10;
+            (grey, grey, grey)
+        }
+    };
+    Color { r, g, b, a: 255 }
+}
@@ -0,0 +1,144 @@
+//! Detect URLs, @mentions, #hashtags and other patterns in text, and style them as spans (ex. for screenshots of social posts).
+
+use crate::{Color, Span, Style};
+use regex::Regex;
+
+/// The color of links, mentions and hashtags in [`AutoStyle::social`].
+const LINK_COLOR: Color = Color {
+    r: 29,
+    g: 155,
+    b: 240,
+    a: 255,
+};
+/// URLs that start with a scheme or `www.`, without the punctuation that ends the sentence around them.
+const URL_PATTERN: &str = r#"\b(?:https?://|www\.)[^\s<>"]*[^\s<>".,;:!?)\]'"]"#;
+/// Mentions that aren't part of a word or an email address (ex. `@rustlang`, but not `user@example.com`).
+const MENTION_PATTERN: &str = r"(?:^|[^\w@])(@\w+)";
+/// Hashtags that aren't part of a word, and aren't only digits (ex. `#rustlang`, but not `#1`).
+const HASHTAG_PATTERN: &str = r"(?:^|[^\w#&])(#\w*[^\W\d]\w*)";
+
+#[derive(Debug, thiserror::Error)]
+pub enum AutoStyleError {
+    #[error("Invalid pattern: {1:?}.")]
+    InvalidPatternError(#[source] regex::Error, String),
+}
+
+/// A regular expression whose matches are styled by [`auto_style`].
+///
+/// If the pattern has a capture group, only the text of the first group is styled, so that the pattern can match the
+/// characters around it (ex. the space before a mention), since regular expressions can't look behind.
+#[derive(Clone, Debug)]
+pub struct AutoStyle {
+    pub pattern: Regex,
+    pub style: Style,
+}
+
+impl AutoStyle {
+    /// Returns a rule that styles the matches of a regular expression.
+    pub fn new(pattern: &str, style: Style) -> Result<Self, AutoStyleError> {
+        let pattern = Regex::new(pattern)
+            .map_err(|e| AutoStyleError::InvalidPatternError(e, pattern.to_string()))?;
+        Ok(AutoStyle { pattern, style })
+    }
+
+    /// Returns a rule that styles URLs (ex. `https://www.rust-lang.org`, `www.rust-lang.org`).
+    pub fn urls(style: Style) -> Self {
+        AutoStyle {
+            pattern: Regex::new(URL_PATTERN).expect("the URL pattern is valid"),
+            style,
+        }
+    }
+
+    /// Returns a rule that styles @mentions (ex. `@rustlang`).
+    pub fn mentions(style: Style) -> Self {
+        AutoStyle {
+            pattern: Regex::new(MENTION_PATTERN).expect("the mention pattern is valid"),
+            style,
+        }
+    }
+
+    /// Returns a rule that styles #hashtags (ex. `#rustlang`).
+    pub fn hashtags(style: Style) -> Self {
+        AutoStyle {
+            pattern: Regex::new(HASHTAG_PATTERN).expect("the hashtag pattern is valid"),
+            style,
+        }
+    }
+
+    /// Returns rules that style URLs, mentions and hashtags like a social network: in blue, with URLs underlined.
+    pub fn social() -> Vec<Self> {
+        let style = Style {
+            color: Some(LINK_COLOR),
+            ..Default::default()
+        };
+        vec![
+            AutoStyle::urls(Style {
+                underline: true,
+                ..style
+            }),
+            AutoStyle::mentions(style),
+            AutoStyle::hashtags(style),
+        ]
+    }
+}
+
+/// Split text into [`Span`]s, where the matches of the rules have the styles of their rules, and the rest of the text
+/// has the default style.
+///
+/// Matches that start first are styled, and where matches of several rules start at the same character, the first
+/// rule is used. Matches that overlap a styled match aren't styled.
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{auto_style, rasterize_spans, AutoStyle, Options, EN_FONT, read_font_bytes};
+///
+/// let text  = "Hello @ferris! Release notes at https://blog.rust-lang.org. #rustlang #1";
+/// let spans = auto_style(text, &AutoStyle::social());
+/// let styled: Vec<&str> = spans.iter().filter(|span| span.style.color.is_some()).map(|span| span.text.as_str()).collect();
+/// assert_eq!(styled, ["@ferris", "https://blog.rust-lang.org", "#rustlang"]);
+/// assert!(spans[3].style.underline);
+///
+/// let font  = read_font_bytes(EN_FONT)?;
+/// let image = rasterize_spans(&spans, &font, &Options { wrap_width: Some(500.0), ..Default::default() })?;
+/// image.save("auto_style.png")?;
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+pub fn auto_style(text: &str, rules: &[AutoStyle]) -> Vec<Span> {
+    let mut spans = Vec::new();
+    let mut start = 0;
+    loop {
+        // The match that starts first, of the text after the last match
+        let found = rules
+            .iter()
+            .filter_map(|rule| {
+                let captures = rule.pattern.captures_at(text, start)?;
+                let found = captures.get(1).or_else(|| captures.get(0))?;
+                Some((found.range(), rule.style))
+            })
+            .filter(|(range, _)| !range.is_empty())
+            .min_by_key(|(range, _)| range.start);
+        let Some((range, style)) = found else {
+            break;
+        };
+        push(&mut spans, &text[start..range.start], Style::default());
+        push(&mut spans, &text[range.clone()], style);
+        start = range.end;
+    }
+    push(&mut spans, &text[start..], Style::default());
+    spans
+}
+
+/// Add text to the last span if it has the same style, and otherwise start a new span.
+fn push(spans: &mut Vec<Span>, text: &str, style: Style) {
+    if text.is_empty() {
+        return;
+    }
+    match spans.last_mut() {
+        Some(span) if span.style == style => span.text.push_str(text),
+        _ => spans.push(Span {
+            style,
+            ..Span::from(text)
+        }),
+    }
+}
@@ -0,0 +1,146 @@
+//! Draw procedural backgrounds behind rasterized text.
+
+use crate::channel::Channel;
+use crate::{Background, Blending, Color};
+use image::{ImageBuffer, Pixel, Rgba};
+use std::ops::Range;
+
+/// Blend a [`Background`] behind the pixels of a rectangle of an [`ImageBuffer`], as its columns and rows,
+/// in the color space of a [`Blending`].
+///
+/// The background is generated from the position of each pixel in the whole image, so a rectangle is drawn the same
+/// as it would be when the background is drawn behind the whole image.
+pub(crate) fn fill<C: Channel>(
+    image_buffer: &mut ImageBuffer<Rgba<C>, Vec<C>>,
+    background: &Background,
+    columns: Range<u32>,
+    rows: Range<u32>,
+    blending: Blending,
+) where
+    Rgba<C>: Pixel<Subpixel = C>,
+{
+    let (width, height) = image_buffer.dimensions();
+    for y in rows {
+        for x in columns.clone() {
+            let color = color_at(background, x, y, width, height);
+            // Pixels are premultiplied (see Channel), so they are blended over the background as is
+            let pixel = image_buffer.get_pixel_mut(x, y);
+            let Rgba([r, g, b, a]) = *pixel;
+            let [red, green, blue, alpha] = C::color(&color, blending);
+            let alpha = alpha / C::MAX;
+            let behind = 1.0 - a.to_f32() / C::MAX;
+            let blend = |channel: C, background: f32| {
+                C::from_f32((channel.to_f32() + background * alpha * behind).round())
+            };
+            *pixel = Rgba([
+                blend(r, red),
+                blend(g, green),
+                blend(b, blue),
+                blend(a, C::MAX),
+            ]);
+        }
+    }
+}
+
+/// Returns the color of a [`Background`] at a pixel of an image that is `width` by `height` pixels.
+fn color_at(background: &Background, x: u32, y: u32, width: u32, height: u32) -> Color {
+    match *background {
+        Background::Solid(color) => color,
+        Background::Noise {
+            color,
+            amount,
+            seed,
+        } => shade(color, amount * noise(seed, x, y)),
+        // Paper is mottled by large blotches, streaked by fibres that run across the page, and grainy up close
+        Background::Paper { color, seed } => {
+            let (x, y) = (x as f32, y as f32);
+            let mottle = value_noise(seed, x / 96.0, y / 96.0) * 0.6
+                + value_noise(seed.wrapping_add(1), x / 24.0, y / 24.0) * 0.4;
+            let fibres = value_noise(seed.wrapping_add(2), x / 48.0, y / 3.0);
+            let grain = noise(seed.wrapping_add(3), x as u32, y as u32);
+            shade(color, 0.03 * mottle + 0.015 * fibres + 0.01 * grain)
+        }
+        Background::Gradient { start, end, angle } => {
+            // Pixels are projected onto the direction of the gradient, which spans the corners of the image
+            let (sin, cos) = sin_cos(angle);
+            let project = |x: f32, y: f32| x * cos + y * sin;
+            let corners = [
+                project(0.0, 0.0),
+                project(width as f32, 0.0),
+                project(0.0, height as f32),
+                project(width as f32, height as f32),
+            ];
+            let min = corners.iter().copied().fold(f32::INFINITY, f32::min);
+            let max = corners.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+            let t = match max > min {
+                true => (project(x as f32 + 0.5, y as f32 + 0.5) - min) / (max - min),
+                false => 0.0,
+            };
+            let mix =
+                |a: u8, b: u8| (f32::from(a) + (f32::from(b) - f32::from(a)) * t).round() as u8;
+            Color {
+                r: mix(start.r, end.r),
+                g: mix(start.g, end.g),
+                b: mix(start.b, end.b),
+                a: mix(start.a, end.a),
+            }
+        }
+    }
+}
+
+/// Returns the sine and cosine of an angle in degrees.
+///
+/// They are exact for multiples of 90 degrees, which are then the same on every platform,
+/// while [`f32::sin_cos`] depends on the math library of the platform.
+fn sin_cos(angle: f32) -> (f32, f32) {
+    let angle = angle.rem_euclid(360.0);
+    if angle == 0.0 {
+        (0.0, 1.0)
+    } else if angle == 90.0 {
+        (1.0, 0.0)
+    } else if angle == 180.0 {
+        (0.0, -1.0)
+    } else if angle == 270.0 {
+        (-1.0, 0.0)
+    } else {
+        angle.to_radians().sin_cos()
+    }
+}
+
+/// Lighten (positive) or darken (negative) a color by a fraction of the full range of each channel.
+fn shade(color: Color, amount: f32) -> Color {
+    let shade = |channel: u8| {
+        (f32::from(channel) + amount * 255.0)
+            .round()
+            .clamp(0.0, 255.0) as u8
+    };
+    Color {
+        r: shade(color.r),
+        g: shade(color.g),
+        b: shade(color.b),
+        a: color.a,
+    }
+}
+
+/// Returns a random number between -1 and 1 for a pixel, which is the same for the same seed and pixel.
+fn noise(seed: u64, x: u32, y: u32) -> f32 {
+    // SplitMix64 of the seed and position
+    let mut z = seed ^ (u64::from(x) << 32 | u64::from(y));
+    z = z.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^= z >> 31;
+    (z >> 40) as f32 / (1u64 << 23) as f32 - 1.0
+}
+
+/// Returns smooth noise between -1 and 1 at a point, interpolated between random values at whole coordinates.
+fn value_noise(seed: u64, x: f32, y: f32) -> f32 {
+    let (x0, y0) = (x.floor(), y.floor());
+    // Smoothstep, so that the noise has no creases at whole coordinates
+    let smooth = |t: f32| t * t * (3.0 - 2.0 * t);
+    let (tx, ty) = (smooth(x - x0), smooth(y - y0));
+    let corner = |dx: f32, dy: f32| noise(seed, (x0 + dx) as u32, (y0 + dy) as u32);
+    let top = corner(0.0, 0.0) + (corner(1.0, 0.0) - corner(0.0, 0.0)) * tx;
+    let bottom = corner(0.0, 1.0) + (corner(1.0, 1.0) - corner(0.0, 1.0)) * tx;
+    top + (bottom - top) * ty
+}
@@ -0,0 +1,394 @@
+//! Frame text in a speech bubble or a nine-patch image, for chat messages and comic balloons.
+
+use crate::card::over;
+use crate::frame::distance;
+use crate::{
+    check_size, rasterize_spans, Border, Color, Options, OptionsError, RasterizeError, Span,
+};
+use image::{imageops, ImageBuffer, Rgba, RgbaImage};
+use rusttype::Font;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// A frame that is sized to the wrapped text around it, rasterized with [`rasterize_bubble`].
+///
+/// The text is inset from the edges of the frame by the padding, and by the insets of a [`BubbleFrame::NinePatch`].
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{Border, Bubble, BubbleFrame, Color, Options, Side, Tail, rasterize_bubble, EN_FONT, read_font_bytes};
+///
+/// let bubble = |tail| Bubble {
+///     frame: BubbleFrame::Speech {
+///         fill: Color { r: 255, g: 255, b: 255, a: 255 },
+///         radius: 24.0,
+///         outline: Some(Border { width: 2.0, color: Color { r: 0, g: 0, b: 0, a: 255 } }),
+///         tail,
+///     },
+///     padding: 16.0,
+/// };
+/// let font    = read_font_bytes(EN_FONT)?;
+/// let options = Options { size: 24.0, wrap_width: Some(300.0), ..Default::default() };
+/// let text    = "Is this the way to the lighthouse?";
+///
+/// let tail  = Tail { side: Side::Bottom, position: 0.2, size: 20.0 };
+/// let image = rasterize_bubble(&text, &font, &bubble(Some(tail)), &options)?;
+/// image.save("rasterize_bubble.png")?;
+///
+/// // The tail hangs below the bubble, and the corners of the bubble are transparent
+/// assert_eq!(image.height(), rasterize_bubble(&text, &font, &bubble(None), &options)?.height() + 20);
+/// assert_eq!(image.get_pixel(0, 0)[3], 0);
+/// assert_eq!(image.get_pixel(image.width() / 2, 0), &image::Rgba([0, 0, 0, 255]));
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+///
+/// A nine-patch image is cut into a 3x3 grid by its insets. Its corners are drawn as is, its edges are stretched along
+/// the edges of the frame, and its center is stretched to fill the rest of the frame.
+///
+/// ```rust
+/// use rasterize_text::{Bubble, BubbleFrame, Options, rasterize_bubble, EN_FONT, read_font_bytes};
+/// use image::{Rgba, RgbaImage};
+///
+/// // A dark frame with a light center, 8 pixels from each edge
+/// let patch = RgbaImage::from_fn(24, 24, |x, y| match (8..16).contains(&x) && (8..16).contains(&y) {
+///     true => Rgba([240, 240, 240, 255]),
+///     false => Rgba([40, 40, 40, 255]),
+/// });
+/// let bubble = Bubble { frame: BubbleFrame::NinePatch { image: patch, insets: [8, 8, 8, 8] }, padding: 4.0 };
+/// let font   = read_font_bytes(EN_FONT)?;
+///
+/// let image = rasterize_bubble(&"Nine patches", &font, &bubble, &Options::default())?;
+/// assert_eq!(image.get_pixel(0, 0), &Rgba([40, 40, 40, 255]));
+/// assert_eq!(image.get_pixel(10, 10), &Rgba([240, 240, 240, 255]));
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bubble {
+    /// The frame that is drawn behind the text.
+    pub frame: BubbleFrame,
+    /// Space between the text and the edges of the frame in pixels.
+    pub padding: f32,
+}
+
+impl Default for Bubble {
+    fn default() -> Self {
+        Bubble {
+            frame: BubbleFrame::Speech {
+                fill: Color {
+                    r: 255,
+                    g: 255,
+                    b: 255,
+                    a: 255,
+                },
+                radius: 16.0,
+                outline: None,
+                tail: None,
+            },
+            padding: 12.0,
+        }
+    }
+}
+
+/// The frame of a [`Bubble`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum BubbleFrame {
+    /// A rectangle with rounded corners of a `radius` in pixels, filled with a color, and optionally outlined
+    /// and with a [`Tail`] that points at the speaker.
+    Speech {
+        fill: Color,
+        radius: f32,
+        outline: Option<Border>,
+        tail: Option<Tail>,
+    },
+    /// An image that is stretched to the size of the text, where `insets` are the widths of its
+    /// left, top, right and bottom edges in pixels, which are not stretched across.
+    NinePatch { image: RgbaImage, insets: [u32; 4] },
+}
+
+/// The tail of a [`BubbleFrame::Speech`], a triangle that points away from a side of the bubble.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Tail {
+    /// The side of the bubble that the tail points away from.
+    pub side: Side,
+    /// Position of the tail along the straight part of its side, from the start (0) to the end (1),
+    /// where the sides start at the top and left of the bubble.
+    pub position: f32,
+    /// Width and length of the tail in pixels.
+    pub size: f32,
+}
+
+/// A side of a rectangle, such as the side of a [`Bubble`] that a [`Tail`] points away from.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Side {
+    Top,
+    Right,
+    #[default]
+    Bottom,
+    Left,
+}
+
+impl Display for Side {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        let lowercase = format!("{:?}", self).to_lowercase();
+        write!(f, "{lowercase}")
+    }
+}
+
+impl FromStr for Side {
+    type Err = OptionsError;
+
+    /// Returns a [`Side`] converted from a [`str`].
+    fn from_str(side: &str) -> Result<Self, Self::Err> {
+        let side = match side {
+            "top" => Side::Top,
+            "right" => Side::Right,
+            "bottom" => Side::Bottom,
+            "left" => Side::Left,
+            _ => Err(OptionsError::UnknownSideError(side.to_string()))?,
+        };
+
+        Ok(side)
+    }
+}
+
+/// Rasterize a string of text in a [`Bubble`], as configured by [`Options`].
+///
+/// The text is wrapped and laid out as with [`rasterize_with_options`](crate::rasterize_with_options), and the bubble is sized to fit it.
+/// The [`Options::corner_radius`] and [`Options::border`] are not drawn, since the bubble frames the text instead.
+pub fn rasterize_bubble(
+    text: impl AsRef<str>,
+    font: &Font,
+    bubble: &Bubble,
+    options: &Options,
+) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>, RasterizeError> {
+    rasterize_bubble_spans(&[Span::from(text.as_ref())], font, bubble, options)
+}
+
+/// Rasterize spans of styled text in a [`Bubble`], as configured by [`Options`].
+///
+/// This is the same as [`rasterize_bubble`], but the [`Style`](crate::Style) of each [`Span`] overrides the [`Options`]
+/// for its text, as with [`rasterize_spans`].
+pub fn rasterize_bubble_spans(
+    spans: &[Span],
+    font: &Font,
+    bubble: &Bubble,
+    options: &Options,
+) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>, RasterizeError> {
+    let text_options = Options {
+        corner_radius: 0.0,
+        border: None,
+        ..options.clone()
+    };
+    let text = rasterize_spans(spans, font, &text_options)?;
+
+    let padding = bubble.padding.max(0.0).ceil() as u64;
+    let [left, top, right, bottom] = match &bubble.frame {
+        BubbleFrame::Speech { .. } => [0; 4],
+        BubbleFrame::NinePatch { insets, .. } => insets.map(u64::from),
+    };
+    let body_width = u64::from(text.width()) + left + right + 2 * padding;
+    let body_height = u64::from(text.height()) + top + bottom + 2 * padding;
+    let (body_width, body_height) = check_size(body_width, body_height, options)?;
+
+    let (mut canvas, x, y) = match &bubble.frame {
+        BubbleFrame::Speech {
+            fill,
+            radius,
+            outline,
+            tail,
+        } => speech(
+            body_width,
+            body_height,
+            *fill,
+            *radius,
+            *outline,
+            *tail,
+            options,
+        )?,
+        BubbleFrame::NinePatch { image, insets } => {
+            (nine_patch(image, *insets, body_width, body_height), 0, 0)
+        }
+    };
+    over(
+        &mut canvas,
+        &text,
+        (x + left + padding) as i64,
+        (y + top + padding) as i64,
+    );
+
+    Ok(canvas)
+}
+
+/// Draw a speech bubble with a body of `width` by `height` pixels.
+///
+/// Returns the image, and the position of the top left corner of the body, which is offset by a tail on the top or left.
+fn speech(
+    width: u32,
+    height: u32,
+    fill: Color,
+    radius: f32,
+    outline: Option<Border>,
+    tail: Option<Tail>,
+    options: &Options,
+) -> Result<(RgbaImage, u64, u64), RasterizeError> {
+    let length = tail.map_or(0, |tail| tail.size.max(0.0).ceil() as u64);
+    let (x, y) = match tail.map(|tail| tail.side) {
+        Some(Side::Left) => (length, 0),
+        Some(Side::Top) => (0, length),
+        _ => (0, 0),
+    };
+    let (canvas_width, canvas_height) = match tail.map(|tail| tail.side) {
+        Some(Side::Left | Side::Right) => (u64::from(width) + length, u64::from(height)),
+        Some(Side::Top | Side::Bottom) => (u64::from(width), u64::from(height) + length),
+        None => (u64::from(width), u64::from(height)),
+    };
+    let (canvas_width, canvas_height) = check_size(canvas_width, canvas_height, options)?;
+
+    let (left, top) = (x as f32, y as f32);
+    let (right, bottom) = (left + width as f32, top + height as f32);
+    let radius = radius.max(0.0).min(width.min(height) as f32 / 2.0);
+    let outline_width = outline.map_or(0.0, |outline| outline.width.max(0.0));
+    // The base of the tail reaches into the body past the outline, so that the outline isn't drawn across it
+    let triangle = tail.map(|tail| {
+        let size = tail.size.max(0.0);
+        let depth = outline_width + 1.0;
+        let half = size / 2.0 * (size + depth) / size.max(f32::EPSILON);
+        let along = |start: f32, end: f32| {
+            let start = start + radius + size / 2.0;
+            let end = (end - radius - size / 2.0).max(start);
+            start + (end - start) * tail.position.clamp(0.0, 1.0)
+        };
+        // The center of the base of the tail on its side, and the direction that the tail points in
+        let ((x, y), (dx, dy)) = match tail.side {
+            Side::Top => ((along(left, right), top), (0.0, -1.0)),
+            Side::Bottom => ((along(left, right), bottom), (0.0, 1.0)),
+            Side::Left => ((left, along(top, bottom)), (-1.0, 0.0)),
+            Side::Right => ((right, along(top, bottom)), (1.0, 0.0)),
+        };
+        let (base_x, base_y) = (x - dx * depth, y - dy * depth);
+        [
+            (base_x - dy * half, base_y + dx * half),
+            (base_x + dy * half, base_y - dx * half),
+            (x + dx * size, y + dy * size),
+        ]
+    });
+
+    // Colors are premultiplied by their alpha, like the pixels of glyphs
+    let premultiply = |color: Color| {
+        let alpha = f32::from(color.a) / 255.0;
+        [
+            f32::from(color.r) * alpha,
+            f32::from(color.g) * alpha,
+            f32::from(color.b) * alpha,
+            f32::from(color.a),
+        ]
+    };
+    let fill = premultiply(fill);
+    let outline_color = outline.map_or([0.0; 4], |outline| premultiply(outline.color));
+    let mut canvas = RgbaImage::new(canvas_width, canvas_height);
+    for (px, py, pixel) in canvas.enumerate_pixels_mut() {
+        let point = (px as f32 + 0.5, py as f32 + 0.5);
+        // The outer edge of the bubble is the nearest edge of the body or tail
+        let body = distance(point.0, point.1, (left, top, right, bottom), radius);
+        let distance = match triangle {
+            Some([a, b, c]) => body.min(triangle_distance(point, a, b, c)),
+            None => body,
+        };
+        let outer = (0.5 - distance).clamp(0.0, 1.0);
+        let inner = (0.5 - distance - outline_width).clamp(0.0, 1.0);
+        for channel in 0..4 {
+            pixel[channel] = (fill[channel] * inner + outline_color[channel] * (outer - inner))
+                .round()
+                .clamp(0.0, 255.0) as u8;
+        }
+    }
+
+    Ok((canvas, x, y))
+}
+
+/// Returns the signed distance from a point to the edge of a triangle, which is negative inside of it.
+fn triangle_distance(p: (f32, f32), a: (f32, f32), b: (f32, f32), c: (f32, f32)) -> f32 {
+    let sub = |u: (f32, f32), v: (f32, f32)| (u.0 - v.0, u.1 - v.1);
+    let dot = |u: (f32, f32), v: (f32, f32)| u.0 * v.0 + u.1 * v.1;
+    let cross = |u: (f32, f32), v: (f32, f32)| u.0 * v.1 - u.1 * v.0;
+    // The winding of the triangle, so that the sign of each cross product is positive inside of it
+    let winding = cross(sub(b, a), sub(c, a)).signum();
+    let mut squared = f32::INFINITY;
+    let mut inside = true;
+    for (start, end) in [(a, b), (b, c), (c, a)] {
+        let edge = sub(end, start);
+        let to_point = sub(p, start);
+        let t = (dot(to_point, edge) / dot(edge, edge).max(f32::EPSILON)).clamp(0.0, 1.0);
+        let nearest = sub(to_point, (edge.0 * t, edge.1 * t));
+        squared = squared.min(dot(nearest, nearest));
+        inside &= winding * cross(edge, to_point) >= 0.0;
+    }
+    match inside {
+        true => -squared.sqrt(),
+        false => squared.sqrt(),
+    }
+}
+
+/// Stretch a nine-patch image to `width` by `height` pixels, where `insets` are the widths of its
+/// left, top, right and bottom edges.
+///
+/// Insets that are larger than the image are reduced, so that the center of the image is never less than a pixel.
+fn nine_patch(image: &RgbaImage, insets: [u32; 4], width: u32, height: u32) -> RgbaImage {
+    let mut canvas = RgbaImage::new(width, height);
+    if image.width() == 0 || image.height() == 0 {
+        return canvas;
+    }
+    let [left, top, right, bottom] = insets;
+    let left = left.min(image.width() - 1);
+    let right = right.min(image.width() - 1 - left);
+    let top = top.min(image.height() - 1);
+    let bottom = bottom.min(image.height() - 1 - top);
+    // The start and length of each column and row, in the image and on the canvas
+    let columns = [
+        (0, left, 0, left),
+        (
+            left,
+            image.width() - left - right,
+            left,
+            width.saturating_sub(left + right),
+        ),
+        (
+            image.width() - right,
+            right,
+            width.saturating_sub(right),
+            right,
+        ),
+    ];
+    let rows = [
+        (0, top, 0, top),
+        (
+            top,
+            image.height() - top - bottom,
+            top,
+            height.saturating_sub(top + bottom),
+        ),
+        (
+            image.height() - bottom,
+            bottom,
+            height.saturating_sub(bottom),
+            bottom,
+        ),
+    ];
+    for (source_y, source_height, y, target_height) in rows {
+        for (source_x, source_width, x, target_width) in columns {
+            if source_width == 0 || source_height == 0 || target_width == 0 || target_height == 0 {
+                continue;
+            }
+            let patch = imageops::crop_imm(image, source_x, source_y, source_width, source_height);
+            let patch = imageops::resize(
+                &patch.to_image(),
+                target_width,
+                target_height,
+                imageops::FilterType::Triangle,
+            );
+            imageops::replace(&mut canvas, &patch, x.into(), y.into());
+        }
+    }
+    canvas
+}
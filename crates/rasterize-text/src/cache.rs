@@ -0,0 +1,112 @@
+//! Reuse the pixels of glyphs that are drawn more than once, at quarter pixel offsets.
+
+use crate::channel::Channel;
+use crate::{draw_glyph, pixel_writer, stroke, Color, Ink, Options};
+use image::{ImageBuffer, Pixel, Rgba};
+use rusttype::{point, PositionedGlyph, Rect};
+use std::collections::HashMap;
+
+/// Number of horizontal positions within a pixel that glyphs are drawn at, with [`PixelSnap::Quarters`](crate::PixelSnap::Quarters).
+pub(crate) const SUBPIXEL_VARIANTS: f32 = 4.0;
+
+/// The coverage of the pixels of glyphs, for each glyph and horizontal offset within a pixel.
+///
+/// Glyphs must be positioned at a multiple of 1/[`SUBPIXEL_VARIANTS`] pixels horizontally, and at a whole pixel vertically.
+/// Each glyph is then rasterized at most once for each of the offsets, and copied wherever else it is drawn.
+#[derive(Default)]
+pub(crate) struct GlyphCache {
+    glyphs: HashMap<GlyphKey, Option<Coverage>>,
+}
+
+/// A glyph at an offset within a pixel.
+///
+/// Glyphs of different fonts are told apart by their bounding box and advance, since [`rusttype::Font`] has no identity to compare.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+struct GlyphKey {
+    id: u16,
+    /// Bits of the horizontal and vertical scale.
+    scale: [u32; 2],
+    /// Bits of the exact bounding box and advance.
+    metrics: [u32; 5],
+    /// Horizontal offset within a pixel, in 1/[`SUBPIXEL_VARIANTS`] pixels.
+    variant: u8,
+}
+
+/// The coverage of the pixels of a glyph, drawn at an offset within the pixel at the origin.
+struct Coverage {
+    bounding_box: Rect<i32>,
+    values: Vec<f32>,
+}
+
+impl GlyphCache {
+    /// Draw a positioned glyph in a color to an [`ImageBuffer`], in the same way as [`draw_glyph`].
+    pub fn draw<C: Channel>(
+        &mut self,
+        image_buffer: &mut ImageBuffer<Rgba<C>, Vec<C>>,
+        glyph: &PositionedGlyph,
+        color: &Color,
+        ink: Ink,
+        options: &Options,
+    ) where
+        Rgba<C>: Pixel<Subpixel = C>,
+    {
+        let Some(bounding_box) = glyph.pixel_bounding_box() else {
+            return;
+        };
+        let position = glyph.position();
+        let pixel = point(position.x.floor(), position.y);
+        let offset = position.x - pixel.x;
+        let variant = (offset * SUBPIXEL_VARIANTS).round();
+        // Glyphs that aren't snapped to a variant are drawn directly
+        if variant != offset * SUBPIXEL_VARIANTS || pixel.y.fract() != 0.0 {
+            return draw_glyph(image_buffer, glyph, color, ink, options);
+        }
+
+        let scaled = glyph.unpositioned();
+        let scale = scaled.scale();
+        let exact = scaled.exact_bounding_box().unwrap_or(Rect {
+            min: point(0.0, 0.0),
+            max: point(0.0, 0.0),
+        });
+        let key = GlyphKey {
+            id: glyph.id().0,
+            scale: [scale.x.to_bits(), scale.y.to_bits()],
+            metrics: [
+                exact.min.x.to_bits(),
+                exact.min.y.to_bits(),
+                exact.max.x.to_bits(),
+                exact.max.y.to_bits(),
+                scaled.h_metrics().advance_width.to_bits(),
+            ],
+            variant: variant as u8,
+        };
+        let coverage = self.glyphs.entry(key).or_insert_with(|| {
+            let glyph = scaled.clone().positioned(point(offset, 0.0));
+            let bounding_box = glyph.pixel_bounding_box()?;
+            let width = bounding_box.width() as usize;
+            let mut values = vec![0.0; width * bounding_box.height() as usize];
+            stroke::draw(&glyph, options, |x, y, v| {
+                values[y as usize * width + x as usize] = v
+            });
+            Some(Coverage {
+                bounding_box,
+                values,
+            })
+        });
+
+        // The cached glyph is moved by whole pixels, so it covers the same pixels as the glyph itself
+        match coverage {
+            Some(coverage)
+                if coverage.bounding_box.width() == bounding_box.width()
+                    && coverage.bounding_box.height() == bounding_box.height() =>
+            {
+                let width = bounding_box.width() as u32;
+                let mut draw = pixel_writer(image_buffer, bounding_box, color, ink, options);
+                for (i, v) in coverage.values.iter().enumerate() {
+                    draw(i as u32 % width, i as u32 / width, *v);
+                }
+            }
+            _ => draw_glyph(image_buffer, glyph, color, ink, options),
+        }
+    }
+}
@@ -0,0 +1,316 @@
+//! Draw a month calendar on a grid of cells, for dashboards (ex. e-ink displays).
+
+use crate::background::fill;
+use crate::card::over;
+use crate::effect::apply_to_image;
+use crate::frame;
+use crate::mask::whole_text;
+use crate::{
+    check_size, rasterize_grid_spans, rasterize_spans, Blending, Color, GridLayout, Options,
+    OptionsError, RasterizeError, Span, Style,
+};
+use image::{ImageBuffer, Rgba, RgbaImage};
+use rusttype::{Font, Scale};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// English names of the months, from January.
+const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+/// English abbreviations of the days of the week, from Sunday.
+const WEEKDAYS: [&str; 7] = ["Su", "Mo", "Tu", "We", "Th", "Fr", "Sa"];
+/// Number of cells in each row of the calendar, which is seven days of two cells with a cell between them.
+const COLS: usize = 7 * 3 - 1;
+
+/// The day that weeks start on, in the columns of a [`Calendar`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum WeekStart {
+    /// Weeks start on Monday, as in ISO 8601.
+    #[default]
+    Monday,
+    /// Weeks start on Sunday.
+    Sunday,
+}
+
+impl Display for WeekStart {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        let lowercase = format!("{:?}", self).to_lowercase();
+        write!(f, "{lowercase}")
+    }
+}
+
+impl FromStr for WeekStart {
+    type Err = OptionsError;
+
+    /// Returns a [`WeekStart`] converted from a [`str`].
+    fn from_str(week_start: &str) -> Result<Self, Self::Err> {
+        let week_start = match week_start {
+            "monday" => WeekStart::Monday,
+            "sunday" => WeekStart::Sunday,
+            _ => Err(OptionsError::UnknownWeekStartError(week_start.to_string()))?,
+        };
+
+        Ok(week_start)
+    }
+}
+
+/// A month of a calendar, drawn by [`rasterize_calendar`].
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{rasterize_calendar, Calendar, Options, WeekStart, EN_FONT, read_font_bytes};
+/// use image::RgbaImage;
+/// use std::ops::Range;
+///
+/// // The runs of consecutive numbers that are drawn, which are separated by numbers that aren't
+/// fn runs(end: u32, drawn: impl Fn(u32) -> bool) -> Vec<Range<u32>> {
+///     let mut runs: Vec<Range<u32>> = Vec::new();
+///     for i in (0..end).filter(|i| drawn(*i)) {
+///         match runs.last_mut() {
+///             Some(run) if run.end == i => run.end += 1,
+///             _ => runs.push(i..i + 1),
+///         }
+///     }
+///     runs
+/// }
+/// // The pixel rows of the lines of an image, and the pixel columns of the words of a line
+/// let lines   = |image: &RgbaImage| runs(image.height(), |y| (0..image.width()).any(|x| image.get_pixel(x, y)[3] > 0));
+/// let words   = |image: &RgbaImage, line: &Range<u32>| {
+///     runs(image.width(), |x| line.clone().any(|y| image.get_pixel(x, y)[3] > 0))
+/// };
+///
+/// let font    = read_font_bytes(EN_FONT)?;
+/// let options = Options::default();
+///
+/// // September 2024 starts on a Sunday, so its first day is in the first column of weeks that start on Sunday, and
+/// // in the last column of weeks that start on Monday. The lines are the title, the days of the week, and the weeks.
+/// let september = Calendar { year: 2024, month: 9, ..Default::default() };
+/// let sunday    = rasterize_calendar(&Calendar { week_start: WeekStart::Sunday, ..september }, &font, &options)?;
+/// let weeks     = lines(&sunday);
+/// assert_eq!(weeks.len(), 2 + 5);
+/// assert_eq!(words(&sunday, &weeks[2]).len(), 7);
+///
+/// let monday    = rasterize_calendar(&september, &font, &options)?;
+/// let weeks     = lines(&monday);
+/// let first     = words(&monday, &weeks[2]);
+/// let second    = words(&monday, &weeks[3]);
+/// assert_eq!((first.len(), second.len()), (1, 7));
+/// // The 1st is in the same column as the 8th, which is the last day of the next week
+/// assert!(first[0].start < second[6].end && second[6].start < first[0].end);
+///
+/// // Today is drawn in white on black, in its column
+/// let today   = Calendar { today: Some(7), ..Calendar { week_start: WeekStart::Sunday, ..september } };
+/// let image   = rasterize_calendar(&today, &font, &options)?;
+/// image.save("rasterize_calendar.png")?;
+/// let line    = &lines(&sunday)[2];
+/// let days    = words(&image, line);
+/// let white: Vec<_> = image.enumerate_pixels().filter(|(_, _, pixel)| pixel.0 == [255; 4]).collect();
+/// assert!(!white.is_empty());
+/// assert!(white.iter().all(|(x, y, _)| line.contains(y) && days[6].contains(x)));
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Calendar {
+    pub year: i32,
+    /// The month, from 1 (January) to 12 (December). Other months are clamped to that range.
+    pub month: u8,
+    /// The day that weeks start on, which is the first column.
+    pub week_start: WeekStart,
+    /// The day of the month that is highlighted with the [`Calendar::today_style`], if any.
+    pub today: Option<u8>,
+    /// The style of today, which is white on a black background by default, like a cell of an e-ink display.
+    pub today_style: Style,
+    /// The style of the month and year above the days.
+    pub title_style: Style,
+}
+
+impl Default for Calendar {
+    fn default() -> Self {
+        Calendar {
+            year: 1970,
+            month: 1,
+            week_start: WeekStart::default(),
+            today: None,
+            today_style: Style {
+                color: Some(Color {
+                    r: 255,
+                    g: 255,
+                    b: 255,
+                    a: 255,
+                }),
+                background: Some(Color {
+                    r: 0,
+                    g: 0,
+                    b: 0,
+                    a: 255,
+                }),
+                bold: true,
+                ..Default::default()
+            },
+            title_style: Style {
+                bold: true,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl Calendar {
+    /// Returns the number of days in the month.
+    pub fn days(&self) -> u8 {
+        let leap = self.year % 4 == 0 && (self.year % 100 != 0 || self.year % 400 == 0);
+        match self.month.clamp(1, 12) {
+            2 if leap => 29,
+            2 => 28,
+            4 | 6 | 9 | 11 => 30,
+            _ => 31,
+        }
+    }
+
+    /// Returns the column of the first day of the month, counted from the [`Calendar::week_start`].
+    fn first_column(&self) -> usize {
+        // The day of the week of the first of the month, from Sunday (Sakamoto's method)
+        const OFFSETS: [i32; 12] = [0, 3, 2, 5, 0, 3, 5, 1, 4, 6, 2, 4];
+        let month = usize::from(self.month.clamp(1, 12));
+        let year = i64::from(self.year) - i64::from(month < 3);
+        let weekday = (year + year.div_euclid(4) - year.div_euclid(100)
+            + year.div_euclid(400)
+            + i64::from(OFFSETS[month - 1])
+            + 1)
+        .rem_euclid(7) as usize;
+        match self.week_start {
+            WeekStart::Sunday => weekday,
+            WeekStart::Monday => (weekday + 6) % 7,
+        }
+    }
+}
+
+/// Rasterize a month of a calendar to an [`ImageBuffer`], as configured by [`Options`].
+///
+/// The month and year are centered above the abbreviated days of the week, and the days of the month are right
+/// aligned in the columns of their day of the week, like the output of `cal`. The names are in English.
+///
+/// The days are laid out on a grid (see [`rasterize_grid_spans`]) whose cells are as wide as the widest digit,
+/// so that the columns line up with any font, and the days of the week are centered over their columns.
+/// The grid always has 6 rows, so that months with fewer weeks don't change the size of the image. The background,
+/// effects without a byte range, corners and border of the [`Options`] are drawn around the whole calendar.
+pub fn rasterize_calendar(
+    calendar: &Calendar,
+    font: &Font,
+    options: &Options,
+) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>, RasterizeError> {
+    // The background, whole image effects and border are drawn once, around the whole calendar
+    let parts = Options {
+        wrap_width: None,
+        line_numbers: None,
+        mask: whole_text(options.mask.as_ref()),
+        effects: Vec::new(),
+        selection: None,
+        background: None,
+        corner_radius: 0.0,
+        border: None,
+        ..options.clone()
+    };
+
+    let title = Span {
+        text: format!(
+            "{} {}",
+            MONTHS[usize::from(calendar.month.clamp(1, 12)) - 1],
+            calendar.year
+        ),
+        style: calendar.title_style,
+        ruby: None,
+        image: None,
+    };
+    let title = rasterize_spans(&[title], font, &parts)?;
+
+    // Days are right aligned in two cells, with a cell between them
+    let column = calendar.first_column();
+    let mut spans = Vec::new();
+    let mut row = "   ".repeat(column);
+    for day in 1..=calendar.days() {
+        let column = (column + usize::from(day) - 1) % 7;
+        if column == 0 && day > 1 {
+            row.push('\n');
+        } else if column > 0 && day > 1 {
+            row.push(' ');
+        }
+        if calendar.today == Some(day) {
+            spans.push(Span::from(row.as_str()));
+            spans.push(Span {
+                text: format!("{day:>2}"),
+                style: calendar.today_style,
+                ruby: None,
+                image: None,
+            });
+            row.clear();
+        } else {
+            row.push_str(&format!("{day:>2}"));
+        }
+    }
+    spans.push(Span::from(row.as_str()));
+    let scale = Scale::uniform(parts.size);
+    let mut grid = GridLayout::new(COLS, 6, font, parts.size);
+    grid.cell_w = ('0'..='9')
+        .map(|digit| font.glyph(digit).scaled(scale).h_metrics().advance_width)
+        .fold(0.0, f32::max);
+    let days = rasterize_grid_spans(&spans, font, &grid, &parts)?;
+
+    let first = match calendar.week_start {
+        WeekStart::Sunday => 0,
+        WeekStart::Monday => 1,
+    };
+    let weekdays = (0..7)
+        .map(|i| rasterize_spans(&[Span::from(WEEKDAYS[(first + i) % 7])], font, &parts))
+        .collect::<Result<Vec<_>, _>>()?;
+    // Days of the week that are wider than their columns overhang the days, within a margin on each side
+    let column_width = 2.0 * grid.cell_w;
+    let margin = weekdays
+        .iter()
+        .map(|weekday| {
+            ((weekday.width() as f32 - column_width) / 2.0)
+                .ceil()
+                .max(0.0) as u64
+        })
+        .max()
+        .unwrap_or_default();
+
+    // The title and the days of the week are each on a line above the days
+    let line_height = grid.cell_h.ceil() as u64;
+    let width = (u64::from(days.width()) + 2 * margin).max(u64::from(title.width()));
+    let height = 2 * line_height + u64::from(days.height());
+    let (width, height) = check_size(width, height, &parts)?;
+    let mut canvas = RgbaImage::new(width, height);
+    over(
+        &mut canvas,
+        &title,
+        i64::from((width - title.width()) / 2),
+        0,
+    );
+    let left = i64::from(width - days.width()) / 2;
+    for (i, weekday) in weekdays.iter().enumerate() {
+        let center = (i * 3 + 1) as f32 * grid.cell_w;
+        let x = left + (center - weekday.width() as f32 / 2.0).round() as i64;
+        over(&mut canvas, weekday, x, line_height as i64);
+    }
+    over(&mut canvas, &days, left, 2 * line_height as i64);
+    if let Some(background) = &options.background {
+        fill(&mut canvas, background, 0..width, 0..height, Blending::Srgb);
+    }
+    apply_to_image(&mut canvas, options);
+
+    frame::apply(canvas, options)
+}
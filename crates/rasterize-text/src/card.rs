@@ -0,0 +1,272 @@
+//! Composite text onto a background image, like a quote card.
+
+use crate::frame;
+use crate::{check_size, rasterize_spans, strict, Options, OptionsError, RasterizeError, Span};
+use image::{imageops, DynamicImage, ImageBuffer, Rgba, RgbaImage};
+use rusttype::Font;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// A background image that text is composited onto, with [`rasterize_card`].
+///
+/// The image is fitted to the size of the card with a [`Fit`], and the text is placed at an [`Anchor`] of the card,
+/// inset from its edges by the margin.
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{Anchor, Card, Fit, Options, rasterize_card, EN_FONT, read_font_bytes};
+/// use image::{DynamicImage, RgbaImage, Rgba};
+///
+/// // A small checkerboard, tiled across a card
+/// let tile = RgbaImage::from_fn(40, 40, |x, y| match (x < 20) == (y < 20) {
+///     true => Rgba([230, 230, 230, 255]),
+///     false => Rgba([250, 250, 250, 255]),
+/// });
+/// let card = Card {
+///     size: Some((600, 300)),
+///     fit: Fit::Tile,
+///     anchor: Anchor::BottomRight,
+///     margin: 20.0,
+///     ..Card::new(DynamicImage::ImageRgba8(tile))
+/// };
+/// let font    = read_font_bytes(EN_FONT)?;
+/// let options = Options { size: 32.0, ..Default::default() };
+///
+/// let image = rasterize_card(&"Simplicity is the ultimate sophistication.", &font, &card, &options)?;
+/// image.save("rasterize_card.png")?;
+/// assert_eq!(image.dimensions(), (600, 300));
+/// // The text wraps within the margins, and leaves the top left corner of the card as is
+/// assert_eq!(image.get_pixel(5, 5), &Rgba([230, 230, 230, 255]));
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Card {
+    /// The background image.
+    pub background: DynamicImage,
+    /// How the background image is fitted to the size of the card.
+    pub fit: Fit,
+    /// The width and height of the card in pixels. If no size is provided, the card is the size of the background image.
+    pub size: Option<(u32, u32)>,
+    /// Where the text is placed on the card.
+    pub anchor: Anchor,
+    /// Space between the text and the edges of the card in pixels.
+    pub margin: f32,
+}
+
+impl Card {
+    /// Returns a card the size of a background image, with the text centered on it.
+    pub fn new(background: DynamicImage) -> Self {
+        Card {
+            background,
+            fit: Fit::default(),
+            size: None,
+            anchor: Anchor::default(),
+            margin: 0.0,
+        }
+    }
+}
+
+/// How a background image is fitted to the size of a [`Card`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Fit {
+    /// Scale the image to cover the card, and crop the edges that overflow it, keeping the image centered.
+    #[default]
+    Cover,
+    /// Scale the image to fit within the card, centered, and leave the rest of the card transparent.
+    Contain,
+    /// Repeat the image at its own size across the card, from the top left corner.
+    Tile,
+}
+
+impl Display for Fit {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        let lowercase = format!("{:?}", self).to_lowercase();
+        write!(f, "{lowercase}")
+    }
+}
+
+impl FromStr for Fit {
+    type Err = OptionsError;
+
+    /// Returns a [`Fit`] converted from a [`str`].
+    fn from_str(fit: &str) -> Result<Self, Self::Err> {
+        let fit = match fit {
+            "cover" => Fit::Cover,
+            "contain" => Fit::Contain,
+            "tile" => Fit::Tile,
+            _ => Err(OptionsError::UnknownFitError(fit.to_string()))?,
+        };
+
+        Ok(fit)
+    }
+}
+
+/// A point of a rectangle that something is placed at, such as text on a [`Card`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Anchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    #[default]
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    /// Returns the position of the anchor within a rectangle, as fractions of its width and height from the top left corner.
+    pub fn fractions(self) -> (f32, f32) {
+        let x = match self {
+            Anchor::TopLeft | Anchor::Left | Anchor::BottomLeft => 0.0,
+            Anchor::Top | Anchor::Center | Anchor::Bottom => 0.5,
+            Anchor::TopRight | Anchor::Right | Anchor::BottomRight => 1.0,
+        };
+        let y = match self {
+            Anchor::TopLeft | Anchor::Top | Anchor::TopRight => 0.0,
+            Anchor::Left | Anchor::Center | Anchor::Right => 0.5,
+            Anchor::BottomLeft | Anchor::Bottom | Anchor::BottomRight => 1.0,
+        };
+        (x, y)
+    }
+}
+
+impl Display for Anchor {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        let lowercase = format!("{:?}", self).to_lowercase();
+        write!(f, "{lowercase}")
+    }
+}
+
+impl FromStr for Anchor {
+    type Err = OptionsError;
+
+    /// Returns an [`Anchor`] converted from a [`str`].
+    fn from_str(anchor: &str) -> Result<Self, Self::Err> {
+        let anchor = match anchor {
+            "topleft" => Anchor::TopLeft,
+            "top" => Anchor::Top,
+            "topright" => Anchor::TopRight,
+            "left" => Anchor::Left,
+            "center" => Anchor::Center,
+            "right" => Anchor::Right,
+            "bottomleft" => Anchor::BottomLeft,
+            "bottom" => Anchor::Bottom,
+            "bottomright" => Anchor::BottomRight,
+            _ => Err(OptionsError::UnknownAnchorError(anchor.to_string()))?,
+        };
+
+        Ok(anchor)
+    }
+}
+
+/// Rasterize a string of text onto a [`Card`], as configured by [`Options`].
+///
+/// If no [`Options::wrap_width`] is provided, the text wraps at the width of the card within its margins.
+/// The [`Options::corner_radius`] and [`Options::border`] apply to the card, rather than the text.
+/// Text that is larger than the card is cut off at its edges.
+pub fn rasterize_card(
+    text: impl AsRef<str>,
+    font: &Font,
+    card: &Card,
+    options: &Options,
+) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>, RasterizeError> {
+    rasterize_card_spans(&[Span::from(text.as_ref())], font, card, options)
+}
+
+/// Rasterize spans of styled text onto a [`Card`], as configured by [`Options`].
+///
+/// This is the same as [`rasterize_card`], but the [`Style`](crate::Style) of each [`Span`] overrides the [`Options`]
+/// for its text, as with [`rasterize_spans`].
+pub fn rasterize_card_spans(
+    spans: &[Span],
+    font: &Font,
+    card: &Card,
+    options: &Options,
+) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>, RasterizeError> {
+    let (width, height) = card
+        .size
+        .unwrap_or((card.background.width(), card.background.height()));
+    let (width, height) = check_size(width.into(), height.into(), options)?;
+    // The corners and border frame the card, rather than the text
+    let text_options = Options {
+        wrap_width: options
+            .wrap_width
+            .or(Some(width as f32 - 2.0 * card.margin)),
+        corner_radius: 0.0,
+        border: None,
+        ..options.clone()
+    };
+    let text = rasterize_spans(spans, font, &text_options)?;
+
+    let mut canvas = fit(&card.background, card.fit, width, height);
+    let (fx, fy) = card.anchor.fractions();
+    let x = card.margin + (width as f32 - 2.0 * card.margin - text.width() as f32) * fx;
+    let y = card.margin + (height as f32 - 2.0 * card.margin - text.height() as f32) * fy;
+    let (x, y) = (x.round() as i64, y.round() as i64);
+    strict::check_placed(&text, (x, y), (width, height), options)?;
+    over(&mut canvas, &text, x, y);
+
+    frame::apply(canvas, options)
+}
+
+/// Returns a background image fitted to `width` by `height` pixels with a [`Fit`].
+pub(crate) fn fit(background: &DynamicImage, fit: Fit, width: u32, height: u32) -> RgbaImage {
+    let background = background.to_rgba8();
+    let mut canvas = RgbaImage::new(width, height);
+    let (w, h) = (background.width() as f32, background.height() as f32);
+    if background.width() == 0 || background.height() == 0 {
+        return canvas;
+    }
+    let scale = match fit {
+        Fit::Cover => (width as f32 / w).max(height as f32 / h),
+        Fit::Contain => (width as f32 / w).min(height as f32 / h),
+        Fit::Tile => {
+            for y in (0..height).step_by(background.height() as usize) {
+                for x in (0..width).step_by(background.width() as usize) {
+                    imageops::replace(&mut canvas, &background, x.into(), y.into());
+                }
+            }
+            return canvas;
+        }
+    };
+    // Scaled images are at least a pixel, and centered on the card
+    let scaled_width = (w * scale).round().max(1.0) as u32;
+    let scaled_height = (h * scale).round().max(1.0) as u32;
+    let scaled = imageops::resize(
+        &background,
+        scaled_width,
+        scaled_height,
+        imageops::FilterType::CatmullRom,
+    );
+    let x = (i64::from(width) - i64::from(scaled_width)) / 2;
+    let y = (i64::from(height) - i64::from(scaled_height)) / 2;
+    imageops::replace(&mut canvas, &scaled, x, y);
+    canvas
+}
+
+/// Composite rasterized text over an image, with its top left corner at `x` and `y`.
+///
+/// Pixels are premultiplied (see [`Channel`](crate::channel::Channel)), so they are composited over the image as is.
+pub(crate) fn over(image: &mut RgbaImage, text: &RgbaImage, x: i64, y: i64) {
+    for (tx, ty, pixel) in text.enumerate_pixels() {
+        let (Ok(ix), Ok(iy)) = (
+            u32::try_from(x + i64::from(tx)),
+            u32::try_from(y + i64::from(ty)),
+        ) else {
+            continue;
+        };
+        let Some(existing) = image.get_pixel_mut_checked(ix, iy) else {
+            continue;
+        };
+        let behind = 1.0 - f32::from(pixel[3]) / 255.0;
+        for channel in 0..4 {
+            existing[channel] = (f32::from(pixel[channel]) + f32::from(existing[channel]) * behind)
+                .round()
+                .min(255.0) as u8;
+        }
+    }
+}
@@ -0,0 +1,131 @@
+//! Place carets and hit test text, for editors and interactive overlays.
+
+use crate::{layout_iter, GlyphMetrics};
+use rusttype::Font;
+
+/// Text that is laid out once, to place carets at byte indices and map points back to byte indices.
+///
+/// Carets are placed between the characters of the text, and characters that are combined into one glyph
+/// (ex. a ligature) share its advance evenly. Positions are in the pixel coordinates of the image that the text is
+/// rasterized to, as with [`layout_iter`]. Text is assumed to be left-to-right.
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{TextLayout, EN_FONT, read_font_bytes};
+///
+/// let text   = "Hello, World!\nSecond line";
+/// let font   = read_font_bytes(EN_FONT)?;
+/// let layout = TextLayout::new(text, &font, 40.0);
+///
+/// // The caret before 'W' is to the right of the caret before ','
+/// assert_eq!(layout.x_for_index(0), 0.0);
+/// assert!(layout.x_for_index(7) > layout.x_for_index(5));
+///
+/// // A click maps back to the nearest caret
+/// let x = layout.x_for_index(7);
+/// assert_eq!(layout.index_for_x(x + 1.0), 7);
+/// assert_eq!(layout.index_for_x(-10.0), 0);
+/// assert_eq!(layout.index_for_x(10_000.0), 13);
+/// // A click on the second line
+/// assert_eq!(layout.index_for_point(0.0, 70.0), 14);
+/// assert_eq!(layout.index_for_point(10_000.0, 70.0), text.len());
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct TextLayout {
+    glyphs: Vec<GlyphMetrics>,
+    /// The carets of each line that has glyphs, as byte indices and x positions in increasing order,
+    /// with the baseline of the line.
+    lines: Vec<(Vec<(usize, f32)>, f32)>,
+}
+
+impl TextLayout {
+    /// Lay out a string of text at a font size in pixels, as with [`layout_iter`].
+    pub fn new(text: &str, font: &Font, size: f32) -> Self {
+        let glyphs: Vec<GlyphMetrics> = layout_iter(text, font, size).collect();
+        let mut lines = Vec::new();
+        let count = glyphs.last().map_or(0, |glyph| glyph.line + 1);
+        for line in 0..count {
+            let mut glyphs: Vec<&GlyphMetrics> =
+                glyphs.iter().filter(|glyph| glyph.line == line).collect();
+            glyphs.sort_by_key(|glyph| glyph.cluster);
+            let Some(last) = glyphs.last() else {
+                continue;
+            };
+            // The line ends before its line break
+            let rest = &text[last.cluster..];
+            let rest = &rest[..rest.find('\n').unwrap_or(rest.len())];
+            let end = last.cluster + rest.trim_end_matches('\r').len();
+            let mut carets = Vec::new();
+            let mut clusters = glyphs.chunk_by(|a, b| a.cluster == b.cluster).peekable();
+            while let Some(cluster) = clusters.next() {
+                let start = cluster[0].cluster;
+                let next = clusters.peek().map_or(end, |next| next[0].cluster);
+                let left = cluster
+                    .iter()
+                    .map(|glyph| glyph.position.x)
+                    .fold(f32::INFINITY, f32::min);
+                let right = cluster
+                    .iter()
+                    .map(|glyph| glyph.position.x + glyph.advance)
+                    .fold(f32::NEG_INFINITY, f32::max);
+                // The characters of a cluster share its advance evenly
+                let characters: Vec<usize> = text[start..next.max(start)]
+                    .char_indices()
+                    .map(|(i, _)| start + i)
+                    .collect();
+                let count = characters.len().max(1) as f32;
+                for (i, index) in characters.into_iter().enumerate() {
+                    carets.push((index, left + (right - left) * i as f32 / count));
+                }
+                if clusters.peek().is_none() {
+                    carets.push((end, right));
+                }
+            }
+            let baseline = glyphs[0].position.y;
+            lines.push((carets, baseline));
+        }
+        TextLayout { glyphs, lines }
+    }
+
+    /// Returns the metrics of the glyphs of the text, as with [`layout_iter`].
+    pub fn glyphs(&self) -> &[GlyphMetrics] {
+        &self.glyphs
+    }
+
+    /// Returns the x position in pixels of a caret before the character at a byte index of the text,
+    /// or after the last character of its line if the index is the end of the line.
+    pub fn x_for_index(&self, index: usize) -> f32 {
+        self.lines
+            .iter()
+            .flat_map(|(carets, _)| carets)
+            .take_while(|(start, _)| *start <= index)
+            .last()
+            .map_or(0.0, |(_, x)| *x)
+    }
+
+    /// Returns the byte index of the caret on the first line that is nearest to an x position in pixels.
+    pub fn index_for_x(&self, x: f32) -> usize {
+        self.lines
+            .first()
+            .map_or(0, |(carets, _)| nearest(carets, x))
+    }
+
+    /// Returns the byte index of the caret that is nearest to a point in pixels, on the line whose baseline is nearest
+    /// to the point.
+    pub fn index_for_point(&self, x: f32, y: f32) -> usize {
+        self.lines
+            .iter()
+            .min_by(|(_, a), (_, b)| (a - y).abs().total_cmp(&(b - y).abs()))
+            .map_or(0, |(carets, _)| nearest(carets, x))
+    }
+}
+
+/// Returns the byte index of the caret that is nearest to an x position.
+fn nearest(carets: &[(usize, f32)], x: f32) -> usize {
+    carets
+        .iter()
+        .min_by(|(_, a), (_, b)| (a - x).abs().total_cmp(&(b - x).abs()))
+        .map_or(0, |(index, _)| *index)
+}
@@ -0,0 +1,67 @@
+//! Draw text to images with 8 or 16 bits per channel.
+
+use crate::{linear, Blending, Color};
+use image::Primitive;
+
+/// A channel of the pixels of an image that text is drawn to, with 8 or 16 bits.
+///
+/// [`Color`](crate::Color)s have 8 bits per channel, and are multiplied by [`Channel::SCALE`] to the range of the channel,
+/// so that pixel math is written once in the units of the channel. Math with 8 bit channels is exactly the same as
+/// math with [`u8`], since their scale is 1.
+///
+/// Pixels are premultiplied: the coverage of a glyph is multiplied into its color and alpha as it is drawn (ex. a pixel
+/// that opaque red text covers by half is `[127, 0, 0, 127]`). So pixels are composited over each other as
+/// `source + destination * (1 - source alpha)`, and their colors are divided by their alpha where they are changed or
+/// stored (ex. by filters, in linear light, or in a PNG).
+pub(crate) trait Channel: Primitive + 'static {
+    /// The largest value of the channel (ex. fully opaque).
+    const MAX: f32;
+    /// The scale from an 8 bit channel to this channel.
+    const SCALE: f32 = Self::MAX / 255.0;
+
+    /// Returns the channel as a number in the units of the channel.
+    fn to_f32(self) -> f32;
+
+    /// Returns the channel converted from a number in the units of the channel, which saturates
+    /// at 0 and [`Channel::MAX`] and is truncated like an `as` cast.
+    fn from_f32(value: f32) -> Self;
+
+    /// Returns the red, green, blue and alpha of a [`Color`] in the units of the channel, with its red, green and blue
+    /// in the color space of a [`Blending`].
+    fn color(color: &Color, blending: Blending) -> [f32; 4] {
+        let scale = |channel: u8| match blending {
+            Blending::Srgb => f32::from(channel) * Self::SCALE,
+            Blending::Linear => linear::to_linear(channel) * Self::MAX,
+        };
+        [
+            scale(color.r),
+            scale(color.g),
+            scale(color.b),
+            f32::from(color.a) * Self::SCALE,
+        ]
+    }
+}
+
+impl Channel for u8 {
+    const MAX: f32 = u8::MAX as f32;
+
+    fn to_f32(self) -> f32 {
+        f32::from(self)
+    }
+
+    fn from_f32(value: f32) -> Self {
+        value as u8
+    }
+}
+
+impl Channel for u16 {
+    const MAX: f32 = u16::MAX as f32;
+
+    fn to_f32(self) -> f32 {
+        f32::from(self)
+    }
+
+    fn from_f32(value: f32) -> Self {
+        value as u16
+    }
+}
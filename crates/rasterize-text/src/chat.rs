@@ -0,0 +1,186 @@
+//! Compose a chat message with an avatar, a name, a timestamp and a body, like a screenshot of a chat app.
+
+use crate::background::fill;
+use crate::card::{fit, over, Fit};
+use crate::effect::apply_to_image;
+use crate::frame::{self, distance};
+use crate::mask::whole_text;
+use crate::{check_size, rasterize_spans, Blending, Color, Options, RasterizeError, Span, Style};
+use image::{DynamicImage, ImageBuffer, Rgba, RgbaImage};
+use rusttype::{Font, Scale};
+
+/// The layout and colors of a chat message, rasterized with [`chat_message`].
+///
+/// The avatar is drawn as a circle on the left, and the name and timestamp are drawn on a line above the body,
+/// like a message in Discord or Slack.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ChatStyle {
+    /// Width and height of the avatar in pixels.
+    pub avatar_size: u32,
+    /// Space between the avatar and the text in pixels.
+    pub gap: f32,
+    /// Space around the message in pixels.
+    pub padding: f32,
+    /// Color of the name. If no color is provided, [`Options::color`] is used.
+    pub name_color: Option<Color>,
+    /// Color of the timestamp, which is drawn at three quarters of the font size.
+    pub timestamp_color: Color,
+}
+
+impl Default for ChatStyle {
+    fn default() -> Self {
+        ChatStyle {
+            avatar_size: 40,
+            gap: 16.0,
+            padding: 16.0,
+            name_color: None,
+            timestamp_color: Color {
+                r: 148,
+                g: 155,
+                b: 164,
+                a: 255,
+            },
+        }
+    }
+}
+
+/// Rasterize a chat message from an avatar, the name of the sender, a timestamp and spans of styled text,
+/// as configured by a [`ChatStyle`] and [`Options`].
+///
+/// The avatar is cropped to a circle, and the body is wrapped and styled as with [`rasterize_spans`].
+/// The [`Options::background`], effects without a range, [`Options::corner_radius`] and [`Options::border`]
+/// apply to the whole message.
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{chat_message, Background, ChatStyle, Color, Options, Span, Style, EN_FONT, read_font_bytes};
+/// use image::{DynamicImage, Rgba, RgbaImage};
+///
+/// let avatar = DynamicImage::ImageRgba8(RgbaImage::from_pixel(64, 64, Rgba([88, 101, 242, 255])));
+/// let body   = vec![
+///     Span::from("The release is out! Read the "),
+///     Span { style: Style { color: Some(Color { r: 0, g: 168, b: 252, a: 255 }), underline: true, ..Default::default() }, ..Span::from("changelog") },
+///     Span::from(" for the details, and let us know what breaks."),
+/// ];
+/// let font    = read_font_bytes(EN_FONT)?;
+/// let options = Options {
+///     size: 18.0,
+///     color: Color { r: 219, g: 222, b: 225, a: 255 },
+///     wrap_width: Some(360.0),
+///     background: Some(Background::Solid(Color { r: 49, g: 51, b: 56, a: 255 })),
+///     ..Default::default()
+/// };
+///
+/// let image = chat_message(&avatar, "Ada", "Today at 9:41", &body, &font, &ChatStyle::default(), &options)?;
+/// image.save("rasterize_chat.png")?;
+///
+/// // The avatar is a circle, so its corners show the background
+/// assert_eq!(image.get_pixel(17, 17), &Rgba([49, 51, 56, 255]));
+/// assert_eq!(image.get_pixel(36, 36), &Rgba([88, 101, 242, 255]));
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+pub fn chat_message(
+    avatar: &DynamicImage,
+    name: &str,
+    timestamp: &str,
+    body: &[Span],
+    font: &Font,
+    style: &ChatStyle,
+    options: &Options,
+) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>, RasterizeError> {
+    // The background, whole image effects and border are drawn once, around the whole message
+    let body_options = Options {
+        effects: options
+            .effects
+            .iter()
+            .filter(|effect| effect.range.is_some())
+            .cloned()
+            .collect(),
+        background: None,
+        corner_radius: 0.0,
+        border: None,
+        ..options.clone()
+    };
+    // The byte ranges of the mask and effects refer to the body, rather than the name and timestamp
+    let header_options = Options {
+        wrap_width: None,
+        line_numbers: None,
+        mask: whole_text(options.mask.as_ref()),
+        effects: Vec::new(),
+        ..body_options.clone()
+    };
+    let name = Span {
+        text: name.to_string(),
+        style: Style {
+            color: style.name_color,
+            bold: true,
+            ..Default::default()
+        },
+        ruby: None,
+        image: None,
+    };
+    let name = rasterize_spans(&[name], font, &header_options)?;
+    let timestamp_options = Options {
+        size: options.size * 0.75,
+        color: style.timestamp_color,
+        ..header_options.clone()
+    };
+    let timestamp = rasterize_spans(&[Span::from(timestamp)], font, &timestamp_options)?;
+    let body = rasterize_spans(body, font, &body_options)?;
+
+    // The timestamp follows the name on the same baseline, and the body starts on the next line
+    let metrics = font.v_metrics(Scale::uniform(options.size));
+    let timestamp_metrics = font.v_metrics(Scale::uniform(timestamp_options.size));
+    let padding = style.padding.max(0.0).round() as u64;
+    let text_x = padding + u64::from(style.avatar_size) + style.gap.max(0.0).round() as u64;
+    let timestamp_x = text_x + u64::from(name.width()) + (options.size / 2.0).round() as u64;
+    let timestamp_y = padding + (metrics.ascent - timestamp_metrics.ascent).round() as u64;
+    let body_y = padding + (metrics.ascent - metrics.descent + metrics.line_gap).ceil() as u64;
+    let width = (timestamp_x + u64::from(timestamp.width())).max(text_x + u64::from(body.width()))
+        + padding;
+    let height =
+        (body_y + u64::from(body.height())).max(padding + u64::from(style.avatar_size)) + padding;
+    let (width, height) = check_size(width, height, options)?;
+
+    let mut canvas = RgbaImage::new(width, height);
+    over(
+        &mut canvas,
+        &circle(avatar, style.avatar_size),
+        padding as i64,
+        padding as i64,
+    );
+    over(&mut canvas, &name, text_x as i64, padding as i64);
+    over(
+        &mut canvas,
+        &timestamp,
+        timestamp_x as i64,
+        timestamp_y as i64,
+    );
+    over(&mut canvas, &body, text_x as i64, body_y as i64);
+    if let Some(background) = &options.background {
+        fill(&mut canvas, background, 0..width, 0..height, Blending::Srgb);
+    }
+    apply_to_image(&mut canvas, options);
+
+    frame::apply(canvas, options)
+}
+
+/// Returns an avatar that is scaled to cover a square of `size` pixels, and cropped to a circle.
+///
+/// Its colors are multiplied by their alpha, like the pixels of glyphs, so it is composited like text.
+fn circle(avatar: &DynamicImage, size: u32) -> RgbaImage {
+    let mut avatar = fit(avatar, Fit::Cover, size, size);
+    let radius = size as f32 / 2.0;
+    let rect = (0.0, 0.0, size as f32, size as f32);
+    for (x, y, pixel) in avatar.enumerate_pixels_mut() {
+        let coverage =
+            (0.5 - distance(x as f32 + 0.5, y as f32 + 0.5, rect, radius)).clamp(0.0, 1.0);
+        let alpha = f32::from(pixel[3]) / 255.0 * coverage;
+        for channel in 0..3 {
+            pixel[channel] = (f32::from(pixel[channel]) * alpha).round() as u8;
+        }
+        pixel[3] = (alpha * 255.0).round() as u8;
+    }
+    avatar
+}
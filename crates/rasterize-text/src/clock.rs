@@ -0,0 +1,80 @@
+//! Draw the time as a digital clock, for dashboards (ex. e-ink displays).
+
+use crate::{rasterize_spans, Options, Position, RasterizeError, Span, Style};
+use image::{ImageBuffer, Rgba};
+use rusttype::Font;
+
+/// A time of day, drawn as a digital clock by [`rasterize_clock`].
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{rasterize_clock, Clock, Options, EN_FONT, read_font_bytes};
+///
+/// let font    = read_font_bytes(EN_FONT)?;
+/// let options = Options { size: 96.0, ..Default::default() };
+///
+/// let clock = Clock { hour: 21, minute: 41, ..Default::default() };
+/// let image = rasterize_clock(&clock, &font, &options)?;
+/// image.save("rasterize_clock.png")?;
+///
+/// // A 12-hour clock is followed by a smaller AM or PM
+/// let twelve_hour = Clock { twelve_hour: true, second: Some(5), ..clock };
+/// assert_eq!(twelve_hour.time(), "9:41:05");
+/// let image = rasterize_clock(&twelve_hour, &font, &options)?;
+/// image.save("rasterize_clock_12_hour.png")?;
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Clock {
+    /// The hour, from 0 to 23. Larger hours wrap around.
+    pub hour: u8,
+    /// The minute, from 0 to 59. Larger minutes wrap around.
+    pub minute: u8,
+    /// The second, from 0 to 59, which is only drawn if it is provided. Larger seconds wrap around.
+    pub second: Option<u8>,
+    /// Draw the hour from 1 to 12, followed by AM or PM, instead of from 00 to 23.
+    pub twelve_hour: bool,
+}
+
+impl Clock {
+    /// Returns the time as text (ex. `21:41` or `9:41:05`), without AM or PM.
+    pub fn time(&self) -> String {
+        let hour = self.hour % 24;
+        let mut time = match self.twelve_hour {
+            true => format!("{}:{:02}", (hour + 11) % 12 + 1, self.minute % 60),
+            false => format!("{hour:02}:{:02}", self.minute % 60),
+        };
+        if let Some(second) = self.second {
+            time.push_str(&format!(":{:02}", second % 60));
+        }
+        time
+    }
+}
+
+/// Rasterize the time of a [`Clock`] to an [`ImageBuffer`], as configured by [`Options`].
+///
+/// The time is drawn at the [`Options::size`], and AM or PM of a 12-hour clock is drawn after it as a superscript.
+/// The other options apply as with [`rasterize_spans`] (ex. a background and rounded corners for a widget).
+pub fn rasterize_clock(
+    clock: &Clock,
+    font: &Font,
+    options: &Options,
+) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>, RasterizeError> {
+    let mut spans = vec![Span::from(clock.time().as_str())];
+    if clock.twelve_hour {
+        spans.push(Span {
+            text: match clock.hour % 24 < 12 {
+                true => " AM".to_string(),
+                false => " PM".to_string(),
+            },
+            style: Style {
+                position: Position::Superscript,
+                ..Default::default()
+            },
+            ruby: None,
+            image: None,
+        });
+    }
+    rasterize_spans(&spans, font, options)
+}
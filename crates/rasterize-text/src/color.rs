@@ -0,0 +1,628 @@
+//! Parse text colors from [CSS Color Level 4](https://www.w3.org/TR/css-color-4/) syntax or space delimited RGBA values.
+
+use std::str::FromStr;
+
+/// A color in the sRGB color space, with Red, Green, Blue and Alpha channels stored as 8-bit values (0-255).
+///
+/// A [`Color`] is most easily created with [`Color::from_str`], which accepts CSS colors and the original space delimited RGBA format.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ColorError {
+    #[error("Failed to parse value {2} to RGBA color in: {1}")]
+    RgbaParseError(#[source] std::num::ParseIntError, String, String),
+    #[error("Failed to parse RGBA because of an incorrect number of values (expected 4): {0:?}.")]
+    RgbaLengthError(Vec<u8>),
+    #[error("Unknown color: {0:?}. Expected a CSS color (ex. 'red', '#ff0000', 'hsl(0 100% 50%)') or a space delimited RGBA value (ex. '255 0 0 255').")]
+    UnknownColorError(String),
+    #[error("Failed to parse hexadecimal color: {0}")]
+    HexParseError(String),
+    #[error("Failed to parse value {1:?} in CSS color: {0}")]
+    CssParseError(String, String),
+    #[error("Failed to parse CSS color because of an incorrect number of values (expected {1}): {0}")]
+    CssLengthError(String, usize),
+}
+
+impl Default for Color {
+    fn default() -> Self {
+        Color {
+            r: 0,
+            g: 0,
+            b: 0,
+            a: 255,
+        }
+    }
+}
+
+impl std::fmt::Display for Color {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        // Convert to lowercase for RUST_LOG env var compatibility
+        let color = format!("{} {} {} {}", self.r, self.g, self.b, self.a);
+        write!(f, "{color}")
+    }
+}
+
+impl FromStr for Color {
+    type Err = ColorError;
+
+    /// Returns a [`Color`] converted from a [`str`].
+    ///
+    /// Two formats are accepted:
+    ///
+    /// - A space delimited RGBA value, where each channel is an integer from 0 to 255 (ex. `"255 0 0 255"`).
+    /// - A [CSS Color Level 4](https://www.w3.org/TR/css-color-4/) value, which includes:
+    ///     - Named colors (ex. `red`, `rebeccapurple`, `transparent`).
+    ///     - Hexadecimal colors (ex. `#f00`, `#f008`, `#ff0000`, `#ff000080`).
+    ///     - The color functions `rgb()`, `rgba()`, `hsl()`, `hsla()`, `hwb()`, `lab()`, `lch()`, `oklab()`, `oklch()` and `color()` (with the `srgb` and `srgb-linear` color spaces).
+    ///     - Both the modern space delimited syntax with an optional `/ alpha`, and the legacy comma delimited syntax.
+    ///
+    /// Colors outside of the sRGB gamut are clamped to the nearest 8-bit channel value.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use rasterize_text::Color;
+    /// use std::str::FromStr;
+    ///
+    /// let red = Color { r: 255, g: 0, b: 0, a: 255 };
+    /// assert_eq!(Color::from_str("255 0 0 255")?, red);
+    /// assert_eq!(Color::from_str("red")?, red);
+    /// assert_eq!(Color::from_str("#f00")?, red);
+    /// assert_eq!(Color::from_str("rgb(255, 0, 0)")?, red);
+    /// assert_eq!(Color::from_str("hsl(0deg 100% 50%)")?, red);
+    ///
+    /// // A semi-transparent blue.
+    /// let color = Color::from_str("hsl(200 50% 40% / 0.5)")?;
+    /// assert_eq!(color, Color { r: 51, g: 119, b: 153, a: 128 });
+    /// # Ok::<(), color_eyre::eyre::Report>(())
+    /// ```
+    fn from_str(color: &str) -> Result<Self, Self::Err> {
+        // CSS colors never start with a digit, so this must be a space delimited RGBA value.
+        match color.trim_start().starts_with(|c: char| c.is_ascii_digit()) {
+            true => parse_rgba(color),
+            false => parse_css(color),
+        }
+    }
+}
+
+impl Color {
+    /// Returns a [`Color`] from sRGB channels in the range 0.0-1.0, clamping values that are out of gamut.
+    fn from_srgb(r: f32, g: f32, b: f32, a: f32) -> Self {
+        let channel = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+        Color {
+            r: channel(r),
+            g: channel(g),
+            b: channel(b),
+            a: channel(a),
+        }
+    }
+
+    /// Returns a [`Color`] from linear-light sRGB channels in the range 0.0-1.0.
+    fn from_linear_srgb(r: f32, g: f32, b: f32, a: f32) -> Self {
+        // Apply the sRGB transfer function (gamma encoding), preserving the sign for out of gamut values.
+        let encode = |v: f32| match v.abs() <= 0.0031308 {
+            true => v * 12.92,
+            false => v.signum() * (1.055 * v.abs().powf(1.0 / 2.4) - 0.055),
+        };
+        Color::from_srgb(encode(r), encode(g), encode(b), a)
+    }
+}
+
+/// Parse the original space delimited RGBA format (ex. `"255 0 0 255"`).
+fn parse_rgba(color: &str) -> Result<Color, ColorError> {
+    let rgba: Vec<u8> = color
+        // Split the color string on a space delimiter
+        .split(" ")
+        // Try to convert the space-delimited text to rgba values (8-bit, 0-255)
+        .map(|s| {
+            s.parse::<u8>()
+                .map_err(|e| ColorError::RgbaParseError(e, color.to_string(), s.to_string()))
+        })
+        // Gather the rgba values into a vector, throw an error if an issue was encountered
+        .collect::<Result<Vec<u8>, ColorError>>()?;
+
+    // Convert the rgba value vector into a fixed array of length 4
+    let rgba: [u8; 4] = rgba
+        .clone()
+        .try_into()
+        .map_err(|_| ColorError::RgbaLengthError(rgba))?;
+
+    let color = Color {
+        r: rgba[0],
+        g: rgba[1],
+        b: rgba[2],
+        a: rgba[3],
+    };
+
+    Ok(color)
+}
+
+/// Parse a CSS color: a named color, hexadecimal color, or color function.
+fn parse_css(color: &str) -> Result<Color, ColorError> {
+    // CSS keywords and function names are ASCII case-insensitive
+    let css = color.trim().to_ascii_lowercase();
+
+    if let Some(hex) = css.strip_prefix('#') {
+        return parse_hex(color, hex);
+    }
+    if let Some((name, args)) = css.strip_suffix(')').and_then(|s| s.split_once('(')) {
+        return parse_function(color, name.trim(), args);
+    }
+    if css == "transparent" {
+        return Ok(Color { r: 0, g: 0, b: 0, a: 0 });
+    }
+
+    NAMED_COLORS
+        .binary_search_by(|(name, _)| name.cmp(&css.as_str()))
+        .map(|i| {
+            let [r, g, b] = NAMED_COLORS[i].1;
+            Color { r, g, b, a: 255 }
+        })
+        .map_err(|_| ColorError::UnknownColorError(color.to_string()))
+}
+
+/// Parse the digits of a hexadecimal color in the forms `rgb`, `rgba`, `rrggbb` or `rrggbbaa`.
+fn parse_hex(color: &str, hex: &str) -> Result<Color, ColorError> {
+    let error = || ColorError::HexParseError(color.to_string());
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(error());
+    }
+
+    // Expand the short forms, where each digit is repeated (ex. "f00" => "ff0000")
+    let hex = match hex.len() {
+        3 | 4 => hex.chars().flat_map(|c| [c, c]).collect::<String>(),
+        6 | 8 => hex.to_string(),
+        _ => return Err(error()),
+    };
+
+    let channels = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| error()))
+        .collect::<Result<Vec<u8>, ColorError>>()?;
+
+    Ok(Color {
+        r: channels[0],
+        g: channels[1],
+        b: channels[2],
+        a: channels.get(3).copied().unwrap_or(255),
+    })
+}
+
+/// A single value inside a CSS color function, such as `50%` or `200deg`.
+#[derive(Copy, Clone, Debug)]
+enum Component {
+    Number(f32),
+    Percentage(f32),
+    /// An angle, converted to degrees.
+    Angle(f32),
+    /// The `none` keyword, which represents a missing component.
+    None,
+}
+
+impl Component {
+    fn parse(color: &str, value: &str) -> Result<Self, ColorError> {
+        let error = || ColorError::CssParseError(color.to_string(), value.to_string());
+        let number = |s: &str| s.parse::<f32>().ok().filter(|n| n.is_finite()).ok_or_else(error);
+
+        if value == "none" {
+            return Ok(Component::None);
+        }
+        if let Some(percentage) = value.strip_suffix('%') {
+            return Ok(Component::Percentage(number(percentage)?));
+        }
+
+        // Angle units, with their conversion factor to degrees
+        let units = [
+            ("deg", 1.0),
+            ("grad", 0.9),
+            ("rad", 180.0 / std::f32::consts::PI),
+            ("turn", 360.0),
+        ];
+        for (unit, factor) in units {
+            if let Some(angle) = value.strip_suffix(unit) {
+                return Ok(Component::Angle(number(angle)? * factor));
+            }
+        }
+
+        Ok(Component::Number(number(value)?))
+    }
+
+    /// Resolve to a number, where a percentage of 100% is equal to `reference`.
+    fn number(self, color: &str, reference: f32) -> Result<f32, ColorError> {
+        match self {
+            Component::Number(n) => Ok(n),
+            Component::Percentage(p) => Ok(p / 100.0 * reference),
+            Component::None => Ok(0.0),
+            Component::Angle(_) => Err(ColorError::CssParseError(
+                color.to_string(),
+                self.to_string(),
+            )),
+        }
+    }
+
+    /// Resolve to a hue in degrees, normalized to the range 0-360.
+    fn hue(self, color: &str) -> Result<f32, ColorError> {
+        match self {
+            Component::Number(degrees) | Component::Angle(degrees) => Ok(degrees.rem_euclid(360.0)),
+            Component::None => Ok(0.0),
+            Component::Percentage(_) => Err(ColorError::CssParseError(
+                color.to_string(),
+                self.to_string(),
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for Component {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Component::Number(n) => write!(f, "{n}"),
+            Component::Percentage(p) => write!(f, "{p}%"),
+            Component::Angle(degrees) => write!(f, "{degrees}deg"),
+            Component::None => write!(f, "none"),
+        }
+    }
+}
+
+/// Parse a CSS color function, such as `rgb(255 0 0 / 50%)` or `hsl(0, 100%, 50%)`.
+fn parse_function(color: &str, name: &str, args: &str) -> Result<Color, ColorError> {
+    // The color() function starts with the name of a color space
+    let (space, args) = match name {
+        "color" => args
+            .trim_start()
+            .split_once(char::is_whitespace)
+            .ok_or_else(|| ColorError::CssLengthError(color.to_string(), 4))?,
+        _ => ("", args),
+    };
+
+    // Separate the alpha value, which is either after a "/" (modern syntax),
+    // or the fourth value of the legacy comma delimited syntax.
+    let (values, alpha): (Vec<&str>, Option<&str>) = match args.contains(',') {
+        true => {
+            let mut values: Vec<&str> = args.split(',').map(str::trim).collect();
+            let alpha = (values.len() == 4).then(|| values.remove(3));
+            (values, alpha)
+        }
+        false => {
+            let (values, alpha) = match args.split_once('/') {
+                Some((values, alpha)) => (values, Some(alpha.trim())),
+                None => (args, None),
+            };
+            (values.split_whitespace().collect(), alpha)
+        }
+    };
+
+    if values.len() != 3 {
+        return Err(ColorError::CssLengthError(color.to_string(), 3));
+    }
+    let c = values
+        .iter()
+        .map(|value| Component::parse(color, value))
+        .collect::<Result<Vec<Component>, ColorError>>()?;
+    let alpha = match alpha {
+        Some(alpha) => Component::parse(color, alpha)?.number(color, 1.0)?,
+        None => 1.0,
+    };
+
+    let color = match (name, space) {
+        ("rgb" | "rgba", _) => Color::from_srgb(
+            c[0].number(color, 255.0)? / 255.0,
+            c[1].number(color, 255.0)? / 255.0,
+            c[2].number(color, 255.0)? / 255.0,
+            alpha,
+        ),
+        ("hsl" | "hsla", _) => {
+            let (r, g, b) = hsl_to_srgb(
+                c[0].hue(color)?,
+                c[1].number(color, 100.0)? / 100.0,
+                c[2].number(color, 100.0)? / 100.0,
+            );
+            Color::from_srgb(r, g, b, alpha)
+        }
+        ("hwb", _) => {
+            let (r, g, b) = hwb_to_srgb(
+                c[0].hue(color)?,
+                c[1].number(color, 100.0)? / 100.0,
+                c[2].number(color, 100.0)? / 100.0,
+            );
+            Color::from_srgb(r, g, b, alpha)
+        }
+        ("lab", _) => {
+            let (r, g, b) = lab_to_linear_srgb(
+                c[0].number(color, 100.0)?,
+                c[1].number(color, 125.0)?,
+                c[2].number(color, 125.0)?,
+            );
+            Color::from_linear_srgb(r, g, b, alpha)
+        }
+        ("lch", _) => {
+            let (a, b) = polar_to_cartesian(c[1].number(color, 150.0)?, c[2].hue(color)?);
+            let (r, g, b) = lab_to_linear_srgb(c[0].number(color, 100.0)?, a, b);
+            Color::from_linear_srgb(r, g, b, alpha)
+        }
+        ("oklab", _) => {
+            let (r, g, b) = oklab_to_linear_srgb(
+                c[0].number(color, 1.0)?,
+                c[1].number(color, 0.4)?,
+                c[2].number(color, 0.4)?,
+            );
+            Color::from_linear_srgb(r, g, b, alpha)
+        }
+        ("oklch", _) => {
+            let (a, b) = polar_to_cartesian(c[1].number(color, 0.4)?, c[2].hue(color)?);
+            let (r, g, b) = oklab_to_linear_srgb(c[0].number(color, 1.0)?, a, b);
+            Color::from_linear_srgb(r, g, b, alpha)
+        }
+        ("color", "srgb") => Color::from_srgb(
+            c[0].number(color, 1.0)?,
+            c[1].number(color, 1.0)?,
+            c[2].number(color, 1.0)?,
+            alpha,
+        ),
+        ("color", "srgb-linear") => Color::from_linear_srgb(
+            c[0].number(color, 1.0)?,
+            c[1].number(color, 1.0)?,
+            c[2].number(color, 1.0)?,
+            alpha,
+        ),
+        _ => Err(ColorError::UnknownColorError(color.to_string()))?,
+    };
+
+    Ok(color)
+}
+
+/// Convert hue (degrees), saturation and lightness (0.0-1.0) to sRGB.
+fn hsl_to_srgb(hue: f32, saturation: f32, lightness: f32) -> (f32, f32, f32) {
+    let a = saturation * lightness.min(1.0 - lightness);
+    let f = |n: f32| {
+        let k = (n + hue / 30.0) % 12.0;
+        lightness - a * (k - 3.0).min(9.0 - k).clamp(-1.0, 1.0)
+    };
+    (f(0.0), f(8.0), f(4.0))
+}
+
+/// Convert hue (degrees), whiteness and blackness (0.0-1.0) to sRGB.
+fn hwb_to_srgb(hue: f32, whiteness: f32, blackness: f32) -> (f32, f32, f32) {
+    if whiteness + blackness >= 1.0 {
+        let gray = whiteness / (whiteness + blackness);
+        return (gray, gray, gray);
+    }
+    let (r, g, b) = hsl_to_srgb(hue, 1.0, 0.5);
+    let f = |v: f32| v * (1.0 - whiteness - blackness) + whiteness;
+    (f(r), f(g), f(b))
+}
+
+/// Convert the chroma and hue (degrees) of a polar color (LCH, OKLCH) to the a and b axes.
+fn polar_to_cartesian(chroma: f32, hue: f32) -> (f32, f32) {
+    let hue = hue.to_radians();
+    (chroma * hue.cos(), chroma * hue.sin())
+}
+
+/// Convert CIE Lab (D50 white point) to linear-light sRGB.
+fn lab_to_linear_srgb(l: f32, a: f32, b: f32) -> (f32, f32, f32) {
+    const KAPPA: f32 = 24389.0 / 27.0;
+    const EPSILON: f32 = 216.0 / 24389.0;
+
+    // Lab to XYZ, relative to the D50 white point
+    let fy = (l + 16.0) / 116.0;
+    let fx = a / 500.0 + fy;
+    let fz = fy - b / 200.0;
+    let x = match fx.powi(3) > EPSILON {
+        true => fx.powi(3),
+        false => (116.0 * fx - 16.0) / KAPPA,
+    };
+    let y = match l > KAPPA * EPSILON {
+        true => fy.powi(3),
+        false => l / KAPPA,
+    };
+    let z = match fz.powi(3) > EPSILON {
+        true => fz.powi(3),
+        false => (116.0 * fz - 16.0) / KAPPA,
+    };
+    let xyz = [x * 0.3457 / 0.3585, y, z * (1.0 - 0.3457 - 0.3585) / 0.3585];
+
+    // Chromatic adaptation from D50 to D65 (Bradford)
+    let xyz = multiply(
+        [
+            [0.955_473_4, -0.023_098_537, 0.063_259_31],
+            [-0.028_369_706, 1.009_995_5, 0.021_041_399],
+            [0.012_314_002, -0.020_507_697, 1.330_366],
+        ],
+        xyz,
+    );
+
+    // XYZ (D65) to linear sRGB
+    let [r, g, b] = multiply(
+        [
+            [3.240_97, -1.537_383_2, -0.498_610_76],
+            [-0.969_243_65, 1.875_967_5, 0.041_555_06],
+            [0.055_630_08, -0.203_976_96, 1.056_971_5],
+        ],
+        xyz,
+    );
+    (r, g, b)
+}
+
+/// Convert OKLab to linear-light sRGB.
+fn oklab_to_linear_srgb(l: f32, a: f32, b: f32) -> (f32, f32, f32) {
+    let lms = multiply(
+        [
+            [1.0, 0.396_337_78, 0.215_803_76],
+            [1.0, -0.105_561_346, -0.063_854_17],
+            [1.0, -0.089_484_18, -1.291_485_5],
+        ],
+        [l, a, b],
+    );
+    let [r, g, b] = multiply(
+        [
+            [4.076_741_7, -3.307_711_6, 0.230_969_94],
+            [-1.268_438, 2.609_757_4, -0.341_319_38],
+            [-0.004_196_086_3, -0.703_418_6, 1.707_614_7],
+        ],
+        lms.map(|v| v.powi(3)),
+    );
+    (r, g, b)
+}
+
+/// Multiply a 3x3 matrix by a vector.
+fn multiply(matrix: [[f32; 3]; 3], vector: [f32; 3]) -> [f32; 3] {
+    matrix.map(|row| row.iter().zip(vector).map(|(m, v)| m * v).sum())
+}
+
+/// The [CSS named colors](https://www.w3.org/TR/css-color-4/#named-colors), sorted by name for binary search.
+const NAMED_COLORS: &[(&str, [u8; 3])] = &[
+    ("aliceblue", [240, 248, 255]),
+    ("antiquewhite", [250, 235, 215]),
+    ("aqua", [0, 255, 255]),
+    ("aquamarine", [127, 255, 212]),
+    ("azure", [240, 255, 255]),
+    ("beige", [245, 245, 220]),
+    ("bisque", [255, 228, 196]),
+    ("black", [0, 0, 0]),
+    ("blanchedalmond", [255, 235, 205]),
+    ("blue", [0, 0, 255]),
+    ("blueviolet", [138, 43, 226]),
+    ("brown", [165, 42, 42]),
+    ("burlywood", [222, 184, 135]),
+    ("cadetblue", [95, 158, 160]),
+    ("chartreuse", [127, 255, 0]),
+    ("chocolate", [210, 105, 30]),
+    ("coral", [255, 127, 80]),
+    ("cornflowerblue", [100, 149, 237]),
+    ("cornsilk", [255, 248, 220]),
+    ("crimson", [220, 20, 60]),
+    ("cyan", [0, 255, 255]),
+    ("darkblue", [0, 0, 139]),
+    ("darkcyan", [0, 139, 139]),
+    ("darkgoldenrod", [184, 134, 11]),
+    ("darkgray", [169, 169, 169]),
+    ("darkgreen", [0, 100, 0]),
+    ("darkgrey", [169, 169, 169]),
+    ("darkkhaki", [189, 183, 107]),
+    ("darkmagenta", [139, 0, 139]),
+    ("darkolivegreen", [85, 107, 47]),
+    ("darkorange", [255, 140, 0]),
+    ("darkorchid", [153, 50, 204]),
+    ("darkred", [139, 0, 0]),
+    ("darksalmon", [233, 150, 122]),
+    ("darkseagreen", [143, 188, 143]),
+    ("darkslateblue", [72, 61, 139]),
+    ("darkslategray", [47, 79, 79]),
+    ("darkslategrey", [47, 79, 79]),
+    ("darkturquoise", [0, 206, 209]),
+    ("darkviolet", [148, 0, 211]),
+    ("deeppink", [255, 20, 147]),
+    ("deepskyblue", [0, 191, 255]),
+    ("dimgray", [105, 105, 105]),
+    ("dimgrey", [105, 105, 105]),
+    ("dodgerblue", [30, 144, 255]),
+    ("firebrick", [178, 34, 34]),
+    ("floralwhite", [255, 250, 240]),
+    ("forestgreen", [34, 139, 34]),
+    ("fuchsia", [255, 0, 255]),
+    ("gainsboro", [220, 220, 220]),
+    ("ghostwhite", [248, 248, 255]),
+    ("gold", [255, 215, 0]),
+    ("goldenrod", [218, 165, 32]),
+    ("gray", [128, 128, 128]),
+    ("green", [0, 128, 0]),
+    ("greenyellow", [173, 255, 47]),
+    ("grey", [128, 128, 128]),
+    ("honeydew", [240, 255, 240]),
+    ("hotpink", [255, 105, 180]),
+    ("indianred", [205, 92, 92]),
+    ("indigo", [75, 0, 130]),
+    ("ivory", [255, 255, 240]),
+    ("khaki", [240, 230, 140]),
+    ("lavender", [230, 230, 250]),
+    ("lavenderblush", [255, 240, 245]),
+    ("lawngreen", [124, 252, 0]),
+    ("lemonchiffon", [255, 250, 205]),
+    ("lightblue", [173, 216, 230]),
+    ("lightcoral", [240, 128, 128]),
+    ("lightcyan", [224, 255, 255]),
+    ("lightgoldenrodyellow", [250, 250, 210]),
+    ("lightgray", [211, 211, 211]),
+    ("lightgreen", [144, 238, 144]),
+    ("lightgrey", [211, 211, 211]),
+    ("lightpink", [255, 182, 193]),
+    ("lightsalmon", [255, 160, 122]),
+    ("lightseagreen", [32, 178, 170]),
+    ("lightskyblue", [135, 206, 250]),
+    ("lightslategray", [119, 136, 153]),
+    ("lightslategrey", [119, 136, 153]),
+    ("lightsteelblue", [176, 196, 222]),
+    ("lightyellow", [255, 255, 224]),
+    ("lime", [0, 255, 0]),
+    ("limegreen", [50, 205, 50]),
+    ("linen", [250, 240, 230]),
+    ("magenta", [255, 0, 255]),
+    ("maroon", [128, 0, 0]),
+    ("mediumaquamarine", [102, 205, 170]),
+    ("mediumblue", [0, 0, 205]),
+    ("mediumorchid", [186, 85, 211]),
+    ("mediumpurple", [147, 112, 219]),
+    ("mediumseagreen", [60, 179, 113]),
+    ("mediumslateblue", [123, 104, 238]),
+    ("mediumspringgreen", [0, 250, 154]),
+    ("mediumturquoise", [72, 209, 204]),
+    ("mediumvioletred", [199, 21, 133]),
+    ("midnightblue", [25, 25, 112]),
+    ("mintcream", [245, 255, 250]),
+    ("mistyrose", [255, 228, 225]),
+    ("moccasin", [255, 228, 181]),
+    ("navajowhite", [255, 222, 173]),
+    ("navy", [0, 0, 128]),
+    ("oldlace", [253, 245, 230]),
+    ("olive", [128, 128, 0]),
+    ("olivedrab", [107, 142, 35]),
+    ("orange", [255, 165, 0]),
+    ("orangered", [255, 69, 0]),
+    ("orchid", [218, 112, 214]),
+    ("palegoldenrod", [238, 232, 170]),
+    ("palegreen", [152, 251, 152]),
+    ("paleturquoise", [175, 238, 238]),
+    ("palevioletred", [219, 112, 147]),
+    ("papayawhip", [255, 239, 213]),
+    ("peachpuff", [255, 218, 185]),
+    ("peru", [205, 133, 63]),
+    ("pink", [255, 192, 203]),
+    ("plum", [221, 160, 221]),
+    ("powderblue", [176, 224, 230]),
+    ("purple", [128, 0, 128]),
+    ("rebeccapurple", [102, 51, 153]),
+    ("red", [255, 0, 0]),
+    ("rosybrown", [188, 143, 143]),
+    ("royalblue", [65, 105, 225]),
+    ("saddlebrown", [139, 69, 19]),
+    ("salmon", [250, 128, 114]),
+    ("sandybrown", [244, 164, 96]),
+    ("seagreen", [46, 139, 87]),
+    ("seashell", [255, 245, 238]),
+    ("sienna", [160, 82, 45]),
+    ("silver", [192, 192, 192]),
+    ("skyblue", [135, 206, 235]),
+    ("slateblue", [106, 90, 205]),
+    ("slategray", [112, 128, 144]),
+    ("slategrey", [112, 128, 144]),
+    ("snow", [255, 250, 250]),
+    ("springgreen", [0, 255, 127]),
+    ("steelblue", [70, 130, 180]),
+    ("tan", [210, 180, 140]),
+    ("teal", [0, 128, 128]),
+    ("thistle", [216, 191, 216]),
+    ("tomato", [255, 99, 71]),
+    ("turquoise", [64, 224, 208]),
+    ("violet", [238, 130, 238]),
+    ("wheat", [245, 222, 179]),
+    ("white", [255, 255, 255]),
+    ("whitesmoke", [245, 245, 245]),
+    ("yellow", [255, 255, 0]),
+    ("yellowgreen", [154, 205, 50]),
+];
@@ -0,0 +1,112 @@
+//! Read the layers of color glyphs from the COLR (version 0) and CPAL tables of fonts.
+
+use crate::shape::font_data;
+use crate::{Color, ColorPalette};
+use rusttype::Font;
+use rustybuzz::ttf_parser::{Face, Tag};
+
+/// The palette entry of a layer that is drawn in the color of the text, rather than a color of the palette.
+const FOREGROUND: u16 = 0xFFFF;
+
+/// The color and palette tables of a font, which define color glyphs as layers of other glyphs.
+pub(crate) struct ColorTables<'a> {
+    colr: &'a [u8],
+    cpal: &'a [u8],
+}
+
+impl<'a> ColorTables<'a> {
+    /// Returns the color tables of a font, or [`None`] if the font has no color glyphs.
+    pub(crate) fn parse(font: &'a Font) -> Option<Self> {
+        let face = Face::parse(font_data(font)?, 0).ok()?;
+        let raw = face.raw_face();
+        let colr = raw.table(Tag::from_bytes(b"COLR"))?;
+        let cpal = raw.table(Tag::from_bytes(b"CPAL"))?;
+        Some(ColorTables { colr, cpal })
+    }
+
+    /// Returns the layers of a color glyph from the bottom up, as glyphs of the same font and their colors, or
+    /// [`None`] if the glyph isn't a color glyph (or its layers are malformed).
+    ///
+    /// Layers in the foreground entry are in the color of the text, and the alpha of the other layers is multiplied
+    /// by the alpha of the text, so that faded text has faded color glyphs.
+    pub(crate) fn layers(
+        &self,
+        glyph: u16,
+        color: Color,
+        palette: &ColorPalette,
+    ) -> Option<Vec<(u16, Color)>> {
+        let count = read_u16(self.colr, 2)?;
+        let base_offset = read_u32(self.colr, 4)? as usize;
+        let layer_offset = read_u32(self.colr, 8)? as usize;
+        // Base glyph records are sorted by glyph, so they are searched by bisection
+        let base = bisect(count, |i| read_u16(self.colr, base_offset + 6 * i), glyph)?;
+        let first = read_u16(self.colr, base_offset + 6 * base + 2)? as usize;
+        let layers = read_u16(self.colr, base_offset + 6 * base + 4)? as usize;
+        (first..first + layers)
+            .map(|layer| {
+                let id = read_u16(self.colr, layer_offset + 4 * layer)?;
+                let entry = read_u16(self.colr, layer_offset + 4 * layer + 2)?;
+                let layer_color = match entry {
+                    FOREGROUND => color,
+                    entry => {
+                        let entry_color = palette
+                            .overrides
+                            .iter()
+                            .rev()
+                            .find(|(index, _)| *index == entry)
+                            .map(|(_, color)| *color)
+                            .or_else(|| self.entry(palette.index, entry))?;
+                        let alpha = u16::from(entry_color.a) * u16::from(color.a) / 255;
+                        Color {
+                            a: alpha as u8,
+                            ..entry_color
+                        }
+                    }
+                };
+                Some((id, layer_color))
+            })
+            .collect()
+    }
+
+    /// Returns the color of an entry of a palette, from the first palette if the font doesn't have the palette.
+    fn entry(&self, palette: u16, entry: u16) -> Option<Color> {
+        let entries = read_u16(self.cpal, 2)?;
+        let palettes = read_u16(self.cpal, 4)?;
+        let records = read_u32(self.cpal, 8)? as usize;
+        if entry >= entries {
+            return None;
+        }
+        let palette = if palette < palettes { palette } else { 0 };
+        let first = read_u16(self.cpal, 12 + 2 * palette as usize)? as usize;
+        // Color records are stored as blue, green, red and alpha
+        let offset = records + 4 * (first + entry as usize);
+        let [b, g, r, a] = self.cpal.get(offset..offset + 4)?.try_into().ok()?;
+        Some(Color { r, g, b, a })
+    }
+}
+
+/// Returns the index of a glyph in sorted records of a count, whose glyphs are read by a function.
+fn bisect(count: u16, read: impl Fn(usize) -> Option<u16>, glyph: u16) -> Option<usize> {
+    let (mut low, mut high) = (0, count as usize);
+    while low < high {
+        let middle = (low + high) / 2;
+        match read(middle)?.cmp(&glyph) {
+            std::cmp::Ordering::Less => low = middle + 1,
+            std::cmp::Ordering::Greater => high = middle,
+            std::cmp::Ordering::Equal => return Some(middle),
+        }
+    }
+    None
+}
+
+/// Returns the big-endian [`u16`] at an offset of a table.
+fn read_u16(table: &[u8], offset: usize) -> Option<u16> {
+    let bytes = table.get(offset..offset + 2)?;
+    Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+}
+
+/// Returns the big-endian [`u32`] at an offset of a table.
+fn read_u32(table: &[u8], offset: usize) -> Option<u32> {
+    let bytes = table.get(offset..offset + 4)?;
+    Some(u32::from_be_bytes(bytes.try_into().ok()?))
+}
@@ -0,0 +1,149 @@
+//! Align columns of numbers on their decimal separators, for tables of figures (ex. financial statements).
+
+use crate::layout::layout;
+use crate::metadata::add_warnings;
+use crate::span::segments;
+use crate::{draw, position_glyphs, strict, Options, RasterizeError, Span};
+use image::{ImageBuffer, Rgba};
+use rusttype::{point, Font, Scale};
+
+/// A column of numbers, rasterized with [`rasterize_number_column`].
+///
+/// The numbers are text that is already formatted (ex. by `format_number` with the `icu` feature), so they can have
+/// grouping separators, signs, currency symbols or units. They are aligned on the last decimal separator of each
+/// number, and numbers without a decimal separator are aligned as if it followed their last character.
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{NumberColumn, Options, PixelSnap, rasterize_number_column, EN_FONT, read_font_bytes};
+/// use image::RgbaImage;
+///
+/// let font    = read_font_bytes(EN_FONT)?;
+/// // Glyphs are snapped to whole pixels, so that glyphs at the same position start in the same pixel column
+/// let options = Options { size: 24.0, paragraph_spacing: 10.0, pixel_snap: PixelSnap::Positions, ..Default::default() };
+/// let column  = |numbers: &[&str]| NumberColumn {
+///     numbers: numbers.iter().map(|number| number.to_string()).collect(),
+///     decimal_separator: ',',
+/// };
+///
+/// // The columns of the drawn pixels of each pixel row of each number, which are separated by transparent pixel rows
+/// let rows = |image: &RgbaImage| {
+///     let mut rows: Vec<Vec<Vec<u32>>> = Vec::new();
+///     let mut gap = true;
+///     for y in 0..image.height() {
+///         let drawn: Vec<u32> = (0..image.width()).filter(|x| image.get_pixel(*x, y)[3] > 0).collect();
+///         if drawn.is_empty() {
+///             gap = true;
+///         } else if std::mem::replace(&mut gap, false) {
+///             rows.push(vec![drawn]);
+///         } else {
+///             rows.last_mut().unwrap().push(drawn);
+///         }
+///     }
+///     rows
+/// };
+///
+/// // The lowest pixels of each number are the tail of its comma, so the commas start in the same pixel column
+/// let image = rasterize_number_column(&column(&["1.234,56", "-7,5", "42,125"]), &font, &options)?;
+/// let commas: Vec<u32> = rows(&image).iter().map(|number| number.last().unwrap()[0]).collect();
+/// assert_eq!(commas.len(), 3);
+/// assert!(commas.iter().all(|comma| *comma == commas[0]));
+///
+/// // Integers are aligned as if their comma followed their last digit, so they are right aligned
+/// let image = rasterize_number_column(&column(&["7", "1.337", "-27"]), &font, &options)?;
+/// let right: Vec<u32> = rows(&image)
+///     .iter()
+///     .map(|number| number.iter().map(|drawn| *drawn.last().unwrap()).max().unwrap())
+///     .collect();
+/// assert_eq!(right.len(), 3);
+/// assert!(right.iter().all(|edge| *edge == right[0]));
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NumberColumn {
+    /// The numbers of the column, from the top.
+    pub numbers: Vec<String>,
+    /// The character that separates the integer part of a number from its fraction (ex. `,` in German).
+    pub decimal_separator: char,
+}
+
+impl Default for NumberColumn {
+    fn default() -> Self {
+        NumberColumn {
+            numbers: Vec::new(),
+            decimal_separator: '.',
+        }
+    }
+}
+
+/// Rasterize a [`NumberColumn`] to an [`ImageBuffer`], as configured by [`Options`].
+///
+/// Each number is laid out on its own line with tabular figures ([`Options::tabular_figures`]), so that digits in the
+/// same place have the same width, and is moved to the right until its decimal separator lines up with the decimal
+/// separators of the other numbers. The column is as wide as its widest integer part and its widest fraction, so that
+/// integers are right aligned. Numbers are not wrapped, and [`Options::paragraph_spacing`] is added between them.
+pub fn rasterize_number_column(
+    column: &NumberColumn,
+    font: &Font,
+    options: &Options,
+) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>, RasterizeError> {
+    let scale = Scale::uniform(options.size);
+    let metrics = font.v_metrics(scale);
+    let line_height = metrics.ascent - metrics.descent + metrics.line_gap;
+    let fonts: Vec<&Font> = std::iter::once(font)
+        .chain(options.fallback_fonts.iter())
+        .collect();
+    let number_options = Options {
+        tabular_figures: true,
+        wrap_width: None,
+        first_line_indent: 0.0,
+        hanging_indent: 0.0,
+        ..options.clone()
+    };
+
+    // The width of the integer part of each number is the position of its decimal separator on the line
+    let mut warnings = Vec::new();
+    let rows: Vec<_> = column
+        .numbers
+        .iter()
+        .map(|number| {
+            let spans = [Span::from(number.as_str())];
+            let (text, segments) = segments(&spans, font, &number_options);
+            let lines = layout(&text, &segments, &fonts, &number_options);
+            add_warnings(&mut warnings, &text, &lines, &fonts);
+            let width = lines.first().map_or(0.0, |line| line.width);
+            let integer = text
+                .rfind(column.decimal_separator)
+                .and_then(|separator| {
+                    lines
+                        .iter()
+                        .flat_map(|line| &line.glyphs)
+                        .find(|glyph| glyph.cluster == separator)
+                })
+                .map_or(width, |glyph| glyph.x);
+            (lines, segments, integer)
+        })
+        .collect();
+    let integer_width = rows
+        .iter()
+        .map(|(_, _, integer)| *integer)
+        .fold(0.0, f32::max);
+
+    let mut glyphs = Vec::new();
+    let mut y = metrics.ascent;
+    for (lines, segments, integer) in &rows {
+        glyphs.extend(position_glyphs(
+            lines,
+            segments,
+            &fonts,
+            point(integer_width - integer, y),
+            &number_options,
+        )?);
+        y += lines.last().map_or(0.0, |line| line.y) + line_height + options.paragraph_spacing;
+    }
+
+    let image = draw(&glyphs, &[], options)?;
+    strict::check(image.dimensions(), &glyphs, &warnings, font, options)?;
+    Ok(image)
+}
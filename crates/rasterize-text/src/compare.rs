@@ -0,0 +1,164 @@
+//! Compare two renderings of a text, with the pixels that are different highlighted, to review rendering changes.
+
+use crate::card::over;
+use crate::{check_size, rasterize_with_options, Color, Options, RasterizeError};
+use image::{ImageBuffer, Rgba, RgbaImage};
+use rusttype::Font;
+
+/// How a comparison is drawn by [`compare_images`] and [`rasterize_comparison`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct CompareStyle {
+    /// The color of the pixels that are different, which is magenta by default.
+    pub highlight: Color,
+    /// The largest difference of a channel (from 0 to 255) that pixels can have and still be the same, which ignores
+    /// small differences (ex. from rounding) if it is more than 0.
+    pub threshold: u8,
+    /// The space between the images of the comparison in pixels.
+    pub gap: u32,
+}
+
+impl Default for CompareStyle {
+    fn default() -> Self {
+        CompareStyle {
+            highlight: Color {
+                r: 255,
+                g: 0,
+                b: 255,
+                a: 255,
+            },
+            threshold: 0,
+            gap: 16,
+        }
+    }
+}
+
+/// The result of a comparison of two images, from [`compare_images`] and [`rasterize_comparison`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Comparison {
+    /// The first image, the second image, and their differences, side by side.
+    pub image: ImageBuffer<Rgba<u8>, Vec<u8>>,
+    /// The number of pixels that are different, including the pixels where only one of the images is.
+    pub changed_pixels: u64,
+}
+
+impl Comparison {
+    /// Whether the images are the same, within the [`CompareStyle::threshold`].
+    pub fn is_identical(&self) -> bool {
+        self.changed_pixels == 0
+    }
+}
+
+/// Compare two images of rasterized text, and draw them side by side with their differences.
+///
+/// The images are aligned at their top left corners, and the third image has the pixels that are different in the
+/// [`CompareStyle::highlight`] color, over a faded copy of the second image. The colors of the images are
+/// premultiplied by their alpha, like the images of rasterized text (images that are read from PNG files have to
+/// be premultiplied first).
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{compare_images, rasterize, Color, CompareStyle, EN_FONT, read_font_bytes};
+///
+/// let font   = read_font_bytes(EN_FONT)?;
+/// let before = rasterize(&"Hello, World!", &font, 50.0, &Color::default())?;
+/// let after  = rasterize(&"Hello, World?", &font, 50.0, &Color::default())?;
+///
+/// let comparison = compare_images(&before, &after, &CompareStyle::default())?;
+/// comparison.image.save("compare_images.png")?;
+/// assert!(!comparison.is_identical());
+/// assert!(compare_images(&before, &before, &CompareStyle::default())?.is_identical());
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+pub fn compare_images(
+    a: &ImageBuffer<Rgba<u8>, Vec<u8>>,
+    b: &ImageBuffer<Rgba<u8>, Vec<u8>>,
+    style: &CompareStyle,
+) -> Result<Comparison, RasterizeError> {
+    let (width, height) = (a.width().max(b.width()), a.height().max(b.height()));
+    // The size is only limited by the size of an image buffer, since the images were already allocated
+    let unlimited = Options {
+        max_width: u32::MAX,
+        max_height: u32::MAX,
+        max_pixels: u64::MAX,
+        ..Default::default()
+    };
+    let (total_width, _) = check_size(
+        u64::from(a.width()) + u64::from(b.width()) + u64::from(width) + 2 * u64::from(style.gap),
+        u64::from(height),
+        &unlimited,
+    )?;
+
+    let alpha = f32::from(style.highlight.a) / 255.0;
+    let highlight = Rgba([
+        (f32::from(style.highlight.r) * alpha).round() as u8,
+        (f32::from(style.highlight.g) * alpha).round() as u8,
+        (f32::from(style.highlight.b) * alpha).round() as u8,
+        style.highlight.a,
+    ]);
+    let mut differences = RgbaImage::new(width, height);
+    let mut changed_pixels = 0;
+    for (x, y, pixel) in differences.enumerate_pixels_mut() {
+        let blank = Rgba([0, 0, 0, 0]);
+        let before = a.get_pixel_checked(x, y).unwrap_or(&blank);
+        let after = b.get_pixel_checked(x, y).unwrap_or(&blank);
+        let difference = (0..4)
+            .map(|c| before[c].abs_diff(after[c]))
+            .max()
+            .unwrap_or_default();
+        *pixel = match difference > style.threshold {
+            true => {
+                changed_pixels += 1;
+                highlight
+            }
+            // Pixels that are the same are faded, so that the highlighted pixels stand out
+            false => Rgba(after.0.map(|channel| channel / 4)),
+        };
+    }
+
+    let mut image = RgbaImage::new(total_width, height);
+    let gap = i64::from(style.gap);
+    over(&mut image, a, 0, 0);
+    over(&mut image, b, i64::from(a.width()) + gap, 0);
+    over(
+        &mut image,
+        &differences,
+        i64::from(a.width()) + i64::from(b.width()) + 2 * gap,
+        0,
+    );
+    Ok(Comparison {
+        image,
+        changed_pixels,
+    })
+}
+
+/// Rasterize a text with two sets of [`Options`], and compare them with [`compare_images`].
+///
+/// This shows how changes to the options (ex. [`Options::pixel_snap`] or [`Options::blending`]) change the pixels
+/// of the text, before they are used (ex. in a golden image test).
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{rasterize_comparison, CompareStyle, Options, PixelSnap, EN_FONT, read_font_bytes};
+///
+/// let font  = read_font_bytes(EN_FONT)?;
+/// let a     = Options { size: 16.0, ..Default::default() };
+/// let b     = Options { pixel_snap: PixelSnap::Advances, ..a.clone() };
+///
+/// let comparison = rasterize_comparison("Hamburgefonstiv", &font, &a, &b, &CompareStyle::default())?;
+/// comparison.image.save("rasterize_comparison.png")?;
+/// assert!(comparison.changed_pixels > 0);
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+pub fn rasterize_comparison(
+    text: &str,
+    font: &Font,
+    a: &Options,
+    b: &Options,
+    style: &CompareStyle,
+) -> Result<Comparison, RasterizeError> {
+    let before = rasterize_with_options(text, font, a)?;
+    let after = rasterize_with_options(text, font, b)?;
+    compare_images(&before, &after, style)
+}
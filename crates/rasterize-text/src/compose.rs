@@ -0,0 +1,283 @@
+//! Arrange rasterized text and images in rows, columns and grids, and composite them into one image (ex. for cards).
+
+use crate::card::{over, Anchor};
+use crate::{check_size, Options, RasterizeError, RasterizedText};
+use image::{ImageBuffer, Rgba, RgbaImage};
+
+/// How the elements of a [`Row`] or a [`Column`] are aligned across it, when they are smaller than the largest element.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum CrossAlign {
+    /// Aligned at the top of a row, or the left of a column.
+    #[default]
+    Start,
+    /// Centered in a row or a column.
+    Center,
+    /// Aligned at the bottom of a row, or the right of a column.
+    End,
+}
+
+impl CrossAlign {
+    /// Returns the position of the alignment across a row or a column, as a fraction of the space left by an element.
+    fn fraction(self) -> f32 {
+        match self {
+            CrossAlign::Start => 0.0,
+            CrossAlign::Center => 0.5,
+            CrossAlign::End => 1.0,
+        }
+    }
+}
+
+/// Something that is placed in a composition by [`compose`]: an image, empty space, or a group of elements.
+///
+/// Images are premultiplied by their alpha, like the images of rasterized text, and are composited over the elements
+/// before them.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Element {
+    /// An image, such as rasterized text or an icon.
+    Image(ImageBuffer<Rgba<u8>, Vec<u8>>),
+    /// Transparent space of a width and a height in pixels (ex. to indent an element, or to push it to a minimum size).
+    Space(u32, u32),
+    /// Elements side by side, from left to right.
+    Row(Row),
+    /// Elements on top of each other, from top to bottom.
+    Column(Column),
+    /// Elements in the cells of a grid.
+    Grid(Grid),
+}
+
+impl From<ImageBuffer<Rgba<u8>, Vec<u8>>> for Element {
+    fn from(image: ImageBuffer<Rgba<u8>, Vec<u8>>) -> Self {
+        Element::Image(image)
+    }
+}
+
+impl From<RasterizedText> for Element {
+    fn from(text: RasterizedText) -> Self {
+        Element::Image(text.image)
+    }
+}
+
+impl From<Row> for Element {
+    fn from(row: Row) -> Self {
+        Element::Row(row)
+    }
+}
+
+impl From<Column> for Element {
+    fn from(column: Column) -> Self {
+        Element::Column(column)
+    }
+}
+
+impl From<Grid> for Element {
+    fn from(grid: Grid) -> Self {
+        Element::Grid(grid)
+    }
+}
+
+/// Elements placed side by side from left to right, as tall as the tallest element.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Row {
+    /// The elements of the row, from the left.
+    pub elements: Vec<Element>,
+    /// The space between neighbouring elements in pixels.
+    pub spacing: u32,
+    /// How elements that are shorter than the row are aligned vertically.
+    pub align: CrossAlign,
+}
+
+/// Elements placed on top of each other from top to bottom, as wide as the widest element.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Column {
+    /// The elements of the column, from the top.
+    pub elements: Vec<Element>,
+    /// The space between neighbouring elements in pixels.
+    pub spacing: u32,
+    /// How elements that are narrower than the column are aligned horizontally.
+    pub align: CrossAlign,
+}
+
+/// Elements placed in the cells of a grid, row by row from the top left cell.
+///
+/// Each column is as wide as its widest element, and each row is as tall as its tallest element.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Grid {
+    /// The elements of the grid, from the top left cell, which fill each row before the next.
+    pub elements: Vec<Element>,
+    /// The number of columns of the grid, which is at least 1.
+    pub columns: usize,
+    /// The space between neighbouring columns in pixels.
+    pub column_spacing: u32,
+    /// The space between neighbouring rows in pixels.
+    pub row_spacing: u32,
+    /// Where each element is placed within its cell, if it is smaller than the cell.
+    pub anchor: Anchor,
+}
+
+impl Default for Grid {
+    fn default() -> Self {
+        Grid {
+            elements: Vec::new(),
+            columns: 1,
+            column_spacing: 0,
+            row_spacing: 0,
+            anchor: Anchor::TopLeft,
+        }
+    }
+}
+
+impl Grid {
+    /// Returns the widths of the columns and the heights of the rows of the grid.
+    fn tracks(&self) -> (Vec<u64>, Vec<u64>) {
+        let columns = self.columns.max(1);
+        let mut widths = vec![0; columns.min(self.elements.len())];
+        let mut heights = vec![0; self.elements.len().div_ceil(columns)];
+        for (i, element) in self.elements.iter().enumerate() {
+            let (width, height) = element.size();
+            widths[i % columns] = widths[i % columns].max(width);
+            heights[i / columns] = heights[i / columns].max(height);
+        }
+        (widths, heights)
+    }
+}
+
+/// Returns the offsets of tracks (ex. the columns of a grid) of lengths, with spacing between them.
+fn offsets(lengths: &[u64], spacing: u32) -> Vec<u64> {
+    lengths
+        .iter()
+        .scan(0, |offset, length| {
+            let start = *offset;
+            *offset += length + u64::from(spacing);
+            Some(start)
+        })
+        .collect()
+}
+
+/// Returns the total length of tracks of lengths, with spacing between them.
+fn total(lengths: impl ExactSizeIterator<Item = u64>, spacing: u32) -> u64 {
+    let gaps = lengths.len().saturating_sub(1) as u64 * u64::from(spacing);
+    lengths.sum::<u64>() + gaps
+}
+
+/// Returns the offset of an element of a length within a space, at a fraction of the space that is left.
+fn place(space: u64, length: u64, fraction: f32) -> i64 {
+    (space.saturating_sub(length) as f32 * fraction).round() as i64
+}
+
+impl Element {
+    /// Returns the width and height of the element in pixels.
+    pub fn size(&self) -> (u64, u64) {
+        match self {
+            Element::Image(image) => (u64::from(image.width()), u64::from(image.height())),
+            Element::Space(width, height) => (u64::from(*width), u64::from(*height)),
+            Element::Row(row) => {
+                let sizes = row.elements.iter().map(Element::size);
+                let width = total(sizes.clone().map(|(width, _)| width), row.spacing);
+                (width, sizes.map(|(_, height)| height).max().unwrap_or(0))
+            }
+            Element::Column(column) => {
+                let sizes = column.elements.iter().map(Element::size);
+                let height = total(sizes.clone().map(|(_, height)| height), column.spacing);
+                (sizes.map(|(width, _)| width).max().unwrap_or(0), height)
+            }
+            Element::Grid(grid) => {
+                let (widths, heights) = grid.tracks();
+                (
+                    total(widths.into_iter(), grid.column_spacing),
+                    total(heights.into_iter(), grid.row_spacing),
+                )
+            }
+        }
+    }
+
+    /// Composite the element over an image, with its top left corner at `x` and `y`.
+    fn draw(&self, image: &mut RgbaImage, x: i64, y: i64) {
+        match self {
+            Element::Image(element) => over(image, element, x, y),
+            Element::Space(..) => {}
+            Element::Row(row) => {
+                let (_, height) = self.size();
+                let mut left = x;
+                for element in &row.elements {
+                    let (element_width, element_height) = element.size();
+                    let top = y + place(height, element_height, row.align.fraction());
+                    element.draw(image, left, top);
+                    left += (element_width + u64::from(row.spacing)) as i64;
+                }
+            }
+            Element::Column(column) => {
+                let (width, _) = self.size();
+                let mut top = y;
+                for element in &column.elements {
+                    let (element_width, element_height) = element.size();
+                    let left = x + place(width, element_width, column.align.fraction());
+                    element.draw(image, left, top);
+                    top += (element_height + u64::from(column.spacing)) as i64;
+                }
+            }
+            Element::Grid(grid) => {
+                let columns = grid.columns.max(1);
+                let (widths, heights) = grid.tracks();
+                let (lefts, tops) = (
+                    offsets(&widths, grid.column_spacing),
+                    offsets(&heights, grid.row_spacing),
+                );
+                let (fx, fy) = grid.anchor.fractions();
+                for (i, element) in grid.elements.iter().enumerate() {
+                    let (column, row) = (i % columns, i / columns);
+                    let (element_width, element_height) = element.size();
+                    let left = x + lefts[column] as i64 + place(widths[column], element_width, fx);
+                    let top = y + tops[row] as i64 + place(heights[row], element_height, fy);
+                    element.draw(image, left, top);
+                }
+            }
+        }
+    }
+}
+
+/// Composite an [`Element`] (ex. a [`Column`] of rasterized text) into one image, which is as large as the element.
+///
+/// Elements are placed without any coordinates, by the spacing and alignment of the rows, columns and grids that
+/// they are in, and the pixels that no element covers are transparent.
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{compose, rasterize_with_metadata, rasterize_with_options, Column, CrossAlign, Element, Grid, Options, Row, EN_FONT, read_font_bytes};
+///
+/// let font     = read_font_bytes(EN_FONT)?;
+/// let title    = rasterize_with_metadata("Release notes", &font, &Options { size: 40.0, ..Default::default() })?;
+/// let subtitle = rasterize_with_options("Version 1.2.0", &font, &Options { size: 24.0, ..Default::default() })?;
+/// let (title_width, title_height) = title.image.dimensions();
+///
+/// let card = Element::from(Row {
+///     elements: vec![
+///         Element::Space(32, 32),
+///         Column { elements: vec![title.into(), subtitle.into()], spacing: 8, align: CrossAlign::Start }.into(),
+///     ],
+///     spacing: 16,
+///     align: CrossAlign::Center,
+/// });
+/// let image = compose(&card)?;
+/// image.save("compose.png")?;
+/// assert_eq!(u64::from(image.width()), 32 + 16 + u64::from(title_width));
+/// assert!(image.height() > title_height);
+///
+/// let grid  = Grid { elements: vec![Element::Space(10, 20), Element::Space(30, 5), Element::Space(5, 5)], columns: 2, column_spacing: 4, ..Default::default() };
+/// assert_eq!(Element::from(grid).size(), (10 + 4 + 30, 20 + 5));
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+pub fn compose(element: &Element) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>, RasterizeError> {
+    // The size is only limited by the size of an image buffer, since the images were already allocated
+    let unlimited = Options {
+        max_width: u32::MAX,
+        max_height: u32::MAX,
+        max_pixels: u64::MAX,
+        ..Default::default()
+    };
+    let (width, height) = element.size();
+    let (width, height) = check_size(width, height, &unlimited)?;
+    let mut image = RgbaImage::new(width, height);
+    element.draw(&mut image, 0, 0);
+    Ok(image)
+}
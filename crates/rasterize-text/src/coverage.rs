@@ -0,0 +1,213 @@
+//! Draw a chart of the glyphs of a font in a range of Unicode characters, to check which characters it supports.
+
+use crate::background::fill;
+use crate::card::over;
+use crate::effect::apply_to_image;
+use crate::frame;
+use crate::mask::whole_text;
+use crate::{
+    check_size, rasterize_glyph_with_options, rasterize_spans, Blending, Color, GridLayout,
+    Options, RasterizeError, Span,
+};
+use image::{ImageBuffer, Rgba, RgbaImage};
+use rusttype::{Font, Scale};
+use std::ops::RangeInclusive;
+
+/// Number of columns of a [`CoverageChart`], which are the last hexadecimal digit of the characters, like the
+/// charts of the Unicode Standard.
+const COLS: u32 = 16;
+
+/// A range of Unicode characters (ex. a block, such as `'\u{0370}'..='\u{03FF}'` for Greek and Coptic), drawn as a
+/// chart of the glyphs of a font by [`rasterize_coverage`].
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{rasterize_coverage, CoverageChart, Options, EN_FONT, read_font_bytes};
+///
+/// let font  = read_font_bytes(EN_FONT)?;
+/// let chart = CoverageChart { range: '\u{0370}'..='\u{03FF}', ..Default::default() };
+/// let image = rasterize_coverage(&chart, &font, &Options::default())?;
+/// image.save("rasterize_coverage.png")?;
+///
+/// // The font supports every character of Basic Latin, except for the control character U+007F
+/// let basic_latin = CoverageChart::default();
+/// assert_eq!(basic_latin.supported(&font).len(), 95);
+/// assert!(!basic_latin.supported(&font).contains(&'\u{7F}'));
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CoverageChart {
+    /// The characters of the chart, which is Basic Latin (`U+0020` to `U+007F`) by default.
+    pub range: RangeInclusive<char>,
+    /// The color of the codepoint below each glyph.
+    pub label_color: Color,
+    /// The color of the lines between the cells, if any.
+    pub line_color: Option<Color>,
+    /// The color that the cells of characters that the font doesn't support are shaded with, if any.
+    pub missing_color: Option<Color>,
+}
+
+impl Default for CoverageChart {
+    fn default() -> Self {
+        CoverageChart {
+            range: ' '..='\u{7F}',
+            label_color: Color {
+                r: 128,
+                g: 128,
+                b: 128,
+                a: 255,
+            },
+            line_color: Some(Color {
+                r: 204,
+                g: 204,
+                b: 204,
+                a: 255,
+            }),
+            missing_color: Some(Color {
+                r: 235,
+                g: 235,
+                b: 235,
+                a: 255,
+            }),
+        }
+    }
+}
+
+impl CoverageChart {
+    /// Returns the characters of the chart that the font has a glyph for.
+    pub fn supported(&self, font: &Font) -> Vec<char> {
+        self.range
+            .clone()
+            .filter(|c| font.glyph(*c).id().0 != 0)
+            .collect()
+    }
+}
+
+/// Rasterize a chart of the glyphs of a font in a range of characters to an [`ImageBuffer`], as configured by
+/// [`Options`].
+///
+/// The characters are laid out on a grid of 16 columns, like the charts of the Unicode Standard, so that each row
+/// has the characters from `U+XXX0` to `U+XXXF`. Each glyph is drawn at the [`Options::size`] in the
+/// [`Options::color`] and centered in its cell (see [`rasterize_glyph_with_options`]), with its codepoint below it.
+/// The cells of characters that the font doesn't support are shaded instead, and the cells before and after the
+/// range are left empty. The background, effects without a byte range, corners and border of the [`Options`] are
+/// drawn around the whole chart.
+pub fn rasterize_coverage(
+    chart: &CoverageChart,
+    font: &Font,
+    options: &Options,
+) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>, RasterizeError> {
+    // The background, whole image effects and border are drawn once, around the whole chart
+    let parts = Options {
+        wrap_width: None,
+        line_numbers: None,
+        mask: whole_text(options.mask.as_ref()),
+        effects: Vec::new(),
+        selection: None,
+        background: None,
+        corner_radius: 0.0,
+        border: None,
+        ..options.clone()
+    };
+    let label_options = Options {
+        size: (parts.size / 4.0).max(8.0),
+        color: chart.label_color,
+        ..parts.clone()
+    };
+
+    let (start, end) = (
+        u32::from(*chart.range.start()),
+        u32::from(*chart.range.end()),
+    );
+    let (first_row, last_row) = (start / COLS, end / COLS);
+    let rows = match start <= end {
+        true => u64::from(last_row - first_row) + 1,
+        false => 0,
+    };
+
+    // Cells are as tall as a line of the glyphs and a line of the labels, and at least as wide as they are tall
+    let glyph_height = GridLayout::new(1, 1, font, parts.size).cell_h.ceil() as u64;
+    let label_height = GridLayout::new(1, 1, font, label_options.size)
+        .cell_h
+        .ceil() as u64;
+    let digits = format!("{end:04X}").len();
+    let scale = Scale::uniform(label_options.size);
+    let label_width = "0123456789ABCDEF"
+        .chars()
+        .map(|digit| font.glyph(digit).scaled(scale).h_metrics().advance_width)
+        .fold(0.0, f32::max);
+    let label_width = (label_width * digits as f32).ceil() as u64;
+    let cell_width = glyph_height.max(label_width + label_height);
+    let cell_height = glyph_height + label_height;
+    // Cells are separated by lines of one pixel, which are around the chart too
+    let (width, height) = check_size(
+        u64::from(COLS) * (cell_width + 1) + 1,
+        rows * (cell_height + 1) + 1,
+        &parts,
+    )?;
+    let mut canvas = RgbaImage::new(width, height);
+    let cell = |c: char| {
+        let i = u32::from(c) - first_row * COLS;
+        let x = u64::from(i % COLS) * (cell_width + 1) + 1;
+        let y = u64::from(i / COLS) * (cell_height + 1) + 1;
+        (x as u32, y as u32)
+    };
+
+    if let Some(color) = chart.line_color {
+        let line = premultiply(color);
+        for (x, y, pixel) in canvas.enumerate_pixels_mut() {
+            if u64::from(x) % (cell_width + 1) == 0 || u64::from(y) % (cell_height + 1) == 0 {
+                *pixel = line;
+            }
+        }
+    }
+    for c in chart.range.clone() {
+        let (x, y) = cell(c);
+        let supported = font.glyph(c).id().0 != 0;
+        if let (false, Some(color)) = (supported, chart.missing_color) {
+            let shade = premultiply(color);
+            for j in y..y + cell_height as u32 {
+                for i in x..x + cell_width as u32 {
+                    canvas.put_pixel(i, j, shade);
+                }
+            }
+        }
+        if supported {
+            let glyph = rasterize_glyph_with_options(font, c, &parts)?;
+            over(
+                &mut canvas,
+                &glyph,
+                i64::from(x) + (cell_width as i64 - i64::from(glyph.width())) / 2,
+                i64::from(y) + (glyph_height as i64 - i64::from(glyph.height())) / 2,
+            );
+        }
+        let label = Span::from(format!("{:04X}", u32::from(c)).as_str());
+        let label = rasterize_spans(&[label], font, &label_options)?;
+        over(
+            &mut canvas,
+            &label,
+            i64::from(x) + (cell_width as i64 - i64::from(label.width())) / 2,
+            i64::from(y)
+                + glyph_height as i64
+                + (label_height as i64 - i64::from(label.height())) / 2,
+        );
+    }
+    if let Some(background) = &options.background {
+        fill(&mut canvas, background, 0..width, 0..height, Blending::Srgb);
+    }
+    apply_to_image(&mut canvas, options);
+
+    frame::apply(canvas, options)
+}
+
+/// Returns a [`Color`] as a pixel whose color is premultiplied by its alpha, like the pixels of glyphs.
+fn premultiply(color: Color) -> Rgba<u8> {
+    let alpha = f32::from(color.a) / 255.0;
+    Rgba([
+        (f32::from(color.r) * alpha).round() as u8,
+        (f32::from(color.g) * alpha).round() as u8,
+        (f32::from(color.b) * alpha).round() as u8,
+        color.a,
+    ])
+}
@@ -0,0 +1,215 @@
+//! Rasterize text again after it changes, only redrawing the pixels of glyphs that changed.
+
+use crate::background::fill;
+use crate::span::position_spans;
+use crate::{draw, draw_glyph, measure, Blending, Color, Ink, Options, RasterizeError, Span};
+use image::{ImageBuffer, Rgba};
+use rusttype::{Font, PositionedGlyph};
+
+/// A rectangle of pixels in an image that was redrawn by [`rasterize_changes`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct DirtyRect {
+    /// The left edge of the rectangle in pixels.
+    pub x: u32,
+    /// The top edge of the rectangle in pixels.
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl DirtyRect {
+    /// Returns the rectangle of pixels that a glyph is drawn to, if it has any pixels.
+    ///
+    /// Glyphs that start at a negative coordinate are drawn from zero (ex. the letter 'T' starting at x=-2).
+    pub(crate) fn of(glyph: &PositionedGlyph) -> Option<DirtyRect> {
+        let bounding_box = glyph.pixel_bounding_box()?;
+        Some(DirtyRect {
+            x: u32::try_from(bounding_box.min.x.max(0)).unwrap_or_default(),
+            y: u32::try_from(bounding_box.min.y.max(0)).unwrap_or_default(),
+            width: u32::try_from(bounding_box.width()).unwrap_or_default(),
+            height: u32::try_from(bounding_box.height()).unwrap_or_default(),
+        })
+    }
+
+    /// Returns whether the rectangles share any pixels.
+    fn intersects(&self, other: &DirtyRect) -> bool {
+        let right = |rect: &DirtyRect| u64::from(rect.x) + u64::from(rect.width);
+        let bottom = |rect: &DirtyRect| u64::from(rect.y) + u64::from(rect.height);
+        u64::from(self.x) < right(other)
+            && u64::from(other.x) < right(self)
+            && u64::from(self.y) < bottom(other)
+            && u64::from(other.y) < bottom(self)
+    }
+
+    /// Returns the smallest rectangle that contains both rectangles.
+    fn union(&self, other: &DirtyRect) -> DirtyRect {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        let right = (self.x.saturating_add(self.width)).max(other.x.saturating_add(other.width));
+        let bottom = (self.y.saturating_add(self.height)).max(other.y.saturating_add(other.height));
+        DirtyRect {
+            x,
+            y,
+            width: right - x,
+            height: bottom - y,
+        }
+    }
+
+    /// Returns the rectangle, cropped to an image of `width` by `height` pixels.
+    fn crop(&self, width: u32, height: u32) -> DirtyRect {
+        let x = self.x.min(width);
+        let y = self.y.min(height);
+        DirtyRect {
+            x,
+            y,
+            width: self.width.min(width - x),
+            height: self.height.min(height - y),
+        }
+    }
+}
+
+/// Rasterize text to an image of the previous text, as configured by [`Options`], and return the rectangles that changed.
+///
+/// Both texts are laid out, and only the pixels of glyphs that moved or changed are cleared and drawn again.
+/// This is useful for text where only a few characters change (ex. a clock or a scoreboard), so that the image
+/// and the display it is copied to can be updated without redrawing the whole text.
+///
+/// The image must have been rasterized from `previous` with the same font and [`Options`]
+/// (ex. with [`rasterize_with_options`](crate::rasterize_with_options)). If the size of the image changes,
+/// or the image has rounded corners or a border, the whole image is rasterized again and returned as a single rectangle.
+/// Overlapping rectangles are merged.
+///
+/// # Arguments
+///
+/// - `previous`: The text that was rasterized to the `image`.
+/// - `text`: The new text to rasterize.
+/// - `image`: An [`ImageBuffer`] with the pixels of the previous text, which is updated to the new text.
+/// - `font`: A [`Font`] reference that contains [TrueType](https://en.wikipedia.org/wiki/TrueType) data.
+/// - `options`: The [`Options`] that the previous text was rasterized with.
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{DirtyRect, Options, rasterize_changes, rasterize_with_options, EN_FONT, read_font_bytes};
+///
+/// let font      = read_font_bytes(EN_FONT)?;
+/// let options   = Options { size: 40.0, ..Default::default() };
+/// let mut image = rasterize_with_options(&"12:59", &font, &options)?;
+///
+/// // The digits of DejaVu Sans have the same width, so only the changed digits are redrawn
+/// let dirty = rasterize_changes("12:59", "12:58", &mut image, &font, &options)?;
+/// assert_eq!(dirty.len(), 1);
+/// assert!(dirty[0].x > image.width() / 2);
+/// assert_eq!(image, rasterize_with_options(&"12:58", &font, &options)?);
+///
+/// let dirty = rasterize_changes("12:58", "12:58", &mut image, &font, &options)?;
+/// assert_eq!(dirty, Vec::<DirtyRect>::new());
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+pub fn rasterize_changes(
+    previous: &str,
+    text: &str,
+    image: &mut ImageBuffer<Rgba<u8>, Vec<u8>>,
+    font: &Font,
+    options: &Options,
+) -> Result<Vec<DirtyRect>, RasterizeError> {
+    let (old, _, _) = position_spans(&[Span::from(previous)], font, options)?;
+    let (new, _, _) = position_spans(&[Span::from(text)], font, options)?;
+
+    // Framed images are offset by their border, and their corners are cut out over the glyphs
+    // Images that are blended in linear light are converted to sRGB as a whole
+    let (width, height) = measure(&new, &[]);
+    let framed = options.corner_radius > 0.0 || options.border.is_some();
+    let linear = options.blending == Blending::Linear;
+    if framed || linear || (width, height) != (u64::from(image.width()), u64::from(image.height()))
+    {
+        *image = draw(&new, &[], options)?;
+        let rect = DirtyRect {
+            x: 0,
+            y: 0,
+            width: image.width(),
+            height: image.height(),
+        };
+        return Ok(Some(rect)
+            .filter(|rect| rect.width > 0 && rect.height > 0)
+            .into_iter()
+            .collect());
+    }
+
+    // Glyphs that are in both texts, at the same position, are already drawn
+    type Glyph<'f> = (PositionedGlyph<'f>, Color, Ink);
+    let changed = |glyphs: &[Glyph], others: &[Glyph]| {
+        glyphs
+            .iter()
+            .filter(|glyph| !others.iter().any(|other| same(glyph, other)))
+            .filter_map(|(glyph, ..)| DirtyRect::of(glyph))
+            .collect::<Vec<_>>()
+    };
+    let mut dirty = changed(&old, &new);
+    dirty.extend(changed(&new, &old));
+    let dirty = merge(dirty)
+        .into_iter()
+        .map(|rect| rect.crop(image.width(), image.height()))
+        .filter(|rect| rect.width > 0 && rect.height > 0)
+        .collect::<Vec<_>>();
+    log::debug!("Dirty rectangles: {dirty:?}");
+
+    // Clear the changed pixels, and draw the glyphs that cover them again in order,
+    // so that they overlap in the same way as when all of the text is drawn
+    for rect in &dirty {
+        for y in rect.y..rect.y + rect.height {
+            for x in rect.x..rect.x + rect.width {
+                image.put_pixel(x, y, Rgba([0, 0, 0, 0]));
+            }
+        }
+    }
+    for (glyph, color, ink) in &new {
+        if DirtyRect::of(glyph).is_some_and(|rect| dirty.iter().any(|d| d.intersects(&rect))) {
+            draw_glyph(image, glyph, color, *ink, options);
+        }
+    }
+    // The background is drawn behind the glyphs, since glyphs are not drawn over opaque pixels
+    if let Some(background) = &options.background {
+        for rect in &dirty {
+            let columns = rect.x..rect.x + rect.width;
+            fill(
+                image,
+                background,
+                columns,
+                rect.y..rect.y + rect.height,
+                Blending::Srgb,
+            );
+        }
+    }
+
+    Ok(dirty)
+}
+
+/// Returns whether two positioned glyphs are drawn to the same pixels in the same color.
+///
+/// Glyphs of different fonts are told apart by their outlines' bounding box and advance,
+/// since [`Font`] has no identity to compare.
+fn same(a: &(PositionedGlyph, Color, Ink), b: &(PositionedGlyph, Color, Ink)) -> bool {
+    let ((a, a_color, a_ink), (b, b_color, b_ink)) = (a, b);
+    a.id() == b.id()
+        && a_ink == b_ink
+        && a.position() == b.position()
+        && a.scale() == b.scale()
+        && a_color == b_color
+        && a.unpositioned().exact_bounding_box() == b.unpositioned().exact_bounding_box()
+        && a.unpositioned().h_metrics() == b.unpositioned().h_metrics()
+}
+
+/// Merge rectangles that overlap, until none of the rectangles overlap.
+fn merge(mut rects: Vec<DirtyRect>) -> Vec<DirtyRect> {
+    let mut merged: Vec<DirtyRect> = Vec::new();
+    while let Some(mut rect) = rects.pop() {
+        // Merging can make a rectangle overlap one that was already merged, so those are checked again
+        while let Some(i) = merged.iter().position(|other| other.intersects(&rect)) {
+            rect = rect.union(&merged.swap_remove(i));
+        }
+        merged.push(rect);
+    }
+    merged.sort_by_key(|rect| (rect.y, rect.x));
+    merged
+}
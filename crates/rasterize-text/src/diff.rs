@@ -0,0 +1,343 @@
+//! Draw the differences between two texts, with insertions and deletions highlighted.
+
+use crate::span::position_spans;
+use crate::{
+    draw, measure, rasterize_spans, strict, Color, Options, OptionsError, RasterizeError, Span,
+    Style,
+};
+use image::{ImageBuffer, Rgba};
+use rusttype::{point, Font};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// How the old and new text of a diff are arranged, by [`rasterize_diff`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum DiffLayout {
+    /// One text, where deleted text is followed by the text that was inserted in its place.
+    #[default]
+    Unified,
+    /// The old text on the left with its deletions, and the new text on the right with its insertions.
+    /// Lines that are in both texts are aligned by adding blank lines.
+    SideBySide,
+}
+
+impl Display for DiffLayout {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        let lowercase = format!("{:?}", self).to_lowercase();
+        write!(f, "{lowercase}")
+    }
+}
+
+impl FromStr for DiffLayout {
+    type Err = OptionsError;
+
+    /// Returns a [`DiffLayout`] converted from a [`str`].
+    fn from_str(layout: &str) -> Result<Self, Self::Err> {
+        let layout = match layout {
+            "unified" => DiffLayout::Unified,
+            "sidebyside" => DiffLayout::SideBySide,
+            _ => Err(OptionsError::UnknownDiffLayoutError(layout.to_string()))?,
+        };
+
+        Ok(layout)
+    }
+}
+
+/// How a diff is drawn by [`rasterize_diff`].
+///
+/// By default, inserted text is green on a light green background, and deleted text is red on a light red background
+/// and struck through, like the diffs of code review tools.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct DiffStyle {
+    /// How the old and new text are arranged.
+    pub layout: DiffLayout,
+    /// The style of text that is only in the new text.
+    pub inserted: Style,
+    /// The style of text that is only in the old text.
+    pub deleted: Style,
+    /// The space between the old and new text of a side-by-side diff in pixels.
+    pub gap: u32,
+}
+
+impl Default for DiffStyle {
+    fn default() -> Self {
+        DiffStyle {
+            layout: DiffLayout::default(),
+            inserted: Style {
+                color: Some(Color {
+                    r: 26,
+                    g: 127,
+                    b: 55,
+                    a: 255,
+                }),
+                background: Some(Color {
+                    r: 204,
+                    g: 255,
+                    b: 216,
+                    a: 255,
+                }),
+                ..Default::default()
+            },
+            deleted: Style {
+                color: Some(Color {
+                    r: 207,
+                    g: 34,
+                    b: 46,
+                    a: 255,
+                }),
+                background: Some(Color {
+                    r: 255,
+                    g: 215,
+                    b: 213,
+                    a: 255,
+                }),
+                strikethrough: true,
+                ..Default::default()
+            },
+            gap: 20,
+        }
+    }
+}
+
+/// Whether a token of a diff is in both texts, or only in one of them.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Change {
+    Equal,
+    Delete,
+    Insert,
+}
+
+/// Compare two texts, and return the spans of a unified diff between them (see [`DiffLayout::Unified`]).
+///
+/// The texts are compared by words, runs of whitespace, and other characters (ex. punctuation and line breaks),
+/// and the text that is only in one of them has the inserted or deleted [`Style`] of the [`DiffStyle`].
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{diff_spans, DiffStyle};
+///
+/// let style = DiffStyle::default();
+/// let spans = diff_spans("let x = 1;", "let y = 1;", &style);
+/// let text: Vec<&str> = spans.iter().map(|span| span.text.as_str()).collect();
+/// assert_eq!(text, ["let ", "x", "y", " = 1;"]);
+/// assert_eq!(spans[1].style, style.deleted);
+/// assert_eq!(spans[2].style, style.inserted);
+/// ```
+pub fn diff_spans(old: &str, new: &str, style: &DiffStyle) -> Vec<Span> {
+    let mut spans = Vec::new();
+    for (change, token) in diff(&tokens(old), &tokens(new)) {
+        let style = match change {
+            Change::Equal => Style::default(),
+            Change::Delete => style.deleted,
+            Change::Insert => style.inserted,
+        };
+        push(&mut spans, token, style);
+    }
+    spans
+}
+
+/// Compare two texts, and rasterize the differences between them to an [`ImageBuffer`], as configured by [`Options`].
+///
+/// The texts are compared as with [`diff_spans`], and arranged by the [`DiffStyle::layout`]. The text that is in both
+/// texts is drawn in the [`Options::color`], and the other options apply to the old and new text of a side-by-side
+/// diff separately (ex. each side is wrapped at the [`Options::wrap_width`]).
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{rasterize_diff, DiffLayout, DiffStyle, Options, EN_FONT, read_font_bytes};
+///
+/// let old  = "fn main() {\n    println!(\"Hello, World!\");\n}";
+/// let new  = "fn main() {\n    let name = \"World\";\n    println!(\"Hello, {name}!\");\n}";
+/// let font = read_font_bytes(EN_FONT)?;
+///
+/// let unified = rasterize_diff(old, new, &font, &DiffStyle::default(), &Options::default())?;
+/// unified.save("rasterize_diff.png")?;
+///
+/// let style = DiffStyle { layout: DiffLayout::SideBySide, ..Default::default() };
+/// let side_by_side = rasterize_diff(old, new, &font, &style, &Options::default())?;
+/// side_by_side.save("rasterize_diff_side_by_side.png")?;
+/// // The closing braces are aligned, so both sides are as tall as the new text
+/// assert_eq!(side_by_side.height(), unified.height());
+/// assert!(side_by_side.width() > unified.width());
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+pub fn rasterize_diff(
+    old: &str,
+    new: &str,
+    font: &Font,
+    style: &DiffStyle,
+    options: &Options,
+) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>, RasterizeError> {
+    if style.layout == DiffLayout::Unified {
+        return rasterize_spans(&diff_spans(old, new, style), font, options);
+    }
+
+    let (mut left, mut right) = (Vec::new(), Vec::new());
+    // The lines of each side since the last line break that is in both texts
+    let (mut old_lines, mut new_lines) = (Vec::new(), Vec::new());
+    for (change, token) in diff(&tokens(old), &tokens(new)) {
+        match change {
+            Change::Equal if token == "\n" => {
+                align(&mut left, &mut old_lines, &mut right, &mut new_lines);
+                push(&mut left, token, Style::default());
+                push(&mut right, token, Style::default());
+            }
+            Change::Equal => {
+                push(&mut old_lines, token, Style::default());
+                push(&mut new_lines, token, Style::default());
+            }
+            Change::Delete => push(&mut old_lines, token, style.deleted),
+            Change::Insert => push(&mut new_lines, token, style.inserted),
+        }
+    }
+    align(&mut left, &mut old_lines, &mut right, &mut new_lines);
+
+    let (mut glyphs, mut decorations, mut warnings) = position_spans(&left, font, options)?;
+    let (right_glyphs, right_decorations, right_warnings) = position_spans(&right, font, options)?;
+    warnings.extend(right_warnings);
+    // The new text is moved to the right of the old text by whole pixels, to keep its glyphs snapped
+    let (width, _) = measure(&glyphs, &decorations);
+    let offset = (width + u64::from(style.gap)).min(i32::MAX as u64) as i32;
+    glyphs.extend(right_glyphs.into_iter().map(|(glyph, color, ink)| {
+        let position = glyph.position();
+        let position = point(position.x + offset as f32, position.y);
+        (glyph.into_unpositioned().positioned(position), color, ink)
+    }));
+    decorations.extend(right_decorations.into_iter().map(|mut decoration| {
+        decoration.rect.min.x = decoration.rect.min.x.saturating_add(offset);
+        decoration.rect.max.x = decoration.rect.max.x.saturating_add(offset);
+        decoration
+    }));
+    let image = draw(&glyphs, &decorations, options)?;
+    strict::check(image.dimensions(), &glyphs, &warnings, font, options)?;
+    Ok(image)
+}
+
+/// Move the lines of each side of a side-by-side diff to the side, after padding the side with fewer lines
+/// with blank lines, so that the lines after them are aligned.
+fn align(
+    left: &mut Vec<Span>,
+    old_lines: &mut Vec<Span>,
+    right: &mut Vec<Span>,
+    new_lines: &mut Vec<Span>,
+) {
+    let count = |lines: &[Span]| -> usize {
+        lines
+            .iter()
+            .map(|span| span.text.matches('\n').count())
+            .sum()
+    };
+    let (old_count, new_count) = (count(old_lines), count(new_lines));
+    for (side, lines, padding) in [
+        (left, old_lines, new_count.saturating_sub(old_count)),
+        (right, new_lines, old_count.saturating_sub(new_count)),
+    ] {
+        push(side, &"\n".repeat(padding), Style::default());
+        for span in lines.drain(..) {
+            push(side, &span.text, span.style);
+        }
+    }
+}
+
+/// Add text to the last span if it has the same style, or otherwise to a new span.
+fn push(spans: &mut Vec<Span>, text: &str, style: Style) {
+    if text.is_empty() {
+        return;
+    }
+    match spans.last_mut() {
+        Some(span) if span.style == style => span.text.push_str(text),
+        _ => spans.push(Span {
+            text: text.to_string(),
+            style,
+            ruby: None,
+            image: None,
+        }),
+    }
+}
+
+/// Split text into the tokens that are compared, which are words, runs of whitespace, and each other character.
+///
+/// Line breaks are their own tokens, so that the lines of a side-by-side diff can be aligned.
+fn tokens(text: &str) -> Vec<&str> {
+    let kind = |c: char| match c {
+        '\n' => 0,
+        _ if c.is_alphanumeric() || c == '_' => 1,
+        _ if c.is_whitespace() => 2,
+        _ => 0,
+    };
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let end = i + c.len_utf8();
+        let joined = chars
+            .peek()
+            .is_some_and(|(_, next)| kind(c) != 0 && kind(*next) == kind(c));
+        if !joined {
+            tokens.push(&text[start..end]);
+            start = end;
+        }
+    }
+    tokens
+}
+
+/// Returns the shortest edit script between two sequences of tokens, using the algorithm of
+/// [Myers (1986)](http://www.xmailserver.org/diff2.pdf), with deletions before insertions where they are interchangeable.
+fn diff<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<(Change, &'a str)> {
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    let max = n + m;
+    // The furthest x of each diagonal k = x - y, and the furthest x of the diagonals -d..=d before each step d
+    let mut v = vec![0; 2 * max as usize + 3];
+    let index = |k: isize| (k + max + 1) as usize;
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+    'search: for d in 0..=max {
+        trace.push(v[index(-d)..=index(d)].to_vec());
+        for k in (-d..=d).step_by(2) {
+            let mut x = match k == -d || (k != d && v[index(k - 1)] < v[index(k + 1)]) {
+                true => v[index(k + 1)],
+                false => v[index(k - 1)] + 1,
+            };
+            let mut y = x - k;
+            while x < n && y < m && old[x as usize] == new[y as usize] {
+                (x, y) = (x + 1, y + 1);
+            }
+            v[index(k)] = x;
+            if x >= n && y >= m {
+                break 'search;
+            }
+        }
+    }
+
+    // Follow the furthest paths back from the end of both sequences
+    let mut changes = Vec::new();
+    let (mut x, mut y) = (n, m);
+    for (d, v) in trace.iter().enumerate().rev() {
+        let d = d as isize;
+        let at = |k: isize| v[(k + d) as usize];
+        let k = x - y;
+        let previous = match k == -d || (k != d && at(k - 1) < at(k + 1)) {
+            true => k + 1,
+            false => k - 1,
+        };
+        let previous_x = match d {
+            0 => 0,
+            _ => at(previous),
+        };
+        let previous_y = previous_x - previous;
+        while x > previous_x && y > previous_y {
+            changes.push((Change::Equal, old[x as usize - 1]));
+            (x, y) = (x - 1, y - 1);
+        }
+        if d > 0 {
+            match x == previous_x {
+                true => changes.push((Change::Insert, new[y as usize - 1])),
+                false => changes.push((Change::Delete, old[x as usize - 1])),
+            }
+        }
+        (x, y) = (previous_x, previous_y);
+    }
+    changes.reverse();
+    changes
+}
@@ -0,0 +1,106 @@
+//! Post-process the pixels of rasterized text with filters.
+
+use crate::channel::Channel;
+use crate::{Filter, Options};
+use image::{imageops, GenericImageView, ImageBuffer, Pixel, Rgba};
+use std::ops::Range;
+
+/// Apply a [`Filter`] to a rectangle of pixels of an [`ImageBuffer`], as its columns and rows.
+///
+/// Pixels are premultiplied (see [`Channel`]), so colors are changed relative to their alpha.
+pub(crate) fn apply<C: Channel>(
+    image_buffer: &mut ImageBuffer<Rgba<C>, Vec<C>>,
+    filter: Filter,
+    columns: Range<u32>,
+    rows: Range<u32>,
+) where
+    Rgba<C>: Pixel<Subpixel = C>,
+{
+    if columns.is_empty() || rows.is_empty() {
+        return;
+    }
+    match filter {
+        Filter::Blur(sigma) => {
+            let region = image_buffer
+                .view(
+                    columns.start,
+                    rows.start,
+                    columns.len() as u32,
+                    rows.len() as u32,
+                )
+                .to_image();
+            imageops::replace(
+                image_buffer,
+                &imageops::blur(&region, sigma),
+                i64::from(columns.start),
+                i64::from(rows.start),
+            );
+        }
+        Filter::Pixelate(size) => {
+            // Blocks are aligned to the corner of the rectangle, and blocks at its edges are cut short
+            let size = size.max(1) as usize;
+            for block_y in rows.clone().step_by(size) {
+                for block_x in columns.clone().step_by(size) {
+                    let block_columns = block_x..(block_x + size as u32).min(columns.end);
+                    let block_rows = block_y..(block_y + size as u32).min(rows.end);
+                    let mut sum = [0u64; 4];
+                    for y in block_rows.clone() {
+                        for x in block_columns.clone() {
+                            let pixel = image_buffer.get_pixel(x, y);
+                            sum.iter_mut()
+                                .zip(pixel.0)
+                                .for_each(|(sum, channel)| *sum += channel.to_f32() as u64);
+                        }
+                    }
+                    let count = (block_columns.len() * block_rows.len()) as u64;
+                    let average =
+                        Rgba(sum.map(|sum| C::from_f32(((sum + count / 2) / count) as f32)));
+                    for y in block_rows.clone() {
+                        for x in block_columns.clone() {
+                            image_buffer.put_pixel(x, y, average);
+                        }
+                    }
+                }
+            }
+        }
+        Filter::Invert | Filter::Desaturate => {
+            for y in rows {
+                for x in columns.clone() {
+                    let pixel = image_buffer.get_pixel_mut(x, y);
+                    let Rgba([r, g, b, a]) = *pixel;
+                    *pixel = match filter {
+                        Filter::Invert => {
+                            let invert = |channel: C| C::from_f32(a.to_f32() - channel.to_f32());
+                            Rgba([invert(r), invert(g), invert(b), a])
+                        }
+                        // The luminance of the color, with the coefficients of sRGB (Rec. 709)
+                        _ => {
+                            let luminance = C::from_f32(
+                                (0.2126 * r.to_f32() + 0.7152 * g.to_f32() + 0.0722 * b.to_f32())
+                                    .round(),
+                            );
+                            Rgba([luminance, luminance, luminance, a])
+                        }
+                    };
+                }
+            }
+        }
+    }
+}
+
+/// Apply the [`Effect`](crate::Effect)s of the [`Options`] that have no range to the whole of an [`ImageBuffer`], in order.
+pub(crate) fn apply_to_image<C: Channel>(
+    image_buffer: &mut ImageBuffer<Rgba<C>, Vec<C>>,
+    options: &Options,
+) where
+    Rgba<C>: Pixel<Subpixel = C>,
+{
+    let (width, height) = image_buffer.dimensions();
+    for effect in options
+        .effects
+        .iter()
+        .filter(|effect| effect.range.is_none())
+    {
+        apply(image_buffer, effect.filter, 0..width, 0..height);
+    }
+}
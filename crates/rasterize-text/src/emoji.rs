@@ -0,0 +1,40 @@
+//! Expand emoji shortcodes (ex. `:rocket:`) to emoji before text is laid out, like chat apps do as they are typed.
+
+use crate::Options;
+use std::borrow::Cow;
+
+/// Replace the shortcodes of text with their emoji, with [`Options::emoji_shortcodes`].
+///
+/// A shortcode is a name of lowercase letters, digits, `_`, `+` and `-` between colons, with the names of GitHub
+/// (ex. `:rocket:`, `:+1:`, `:tada:`). Names that aren't emoji (ex. the `:30:` of `12:30:45`) are kept as is,
+/// and their closing colon can open the next shortcode.
+pub(crate) fn expand<'t>(text: &'t str, options: &Options) -> Cow<'t, str> {
+    if !options.emoji_shortcodes || !text.contains(':') {
+        return Cow::Borrowed(text);
+    }
+    let mut expanded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(open) = rest.find(':') {
+        expanded.push_str(&rest[..open]);
+        let after = &rest[open + 1..];
+        let name_len = after
+            .find(|c: char| !(c.is_ascii_lowercase() || c.is_ascii_digit() || "_+-".contains(c)))
+            .unwrap_or(after.len());
+        let emoji = match after[name_len..].starts_with(':') && name_len > 0 {
+            true => emojis::get_by_shortcode(&after[..name_len]),
+            false => None,
+        };
+        match emoji {
+            Some(emoji) => {
+                expanded.push_str(emoji.as_str());
+                rest = &after[name_len + 1..];
+            }
+            None => {
+                expanded.push(':');
+                rest = after;
+            }
+        }
+    }
+    expanded.push_str(rest);
+    Cow::Owned(expanded)
+}
@@ -0,0 +1,437 @@
+//! Save rasterized text as PNG files that declare their color space, so that they look the same in color-managed viewers,
+//! and that optionally describe the parameters they were rendered with.
+
+use crate::shape::font_data;
+use crate::{Color, Direction, LanguageTag, Options, OptionsError};
+use image::{ImageBuffer, Rgba};
+use rusttype::Font;
+use siphasher::sip128::SipHasher13;
+use std::borrow::Cow;
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// The cICP chunk of an sRGB image: BT.709 primaries, the sRGB transfer function, RGB (no matrix) and full range.
+const SRGB_CICP: [u8; 4] = [1, 13, 0, 1];
+
+#[derive(Debug, thiserror::Error)]
+pub enum EncodeError {
+    #[error("Failed to write the PNG file: {1:?}.")]
+    FileWriteError(#[source] std::io::Error, PathBuf),
+    #[error("Failed to encode the PNG.")]
+    PngError(#[from] png::EncodingError),
+    #[error("Failed to create the sRGB ICC profile: {0}")]
+    IccProfileError(String),
+}
+
+/// How the color space of a PNG is declared, with [`write_png`] and [`save_png`].
+///
+/// Colors (ex. [`Options::color`](crate::Options::color)) are sRGB, and glyphs are blended with them as they are,
+/// so images are always sRGB. Every tag except [`ColorTag::None`] also writes a cICP chunk for sRGB,
+/// which newer viewers prefer over the other chunks.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ColorTag {
+    /// An sRGB chunk, which is small and understood by most viewers.
+    #[default]
+    Srgb,
+    /// An embedded sRGB ICC profile (iCCP chunk), for print workflows and viewers that only read ICC profiles.
+    Icc,
+    /// No color space, so viewers guess (usually sRGB).
+    None,
+}
+
+impl Display for ColorTag {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        let lowercase = format!("{:?}", self).to_lowercase();
+        write!(f, "{lowercase}")
+    }
+}
+
+impl FromStr for ColorTag {
+    type Err = OptionsError;
+
+    /// Returns a [`ColorTag`] converted from a [`str`].
+    fn from_str(tag: &str) -> Result<Self, Self::Err> {
+        let tag = match tag {
+            "srgb" => ColorTag::Srgb,
+            "icc" => ColorTag::Icc,
+            "none" => ColorTag::None,
+            _ => Err(OptionsError::UnknownColorTagError(tag.to_string()))?,
+        };
+
+        Ok(tag)
+    }
+}
+
+/// How a PNG is encoded, with [`write_png`] and [`save_png`].
+///
+/// A [`ColorTag`] converts to options without metadata, so it can be passed instead.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PngOptions {
+    /// How the color space of the PNG is declared.
+    pub color_tag: ColorTag,
+    /// Render parameters that are embedded as text chunks. If no metadata is provided, no text chunks are written.
+    pub metadata: Option<RenderMetadata>,
+    /// The text of the image, its language and direction, which are embedded as a description for screen readers.
+    /// If no alternative text is provided, the text isn't embedded.
+    pub alt_text: Option<AltText>,
+}
+
+impl From<ColorTag> for PngOptions {
+    fn from(color_tag: ColorTag) -> Self {
+        PngOptions {
+            color_tag,
+            metadata: None,
+            alt_text: None,
+        }
+    }
+}
+
+/// The parameters that an image was rendered with, which are embedded in a PNG as text chunks (tEXt)
+/// so that generated images describe how to render them again.
+///
+/// The text itself isn't embedded, only a hash of it, so that the text of images can't be read from their metadata.
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{ColorTag, Options, PngOptions, RenderMetadata, rasterize_with_options, write_png, EN_FONT, read_font_bytes};
+///
+/// let text    = "Hello, World!";
+/// let font    = read_font_bytes(EN_FONT)?;
+/// let options = Options::default();
+/// let image   = rasterize_with_options(&text, &font, &options)?;
+///
+/// let metadata = RenderMetadata::new(&text, &font, &options);
+/// let mut png  = Vec::new();
+/// write_png(&image, &mut png, PngOptions { metadata: Some(metadata.clone()), ..ColorTag::Srgb.into() })?;
+///
+/// // The same text always has the same hash
+/// assert_eq!(metadata.text_hash, RenderMetadata::new(&"Hello, World!", &font, &options).text_hash);
+/// assert_eq!(metadata.font_name.as_deref(), Some("DejaVu Sans"));
+///
+/// let decoder = png::Decoder::new(std::io::Cursor::new(png));
+/// let reader  = decoder.read_info()?;
+/// let text    = &reader.info().uncompressed_latin1_text;
+/// let chunk   = |keyword: &str| text.iter().find(|chunk| chunk.keyword == keyword).map(|chunk| chunk.text.clone());
+/// assert_eq!(chunk("Software"), Some(format!("rasterize-text {}", env!("CARGO_PKG_VERSION"))));
+/// assert_eq!(chunk("Font size"), Some("50".to_string()));
+/// assert_eq!(chunk("Text hash"), Some(metadata.text_hash));
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct RenderMetadata {
+    /// A hash of the text, as 32 hexadecimal digits (the 128 bit SipHash-1-3 of its UTF-8 bytes, with keys of zero).
+    pub text_hash: String,
+    /// The full name of the font (ex. `DejaVu Sans Bold`), if the font has one.
+    pub font_name: Option<String>,
+    /// Size of the font in pixels.
+    pub size: f32,
+    /// Color of the text.
+    pub color: Color,
+    /// The name and version of the crate that rendered the image (ex. `rasterize-text 0.1.0`).
+    pub software: String,
+}
+
+impl RenderMetadata {
+    /// Returns the metadata of text that is rendered with a [`Font`], as configured by [`Options`].
+    pub fn new(text: impl AsRef<str>, font: &Font, options: &Options) -> Self {
+        let text_hash = SipHasher13::new().hash(text.as_ref().as_bytes()).as_u128();
+        RenderMetadata {
+            text_hash: format!("{text_hash:032x}"),
+            font_name: font_name(font),
+            size: options.size,
+            color: options.color,
+            software: format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+        }
+    }
+
+    /// Returns the keywords and text of the text chunks of the metadata.
+    fn chunks(&self) -> Vec<(&'static str, String)> {
+        let mut chunks = vec![
+            ("Software", self.software.clone()),
+            ("Text hash", self.text_hash.clone()),
+        ];
+        if let Some(font_name) = &self.font_name {
+            chunks.push(("Font", font_name.clone()));
+        }
+        chunks.push(("Font size", self.size.to_string()));
+        chunks.push(("Text color", self.color.to_string()));
+        chunks
+    }
+}
+
+/// The text of an image, with its language and direction, so that publishing tools can describe the image to screen
+/// readers (ex. the `alt`, `lang` and `dir` attributes of an HTML `img`).
+///
+/// It is embedded in a PNG as the international text chunk (iTXt) `Description`, whose language tag is the language of
+/// the text, and the text chunk `Text direction`. It can also be written to a sidecar JSON file with [`AltText::to_json`].
+/// Unlike [`RenderMetadata`], the text itself is embedded, so it should only be used for text that is public.
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{AltText, ColorTag, Direction, Options, PngOptions, rasterize_with_options, write_png, EN_FONT, read_font_bytes};
+///
+/// let text    = "שלום, \"world\"";
+/// let font    = read_font_bytes(EN_FONT)?;
+/// let options = Options { lang: Some("he".parse()?), ..Default::default() };
+/// let image   = rasterize_with_options(&text, &font, &options)?;
+///
+/// let alt_text = AltText::new(&text, &options);
+/// assert_eq!(alt_text.direction, Direction::Rtl);
+/// assert_eq!(alt_text.to_json(), r#"{"text":"שלום, \"world\"","lang":"he","dir":"rtl"}"#);
+///
+/// let mut png = Vec::new();
+/// write_png(&image, &mut png, PngOptions { alt_text: Some(alt_text), ..ColorTag::Srgb.into() })?;
+/// let decoder     = png::Decoder::new(std::io::Cursor::new(png));
+/// let reader      = decoder.read_info()?;
+/// let description = reader.info().utf8_text.iter().find(|chunk| chunk.keyword == "Description").unwrap();
+/// assert_eq!(description.get_text()?, text);
+/// assert_eq!(description.language_tag, "he");
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AltText {
+    /// The text, as it was given to be rendered.
+    pub text: String,
+    /// The language of the text, if it is known (see [`Options::lang`]).
+    pub lang: Option<LanguageTag>,
+    /// The base direction of the text, which is resolved from the text if it is [`Direction::Auto`].
+    pub direction: Direction,
+}
+
+impl AltText {
+    /// Returns the alternative text of text that is rendered as configured by [`Options`].
+    pub fn new(text: impl AsRef<str>, options: &Options) -> Self {
+        let text = text.as_ref();
+        AltText {
+            text: text.to_string(),
+            lang: options.lang.clone(),
+            direction: options.direction.resolve(text),
+        }
+    }
+
+    /// Returns the alternative text as a JSON object, with the fields `text`, `lang` (which is `null` if the language
+    /// isn't known) and `dir` (`ltr` or `rtl`), for a sidecar file next to the image.
+    pub fn to_json(&self) -> String {
+        let lang = match &self.lang {
+            Some(lang) => json_string(lang.as_str()),
+            None => "null".to_string(),
+        };
+        format!(
+            r#"{{"text":{},"lang":{lang},"dir":"{}"}}"#,
+            json_string(&self.text),
+            self.direction
+        )
+    }
+
+    /// Returns the keywords, language tags and text of the text chunks of the alternative text.
+    fn chunks(&self) -> Vec<(&'static str, &str, String)> {
+        let lang = self.lang.as_ref().map_or("", LanguageTag::as_str);
+        vec![
+            ("Description", lang, self.text.clone()),
+            ("Text direction", "", self.direction.to_string()),
+        ]
+    }
+}
+
+/// Returns a string as a JSON string, in quotes and with its quotes, backslashes and control characters escaped.
+fn json_string(string: &str) -> String {
+    let mut json = String::with_capacity(string.len() + 2);
+    json.push('"');
+    for c in string.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+/// Returns the full name of a font from its naming table, falling back to its family name.
+fn font_name(font: &Font) -> Option<String> {
+    use rustybuzz::ttf_parser::{name_id, Face};
+
+    let face = Face::parse(font_data(font)?, 0).ok()?;
+    let name = |id: u16| {
+        face.names()
+            .into_iter()
+            .filter(|name| name.name_id == id && name.is_unicode())
+            .find_map(|name| name.to_string())
+    };
+    name(name_id::FULL_NAME).or_else(|| name(name_id::FAMILY))
+}
+
+/// Encode rasterized text as a PNG to a writer, with its color space declared by a [`ColorTag`]
+/// and optionally with [`RenderMetadata`] (see [`PngOptions`]).
+///
+/// Rasterized text is premultiplied by the coverage of its glyphs, while PNG stores colors and alpha separately, so
+/// colors are divided by their alpha as they are written. This keeps the edges of glyphs from looking
+/// darker than the text in viewers, which [`ImageBuffer::save`] doesn't do.
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{ColorTag, Options, rasterize_with_options, write_png, EN_FONT, read_font_bytes};
+///
+/// let font  = read_font_bytes(EN_FONT)?;
+/// let image = rasterize_with_options(&"Hello, World!", &font, &Options::default())?;
+///
+/// let mut srgb = Vec::new();
+/// write_png(&image, &mut srgb, ColorTag::Srgb)?;
+/// let mut icc = Vec::new();
+/// write_png(&image, &mut icc, ColorTag::Icc)?;
+///
+/// // The chunks that declare the color space come before the pixels
+/// let has = |png: &[u8], chunk: &[u8]| png.windows(4).any(|window| window == chunk);
+/// assert!(has(&srgb, b"sRGB") && has(&srgb, b"cICP") && !has(&srgb, b"iCCP"));
+/// assert!(has(&icc, b"iCCP") && has(&icc, b"cICP") && !has(&icc, b"sRGB"));
+///
+/// // The pixels are the same, whichever way the color space is declared
+/// let decoded = image::load_from_memory(&icc)?.to_rgba8();
+/// assert_eq!(decoded, image::load_from_memory(&srgb)?.to_rgba8());
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+pub fn write_png<W: Write>(
+    image_buffer: &ImageBuffer<Rgba<u8>, Vec<u8>>,
+    writer: W,
+    options: impl Into<PngOptions>,
+) -> Result<(), EncodeError> {
+    let data: Vec<u8> = image_buffer
+        .pixels()
+        .flat_map(|Rgba([r, g, b, a])| {
+            let unmultiply = |channel: &u8| match a {
+                0 => 0,
+                _ => {
+                    ((u32::from(*channel) * 255 + u32::from(*a) / 2) / u32::from(*a)).min(255) as u8
+                }
+            };
+            [unmultiply(r), unmultiply(g), unmultiply(b), *a]
+        })
+        .collect();
+    let size = (image_buffer.width(), image_buffer.height());
+
+    encode(writer, size, png::BitDepth::Eight, &data, &options.into())
+}
+
+/// Encode rasterized text with 16 bits per channel (ex. from [`rasterize_rgba16`](crate::rasterize_rgba16))
+/// as a 16 bit PNG to a writer, with its color space declared by a [`ColorTag`].
+///
+/// This is the same as [`write_png`], but keeps all 16 bits of each channel.
+pub fn write_png16<W: Write>(
+    image_buffer: &ImageBuffer<Rgba<u16>, Vec<u16>>,
+    writer: W,
+    options: impl Into<PngOptions>,
+) -> Result<(), EncodeError> {
+    // PNG stores 16 bit channels as big endian
+    let data: Vec<u8> = image_buffer
+        .pixels()
+        .flat_map(|Rgba([r, g, b, a])| {
+            let unmultiply = |channel: &u16| match a {
+                0 => 0,
+                _ => ((u64::from(*channel) * 65535 + u64::from(*a) / 2) / u64::from(*a)).min(65535)
+                    as u16,
+            };
+            [unmultiply(r), unmultiply(g), unmultiply(b), *a]
+        })
+        .flat_map(u16::to_be_bytes)
+        .collect();
+    let size = (image_buffer.width(), image_buffer.height());
+
+    encode(writer, size, png::BitDepth::Sixteen, &data, &options.into())
+}
+
+/// Encode RGBA pixel data, with colors that aren't multiplied by their alpha, as a PNG with [`PngOptions`].
+fn encode<W: Write>(
+    writer: W,
+    (width, height): (u32, u32),
+    bit_depth: png::BitDepth,
+    data: &[u8],
+    options: &PngOptions,
+) -> Result<(), EncodeError> {
+    let tag = options.color_tag;
+    let mut info = png::Info::with_size(width, height);
+    info.color_type = png::ColorType::Rgba;
+    info.bit_depth = bit_depth;
+    // Text that isn't Latin-1 (ex. the names of CJK fonts) is written to international text chunks (iTXt)
+    for (keyword, text) in options.metadata.iter().flat_map(RenderMetadata::chunks) {
+        match text.chars().all(|c| u32::from(c) <= 0xFF) {
+            true => info
+                .uncompressed_latin1_text
+                .push(png::text_metadata::TEXtChunk::new(keyword, text)),
+            false => info
+                .utf8_text
+                .push(png::text_metadata::ITXtChunk::new(keyword, text)),
+        }
+    }
+    // The description is always international text, since its language is declared in the chunk
+    for (keyword, lang, text) in options.alt_text.iter().flat_map(AltText::chunks) {
+        let mut chunk = png::text_metadata::ITXtChunk::new(keyword, text);
+        chunk.language_tag = lang.to_string();
+        info.utf8_text.push(chunk);
+    }
+    match tag {
+        // Text is drawn over its background with colors that are matched relative to the white point, like a logo
+        ColorTag::Srgb => info.srgb = Some(png::SrgbRenderingIntent::RelativeColorimetric),
+        ColorTag::Icc => {
+            let profile = moxcms::ColorProfile::new_srgb()
+                .encode()
+                .map_err(|error| EncodeError::IccProfileError(format!("{error:?}")))?;
+            info.icc_profile = Some(Cow::Owned(profile));
+        }
+        ColorTag::None => {}
+    }
+    let mut writer = png::Encoder::with_info(writer, info)?.write_header()?;
+    if tag != ColorTag::None {
+        writer.write_chunk(png::chunk::ChunkType(*b"cICP"), &SRGB_CICP)?;
+    }
+    writer.write_image_data(data)?;
+    writer.finish()?;
+
+    Ok(())
+}
+
+/// Save rasterized text as a PNG file, with its color space declared by a [`ColorTag`].
+///
+/// This is the same as [`write_png`], but writes to a file at a [`Path`].
+pub fn save_png<P: AsRef<Path>>(
+    image_buffer: &ImageBuffer<Rgba<u8>, Vec<u8>>,
+    path: P,
+    options: impl Into<PngOptions>,
+) -> Result<(), EncodeError> {
+    let path = path.as_ref();
+    let file = File::create(path)
+        .map_err(|error| EncodeError::FileWriteError(error, path.to_path_buf()))?;
+    let mut writer = BufWriter::new(file);
+    write_png(image_buffer, &mut writer, options)?;
+    writer
+        .flush()
+        .map_err(|error| EncodeError::FileWriteError(error, path.to_path_buf()))
+}
+
+/// Save rasterized text with 16 bits per channel as a 16 bit PNG file, with its color space declared by a [`ColorTag`].
+///
+/// This is the same as [`write_png16`], but writes to a file at a [`Path`].
+pub fn save_png16<P: AsRef<Path>>(
+    image_buffer: &ImageBuffer<Rgba<u16>, Vec<u16>>,
+    path: P,
+    options: impl Into<PngOptions>,
+) -> Result<(), EncodeError> {
+    let path = path.as_ref();
+    let file = File::create(path)
+        .map_err(|error| EncodeError::FileWriteError(error, path.to_path_buf()))?;
+    let mut writer = BufWriter::new(file);
+    write_png16(image_buffer, &mut writer, options)?;
+    writer
+        .flush()
+        .map_err(|error| EncodeError::FileWriteError(error, path.to_path_buf()))
+}
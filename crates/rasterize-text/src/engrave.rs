@@ -0,0 +1,305 @@
+//! Export the outlines of glyphs for engraving and plotting, as DXF polylines, G-code or HPGL.
+
+use crate::{GlyphOutline, OptionsError};
+use kurbo::PathEl;
+use std::fmt::{Display, Formatter};
+use std::io::Write;
+use std::str::FromStr;
+
+/// Number of HPGL plotter units in a millimeter.
+const PLOTTER_UNITS_PER_MM: f64 = 40.0;
+
+/// Configure how the outlines of glyphs are flattened and scaled for engraving, with [`write_dxf`] and [`write_gcode`].
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{outline_text, write_dxf, write_gcode, EngraveOptions, Options, EN_FONT, read_font_bytes};
+///
+/// let font     = read_font_bytes(EN_FONT)?;
+/// let outlines = outline_text(&"Engrave", &font, &Options::default())?;
+/// // Each pixel is a tenth of a millimeter, and the bit cuts half a millimeter deep
+/// let options  = EngraveOptions { scale: 0.1, cut_z: -0.5, ..Default::default() };
+///
+/// let mut gcode = Vec::new();
+/// write_gcode(&outlines, &options, &mut gcode)?;
+/// let gcode = String::from_utf8(gcode)?;
+/// assert!(gcode.starts_with("G21"));
+/// assert!(gcode.contains("G1 Z-0.500"));
+///
+/// let mut dxf = Vec::new();
+/// write_dxf(&outlines, &options, &mut dxf)?;
+/// let dxf = String::from_utf8(dxf)?;
+/// // The 'g', 'a' and 'e' have two contours, and the other letters have one
+/// assert_eq!(dxf.matches("POLYLINE").count(), 10);
+/// assert!(dxf.ends_with("EOF\n"));
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct EngraveOptions {
+    /// The most distance in pixels between the curves of outlines and the straight lines that they are flattened to.
+    pub tolerance: f64,
+    /// The size of a pixel in millimeters.
+    pub scale: f64,
+    /// The height in millimeters that the tool moves between outlines, above the surface at 0.
+    pub safe_z: f64,
+    /// The height in millimeters that the tool cuts at, which is negative to cut below the surface.
+    pub cut_z: f64,
+    /// The speed in millimeters per minute that the tool cuts at.
+    pub feed_rate: f64,
+}
+
+impl Default for EngraveOptions {
+    fn default() -> Self {
+        EngraveOptions {
+            tolerance: 0.1,
+            scale: 0.1,
+            safe_z: 5.0,
+            cut_z: -0.2,
+            feed_rate: 300.0,
+        }
+    }
+}
+
+/// Write the outlines of glyphs to a DXF file, as a polyline for each contour.
+///
+/// Curves are flattened to straight lines within the [`EngraveOptions::tolerance`]. The text is scaled to millimeters,
+/// and flipped so that y increases upwards, with its lowest point at 0. The file is DXF R12, which most CAD and laser
+/// cutting software can read.
+pub fn write_dxf(
+    outlines: &[GlyphOutline],
+    options: &EngraveOptions,
+    mut writer: impl Write,
+) -> std::io::Result<()> {
+    writeln!(writer, "0\nSECTION\n2\nENTITIES")?;
+    for (points, closed) in polylines(outlines, options.tolerance) {
+        writeln!(writer, "0\nPOLYLINE\n8\n0\n66\n1\n70\n{}", u8::from(closed))?;
+        writeln!(writer, "10\n0.0\n20\n0.0\n30\n0.0")?;
+        for (x, y) in points {
+            let (x, y) = (x * options.scale, y * options.scale);
+            writeln!(writer, "0\nVERTEX\n8\n0\n10\n{x:.4}\n20\n{y:.4}\n30\n0.0")?;
+        }
+        writeln!(writer, "0\nSEQEND\n8\n0")?;
+    }
+    writeln!(writer, "0\nENDSEC\n0\nEOF")
+}
+
+/// Write the outlines of glyphs as G-code for engraving, tracing each contour with the tool.
+///
+/// The outlines are flattened and placed in the same way as with [`write_dxf`]. Coordinates are absolute, in millimeters,
+/// and the tool is raised to the [`EngraveOptions::safe_z`] before moving to the start of each contour.
+pub fn write_gcode(
+    outlines: &[GlyphOutline],
+    options: &EngraveOptions,
+    mut writer: impl Write,
+) -> std::io::Result<()> {
+    let EngraveOptions {
+        scale,
+        safe_z,
+        cut_z,
+        feed_rate,
+        ..
+    } = *options;
+    writeln!(writer, "G21\nG90\nG0 Z{safe_z:.3}")?;
+    for (points, closed) in polylines(outlines, options.tolerance) {
+        let Some(&(x, y)) = points.first() else {
+            continue;
+        };
+        writeln!(writer, "G0 X{:.3} Y{:.3}", x * scale, y * scale)?;
+        writeln!(writer, "G1 Z{cut_z:.3} F{feed_rate:.1}")?;
+        // Closed contours are traced back to their start
+        let end = closed.then_some((x, y));
+        for (x, y) in points.iter().skip(1).chain(end.as_ref()) {
+            writeln!(writer, "G1 X{:.3} Y{:.3}", x * scale, y * scale)?;
+        }
+        writeln!(writer, "G0 Z{safe_z:.3}")?;
+    }
+    writeln!(writer, "M2")
+}
+
+/// Configure how the outlines of glyphs are plotted on paper, with [`write_hpgl`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct PlotOptions {
+    /// The paper that the text is scaled to fit within the margin of, and centered on.
+    ///
+    /// If no paper is provided, the text is scaled by the [`EngraveOptions::scale`], from the origin of the plotter.
+    pub paper: Option<PaperSize>,
+    /// The margin around the edges of the paper in millimeters.
+    pub margin: f64,
+    /// The number of the pen that the text is plotted with.
+    pub pen: u8,
+}
+
+impl Default for PlotOptions {
+    fn default() -> Self {
+        PlotOptions {
+            paper: None,
+            margin: 10.0,
+            pen: 1,
+        }
+    }
+}
+
+/// The sizes of paper that text can be plotted on, in landscape.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum PaperSize {
+    /// 297 by 210 millimeters.
+    #[default]
+    A4,
+    /// 420 by 297 millimeters.
+    A3,
+    /// 11 by 8.5 inches (279.4 by 215.9 millimeters).
+    Letter,
+}
+
+impl PaperSize {
+    /// Returns the width and height of the paper in millimeters.
+    pub fn dimensions(&self) -> (f64, f64) {
+        match self {
+            PaperSize::A4 => (297.0, 210.0),
+            PaperSize::A3 => (420.0, 297.0),
+            PaperSize::Letter => (279.4, 215.9),
+        }
+    }
+}
+
+impl Display for PaperSize {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        let lowercase = format!("{:?}", self).to_lowercase();
+        write!(f, "{lowercase}")
+    }
+}
+
+impl FromStr for PaperSize {
+    type Err = OptionsError;
+
+    /// Returns a [`PaperSize`] converted from a [`str`].
+    fn from_str(paper: &str) -> Result<Self, Self::Err> {
+        let paper = match paper {
+            "a4" => PaperSize::A4,
+            "a3" => PaperSize::A3,
+            "letter" => PaperSize::Letter,
+            _ => Err(OptionsError::UnknownPaperSizeError(paper.to_string()))?,
+        };
+
+        Ok(paper)
+    }
+}
+
+/// Write the outlines of glyphs as HPGL for pen plotters, tracing each contour with the pen.
+///
+/// The outlines are flattened within the [`EngraveOptions::tolerance`] and flipped in the same way as with [`write_dxf`].
+/// Coordinates are in plotter units (40 per millimeter), and are either scaled to fit the [`PlotOptions::paper`],
+/// or by the [`EngraveOptions::scale`].
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{outline_text, write_hpgl, EngraveOptions, Options, PaperSize, PlotOptions, EN_FONT, read_font_bytes};
+///
+/// let font     = read_font_bytes(EN_FONT)?;
+/// let outlines = outline_text(&"Plot", &font, &Options::default())?;
+/// let options  = PlotOptions { paper: Some(PaperSize::A4), ..Default::default() };
+///
+/// let mut hpgl = Vec::new();
+/// write_hpgl(&outlines, &EngraveOptions::default(), &options, &mut hpgl)?;
+/// let hpgl = String::from_utf8(hpgl)?;
+/// assert!(hpgl.starts_with("IN;SP1;"));
+/// // The 'P' and 'o' have two contours, and the 'l' and 't' have one
+/// assert_eq!(hpgl.matches("PD").count(), 6);
+/// // The text is scaled to the width of the paper within its margins, which is 277 mm
+/// let xs: Vec<i64> = hpgl
+///     .split(';')
+///     .filter_map(|command| command.strip_prefix("PU").or(command.strip_prefix("PD")))
+///     .flat_map(|points| points.split(',').step_by(2).filter_map(|x| x.parse().ok()).collect::<Vec<_>>())
+///     .collect();
+/// assert_eq!(xs.iter().min(), Some(&(10 * 40)));
+/// assert_eq!(xs.iter().max(), Some(&(287 * 40)));
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+pub fn write_hpgl(
+    outlines: &[GlyphOutline],
+    options: &EngraveOptions,
+    plot: &PlotOptions,
+    mut writer: impl Write,
+) -> std::io::Result<()> {
+    let polylines = polylines(outlines, options.tolerance);
+    let (mut min_x, mut max_x, mut max_y) = (f64::INFINITY, f64::NEG_INFINITY, 0.0_f64);
+    for &(x, y) in polylines.iter().flat_map(|(points, _)| points) {
+        (min_x, max_x, max_y) = (min_x.min(x), max_x.max(x), max_y.max(y));
+    }
+    // The text is scaled uniformly to fit the paper within its margin, and centered on it
+    let (scale, offset) = match plot.paper {
+        Some(paper) if min_x < max_x => {
+            let (width, height) = paper.dimensions();
+            let (text_width, text_height) = (max_x - min_x, max_y);
+            let scale = ((width - 2.0 * plot.margin) / text_width)
+                .min((height - 2.0 * plot.margin) / text_height.max(f64::MIN_POSITIVE));
+            let offset = (
+                (width - text_width * scale) / 2.0 - min_x * scale,
+                (height - text_height * scale) / 2.0,
+            );
+            (scale, offset)
+        }
+        _ => (options.scale, (0.0, 0.0)),
+    };
+    let units = |(x, y): (f64, f64)| {
+        let x = ((x * scale + offset.0) * PLOTTER_UNITS_PER_MM).round() as i64;
+        let y = ((y * scale + offset.1) * PLOTTER_UNITS_PER_MM).round() as i64;
+        format!("{x},{y}")
+    };
+    write!(writer, "IN;SP{};", plot.pen)?;
+    for (points, closed) in polylines {
+        let Some(&start) = points.first() else {
+            continue;
+        };
+        // Closed contours are traced back to their start
+        let end = closed.then_some(start);
+        let points: Vec<String> = points.into_iter().skip(1).chain(end).map(units).collect();
+        writeln!(writer, "PU{};PD{};", units(start), points.join(","))?;
+    }
+    writeln!(writer, "PU;SP0;")
+}
+
+/// Returns the contours of the outlines of glyphs, flattened to points in pixels with y increasing upwards
+/// from the lowest point of the text, and whether each contour is closed.
+fn polylines(outlines: &[GlyphOutline], tolerance: f64) -> Vec<(Vec<(f64, f64)>, bool)> {
+    let mut polylines: Vec<(Vec<kurbo::Point>, bool)> = Vec::new();
+    for outline in outlines {
+        kurbo::flatten(outline.positioned_path(), tolerance, |element| {
+            match element {
+                PathEl::MoveTo(point) => polylines.push((vec![point], false)),
+                PathEl::LineTo(point) => match polylines.last_mut() {
+                    Some((points, _)) => points.push(point),
+                    None => polylines.push((vec![point], false)),
+                },
+                PathEl::ClosePath => {
+                    if let Some((points, closed)) = polylines.last_mut() {
+                        // Contours that already end at their start would have the start twice
+                        if points.len() > 1 && points.first() == points.last() {
+                            points.pop();
+                        }
+                        *closed = true;
+                    }
+                }
+                // Flattening only returns straight lines
+                PathEl::QuadTo(..) | PathEl::CurveTo(..) => {}
+            }
+        });
+    }
+    // The pixel coordinates of the image have y increasing downwards, so the text is flipped at its lowest point
+    let bottom = polylines
+        .iter()
+        .flat_map(|(points, _)| points.iter().map(|point| point.y))
+        .fold(f64::NEG_INFINITY, f64::max);
+    polylines
+        .into_iter()
+        .map(|(points, closed)| {
+            let points = points
+                .iter()
+                .map(|point| (point.x, bottom - point.y))
+                .collect();
+            (points, closed)
+        })
+        .collect()
+}
@@ -0,0 +1,94 @@
+//! Estimate the memory used by rasterized text, before it is rasterized.
+
+use crate::span::position_spans;
+use crate::{measure, Options, RasterizeError, Span};
+use rusttype::Font;
+
+/// Number of bytes used by each RGBA pixel of an image.
+const BYTES_PER_PIXEL: u64 = 4;
+
+/// Estimate the width and height in pixels, and the size in bytes, of the image that [`rasterize`](crate::rasterize) would return.
+///
+/// The text is laid out but not drawn, and no image is allocated, so this can be used to reject text
+/// that would exceed a memory budget before rasterizing it. The limits of [`Options`] (ex. [`Options::max_pixels`])
+/// are not applied, so the size of text that [`rasterize`](crate::rasterize) rejects is still estimated.
+///
+/// # Arguments
+///
+/// - `text`: A text [`str`] reference to estimate the size of.
+/// - `font`: A [`Font`] reference that contains [TrueType](https://en.wikipedia.org/wiki/TrueType) data.
+/// - `size`: Font size in pixels (ex. `50.0`).
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{Color, estimate_buffer_size, rasterize, EN_FONT, read_font_bytes};
+///
+/// let text  = "This is a test, we love Unicode ÅΩ!";
+/// let font  = read_font_bytes(EN_FONT)?;
+/// let (width, height, bytes) = estimate_buffer_size(&text, &font, 50.0);
+/// assert_eq!((width, height, bytes), (740, 45, 740 * 45 * 4));
+///
+/// let image = rasterize(&text, &font, 50.0, &Color::default())?;
+/// assert_eq!((image.width() as u64, image.height() as u64), (width, height));
+/// assert_eq!(image.as_raw().len() as u64, bytes);
+///
+/// // Text at a huge font size is measured rather than rasterized
+/// let (_, _, bytes) = estimate_buffer_size(&text, &font, 100_000.0);
+/// assert!(bytes > 1 << 30);
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+pub fn estimate_buffer_size(text: impl AsRef<str>, font: &Font, size: f32) -> (u64, u64, u64) {
+    let options = Options {
+        size,
+        ..Default::default()
+    };
+    estimate_buffer_size_with_options(text, font, &options)
+}
+
+/// Estimate the width and height in pixels, and the size in bytes, of the image that
+/// [`rasterize_with_options`](crate::rasterize_with_options) would return, as configured by [`Options`].
+///
+/// This is the same as [`estimate_buffer_size`], but accepts [`Options`] to control additional aspects of the layout (ex. wrapping).
+///
+/// # Examples
+///
+/// A server can enforce a memory quota for each request, with a clear error for text that exceeds it.
+///
+/// ```rust
+/// use rasterize_text::{Options, estimate_buffer_size_with_options, rasterize_with_options, EN_FONT, read_font_bytes};
+///
+/// let text    = "The quick brown fox jumps over the lazy dog. ".repeat(20);
+/// let font    = read_font_bytes(EN_FONT)?;
+/// let quota   = 1 << 20;
+///
+/// let wrapped = Options { size: 20.0, wrap_width: Some(400.0), ..Default::default() };
+/// let (width, height, bytes) = estimate_buffer_size_with_options(&text.as_str(), &font, &wrapped);
+/// assert!(width <= 400 && bytes <= quota);
+/// let image = rasterize_with_options(&text.as_str(), &font, &wrapped)?;
+/// assert_eq!((image.width() as u64, image.height() as u64), (width, height));
+///
+/// let large = Options { size: 200.0, ..Default::default() };
+/// let (_, _, bytes) = estimate_buffer_size_with_options(&text.as_str(), &font, &large);
+/// assert!(bytes > quota);
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+pub fn estimate_buffer_size_with_options(
+    text: impl AsRef<str>,
+    font: &Font,
+    options: &Options,
+) -> (u64, u64, u64) {
+    let (width, height) = match position_spans(&[Span::from(text.as_ref())], font, options) {
+        Ok((glyphs, decorations, _)) => measure(&glyphs, &decorations),
+        // Glyphs that are too far from the origin to position are reported with the extent they would need
+        Err(RasterizeError::TooLarge { width, height, .. }) => (width, height),
+        // Strict mode is only checked after the glyphs are drawn, so positioning doesn't return its errors
+        Err(RasterizeError::Strict(_)) => (0, 0),
+        // Text with a denied font isn't drawn at all
+        Err(RasterizeError::RestrictedFont(_)) => (0, 0),
+        // Neither is text with more glyphs than the limit
+        Err(RasterizeError::TooManyGlyphs { .. }) => (0, 0),
+    };
+    let bytes = width.saturating_mul(height).saturating_mul(BYTES_PER_PIXEL);
+    (width, height, bytes)
+}
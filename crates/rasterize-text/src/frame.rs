@@ -0,0 +1,90 @@
+//! Round the corners of rasterized text, and draw a border around it.
+
+use crate::channel::Channel;
+use crate::{check_size, Options, RasterizeError};
+use image::{imageops, ImageBuffer, Pixel, Rgba};
+
+/// Cut out the rounded corners of an [`ImageBuffer`], and draw the [`Border`](crate::Border) of the [`Options`] around it.
+///
+/// The image is made larger by the width of the border (rounded up to whole pixels) on each side.
+/// Images without rounded corners or a border are returned as is.
+pub(crate) fn apply<C: Channel>(
+    image_buffer: ImageBuffer<Rgba<C>, Vec<C>>,
+    options: &Options,
+) -> Result<ImageBuffer<Rgba<C>, Vec<C>>, RasterizeError>
+where
+    Rgba<C>: Pixel<Subpixel = C>,
+{
+    let border_width = options.border.map_or(0.0, |border| border.width.max(0.0));
+    if options.corner_radius <= 0.0 && border_width <= 0.0 {
+        return Ok(image_buffer);
+    }
+    let inset = border_width.ceil() as u32;
+    let (width, height) = check_size(
+        u64::from(image_buffer.width()) + 2 * u64::from(inset),
+        u64::from(image_buffer.height()) + 2 * u64::from(inset),
+        options,
+    )?;
+    let mut framed = ImageBuffer::<Rgba<C>, Vec<_>>::new(width, height);
+    imageops::replace(&mut framed, &image_buffer, inset.into(), inset.into());
+
+    // The radius can't be more than half of the image, where the corners meet
+    let (w, h) = (width as f32, height as f32);
+    let radius = options.corner_radius.max(0.0).min(w.min(h) / 2.0);
+    let inner_radius = (radius - border_width).max(0.0);
+    let inner_rect = (
+        border_width,
+        border_width,
+        w - border_width,
+        h - border_width,
+    );
+    // Pixels are premultiplied (see Channel), so the border is too
+    let border = options.border.map_or([0.0; 4], |border| {
+        let alpha = f32::from(border.color.a) / 255.0;
+        [
+            f32::from(border.color.r) * C::SCALE * alpha,
+            f32::from(border.color.g) * C::SCALE * alpha,
+            f32::from(border.color.b) * C::SCALE * alpha,
+            f32::from(border.color.a) * C::SCALE,
+        ]
+    });
+    for (x, y, pixel) in framed.enumerate_pixels_mut() {
+        let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+        let outer = coverage(px, py, (0.0, 0.0, w, h), radius);
+        let inner = coverage(px, py, inner_rect, inner_radius);
+        for channel in 0..4 {
+            pixel[channel] = C::from_f32(
+                (pixel[channel].to_f32() * inner + border[channel] * (outer - inner))
+                    .round()
+                    .clamp(0.0, C::MAX),
+            );
+        }
+    }
+
+    Ok(framed)
+}
+
+/// Returns how much of the pixel centered at `x` and `y` is covered by a rectangle with rounded corners, from 0 to 1.
+///
+/// The rectangle is given by its left, top, right and bottom edges, and edges are antialiased over a pixel.
+fn coverage(x: f32, y: f32, rect: (f32, f32, f32, f32), radius: f32) -> f32 {
+    (0.5 - distance(x, y, rect, radius)).clamp(0.0, 1.0)
+}
+
+/// Returns the signed distance from a point to the edge of a rectangle with rounded corners, which is negative inside of it.
+pub(crate) fn distance(
+    x: f32,
+    y: f32,
+    (left, top, right, bottom): (f32, f32, f32, f32),
+    radius: f32,
+) -> f32 {
+    if right <= left || bottom <= top {
+        return f32::INFINITY;
+    }
+    let radius = radius.min((right - left) / 2.0).min((bottom - top) / 2.0);
+    let dx = (x - (left + right) / 2.0).abs() - ((right - left) / 2.0 - radius);
+    let dy = (y - (top + bottom) / 2.0).abs() - ((bottom - top) / 2.0 - radius);
+    // Square roots are correctly rounded in IEEE 754, while hypot depends on the math library of the platform
+    let (outside_x, outside_y) = (dx.max(0.0), dy.max(0.0));
+    (outside_x * outside_x + outside_y * outside_y).sqrt() + dx.max(dy).min(0.0) - radius
+}
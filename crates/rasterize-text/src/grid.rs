@@ -0,0 +1,282 @@
+//! Lay out text on a grid of fixed size cells, like a terminal.
+
+use crate::frame;
+use crate::layout::Line;
+use crate::metadata::add_warnings;
+use crate::shape::{ShapedGlyph, Shaper};
+use crate::span::{decorations, segments};
+use crate::{check_size, draw_unframed, position_glyphs, strict, Options, RasterizeError, Span};
+use image::{ImageBuffer, Rgba};
+use rusttype::{point, Font, Scale};
+use std::ops::Range;
+use unicode_bidi::{BidiInfo, Level};
+use unicode_normalization::UnicodeNormalization;
+use unicode_properties::{GeneralCategory, UnicodeGeneralCategory};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// A grid of fixed size cells that text is laid out on with [`rasterize_grid`], like the screen of a terminal.
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{GridLayout, EN_FONT, read_font_bytes};
+///
+/// let font = read_font_bytes(EN_FONT)?;
+/// let grid = GridLayout::new(80, 24, &font, 20.0);
+/// assert_eq!(grid.cell_w, font.glyph(' ').scaled(rusttype::Scale::uniform(20.0)).h_metrics().advance_width);
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct GridLayout {
+    /// Number of columns of cells.
+    pub cols: usize,
+    /// Number of rows of cells.
+    pub rows: usize,
+    /// Width of each cell in pixels.
+    pub cell_w: f32,
+    /// Height of each cell in pixels.
+    pub cell_h: f32,
+}
+
+impl GridLayout {
+    /// A grid of `cols` by `rows` cells, sized for a font at `size` pixels.
+    ///
+    /// Cells are as wide as a space, and as tall as the line height of the font.
+    /// With a monospace font, every (narrow) character is as wide as a space.
+    pub fn new(cols: usize, rows: usize, font: &Font, size: f32) -> Self {
+        let scale = Scale::uniform(size);
+        let metrics = font.v_metrics(scale);
+        GridLayout {
+            cols,
+            rows,
+            cell_w: font.glyph(' ').scaled(scale).h_metrics().advance_width,
+            cell_h: metrics.ascent - metrics.descent + metrics.line_gap,
+        }
+    }
+
+    /// Returns the number of rows that text takes up on the grid, as configured by [`Options`],
+    /// including rows that wrap and rows past the bottom of the grid.
+    ///
+    /// This can be used to size a grid to fit the text (ex. all of the output of a command).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use rasterize_text::{GridLayout, Options, EN_FONT, read_font_bytes};
+    ///
+    /// let font = read_font_bytes(EN_FONT)?;
+    /// let grid = GridLayout::new(4, 1, &font, 20.0);
+    /// assert_eq!(grid.rows_for("abc\ndefgh\n", &Options::default()), 3);
+    /// # Ok::<(), color_eyre::eyre::Report>(())
+    /// ```
+    pub fn rows_for(&self, text: &str, options: &Options) -> usize {
+        let text = text.nfc().collect::<String>();
+        let bidi = BidiInfo::new(&text, Some(Level::ltr()));
+        place(&text, &bidi, self, options, usize::MAX)
+            .last()
+            .map_or(0, |placement| placement.row + 1)
+    }
+}
+
+/// Rasterize text on a grid of fixed size cells to an [`ImageBuffer`], as configured by [`Options`].
+///
+/// This is intended for text that was laid out for a terminal (ex. terminal output or ASCII art).
+/// Each grapheme (an extended grapheme cluster, ex. a letter and its accents, or a flag) is placed in the next cell of
+/// the row, and centered within it. Graphemes take up as many cells as their width in a terminal, so wide characters
+/// (ex. Chinese, Japanese and Korean characters, and most emoji) take up two cells.
+///
+/// Newlines and line separators start the next row, and tabs move to the next tab stop of [`Options::tab_stops`] (every 8 cells by default).
+/// Rows that are longer than the grid wrap onto the next row, and rows past the bottom of the grid are not drawn.
+/// Other control characters are skipped. The text is not wrapped, aligned or indented according to the [`Options`].
+///
+/// The image is always the size of the grid, rounded up to whole pixels.
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{GridLayout, Options, rasterize_grid, EN_FONT, KR_FONT, read_font_bytes};
+///
+/// let text = "+------+\n| 안녕 |\n+------+\n\tTab";
+/// let font = read_font_bytes(EN_FONT)?;
+/// let options = Options { size: 20.0, fallback_fonts: vec![read_font_bytes(KR_FONT)?], ..Default::default() };
+/// let grid = GridLayout::new(16, 5, &font, options.size);
+///
+/// let image = rasterize_grid(&text, &font, &grid, &options)?;
+/// image.save("rasterize_grid.png")?;
+/// assert_eq!(image.width(), (16.0 * grid.cell_w).ceil() as u32);
+/// assert_eq!(image.height(), (5.0 * grid.cell_h).ceil() as u32);
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+pub fn rasterize_grid(
+    text: impl AsRef<str>,
+    font: &Font,
+    grid: &GridLayout,
+    options: &Options,
+) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>, RasterizeError> {
+    rasterize_grid_spans(&[Span::from(text.as_ref())], font, grid, options)
+}
+
+/// Rasterize spans of styled text on a grid of fixed size cells to an [`ImageBuffer`], as configured by [`Options`].
+///
+/// This is the same as [`rasterize_grid`], but the [`Style`](crate::Style) of each [`Span`] overrides [`Options::color`],
+/// and can give the text a background, an underline, a strikethrough or bold glyphs. The background of each grapheme spans the height of its row.
+/// The text is drawn at the font size, regardless of the [`Position`](crate::Position) of the span, and ruby is not drawn.
+pub fn rasterize_grid_spans(
+    spans: &[Span],
+    font: &Font,
+    grid: &GridLayout,
+    options: &Options,
+) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>, RasterizeError> {
+    // Float to integer casts saturate, so a huge or infinite grid is reported as the maximum
+    let width = (grid.cols as f64 * grid.cell_w as f64).ceil() as u64;
+    let height = (grid.rows as f64 * grid.cell_h as f64).ceil() as u64;
+    let (width, height) = check_size(width, height, options)?;
+
+    let fonts: Vec<&Font> = std::iter::once(font)
+        .chain(options.fallback_fonts.iter())
+        .collect();
+    let (text, mut segments) = segments(spans, font, options);
+    for segment in &mut segments {
+        segment.size = options.size;
+        segment.rise = 0.0;
+    }
+    let bidi = BidiInfo::new(&text, Some(Level::ltr()));
+    let shaper = Shaper::new(&fonts, &segments, options);
+
+    let mut rows: Vec<Vec<ShapedGlyph>> = vec![Vec::new(); grid.rows];
+    // The cells of each grapheme, as a glyph that spans them, for drawing backgrounds and underlines
+    let mut cell_rows: Vec<Vec<ShapedGlyph>> = vec![Vec::new(); grid.rows];
+    for placement in place(&text, &bidi, grid, options, grid.rows) {
+        let paragraph = &bidi.paragraphs[placement.paragraph];
+        let (row, col, cells) = (placement.row, placement.col, placement.cells);
+
+        // Center the grapheme within its cells
+        let shaped = shaper.shape_line(&text, &bidi, paragraph, placement.range);
+        let advance: f32 = shaped.iter().map(|glyph| glyph.advance).sum();
+        let offset = (col as f32 + cells as f32 / 2.0) * grid.cell_w - advance / 2.0;
+        if let Some(glyph) = shaped.first() {
+            cell_rows[row].push(ShapedGlyph {
+                x: col as f32 * grid.cell_w,
+                y: 0.0,
+                advance: cells as f32 * grid.cell_w,
+                ..*glyph
+            });
+        }
+        rows[row].extend(shaped.into_iter().map(|mut glyph| {
+            glyph.x += offset;
+            glyph
+        }));
+    }
+
+    // The baseline is centered vertically within each row, using the metrics of the main font
+    let metrics = font.v_metrics(Scale::uniform(options.size));
+    let baseline = (grid.cell_h - (metrics.ascent - metrics.descent)) / 2.0 + metrics.ascent;
+    let lines = |rows: Vec<Vec<ShapedGlyph>>| -> Vec<Line> {
+        rows.into_iter()
+            .enumerate()
+            .map(|(row, glyphs)| Line::new(glyphs, row as f32 * grid.cell_h))
+            .collect()
+    };
+    let text_lines = lines(rows);
+    let mut warnings = Vec::new();
+    add_warnings(&mut warnings, &text, &text_lines, &fonts);
+    let glyphs = position_glyphs(
+        &text_lines,
+        &segments,
+        &fonts,
+        point(0.0, baseline),
+        options,
+    )?;
+    // Backgrounds fill the cells of each grapheme
+    let decorations = decorations(
+        &lines(cell_rows),
+        &segments,
+        font,
+        point(0.0, baseline),
+        (baseline, grid.cell_h - baseline),
+    );
+
+    let image_buffer = draw_unframed((width, height), &glyphs, &decorations, options);
+    strict::check((width, height), &glyphs, &warnings, font, options)?;
+
+    frame::apply(image_buffer, options)
+}
+
+/// A grapheme of text, placed in cells of a grid.
+struct Placement {
+    /// Byte range of the grapheme within the text.
+    range: Range<usize>,
+    /// Index of the paragraph of the grapheme.
+    paragraph: usize,
+    row: usize,
+    col: usize,
+    /// Number of cells that the grapheme takes up.
+    cells: usize,
+}
+
+/// Place the graphemes of text in the cells of a grid, up to `max_rows` rows (which may be more than the rows of the grid).
+///
+/// Newlines and line separators start the next row, tabs move to the next tab stop, and other control characters are skipped.
+fn place(
+    text: &str,
+    bidi: &BidiInfo,
+    grid: &GridLayout,
+    options: &Options,
+    max_rows: usize,
+) -> Vec<Placement> {
+    let tab_stops = options.tab_stops.clone().unwrap_or_default();
+    let mut placements = Vec::new();
+    let (mut row, mut col) = (0, 0);
+    'paragraphs: for (paragraph, info) in bidi.paragraphs.iter().enumerate() {
+        for (start, grapheme) in text[info.range.clone()].grapheme_indices(true) {
+            let grapheme = info.range.start + start..info.range.start + start + grapheme.len();
+            let first = text[grapheme.clone()].chars().next().unwrap_or_default();
+            if first == '\t' {
+                let x = tab_stops.next(col as f32 * grid.cell_w, grid.cell_w);
+                // Allow for rounding, so that a tab stop at a cell boundary isn't rounded up to the next cell
+                col = (x / grid.cell_w - 1e-3).ceil().max(col as f32 + 1.0) as usize;
+                continue;
+            }
+            match first.general_category() {
+                GeneralCategory::LineSeparator => {
+                    row += 1;
+                    col = 0;
+                    continue;
+                }
+                GeneralCategory::Control | GeneralCategory::ParagraphSeparator => continue,
+                _ => {}
+            }
+
+            // Rows wrap onto the next row, like a terminal
+            let cells = cells(&text[grapheme.clone()]);
+            if col + cells > grid.cols && col > 0 {
+                row += 1;
+                col = 0;
+            }
+            if row >= max_rows {
+                break 'paragraphs;
+            }
+            placements.push(Placement {
+                range: grapheme,
+                paragraph,
+                row,
+                col,
+                cells,
+            });
+            col += cells;
+        }
+        row += 1;
+        col = 0;
+        if row >= max_rows {
+            break;
+        }
+    }
+    placements
+}
+
+/// Returns the number of cells that a grapheme takes up, which is its display width in a terminal (two for wide
+/// characters and emoji). Graphemes without a width (ex. a mark without a base) take up a cell, where they are drawn.
+fn cells(grapheme: &str) -> usize {
+    grapheme.width().clamp(1, 2)
+}
@@ -0,0 +1,102 @@
+//! Number the lines of text in a gutter to the left of the text.
+
+use crate::layout::{layout, Line};
+use crate::span::{segments, Decoration, DecorationKind};
+use crate::{
+    position_glyphs, Align, Color, Direction, Ink, LineNumbers, Options, RasterizeError, Span,
+};
+use rusttype::{point, Font, PositionedGlyph, Rect, Scale};
+
+/// The numbers of lines in a gutter, and the rule between the gutter and the text.
+pub(crate) struct Gutter<'f> {
+    /// Width of the gutter in whole pixels, which the text is moved to the right by.
+    pub width: f32,
+    pub glyphs: Vec<(PositionedGlyph<'f>, Color, Ink)>,
+    pub rule: Option<Decoration>,
+}
+
+/// Number the lines of text that start a line of the source (ex. after a newline), and position the numbers right-aligned
+/// in a gutter, relative to the baseline of the first line at the ascent of the main font (or [`Options::fixed_ascent`]).
+///
+/// The numbers are separated from the rule and the text by half of the font size, and the rule is a 20th of the font size thick.
+pub(crate) fn gutter<'f>(
+    lines: &[Line],
+    line_numbers: &LineNumbers,
+    font: &Font<'f>,
+    fonts: &[&Font<'f>],
+    options: &Options,
+) -> Result<Gutter<'f>, RasterizeError> {
+    // Numbers are laid out on their own, left-to-right and unwrapped
+    let options = Options {
+        color: line_numbers.color,
+        direction: Direction::Ltr,
+        wrap_width: None,
+        align: Align::Left,
+        first_line_indent: 0.0,
+        hanging_indent: 0.0,
+        tab_stops: None,
+        line_numbers: None,
+        mask: None,
+        effects: Vec::new(),
+        ..options.clone()
+    };
+    let metrics = font.v_metrics(Scale::uniform(options.size));
+    let ascent = options.fixed_ascent.unwrap_or(metrics.ascent);
+
+    // Lines that follow the last line of a paragraph (or a line separator) start a line of the source
+    let mut numbers = Vec::new();
+    let mut number = line_numbers.start;
+    let mut starts_line = true;
+    for line in lines {
+        if starts_line {
+            let spans = [Span::from(number.to_string())];
+            let (text, segments) = segments(&spans, font, &options);
+            let number_lines = layout(&text, &segments, fonts, &options);
+            numbers.push((line.y, number_lines, segments));
+            number = number.saturating_add(1);
+        }
+        starts_line = line.last;
+    }
+    let digits = numbers
+        .iter()
+        .flat_map(|(_, lines, _)| lines.iter().map(|line| line.width))
+        .fold(0.0, f32::max);
+
+    let gap = options.size / 2.0;
+    let thickness = (options.size / 20.0).round().max(1.0);
+    let rule_x = (digits + gap).round();
+    let width = match line_numbers.rule_color {
+        Some(_) => rule_x + thickness + gap,
+        None => digits + gap,
+    }
+    .round();
+
+    let mut glyphs = Vec::new();
+    for (y, number_lines, segments) in &numbers {
+        let x = digits - number_lines.first().map_or(0.0, |line| line.width);
+        glyphs.extend(position_glyphs(
+            number_lines,
+            segments,
+            fonts,
+            point(x, ascent + y),
+            &options,
+        )?);
+    }
+
+    // The rule spans from the top of the first line to the bottom of the last line
+    let bottom = lines.last().map_or(0.0, |line| line.y) + ascent - metrics.descent;
+    let rule = line_numbers.rule_color.map(|color| Decoration {
+        rect: Rect {
+            min: point(rule_x as i32, 0),
+            max: point((rule_x + thickness) as i32, bottom.round() as i32),
+        },
+        color,
+        kind: DecorationKind::Foreground,
+    });
+
+    Ok(Gutter {
+        width,
+        glyphs,
+        rule,
+    })
+}
@@ -0,0 +1,286 @@
+//! Draw text with single-stroke (Hershey) fonts, for engraving and pen plotting.
+
+use crate::channel::Channel;
+use crate::stroke::distance_to_line;
+use crate::{
+    background, check_size, effect, frame, linear, pixel_writer, Blending, GlyphOutline, Ink,
+    Options, RasterizeError,
+};
+use image::{ImageBuffer, Pixel, Rgba};
+use kurbo::{BezPath, PathEl, Point};
+use rusttype::point;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Height of a line of text in the units of Hershey fonts, which is the font size.
+const LINE_HEIGHT: f64 = 32.0;
+/// The baseline of the glyphs of Hershey fonts, in units below their vertical center.
+const BASELINE: f64 = 9.0;
+
+#[derive(Debug, thiserror::Error)]
+pub enum HersheyError {
+    #[error("Failed to read the Hershey font file: {1:?}.")]
+    FileReadError(#[source] std::io::Error, PathBuf),
+    #[error("Malformed glyph on line {0} of the Hershey font.")]
+    MalformedGlyphError(usize),
+}
+
+/// A single-stroke font in the Hershey format, whose glyphs are lines for a pen to follow rather than outlines to fill.
+///
+/// Filled outlines are traced around their edges when they are engraved or plotted, so each stroke of a letter is
+/// drawn twice. The glyphs of Hershey fonts are drawn once, as with handwriting.
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{outline_hershey, rasterize_hershey, write_gcode, EngraveOptions, HersheyFont, Options};
+///
+/// // A font with glyphs for 'H' and 'I' from Roman Simplex, in the order of ASCII from the space
+/// let jhf: String = (' '..='I')
+///     .map(|c| match c {
+///         'H' => "    8  9G]KFK[ RYFY[ RKPYP\n",
+///         'I' => "    9  3NVRFR[\n",
+///         _   => "12345  1JZ\n",
+///     })
+///     .collect();
+/// let font    = HersheyFont::from_jhf(&jhf)?;
+/// let options = Options { size: 64.0, ..Default::default() };
+///
+/// let image = rasterize_hershey("HI", &font, &options)?;
+/// image.save("rasterize_hershey.png")?;
+/// // Each unit of the font is 2 pixels, and the glyphs are 22 and 8 units wide
+/// assert_eq!(image.dimensions(), (60, 64));
+///
+/// // The strokes can also be exported as vectors, which aren't closed
+/// let outlines = outline_hershey("HI", &font, &options);
+/// let mut gcode = Vec::new();
+/// write_gcode(&outlines, &EngraveOptions::default(), &mut gcode)?;
+/// assert_eq!(String::from_utf8(gcode)?.matches("Z-").count(), 4);
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct HersheyFont {
+    glyphs: HashMap<char, HersheyGlyph>,
+}
+
+/// A glyph of a [`HersheyFont`], in the units of the font with y increasing downwards.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HersheyGlyph {
+    /// The number of the glyph in the font.
+    pub id: u16,
+    /// The left edge of the glyph, relative to its center.
+    pub left: i8,
+    /// The right edge of the glyph, relative to its center.
+    pub right: i8,
+    /// The strokes of the glyph, as the points that the pen moves through while it is down.
+    pub strokes: Vec<Vec<(i8, i8)>>,
+}
+
+impl HersheyFont {
+    /// Read a Hershey font from a JHF file [`Path`] (see [`HersheyFont::from_jhf`]).
+    pub fn from_file<P>(path: &P) -> Result<Self, HersheyError>
+    where
+        P: AsRef<Path>,
+    {
+        let data = std::fs::read_to_string(path)
+            .map_err(|e| HersheyError::FileReadError(e, path.as_ref().to_path_buf()))?;
+        Self::from_jhf(&data)
+    }
+
+    /// Read a Hershey font from the text of a JHF file, whose glyphs are in the order of ASCII from the space (`' '`),
+    /// like the fonts that are distributed with plotting software (ex. `futural.jhf`).
+    ///
+    /// Each glyph starts with its number in 5 columns, the number of its points in 3 columns, and its left and right
+    /// edges, followed by pairs of characters that are coordinates relative to `'R'`. A pair of `" R"` lifts the pen.
+    /// Glyphs with many points continue on the following lines.
+    pub fn from_jhf(data: &str) -> Result<Self, HersheyError> {
+        let mut glyphs = HashMap::new();
+        let mut lines = data.lines().enumerate();
+        let mut characters = (' '..='~').chain(std::iter::repeat('\u{FFFD}'));
+        while let Some((number, line)) = lines.next() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let malformed = || HersheyError::MalformedGlyphError(number + 1);
+            let id = line.get(0..5).ok_or_else(malformed)?.trim();
+            let count = line.get(5..8).ok_or_else(malformed)?.trim();
+            let (Ok(id), Ok(count)) = (id.parse::<u16>(), count.parse::<usize>()) else {
+                return Err(malformed());
+            };
+            // The left and right edges are the first pair of characters
+            let mut coordinates: Vec<u8> = line.as_bytes()[8..].to_vec();
+            while coordinates.len() < count * 2 {
+                let (_, line) = lines.next().ok_or_else(malformed)?;
+                coordinates.extend_from_slice(line.as_bytes());
+            }
+            let unit = |byte: u8| (i16::from(byte) - i16::from(b'R')) as i8;
+            let mut pairs = coordinates[..count * 2].chunks(2);
+            let Some(&[left, right]) = pairs.next() else {
+                return Err(malformed());
+            };
+            let mut strokes = vec![Vec::new()];
+            for pair in pairs {
+                match pair {
+                    b" R" => strokes.push(Vec::new()),
+                    &[x, y] => strokes.last_mut().unwrap().push((unit(x), unit(y))),
+                    _ => return Err(malformed()),
+                }
+            }
+            strokes.retain(|stroke| !stroke.is_empty());
+            let glyph = HersheyGlyph {
+                id,
+                left: unit(left),
+                right: unit(right),
+                strokes,
+            };
+            if let Some(character) = characters.next().filter(|c| *c != '\u{FFFD}') {
+                glyphs.insert(character, glyph);
+            }
+        }
+        Ok(HersheyFont { glyphs })
+    }
+
+    /// Returns the glyph of a character, if the font has one.
+    pub fn glyph(&self, character: char) -> Option<&HersheyGlyph> {
+        self.glyphs.get(&character)
+    }
+}
+
+/// Lay out text with a [`HersheyFont`], and return the strokes of its glyphs as open vector paths.
+///
+/// The text is laid out left to right at the [`Options::size`], which is the height of a line, in the
+/// [`Options::color`]. Lines are broken at newlines, and characters that the font doesn't have are skipped.
+/// Other layout options (ex. wrapping and alignment) don't apply to Hershey fonts.
+///
+/// The paths can be written for engraving or plotting (ex. [`write_gcode`](crate::write_gcode)), and are in the pixel
+/// coordinates of the image that [`rasterize_hershey`] draws.
+pub fn outline_hershey(text: &str, font: &HersheyFont, options: &Options) -> Vec<GlyphOutline> {
+    let scale = f64::from(options.size) / LINE_HEIGHT;
+    let mut outlines = Vec::new();
+    for (row, line) in text.split('\n').enumerate() {
+        let baseline = (row as f64 * LINE_HEIGHT + LINE_HEIGHT / 2.0 + BASELINE) * scale;
+        let mut x = 0.0;
+        for character in line.chars() {
+            let Some(glyph) = font.glyph(character) else {
+                log::warn!("The Hershey font has no glyph for {character:?}.");
+                continue;
+            };
+            let left = f64::from(glyph.left);
+            let mut path = BezPath::new();
+            for stroke in &glyph.strokes {
+                for (i, &(px, py)) in stroke.iter().enumerate() {
+                    let point = Point::new(
+                        (f64::from(px) - left) * scale,
+                        (f64::from(py) - BASELINE) * scale,
+                    );
+                    match i {
+                        0 => path.move_to(point),
+                        _ => path.line_to(point),
+                    }
+                }
+            }
+            outlines.push(GlyphOutline {
+                id: glyph.id,
+                position: Point::new(x, baseline),
+                color: options.color,
+                path,
+            });
+            x += f64::from(i16::from(glyph.right) - i16::from(glyph.left)) * scale;
+        }
+    }
+    outlines
+}
+
+/// Rasterize text with a [`HersheyFont`] to an [`ImageBuffer`], by drawing its strokes with a pen.
+///
+/// The text is laid out as with [`outline_hershey`], and the strokes are drawn with the [`Options::stroke_width`],
+/// or a 20th of the [`Options::size`] (at least a pixel) if no width is provided. The background, effects, corners
+/// and border of the [`Options`] are drawn in the same way as with [`rasterize_with_options`](crate::rasterize_with_options).
+pub fn rasterize_hershey(
+    text: &str,
+    font: &HersheyFont,
+    options: &Options,
+) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>, RasterizeError> {
+    let scale = f64::from(options.size) / LINE_HEIGHT;
+    let lines = text.split('\n').count();
+    let width = text
+        .split('\n')
+        .map(|line| {
+            line.chars()
+                .filter_map(|character| font.glyph(character))
+                .map(|glyph| i64::from(glyph.right) - i64::from(glyph.left))
+                .sum::<i64>()
+        })
+        .max()
+        .unwrap_or_default();
+    let width = (width as f64 * scale).ceil().max(0.0) as u64;
+    let height = (lines as f64 * LINE_HEIGHT * scale).ceil().max(0.0) as u64;
+    let (width, height) = check_size(width, height, options)?;
+
+    let outlines = outline_hershey(text, font, options);
+    let image_buffer = match options.blending {
+        Blending::Srgb => paint((width, height), &outlines, options),
+        Blending::Linear => {
+            linear::to_srgb_image(&paint::<u16>((width, height), &outlines, options))
+        }
+    };
+    frame::apply(image_buffer, options)
+}
+
+/// Draw the strokes of glyphs with a pen, and the background and effects of the [`Options`], to an [`ImageBuffer`]
+/// in the color space of the [`Options::blending`].
+fn paint<C: Channel>(
+    (width, height): (u32, u32),
+    outlines: &[GlyphOutline],
+    options: &Options,
+) -> ImageBuffer<Rgba<C>, Vec<C>>
+where
+    Rgba<C>: Pixel<Subpixel = C>,
+{
+    let mut image_buffer = ImageBuffer::<Rgba<C>, Vec<_>>::new(width, height);
+    let pen = options.stroke_width.unwrap_or(options.size / 20.0).max(1.0) / 2.0;
+    let origin = rusttype::Rect {
+        min: point(0, 0),
+        max: point(0, 0),
+    };
+    for outline in outlines {
+        let mut draw = pixel_writer(
+            &mut image_buffer,
+            origin,
+            &outline.color,
+            Ink::First,
+            options,
+        );
+        let mut last = point(0.0_f32, 0.0);
+        for element in outline.positioned_path().elements() {
+            let (PathEl::MoveTo(next) | PathEl::LineTo(next)) = *element else {
+                continue;
+            };
+            let next = point(next.x as f32, next.y as f32);
+            if let PathEl::LineTo(_) = element {
+                // Only the pixels near the line are measured, and pixels that lines share keep the most coverage
+                let columns = (last.x.min(next.x) - pen - 1.0).max(0.0) as u32
+                    ..((last.x.max(next.x) + pen + 1.0).max(0.0) as u32).min(width);
+                let rows = (last.y.min(next.y) - pen - 1.0).max(0.0) as u32
+                    ..((last.y.max(next.y) + pen + 1.0).max(0.0) as u32).min(height);
+                for y in rows {
+                    for x in columns.clone() {
+                        let center = point(x as f32 + 0.5, y as f32 + 0.5);
+                        let v =
+                            (pen + 0.5 - distance_to_line(center, [last, next])).clamp(0.0, 1.0);
+                        if v > 0.0 {
+                            draw(x, y, v);
+                        }
+                    }
+                }
+            }
+            last = next;
+        }
+    }
+    if let Some(background) = &options.background {
+        let blending = options.blending;
+        background::fill(&mut image_buffer, background, 0..width, 0..height, blending);
+    }
+    effect::apply_to_image(&mut image_buffer, options);
+    image_buffer
+}
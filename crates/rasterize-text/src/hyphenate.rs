@@ -0,0 +1,68 @@
+//! Find the positions where words may be hyphenated when wrapping, with language-specific patterns.
+
+use crate::linebreak::Break;
+use crate::LanguageTag;
+use hypher::Lang;
+use std::ops::Range;
+
+/// Hyphenates words with the patterns of a single language.
+pub(crate) struct Hyphenator {
+    lang: Lang,
+}
+
+impl Hyphenator {
+    /// Select the hyphenation patterns for a language, if any are available.
+    pub fn new(lang: &LanguageTag) -> Option<Self> {
+        match to_lang(lang) {
+            Some(lang) => Some(Hyphenator { lang }),
+            None => {
+                log::debug!("No hyphenation patterns are available for language: {lang}");
+                None
+            }
+        }
+    }
+
+    /// Returns the positions within a range of text where words may be hyphenated, in order.
+    ///
+    /// A word is a run of alphabetic characters, so words that are already hyphenated (ex. "hard-nosed")
+    /// are only hyphenated within each part.
+    pub fn breaks(&self, text: &str, range: Range<usize>) -> Vec<Break> {
+        let mut breaks = Vec::new();
+        let mut word_start: Option<usize> = None;
+        let chars = text[range.clone()]
+            .char_indices()
+            .map(|(i, c)| (range.start + i, c));
+        for (i, c) in chars.chain([(range.end, ' ')]) {
+            match (c.is_alphabetic(), word_start) {
+                (true, None) => word_start = Some(i),
+                (false, Some(start)) => {
+                    // Each syllable but the last ends where the word may be hyphenated
+                    let mut index = start;
+                    for syllable in hypher::hyphenate(&text[start..i], self.lang) {
+                        index += syllable.len();
+                        if index < i {
+                            breaks.push(Break {
+                                index,
+                                mandatory: false,
+                                hyphen: true,
+                            });
+                        }
+                    }
+                    word_start = None;
+                }
+                _ => (),
+            }
+        }
+
+        breaks
+    }
+}
+
+/// Convert a [`LanguageTag`] to a language with hyphenation patterns.
+///
+/// Patterns are selected by the language subtag alone, so regional variants share a set of patterns
+/// (ex. `en-GB` uses the patterns of `en`).
+fn to_lang(lang: &LanguageTag) -> Option<Lang> {
+    let language = lang.language().to_ascii_lowercase();
+    Lang::from_iso(language.as_bytes().try_into().ok()?)
+}
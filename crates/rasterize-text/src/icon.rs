@@ -0,0 +1,303 @@
+//! Rasterize a single glyph by its character, id or name, for icon fonts.
+
+use crate::colr::ColorTables;
+use crate::shape::font_data;
+use crate::{draw, strict, Color, Ink, Options, RasterizeError};
+use image::{ImageBuffer, Rgba};
+use rusttype::{point, Font, GlyphId, IntoGlyphId, Scale};
+use rustybuzz::UnicodeBuffer;
+
+#[derive(Debug, thiserror::Error)]
+pub enum IconError {
+    #[error("The font has no icon named {0:?}, as a ligature or a glyph name.")]
+    UnknownIconError(String),
+    #[error("The font has no glyph named {name:?}.{}", similar_names(.similar))]
+    UnknownGlyphNameError { name: String, similar: Vec<String> },
+    #[error("The font has no glyph names, in its post or CFF table.")]
+    NoGlyphNamesError,
+    #[error(transparent)]
+    RasterizeError(#[from] RasterizeError),
+}
+
+/// Maximum number of similar glyph names that are listed when a glyph name is not found.
+const MAX_SIMILAR_NAMES: usize = 5;
+
+/// Rasterize a single glyph of a font to an [`ImageBuffer`] that is cropped to its pixels.
+///
+/// The glyph is chosen by its character (ex. `'\u{F185}'` for the sun of an icon font) or its [`GlyphId`](rusttype::GlyphId)
+/// in the font, and is drawn on its own, without any layout. This is intended for icon fonts (ex. Font Awesome,
+/// Material Symbols or Weather Icons), whose glyphs are pictures rather than letters. Icons that are only reached by
+/// ligatures (ex. `home` in Material Symbols) can be drawn by their name with [`rasterize_icon`].
+///
+/// # Arguments
+///
+/// - `font`: A [`Font`] reference that contains [TrueType](https://en.wikipedia.org/wiki/TrueType) data.
+/// - `glyph`: A [`char`] or a [`GlyphId`](rusttype::GlyphId) of a glyph of the font.
+/// - `size`: Font size in pixels (ex. `50.0`).
+/// - `color`: A [`Color`] that stores RGBA values reflecting the Red, Green, Blue, and Alpha channels.
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{Color, rasterize_glyph, EN_FONT, read_font_bytes};
+/// use rusttype::GlyphId;
+///
+/// let font  = read_font_bytes(EN_FONT)?;
+/// let color = Color { r: 255, g: 170, b: 0, a: 255 };
+///
+/// // A sun for a weather dashboard, by its character
+/// let sun = rasterize_glyph(&font, '☀', 64.0, &color)?;
+/// sun.save("rasterize_glyph.png")?;
+/// assert!(sun.width() > 40 && sun.height() > 40);
+///
+/// // The same glyph, by its id in the font
+/// let id = font.glyph('☀').id();
+/// assert_eq!(rasterize_glyph(&font, GlyphId(id.0), 64.0, &color)?, sun);
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+pub fn rasterize_glyph<G: IntoGlyphId>(
+    font: &Font,
+    glyph: G,
+    size: f32,
+    color: &Color,
+) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>, RasterizeError> {
+    let options = Options {
+        size,
+        color: *color,
+        ..Default::default()
+    };
+    rasterize_glyph_with_options(font, glyph, &options)
+}
+
+/// Rasterize a single glyph of a font to an [`ImageBuffer`] that is cropped to its pixels, as configured by [`Options`].
+///
+/// This is the same as [`rasterize_glyph`], but the glyph is drawn at the [`Options::size`] in the [`Options::color`],
+/// and the options that don't lay out text apply (ex. [`Options::stroke_width`], [`Options::color_palette`],
+/// [`Options::background`] and [`Options::border`]).
+pub fn rasterize_glyph_with_options<G: IntoGlyphId>(
+    font: &Font,
+    glyph: G,
+    options: &Options,
+) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>, RasterizeError> {
+    let glyph = font.glyph(glyph);
+    if glyph.id().0 == 0 {
+        log::warn!("The font has no glyph for the icon, so its missing glyph is drawn.");
+    }
+    // Color glyphs are drawn as their layers, from the bottom layer up (see Options::color_palette)
+    let (layers, ink) = ColorTables::parse(font)
+        .filter(|_| !options.color_palette.monochrome)
+        .and_then(|tables| tables.layers(glyph.id().0, options.color, &options.color_palette))
+        .map(|layers| (layers, Ink::Layer))
+        .unwrap_or_else(|| (vec![(glyph.id().0, options.color)], Ink::First));
+    let scale = Scale::uniform(options.size);
+    let scaled: Vec<_> = layers
+        .into_iter()
+        .map(|(id, color)| (font.glyph(GlyphId(id)).scaled(scale), color))
+        .collect();
+    // The top left corner of the outlines is placed at the origin, so that the image is cropped to the glyph
+    let corner = scaled
+        .iter()
+        .filter_map(|(layer, _)| layer.exact_bounding_box())
+        .filter(|bounds| bounds.min.x.is_finite() && bounds.min.y.is_finite())
+        .map(|bounds| bounds.min)
+        .reduce(|corner, min| point(corner.x.min(min.x), corner.y.min(min.y)))
+        .unwrap_or(point(0.0, 0.0));
+    let positioned: Vec<_> = scaled
+        .into_iter()
+        .map(|(layer, color)| {
+            let position = point(-corner.x, -corner.y);
+            (layer.positioned(position), color, ink)
+        })
+        .collect();
+    let image = draw(&positioned, &[], options)?;
+    strict::check_glyph(image.dimensions(), &positioned, options)?;
+    Ok(image)
+}
+
+/// Returns the id of the glyph of an icon in a font by its name (ex. `home`), if the font has one.
+///
+/// Icon fonts (ex. Material Symbols) replace the letters of an icon's name with its glyph through a ligature, so the
+/// name is shaped with the default features of the font, and resolves to the glyph if it is shaped to a single glyph.
+/// Otherwise, the name is looked up in the names of the glyphs of the font (in the post or CFF table,
+/// ex. `house` in Font Awesome). Fonts that are read with [`Font::try_from_bytes`] can't be shaped, so only
+/// single characters are resolved.
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{icon_glyph, EN_FONT, read_font_bytes};
+///
+/// let font = read_font_bytes(EN_FONT)?;
+/// // The letters 'f' and 'i' are replaced by a ligature, like the name of an icon
+/// assert_eq!(icon_glyph(&font, "fi"), Some(font.glyph('\u{FB01}').id()));
+/// // Glyphs can be found by their names
+/// assert_eq!(icon_glyph(&font, "uni2600"), Some(font.glyph('☀').id()));
+/// assert_eq!(icon_glyph(&font, "not an icon"), None);
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+pub fn icon_glyph(font: &Font, name: &str) -> Option<GlyphId> {
+    let mut chars = name.chars();
+    if let (Some(character), None) = (chars.next(), chars.next()) {
+        let id = font.glyph(character).id();
+        return (id.0 != 0).then_some(id);
+    }
+    let face = font_data(font).and_then(|data| rustybuzz::Face::from_slice(data, 0))?;
+    let mut buffer = UnicodeBuffer::new();
+    buffer.push_str(name);
+    let shaped = rustybuzz::shape(&face, &[], buffer);
+    if let [info] = shaped.glyph_infos() {
+        if info.glyph_id != 0 {
+            return Some(GlyphId(info.glyph_id as u16));
+        }
+    }
+    let id = glyph_names(&face)
+        .find(|(_, glyph_name)| *glyph_name == name)
+        .map(|(id, _)| id);
+    id
+}
+
+/// Returns the id of a glyph in a font by its PostScript name (ex. `uni2600` or `house`), from the post or CFF table.
+///
+/// Icon fonts and their cheat sheets often refer to glyphs by these names. The names are case sensitive, and a name
+/// that is not found returns [`IconError::UnknownGlyphNameError`] with the most similar names in the font, to catch
+/// typos and differences in case or prefixes.
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{glyph_by_name, IconError, EN_FONT, read_font_bytes};
+///
+/// let font = read_font_bytes(EN_FONT)?;
+/// assert_eq!(glyph_by_name(&font, "uni2600")?, font.glyph('☀').id());
+///
+/// let error = glyph_by_name(&font, "Cent").unwrap_err();
+/// assert!(matches!(&error, IconError::UnknownGlyphNameError { similar, .. } if similar[0] == "cent"));
+/// println!("{error}");
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+pub fn glyph_by_name(font: &Font, name: &str) -> Result<GlyphId, IconError> {
+    let face = font_data(font)
+        .and_then(|data| rustybuzz::ttf_parser::Face::parse(data, 0).ok())
+        .ok_or(IconError::NoGlyphNamesError)?;
+    let mut names = glyph_names(&face).peekable();
+    if names.peek().is_none() {
+        return Err(IconError::NoGlyphNamesError);
+    }
+
+    // Names are similar if they differ in a few characters, or if they contain the name, ignoring case
+    let lowercase = name.to_lowercase();
+    let mut similar = Vec::new();
+    for (id, glyph_name) in names {
+        if glyph_name == name {
+            return Ok(id);
+        }
+        let other = glyph_name.to_lowercase();
+        let distance = edit_distance(&lowercase, &other);
+        if distance <= (lowercase.chars().count() / 3).max(1) || other.contains(&lowercase) {
+            similar.push((distance, glyph_name));
+        }
+    }
+    similar.sort();
+    similar.dedup();
+    Err(IconError::UnknownGlyphNameError {
+        name: name.to_string(),
+        similar: similar
+            .into_iter()
+            .take(MAX_SIMILAR_NAMES)
+            .map(|(_, glyph_name)| glyph_name.to_string())
+            .collect(),
+    })
+}
+
+/// Rasterize a glyph of a font by its PostScript name (ex. `uni2600` or `house`) to an [`ImageBuffer`] that is cropped
+/// to its pixels, as configured by [`Options`].
+///
+/// The name is resolved as with [`glyph_by_name`], and the glyph is drawn as with [`rasterize_glyph_with_options`].
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{rasterize_glyph, rasterize_named_glyph, Color, Options, EN_FONT, read_font_bytes};
+///
+/// let font    = read_font_bytes(EN_FONT)?;
+/// let options = Options { size: 64.0, ..Default::default() };
+///
+/// let sun = rasterize_named_glyph(&font, "uni2600", &options)?;
+/// sun.save("rasterize_named_glyph.png")?;
+/// assert_eq!(sun, rasterize_glyph(&font, '☀', 64.0, &Color::default())?);
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+pub fn rasterize_named_glyph(
+    font: &Font,
+    name: &str,
+    options: &Options,
+) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>, IconError> {
+    let glyph = glyph_by_name(font, name)?;
+    Ok(rasterize_glyph_with_options(font, glyph, options)?)
+}
+
+/// Rasterize the glyph of an icon in a font by its name (ex. `home`) to an [`ImageBuffer`] that is cropped to its
+/// pixels, as configured by [`Options`].
+///
+/// The name is resolved as with [`icon_glyph`], and the glyph is drawn as with [`rasterize_glyph_with_options`].
+/// Returns [`IconError::UnknownIconError`] if the font has no icon with the name.
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{rasterize_glyph, rasterize_icon, Color, IconError, Options, EN_FONT, read_font_bytes};
+///
+/// let font    = read_font_bytes(EN_FONT)?;
+/// let options = Options { size: 64.0, ..Default::default() };
+///
+/// let icon = rasterize_icon(&font, "uni2600", &options)?;
+/// assert_eq!(icon, rasterize_glyph(&font, '☀', 64.0, &Color::default())?);
+/// assert!(matches!(rasterize_icon(&font, "unknown", &options), Err(IconError::UnknownIconError(_))));
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+pub fn rasterize_icon(
+    font: &Font,
+    name: &str,
+    options: &Options,
+) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>, IconError> {
+    let glyph =
+        icon_glyph(font, name).ok_or_else(|| IconError::UnknownIconError(name.to_string()))?;
+    Ok(rasterize_glyph_with_options(font, glyph, options)?)
+}
+
+/// Returns the ids and names of the glyphs of a font that have names.
+fn glyph_names<'a>(
+    face: &'a rustybuzz::ttf_parser::Face,
+) -> impl Iterator<Item = (GlyphId, &'a str)> + 'a {
+    (0..face.number_of_glyphs()).filter_map(|id| {
+        face.glyph_name(rustybuzz::ttf_parser::GlyphId(id))
+            .map(|name| (GlyphId(id), name))
+    })
+}
+
+/// Returns the number of characters that are inserted, deleted or substituted to turn one text into the other
+/// (the Levenshtein distance).
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, b) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(a != *b);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Returns the similar names of an [`IconError::UnknownGlyphNameError`] as a sentence, if there are any.
+fn similar_names(similar: &[String]) -> String {
+    match similar.is_empty() {
+        true => String::new(),
+        false => {
+            let names: Vec<String> = similar.iter().map(|name| format!("{name:?}")).collect();
+            format!(" Similar names: {}.", names.join(", "))
+        }
+    }
+}
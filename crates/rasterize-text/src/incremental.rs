@@ -0,0 +1,300 @@
+//! Rasterize text that grows at the end, without laying out the whole text again.
+
+use crate::damage::DirtyRect;
+use crate::layout::{layout, Line};
+use crate::linebreak::break_opportunities;
+use crate::span::{segments, Segment};
+use crate::{
+    check_size, draw_glyph, position_glyphs, Align, Blending, Color, Ink, Options, RasterizeError,
+    Span, TextTransform, Whitespace,
+};
+use image::{imageops, GenericImageView, ImageBuffer, Rgba, SubImage};
+use rusttype::{point, Font, Point, PositionedGlyph, Scale};
+use unicode_normalization::UnicodeNormalization;
+
+type Image = ImageBuffer<Rgba<u8>, Vec<u8>>;
+
+/// Text that is rasterized as it grows, such as a log or a ticker.
+///
+/// Text is added to the end with [`IncrementalText::append`], which only lays out and draws the text since the last
+/// break opportunity (ex. the last word), rather than the whole text. The image is drawn into a buffer that grows
+/// by doubling, so it is only reallocated and copied when the text outgrows the buffer.
+///
+/// The text is laid out as with [`rasterize_with_options`](crate::rasterize_with_options), except that lines are
+/// not wrapped or indented, and are aligned to the left. This is intended for left-to-right text, since text
+/// that is appended is always placed after the text before it. Glyphs are positioned relative to the start of
+/// the text that was laid out again, so their coverage can differ slightly due to floating point rounding.
+///
+/// A second buffer holds the pixels of the text that won't be laid out again, so the rasterized text uses up to
+/// twice the memory of [`rasterize_with_options`](crate::rasterize_with_options).
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{IncrementalText, Options, rasterize_with_options, EN_FONT, read_font_bytes};
+///
+/// let font    = read_font_bytes(EN_FONT)?;
+/// let options = Options { size: 20.0, ..Default::default() };
+/// let mut log = IncrementalText::new(&font, &options);
+///
+/// // Each frame appends the characters that were logged since the last frame
+/// for line in ["INFO Starting server\n", "INFO Listening on port 8080\n", "WARN Slow request"] {
+///     for character in line.chars() {
+///         log.append(&character.to_string())?;
+///     }
+/// }
+/// assert_eq!(log.text(), "INFO Starting server\nINFO Listening on port 8080\nWARN Slow request");
+///
+/// let image = log.image().to_image();
+/// image.save("rasterize_incremental.png")?;
+/// let full  = rasterize_with_options(&log.text(), &font, &options)?;
+/// assert_eq!(image.dimensions(), full.dimensions());
+/// // The glyphs are drawn at the same pixels, whose coverage differs by at most one step due to rounding
+/// for (incremental, full) in image.as_raw().iter().zip(full.as_raw()) {
+///     assert!(incremental.abs_diff(*full) <= 1);
+/// }
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+#[derive(Clone)]
+pub struct IncrementalText<'f> {
+    font: Font<'f>,
+    options: Options,
+    text: String,
+    /// Byte index of the text that is laid out again when text is appended.
+    start: usize,
+    /// Position of the start of the text that is laid out again, relative to the baseline of the first line.
+    origin: Point<f32>,
+    /// Glyphs of the text that is laid out again, which are drawn to the image but not to `committed`.
+    tail: Vec<(PositionedGlyph<'f>, Color, Ink)>,
+    /// Minimum and maximum pixel coordinates of the glyphs in `committed`, as (min x, min y, max x, max y).
+    bounds: [i32; 4],
+    width: u32,
+    height: u32,
+    /// Pixels of the glyphs that won't be laid out again.
+    committed: Image,
+    /// Pixels of all the glyphs, which may be larger than the width and height of the text.
+    image: Image,
+}
+
+impl<'f> IncrementalText<'f> {
+    /// Start rasterizing text with a [`Font`], as configured by [`Options`].
+    ///
+    /// Lines are not wrapped or indented and are aligned to the left, the whitespace and case of the text are
+    /// not transformed, and the text is not masked or filtered, has no background or border and is blended in sRGB,
+    /// regardless of the [`Options`].
+    pub fn new(font: &Font<'f>, options: &Options) -> Self {
+        let options = Options {
+            wrap_width: None,
+            align: Align::Left,
+            first_line_indent: 0.0,
+            hanging_indent: 0.0,
+            whitespace: Whitespace::Preserve,
+            text_transform: TextTransform::None,
+            mask: None,
+            effects: Vec::new(),
+            background: None,
+            corner_radius: 0.0,
+            border: None,
+            blending: Blending::Srgb,
+            ..options.clone()
+        };
+        IncrementalText {
+            font: font.clone(),
+            options,
+            text: String::new(),
+            start: 0,
+            origin: point(0.0, 0.0),
+            tail: Vec::new(),
+            bounds: [0; 4],
+            width: 0,
+            height: 0,
+            committed: Image::new(0, 0),
+            image: Image::new(0, 0),
+        }
+    }
+
+    /// Add text to the end, and draw it to the image.
+    ///
+    /// Returns a [`RasterizeError`] if the image would be larger than the limits of the [`Options`],
+    /// in which case the text is not added.
+    pub fn append(&mut self, text: &str) -> Result<(), RasterizeError> {
+        let length = self.text.len();
+        self.text.extend(text.nfc());
+        let result = self.layout_tail();
+        if result.is_err() {
+            self.text.truncate(length);
+        }
+        result
+    }
+
+    /// Returns the text that has been appended, after Unicode normalization.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Returns the rasterized text, which is the same size as the image from [`rasterize_with_options`](crate::rasterize_with_options).
+    ///
+    /// Use [`SubImage::to_image`] to copy the pixels to an [`ImageBuffer`].
+    pub fn image(&self) -> SubImage<&ImageBuffer<Rgba<u8>, Vec<u8>>> {
+        self.image.view(0, 0, self.width, self.height)
+    }
+
+    /// Lay out the text since the last break opportunity again, and draw it to the image.
+    fn layout_tail(&mut self) -> Result<(), RasterizeError> {
+        let fonts: Vec<&Font> = std::iter::once(&self.font)
+            .chain(self.options.fallback_fonts.iter())
+            .collect();
+        let spans = [Span::from(&self.text[self.start..])];
+        let (text, segments) = segments(&spans, &self.font, &self.options);
+        let lines = layout(&text, &segments, &fonts, &self.options);
+
+        // The text before the last break opportunity won't change when more text is appended,
+        // as long as it starts a glyph that can be used as the origin of the next layout
+        let split = break_opportunities(&text, 0..text.len())
+            .iter()
+            .rev()
+            .find_map(|opportunity| {
+                lines.iter().enumerate().find_map(|(i, line)| {
+                    let glyph = line
+                        .glyphs
+                        .iter()
+                        .find(|glyph| glyph.cluster >= opportunity.index)?;
+                    (glyph.cluster == opportunity.index).then(|| {
+                        let x = if i == 0 { self.origin.x } else { 0.0 };
+                        (
+                            opportunity.index,
+                            point(x + glyph.x, self.origin.y + line.y),
+                        )
+                    })
+                })
+            });
+        let index = split.map_or(0, |(index, _)| index);
+        let committed = self.position(&lines, &segments, &fonts, |cluster| cluster < index)?;
+        let tail = self.position(&lines, &segments, &fonts, |cluster| cluster >= index)?;
+
+        // The image fits the glyphs of all the text, as measured by `draw`
+        let mut bounds = self.bounds;
+        committed
+            .iter()
+            .for_each(|(glyph, ..)| extend(&mut bounds, glyph));
+        let committed_bounds = bounds;
+        tail.iter()
+            .for_each(|(glyph, ..)| extend(&mut bounds, glyph));
+        let [min_x, min_y, max_x, max_y] = bounds.map(i64::from);
+        let (width, height) = check_size(
+            u64::try_from(max_x - min_x).unwrap_or_default(),
+            u64::try_from(max_y - min_y).unwrap_or_default(),
+            &self.options,
+        )?;
+        self.reserve(width, height);
+
+        // Pixels of the previous tail are replaced with the pixels of the text that won't change,
+        // before the new tail is drawn over them
+        for (glyph, color, ink) in &committed {
+            draw_glyph(&mut self.committed, glyph, color, *ink, &self.options);
+        }
+        for (glyph, ..) in &self.tail {
+            restore(&mut self.image, &self.committed, glyph);
+        }
+        for (glyph, color, ink) in committed.iter().chain(&tail) {
+            draw_glyph(&mut self.image, glyph, color, *ink, &self.options);
+        }
+
+        if let Some((index, origin)) = split {
+            self.start += index;
+            self.origin = origin;
+        }
+        self.tail = tail;
+        self.bounds = committed_bounds;
+        self.width = width;
+        self.height = height;
+        Ok(())
+    }
+
+    /// Position the glyphs of lines whose clusters match a predicate.
+    ///
+    /// The first line starts at the origin, and the rest start at the left edge.
+    fn position(
+        &self,
+        lines: &[Line],
+        segments: &[Segment],
+        fonts: &[&Font<'f>],
+        predicate: impl Fn(usize) -> bool,
+    ) -> Result<Vec<(PositionedGlyph<'f>, Color, Ink)>, RasterizeError> {
+        let lines: Vec<Line> = lines
+            .iter()
+            .map(|line| {
+                let mut line = line.clone();
+                line.glyphs.retain(|glyph| predicate(glyph.cluster));
+                line
+            })
+            .collect();
+        let ascent = self
+            .font
+            .v_metrics(Scale::uniform(self.options.size))
+            .ascent;
+        let y = self.origin.y + ascent;
+        let mut glyphs = position_glyphs(
+            &lines[..lines.len().min(1)],
+            segments,
+            fonts,
+            point(self.origin.x, y),
+            &self.options,
+        )?;
+        glyphs.extend(position_glyphs(
+            lines.get(1..).unwrap_or_default(),
+            segments,
+            fonts,
+            point(0.0, y),
+            &self.options,
+        )?);
+        Ok(glyphs)
+    }
+
+    /// Grow the buffers to fit an image of `width` by `height` pixels, doubling their size when possible.
+    fn reserve(&mut self, width: u32, height: u32) {
+        let (capacity_width, capacity_height) = self.image.dimensions();
+        if width <= capacity_width && height <= capacity_height {
+            return;
+        }
+        let grow =
+            |capacity: u32, size: u32, max: u32| size.max(capacity.saturating_mul(2).min(max));
+        let mut capacity = (
+            grow(capacity_width, width, self.options.max_width),
+            grow(capacity_height, height, self.options.max_height),
+        );
+        if u64::from(capacity.0) * u64::from(capacity.1) > self.options.max_pixels {
+            capacity = (width.max(capacity_width), height.max(capacity_height));
+        }
+        log::debug!("Image capacity: {capacity:?}");
+
+        for buffer in [&mut self.committed, &mut self.image] {
+            let mut grown = Image::new(capacity.0, capacity.1);
+            imageops::replace(&mut grown, buffer, 0, 0);
+            *buffer = grown;
+        }
+    }
+}
+
+/// Extend the minimum and maximum pixel coordinates, as (min x, min y, max x, max y), to include a glyph.
+fn extend(bounds: &mut [i32; 4], glyph: &PositionedGlyph) {
+    if let Some(bounding_box) = glyph.pixel_bounding_box() {
+        bounds[0] = bounds[0].min(bounding_box.min.x);
+        bounds[1] = bounds[1].min(bounding_box.min.y);
+        bounds[2] = bounds[2].max(bounding_box.max.x);
+        bounds[3] = bounds[3].max(bounding_box.max.y);
+    }
+}
+
+/// Copy the pixels that a glyph was drawn to from one image to another.
+fn restore(image: &mut Image, source: &Image, glyph: &PositionedGlyph) {
+    let Some(rect) = DirtyRect::of(glyph) else {
+        return;
+    };
+    let (width, height) = image.dimensions();
+    for y in rect.y..rect.y.saturating_add(rect.height).min(height) {
+        for x in rect.x..rect.x.saturating_add(rect.width).min(width) {
+            image.put_pixel(x, y, *source.get_pixel(x, y));
+        }
+    }
+}
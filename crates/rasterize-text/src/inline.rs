@@ -0,0 +1,76 @@
+//! Draw images inline with text, in the advances that the shaper reserved for them (see [`Span::image`]).
+
+use crate::layout::Line;
+use crate::span::{Decoration, DecorationKind, Segment};
+use crate::{Color, ImageAlign, Span};
+use image::RgbaImage;
+use rusttype::{point, Font, Point, Rect, Scale};
+use std::sync::Arc;
+
+/// Returns the [`InlineImage`](crate::InlineImage)s of spans in lines, with the first line's baseline
+/// starting at `origin`.
+pub(crate) fn images(
+    lines: &[Line],
+    segments: &[Segment],
+    spans: &[Span],
+    font: &Font,
+    origin: Point<f32>,
+) -> Vec<Decoration> {
+    let mut decorations = Vec::new();
+    for line in lines {
+        // The other glyphs of an image have no advance, so each image is drawn once
+        for glyph in line.glyphs.iter().filter(|glyph| glyph.advance > 0.0) {
+            let segment = &segments[glyph.segment];
+            let inline = match segment.object {
+                Some(_) => spans[segment.span].image.as_ref(),
+                None => None,
+            };
+            let Some(inline) = inline else {
+                continue;
+            };
+            let metrics = font.v_metrics(Scale::uniform(segment.size));
+            let baseline = origin.y + line.y + glyph.y;
+            let height = inline.image.height() as f32;
+            let top = match inline.align {
+                ImageAlign::Baseline => baseline - height,
+                ImageAlign::Top => baseline - metrics.ascent,
+                ImageAlign::Middle => baseline - (metrics.ascent + metrics.descent + height) / 2.0,
+                ImageAlign::Bottom => baseline - metrics.descent - height,
+            };
+            let position = point(origin.x + glyph.x, top);
+            let image = Arc::new(inline.image.clone());
+            decorations.extend(self::image(image, position, segment.color.a));
+        }
+    }
+    decorations
+}
+
+/// Returns an image as a decoration, with its top left corner at a position, so that it is drawn, measured and framed
+/// with the glyphs, or [`None`] if it is transparent. The image is faded with an alpha.
+pub(crate) fn image(image: Arc<RgbaImage>, position: Point<f32>, alpha: u8) -> Option<Decoration> {
+    // Float to integer casts saturate, so images that are far away are drawn at the edge
+    let origin = point(position.x.round() as i32, position.y.round() as i32);
+    let offset = |start: i32, offset: u32| start.saturating_add(offset.min(i32::MAX as u32) as i32);
+    let mut visible = image
+        .enumerate_pixels()
+        .filter(|(_, _, pixel)| pixel.0[3] > 0)
+        .map(|(x, y, _)| point(offset(origin.x, x), offset(origin.y, y)));
+    let first = visible.next()?;
+    let (min, max) = visible.fold((first, first), |(min, max), p| {
+        (
+            point(min.x.min(p.x), min.y.min(p.y)),
+            point(max.x.max(p.x), max.y.max(p.y)),
+        )
+    });
+    Some(Decoration {
+        rect: Rect {
+            min,
+            max: point(max.x.saturating_add(1), max.y.saturating_add(1)),
+        },
+        color: Color {
+            a: alpha,
+            ..Color::default()
+        },
+        kind: DecorationKind::Image(image, origin),
+    })
+}
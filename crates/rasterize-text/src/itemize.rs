@@ -0,0 +1,70 @@
+//! Split text into runs of a single script and font, so that each run can be shaped independently.
+
+use rusttype::Font;
+use std::ops::Range;
+use unicode_script::{Script, UnicodeScript};
+
+/// A run of text in a single script, that is displayed with a single font.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Run {
+    /// Byte range of the run within the text.
+    pub range: Range<usize>,
+    pub script: Script,
+    /// Index of the font in the font stack, where 0 is the primary font and the rest are fallback fonts.
+    pub font: usize,
+}
+
+/// Split a range of text into runs of a single script and font, in logical order.
+///
+/// Characters in the Common or Inherited scripts (ex. spaces, punctuation, combining marks) join the script of the text around them.
+/// Every other character is displayed with the first font in `fonts` that has a glyph for it,
+/// while Common and Inherited characters stay with the font of the previous character when possible.
+/// Characters that no font supports are assigned to the primary font.
+pub(crate) fn itemize(text: &str, range: Range<usize>, fonts: &[&Font]) -> Vec<Run> {
+    let covers = |font: &Font, c: char| font.glyph(c).id().0 != 0;
+
+    // Assign a script and font to each character
+    let mut chars: Vec<(Range<usize>, Script, usize)> = Vec::new();
+    for (i, c) in text[range.clone()].char_indices() {
+        let start = range.start + i;
+        let previous = chars.last().map(|(_, script, font)| (*script, *font));
+        let neutral = matches!(c.script(), Script::Common | Script::Inherited);
+
+        let script = match (neutral, previous) {
+            (true, Some((script, _))) => script,
+            _ => c.script(),
+        };
+        let font = match previous {
+            Some((_, font)) if neutral && covers(fonts[font], c) => font,
+            _ => fonts.iter().position(|font| covers(font, c)).unwrap_or(0),
+        };
+        chars.push((start..start + c.len_utf8(), script, font));
+    }
+
+    // Neutral characters at the start take the script of the first character that has one
+    if let Some(first) = chars
+        .iter()
+        .map(|(_, script, _)| *script)
+        .find(|script| !matches!(script, Script::Common | Script::Inherited))
+    {
+        chars
+            .iter_mut()
+            .take_while(|(_, script, _)| matches!(script, Script::Common | Script::Inherited))
+            .for_each(|(_, script, _)| *script = first);
+    }
+
+    // Merge consecutive characters with the same script and font into runs
+    let mut runs: Vec<Run> = Vec::new();
+    for (range, script, font) in chars {
+        match runs.last_mut() {
+            Some(run) if run.script == script && run.font == font => run.range.end = range.end,
+            _ => runs.push(Run {
+                range,
+                script,
+                font,
+            }),
+        }
+    }
+
+    runs
+}
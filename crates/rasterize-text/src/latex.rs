@@ -0,0 +1,392 @@
+//! Render LaTeX math with an external TeX installation, inline with regular text.
+
+use crate::background::fill;
+use crate::effect::apply_to_image;
+use crate::frame;
+use crate::mask::whole_text;
+use crate::shape::font_data;
+use crate::span::segments;
+use crate::whitespace::collapse;
+use crate::{
+    check_size, draw, layout, position_glyphs, Blending, Options, RasterizeError, Span, Whitespace,
+};
+use image::{imageops, ImageBuffer, Rgba, RgbaImage};
+use rusttype::{point, Font, Scale};
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[derive(Debug, thiserror::Error)]
+pub enum LatexError {
+    #[error("Failed to run {1:?}. Is a TeX distribution (ex. TeX Live) with latex and dvipng installed?")]
+    CommandError(#[source] std::io::Error, String),
+    #[error("{0} failed to render the math: {1}")]
+    RenderError(String, String),
+    #[error("Failed to read the depth of the rendered math from dvipng: {0:?}")]
+    DepthParseError(String),
+    #[error("Failed to read the rendered math image.")]
+    ImageReadError(#[source] image::ImageError),
+    #[error("Unclosed math at position {0}. Math must be surrounded by '$' (ex. '$x^2$').")]
+    UnclosedMathError(usize),
+    #[error("The math uses {0:?}, which could read or write files, so it isn't rendered.")]
+    ForbiddenCommandError(String),
+    #[error(transparent)]
+    RasterizeError(#[from] RasterizeError),
+}
+
+/// Math rendered by TeX, with the distance in pixels from the baseline to the bottom of the image.
+struct Math {
+    image: RgbaImage,
+    depth: u32,
+}
+
+/// Rasterize text containing LaTeX math to an [`ImageBuffer`], as configured by [`Options`].
+///
+/// Math is surrounded by `$` (ex. `$\frac{a}{b}$`), and a literal dollar sign is written as `\$`.
+/// The math is rendered in the text color by an external TeX installation (`latex` and `dvipng`),
+/// scaled so that its font size matches the text, and placed inline on the baseline of the text.
+///
+/// The text and math are laid out on a single line, so [`Options::wrap_width`] is not used.
+///
+/// Math is often written by users, so TeX can't run shell commands, and can only read and write files in the directory
+/// of each render. Math that uses commands which read or write files (ex. `\input` or `\write`), that can spell or
+/// rename them (ex. `\csname`, `\scantokens` or `\let`), or that pass code on to other programs (ex. `\special`),
+/// returns [`LatexError::ForbiddenCommandError`] without running TeX, and dvipng doesn't run Ghostscript.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use rasterize_text::{rasterize_with_latex, EN_FONT, read_font_bytes};
+///
+/// let text  = r"The roots are $x = \frac{-b \pm \sqrt{b^2 - 4ac}}{2a}$ in general.";
+/// let font  = read_font_bytes(EN_FONT)?;
+/// let image = rasterize_with_latex(text, &font, &Default::default())?;
+/// image.save("rasterize_latex.png")?;
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+///
+/// ```rust
+/// use rasterize_text::{rasterize_with_latex, LatexError, EN_FONT, read_font_bytes};
+///
+/// let font = read_font_bytes(EN_FONT)?;
+/// let texts = [
+///     r"$\input{/etc/passwd}$",
+///     r"$\immediate\write18{rm -rf ~}$",
+///     r"$\csname input\endcsname x$",
+///     r"$\expandafter\scantokens\expandafter{\string\in put{/etc/passwd}}$",
+///     r"$\let\x=\relax \x$",
+///     r"$\special{ps: (/etc/passwd) (r) file}$",
+/// ];
+/// for text in texts {
+///     let error = rasterize_with_latex(text, &font, &Default::default()).unwrap_err();
+///     assert!(matches!(error, LatexError::ForbiddenCommandError(_)));
+/// }
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+pub fn rasterize_with_latex(
+    text: &str,
+    font: &Font,
+    options: &Options,
+) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>, LatexError> {
+    let scale = Scale::uniform(options.size);
+    let metrics = font.v_metrics(scale);
+    let fonts: Vec<&Font> = std::iter::once(font)
+        .chain(options.fallback_fonts.iter())
+        .collect();
+    // Whitespace is collapsed around the math, rather than within each piece of text
+    let text_options = Options {
+        wrap_width: None,
+        whitespace: Whitespace::Preserve,
+        mask: whole_text(options.mask.as_ref()),
+        effects: Vec::new(),
+        background: None,
+        corner_radius: 0.0,
+        border: None,
+        ..options.clone()
+    };
+    let text = collapse(vec![Cow::Borrowed(text)], options.whitespace).concat();
+
+    // Render the math first, so the baseline can be lowered to make room for tall math
+    let pieces = split_math(&text)?;
+    let math = pieces
+        .iter()
+        .map(|(piece, is_math)| match is_math {
+            true => render_latex(piece, font, options).map(Some),
+            false => Ok(None),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let baseline = math
+        .iter()
+        .flatten()
+        .map(|math| (math.image.height() - math.depth) as f32)
+        .fold(metrics.ascent, f32::max);
+
+    // Place the text and math one after the other on the baseline
+    let mut caret = 0.0;
+    let mut glyphs = Vec::new();
+    // Emoji of the text are drawn as images with the twemoji feature
+    #[cfg_attr(not(feature = "twemoji"), allow(unused_mut))]
+    let mut decorations = Vec::new();
+    let mut images = Vec::new();
+    for ((piece, _), math) in pieces.iter().zip(math) {
+        match math {
+            Some(math) => {
+                let y = baseline - (math.image.height() - math.depth) as f32;
+                let width = math.image.width() as f32;
+                images.push((caret, y, math.image));
+                caret += width;
+            }
+            None => {
+                let spans = [Span::from(piece.as_str())];
+                let (piece, segments) = segments(&spans, font, &text_options);
+                let lines = layout::layout(&piece, &segments, &fonts, &text_options);
+                glyphs.extend(position_glyphs(
+                    &lines,
+                    &segments,
+                    &fonts,
+                    point(caret, baseline),
+                    options,
+                )?);
+                #[cfg(feature = "twemoji")]
+                decorations.extend(crate::twemoji::images(
+                    &piece,
+                    &lines,
+                    &segments,
+                    font,
+                    point(caret, baseline),
+                    &text_options,
+                ));
+                caret += lines.first().map_or(0.0, |line| line.width);
+                // Lines don't include trailing whitespace, which is needed before the next math
+                caret += piece[piece.trim_end().len()..]
+                    .chars()
+                    .map(|c| font.glyph(c).scaled(scale).h_metrics().advance_width)
+                    .sum::<f32>();
+            }
+        }
+    }
+
+    // Draw the text, and then overlay the math on a canvas that fits both
+    let text_image = draw(&glyphs, &decorations, &text_options)?;
+    // Float to integer casts saturate, so the sizes can't wrap around
+    let width = images
+        .iter()
+        .map(|(x, _, image)| (x.round() as u64).saturating_add(image.width().into()))
+        .fold(text_image.width().into(), u64::max);
+    let height = images
+        .iter()
+        .map(|(_, y, image)| (y.round() as u64).saturating_add(image.height().into()))
+        .fold(text_image.height().into(), u64::max);
+    let (width, height) = check_size(width, height, options)?;
+    let mut canvas = RgbaImage::new(width, height);
+    imageops::overlay(&mut canvas, &text_image, 0, 0);
+    for (x, y, image) in images {
+        imageops::overlay(&mut canvas, &image, x.round() as i64, y.round() as i64);
+    }
+    // The background, effects without a range and border apply to the math too
+    if let Some(background) = &options.background {
+        fill(&mut canvas, background, 0..width, 0..height, Blending::Srgb);
+    }
+    apply_to_image(&mut canvas, options);
+
+    Ok(frame::apply(canvas, options)?)
+}
+
+/// Split text into pieces of regular text and math, where math is surrounded by `$`.
+///
+/// Returns each piece, and whether it is math.
+fn split_math(text: &str) -> Result<Vec<(String, bool)>, LatexError> {
+    let mut pieces = vec![(String::new(), false)];
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            // An escaped dollar sign is a literal in text, and is passed on to TeX in math
+            '\\' if chars.peek().is_some_and(|(_, c)| *c == '$') => {
+                let (_, dollar) = chars.next().unwrap_or((i, '$'));
+                let (piece, is_math) = pieces.last_mut().expect("pieces is never empty");
+                if *is_math {
+                    piece.push('\\');
+                }
+                piece.push(dollar);
+            }
+            '$' => {
+                let is_math = !pieces.last().is_some_and(|(_, is_math)| *is_math);
+                pieces.push((String::new(), is_math));
+                start = i;
+            }
+            c => pieces.last_mut().expect("pieces is never empty").0.push(c),
+        }
+    }
+    if pieces.last().is_some_and(|(_, is_math)| *is_math) {
+        return Err(LatexError::UnclosedMathError(start));
+    }
+    pieces.retain(|(piece, _)| !piece.is_empty());
+
+    Ok(pieces)
+}
+
+/// Control sequences that read or write files, that can spell other control sequences (ex. `\csname input\endcsname`,
+/// `\inp^^75t` or `\scantokens{\string\in put}`) or give them other names (ex. `\let\x=\relax`), or that pass code on
+/// to other programs (ex. `\special`, which dvipng hands to Ghostscript), which math may not use.
+const FORBIDDEN_COMMANDS: [&str; 22] = [
+    "input",
+    "include",
+    "openin",
+    "openout",
+    "write",
+    "read",
+    "csname",
+    "catcode",
+    "scantokens",
+    "string",
+    "detokenize",
+    "expandafter",
+    "def",
+    "edef",
+    "gdef",
+    "xdef",
+    "let",
+    "futurelet",
+    "special",
+    "immediate",
+    "newread",
+    "newwrite",
+];
+
+/// Returns an error if a LaTeX math expression uses a [forbidden command](FORBIDDEN_COMMANDS).
+fn check_commands(expr: &str) -> Result<(), LatexError> {
+    if expr.contains("^^") {
+        return Err(LatexError::ForbiddenCommandError("^^".into()));
+    }
+    // Control words are a backslash and letters, and are compared by prefix to forbid variants (ex. `\includegraphics`)
+    for (i, _) in expr.match_indices('\\') {
+        let word: String = expr[i + 1..]
+            .chars()
+            .take_while(|c| c.is_ascii_alphabetic())
+            .collect();
+        if FORBIDDEN_COMMANDS
+            .iter()
+            .any(|command| word.starts_with(command))
+        {
+            return Err(LatexError::ForbiddenCommandError(format!("\\{word}")));
+        }
+    }
+    Ok(())
+}
+
+/// Render a LaTeX math expression to an image with `latex` and `dvipng`.
+fn render_latex(expr: &str, font: &Font, options: &Options) -> Result<Math, LatexError> {
+    check_commands(expr)?;
+    // Each render uses its own directory, so renders can run in parallel
+    static RENDERS: AtomicUsize = AtomicUsize::new(0);
+    let id = RENDERS.fetch_add(1, Ordering::Relaxed);
+    let dir = std::env::temp_dir().join(format!("rasterize-text-{}-{id}", std::process::id()));
+    std::fs::create_dir_all(&dir).map_err(|e| LatexError::CommandError(e, "latex".into()))?;
+    let result = render_latex_in(&dir, expr, font, options);
+    if let Err(e) = std::fs::remove_dir_all(&dir) {
+        log::warn!("Failed to remove temporary directory {dir:?}: {e}");
+    }
+    result
+}
+
+fn render_latex_in(
+    dir: &Path,
+    expr: &str,
+    font: &Font,
+    options: &Options,
+) -> Result<Math, LatexError> {
+    let tex: PathBuf = dir.join("math.tex");
+    let document = format!(
+        "\\documentclass[10pt]{{article}}\n\\usepackage{{amsmath,amssymb}}\n\\pagestyle{{empty}}\n\\begin{{document}}\n${expr}$\n\\end{{document}}\n"
+    );
+    std::fs::write(&tex, document).map_err(|e| LatexError::CommandError(e, "latex".into()))?;
+
+    // TeX may only open files in the directory of the render (`p` is paranoid), and can't run shell commands
+    let output = Command::new("latex")
+        .env("openin_any", "p")
+        .env("openout_any", "p")
+        .arg("-no-shell-escape")
+        .arg("-interaction=nonstopmode")
+        .arg("-halt-on-error")
+        .arg("-output-directory")
+        .arg(dir)
+        .arg(&tex)
+        .output()
+        .map_err(|e| LatexError::CommandError(e, "latex".into()))?;
+    if !output.status.success() {
+        // TeX reports errors on lines starting with '!'
+        let log = String::from_utf8_lossy(&output.stdout);
+        let error = log
+            .lines()
+            .filter(|line| line.starts_with('!'))
+            .collect::<Vec<_>>()
+            .join("\n");
+        return Err(LatexError::RenderError("latex".into(), error));
+    }
+
+    // Match the size of the math to the text, where TeX's 10pt font is 10/72.27 inches
+    let em = font_data(font)
+        .and_then(|data| rustybuzz::ttf_parser::Face::parse(data, 0).ok())
+        .map_or(options.size, |face| {
+            font.scale_for_pixel_height(options.size) * face.units_per_em() as f32
+        });
+    let dpi = em * 72.27 / 10.0;
+    let color = options.color;
+    let foreground = format!(
+        "rgb {} {} {}",
+        color.r as f32 / 255.0,
+        color.g as f32 / 255.0,
+        color.b as f32 / 255.0
+    );
+
+    let png = dir.join("math.png");
+    let output = Command::new("dvipng")
+        .args(["-D", &dpi.round().to_string()])
+        // PostScript specials aren't rendered, so Ghostscript (with or without -dSAFER) is never run
+        .arg("--noghostscript")
+        .args([
+            "-T",
+            "tight",
+            "-bg",
+            "Transparent",
+            "-fg",
+            &foreground,
+            "--depth",
+            "-q",
+        ])
+        .arg("-o")
+        .arg(&png)
+        .arg(dir.join("math.dvi"))
+        .output()
+        .map_err(|e| LatexError::CommandError(e, "dvipng".into()))?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(LatexError::RenderError("dvipng".into(), stderr.to_string()));
+    }
+
+    // dvipng reports the depth below the baseline (ex. "depth=5")
+    let depth = stdout
+        .split_whitespace()
+        .find_map(|word| {
+            word.trim_matches(|c| c == '[' || c == ']')
+                .strip_prefix("depth=")
+        })
+        .and_then(|depth| depth.parse::<u32>().ok())
+        .ok_or_else(|| LatexError::DepthParseError(stdout.to_string()))?;
+    let mut image = image::open(&png)
+        .map_err(LatexError::ImageReadError)?
+        .to_rgba8();
+
+    // dvipng has no alpha for the foreground, so apply the alpha of the text color
+    image
+        .pixels_mut()
+        .for_each(|pixel| pixel[3] = (pixel[3] as f32 * color.a as f32 / 255.0) as u8);
+
+    Ok(Math {
+        depth: depth.min(image.height()),
+        image,
+    })
+}
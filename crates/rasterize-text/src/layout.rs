@@ -0,0 +1,283 @@
+//! Lay out text as lines of shaped glyphs, wrapping lines that are too wide.
+
+use crate::linebreak::{break_opportunities, Break};
+use crate::shape::{ShapedGlyph, Shaper};
+use crate::span::Segment;
+use crate::{Align, Direction, Justification, LastLine, Options};
+use rusttype::{Font, Scale};
+use std::ops::Range;
+use unicode_bidi::{BidiInfo, Level};
+
+/// Lay out text as lines of glyphs, from top to bottom.
+///
+/// Each paragraph of the text starts a new line, as does a line separator (`U+2028`).
+/// If [`Options::wrap_width`] is set, paragraphs are also wrapped at the break opportunities
+/// found by [`break_opportunities`], so that each line fits within the width when possible.
+/// A word that is wider than the wrap width by itself is placed on its own line.
+///
+/// With the `hyphenation` feature, words in left-to-right paragraphs can also be broken with a hyphen,
+/// using the patterns for the language in [`Options::lang`].
+///
+/// The glyphs of each line are positioned horizontally, starting at x = 0 plus the paragraph indent,
+/// and then aligned according to [`Options::align`].
+pub(crate) fn layout(
+    text: &str,
+    segments: &[Segment],
+    fonts: &[&Font],
+    options: &Options,
+) -> Vec<Line> {
+    let level = match options.direction {
+        Direction::Auto => None,
+        Direction::Ltr => Some(Level::ltr()),
+        Direction::Rtl => Some(Level::rtl()),
+    };
+    let bidi = BidiInfo::new(text, level);
+    let shaper = Shaper::new(fonts, segments, options);
+    #[cfg(feature = "twemoji")]
+    let shaper = shaper.with_emoji(text);
+
+    // Words are only hyphenated when wrapping, in the language of the text
+    #[cfg(feature = "hyphenation")]
+    let hyphenator = options
+        .wrap_width
+        .and(options.lang.as_ref())
+        .and_then(crate::hyphenate::Hyphenator::new);
+    let scale = Scale::uniform(options.size);
+    let hyphen = fonts[0].glyph('-').scaled(scale);
+    let hyphen_width = hyphen.h_metrics().advance_width;
+
+    // Lines are spaced by the metrics of the main font, with extra space between paragraphs
+    let metrics = fonts[0].v_metrics(scale);
+    let line_height = metrics.ascent - metrics.descent + metrics.line_gap;
+    let mut y = 0.0;
+
+    let mut lines = Vec::new();
+    for (i, paragraph) in bidi.paragraphs.iter().enumerate() {
+        if i > 0 {
+            y += options.paragraph_spacing;
+        }
+
+        // The paragraph separator (ex. a newline) is not displayed
+        let start = paragraph.range.start;
+        let end = start
+            + text[paragraph.range.clone()]
+                .trim_end_matches(is_paragraph_separator)
+                .len();
+
+        // Measure the width of each character by shaping the whole paragraph at once.
+        // Lines are shaped again once they are chosen, since shaping can depend on the context.
+        let mut advances: Vec<(usize, f32)> = shaper
+            .shape_line(text, &bidi, paragraph, start..end)
+            .iter()
+            .map(|glyph| (glyph.cluster, glyph.advance))
+            .collect();
+        advances.sort_by_key(|(cluster, _)| *cluster);
+        let mut offsets = Vec::with_capacity(advances.len() + 1);
+        offsets.push(0.0);
+        for (_, advance) in &advances {
+            offsets.push(offsets.last().unwrap_or(&0.0) + advance);
+        }
+        // Trailing whitespace hangs past the end of the line, so it doesn't count towards the width
+        let width = |range: Range<usize>| {
+            let range = trim_end(text, range);
+            let offset = |i: usize| offsets[advances.partition_point(|(cluster, _)| *cluster < i)];
+            offset(range.end) - offset(range.start)
+        };
+        // The first line of a paragraph has its own indent, and the rest share the hanging indent
+        let indent = |line_start: usize| match line_start == start {
+            true => options.first_line_indent,
+            false => options.hanging_indent,
+        };
+        let fits = |range: Range<usize>, hyphen: bool| {
+            let hyphen_width = if hyphen { hyphen_width } else { 0.0 };
+            let indent = indent(range.start);
+            options
+                .wrap_width
+                .is_none_or(|wrap_width| indent + width(range) + hyphen_width <= wrap_width)
+        };
+
+        #[allow(unused_mut)]
+        let mut opportunities = break_opportunities(text, start..end);
+        #[cfg(feature = "hyphenation")]
+        if let Some(hyphenator) = hyphenator.as_ref().filter(|_| paragraph.level.is_ltr()) {
+            opportunities.extend(hyphenator.breaks(text, start..end));
+            opportunities.sort_by_key(|opportunity| (opportunity.index, opportunity.hyphen));
+            opportunities.dedup_by_key(|opportunity| opportunity.index);
+        }
+
+        // Greedily fill each line with as much text as fits
+        let mut ranges: Vec<(Range<usize>, bool, bool)> = Vec::new();
+        let mut line_start = start;
+        let mut candidate: Option<Break> = None;
+        let last = Break {
+            index: end,
+            mandatory: true,
+            hyphen: false,
+        };
+        for opportunity in opportunities.into_iter().chain([last]) {
+            if let Some(candidate) = candidate {
+                if !fits(line_start..opportunity.index, opportunity.hyphen) {
+                    ranges.push((
+                        trim_end(text, line_start..candidate.index),
+                        candidate.hyphen,
+                        false,
+                    ));
+                    line_start = candidate.index;
+                }
+            }
+            candidate = Some(opportunity);
+            if opportunity.mandatory {
+                ranges.push((trim_end(text, line_start..opportunity.index), false, true));
+                line_start = opportunity.index;
+                candidate = None;
+            }
+        }
+
+        for (range, hyphenated, last) in ranges {
+            log::debug!("Line: {range:?}, {:?}", &text[range.clone()]);
+            let mut glyphs = shaper.shape_line(text, &bidi, paragraph, range.clone());
+            // Words that are broken across lines end with a hyphen
+            if hyphenated {
+                glyphs.push(ShapedGlyph {
+                    id: hyphen.id().0,
+                    font: 0,
+                    segment: glyphs.last().map_or(0, |glyph| glyph.segment),
+                    cluster: range.end,
+                    x: glyphs.iter().map(|glyph| glyph.advance).sum(),
+                    y: 0.0,
+                    advance: hyphen_width,
+                });
+            }
+            let indent = indent(range.start);
+            glyphs.iter_mut().for_each(|glyph| glyph.x += indent);
+            let width = indent + glyphs.iter().map(|glyph| glyph.advance).sum::<f32>();
+            lines.push(Line {
+                glyphs,
+                y,
+                width,
+                last,
+            });
+            y += line_height;
+        }
+    }
+
+    // Lines are aligned within the wrap width, or the width of the longest line
+    let max_width = options
+        .wrap_width
+        .unwrap_or_else(|| lines.iter().map(|line| line.width).fold(0.0, f32::max));
+    match options.align {
+        Align::Left => {}
+        Align::Center => shift(&mut lines, max_width, 0.5),
+        Align::Right => shift(&mut lines, max_width, 1.0),
+        Align::Justify => {
+            let justification = &options.justification;
+            for line in lines.iter_mut() {
+                match (line.last, justification.last_line) {
+                    (true, LastLine::Left) => {}
+                    (true, LastLine::Center) => shift(std::slice::from_mut(line), max_width, 0.5),
+                    (true, LastLine::Right) => shift(std::slice::from_mut(line), max_width, 1.0),
+                    _ => justify(text, line, max_width, justification, options.size),
+                }
+            }
+        }
+    }
+
+    lines
+}
+
+/// A line of glyphs, positioned horizontally in visual (left-to-right) order.
+#[derive(Clone, Debug)]
+pub(crate) struct Line {
+    pub glyphs: Vec<ShapedGlyph>,
+    /// Vertical position of the baseline in pixels, relative to the baseline of the first line.
+    pub y: f32,
+    /// Width of the line in pixels, including the indent but not trailing whitespace.
+    pub width: f32,
+    /// Whether this is the last line of a paragraph, or ends with a line separator.
+    pub last: bool,
+}
+
+impl Line {
+    /// A line of glyphs that were positioned without [`layout`] (ex. in a grid), which is never justified.
+    pub fn new(glyphs: Vec<ShapedGlyph>, y: f32) -> Self {
+        let width = glyphs
+            .iter()
+            .map(|glyph| glyph.x + glyph.advance)
+            .fold(0.0, f32::max);
+        Line {
+            glyphs,
+            y,
+            width,
+            last: true,
+        }
+    }
+}
+
+/// Move lines to the right by a fraction of the space between their width and a width (ex. a half to center them).
+///
+/// Lines that are wider than the width (ex. a long word that doesn't fit the wrap width) stay at the left edge.
+fn shift(lines: &mut [Line], width: f32, fraction: f32) {
+    for line in lines {
+        let offset = ((width - line.width) * fraction).max(0.0);
+        line.glyphs.iter_mut().for_each(|glyph| glyph.x += offset);
+        line.width += offset;
+    }
+}
+
+/// Stretch a line to fill `max_width`, by adding space between words and then between letters.
+///
+/// The extra space is limited by the [`Justification`], so a line with too few words is left short
+/// rather than being stretched into wide gaps. Letters are only spaced apart between clusters,
+/// so ligatures and combining marks stay together.
+fn justify(text: &str, line: &mut Line, max_width: f32, justification: &Justification, size: f32) {
+    let is_space = |glyph: &ShapedGlyph| {
+        text.get(glyph.cluster..)
+            .and_then(|s| s.chars().next())
+            .is_some_and(char::is_whitespace)
+    };
+    let spaces = line.glyphs.iter().filter(|glyph| is_space(glyph)).count();
+    let gaps = line
+        .glyphs
+        .windows(2)
+        .filter(|pair| pair[0].cluster != pair[1].cluster)
+        .count();
+
+    // Space is first added between words, and only the remainder between letters
+    let extra = (max_width - line.width).max(0.0);
+    let word_stretch = match spaces {
+        0 => 0.0,
+        n => (extra / n as f32).min(justification.max_word_stretch * size),
+    };
+    let extra = extra - word_stretch * spaces as f32;
+    let letter_stretch = match gaps {
+        0 => 0.0,
+        n => (extra / n as f32).min(justification.max_letter_stretch * size),
+    };
+    log::debug!("Justification: word stretch {word_stretch}, letter stretch {letter_stretch}");
+
+    let mut offset = 0.0;
+    for i in 0..line.glyphs.len() {
+        line.glyphs[i].x += offset;
+        if is_space(&line.glyphs[i]) {
+            offset += word_stretch;
+        }
+        if line
+            .glyphs
+            .get(i + 1)
+            .is_some_and(|next| next.cluster != line.glyphs[i].cluster)
+        {
+            offset += letter_stretch;
+        }
+    }
+    line.width += offset;
+}
+
+/// Returns whether a character separates paragraphs in the Unicode Bidirectional Algorithm (ex. a newline).
+fn is_paragraph_separator(c: char) -> bool {
+    matches!(c, '\n' | '\r' | '\u{1C}'..='\u{1E}' | '\u{85}' | '\u{2029}')
+}
+
+/// Returns a range of text without the whitespace at its end, including line separators.
+fn trim_end(text: &str, range: Range<usize>) -> Range<usize> {
+    range.start..range.start + text[range].trim_end().len()
+}
@@ -17,7 +17,7 @@
 //! let color = Color { r: 255, g: 0, b: 0, a: 255 };  // An opaque red color for the text.
 //!
 //! // Rasterize the text to pixels.
-//! let image = rasterize(&text, &font, size, &color);
+//! let image = rasterize(&text, &font, size, &color)?;
 //!
 //! // Get some stats
 //! assert_eq!(image.height(), 45 );
@@ -43,7 +43,7 @@
 //! let color     = Color { r: 0, g: 0, b: 255, a: 212 };                       // A transparent blue color for the text.
 //!
 //! // Rasterize the text to pixels.
-//! let image = rasterize(&text, &font, size, &color);
+//! let image = rasterize(&text, &font, size, &color)?;
 //!
 //! // Get some stats
 //! assert_eq!(image.height(), 43 );
@@ -55,32 +55,442 @@
 //! ```
 //!
 //! ![image](../../../assets/fonts/noto/NotoSansKR.png)
+//!
+//! ## Arabic
+//!
+//! This example demonstrates how to rasterize Arabic text to a pixel image.
+//! Arabic is written right-to-left, and the letters of a word join together, which is handled by the text shaping.
+//! It uses an Arabic font (Amiri) that is loaded from a local file path, and is also vendored within the crate binary as [`AR_FONT`] with the `arabic` feature.
+//!
+//! ```rust
+//! use rasterize_text::{Color, Direction, read_font_file, rasterize};
+//!
+//! // Configure aesthetics
+//! let text      = "مرحبا بالعالم";                                               // A single-line of text to rasterize.
+//! let font      = read_font_file(&"../../assets/fonts/amiri/Amiri-Regular.ttf")?; // Load a local Arabic font
+//! let size      = 50.0;                                                          // A font-size in pixels.
+//! let color     = Color { r: 0, g: 128, b: 0, a: 255 };                          // An opaque green color for the text.
+//!
+//! // The right-to-left direction is detected automatically
+//! assert_eq!(Direction::Auto.resolve(text), Direction::Rtl);
+//!
+//! // Rasterize the text to pixels.
+//! let image = rasterize(&text, &font, size, &color)?;
+//!
+//! // Get some stats
+//! assert_eq!(image.height(), 47 );
+//! assert_eq!(image.width(), 109 );
+//!
+//! // Save image to file
+//! image.save("rasterize_ar.png")?;
+//! # Ok::<(), color_eyre::eyre::Report>(())
+//! ```
+//!
+//! ![image](../../../assets/fonts/amiri/Amiri.png)
+//!
+//! # Determinism
+//!
+//! The same text, fonts and [`Options`] rasterize to bit-identical images on every platform and architecture,
+//! so images can be cached by a hash of their inputs. Layout, shaping and drawing use integers and the basic operations
+//! of IEEE 754 floats (ex. `+`, `*`, `/`, square roots and rounding), which give the same results on every platform
+//! that Rust supports with SSE2 or an equivalent, since Rust never fuses or reorders them. Targets that compute floats
+//! with the x87 FPU (ex. `i586`) can differ.
+//!
+//! A few features use functions of the math library of the platform (ex. `powf`, `sin`), whose last bit can differ
+//! between platforms, so that some of their pixels can differ by one step:
+//!
+//! - [`Blending::Linear`], which converts colors between sRGB and linear light.
+//! - [`Filter::Blur`] and [`Mask::Blur`], whose kernels are computed by the `image` crate.
+//! - [`Background::Gradient`]s at angles that aren't multiples of 90 degrees.
+//! - CSS colors in the `lab()`, `lch()`, `oklab()` and `oklch()` color spaces, which are converted to sRGB when they are parsed.
+//! - Math that is rendered by an external TeX installation, with the `latex` feature.
+//! - Comparisons with text that is rendered by an external Pango installation, with the `pango` feature.
+//! - Glyphs that are SVG documents, which are rendered by resvg with the `svg` feature.
+//!
+//! Everything else is covered by snapshot tests, which compare the hashes of images with hashes of images that were
+//! rasterized on x86_64 Linux.
+//!
+//! ```rust
+//! use rasterize_text::{
+//!     parse_ansi, rasterize, rasterize_rgba16, rasterize_spans, rasterize_with_options, read_font_bytes,
+//!     read_font_file, Align, Background, Border, Color, Effect, Filter, Options, PixelSnap, EN_FONT,
+//! };
+//! use image::{ImageBuffer, Pixel};
+//! use siphasher::sip128::SipHasher13;
+//!
+//! // A hash of the width, height and pixels of an image
+//! fn hash<P: Pixel<Subpixel = S>, S: Copy + Into<u64>>(image: &ImageBuffer<P, Vec<S>>) -> String {
+//!     let mut bytes = [image.width(), image.height()].map(u32::to_le_bytes).concat();
+//!     bytes.extend(image.as_raw().iter().flat_map(|&channel| channel.into().to_le_bytes()));
+//!     format!("{:032x}", SipHasher13::new().hash(&bytes).as_u128())
+//! }
+//!
+//! let en = read_font_bytes(EN_FONT)?;
+//! let kr = read_font_file(&"../../assets/fonts/noto/NotoSansKR.ttf")?;
+//! let black = Color { r: 0, g: 0, b: 0, a: 255 };
+//!
+//! // Text at a fractional size
+//! let plain = rasterize(&"The quick brown fox jumps over the lazy dog.", &en, 17.5, &black)?;
+//!
+//! // Wrapped and justified text at quarter pixel positions, on paper, with effects and a frame
+//! let options = Options {
+//!     size: 23.5,
+//!     color: Color { r: 0, g: 96, b: 128, a: 200 },
+//!     fallback_fonts: vec![kr.clone()],
+//!     wrap_width: Some(300.0),
+//!     align: Align::Justify,
+//!     pixel_snap: PixelSnap::Quarters,
+//!     background: Some(Background::Paper { color: Color { r: 250, g: 245, b: 230, a: 255 }, seed: 7 }),
+//!     effects: vec![
+//!         Effect { filter: Filter::Pixelate(3), range: Some(4..9) },
+//!         Effect { filter: Filter::Desaturate, range: None },
+//!     ],
+//!     corner_radius: 6.5,
+//!     border: Some(Border { width: 1.5, color: black }),
+//!     ..Default::default()
+//! };
+//! let text = "Typography is the craft of endowing human language with a durable visual form. 타이포그래피";
+//! let styled = rasterize_with_options(&text, &en, &options)?;
+//! // Glyphs that are drawn from the cache of quarter pixel positions are the same every time
+//! assert_eq!(styled, rasterize_with_options(&text, &en, &options)?);
+//!
+//! // Bold, underlined and highlighted spans, over a vertical gradient with noise
+//! let spans = parse_ansi("\x1b[1mbold\x1b[0m \x1b[4;31munderlined\x1b[0m \x1b[42;97mhighlighted\x1b[0m");
+//! let gradient = Options {
+//!     background: Some(Background::Gradient {
+//!         start: Color { r: 255, g: 255, b: 255, a: 255 },
+//!         end:   Color { r: 200, g: 220, b: 255, a: 255 },
+//!         angle: 90.0,
+//!     }),
+//!     ..Default::default()
+//! };
+//! let spans = rasterize_spans(&spans, &en, &gradient)?;
+//! let noise = Options {
+//!     background: Some(Background::Noise { color: Color { r: 40, g: 40, b: 40, a: 255 }, amount: 0.1, seed: 42 }),
+//!     color: Color { r: 255, g: 200, b: 0, a: 255 },
+//!     ..Default::default()
+//! };
+//! let korean = rasterize_with_options(&"제 눈에 안경이다", &kr, &noise)?;
+//!
+//! // 16 bits per channel
+//! let rgba16 = rasterize_rgba16(&"Hello, World!", &en, &Options { size: 31.25, ..Default::default() })?;
+//!
+//! assert_eq!(hash(&plain),  "22c4012be11d1e1433085eb11a15aaae");
+//! assert_eq!(hash(&styled), "c4a704356f82e8ee5571ebeeeafe3572");
+//! assert_eq!(hash(&spans),  "c04a93d0df793e1846ff03d9fe850416");
+//! assert_eq!(hash(&korean), "ee9d093263c904e5926b6c4132f9176e");
+//! assert_eq!(hash(&rgba16), "002116ed14174a58efba6e47534744e1");
+//! # Ok::<(), color_eyre::eyre::Report>(())
+//! ```
+
+pub mod ansi;
+pub mod autostyle;
+mod background;
+pub mod bubble;
+mod cache;
+pub mod card;
+pub mod calendar;
+pub mod caret;
+mod channel;
+pub mod chat;
+pub mod clock;
+pub mod color;
+mod colr;
+pub mod column;
+pub mod compare;
+pub mod compose;
+pub mod coverage;
+pub mod damage;
+pub mod diff;
+mod effect;
+#[cfg(feature = "emoji")]
+mod emoji;
+pub mod encode;
+pub mod engrave;
+pub mod estimate;
+mod frame;
+pub mod grid;
+mod gutter;
+pub mod hershey;
+#[cfg(feature = "hyphenation")]
+mod hyphenate;
+pub mod icon;
+pub mod incremental;
+mod inline;
+mod itemize;
+#[cfg(feature = "latex")]
+pub mod latex;
+mod layout;
+pub mod license;
+mod linear;
+mod linebreak;
+pub mod list;
+#[cfg(feature = "icu")]
+pub mod locale;
+mod mask;
+pub mod math;
+pub mod metadata;
+pub mod metrics;
+pub mod options;
+pub mod outline;
+#[cfg(feature = "pango")]
+pub mod pango;
+pub mod rgba16;
+mod shape;
+pub mod sink;
+pub mod span;
+pub mod spoiler;
+pub mod store;
+pub mod strict;
+mod stroke;
+pub mod subset;
+#[cfg(feature = "svg")]
+mod svg;
+#[cfg(feature = "template")]
+pub mod template;
+mod tofu;
+mod transform;
+#[cfg(feature = "twemoji")]
+mod twemoji;
+pub mod untrusted;
+pub mod vendored;
+pub mod waterfall;
+mod whitespace;
 
-use image::{ImageBuffer, Rgba};
-use log;
-use rusttype::{point, Font, Scale};
+#[doc(inline)]
+pub use crate::ansi::parse_ansi;
+#[doc(inline)]
+pub use crate::autostyle::{auto_style, AutoStyle, AutoStyleError};
+#[doc(inline)]
+pub use crate::bubble::{
+    rasterize_bubble, rasterize_bubble_spans, Bubble, BubbleFrame, Side, Tail,
+};
+#[doc(inline)]
+pub use crate::card::{rasterize_card, rasterize_card_spans, Anchor, Card, Fit};
+#[doc(inline)]
+pub use crate::calendar::{rasterize_calendar, Calendar, WeekStart};
+#[doc(inline)]
+pub use crate::caret::TextLayout;
+#[doc(inline)]
+pub use crate::chat::{chat_message, ChatStyle};
+#[doc(inline)]
+pub use crate::clock::{rasterize_clock, Clock};
+#[doc(inline)]
+pub use crate::color::{Color, ColorError};
+#[doc(inline)]
+pub use crate::column::{rasterize_number_column, NumberColumn};
+#[doc(inline)]
+pub use crate::compare::{compare_images, rasterize_comparison, CompareStyle, Comparison};
+#[doc(inline)]
+pub use crate::compose::{compose, Column, CrossAlign, Element, Grid, Row};
+#[doc(inline)]
+pub use crate::coverage::{rasterize_coverage, CoverageChart};
+#[doc(inline)]
+pub use crate::damage::{rasterize_changes, DirtyRect};
+#[doc(inline)]
+pub use crate::diff::{diff_spans, rasterize_diff, DiffLayout, DiffStyle};
+#[doc(inline)]
+pub use crate::encode::{
+    save_png, save_png16, write_png, write_png16, AltText, ColorTag, EncodeError, PngOptions,
+    RenderMetadata,
+};
+#[doc(inline)]
+pub use crate::engrave::{
+    write_dxf, write_gcode, write_hpgl, EngraveOptions, PaperSize, PlotOptions,
+};
+#[doc(inline)]
+pub use crate::estimate::{estimate_buffer_size, estimate_buffer_size_with_options};
+#[doc(inline)]
+pub use crate::grid::{rasterize_grid, rasterize_grid_spans, GridLayout};
+#[doc(inline)]
+pub use crate::hershey::{
+    outline_hershey, rasterize_hershey, HersheyError, HersheyFont, HersheyGlyph,
+};
+#[doc(inline)]
+pub use crate::icon::{
+    glyph_by_name, icon_glyph, rasterize_glyph, rasterize_glyph_with_options, rasterize_icon,
+    rasterize_named_glyph, IconError,
+};
+#[doc(inline)]
+pub use crate::incremental::IncrementalText;
+#[cfg(feature = "latex")]
+#[doc(inline)]
+pub use crate::latex::{rasterize_with_latex, LatexError};
+#[doc(inline)]
+pub use crate::license::{font_info, Embedding, FontInfo, LicensePolicy};
+#[doc(inline)]
+pub use crate::list::{rasterize_list, List, ListItem, Marker};
+#[cfg(feature = "icu")]
+#[doc(inline)]
+pub use crate::locale::{format_currency, format_date, format_number, DateLength, LocaleError};
+#[doc(inline)]
+pub use crate::math::{parse_math, MathError};
+#[doc(inline)]
+pub use crate::metadata::{content_hash, rasterize_with_metadata, CoverageWarning, RasterizedText};
+#[doc(inline)]
+pub use crate::metrics::{layout_iter, GlyphMetrics, LayoutIter};
+#[doc(inline)]
+pub use crate::options::{
+    Align, Background, Blending, Border, ColorPalette, Direction, Effect, Filter, Justification,
+    LanguageTag, LastLine, LineNumbers, Mask, Options, OptionsError, PixelSnap, Selection,
+    TabStops, TabUnit, TextTransform, Tofu, TofuStyle, Whitespace,
+};
+#[doc(inline)]
+pub use crate::outline::{outline_spans, outline_text, GlyphOutline};
+#[cfg(feature = "pango")]
+#[doc(inline)]
+pub use crate::pango::{compare_with_pango, PangoComparison, PangoError};
+#[doc(inline)]
+pub use crate::rgba16::{rasterize_rgba16, rasterize_rgba16_spans};
+#[doc(inline)]
+pub use crate::sink::{DirectorySink, OutputSink};
+#[cfg(feature = "object_store")]
+#[doc(inline)]
+pub use crate::sink::ObjectStoreSink;
+#[doc(inline)]
+#[doc(inline)]
+pub use crate::span::{
+    rasterize_spans, rasterize_spans_with_metadata, ImageAlign, InlineImage, Position, Span, Style,
+};
+#[doc(inline)]
+pub use crate::spoiler::{parse_spoilers, rasterize_spoilers, SpoilerLayers};
+#[doc(inline)]
+pub use crate::store::{
+    rasterize_cached, rasterize_spans_cached, CacheStore, CachedText, DiskCache, MemoryCache,
+    RenderCacheError, RenderKey,
+};
+#[doc(inline)]
+pub use crate::strict::StrictError;
+#[doc(inline)]
+pub use crate::subset::{subset_font, SubsetError};
+#[cfg(feature = "template")]
+#[doc(inline)]
+pub use crate::template::{
+    render_template, render_template_with_data, Template, TemplateElement, TemplateEngine,
+    TemplateError,
+};
+#[doc(inline)]
+pub use crate::untrusted::{read_untrusted_font, FontLimits, UntrustedFontError};
+#[doc(inline)]
+pub use crate::vendored::VendoredFont;
+#[doc(inline)]
+pub use crate::waterfall::{rasterize_waterfall, Waterfall};
+
+use crate::cache::{GlyphCache, SUBPIXEL_VARIANTS};
+use crate::channel::Channel;
+use crate::span::{Decoration, DecorationKind};
+use image::{ImageBuffer, Pixel, Rgba};
+use rusttype::{point, Font, GlyphId, Point, PositionedGlyph, Scale};
+use rustybuzz::ttf_parser::opentype_layout::{ChainedContextLookup, ContextLookup, Coverage};
 //use std::error::Error;
 use std::fmt::Debug;
 use std::path::{Path, PathBuf};
-use std::str::FromStr;
-use unicode_normalization::UnicodeNormalization;
 
 // Embed fonts at compile time, so that their is a universal fallback
-// See license in assets/fonts/*/LICENSE
+// See the registry of vendored fonts, and the license in assets/fonts/*/LICENSE
 
 /// English font DejaVu Sans is provided within the application (vendored).
-pub const EN_FONT: &[u8] = include_bytes!("../../../assets/fonts/dejavu/DejaVuSans.ttf");
+pub const EN_FONT: &[u8] = VendoredFont::DejaVuSans.bytes();
 /// English bold font DejaVu Sans Bold is provided within the application (vendored).
-pub const EN_BOLD_FONT: &[u8] = include_bytes!("../../../assets/fonts/dejavu/DejaVuSans-Bold.ttf");
+pub const EN_BOLD_FONT: &[u8] = VendoredFont::DejaVuSansBold.bytes();
 /// Korean font Noto Sans is provided within the application (vendored).
-pub const KR_FONT: &[u8] = include_bytes!("../../../assets/fonts/noto/NotoSansKR.ttf");
+pub const KR_FONT: &[u8] = VendoredFont::NotoSansKR.bytes();
+/// Arabic font Amiri is provided within the application (vendored), when the `arabic` feature is enabled.
+///
+/// # Examples
+///
+/// The letters of an Arabic word join together, which makes the word narrower than its isolated letters
+/// (separated here by a zero width non-joiner).
+///
+/// ```rust
+/// use rasterize_text::{Color, rasterize, read_font_bytes, AR_FONT};
+///
+/// let font     = read_font_bytes(AR_FONT)?;
+/// let color    = Color::default();
+/// let joined   = rasterize(&"مرحبا", &font, 50.0, &color)?;
+/// let isolated = rasterize(&"م\u{200C}ر\u{200C}ح\u{200C}ب\u{200C}ا", &font, 50.0, &color)?;
+///
+/// assert_eq!(joined.width(), 54);
+/// assert_eq!(isolated.width(), 74);
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+#[cfg(feature = "arabic")]
+pub const AR_FONT: &[u8] = VendoredFont::Amiri.bytes();
+
+/// An error from rasterizing text.
+///
+/// # Examples
+///
+/// Text at a huge font size would need more memory than the limits in [`Options`] allow.
+///
+/// ```rust
+/// use rasterize_text::{Color, RasterizeError, rasterize, EN_FONT, read_font_bytes};
+///
+/// let font   = read_font_bytes(EN_FONT)?;
+/// let result = rasterize(&"Hello", &font, 100_000.0, &Color::default());
+/// assert!(matches!(result, Err(RasterizeError::TooLarge { .. })));
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+///
+/// Glyphs that are placed far from the origin, or at positions that aren't finite, also return an error
+/// or are skipped, rather than overflowing the pixel coordinates.
+///
+/// ```rust
+/// use rasterize_text::{Options, RasterizeError, rasterize_with_options, EN_FONT, read_font_bytes};
+///
+/// let font = read_font_bytes(EN_FONT)?;
+/// let far  = Options { first_line_indent: -1e30, ..Default::default() };
+/// let tall = Options { paragraph_spacing: f32::INFINITY, ..Default::default() };
+/// for options in [far, tall] {
+///     let result = rasterize_with_options(&"Hello\nWorld", &font, &options);
+///     assert!(matches!(result, Err(RasterizeError::TooLarge { .. })));
+/// }
+///
+/// // A negative or NaN size has no pixels to draw
+/// for size in [-50.0, f32::NAN] {
+///     let image = rasterize_with_options(&"Hello", &font, &Options { size, ..Default::default() })?;
+///     assert_eq!((image.width(), image.height()), (0, 0));
+/// }
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+///
+/// Text with more glyphs than the limit is an error before it is drawn, even if its image is small.
+///
+/// ```rust
+/// use rasterize_text::{Options, RasterizeError, rasterize_with_options, EN_FONT, read_font_bytes};
+///
+/// let font    = read_font_bytes(EN_FONT)?;
+/// let options = Options { size: 2.0, max_glyphs: 1000, ..Default::default() };
+/// let result  = rasterize_with_options(&"a".repeat(1001), &font, &options);
+/// assert!(matches!(result, Err(RasterizeError::TooManyGlyphs { glyphs: 1001, max_glyphs: 1000 })));
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+#[derive(Debug, thiserror::Error)]
+pub enum RasterizeError {
+    #[error("The image would be {width}x{height} pixels, which is larger than the limits of {max_width}x{max_height} pixels and {max_pixels} pixels in total.")]
+    TooLarge {
+        width: u64,
+        height: u64,
+        max_width: u32,
+        max_height: u32,
+        max_pixels: u64,
+    },
+    #[error("The text has {glyphs} glyphs, which is more than the limit of {max_glyphs} glyphs.")]
+    TooManyGlyphs { glyphs: usize, max_glyphs: usize },
+    #[error("Strict mode: {0}")]
+    Strict(#[from] StrictError),
+    #[error("The font {0:?} has restricted embedding permissions, so it must not be used without the permission of its owner.")]
+    RestrictedFont(String),
+}
 
 #[derive(Debug, thiserror::Error)]
 pub enum FontError {
-    #[error("Failed to read the font file: {1:?}.\nNote: The current working directory is: {:?}", std::env::current_dir().unwrap_or(PathBuf::new()))]
+    #[error("Failed to read the font file: {1:?}.\nNote: The current working directory is: {:?}", std::env::current_dir().unwrap_or_default())]
     FileReadError(#[source] std::io::Error, PathBuf),
     #[error("Failed to read font bytes.")]
     BytesReadError,
+    #[error("The font is malformed: {0}.")]
+    MalformedFontError(String),
 }
 
 /// Read [TrueType](https://en.wikipedia.org/wiki/TrueType) [`Font`] data from a file [`Path`].
@@ -104,13 +514,15 @@ pub enum FontError {
 /// let font = rasterize_text::read_font_file(&std::path::PathBuf::from(path))?;
 /// # Ok::<(), color_eyre::eyre::Report>(())
 /// ```
-pub fn read_font_file<P>(path: &P) -> Result<Font, FontError>
+pub fn read_font_file<P>(path: &P) -> Result<Font<'static>, FontError>
 where
     P: AsRef<Path>,
 {
     let font_bytes = std::fs::read(path)
         .map_err(|e| FontError::FileReadError(e, path.as_ref().to_path_buf()))?;
-    Font::try_from_vec(font_bytes).ok_or(FontError::BytesReadError)
+    Font::try_from_vec(font_bytes)
+        .ok_or(FontError::BytesReadError)
+        .and_then(validate_font)
 }
 
 /// Read [TrueType](https://en.wikipedia.org/wiki/TrueType) [`Font`] data from [`u8`] bytes.
@@ -140,13 +552,127 @@ where
 /// let font = rasterize_text::read_font_bytes(&bytes)?;
 /// # Ok::<(), color_eyre::eyre::Report>(())
 /// ```
-pub fn read_font_bytes(bytes: &[u8]) -> Result<Font, FontError> {
-    Font::try_from_vec(bytes.to_vec()).ok_or(FontError::BytesReadError)
+pub fn read_font_bytes(bytes: &[u8]) -> Result<Font<'static>, FontError> {
+    Font::try_from_vec(bytes.to_vec())
+        .ok_or(FontError::BytesReadError)
+        .and_then(validate_font)
+}
+
+/// Check that the tables of a font are consistent, since [`rusttype`] and [`rustybuzz`] panic on some malformed fonts.
+///
+/// Every glyph needs horizontal metrics, every character in the character map must map to a glyph in the font, and
+/// the coverage tables of the substitution and positioning lookups must be readable and in order.
+fn validate_font(font: Font<'static>) -> Result<Font<'static>, FontError> {
+    use rustybuzz::ttf_parser::gpos::PositioningSubtable;
+    use rustybuzz::ttf_parser::gsub::SubstitutionSubtable;
+    use rustybuzz::ttf_parser::{Face, GlyphId};
+
+    let face = shape::font_data(&font)
+        .and_then(|data| Face::parse(data, 0).ok())
+        .ok_or(FontError::BytesReadError)?;
+    let glyph_count = font.glyph_count();
+
+    if let Some(id) = (0..glyph_count as u16).find(|id| {
+        face.glyph_hor_advance(GlyphId(*id)).is_none()
+            || face.glyph_hor_side_bearing(GlyphId(*id)).is_none()
+    }) {
+        return Err(FontError::MalformedFontError(format!(
+            "glyph {id} has no horizontal metrics"
+        )));
+    }
+
+    let mut invalid = None;
+    for subtable in face.tables().cmap.iter().flat_map(|cmap| cmap.subtables) {
+        if !subtable.is_unicode() {
+            continue;
+        }
+        subtable.codepoints(|codepoint| {
+            let glyph = subtable.glyph_index(codepoint);
+            if glyph.is_some_and(|glyph| glyph.0 as usize >= glyph_count) {
+                invalid.get_or_insert(codepoint);
+            }
+        });
+    }
+    if let Some(codepoint) = invalid {
+        return Err(FontError::MalformedFontError(format!(
+            "character U+{codepoint:04X} maps to a glyph that is not in the font"
+        )));
+    }
+
+    let tables = face.tables();
+    let substitutions = tables
+        .gsub
+        .into_iter()
+        .flat_map(|table| table.lookups)
+        .flat_map(|lookup| lookup.subtables.into_iter::<SubstitutionSubtable>())
+        .map(|subtable| match subtable {
+            SubstitutionSubtable::Context(context) => (subtable.coverage(), Some(context), None),
+            SubstitutionSubtable::ChainContext(chained) => {
+                (subtable.coverage(), None, Some(chained))
+            }
+            _ => (subtable.coverage(), None, None),
+        });
+    let positions = tables
+        .gpos
+        .into_iter()
+        .flat_map(|table| table.lookups)
+        .flat_map(|lookup| lookup.subtables.into_iter::<PositioningSubtable>())
+        .map(|subtable| match subtable {
+            PositioningSubtable::Context(context) => (subtable.coverage(), Some(context), None),
+            PositioningSubtable::ChainContext(chained) => {
+                (subtable.coverage(), None, Some(chained))
+            }
+            _ => (subtable.coverage(), None, None),
+        });
+    if substitutions
+        .chain(positions)
+        .any(|(coverage, context, chained)| malformed_lookup(coverage, context, chained))
+    {
+        return Err(FontError::MalformedFontError(
+            "a lookup has a coverage table that is out of order or can't be read".to_string(),
+        ));
+    }
+
+    Ok(font)
+}
+
+/// Returns whether a lookup subtable would make [`rustybuzz`] panic while shaping: it subtracts the start of each range
+/// of the coverage of a subtable from its end, and unwraps the coverages of contextual subtables.
+fn malformed_lookup(
+    coverage: Coverage,
+    context: Option<ContextLookup>,
+    chained: Option<ChainedContextLookup>,
+) -> bool {
+    let reversed = match coverage {
+        Coverage::Format1 { .. } => false,
+        Coverage::Format2 { records } => {
+            records.into_iter().any(|record| record.start > record.end)
+        }
+    };
+    let unreadable = match (context, chained) {
+        (Some(ContextLookup::Format3 { coverages, .. }), _) => {
+            (0..coverages.len()).any(|i| coverages.get(i).is_none())
+        }
+        (
+            _,
+            Some(ChainedContextLookup::Format3 {
+                backtrack_coverages,
+                input_coverages,
+                lookahead_coverages,
+                ..
+            }),
+        ) => [backtrack_coverages, input_coverages, lookahead_coverages]
+            .iter()
+            .any(|coverages| (0..coverages.len()).any(|i| coverages.get(i).is_none())),
+        _ => false,
+    };
+    reversed || unreadable
 }
 
 /// Rasterize a string of text string to an [`ImageBuffer`].
 ///
-/// Returns an [`ImageBuffer`] which contains the pixels of the text laid out horizontally.
+/// Returns a [`Result`] which contains either an [`ImageBuffer`] with the pixels of the text laid out horizontally,
+/// or a [`RasterizeError`] if the image would be larger than the default limits of [`Options`] (ex. [`Options::max_pixels`]).
 ///
 /// The [`ImageBuffer`] can be used in downstream applications as provided by the [`image`] crate.
 /// This could include things such as [`save`](https://docs.rs/image/latest/image/struct.ImageBuffer.html#method.save) to a local file, or investigating the dimensions with the [`width`](https://docs.rs/image/latest/image/struct.ImageBuffer.html#method.width) and [`height`](https://docs.rs/image/latest/image/struct.ImageBuffer.html#method.height).
@@ -175,7 +701,7 @@ pub fn read_font_bytes(bytes: &[u8]) -> Result<Font, FontError> {
 /// let color = Color { r: 255, g: 0, b: 0, a: 255 }; // Render as an opaque red color.
 ///
 /// // Rasterize the text to pixels.
-/// let image = rasterize_text::rasterize(&text, &font, size, &color);
+/// let image = rasterize_text::rasterize(&text, &font, size, &color)?;
 ///
 /// // Save to a local file.
 /// image.save("rasterize_str.png")?;
@@ -186,16 +712,22 @@ pub fn read_font_bytes(bytes: &[u8]) -> Result<Font, FontError> {
 /// # Ok::<(), color_eyre::eyre::Report>(())
 /// ```
 ///
-/// Converting a [`String`] to [`str`] for [`rasterize`].
+/// A [`String`] (or anything else that is [`AsRef<str>`]) is rasterized as is, without being converted or cloned.
+/// Lines are broken, reordered (bidi) and shaped with the whole text of a paragraph at once, so text from other
+/// sources (ex. the chunks of a rope, or characters that are streamed in) is collected into a [`String`] first.
 ///
 /// ```rust
 /// # use rasterize_text::{Color, rasterize, EN_FONT, read_font_bytes};
 /// # let font  = read_font_bytes(EN_FONT)?;
 /// # let size  = 50.0;
 /// # let color = Color { r: 255, g: 0, b: 0, a: 255 };
-/// let text = String::from("This is a test, we like unicode ÅΩ!");
-/// let text = text.as_str();
-/// let image = rasterize_text::rasterize(&text, &font, size, &color);
+/// let text  = String::from("This is a test, we like unicode ÅΩ!");
+/// let image = rasterize_text::rasterize(&text, &font, size, &color)?;
+/// assert_eq!(image, rasterize_text::rasterize(text.as_str(), &font, size, &color)?);
+///
+/// // Chunks of text (ex. of a rope) are joined into one string
+/// let chunks = ["This is a test, ", "we like unicode ÅΩ!"];
+/// assert_eq!(image, rasterize_text::rasterize(chunks.concat(), &font, size, &color)?);
 /// # Ok::<(), color_eyre::eyre::Report>(())
 /// ```
 ///
@@ -208,41 +740,565 @@ pub fn read_font_bytes(bytes: &[u8]) -> Result<Font, FontError> {
 /// # let color = Color { r: 255, g: 0, b: 0, a: 255 };
 /// let text = std::path::PathBuf::from("This is a test, we like unicode ÅΩ!");
 /// let text = text.as_os_str().to_str().unwrap_or("");
-/// let image = rasterize_text::rasterize(&text, &font, size, &color);
+/// let image = rasterize_text::rasterize(&text, &font, size, &color)?;
 /// # Ok::<(), color_eyre::eyre::Report>(())
 /// ```
-
-pub fn rasterize<T, I>(
-    text: &T,
+pub fn rasterize(
+    text: impl AsRef<str>,
     font: &Font,
     size: f32,
     color: &Color,
-) -> ImageBuffer<Rgba<u8>, Vec<u8>>
-where
-    T: AsRef<str> + Clone + UnicodeNormalization<I>,
-    I: Iterator<Item = char>,
-{
-    // Use uniform scaling of the text based on the size in pixels
-    let scale = Scale::uniform(size);
-    log::debug!("Font Size (pixels): {scale:?}");
+) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>, RasterizeError> {
+    let options = Options {
+        size,
+        color: *color,
+        ..Default::default()
+    };
+    rasterize_with_options(text, font, &options)
+}
 
-    // Configure spatial metrics based on the uniform scaling
-    let metrics = font.v_metrics(scale);
-    log::debug!("Font Metrics: {metrics:?}");
+/// Rasterize a string of text to an [`ImageBuffer`], as configured by [`Options`].
+///
+/// This is the same as [`rasterize`], but accepts [`Options`] to control additional aspects of the layout.
+///
+/// # Arguments
+///
+/// - `text`: A text [`str`] reference to rasterize as a pixel image.
+/// - `font`: A [`Font`] reference that contains [TrueType](https://en.wikipedia.org/wiki/TrueType) data.
+/// - `options`: The [`Options`] that configure the size, color, direction, language, fallback fonts and wrapping of the text.
+///
+/// # Examples
+///
+/// This example shows how to rasterize text that mixes right-to-left (Hebrew) and left-to-right (English) text.
+/// With [`Direction::Auto`], the base direction is detected from the first strong directional character.
+///
+/// ```rust
+/// use rasterize_text::{Direction, Options, rasterize_with_options, EN_FONT, read_font_bytes};
+///
+/// let text    = "שלום, world!";
+/// let font    = read_font_bytes(EN_FONT)?;
+/// let options = Options { direction: Direction::Auto, ..Default::default() };
+/// assert_eq!(options.direction.resolve(text), Direction::Rtl);
+///
+/// let image = rasterize_with_options(&text, &font, &options)?;
+/// image.save("rasterize_rtl.png")?;
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+///
+/// This example shows how to rasterize text in several scripts at once. The text is split into runs by script and direction,
+/// and each run uses the first font that supports it. English, Russian and Arabic are displayed with the main font (DejaVu Sans),
+/// while Korean falls back to Noto Sans KR.
+///
+/// ```rust
+/// use rasterize_text::{
+///     rasterize_with_metadata, rasterize_with_options, CoverageWarning, Options, EN_FONT, KR_FONT, read_font_bytes,
+/// };
+///
+/// let text    = "Hello Мир 안녕 مرحبا";
+/// let font    = read_font_bytes(EN_FONT)?;
+/// let options = Options { fallback_fonts: vec![read_font_bytes(KR_FONT)?], ..Default::default() };
+///
+/// let image = rasterize_with_options(&text, &font, &options)?;
+/// image.save("rasterize_multilingual.png")?;
+///
+/// // Only the Korean characters are drawn with the fallback font
+/// let rasterized = rasterize_with_metadata(&text, &font, &options)?;
+/// assert_eq!(rasterized.image, image);
+/// assert_eq!(rasterized.warnings, vec![
+///     CoverageWarning::Fallback { character: '안', font: 0, count: 1 },
+///     CoverageWarning::Fallback { character: '녕', font: 0, count: 1 },
+/// ]);
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+///
+/// This example shows how to wrap Japanese text onto several lines. Lines can break between most Chinese and Japanese characters,
+/// except where this would start a line with closing punctuation (ex. `。`, `」`, `～`) or end a line with an opening bracket (ex. `「`).
+///
+/// ```rust
+/// use rasterize_text::{outline_text, Options, rasterize_with_options, KR_FONT, read_font_bytes};
+///
+/// let text    = "吾輩は猫である。名前はまだ無い。「どこで生れたか」とんと見当がつかぬ。";
+/// let font    = read_font_bytes(KR_FONT)?;
+/// let options = Options { wrap_width: Some(300.0), ..Default::default() };
+///
+/// let image = rasterize_with_options(&text, &font, &options)?;
+/// image.save("rasterize_wrap.png")?;
+///
+/// // Get some stats
+/// assert_eq!(image.height(), 242 );
+/// assert_eq!(image.width(), 276 );
+///
+/// // At any wrap width, no line starts with closing punctuation or a wave dash, or ends with an opening bracket
+/// let text   = "すごい～！「どこで生れたか」とんと見当がつかぬ～。さあ～、行こう～";
+/// let glyphs = |characters: &str| characters.chars().map(|c| font.glyph(c).id().0).collect::<Vec<_>>();
+/// let (closing, opening) = (glyphs("。、！」～"), glyphs("「"));
+/// for wrap_width in (60..=400).step_by(10) {
+///     let options  = Options { wrap_width: Some(wrap_width as f32), ..Default::default() };
+///     let outlines = outline_text(&text, &font, &options)?;
+///     // Glyphs are in the order of the lines, so a glyph below the one before it starts a line
+///     let breaks: Vec<_> = outlines.windows(2).filter(|pair| pair[1].position.y > pair[0].position.y).collect();
+///     assert!(!breaks.is_empty());
+///     for pair in breaks {
+///         assert!(!closing.contains(&pair[1].id) && !opening.contains(&pair[0].id));
+///     }
+/// }
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+///
+/// With the `hyphenation` feature, words can also be broken with a hyphen, using the patterns of the [`Options::lang`].
+///
+/// ```rust
+/// # #[cfg(feature = "hyphenation")] {
+/// use rasterize_text::{outline_text, Options, EN_FONT, read_font_bytes};
+///
+/// let text    = "Hyphenation breaks extraordinarily long words";
+/// let font    = read_font_bytes(EN_FONT)?;
+/// let options = Options { wrap_width: Some(150.0), lang: Some("en".parse()?), ..Default::default() };
+///
+/// // The text has no hyphens, so a hyphen is only drawn at the end of a line that breaks a word
+/// let hyphen   = font.glyph('-').id().0;
+/// let outlines = outline_text(&text, &font, &options)?;
+/// let hyphens: Vec<_> = outlines.iter().enumerate().filter(|(_, outline)| outline.id == hyphen).collect();
+/// assert!(!hyphens.is_empty());
+/// for (i, outline) in hyphens {
+///     assert!(outlines[i + 1].position.y > outline.position.y);
+/// }
+/// // Zulu has no hyphenation patterns, so its words are never hyphenated
+/// let options = Options { lang: Some("zu".parse()?), ..options };
+/// assert!(outline_text(&text, &font, &options)?.iter().all(|outline| outline.id != hyphen));
+/// # }
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+pub fn rasterize_with_options(
+    text: impl AsRef<str>,
+    font: &Font,
+    options: &Options,
+) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>, RasterizeError> {
+    rasterize_spans(&[Span::from(text.as_ref())], font, options)
+}
 
-    // Apply unicode normalization
-    let normalized = text.clone().nfc().collect::<String>();
+/// How the pixels of a positioned glyph are drawn over the pixels that are already drawn (see [`pixel_writer`]).
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) enum Ink {
+    /// Pixels that are already drawn are kept, so each pixel has the color of the first glyph that covers it.
+    #[default]
+    First,
+    /// A copy of a bold glyph, which replaces the pixels that are less covered by its other copies.
+    Bold,
+    /// A layer of a color glyph, which is composited over the layers below it.
+    Layer,
+}
 
-    // layout the glyphs in the text horizontally
-    let glyphs: Vec<_> = font
-        .layout(normalized.as_ref(), scale, point(0., 0. + metrics.ascent))
-        .collect();
+/// Position the glyphs of lines of text, with the first line's baseline starting at `origin`.
+///
+/// Each glyph is scaled to the size of its segment, and paired with the segment's color and its [`Ink`].
+///
+/// The pixel coordinates of rusttype are [`i32`], and overflow for glyphs that are far from the origin
+/// (ex. from a huge font size or indent), so glyphs are first measured with floating point.
+/// Glyphs that are too far away return [`RasterizeError::TooLarge`], and glyphs that can't be measured
+/// (ex. a NaN position, or an inverted box from a negative size) are skipped.
+pub(crate) fn position_glyphs<'f>(
+    lines: &[layout::Line],
+    segments: &[span::Segment],
+    fonts: &[&Font<'f>],
+    origin: Point<f32>,
+    options: &Options,
+) -> Result<Vec<(PositionedGlyph<'f>, Color, Ink)>, RasterizeError> {
+    let count = lines.iter().map(|line| line.glyphs.len()).sum();
+    if count > options.max_glyphs {
+        return Err(RasterizeError::TooManyGlyphs {
+            glyphs: count,
+            max_glyphs: options.max_glyphs,
+        });
+    }
+    // Fonts are checked before glyphs are positioned, so that a denied font draws nothing
+    if options.license_policy != LicensePolicy::Ignore {
+        let mut used = vec![false; fonts.len()];
+        for glyph in lines.iter().flat_map(|line| &line.glyphs) {
+            used[glyph.font] = true;
+        }
+        license::check(fonts, &used, options)?;
+    }
+    // Color glyphs are drawn as layers of other glyphs, unless they are drawn in the color of the text
+    let color_tables: Vec<_> = match options.color_palette.monochrome {
+        true => fonts.iter().map(|_| None).collect(),
+        false => fonts.iter().map(|font| colr::ColorTables::parse(font)).collect(),
+    };
+    let mut glyphs = Vec::new();
+    for line in lines {
+        for glyph in &line.glyphs {
+            let segment = &segments[glyph.segment];
+            let scaled = fonts[glyph.font]
+                .glyph(GlyphId(glyph.id))
+                .scaled(Scale::uniform(segment.size));
+            let snap = |position: Point<f32>| match options.pixel_snap {
+                PixelSnap::Off => position,
+                PixelSnap::Positions | PixelSnap::Advances => {
+                    point(position.x.round(), position.y.round())
+                }
+                PixelSnap::Quarters => {
+                    let x = (position.x * SUBPIXEL_VARIANTS).round() / SUBPIXEL_VARIANTS;
+                    point(x, position.y.round())
+                }
+            };
+            let position = snap(point(origin.x + glyph.x, origin.y + line.y + glyph.y));
+            // Glyphs without a bounding box (ex. spaces) have no pixels, so are always safe to position
+            if let Some(bounds) = scaled.exact_bounding_box() {
+                let min = point(bounds.min.x + position.x, bounds.min.y + position.y);
+                let max = point(bounds.max.x + position.x, bounds.max.y + position.y);
+                let coordinates = [min.x, min.y, max.x, max.y];
+                if coordinates.iter().any(|coordinate| coordinate.is_nan()) {
+                    continue;
+                }
+                if coordinates
+                    .iter()
+                    .any(|coordinate| coordinate.abs() > MAX_COORDINATE)
+                {
+                    // Float to integer casts saturate, so an infinite extent is reported as the maximum
+                    let width = (max.x.max(0.0) as f64 - min.x.min(0.0) as f64).ceil() as u64;
+                    let height = (max.y.max(0.0) as f64 - min.y.min(0.0) as f64).ceil() as u64;
+                    return Err(too_large(width, height, options));
+                }
+                if min.x >= max.x || min.y >= max.y {
+                    continue;
+                }
+            }
+            // Bold glyphs are drawn again in half pixel steps to the right,
+            // up to a 32nd of the font size (at least a pixel), which thickens their vertical strokes
+            let mut positions = Vec::new();
+            if segment.bold {
+                let weight = (segment.size / 32.0).max(1.0);
+                let steps = (weight * 2.0).ceil();
+                for step in 1..=steps as usize {
+                    let x = position.x + weight * step as f32 / steps;
+                    positions.push(snap(point(x, position.y)));
+                }
+            }
+            positions.push(position);
+            let ink = match segment.bold {
+                true => Ink::Bold,
+                false => Ink::First,
+            };
+            let layers = color_tables[glyph.font].as_ref().and_then(|tables| {
+                tables.layers(glyph.id, segment.color, &options.color_palette)
+            });
+            match layers {
+                // Layers are composited over each other, from the bottom layer up
+                Some(layers) => {
+                    for (id, color) in layers {
+                        let layer = fonts[glyph.font]
+                            .glyph(GlyphId(id))
+                            .scaled(Scale::uniform(segment.size));
+                        for position in &positions {
+                            glyphs.push((layer.clone().positioned(*position), color, Ink::Layer));
+                        }
+                    }
+                }
+                None => {
+                    // Hollow glyphs are drawn in the color of their strokes
+                    let color = options
+                        .stroke_width
+                        .and(options.stroke_color)
+                        .unwrap_or(segment.color);
+                    for position in positions {
+                        glyphs.push((scaled.clone().positioned(position), color, ink));
+                    }
+                }
+            }
+        }
+    }
+    Ok(glyphs)
+}
 
+/// Draw positioned glyphs in their colors, and their backgrounds and underlines,
+/// to an [`ImageBuffer`] that is cropped to the pixels they cover, and then framed by the border of the [`Options`].
+pub(crate) fn draw<C: Channel>(
+    glyphs: &[(PositionedGlyph, Color, Ink)],
+    decorations: &[Decoration],
+    options: &Options,
+) -> Result<ImageBuffer<Rgba<C>, Vec<C>>, RasterizeError>
+where
+    Rgba<C>: Pixel<Subpixel = C>,
+{
     // Display the individual glyph info in debug mode
     glyphs
         .iter()
-        .for_each(|glyph| log::debug!("Glyph: {glyph:?}"));
+        .for_each(|(glyph, ..)| log::debug!("Glyph: {glyph:?}"));
+
+    let (width, height) = measure(glyphs, decorations);
+    let (width, height) = check_size(width, height, options)?;
+    let image_buffer = draw_unframed((width, height), glyphs, decorations, options);
+
+    frame::apply(image_buffer, options)
+}
+
+/// Draw positioned glyphs in their colors, and their backgrounds, underlines and effects, to an [`ImageBuffer`]
+/// with a width and height, blending them in the color space of the [`Options::blending`].
+///
+/// Images that are blended in linear light are drawn with 16 bits per channel, and then converted to sRGB.
+pub(crate) fn draw_unframed<C: Channel>(
+    (width, height): (u32, u32),
+    glyphs: &[(PositionedGlyph, Color, Ink)],
+    decorations: &[Decoration],
+    options: &Options,
+) -> ImageBuffer<Rgba<C>, Vec<C>>
+where
+    Rgba<C>: Pixel<Subpixel = C>,
+{
+    match options.blending {
+        Blending::Srgb => paint((width, height), glyphs, decorations, options),
+        Blending::Linear => {
+            linear::to_srgb_image(&paint::<u16>((width, height), glyphs, decorations, options))
+        }
+    }
+}
+
+/// Draw positioned glyphs, and their backgrounds, underlines and effects, to an [`ImageBuffer`] in the color space
+/// of the [`Options::blending`].
+fn paint<C: Channel>(
+    (width, height): (u32, u32),
+    glyphs: &[(PositionedGlyph, Color, Ink)],
+    decorations: &[Decoration],
+    options: &Options,
+) -> ImageBuffer<Rgba<C>, Vec<C>>
+where
+    Rgba<C>: Pixel<Subpixel = C>,
+{
+    // construct an image buffer to hold RGBA pixels representing each character
+    let mut image_buffer = ImageBuffer::<Rgba<C>, Vec<_>>::new(width, height);
+
+    // Iterate through each glyph ('letter'), and add it's pixels to the buffer
+    // Glyphs at quarter pixel positions are rasterized once for each offset, and then reused
+    let mut cache = (options.pixel_snap == PixelSnap::Quarters).then(GlyphCache::default);
+    // Glyphs that are SVG documents are drawn from their documents, unless they are drawn in the color of the text
+    #[cfg(feature = "svg")]
+    let mut svg_glyphs = (!options.color_palette.monochrome).then(svg::SvgGlyphs::default);
+    for (glyph, color, ink) in glyphs {
+        #[cfg(feature = "svg")]
+        if let Some(svg_glyphs) = svg_glyphs.as_mut() {
+            if svg_glyphs.draw(&mut image_buffer, glyph, color, options) {
+                continue;
+            }
+        }
+        match cache.as_mut() {
+            Some(cache) => cache.draw(&mut image_buffer, glyph, color, *ink, options),
+            None => draw_glyph(&mut image_buffer, glyph, color, *ink, options),
+        }
+    }
+
+    draw_decorations(&mut image_buffer, decorations, options);
+    if let Some(background) = &options.background {
+        let blending = options.blending;
+        background::fill(&mut image_buffer, background, 0..width, 0..height, blending);
+    }
+    effect::apply_to_image(&mut image_buffer, options);
+
+    image_buffer
+}
+
+/// Draw the backgrounds, underlines and filters of glyphs to an [`ImageBuffer`] that the glyphs are already drawn to,
+/// blending their colors in the color space of the [`Options::blending`].
+///
+/// Underlines are drawn like glyphs and inline images are composited over them, then the pixels within filters are
+/// filtered (ex. blurred), and backgrounds are blended behind everything else.
+/// Each pixel is only blended with the first background that covers it, so overlapping backgrounds don't darken.
+pub(crate) fn draw_decorations<C: Channel>(
+    image_buffer: &mut ImageBuffer<Rgba<C>, Vec<C>>,
+    decorations: &[Decoration],
+    options: &Options,
+) where
+    Rgba<C>: Pixel<Subpixel = C>,
+{
+    let (width, height) = image_buffer.dimensions();
+    let mut covered = Vec::new();
+    // Underlines are drawn first, since the pixels that a background is blended with are then opaque
+    let mut decorations: Vec<&Decoration> = decorations.iter().collect();
+    decorations.sort_by_key(|decoration| match decoration.kind {
+        DecorationKind::Foreground | DecorationKind::Image(..) => 0,
+        DecorationKind::Filter(_) => 1,
+        DecorationKind::Background => 2,
+        DecorationKind::Extent => 3,
+    });
+    for decoration in decorations {
+        let rect = decoration.rect;
+        let columns = rect.min.x.max(0) as u32..(rect.max.x.max(0) as u32).min(width);
+        let rows = rect.min.y.max(0) as u32..(rect.max.y.max(0) as u32).min(height);
+        if decoration.kind == DecorationKind::Extent {
+            continue;
+        }
+        if let DecorationKind::Filter(filter) = decoration.kind {
+            effect::apply(image_buffer, filter, columns, rows);
+            continue;
+        }
+        if let DecorationKind::Image(image, origin) = &decoration.kind {
+            let fade = f32::from(decoration.color.a) / 255.0;
+            for y in rows {
+                for x in columns.clone() {
+                    let image_x = u32::try_from(i64::from(x) - i64::from(origin.x));
+                    let image_y = u32::try_from(i64::from(y) - i64::from(origin.y));
+                    let pixel = match (image_x, image_y) {
+                        (Ok(image_x), Ok(image_y)) => image.get_pixel_checked(image_x, image_y),
+                        _ => None,
+                    };
+                    let Some(&Rgba([r, g, b, a])) = pixel else {
+                        continue;
+                    };
+                    // The alpha of the image is its coverage, and it is faded like the color of a glyph
+                    let opaque = Color { r, g, b, a: 255 };
+                    let [red, green, blue, _] = C::color(&opaque, options.blending);
+                    let coverage = f32::from(a) / 255.0;
+                    let behind = 1.0 - coverage * fade;
+                    let pixel = image_buffer.get_pixel_mut(x, y);
+                    let Rgba([r, g, b, a]) = *pixel;
+                    let over = |channel: C, source: f32| {
+                        C::from_f32((source * coverage + channel.to_f32() * behind).round())
+                    };
+                    *pixel = Rgba([
+                        over(r, red),
+                        over(g, green),
+                        over(b, blue),
+                        over(a, C::MAX * fade),
+                    ]);
+                }
+            }
+            continue;
+        }
+        if decoration.kind == DecorationKind::Foreground {
+            let origin = rusttype::Rect {
+                min: point(0, 0),
+                max: point(0, 0),
+            };
+            let mut draw =
+                pixel_writer(image_buffer, origin, &decoration.color, Ink::First, options);
+            for y in rows {
+                columns.clone().for_each(|x| draw(x, y, 1.0));
+            }
+            continue;
+        }
+        if covered.is_empty() {
+            covered = vec![false; width as usize * height as usize];
+        }
+        let [red, green, blue, alpha] = C::color(&decoration.color, options.blending);
+        let alpha = alpha / C::MAX;
+        for y in rows {
+            for x in columns.clone() {
+                let i = y as usize * width as usize + x as usize;
+                if std::mem::replace(&mut covered[i], true) {
+                    continue;
+                }
+                // Pixels are premultiplied (see Channel), so they are blended over the background as is
+                let pixel = image_buffer.get_pixel_mut(x, y);
+                let Rgba([r, g, b, a]) = *pixel;
+                let behind = 1.0 - a.to_f32() / C::MAX;
+                let blend = |channel: C, background: f32| {
+                    C::from_f32((channel.to_f32() + background * alpha * behind).round())
+                };
+                *pixel = Rgba([
+                    blend(r, red),
+                    blend(g, green),
+                    blend(b, blue),
+                    blend(a, C::MAX),
+                ]);
+            }
+        }
+    }
+}
+
+/// Draw a positioned glyph in a color to an [`ImageBuffer`], as configured by the [`Options`] (see [`pixel_writer`]),
+/// skipping pixels outside of the image and pixels that are already drawn (unless its [`Ink`] replaces them).
+pub(crate) fn draw_glyph<C: Channel>(
+    image_buffer: &mut ImageBuffer<Rgba<C>, Vec<C>>,
+    glyph: &PositionedGlyph,
+    color: &Color,
+    ink: Ink,
+    options: &Options,
+) where
+    Rgba<C>: Pixel<Subpixel = C>,
+{
+    // I don't remember in which cases a glyph might not have a pixel bounding box...
+    if let Some(bounding_box) = glyph.pixel_bounding_box() {
+        log::debug!("{0:?}, {bounding_box:?}", glyph.id());
+
+        // Iterate through all the pixels in this letter (glyph)
+        // 'x' and 'y' are relative to this letter's bounding box.
+        // 'v' is 'coverage', which I think represents the intensity
+        // of how the pixel should be colored in. If it's '0' then the
+        // pixel is not colored in.
+        let draw = pixel_writer(image_buffer, bounding_box, color, ink, options);
+        stroke::draw(glyph, options, draw);
+    }
+}
+
+/// Returns a function that draws a pixel of a glyph with a pixel bounding box, from its position `x` and `y`
+/// relative to the bounding box and its coverage `v`.
+///
+/// The color is blended in the color space of the [`Options::blending`], and the coverage is cut off
+/// at the [`Options::coverage_threshold`] and clamped between the [`Options::min_alpha`] and [`Options::max_alpha`],
+/// and then multiplied into the color (see [`Channel`]).
+/// Pixels that are already drawn are kept, unless the [`Ink`] of the glyph replaces them.
+pub(crate) fn pixel_writer<'a, C: Channel>(
+    image_buffer: &'a mut ImageBuffer<Rgba<C>, Vec<C>>,
+    bounding_box: rusttype::Rect<i32>,
+    color: &Color,
+    ink: Ink,
+    options: &Options,
+) -> impl FnMut(u32, u32, f32) + 'a
+where
+    Rgba<C>: Pixel<Subpixel = C>,
+{
+    // Make a default pixel, which is fully transparent
+    let default_pixel: Rgba<C> = Rgba([C::DEFAULT_MIN_VALUE; 4]);
+    let color = C::color(color, options.blending);
+    let coverage = options.coverage();
 
+    move |x, y, v| {
+        // Convert the pixel's relative position to an absolute position in the buffer
+        // With special handling for if the absolute position of the letter is negative
+        // Ex. the letter 'T' starting at absolute position x=-2;
+        let y = match bounding_box.min.y >= 0 {
+            true => i64::from(y) + i64::from(bounding_box.min.y),
+            false => i64::from(y),
+        };
+        let x = match bounding_box.min.x >= 0 {
+            true => i64::from(x) + i64::from(bounding_box.min.x),
+            false => i64::from(x),
+        };
+        // Pixels outside of the image are skipped, rather than wrapping around
+        let (Ok(x), Ok(y)) = (u32::try_from(x), u32::try_from(y)) else {
+            return;
+        };
+
+        // construct a pixel
+        let v = coverage(v);
+        let pixel = Rgba(color.map(|channel| C::from_f32(channel * v)));
+
+        // add pixel to image buffer, if that pixel is still the default
+        // (or is less covered by another copy of a bold glyph, so that the copies don't leave faint pixels at their edges)
+        let Some(existing) = image_buffer.get_pixel_mut_checked(x, y) else {
+            return;
+        };
+        match ink {
+            // Layers are composited over the pixels below them, so that translucent layers show the layers below
+            Ink::Layer => {
+                let behind = 1.0 - pixel[3].to_f32() / C::MAX;
+                for (channel, source) in existing.0.iter_mut().zip(pixel.0) {
+                    *channel = C::from_f32((source.to_f32() + channel.to_f32() * behind).round());
+                }
+            }
+            _ if *existing == default_pixel || (ink == Ink::Bold && pixel[3] > existing[3]) => {
+                *existing = pixel;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Measure the width and height in pixels of the image that positioned glyphs and their decorations are drawn to,
+/// without allocating it.
+pub(crate) fn measure(
+    glyphs: &[(PositionedGlyph, Color, Ink)],
+    decorations: &[Decoration],
+) -> (u64, u64) {
     // Get the width and height of the final image raster, based on the pixels used.
     // Note: In certain cases, the min_x can actually be less than 0! For example,
     // when "T" is rendered in isolation, it wil start at -2 pixels, because of kerning.
@@ -251,21 +1307,29 @@ where
     let mut min_y: i32 = 0;
     let mut max_y: i32 = 0;
 
-    // Iterate through the glyphs, updating our x coordinate extremes
-    glyphs.iter().for_each(|glyph| {
-        if let Some(bounding_box) = glyph.pixel_bounding_box() {
-            if bounding_box.min.x < min_x {
-                min_x = bounding_box.min.x
-            }
-            if bounding_box.max.x > max_x {
-                max_x = bounding_box.max.x
-            }
-            if bounding_box.min.y < min_y {
-                min_y = bounding_box.min.y
-            }
-            if bounding_box.max.y > max_y {
-                max_y = bounding_box.max.y
-            }
+    // Iterate through the glyphs and decorations, updating our x coordinate extremes
+    // Filters only change the pixels that are already drawn, so they don't make the image larger
+    let bounding_boxes = glyphs
+        .iter()
+        .filter_map(|(glyph, ..)| glyph.pixel_bounding_box())
+        .chain(
+            decorations
+                .iter()
+                .filter(|decoration| !matches!(decoration.kind, DecorationKind::Filter(_)))
+                .map(|decoration| decoration.rect),
+        );
+    bounding_boxes.for_each(|bounding_box| {
+        if bounding_box.min.x < min_x {
+            min_x = bounding_box.min.x
+        }
+        if bounding_box.max.x > max_x {
+            max_x = bounding_box.max.x
+        }
+        if bounding_box.min.y < min_y {
+            min_y = bounding_box.min.y
+        }
+        if bounding_box.max.y > max_y {
+            max_y = bounding_box.max.y
         }
     });
     log::debug!("Minimum x coordinate: {min_x:?}");
@@ -275,8 +1339,8 @@ where
 
     // If the min_x is negative, such as a pixel range from x=-2 to x=4,
     // we need to account or that in the width, which would be width=6 in this case.
-    let width = max_x - min_x;
-    let height = max_y - min_y;
+    let width = i64::from(max_x) - i64::from(min_x);
+    let height = i64::from(max_y) - i64::from(min_y);
 
     // // get output image height according to the font metrics
     // // Get height based on font metrics? Maybe we shouldn't use this, and
@@ -286,125 +1350,41 @@ where
 
     log::debug!("Image Width: {width:?}");
     log::debug!("Image Height: {height:?}");
-
-    // construct an image buffer to hold RGBA pixels representing each character
-    let mut image_buffer = ImageBuffer::<Rgba<u8>, Vec<_>>::new(width as u32, height as u32);
-
-    // Make a default pixel, which is fully transparent
-    let default_pixel: Rgba<u8> = Rgba([0, 0, 0, 0]);
-
-    // Iterate through each glyph ('letter'), and add it's pixels to the buffer
-    for glyph in glyphs {
-        // I don't remember in which cases a glyph might not have a pixel bounding box...
-        if let Some(bounding_box) = glyph.pixel_bounding_box() {
-            log::debug!("{0:?}, {bounding_box:?}", glyph.id());
-
-            // Iterate through all the pixels in this letter (glyph)
-            // 'x' and 'y' are relative to this letter's bounding box.
-            // 'v' is 'coverage', which I think represents the intensity
-            // of how the pixel should be colored in. If it's '0' then the
-            // pixel is not colored in.
-            glyph.draw(|x, y, v| {
-                // Convert the pixel's relative position to an absolute position in the buffer
-                // With special handling for if the absolute position of the letter is negative
-                // Ex. the letter 'T' starting at absolute position x=-2;
-                let y = match bounding_box.min.y >= 0 {
-                    true => y as i32 + bounding_box.min.y,
-                    false => y as i32,
-                };
-                let x = match bounding_box.min.x >= 0 {
-                    true => x as i32 + bounding_box.min.x,
-                    false => x as i32,
-                };
-
-                // construct a pixel
-                let pixel = Rgba([
-                    (color.r as f32 * v) as u8,
-                    (color.g as f32 * v) as u8,
-                    (color.b as f32 * v) as u8,
-                    (color.a as f32 * v) as u8,
-                ]);
-
-                // add pixel to image buffer, if that pixel is still the default
-                // I can't remember why I had this check...
-                if image_buffer.get_pixel(x as u32, y as u32) == &default_pixel {
-                    image_buffer.put_pixel(x as u32, y as u32, pixel);
-                }
-            });
-        }
-    }
-
-    image_buffer
-}
-
-#[derive(Copy, Clone, Debug)]
-pub struct Color {
-    pub r: u8,
-    pub g: u8,
-    pub b: u8,
-    pub a: u8,
+    (
+        u64::try_from(width).unwrap_or_default(),
+        u64::try_from(height).unwrap_or_default(),
+    )
 }
 
-#[derive(Debug, thiserror::Error)]
-pub enum ColorError {
-    #[error("Failed to parse value {2} to RGBA color in: {1}")]
-    RgbaParseError(#[source] std::num::ParseIntError, String, String),
-    #[error("Failed to parse RGBA because of an incorrect number of values (expected 4): {0:?}.")]
-    RgbaLengthError(Vec<u8>),
-}
+/// Glyphs are only drawn within this distance from the origin in pixels, so that pixel coordinates fit in an [`i32`].
+const MAX_COORDINATE: f32 = (1 << 30) as f32;
 
-impl Default for Color {
-    fn default() -> Self {
-        Color {
-            r: 0,
-            g: 0,
-            b: 0,
-            a: 255,
+/// Check that an image of `width` by `height` pixels is within the limits of the [`Options`], before it is allocated.
+///
+/// Returns the width and height of the image.
+pub(crate) fn check_size(
+    width: u64,
+    height: u64,
+    options: &Options,
+) -> Result<(u32, u32), RasterizeError> {
+    match (u32::try_from(width), u32::try_from(height)) {
+        (Ok(w), Ok(h))
+            if w <= options.max_width
+                && h <= options.max_height
+                && width.saturating_mul(height) <= options.max_pixels =>
+        {
+            Ok((w, h))
         }
+        _ => Err(too_large(width, height, options)),
     }
 }
 
-impl std::fmt::Display for Color {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        // Convert to lowercase for RUST_LOG env var compatibility
-        let color = format!("{} {} {} {}", self.r, self.g, self.b, self.a);
-        write!(f, "{color}")
-    }
-}
-
-impl FromStr for Color {
-    type Err = ColorError;
-
-    /// Returns a [`Color`] converted from a [`str`].
-    ///
-    /// ## Examples
-    ///
-    fn from_str(color: &str) -> Result<Self, Self::Err> {
-        //let rgba: Vec<u8> = color.split(" ").map(|s| s.parse::<u8>().map_err(ColorError::UnknownColorError)).collect::<Vec<Result<u8>, ColorError>>()?;
-        let rgba: Vec<u8> = color
-            // Split the color string on a space delimiter
-            .split(" ")
-            // Try to convert the space-delimited text to rgba values (8-bit, 0-255)
-            .map(|s| {
-                s.parse::<u8>()
-                    .map_err(|e| ColorError::RgbaParseError(e, color.to_string(), s.to_string()))
-            })
-            // Gather the rgba values into a vector, throw an error if an issue was encountered
-            .collect::<Result<Vec<u8>, ColorError>>()?;
-
-        // Convert the rgba value vector into a fixed array of length 4
-        let rgba: [u8; 4] = rgba
-            .clone()
-            .try_into()
-            .map_err(|_| ColorError::RgbaLengthError(rgba))?;
-
-        let color = Color {
-            r: rgba[0],
-            g: rgba[1],
-            b: rgba[2],
-            a: rgba[3],
-        };
-
-        Ok(color)
+fn too_large(width: u64, height: u64, options: &Options) -> RasterizeError {
+    RasterizeError::TooLarge {
+        width,
+        height,
+        max_width: options.max_width,
+        max_height: options.max_height,
+        max_pixels: options.max_pixels,
     }
 }
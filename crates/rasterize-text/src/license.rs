@@ -0,0 +1,211 @@
+//! Read the names, license and embedding permissions of fonts, for asset pipelines that must stay compliant.
+
+use crate::shape::font_data;
+use crate::{Options, OptionsError, RasterizeError};
+use rusttype::Font;
+use rustybuzz::ttf_parser::{name_id, Face, Permissions};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// What the embedding permissions of a font (the `fsType` of its OS/2 table) allow a document that embeds it to do.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Embedding {
+    /// The font can be embedded, and installed permanently on the system that reads the document.
+    Installable,
+    /// The font can be embedded, and documents can be edited with it.
+    Editable,
+    /// The font can be embedded, but documents can only be viewed and printed with it.
+    PreviewAndPrint,
+    /// The font must not be embedded or otherwise redistributed without the permission of its owner.
+    Restricted,
+}
+
+impl Display for Embedding {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        let lowercase = format!("{:?}", self).to_lowercase();
+        write!(f, "{lowercase}")
+    }
+}
+
+/// The names, license and embedding permissions of a font, from its naming and OS/2 tables, with [`font_info`].
+///
+/// Names are in English when the font has English names, and are missing when the font doesn't have them.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FontInfo {
+    /// The family name (ex. "DejaVu Sans").
+    pub family: Option<String>,
+    /// The style within the family (ex. "Bold").
+    pub subfamily: Option<String>,
+    /// The full name, which is the family and the style (ex. "DejaVu Sans Bold").
+    pub full_name: Option<String>,
+    /// The PostScript name (ex. "DejaVuSans-Bold").
+    pub postscript_name: Option<String>,
+    /// The version (ex. "Version 2.37").
+    pub version: Option<String>,
+    /// The copyright notice.
+    pub copyright: Option<String>,
+    /// The trademark notice.
+    pub trademark: Option<String>,
+    /// The name of the foundry that made the font.
+    pub manufacturer: Option<String>,
+    /// The name of the designer of the font.
+    pub designer: Option<String>,
+    /// A description of the font, its history or its use.
+    pub description: Option<String>,
+    /// A description of the license of the font, in plain language.
+    pub license: Option<String>,
+    /// The URL of the full license of the font.
+    pub license_url: Option<String>,
+    /// The URL of the foundry that made the font.
+    pub vendor_url: Option<String>,
+    /// The embedding permissions of the font. If the font has no OS/2 table, its permissions are unknown.
+    pub embedding: Option<Embedding>,
+    /// Whether the font may be subset (ex. by [`subset_font`](crate::subset_font)) before it is embedded.
+    pub subsetting: bool,
+}
+
+impl FontInfo {
+    /// Returns the name of the font to show in messages, which is its full name, family name or PostScript name.
+    pub fn name(&self) -> &str {
+        self.full_name
+            .as_deref()
+            .or(self.family.as_deref())
+            .or(self.postscript_name.as_deref())
+            .unwrap_or("unnamed font")
+    }
+}
+
+/// Returns the names, license and embedding permissions of a font.
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{font_info, Embedding, EN_FONT, read_font_bytes};
+///
+/// let font = read_font_bytes(EN_FONT)?;
+/// let info = font_info(&font);
+/// assert_eq!(info.family.as_deref(), Some("DejaVu Sans"));
+/// assert_eq!(info.embedding, Some(Embedding::Installable));
+/// assert!(info.copyright.is_some_and(|copyright| copyright.contains("Bitstream")));
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+pub fn font_info(font: &Font) -> FontInfo {
+    let Some(face) = font_data(font).and_then(|data| Face::parse(data, 0).ok()) else {
+        return FontInfo {
+            subsetting: true,
+            ..Default::default()
+        };
+    };
+    // English names are preferred, and then the names in any language
+    let name = |id: u16| {
+        let names = face.names().into_iter().filter(|name| name.name_id == id);
+        names
+            .clone()
+            .filter(|name| name.language().primary_language() == "English")
+            .chain(names)
+            .find_map(|name| name.to_string())
+    };
+    FontInfo {
+        family: name(name_id::TYPOGRAPHIC_FAMILY).or_else(|| name(name_id::FAMILY)),
+        subfamily: name(name_id::TYPOGRAPHIC_SUBFAMILY).or_else(|| name(name_id::SUBFAMILY)),
+        full_name: name(name_id::FULL_NAME),
+        postscript_name: name(name_id::POST_SCRIPT_NAME),
+        version: name(name_id::VERSION),
+        copyright: name(name_id::COPYRIGHT_NOTICE),
+        trademark: name(name_id::TRADEMARK),
+        manufacturer: name(name_id::MANUFACTURER),
+        designer: name(name_id::DESIGNER),
+        description: name(name_id::DESCRIPTION),
+        license: name(name_id::LICENSE),
+        license_url: name(name_id::LICENSE_URL),
+        vendor_url: name(name_id::VENDOR_URL),
+        embedding: face.permissions().map(|permissions| match permissions {
+            Permissions::Installable => Embedding::Installable,
+            Permissions::Editable => Embedding::Editable,
+            Permissions::PreviewAndPrint => Embedding::PreviewAndPrint,
+            Permissions::Restricted => Embedding::Restricted,
+        }),
+        subsetting: face.is_subsetting_allowed(),
+    }
+}
+
+/// What happens when text is rendered with a font whose embedding permissions are [`Embedding::Restricted`],
+/// with [`Options::license_policy`].
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{font_info, rasterize_with_options, Embedding, LicensePolicy, Options, RasterizeError, EN_FONT, read_font_bytes};
+///
+/// // A copy of the font with restricted embedding permissions (an fsType of 2, in its OS/2 table)
+/// let mut bytes = EN_FONT.to_vec();
+/// let tables    = u16::from_be_bytes([bytes[4], bytes[5]]) as usize;
+/// let record    = (0..tables).map(|i| 12 + 16 * i).find(|record| &bytes[*record..*record + 4] == b"OS/2").unwrap();
+/// let os2       = u32::from_be_bytes(bytes[record + 8..record + 12].try_into()?) as usize;
+/// bytes[os2 + 8..os2 + 10].copy_from_slice(&2u16.to_be_bytes());
+/// let restricted = read_font_bytes(&bytes)?;
+/// assert_eq!(font_info(&restricted).embedding, Some(Embedding::Restricted));
+///
+/// let deny   = Options { license_policy: LicensePolicy::Deny, ..Default::default() };
+/// let result = rasterize_with_options(&"Hello", &restricted, &deny);
+/// assert!(matches!(result, Err(RasterizeError::RestrictedFont(name)) if name == "DejaVu Sans"));
+/// rasterize_with_options(&"Hello", &read_font_bytes(EN_FONT)?, &deny)?;
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum LicensePolicy {
+    /// Render the text, without checking the permissions of the fonts.
+    #[default]
+    Ignore,
+    /// Render the text, and log a warning for each restricted font.
+    Warn,
+    /// Return [`RasterizeError::RestrictedFont`] instead of rendering the text.
+    Deny,
+}
+
+impl Display for LicensePolicy {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        let lowercase = format!("{:?}", self).to_lowercase();
+        write!(f, "{lowercase}")
+    }
+}
+
+impl FromStr for LicensePolicy {
+    type Err = OptionsError;
+
+    /// Returns a [`LicensePolicy`] converted from a [`str`].
+    fn from_str(policy: &str) -> Result<Self, Self::Err> {
+        let policy = match policy {
+            "ignore" => LicensePolicy::Ignore,
+            "warn" => LicensePolicy::Warn,
+            "deny" => LicensePolicy::Deny,
+            _ => Err(OptionsError::UnknownLicensePolicyError(policy.to_string()))?,
+        };
+
+        Ok(policy)
+    }
+}
+
+/// Check the embedding permissions of the fonts that glyphs are drawn with, with the [`Options::license_policy`].
+///
+/// Only fonts that are used are checked, so a restricted fallback font that no character falls back to is allowed.
+pub(crate) fn check(
+    fonts: &[&Font],
+    used: &[bool],
+    options: &Options,
+) -> Result<(), RasterizeError> {
+    for (font, _) in fonts.iter().zip(used).filter(|(_, used)| **used) {
+        let info = font_info(font);
+        if info.embedding != Some(Embedding::Restricted) {
+            continue;
+        }
+        match options.license_policy {
+            LicensePolicy::Deny => Err(RasterizeError::RestrictedFont(info.name().to_string()))?,
+            _ => log::warn!(
+                "The font {:?} has restricted embedding permissions, so it must not be redistributed without the permission of its owner.",
+                info.name()
+            ),
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,50 @@
+//! Convert colors between sRGB and linear light, for blending text with [`Blending::Linear`](crate::Blending::Linear).
+
+use crate::channel::Channel;
+use image::{ImageBuffer, Pixel, Rgba};
+
+/// Returns an 8 bit sRGB channel in linear light, from 0 to 1.
+pub(crate) fn to_linear(channel: u8) -> f32 {
+    let channel = f32::from(channel) / 255.0;
+    match channel <= 0.04045 {
+        true => channel / 12.92,
+        false => ((channel + 0.055) / 1.055).powf(2.4),
+    }
+}
+
+/// Returns a channel in linear light, from 0 to 1, in sRGB.
+fn to_srgb(channel: f32) -> f32 {
+    match channel <= 0.003_130_8 {
+        true => channel * 12.92,
+        false => 1.055 * channel.powf(1.0 / 2.4) - 0.055,
+    }
+}
+
+/// Convert an image in linear light with 16 bits per channel to sRGB.
+///
+/// Pixels are premultiplied (see [`Channel`]), so colors are divided by their alpha before they are converted, and
+/// multiplied by it again after.
+pub(crate) fn to_srgb_image<C: Channel>(
+    image_buffer: &ImageBuffer<Rgba<u16>, Vec<u16>>,
+) -> ImageBuffer<Rgba<C>, Vec<C>>
+where
+    Rgba<C>: Pixel<Subpixel = C>,
+{
+    ImageBuffer::from_fn(image_buffer.width(), image_buffer.height(), |x, y| {
+        let Rgba([r, g, b, a]) = *image_buffer.get_pixel(x, y);
+        if a == 0 {
+            return Rgba([C::DEFAULT_MIN_VALUE; 4]);
+        }
+        let alpha = f32::from(a) / f32::from(u16::MAX);
+        let convert = |channel: u16| {
+            let color = (f32::from(channel) / f32::from(a)).min(1.0);
+            C::from_f32((to_srgb(color) * alpha * C::MAX).round())
+        };
+        Rgba([
+            convert(r),
+            convert(g),
+            convert(b),
+            C::from_f32((alpha * C::MAX).round()),
+        ])
+    })
+}
@@ -0,0 +1,70 @@
+//! Find the positions where a line of text may be broken when wrapping.
+
+use std::ops::Range;
+use unicode_linebreak::{linebreaks, BreakOpportunity};
+
+/// Characters that must not start a line in Chinese and Japanese text (kinsoku shori).
+///
+/// This includes closing brackets and quotes, sentence and clause punctuation, iteration marks,
+/// the prolonged sound mark, and small kana.
+#[rustfmt::skip]
+const LINE_START_PROHIBITED: &[char] = &[
+    // Closing brackets and quotes
+    ')', ']', '}', '»', '’', '”', '）', '〕', '］', '｝', '〉', '》', '」', '』', '】', '〙', '〗', '〟', '｠',
+    // Punctuation
+    ',', '.', ':', ';', '?', '!', '、', '。', '，', '．', '：', '；', '？', '！', '‼', '⁇', '⁈', '⁉', '・',
+    '‐', '゠', '–', '〜', '～', '…', '‥', '％', '‰', '℃', '°', '′', '″',
+    // Iteration marks and the prolonged sound mark
+    'ヽ', 'ヾ', 'ゝ', 'ゞ', '々', '〻', 'ー',
+    // Small hiragana and katakana
+    'ぁ', 'ぃ', 'ぅ', 'ぇ', 'ぉ', 'っ', 'ゃ', 'ゅ', 'ょ', 'ゎ', 'ゕ', 'ゖ',
+    'ァ', 'ィ', 'ゥ', 'ェ', 'ォ', 'ッ', 'ャ', 'ュ', 'ョ', 'ヮ', 'ヵ', 'ヶ',
+    'ㇰ', 'ㇱ', 'ㇲ', 'ㇳ', 'ㇴ', 'ㇵ', 'ㇶ', 'ㇷ', 'ㇸ', 'ㇹ', 'ㇺ', 'ㇻ', 'ㇼ', 'ㇽ', 'ㇾ', 'ㇿ',
+];
+
+/// Characters that must not end a line in Chinese and Japanese text (kinsoku shori).
+///
+/// This includes opening brackets and quotes, and currency symbols that prefix a number.
+#[rustfmt::skip]
+const LINE_END_PROHIBITED: &[char] = &[
+    '(', '[', '{', '«', '‘', '“', '（', '〔', '［', '｛', '〈', '《', '「', '『', '【', '〘', '〖', '〝', '｟',
+    '￥', '＄', '£', '¥', '$', '＃',
+];
+
+/// A position where a line may be broken.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Break {
+    /// Byte index of the character that would start the next line.
+    pub index: usize,
+    /// Whether the line must be broken here (ex. at a line separator).
+    pub mandatory: bool,
+    /// Whether the break is within a word, so a hyphen is added to the end of the line.
+    pub hyphen: bool,
+}
+
+/// Returns the positions within a range of text where a line may be broken, in order.
+///
+/// Break opportunities follow the [Unicode Line Breaking Algorithm](https://www.unicode.org/reports/tr14/),
+/// which allows breaks at spaces and between Chinese, Japanese and Korean characters. On top of this,
+/// the kinsoku shori rules prevent a line from starting with closing punctuation or small kana,
+/// and from ending with opening brackets.
+///
+/// The end of the range is not included.
+pub(crate) fn break_opportunities(text: &str, range: Range<usize>) -> Vec<Break> {
+    let segment = &text[range.clone()];
+    linebreaks(segment)
+        .filter(|(i, _)| *i > 0 && *i < segment.len())
+        .filter(|(i, opportunity)| {
+            let next = segment[*i..].chars().next();
+            let previous = segment[..*i].chars().next_back();
+            let prohibited = next.is_some_and(|c| LINE_START_PROHIBITED.contains(&c))
+                || previous.is_some_and(|c| LINE_END_PROHIBITED.contains(&c));
+            *opportunity == BreakOpportunity::Mandatory || !prohibited
+        })
+        .map(|(i, opportunity)| Break {
+            index: range.start + i,
+            mandatory: opportunity == BreakOpportunity::Mandatory,
+            hyphen: false,
+        })
+        .collect()
+}
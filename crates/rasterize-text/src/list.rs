@@ -0,0 +1,208 @@
+//! Lay out bulleted and numbered lists, with nested levels.
+
+use crate::layout::layout;
+use crate::metadata::add_warnings;
+use crate::span::segments;
+use crate::{draw, position_glyphs, strict, Options, RasterizeError, Span};
+use image::{ImageBuffer, Rgba};
+use rusttype::{point, Font, Scale};
+
+/// A bulleted or numbered list, rasterized with [`rasterize_list`].
+///
+/// Each item starts with a marker, and the text of the item is indented past the marker,
+/// so that lines which wrap are aligned with the first line of the item (a hanging indent).
+/// Nested items are indented further, by `indent` pixels per level.
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{List, ListItem, Marker, Options, rasterize_list, EN_FONT, read_font_bytes};
+///
+/// let list = List {
+///     items: vec![
+///         ListItem { text: "Add list rendering".to_string(), level: 0 },
+///         ListItem { text: "Bulleted lists".to_string(), level: 1 },
+///         ListItem { text: "Numbered lists".to_string(), level: 1 },
+///         ListItem { text: "Fix a bug that was found while wrapping long lines".to_string(), level: 0 },
+///     ],
+///     marker: Marker::Bullet,
+///     ..Default::default()
+/// };
+/// let font    = read_font_bytes(EN_FONT)?;
+/// let options = Options { size: 24.0, wrap_width: Some(400.0), ..Default::default() };
+///
+/// let image = rasterize_list(&list, &font, &options)?;
+/// image.save("rasterize_list.png")?;
+///
+/// // The leftmost drawn pixel of each line, whose lines are separated by transparent pixel rows
+/// let drawn = |x, y| image.get_pixel(x, y)[3] > 0;
+/// let mut lefts: Vec<u32> = Vec::new();
+/// let mut gap = true;
+/// for y in 0..image.height() {
+///     match (0..image.width()).find(|x| drawn(*x, y)) {
+///         Some(x) if std::mem::replace(&mut gap, false) => lefts.push(x),
+///         Some(x) => {
+///             let left = lefts.last_mut().unwrap();
+///             *left = (*left).min(x);
+///         }
+///         None => gap = true,
+///     }
+/// }
+/// // Nested items are indented, and the last item wraps onto a second line that is aligned with its text
+/// assert_eq!(lefts.len(), 5);
+/// assert_eq!((lefts[1], lefts[2], lefts[3]), (lefts[0] + 40, lefts[0] + 40, lefts[0]));
+/// assert!(lefts[4] > lefts[3]);
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct List {
+    /// The items of the list, in order.
+    pub items: Vec<ListItem>,
+    /// The kind of marker at the start of each item.
+    pub marker: Marker,
+    /// Indent of each nested level in pixels.
+    pub indent: f32,
+}
+
+impl Default for List {
+    fn default() -> Self {
+        List {
+            items: Vec::new(),
+            marker: Marker::default(),
+            indent: 40.0,
+        }
+    }
+}
+
+/// An item of a [`List`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ListItem {
+    /// The text of the item, which may be wrapped onto several lines.
+    pub text: String,
+    /// The nesting level of the item, where 0 is the top level.
+    pub level: usize,
+}
+
+/// The kind of marker at the start of each [`ListItem`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Marker {
+    /// A bullet, which changes with the nesting level (`•`, `◦`, `▪`).
+    #[default]
+    Bullet,
+    /// A number, which alternates between digits and letters with the nesting level (`1.`, `a.`).
+    ///
+    /// Numbering restarts for each nested list.
+    Numbered,
+}
+
+impl Marker {
+    /// Returns the marker text of the nth item (starting at 0) at a nesting level.
+    fn text(self, n: usize, level: usize) -> String {
+        match (self, level % 2) {
+            (Marker::Bullet, _) => ["•", "◦", "▪"][level % 3].to_string(),
+            (Marker::Numbered, 0) => format!("{}.", n + 1),
+            (Marker::Numbered, _) => format!("{}.", alphabetic(n)),
+        }
+    }
+}
+
+/// Returns the alphabetic numbering of the nth item, starting at 0 (ex. `a`, `b`, ..., `z`, `aa`).
+fn alphabetic(mut n: usize) -> String {
+    let mut letters = Vec::new();
+    loop {
+        letters.push((b'a' + (n % 26) as u8) as char);
+        match n / 26 {
+            0 => break,
+            rest => n = rest - 1,
+        }
+    }
+    letters.iter().rev().collect()
+}
+
+/// Rasterize a [`List`] to an [`ImageBuffer`], as configured by [`Options`].
+///
+/// Each item is laid out as its own block of text, with the [`Options`] applied to the text of the item.
+/// Markers are placed at the indent of the item's level, and the text is aligned after the widest marker.
+/// The [`Options::paragraph_spacing`] is also added between items.
+pub fn rasterize_list(
+    list: &List,
+    font: &Font,
+    options: &Options,
+) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>, RasterizeError> {
+    let scale = Scale::uniform(options.size);
+    let metrics = font.v_metrics(scale);
+    let line_height = metrics.ascent - metrics.descent + metrics.line_gap;
+    let fonts: Vec<&Font> = std::iter::once(font)
+        .chain(options.fallback_fonts.iter())
+        .collect();
+
+    // Markers are laid out on a single line, without indents or alignment
+    let marker_options = Options {
+        wrap_width: None,
+        first_line_indent: 0.0,
+        hanging_indent: 0.0,
+        ..options.clone()
+    };
+
+    // Number the items, restarting the count for each nested list
+    let mut counts: Vec<usize> = Vec::new();
+    let mut warnings = Vec::new();
+    let markers: Vec<_> = list
+        .items
+        .iter()
+        .map(|item| {
+            counts.resize(item.level + 1, 0);
+            let marker = list.marker.text(counts[item.level], item.level);
+            counts[item.level] += 1;
+            let spans = [Span::from(marker)];
+            let (marker, segments) = segments(&spans, font, options);
+            let lines = layout(&marker, &segments, &fonts, &marker_options);
+            add_warnings(&mut warnings, &marker, &lines, &fonts);
+            (lines, segments)
+        })
+        .collect();
+
+    // The text of every item starts after the widest marker, with a gap of half the font size
+    let marker_width = markers
+        .iter()
+        .flat_map(|(lines, _)| lines.iter().flat_map(|line| &line.glyphs))
+        .map(|glyph| glyph.x + glyph.advance)
+        .fold(0.0, f32::max);
+    let gap = options.size / 2.0;
+
+    let mut glyphs = Vec::new();
+    let mut y = metrics.ascent;
+    for (item, (marker, marker_segments)) in list.items.iter().zip(markers) {
+        let x = item.level as f32 * list.indent;
+        let text_options = Options {
+            first_line_indent: x + marker_width + gap,
+            hanging_indent: x + marker_width + gap,
+            ..options.clone()
+        };
+        let spans = [Span::from(item.text.as_str())];
+        let (text, segments) = segments(&spans, font, options);
+        let lines = layout(&text, &segments, &fonts, &text_options);
+        add_warnings(&mut warnings, &text, &lines, &fonts);
+
+        let origin = point(x, y);
+        glyphs.extend(position_glyphs(
+            &marker,
+            &marker_segments,
+            &fonts,
+            origin,
+            options,
+        )?);
+        glyphs.extend(position_glyphs(
+            &lines,
+            &segments,
+            &fonts,
+            point(0.0, y),
+            options,
+        )?);
+        y += lines.last().map_or(0.0, |line| line.y) + line_height + options.paragraph_spacing;
+    }
+
+    let image = draw(&glyphs, &[], options)?;
+    strict::check(image.dimensions(), &glyphs, &warnings, font, options)?;
+    Ok(image)
+}
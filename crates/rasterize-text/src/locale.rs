@@ -0,0 +1,209 @@
+//! Format numbers, amounts of money and dates for a locale with ICU4X, before they are rasterized (ex. on dashboards).
+
+use crate::OptionsError;
+use fixed_decimal::{FixedDecimal, FloatPrecision};
+use icu_calendar::Date;
+use icu_datetime::options::length;
+use icu_datetime::DateFormatter;
+use icu_decimal::options::FixedDecimalFormatterOptions;
+use icu_decimal::FixedDecimalFormatter;
+use icu_experimental::dimension::currency::formatter::{CurrencyCode, CurrencyFormatter};
+use icu_locid::Locale;
+use icu_provider::DataLocale;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use tinystr::TinyAsciiStr;
+use writeable::Writeable;
+
+/// Currencies without minor units (ex. the yen has no cents), from ISO 4217.
+const ZERO_DECIMAL_CURRENCIES: [&str; 17] = [
+    "BIF", "CLP", "DJF", "GNF", "ISK", "JPY", "KMF", "KRW", "PYG", "RWF", "UGX", "UYI", "VND",
+    "VUV", "XAF", "XOF", "XPF",
+];
+/// Currencies with three decimal places of minor units, from ISO 4217.
+const THREE_DECIMAL_CURRENCIES: [&str; 7] = ["BHD", "IQD", "JOD", "KWD", "LYD", "OMR", "TND"];
+
+#[derive(Debug, thiserror::Error)]
+pub enum LocaleError {
+    #[error("Invalid locale: {0:?}. Expected a BCP 47 locale (ex. 'en-US', 'de-DE', 'ar-EG').")]
+    InvalidLocaleError(String),
+    #[error("Invalid currency: {0:?}. Expected an ISO 4217 currency code (ex. 'USD', 'EUR').")]
+    InvalidCurrencyError(String),
+    #[error("Invalid number: {0}. Numbers must be finite.")]
+    InvalidNumberError(f64),
+    #[error("Invalid date: {year}-{month:02}-{day:02}.")]
+    InvalidDateError { year: i32, month: u8, day: u8 },
+    #[error("Failed to load the data of the locale {0:?}: {1}")]
+    DataError(String, String),
+}
+
+/// How long a date formatted by [`format_date`] is, with the names and abbreviations of the locale.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum DateLength {
+    /// The full date with the day of the week (ex. "Thursday, February 29, 2024" in English).
+    Full,
+    /// The date with the name of the month (ex. "February 29, 2024" in English).
+    Long,
+    /// The date with the abbreviated name of the month (ex. "Feb 29, 2024" in English).
+    #[default]
+    Medium,
+    /// The date in numbers (ex. "2/29/24" in English).
+    Short,
+}
+
+impl Display for DateLength {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        let lowercase = format!("{:?}", self).to_lowercase();
+        write!(f, "{lowercase}")
+    }
+}
+
+impl FromStr for DateLength {
+    type Err = OptionsError;
+
+    /// Returns a [`DateLength`] converted from a [`str`].
+    fn from_str(date_length: &str) -> Result<Self, Self::Err> {
+        let date_length = match date_length {
+            "full" => DateLength::Full,
+            "long" => DateLength::Long,
+            "medium" => DateLength::Medium,
+            "short" => DateLength::Short,
+            _ => Err(OptionsError::UnknownDateLengthError(
+                date_length.to_string(),
+            ))?,
+        };
+
+        Ok(date_length)
+    }
+}
+
+/// Returns the ICU4X locale of a BCP 47 locale (ex. `de-DE`).
+///
+/// Locales without data fall back to their language, and then to the root locale, as ICU4X does.
+fn data_locale(locale: &str) -> Result<DataLocale, LocaleError> {
+    let parsed: Locale = locale
+        .parse()
+        .map_err(|_| LocaleError::InvalidLocaleError(locale.to_string()))?;
+    Ok(DataLocale::from(&parsed))
+}
+
+/// Returns a number rounded to a number of digits after the decimal point, which are padded with zeros.
+fn decimal(value: f64, fraction_digits: u8) -> Result<FixedDecimal, LocaleError> {
+    let position = -i16::from(fraction_digits);
+    let mut decimal = FixedDecimal::try_from_f64(value, FloatPrecision::Magnitude(position))
+        .map_err(|_| LocaleError::InvalidNumberError(value))?;
+    decimal.pad_end(position);
+    Ok(decimal)
+}
+
+/// Returns a number formatted for a locale, with its digits, decimal separator and grouping separators, rounded to a
+/// number of digits after the decimal point.
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{format_number, rasterize_with_options, Options, EN_FONT, read_font_bytes};
+///
+/// assert_eq!(format_number(1234.5, 2, "en-US")?, "1,234.50");
+/// assert_eq!(format_number(1234.5, 2, "de-DE")?, "1.234,50");
+/// assert_eq!(format_number(1234567.0, 0, "hi-IN")?, "12,34,567");
+/// assert_eq!(format_number(1234.5, 1, "ar-EG")?, "١٬٢٣٤٫٥");
+///
+/// let font  = read_font_bytes(EN_FONT)?;
+/// let image = rasterize_with_options(format_number(1234.5, 2, "de-DE")?, &font, &Options::default())?;
+/// image.save("format_number.png")?;
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+pub fn format_number(value: f64, fraction_digits: u8, locale: &str) -> Result<String, LocaleError> {
+    let data_locale = data_locale(locale)?;
+    let formatter =
+        FixedDecimalFormatter::try_new(&data_locale, FixedDecimalFormatterOptions::default())
+            .map_err(|e| LocaleError::DataError(locale.to_string(), e.to_string()))?;
+    Ok(formatter.format_to_string(&decimal(value, fraction_digits)?))
+}
+
+/// Returns an amount of money formatted for a locale, with the symbol of its currency (an ISO 4217 code, ex. `EUR`)
+/// placed as the locale places it.
+///
+/// The amount is rounded to the minor units of the currency (ex. cents), and is formatted like [`format_number`].
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{format_currency, rasterize_with_options, Options, EN_FONT, read_font_bytes};
+///
+/// assert_eq!(format_currency(1234.56, "EUR", "de-DE")?, "1.234,56\u{a0}€");
+/// assert_eq!(format_currency(1234.56, "USD", "en-US")?, "$1,234.56");
+/// assert_eq!(format_currency(1234.56, "JPY", "ja-JP")?, "￥1,235");
+/// assert!(format_currency(1.0, "euro", "de-DE").is_err());
+///
+/// let font  = read_font_bytes(EN_FONT)?;
+/// let image = rasterize_with_options(format_currency(1234.56, "EUR", "de-DE")?, &font, &Options::default())?;
+/// image.save("format_currency.png")?;
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+pub fn format_currency(amount: f64, currency: &str, locale: &str) -> Result<String, LocaleError> {
+    let code = TinyAsciiStr::<3>::from_str(currency)
+        .ok()
+        .filter(|code| code.len() == 3 && code.is_ascii_alphabetic_uppercase())
+        .ok_or_else(|| LocaleError::InvalidCurrencyError(currency.to_string()))?;
+    let fraction_digits = if ZERO_DECIMAL_CURRENCIES.contains(&currency) {
+        0
+    } else if THREE_DECIMAL_CURRENCIES.contains(&currency) {
+        3
+    } else {
+        2
+    };
+    let number = format_number(amount, fraction_digits, locale)?;
+
+    let data_locale = data_locale(locale)?;
+    let formatter = CurrencyFormatter::try_new(&data_locale, Default::default())
+        .map_err(|e| LocaleError::DataError(locale.to_string(), e.to_string()))?;
+    // The currency formatter places the symbol, but doesn't localize the number, so the number is formatted
+    // separately and replaces a zero in the pattern
+    let pattern = formatter
+        .format_fixed_decimal(&FixedDecimal::from(0), CurrencyCode(code))
+        .write_to_string()
+        .into_owned();
+    Ok(pattern.replacen('0', &number, 1))
+}
+
+/// Returns a date (in the ISO calendar, like `2024-02-29`) formatted for a locale, with the names of its months and
+/// days and the order of its fields.
+///
+/// Dates are converted to the calendar of the locale (ex. the Buddhist calendar in Thailand).
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{format_date, DateLength};
+///
+/// assert_eq!(format_date(2024, 2, 29, DateLength::Medium, "en-US")?, "Feb 29, 2024");
+/// assert_eq!(format_date(2024, 2, 29, DateLength::Medium, "de-DE")?, "29.02.2024");
+/// assert_eq!(format_date(2024, 2, 29, DateLength::Long, "fr-FR")?, "29 février 2024");
+/// assert!(format_date(2023, 2, 29, DateLength::Medium, "en-US").is_err());
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+pub fn format_date(
+    year: i32,
+    month: u8,
+    day: u8,
+    length: DateLength,
+    locale: &str,
+) -> Result<String, LocaleError> {
+    let date = Date::try_new_iso_date(year, month, day)
+        .map_err(|_| LocaleError::InvalidDateError { year, month, day })?;
+    let length = match length {
+        DateLength::Full => length::Date::Full,
+        DateLength::Long => length::Date::Long,
+        DateLength::Medium => length::Date::Medium,
+        DateLength::Short => length::Date::Short,
+    };
+
+    let data_locale = data_locale(locale)?;
+    let formatter = DateFormatter::try_new_with_length(&data_locale, length)
+        .map_err(|e| LocaleError::DataError(locale.to_string(), e.to_string()))?;
+    formatter
+        .format_to_string(&date.to_any())
+        .map_err(|e| LocaleError::DataError(locale.to_string(), e.to_string()))
+}
@@ -0,0 +1,105 @@
+//! Hide text before it is laid out, so that images of secrets can be shared.
+
+use crate::whitespace::is_line_break;
+use crate::{Filter, Mask, Options, Span};
+use std::ops::Range;
+
+/// The glyph that the characters within the byte ranges of [`Mask::Blur`] are replaced with.
+const BLUR_GLYPH: char = '•';
+
+/// A piece of the text of a span, which is hidden by a [`Mask`] or filtered by [`Effect`](crate::Effect)s as a whole.
+pub(crate) struct Piece<'s> {
+    /// Index of the span that the piece is from.
+    pub span: usize,
+    pub text: &'s str,
+    pub hidden: bool,
+    /// Whether the piece is within the byte ranges of the [`Options::selection`].
+    pub selected: bool,
+    /// Filters that are applied to the glyphs of the piece, from the byte ranges of the mask and effects.
+    pub filters: Vec<Filter>,
+}
+
+/// Split the texts of spans into pieces at the byte ranges of [`Mask::Blur`], [`Options::effects`] and [`Options::selection`].
+///
+/// The byte ranges are relative to the texts of the spans joined together, and a character is within a range
+/// if any of its bytes are. Text that is hidden by [`Mask::Blur`] is blurred by about a sixth of the height of a line.
+pub(crate) fn pieces<'s>(spans: &'s [Span], options: &Options) -> Vec<Piece<'s>> {
+    let mask_ranges = match &options.mask {
+        Some(Mask::Blur(ranges)) => ranges.as_slice(),
+        _ => &[],
+    };
+    let overlaps = |range: &Range<usize>, bytes: &Range<usize>| {
+        range.start < bytes.end && bytes.start < range.end
+    };
+    let selection_ranges = match &options.selection {
+        Some(selection) => selection.ranges.as_slice(),
+        None => &[],
+    };
+    let all = matches!(options.mask, Some(Mask::All(_)));
+    let mut pieces = Vec::new();
+    let mut offset = 0;
+    for (i, span) in spans.iter().enumerate() {
+        let mut start = 0;
+        let mut current = (all, false, Vec::new());
+        for (j, c) in span.text.char_indices() {
+            let bytes = offset + j..offset + j + c.len_utf8();
+            let blurred = mask_ranges.iter().any(|range| overlaps(range, &bytes));
+            let effects = options.effects.iter().filter(|effect| {
+                effect
+                    .range
+                    .as_ref()
+                    .is_some_and(|range| overlaps(range, &bytes))
+            });
+            let filters: Vec<Filter> = blurred
+                .then_some(Filter::Blur(options.size / 5.0))
+                .into_iter()
+                .chain(effects.map(|effect| effect.filter))
+                .collect();
+            let selected = selection_ranges.iter().any(|range| overlaps(range, &bytes));
+            let next = (all || blurred, selected, filters);
+            if next != current && j > start {
+                let (hidden, selected, filters) = std::mem::replace(&mut current, next);
+                pieces.push(Piece {
+                    span: i,
+                    text: &span.text[start..j],
+                    hidden,
+                    selected,
+                    filters,
+                });
+                start = j;
+            } else {
+                current = next;
+            }
+        }
+        // Empty spans are kept as empty pieces, so that every span has a segment
+        let (hidden, selected, filters) = current;
+        if start < span.text.len() || span.text.is_empty() {
+            pieces.push(Piece {
+                span: i,
+                text: &span.text[start..],
+                hidden,
+                selected,
+                filters,
+            });
+        }
+        offset += span.text.len();
+    }
+    pieces
+}
+
+/// Replace every character of hidden text except line breaks with the glyph of a [`Mask`].
+pub(crate) fn hide(text: &str, mask: Option<&Mask>) -> String {
+    let glyph = match mask {
+        Some(Mask::All(glyph)) => *glyph,
+        _ => BLUR_GLYPH,
+    };
+    text.chars()
+        .map(|c| if is_line_break(c) { c } else { glyph })
+        .collect()
+}
+
+/// Returns the [`Mask`] for text that the byte ranges of a mask don't refer to (ex. ruby),
+/// which is only hidden when all text is.
+pub(crate) fn whole_text(mask: Option<&Mask>) -> Option<Mask> {
+    mask.filter(|mask| matches!(mask, Mask::All(_))).cloned()
+}
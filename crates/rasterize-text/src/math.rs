@@ -0,0 +1,176 @@
+//! Parse a small subset of math notation into styled spans.
+
+use crate::{Position, Span, Style};
+
+#[derive(Debug, thiserror::Error)]
+pub enum MathError {
+    #[error("Missing a superscript or subscript after {0:?} at position {1}.")]
+    MissingScriptError(char, usize),
+    #[error("Unclosed group '{{' at position {0}.")]
+    UnclosedGroupError(usize),
+    #[error("Unexpected '}}' at position {0}, without an opening '{{'.")]
+    UnexpectedCloseError(usize),
+}
+
+/// A piece of a math expression, before fractions are assembled.
+#[derive(Clone, Debug, PartialEq)]
+enum Element {
+    /// A run of letters and digits, or a group in braces.
+    Atom(String),
+    Superscript(String),
+    Subscript(String),
+    Slash,
+    /// Any other character (ex. operators, spaces).
+    Other(char),
+}
+
+/// Parse a math expression into [`Span`]s, for rasterizing with [`rasterize_spans`](crate::rasterize_spans).
+///
+/// The notation is a small subset of TeX:
+///
+/// - `x^2` is a superscript, and `x_2` is a subscript. A script is a run of letters and digits (ex. `x^10`) or a group in braces (ex. `e^{-x}`).
+/// - `a/b` is a fraction, drawn with a raised numerator and lowered denominator (ex. ½). The numerator and denominator are runs of letters and digits, or groups in braces.
+/// - `-` is drawn as a minus sign (`−`), and `*` as a multiplication dot (`·`).
+///
+/// Scripts can't be nested, so the script of a script is drawn at the same position.
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{parse_math, rasterize_spans, Position, EN_FONT, read_font_bytes};
+///
+/// let spans = parse_math("E = mc^2")?;
+/// assert_eq!(spans[0].text, "E = mc");
+/// assert_eq!(spans[1].text, "2");
+/// assert_eq!(spans[1].style.position, Position::Superscript);
+///
+/// let font  = read_font_bytes(EN_FONT)?;
+/// let spans = parse_math("f(x) = e^{-x} + 1/2 x_0")?;
+/// let image = rasterize_spans(&spans, &font, &Default::default())?;
+/// image.save("rasterize_math.png")?;
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+pub fn parse_math(expr: &str) -> Result<Vec<Span>, MathError> {
+    let chars: Vec<char> = expr.chars().collect();
+
+    // Split the expression into elements
+    let mut elements = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let element = match c {
+            '^' | '_' => {
+                let (atom, end) =
+                    atom(&chars, i + 1)?.ok_or(MathError::MissingScriptError(c, i))?;
+                i = end;
+                match c {
+                    '^' => Element::Superscript(atom),
+                    _ => Element::Subscript(atom),
+                }
+            }
+            '/' => {
+                i += 1;
+                Element::Slash
+            }
+            '}' => return Err(MathError::UnexpectedCloseError(i)),
+            _ => match atom(&chars, i)? {
+                Some((atom, end)) => {
+                    i = end;
+                    Element::Atom(atom)
+                }
+                None => {
+                    i += 1;
+                    Element::Other(c)
+                }
+            },
+        };
+        elements.push(element);
+    }
+
+    // Assemble fractions from an atom, a slash and an atom, and convert the rest to styled text
+    let mut pieces: Vec<(String, Position)> = Vec::new();
+    let mut i = 0;
+    while i < elements.len() {
+        match &elements[i..] {
+            [Element::Atom(numerator), Element::Slash, Element::Atom(denominator), ..] => {
+                pieces.push((numerator.clone(), Position::Superscript));
+                pieces.push(("\u{2044}".to_string(), Position::Normal));
+                pieces.push((denominator.clone(), Position::Subscript));
+                i += 3;
+                continue;
+            }
+            [Element::Atom(atom), ..] => pieces.push((atom.clone(), Position::Normal)),
+            [Element::Superscript(script), ..] => {
+                pieces.push((script.clone(), Position::Superscript))
+            }
+            [Element::Subscript(script), ..] => pieces.push((script.clone(), Position::Subscript)),
+            [Element::Slash, ..] => pieces.push(("/".to_string(), Position::Normal)),
+            [Element::Other(c), ..] => pieces.push((symbol(*c).to_string(), Position::Normal)),
+            [] => break,
+        }
+        i += 1;
+    }
+
+    // Merge neighbouring pieces at the same position into spans
+    let mut spans: Vec<Span> = Vec::new();
+    for (text, position) in pieces {
+        match spans.last_mut() {
+            Some(span) if span.style.position == position => span.text.push_str(&text),
+            _ => spans.push(Span {
+                text,
+                style: Style {
+                    position,
+                    ..Default::default()
+                },
+                ruby: None,
+                image: None,
+            }),
+        }
+    }
+
+    Ok(spans)
+}
+
+/// Read an atom starting at index `start`: a group in braces, or a run of letters and digits.
+///
+/// Returns the text of the atom, and the index after its end.
+fn atom(chars: &[char], start: usize) -> Result<Option<(String, usize)>, MathError> {
+    match chars.get(start) {
+        Some('{') => {
+            let mut depth = 0;
+            for (i, c) in chars.iter().enumerate().skip(start) {
+                match c {
+                    '{' => depth += 1,
+                    '}' => depth -= 1,
+                    _ => (),
+                }
+                if depth == 0 {
+                    let text = chars[start + 1..i]
+                        .iter()
+                        .filter(|c| !matches!(c, '{' | '}'))
+                        .map(|c| symbol(*c))
+                        .collect();
+                    return Ok(Some((text, i + 1)));
+                }
+            }
+            Err(MathError::UnclosedGroupError(start))
+        }
+        Some(c) if c.is_alphanumeric() => {
+            let end = chars[start..]
+                .iter()
+                .position(|c| !c.is_alphanumeric())
+                .map_or(chars.len(), |n| start + n);
+            Ok(Some((chars[start..end].iter().collect(), end)))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Returns the typographic symbol for an ASCII operator (ex. `-` is drawn as a minus sign).
+fn symbol(c: char) -> char {
+    match c {
+        '-' => '−',
+        '*' => '·',
+        c => c,
+    }
+}
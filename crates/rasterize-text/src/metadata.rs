@@ -0,0 +1,180 @@
+//! Describe how text was rasterized, alongside the image.
+
+use crate::layout::Line;
+use crate::{rasterize_spans_with_metadata, Options, RasterizeError, Span};
+use image::{ImageBuffer, Rgba};
+use rusttype::Font;
+use siphasher::sip128::SipHasher13;
+use std::fmt;
+
+/// An image of rasterized text, with metadata about how the text was rasterized.
+#[derive(Clone, Debug)]
+pub struct RasterizedText {
+    /// The rasterized text.
+    pub image: ImageBuffer<Rgba<u8>, Vec<u8>>,
+    /// Characters that the main font doesn't support, in the order that they first appear in the text.
+    pub warnings: Vec<CoverageWarning>,
+}
+
+impl RasterizedText {
+    /// Returns a hash of the rasterized image, which is the same for the same pixels across runs, platforms
+    /// and versions of the crate (see [`content_hash`]).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use rasterize_text::{Options, rasterize_with_metadata, EN_FONT, read_font_bytes};
+    /// use siphasher::sip128::{Hasher128, SipHasher13};
+    /// use std::hash::Hasher;
+    ///
+    /// let font    = read_font_bytes(EN_FONT)?;
+    /// let options = Options::default();
+    ///
+    /// let hello = rasterize_with_metadata(&"Hello, World!", &font, &options)?;
+    /// // Rendering the same text again has the same hash, so the image doesn't need to be uploaded again
+    /// assert_eq!(hello.content_hash(), rasterize_with_metadata(&"Hello, World!", &font, &options)?.content_hash());
+    /// assert_ne!(hello.content_hash(), rasterize_with_metadata(&"Hello, World?", &font, &options)?.content_hash());
+    /// // The hash is the SipHash-1-3 of the size of the image and its pixels
+    /// let mut hasher = SipHasher13::new();
+    /// hasher.write(&hello.image.width().to_le_bytes());
+    /// hasher.write(&hello.image.height().to_le_bytes());
+    /// hasher.write(hello.image.as_raw());
+    /// assert_eq!(hello.content_hash(), hasher.finish128().as_u128());
+    /// # Ok::<(), color_eyre::eyre::Report>(())
+    /// ```
+    pub fn content_hash(&self) -> u128 {
+        content_hash(&self.image)
+    }
+}
+
+/// Returns a hash of an image, which is the same for the same pixels across runs, platforms and versions of the crate.
+///
+/// The hash is the 128 bit SipHash-1-3 (with keys of zero) of the width and height of the image as little endian
+/// 32 bit integers, followed by its RGBA pixels. Only the pixels are hashed, so the hash doesn't change with the way
+/// that the image is encoded (ex. the [`ColorTag`](crate::ColorTag) of a PNG).
+pub fn content_hash(image_buffer: &ImageBuffer<Rgba<u8>, Vec<u8>>) -> u128 {
+    let mut bytes = Vec::with_capacity(8 + image_buffer.as_raw().len());
+    bytes.extend(image_buffer.width().to_le_bytes());
+    bytes.extend(image_buffer.height().to_le_bytes());
+    bytes.extend(image_buffer.as_raw());
+    SipHasher13::new().hash(&bytes).as_u128()
+}
+
+/// A character that the main font doesn't support.
+///
+/// The warnings of a [`RasterizedText`] can be used to detect text that is not fully supported by the fonts,
+/// such as a change in the text or fonts that makes characters fall back to another font, or be drawn as a missing glyph (`.notdef`).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CoverageWarning {
+    /// The character was drawn with a fallback font.
+    Fallback {
+        character: char,
+        /// Index of the font in [`Options::fallback_fonts`].
+        font: usize,
+        /// Number of times the character was drawn with the fallback font.
+        count: usize,
+    },
+    /// No font supports the character, so it was drawn as the missing glyph of the main font (often a box).
+    Missing {
+        character: char,
+        /// Number of times the character was drawn as a missing glyph.
+        count: usize,
+    },
+}
+
+impl fmt::Display for CoverageWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CoverageWarning::Fallback {
+                character,
+                font,
+                count,
+            } => write!(
+                f,
+                "Character {character:?} (U+{:04X}) is not supported by the main font, and was drawn {count} time(s) with the fallback font at index {font}.",
+                *character as u32
+            ),
+            CoverageWarning::Missing { character, count } => write!(
+                f,
+                "Character {character:?} (U+{:04X}) is not supported by any font, and was drawn {count} time(s) as a missing glyph.",
+                *character as u32
+            ),
+        }
+    }
+}
+
+/// Rasterize a string of text to a [`RasterizedText`], as configured by [`Options`].
+///
+/// This is the same as [`rasterize_with_options`](crate::rasterize_with_options), but also returns warnings
+/// for characters that the main font doesn't support.
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{CoverageWarning, Options, rasterize_with_metadata, EN_FONT, KR_FONT, read_font_bytes};
+///
+/// let text    = "Hello 안녕 🦀";
+/// let font    = read_font_bytes(EN_FONT)?;
+/// let options = Options { fallback_fonts: vec![read_font_bytes(KR_FONT)?], ..Default::default() };
+///
+/// let rasterized = rasterize_with_metadata(&text, &font, &options)?;
+/// assert_eq!(rasterized.warnings, vec![
+///     CoverageWarning::Fallback { character: '안', font: 0, count: 1 },
+///     CoverageWarning::Fallback { character: '녕', font: 0, count: 1 },
+///     CoverageWarning::Missing { character: '🦀', count: 1 },
+/// ]);
+/// rasterized.image.save("rasterize_metadata.png")?;
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+pub fn rasterize_with_metadata(
+    text: impl AsRef<str>,
+    font: &Font,
+    options: &Options,
+) -> Result<RasterizedText, RasterizeError> {
+    rasterize_spans_with_metadata(&[Span::from(text.as_ref())], font, options)
+}
+
+/// Add a warning for each glyph of the lines that is not drawn with the main font, counting repeated characters.
+///
+/// Characters that the main font supports are not reported, even if they were drawn with a fallback font
+/// to match their neighbours (ex. spaces between words of another script).
+pub(crate) fn add_warnings(
+    warnings: &mut Vec<CoverageWarning>,
+    text: &str,
+    lines: &[Line],
+    fonts: &[&Font],
+) {
+    for glyph in lines.iter().flat_map(|line| &line.glyphs) {
+        // Glyphs that were added during layout (ex. hyphens) are not part of the text
+        let Some(character) = text.get(glyph.cluster..).and_then(|s| s.chars().next()) else {
+            continue;
+        };
+        if glyph.font == 0 && glyph.id != 0 || fonts[0].glyph(character).id().0 != 0 {
+            continue;
+        }
+        let existing = warnings.iter_mut().find_map(|warning| match warning {
+            CoverageWarning::Fallback {
+                character: c,
+                font,
+                count,
+            } if *c == character && *font + 1 == glyph.font => Some(count),
+            CoverageWarning::Missing {
+                character: c,
+                count,
+            } if *c == character => Some(count),
+            _ => None,
+        });
+        match (existing, glyph.id, glyph.font) {
+            (Some(count), _, _) => *count += 1,
+            (None, 0, _) | (None, _, 0) => warnings.push(CoverageWarning::Missing {
+                character,
+                count: 1,
+            }),
+            (None, _, font) => warnings.push(CoverageWarning::Fallback {
+                character,
+                font: font - 1,
+                count: 1,
+            }),
+        }
+    }
+}
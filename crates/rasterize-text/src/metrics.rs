@@ -0,0 +1,158 @@
+//! Iterate over the metrics of laid out glyphs, for caret positioning and hit testing.
+
+use crate::layout::layout;
+use crate::span::Segment;
+use crate::{Color, Options};
+use rusttype::{point, Font, GlyphId, Point, Rect, Scale};
+use std::str::SplitInclusive;
+use std::vec::IntoIter;
+
+/// The metrics of a laid out glyph, from [`layout_iter`].
+///
+/// Positions are in the pixel coordinates of the image that the text is rasterized to, with y increasing downwards.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct GlyphMetrics {
+    /// The first character of the cluster of the glyph.
+    pub character: char,
+    /// The id of the glyph in the font.
+    pub id: u16,
+    /// The position of the glyph on its baseline, where a caret before it is placed.
+    pub position: Point<f32>,
+    /// How far the pen moves after the glyph in pixels, including kerning.
+    pub advance: f32,
+    /// The exact bounding box of the outline of the glyph, if it has one (ex. not a space).
+    pub bounding_box: Option<Rect<f32>>,
+    /// The byte index in the text of the first character of the cluster of the glyph.
+    ///
+    /// Glyphs of the same cluster (ex. a base and its combining mark) have the same index,
+    /// and characters that are combined into one glyph (ex. a ligature) are covered by the index of the first.
+    pub cluster: usize,
+    /// The index of the line of the glyph, from the top.
+    pub line: usize,
+}
+
+/// An iterator over the metrics of laid out glyphs, from [`layout_iter`].
+pub struct LayoutIter<'a> {
+    font: &'a Font<'a>,
+    options: Options,
+    paragraphs: SplitInclusive<'a, char>,
+    /// The byte index of the next paragraph, and the index and position of its first line.
+    start: usize,
+    line: usize,
+    y: f32,
+    glyphs: IntoIter<GlyphMetrics>,
+}
+
+/// Lay out a string of text at a font size in pixels, and iterate over the metrics of its glyphs.
+///
+/// The text is laid out the same as with [`rasterize`](crate::rasterize), so custom caret positioning and hit testing
+/// can use the positions of glyphs instead of deriving them from pixels. Paragraphs are only laid out when the
+/// iterator reaches them, and their glyphs are in visual (left-to-right) order.
+///
+/// Unlike rasterizing, the text isn't normalized, so the clusters of glyphs are byte indices in the text as given.
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{layout_iter, EN_FONT, read_font_bytes};
+///
+/// let text = "Hello\nWorld";
+/// let font = read_font_bytes(EN_FONT)?;
+///
+/// let glyphs: Vec<_> = layout_iter(text, &font, 40.0).collect();
+/// assert_eq!(glyphs.len(), 10);
+/// assert_eq!(glyphs[5].character, 'W');
+/// assert_eq!(glyphs[5].cluster, 6);
+/// // The second line starts at the left, below the first
+/// assert_eq!(glyphs[5].position.x, 0.0);
+/// assert!(glyphs[5].position.y > glyphs[0].position.y);
+///
+/// // Hit test a point, to find the character under it
+/// let hit = glyphs.iter().find(|glyph| {
+///     let x = 50.0;
+///     glyph.position.y == glyphs[0].position.y && glyph.position.x <= x && x < glyph.position.x + glyph.advance
+/// });
+/// assert_eq!(hit.map(|glyph| glyph.character), Some('l'));
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+pub fn layout_iter<'a>(text: &'a str, font: &'a Font<'a>, size: f32) -> LayoutIter<'a> {
+    let options = Options {
+        size,
+        ..Default::default()
+    };
+    LayoutIter {
+        font,
+        y: font.v_metrics(Scale::uniform(size)).ascent,
+        options,
+        paragraphs: text.split_inclusive('\n'),
+        start: 0,
+        line: 0,
+        glyphs: Vec::new().into_iter(),
+    }
+}
+
+impl Iterator for LayoutIter<'_> {
+    type Item = GlyphMetrics;
+
+    fn next(&mut self) -> Option<GlyphMetrics> {
+        loop {
+            if let Some(glyph) = self.glyphs.next() {
+                return Some(glyph);
+            }
+            let paragraph = self.paragraphs.next()?;
+            let start = self.start;
+            self.start += paragraph.len();
+
+            let size = self.options.size;
+            let segments = [Segment {
+                range: 0..paragraph.len(),
+                span: 0,
+                size,
+                rise: 0.0,
+                color: Color::default(),
+                background: None,
+                bold: false,
+                underline: false,
+                strikethrough: false,
+                spoiler: None,
+                object: None,
+                filters: Vec::new(),
+            }];
+            let lines = layout(paragraph, &segments, &[self.font], &self.options);
+            let scale = Scale::uniform(size);
+            let metrics = self.font.v_metrics(scale);
+            let mut glyphs = Vec::new();
+            for (i, line) in lines.iter().enumerate() {
+                for glyph in &line.glyphs {
+                    let position = point(glyph.x, self.y + line.y + glyph.y);
+                    let bounding_box = self
+                        .font
+                        .glyph(GlyphId(glyph.id))
+                        .scaled(scale)
+                        .exact_bounding_box()
+                        .map(|bounds| Rect {
+                            min: point(bounds.min.x + position.x, bounds.min.y + position.y),
+                            max: point(bounds.max.x + position.x, bounds.max.y + position.y),
+                        });
+                    glyphs.push(GlyphMetrics {
+                        character: paragraph[glyph.cluster..]
+                            .chars()
+                            .next()
+                            .unwrap_or_default(),
+                        id: glyph.id,
+                        position,
+                        advance: glyph.advance,
+                        bounding_box,
+                        cluster: start + glyph.cluster,
+                        line: self.line + i,
+                    });
+                }
+            }
+            // The next paragraph starts a line below the last line of this one
+            let line_height = metrics.ascent - metrics.descent + metrics.line_gap;
+            self.y += lines.last().map_or(0.0, |line| line.y) + line_height;
+            self.line += lines.len().max(1);
+            self.glyphs = glyphs.into_iter();
+        }
+    }
+}
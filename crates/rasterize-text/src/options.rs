@@ -0,0 +1,1725 @@
+//! Configure how text is laid out and rasterized.
+
+use crate::{Color, LicensePolicy};
+use rusttype::Font;
+use std::fmt::{Display, Formatter};
+use std::ops::Range;
+use std::str::FromStr;
+
+/// Options that control how text is laid out and rasterized with [`rasterize_with_options`](crate::rasterize_with_options).
+///
+/// Options are most easily created by setting the fields of interest, and using the [`Default`] for the rest.
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{Color, Direction, Options};
+///
+/// let options = Options {
+///     size: 24.0,
+///     color: Color { r: 255, g: 0, b: 0, a: 255 },
+///     ..Default::default()
+/// };
+/// assert_eq!(options.direction, Direction::Auto);
+/// ```
+///
+/// Paragraphs can be formatted like typeset copy, with an indent on the first line and extra space between paragraphs.
+///
+/// ```rust
+/// use rasterize_text::{Options, rasterize_with_options, EN_FONT, read_font_bytes};
+///
+/// let text    = "The quick brown fox jumps over the lazy dog.\nPack my box with five dozen liquor jugs.";
+/// let font    = read_font_bytes(EN_FONT)?;
+/// let flat    = Options { wrap_width: Some(500.0), ..Default::default() };
+/// let typeset = Options { first_line_indent: 40.0, paragraph_spacing: 20.0, ..flat.clone() };
+///
+/// let flat    = rasterize_with_options(&text, &font, &flat)?;
+/// let typeset = rasterize_with_options(&text, &font, &typeset)?;
+/// typeset.save("rasterize_paragraphs.png")?;
+/// assert_eq!(typeset.height(), flat.height() + 20);
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+///
+/// Strings rendered independently can share their baselines, so that they line up when they are tiled side by side.
+///
+/// ```rust
+/// use rasterize_text::{Options, rasterize_with_options, EN_FONT, read_font_bytes};
+///
+/// let font    = read_font_bytes(EN_FONT)?;
+/// let options = Options { size: 24.0, fixed_ascent: Some(24.0), baseline_grid: Some(8.0), ..Default::default() };
+///
+/// let low  = rasterize_with_options(&"ace", &font, &options)?;
+/// let tall = rasterize_with_options(&"Hdgy", &font, &options)?;
+/// let two  = rasterize_with_options(&"ace\nace", &font, &options)?;
+/// assert_eq!(low.height(), tall.height());
+/// assert_eq!(low.height() % 8, 0);
+/// assert_eq!(two.height() % 8, 0);
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+///
+/// The faint fringes around glyphs can be cut off, and their edges made more solid.
+///
+/// ```rust
+/// use rasterize_text::{Options, rasterize_with_options, EN_FONT, read_font_bytes};
+///
+/// let text  = "Crisp edges";
+/// let font  = read_font_bytes(EN_FONT)?;
+/// let crisp = Options { size: 20.0, coverage_threshold: 0.1, min_alpha: 0.3, ..Default::default() };
+///
+/// let smooth = rasterize_with_options(&text, &font, &Options { size: 20.0, ..Default::default() })?;
+/// let crisp  = rasterize_with_options(&text, &font, &crisp)?;
+/// // Every pixel is either transparent, or has at least 30% of the alpha of the text
+/// assert!(crisp.pixels().all(|pixel| pixel[3] == 0 || pixel[3] >= 76));
+/// assert!(smooth.pixels().any(|pixel| pixel[3] > 0 && pixel[3] < 26));
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+///
+/// Only the outlines of glyphs can be drawn, for stencils and plotters.
+///
+/// ```rust
+/// use rasterize_text::{Color, Options, rasterize_with_options, EN_FONT, read_font_bytes};
+///
+/// let text   = "Hollow";
+/// let font   = read_font_bytes(EN_FONT)?;
+/// let filled = rasterize_with_options(&text, &font, &Options { size: 80.0, ..Default::default() })?;
+/// let hollow = rasterize_with_options(&text, &font, &Options { size: 80.0, stroke_width: Some(2.0), ..Default::default() })?;
+/// hollow.save("rasterize_hollow.png")?;
+///
+/// // The hollow glyphs cover the same pixels, but fewer of them are opaque
+/// assert_eq!(hollow.dimensions(), filled.dimensions());
+/// let opaque = |image: &image::RgbaImage| image.pixels().filter(|pixel| pixel[3] == 255).count();
+/// assert!(opaque(&hollow) > 0);
+/// assert!(opaque(&hollow) < opaque(&filled) / 2);
+///
+/// // The strokes can be drawn in another color than the text
+/// let red     = Color { r: 255, g: 0, b: 0, a: 255 };
+/// let options = Options { size: 80.0, stroke_width: Some(2.0), stroke_color: Some(red), ..Default::default() };
+/// let red_hollow = rasterize_with_options(&text, &font, &options)?;
+/// assert!(red_hollow.pixels().any(|pixel| pixel[3] == 255 && pixel[0] == 255 && pixel[1] == 0));
+/// assert!(red_hollow.pixels().all(|pixel| pixel[0] == pixel[3] && pixel[1] == 0));
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+#[derive(Clone, Debug)]
+pub struct Options {
+    /// Font size in pixels (ex. `50.0`).
+    pub size: f32,
+    /// A [`Color`] that stores RGBA values reflecting the Red, Green, Blue, and Alpha channels.
+    pub color: Color,
+    /// The base [`Direction`] of the text, used by the Unicode Bidirectional Algorithm.
+    pub direction: Direction,
+    /// The language of the text, for scripts where glyph choice depends on the language (ex. Serbian vs. Russian Cyrillic).
+    ///
+    /// If no language is provided, only the script of the text is used during shaping.
+    ///
+    /// With the `hyphenation` feature, the language also selects the patterns used to hyphenate words when wrapping.
+    pub lang: Option<LanguageTag>,
+    /// Fonts to use, in order, for characters that are missing from the main font (ex. Korean text in an English font).
+    pub fallback_fonts: Vec<Font<'static>>,
+    /// Maximum width of a line in pixels, beyond which text wraps onto the next line.
+    ///
+    /// If no width is provided, lines are only broken at newlines and line separators.
+    pub wrap_width: Option<f32>,
+    /// How lines are aligned horizontally, within the wrap width or the width of the longest line.
+    pub align: Align,
+    /// Limits on how much a line is stretched when it is justified with [`Align::Justify`].
+    pub justification: Justification,
+    /// Indent of the first line of each paragraph in pixels, from the left edge.
+    pub first_line_indent: f32,
+    /// Indent of every line of a paragraph except the first in pixels, from the left edge (ex. for references or list items).
+    pub hanging_indent: f32,
+    /// Extra vertical space between paragraphs in pixels.
+    pub paragraph_spacing: f32,
+    /// Extra horizontal space between characters in pixels (tracking), which is added to the advance of each character
+    /// (ex. `4.0` for spaced out small capitals, or `-2.0` for tight headlines).
+    ///
+    /// Ligatures are turned off when the text is spaced, so that the characters they join are spaced too.
+    /// Tabs still advance to their tab stops, and the spacing is added to the advance of an inline image once.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use rasterize_text::{rasterize_with_options, Options, EN_FONT, read_font_bytes};
+    ///
+    /// let font   = read_font_bytes(EN_FONT)?;
+    /// let normal = rasterize_with_options("TRACKING", &font, &Options::default())?;
+    /// let spaced = rasterize_with_options("TRACKING", &font, &Options { letter_spacing: 10.0, ..Default::default() })?;
+    /// let tight  = rasterize_with_options("TRACKING", &font, &Options { letter_spacing: -2.0, ..Default::default() })?;
+    ///
+    /// // The space is added between the 8 characters, so the ink is 7 spaces wider
+    /// let ink = |image: &image::RgbaImage| {
+    ///     let columns: Vec<u32> = (0..image.width())
+    ///         .filter(|x| (0..image.height()).any(|y| image.get_pixel(*x, y)[3] > 0))
+    ///         .collect();
+    ///     columns.last().unwrap() - columns.first().unwrap()
+    /// };
+    /// assert_eq!(ink(&spaced), ink(&normal) + 70);
+    /// assert_eq!(ink(&tight), ink(&normal) - 14);
+    /// # Ok::<(), color_eyre::eyre::Report>(())
+    /// ```
+    pub letter_spacing: f32,
+    /// Distance in pixels from the top of the image to the baseline of the first line, instead of the ascent of the main font.
+    ///
+    /// The image then also spans the full height of its lines, down to the descent of the last line, rather than only their ink,
+    /// so that strings rendered independently (ex. each cell of a table) have their baselines at the same height and can be tiled.
+    /// Glyphs that rise above the fixed ascent are clipped.
+    pub fixed_ascent: Option<f32>,
+    /// Snaps the baseline of each line down to the next multiple of this many pixels from the top of the image (ex. `8.0`).
+    ///
+    /// Lines are at least one grid step apart, and with [`Options::fixed_ascent`] the bottom of the image is snapped to the grid too,
+    /// so that independently rendered strings in different fonts or sizes share a baseline grid.
+    /// If no grid is provided, baselines are spaced by the line height of the main font.
+    pub baseline_grid: Option<f32>,
+    /// Column stops that tab characters (`\t`) advance to, for aligning columns of text (ex. the output of `ps`).
+    ///
+    /// If no tab stops are provided, tabs are drawn as a glyph of the font (often a missing glyph).
+    pub tab_stops: Option<TabStops>,
+    /// Numbers drawn in a gutter to the left of each line of the text (ex. for code or logs).
+    ///
+    /// If no line numbers are provided, the lines are not numbered.
+    pub line_numbers: Option<LineNumbers>,
+    /// How whitespace is trimmed and collapsed before the text is laid out (ex. for text from a form).
+    pub whitespace: Whitespace,
+    /// How the case of the text is changed before it is laid out (ex. uppercase for headings).
+    pub text_transform: TextTransform,
+    /// Whether digits are drawn at the same width (tabular figures), with the `tnum` feature of the fonts, so that the
+    /// digits of numbers in rows line up (ex. in tables or counters). Many fonts have tabular digits by default.
+    pub tabular_figures: bool,
+    /// Whether emoji shortcodes (ex. `:rocket:`) are replaced with their emoji before the text is laid out, so that chat
+    /// messages are drawn as they were typed. Shortcodes that aren't emoji are drawn as is. This requires the `emoji`
+    /// feature, without which shortcodes are always drawn as is.
+    ///
+    /// ```rust
+    /// # #[cfg(feature = "emoji")] {
+    /// use rasterize_text::{rasterize_with_options, Options, EN_FONT, read_font_bytes};
+    ///
+    /// let font    = read_font_bytes(EN_FONT)?;
+    /// let options = Options { emoji_shortcodes: true, ..Default::default() };
+    /// let typed   = rasterize_with_options("Shipped :rocket: at 12:30:45", &font, &options)?;
+    /// let emoji   = rasterize_with_options("Shipped 🚀 at 12:30:45", &font, &Options::default())?;
+    /// assert_eq!(typed, emoji);
+    /// # }
+    /// # Ok::<(), color_eyre::eyre::Report>(())
+    /// ```
+    pub emoji_shortcodes: bool,
+    /// How the text is hidden before it is laid out (ex. for screenshots of passwords or API keys).
+    ///
+    /// If no mask is provided, the text is drawn as is.
+    pub mask: Option<Mask>,
+    /// Whether the text of [`Style::spoiler`](crate::Style::spoiler) spans is drawn over a faint bar, rather than hidden
+    /// behind a solid bar (ex. for the revealed layer of a preview of moderated content).
+    pub reveal_spoilers: bool,
+    /// Filters that are applied to the image after the text is drawn, to the whole image or to the glyphs of byte ranges of the text.
+    pub effects: Vec<Effect>,
+    /// Byte ranges of the text that are selected, which are drawn over a [`Selection`] color like in a text editor.
+    ///
+    /// If no selection is provided, no text is selected.
+    pub selection: Option<Selection>,
+    /// A [`Background`] that the text is drawn over, which fills the image (ex. for quote images).
+    ///
+    /// If no background is provided, the background is transparent.
+    pub background: Option<Background>,
+    /// Radius of the corners of the image in pixels, which are cut out so that they are transparent (ex. for labels in UIs).
+    pub corner_radius: f32,
+    /// A [`Border`] that is drawn around the outside of the image, following its rounded corners.
+    ///
+    /// If no border is provided, the image has no border.
+    pub border: Option<Border>,
+    /// Whether glyphs are snapped to whole pixels, which keeps text from shimmering when it is drawn at fractional offsets (ex. in animations).
+    pub pixel_snap: PixelSnap,
+    /// The color space that the coverage of glyphs is blended in, with their backgrounds and each other.
+    pub blending: Blending,
+    /// Coverage below which the pixels of glyphs are left transparent, from 0 to 1 (ex. `0.05`).
+    ///
+    /// Pixels that glyphs barely cover make faint fringes around them, which look dirty on some displays.
+    pub coverage_threshold: f32,
+    /// The least alpha of the pixels of glyphs that are drawn, as a fraction of the alpha of their color,
+    /// which makes the edges of glyphs more solid (ex. `0.25`).
+    pub min_alpha: f32,
+    /// The most alpha of the pixels of glyphs and underlines, as a fraction of the alpha of their color (ex. `0.9`).
+    ///
+    /// If no maximum is provided, the alpha isn't clamped.
+    pub max_alpha: Option<f32>,
+    /// Draw only the outlines of glyphs (hollow text), with a stroke of a width in pixels, rather than filling them (ex. `2.0`).
+    ///
+    /// The stroke is drawn inside of the outlines, so hollow glyphs cover the same pixels as filled glyphs.
+    /// Underlines and backgrounds are still filled. If no width is provided, glyphs are filled.
+    pub stroke_width: Option<f32>,
+    /// The color of the strokes of hollow glyphs, with the [`Options::stroke_width`].
+    ///
+    /// If no color is provided, the strokes are drawn in the color of the text. Color glyphs (ex. emoji) keep
+    /// their colors.
+    pub stroke_color: Option<Color>,
+    /// The palette that color glyphs of the fonts are drawn in (ex. for emoji or branded icon fonts), and the colors
+    /// that replace its entries.
+    pub color_palette: ColorPalette,
+    /// A directory of Twemoji assets (ex. the `assets/72x72` or `assets/svg` directory of Twemoji), whose images are
+    /// drawn instead of the glyphs of emoji, as squares that span the height of the line. SVG assets are only read with
+    /// the `svg` feature.
+    ///
+    /// Assets are named by the codepoints of their emoji (ex. `1f680.png` for 🚀), and emoji without an asset are drawn
+    /// with the fonts. The assets aren't bundled, so they must be downloaded (ex. from the Twemoji repository), and
+    /// credited under the CC-BY 4.0 license of their graphics. This requires the `twemoji` feature, without which emoji
+    /// are always drawn with the fonts.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # #[cfg(feature = "twemoji")] {
+    /// use image::{Rgba, RgbaImage};
+    /// use rasterize_text::{Options, rasterize_with_options, EN_FONT, read_font_bytes};
+    /// use rusttype::Scale;
+    ///
+    /// // A directory with a red square for the rocket emoji
+    /// let assets = std::env::temp_dir().join("rasterize_twemoji");
+    /// std::fs::create_dir_all(&assets)?;
+    /// RgbaImage::from_pixel(72, 72, Rgba([255, 0, 0, 255])).save(assets.join("1f680.png"))?;
+    ///
+    /// let font    = read_font_bytes(EN_FONT)?;
+    /// let options = Options { twemoji: Some(assets), ..Default::default() };
+    /// let image   = rasterize_with_options("Go 🚀!", &font, &options)?;
+    ///
+    /// // The emoji spans the height of the line, from the ascent to the descent of the font
+    /// let metrics = font.v_metrics(Scale::uniform(options.size));
+    /// let size    = (metrics.ascent - metrics.descent).round() as usize;
+    /// let red     = image.pixels().filter(|pixel| **pixel == Rgba([255, 0, 0, 255])).count();
+    /// assert_eq!(red, size * size);
+    /// # }
+    /// # Ok::<(), color_eyre::eyre::Report>(())
+    /// ```
+    pub twemoji: Option<std::path::PathBuf>,
+    /// How characters that no font supports are drawn, as a [`Tofu`] box with their codepoint (ex. to debug generated assets).
+    ///
+    /// If no tofu is provided, the missing glyph of the main font (`.notdef`) is drawn, which is often an empty box.
+    pub tofu: Option<Tofu>,
+    /// Whether conditions that are recovered from are errors, for pipelines that must not produce broken images
+    /// (see [`StrictError`](crate::StrictError)).
+    ///
+    /// Missing glyphs, an empty image, a font without kerning, and glyphs that are clipped at the edges of the image
+    /// (ex. text that doesn't fit on a card, or a glyph that is wider than the cells of a grid) return
+    /// [`RasterizeError::Strict`](crate::RasterizeError::Strict) instead of an image.
+    pub strict: bool,
+    /// What happens when text is rendered with a font whose embedding permissions are restricted (see [`font_info`](crate::font_info)),
+    /// for pipelines that must not redistribute fonts without a license.
+    ///
+    /// Only the fonts that glyphs are drawn with are checked, including fallback fonts.
+    pub license_policy: LicensePolicy,
+    /// Maximum width of the image in pixels.
+    ///
+    /// Rasterizing text that is wider returns [`RasterizeError::TooLarge`](crate::RasterizeError::TooLarge)
+    /// instead of allocating the image, which protects against excessive memory use from untrusted input (ex. a huge font size).
+    /// Regardless of the limits, glyphs must also be within 2<sup>30</sup> pixels of the origin.
+    pub max_width: u32,
+    /// Maximum height of the image in pixels (see [`Options::max_width`]).
+    pub max_height: u32,
+    /// Maximum number of pixels in the image (see [`Options::max_width`]). Each pixel uses 4 bytes.
+    pub max_pixels: u64,
+    /// Maximum number of glyphs that are laid out, which bounds the time that drawing untrusted input takes (ex. a
+    /// long text at a small size, whose image is within the pixel limits).
+    ///
+    /// Laying out more glyphs returns [`RasterizeError::TooManyGlyphs`](crate::RasterizeError::TooManyGlyphs)
+    /// before they are drawn.
+    pub max_glyphs: usize,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            size: 50.0,
+            color: Color::default(),
+            direction: Direction::default(),
+            lang: None,
+            fallback_fonts: Vec::new(),
+            wrap_width: None,
+            align: Align::default(),
+            justification: Justification::default(),
+            first_line_indent: 0.0,
+            hanging_indent: 0.0,
+            paragraph_spacing: 0.0,
+            letter_spacing: 0.0,
+            fixed_ascent: None,
+            baseline_grid: None,
+            tab_stops: None,
+            line_numbers: None,
+            whitespace: Whitespace::default(),
+            text_transform: TextTransform::default(),
+            tabular_figures: false,
+            emoji_shortcodes: false,
+            mask: None,
+            reveal_spoilers: false,
+            effects: Vec::new(),
+            selection: None,
+            background: None,
+            corner_radius: 0.0,
+            border: None,
+            pixel_snap: PixelSnap::default(),
+            blending: Blending::default(),
+            coverage_threshold: 0.0,
+            min_alpha: 0.0,
+            max_alpha: None,
+            stroke_width: None,
+            stroke_color: None,
+            color_palette: ColorPalette::default(),
+            twemoji: None,
+            tofu: None,
+            strict: false,
+            license_policy: LicensePolicy::default(),
+            max_width: 16_384,
+            max_height: 16_384,
+            // 256 MiB of RGBA pixels
+            max_pixels: 1 << 26,
+            max_glyphs: 1 << 20,
+        }
+    }
+}
+
+impl Options {
+    /// Returns a function that cuts off and clamps the coverage of a pixel of a glyph, from 0 to 1,
+    /// with the [`Options::coverage_threshold`], [`Options::min_alpha`] and [`Options::max_alpha`].
+    pub(crate) fn coverage(&self) -> impl Fn(f32) -> f32 {
+        let (threshold, min, max) = (self.coverage_threshold, self.min_alpha, self.max_alpha);
+        move |v| match v <= 0.0 || v < threshold {
+            true => 0.0,
+            // The alpha isn't clamped with f32::clamp, which panics if the minimum is more than the maximum
+            false => max.map_or(v.max(min), |max| v.max(min).min(max)),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum OptionsError {
+    #[error("Unknown direction: {0:?}. Expected one of: auto, ltr, rtl.")]
+    UnknownDirectionError(String),
+    #[error("Invalid language tag: {0:?}. Expected a BCP 47 language tag (ex. 'en', 'sr-Cyrl', 'tr-TR').")]
+    InvalidLanguageTagError(String),
+    #[error("Unknown alignment: {0:?}. Expected one of: left, center, right, justify.")]
+    UnknownAlignError(String),
+    #[error("Unknown last line policy: {0:?}. Expected one of: left, center, right, justify.")]
+    UnknownLastLineError(String),
+    #[error("Unknown pixel snapping: {0:?}. Expected one of: off, positions, advances, quarters.")]
+    UnknownPixelSnapError(String),
+    #[error("Unknown blending: {0:?}. Expected one of: srgb, linear.")]
+    UnknownBlendingError(String),
+    #[error("Unknown whitespace handling: {0:?}. Expected one of: preserve, trim, collapse, collapsespaces.")]
+    UnknownWhitespaceError(String),
+    #[error("Unknown text transform: {0:?}. Expected one of: none, uppercase, lowercase, titlecase, smallcaps.")]
+    UnknownTextTransformError(String),
+    #[error("Unknown filter: {0:?}. Expected one of: blur:SIGMA, pixelate:SIZE, invert, desaturate (ex. 'blur:4', 'pixelate:8').")]
+    UnknownFilterError(String),
+    #[error("Unknown fit: {0:?}. Expected one of: cover, contain, tile.")]
+    UnknownFitError(String),
+    #[error("Unknown anchor: {0:?}. Expected one of: topleft, top, topright, left, center, right, bottomleft, bottom, bottomright.")]
+    UnknownAnchorError(String),
+    #[error("Unknown side: {0:?}. Expected one of: top, right, bottom, left.")]
+    UnknownSideError(String),
+    #[error("Unknown color tag: {0:?}. Expected one of: srgb, icc, none.")]
+    UnknownColorTagError(String),
+    #[error("Unknown tofu style: {0:?}. Expected one of: outline, filled.")]
+    UnknownTofuStyleError(String),
+    #[error("Unknown paper size: {0:?}. Expected one of: a4, a3, letter.")]
+    UnknownPaperSizeError(String),
+    #[error("Unknown diff layout: {0:?}. Expected one of: unified, sidebyside.")]
+    UnknownDiffLayoutError(String),
+    #[error("Unknown week start: {0:?}. Expected one of: monday, sunday.")]
+    UnknownWeekStartError(String),
+    #[error("Unknown date length: {0:?}. Expected one of: full, long, medium, short.")]
+    UnknownDateLengthError(String),
+    #[error("Unknown license policy: {0:?}. Expected one of: ignore, warn, deny.")]
+    UnknownLicensePolicyError(String),
+    #[error("Invalid tab stops: {0:?}. Expected increasing numbers separated by commas, optionally ending with 'px' for pixels (ex. '8', '10,24,40', '120px').")]
+    InvalidTabStopsError(String),
+}
+
+/// The base (paragraph) direction of text.
+///
+/// The base direction decides how runs of left-to-right and right-to-left text are ordered relative to each other,
+/// and which direction neutral characters (ex. spaces, punctuation) follow.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Direction {
+    /// Detect the direction from the first strong directional character, defaulting to left-to-right.
+    #[default]
+    Auto,
+    /// Left-to-right (ex. English, Korean).
+    Ltr,
+    /// Right-to-left (ex. Arabic, Hebrew).
+    Rtl,
+}
+
+impl Direction {
+    /// Returns the direction to use for `text`, resolving [`Direction::Auto`] to either [`Direction::Ltr`] or [`Direction::Rtl`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use rasterize_text::Direction;
+    ///
+    /// assert_eq!(Direction::Auto.resolve("Hello"), Direction::Ltr);
+    /// assert_eq!(Direction::Auto.resolve("123 שלום"), Direction::Rtl);
+    /// assert_eq!(Direction::Auto.resolve("123"), Direction::Ltr);
+    /// assert_eq!(Direction::Rtl.resolve("Hello"), Direction::Rtl);
+    /// ```
+    pub fn resolve(self, text: &str) -> Direction {
+        match self {
+            Direction::Auto => match unicode_bidi::get_base_direction(text) {
+                unicode_bidi::Direction::Rtl => Direction::Rtl,
+                _ => Direction::Ltr,
+            },
+            direction => direction,
+        }
+    }
+}
+
+impl Display for Direction {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        let lowercase = format!("{:?}", self).to_lowercase();
+        write!(f, "{lowercase}")
+    }
+}
+
+impl FromStr for Direction {
+    type Err = OptionsError;
+
+    /// Returns a [`Direction`] converted from a [`str`].
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use rasterize_text::Direction;
+    /// use std::str::FromStr;
+    ///
+    /// assert_eq!(Direction::from_str("rtl")?, Direction::Rtl);
+    /// # Ok::<(), color_eyre::eyre::Report>(())
+    /// ```
+    fn from_str(direction: &str) -> Result<Self, Self::Err> {
+        let direction = match direction {
+            "auto" => Direction::Auto,
+            "ltr" => Direction::Ltr,
+            "rtl" => Direction::Rtl,
+            _ => Err(OptionsError::UnknownDirectionError(direction.to_string()))?,
+        };
+
+        Ok(direction)
+    }
+}
+
+/// The horizontal alignment of lines of text.
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{Align, Options, rasterize_with_options, EN_FONT, read_font_bytes};
+///
+/// let font  = read_font_bytes(EN_FONT)?;
+/// let text  = "A longer first line\nshort";
+/// let left  = rasterize_with_options(text, &font, &Options::default())?;
+/// let right = rasterize_with_options(text, &font, &Options { align: Align::Right, ..Default::default() })?;
+/// right.save("rasterize_align_right.png")?;
+///
+/// // Lines are aligned within the width of the longest line, so the short line is moved to the right edge
+/// let ink = |image: &image::RgbaImage, x: u32| (image.height() / 2..image.height()).any(|y| image.get_pixel(x, y)[3] > 0);
+/// assert!(ink(&left, 8) && !ink(&left, left.width() - 8));
+/// assert!(!ink(&right, 8) && ink(&right, right.width() - 8));
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Align {
+    /// Align lines to the left edge.
+    #[default]
+    Left,
+    /// Center lines between the edges.
+    Center,
+    /// Align lines to the right edge.
+    Right,
+    /// Stretch lines to fill the width, so they are flush with both edges.
+    ///
+    /// How much lines can be stretched is controlled by the [`Justification`] options.
+    Justify,
+}
+
+impl Display for Align {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        let lowercase = format!("{:?}", self).to_lowercase();
+        write!(f, "{lowercase}")
+    }
+}
+
+impl FromStr for Align {
+    type Err = OptionsError;
+
+    /// Returns an [`Align`] converted from a [`str`].
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use rasterize_text::Align;
+    /// use std::str::FromStr;
+    ///
+    /// assert_eq!(Align::from_str("center")?, Align::Center);
+    /// assert_eq!(Align::from_str("justify")?, Align::Justify);
+    /// # Ok::<(), color_eyre::eyre::Report>(())
+    /// ```
+    fn from_str(align: &str) -> Result<Self, Self::Err> {
+        let align = match align {
+            "left" => Align::Left,
+            "center" => Align::Center,
+            "right" => Align::Right,
+            "justify" => Align::Justify,
+            _ => Err(OptionsError::UnknownAlignError(align.to_string()))?,
+        };
+
+        Ok(align)
+    }
+}
+
+/// Limits on how much space is added to a line when it is justified with [`Align::Justify`].
+///
+/// Extra space is added between words first, up to `max_word_stretch`, and then between letters, up to `max_letter_stretch`.
+/// Lines that would need more space than this are left short, since stretching a line with only a few words
+/// produces wide gaps (and "rivers" of white space running through narrow columns).
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{Align, Justification, LastLine, Options, rasterize_with_options, EN_FONT, read_font_bytes};
+///
+/// let text    = "The quick brown fox jumps over the lazy dog.";
+/// let font    = read_font_bytes(EN_FONT)?;
+/// let options = Options {
+///     wrap_width: Some(500.0),
+///     align: Align::Justify,
+///     justification: Justification { max_word_stretch: 1.0, max_letter_stretch: 0.05, last_line: LastLine::Left },
+///     ..Default::default()
+/// };
+///
+/// let image = rasterize_with_options(&text, &font, &options)?;
+/// image.save("rasterize_justify.png")?;
+///
+/// // The first line is stretched to the full width
+/// assert_eq!(image.width(), 500 );
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Justification {
+    /// Maximum space added to each space between words, as a fraction of the font size (ex. `0.5`).
+    pub max_word_stretch: f32,
+    /// Maximum space added between each pair of letters, as a fraction of the font size (ex. `0.05`).
+    pub max_letter_stretch: f32,
+    /// How the last line of each paragraph is aligned.
+    pub last_line: LastLine,
+}
+
+impl Default for Justification {
+    fn default() -> Self {
+        Justification {
+            max_word_stretch: 0.5,
+            max_letter_stretch: 0.0,
+            last_line: LastLine::default(),
+        }
+    }
+}
+
+/// Numbers for the lines of text, drawn right-aligned in a gutter to the left of the text with [`Options::line_numbers`].
+///
+/// Each line of the text (ex. after a newline) is numbered, but lines that wrap onto the next line are not numbered again,
+/// so the numbers match the lines of the source. The gutter is separated from the text by a rule, and the text
+/// (including its [`Options::wrap_width`]) is moved to the right of the gutter.
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{LineNumbers, Options, rasterize_with_options, EN_FONT, read_font_bytes};
+///
+/// let text     = "fn main() {\n    println!(\"Hello, world! This line is long enough to wrap.\");\n}";
+/// let font     = read_font_bytes(EN_FONT)?;
+/// let options  = Options { size: 20.0, wrap_width: Some(400.0), ..Default::default() };
+/// let numbered = Options { line_numbers: Some(LineNumbers::default()), ..options.clone() };
+///
+/// let image    = rasterize_with_options(&text, &font, &numbered)?;
+/// image.save("rasterize_line_numbers.png")?;
+/// // The text is moved to the right of the gutter, and still wraps at 400 pixels
+/// let plain    = rasterize_with_options(&text, &font, &options)?;
+/// assert!(image.width() > plain.width());
+/// assert!(image.width() - plain.width() < 100);
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct LineNumbers {
+    /// The number of the first line.
+    pub start: usize,
+    /// The color of the numbers.
+    pub color: Color,
+    /// The color of the rule between the numbers and the text. If no color is provided, there is no rule.
+    pub rule_color: Option<Color>,
+}
+
+impl Default for LineNumbers {
+    fn default() -> Self {
+        // A mid grey, that is legible but less prominent than the text on light or dark backgrounds
+        let grey = Color {
+            r: 128,
+            g: 128,
+            b: 128,
+            a: 255,
+        };
+        LineNumbers {
+            start: 1,
+            color: grey,
+            rule_color: Some(grey),
+        }
+    }
+}
+
+/// How the last line of a paragraph is aligned when text is justified.
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{Align, Justification, LastLine, Options, rasterize_with_options, EN_FONT, read_font_bytes};
+///
+/// let text    = "Grand opening of the new public library this Saturday";
+/// let font    = read_font_bytes(EN_FONT)?;
+/// let banner  = |last_line| Options {
+///     wrap_width: Some(600.0),
+///     align: Align::Justify,
+///     justification: Justification { last_line, ..Default::default() },
+///     ..Default::default()
+/// };
+/// let left   = rasterize_with_options(&text, &font, &banner(LastLine::Left))?;
+/// let center = rasterize_with_options(&text, &font, &banner(LastLine::Center))?;
+/// center.save("rasterize_last_line_center.png")?;
+///
+/// // The justified lines are the same, and only the last line is moved to the center
+/// let ink = |image: &image::RgbaImage, x: u32| (image.height() - 20..image.height()).any(|y| image.get_pixel(x, y)[3] > 0);
+/// assert_eq!(left.width(), center.width());
+/// assert!(ink(&left, 10) && !ink(&center, 10));
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum LastLine {
+    /// Align the last line to the left edge, without stretching it.
+    #[default]
+    Left,
+    /// Center the last line, without stretching it (ex. the last line of a justified banner).
+    Center,
+    /// Align the last line to the right edge, without stretching it.
+    Right,
+    /// Stretch the last line like every other line.
+    Justify,
+}
+
+impl Display for LastLine {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        let lowercase = format!("{:?}", self).to_lowercase();
+        write!(f, "{lowercase}")
+    }
+}
+
+impl FromStr for LastLine {
+    type Err = OptionsError;
+
+    /// Returns a [`LastLine`] converted from a [`str`].
+    fn from_str(last_line: &str) -> Result<Self, Self::Err> {
+        let last_line = match last_line {
+            "left" => LastLine::Left,
+            "center" => LastLine::Center,
+            "right" => LastLine::Right,
+            "justify" => LastLine::Justify,
+            _ => Err(OptionsError::UnknownLastLineError(last_line.to_string()))?,
+        };
+
+        Ok(last_line)
+    }
+}
+
+/// Column stops that tab characters advance to, measured from the start of each line.
+///
+/// A tab moves the text after it to the first stop past the end of the text before it. After the last of the `stops`,
+/// stops repeat every `interval`. Stops are measured in [`TabUnit`]s, which are either pixels or character cells
+/// (the width of a space in the main font), so that columns line up like in a terminal when using a monospace font.
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{Options, TabStops, TabUnit, rasterize_with_options, EN_FONT, read_font_bytes};
+/// use std::str::FromStr;
+///
+/// let text = "PID\tTTY\tTIME\tCMD\n4121\tpts/0\t00:00:01\tbash\n90211\tpts/0\t00:00:00\tps";
+/// let font = read_font_bytes(EN_FONT)?;
+///
+/// // A stop every 8 characters, like a terminal
+/// let terminal = Options { size: 20.0, tab_stops: Some(TabStops::default()), ..Default::default() };
+/// let image    = rasterize_with_options(&text, &font, &terminal)?;
+/// image.save("rasterize_tabs.png")?;
+///
+/// // Columns at 80, 160 and 280 pixels, and every 120 pixels after that
+/// let tab_stops = TabStops::from_str("80,160,280px")?;
+/// assert_eq!(tab_stops, TabStops { stops: vec![80.0, 160.0, 280.0], interval: 120.0, unit: TabUnit::Pixels });
+/// let columns = Options { size: 20.0, tab_stops: Some(tab_stops), ..Default::default() };
+/// let image   = rasterize_with_options(&text, &font, &columns)?;
+/// assert!(image.width() > 280);
+///
+/// // Text after a tab starts at the same stop, however wide the text before it is
+/// let short = rasterize_with_options(&"1\tend", &font, &columns)?;
+/// let long  = rasterize_with_options(&"10000\tend", &font, &columns)?;
+/// assert_eq!(short.width(), long.width());
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct TabStops {
+    /// Positions of the first stops from the start of the line, in increasing order.
+    pub stops: Vec<f32>,
+    /// Distance between the stops after the last of the `stops`.
+    pub interval: f32,
+    /// The unit of the stops and interval.
+    pub unit: TabUnit,
+}
+
+impl Default for TabStops {
+    fn default() -> Self {
+        TabStops {
+            stops: Vec::new(),
+            interval: 8.0,
+            unit: TabUnit::default(),
+        }
+    }
+}
+
+impl TabStops {
+    /// Returns the position in pixels of the first stop after `x`, where a character cell is `cell` pixels wide.
+    ///
+    /// If there are no stops after `x` (ex. the interval is zero), `x` is returned.
+    pub(crate) fn next(&self, x: f32, cell: f32) -> f32 {
+        let scale = match self.unit {
+            TabUnit::Pixels => 1.0,
+            TabUnit::Cells => cell,
+        };
+        if let Some(stop) = self
+            .stops
+            .iter()
+            .map(|stop| stop * scale)
+            .find(|stop| *stop > x)
+        {
+            return stop;
+        }
+        let last = self.stops.last().map_or(0.0, |stop| stop * scale);
+        let interval = self.interval * scale;
+        match interval > 0.0 {
+            true => last + ((x - last) / interval).floor() * interval + interval,
+            false => x,
+        }
+    }
+}
+
+impl FromStr for TabStops {
+    type Err = OptionsError;
+
+    /// Returns [`TabStops`] converted from a [`str`], as increasing numbers separated by commas.
+    ///
+    /// A single number is the interval between stops. With several numbers, the interval after the last stop
+    /// is the distance between the last two. Stops are in character cells, or in pixels if they end with `px`.
+    ///
+    /// ## Examples
+    ///
+    /// ```rust
+    /// use rasterize_text::{TabStops, TabUnit};
+    /// use std::str::FromStr;
+    ///
+    /// assert_eq!(TabStops::from_str("4")?, TabStops { stops: vec![], interval: 4.0, unit: TabUnit::Cells });
+    /// assert_eq!(TabStops::from_str("10,24")?, TabStops { stops: vec![10.0, 24.0], interval: 14.0, unit: TabUnit::Cells });
+    /// assert!(TabStops::from_str("24,10").is_err());
+    /// # Ok::<(), color_eyre::eyre::Report>(())
+    /// ```
+    fn from_str(tab_stops: &str) -> Result<Self, Self::Err> {
+        let error = || OptionsError::InvalidTabStopsError(tab_stops.to_string());
+
+        let (stops, unit) = match tab_stops.strip_suffix("px") {
+            Some(stops) => (stops, TabUnit::Pixels),
+            None => (tab_stops, TabUnit::Cells),
+        };
+        let stops = stops
+            .split(',')
+            .map(|stop| stop.trim().parse::<f32>().map_err(|_| error()))
+            .collect::<Result<Vec<f32>, _>>()?;
+        let increasing = stops.windows(2).all(|pair| pair[0] < pair[1]);
+        if !increasing || stops.iter().any(|stop| !stop.is_finite() || *stop <= 0.0) {
+            return Err(error());
+        }
+
+        match stops.as_slice() {
+            [interval] => Ok(TabStops {
+                stops: Vec::new(),
+                interval: *interval,
+                unit,
+            }),
+            [.., previous, last] => Ok(TabStops {
+                interval: last - previous,
+                stops,
+                unit,
+            }),
+            [] => Err(error()),
+        }
+    }
+}
+
+/// The unit of [`TabStops`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum TabUnit {
+    /// Character cells, the width of a space in the main font at the font size.
+    #[default]
+    Cells,
+    /// Pixels.
+    Pixels,
+}
+
+/// How glyphs are snapped to whole pixels.
+///
+/// Glyphs are normally placed at fractional pixel positions, which spaces them accurately, but changes how
+/// they are anti-aliased depending on where they fall within a pixel. Text that moves by fractional offsets
+/// (ex. scrolling in an animation) then appears to shimmer. Snapping draws each glyph the same way wherever it is placed.
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{outline_text, rasterize_with_options, Options, PixelSnap, EN_FONT, read_font_bytes};
+///
+/// let font      = read_font_bytes(EN_FONT)?;
+/// let text      = "Hello, World!";
+/// let positions = |pixel_snap| {
+///     let options = Options { size: 17.0, pixel_snap, ..Default::default() };
+///     outline_text(&text, &font, &options).map(|outlines| {
+///         outlines.iter().map(|outline| (outline.position.x, outline.position.y)).collect::<Vec<_>>()
+///     })
+/// };
+/// let whole = |x: f64| x.fract() == 0.0;
+///
+/// // Glyphs are placed at fractional positions
+/// let exact = positions(PixelSnap::Off)?;
+/// assert!(exact.iter().any(|(x, _)| !whole(*x)));
+///
+/// // Each position is rounded to the nearest whole pixel
+/// let rounded = positions(PixelSnap::Positions)?;
+/// for ((x, y), (exact_x, exact_y)) in rounded.iter().zip(&exact) {
+///     assert_eq!((*x, *y), (exact_x.round(), exact_y.round()));
+/// }
+///
+/// // Whole pixel advances also place every glyph at a whole pixel, but the rounding adds up along the line
+/// let advances = positions(PixelSnap::Advances)?;
+/// assert!(advances.iter().all(|(x, y)| whole(*x) && whole(*y)));
+/// assert_ne!(advances, rounded);
+///
+/// // Quarter pixel positions are within an eighth of a pixel of the fractional positions
+/// let quarters = positions(PixelSnap::Quarters)?;
+/// for ((x, y), (exact_x, exact_y)) in quarters.iter().zip(&exact) {
+///     assert!(whole(x * 4.0) && (x - exact_x).abs() <= 0.125);
+///     assert_eq!(*y, exact_y.round());
+/// }
+///
+/// // Glyphs at quarter pixels are drawn from a cache, which draws a glyph at a whole pixel exactly like other glyphs
+/// let image = |text: &str, pixel_snap| {
+///     rasterize_with_options(&text, &font, &Options { size: 17.0, pixel_snap, ..Default::default() })
+/// };
+/// for character in ["H", "e", "W", "!"] {
+///     assert_eq!(image(character, PixelSnap::Quarters)?, image(character, PixelSnap::Positions)?);
+/// }
+/// assert_ne!(image(text, PixelSnap::Quarters)?, image(text, PixelSnap::Positions)?);
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum PixelSnap {
+    /// Place glyphs at fractional pixel positions.
+    #[default]
+    Off,
+    /// Round the position of each glyph to a whole pixel, after it is laid out.
+    Positions,
+    /// Round the advance of each glyph to whole pixels during layout, as well as its position,
+    /// so that the space between glyphs is the same wherever the text is placed.
+    Advances,
+    /// Round the horizontal position of each glyph to a quarter of a pixel, and its vertical position to a whole pixel.
+    ///
+    /// This spaces glyphs more accurately than [`PixelSnap::Positions`] at small sizes. Each glyph is rasterized
+    /// at most once for each of the four offsets within a pixel, and reused wherever else it is drawn.
+    Quarters,
+}
+
+impl Display for PixelSnap {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        let lowercase = format!("{:?}", self).to_lowercase();
+        write!(f, "{lowercase}")
+    }
+}
+
+impl FromStr for PixelSnap {
+    type Err = OptionsError;
+
+    /// Returns a [`PixelSnap`] converted from a [`str`].
+    fn from_str(pixel_snap: &str) -> Result<Self, Self::Err> {
+        let pixel_snap = match pixel_snap {
+            "off" => PixelSnap::Off,
+            "positions" => PixelSnap::Positions,
+            "advances" => PixelSnap::Advances,
+            "quarters" => PixelSnap::Quarters,
+            _ => Err(OptionsError::UnknownPixelSnapError(pixel_snap.to_string()))?,
+        };
+
+        Ok(pixel_snap)
+    }
+}
+
+/// The color space that the coverage of glyphs is blended in, with [`Options::blending`].
+///
+/// Blending in sRGB makes thin strokes look thinner and darker than they are when they are light on a dark background
+/// or colored on a colored background, since half of the coverage of a pixel is less than half of its brightness.
+/// Blending in linear light keeps the weight of strokes the same in any colors.
+///
+/// [`IncrementalText`](crate::IncrementalText) always blends in sRGB, and [`rasterize_changes`](crate::rasterize_changes)
+/// redraws the whole image with linear blending. Images that are composited from several parts (ex. cards, bubbles
+/// and chat messages) blend their text in linear light, and composite the parts onto their backgrounds in sRGB.
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{Background, Blending, Color, Options, rasterize_with_options, EN_FONT, read_font_bytes};
+///
+/// let text    = "Hairline";
+/// let font    = read_font_bytes(EN_FONT)?;
+/// let white   = Color { r: 255, g: 255, b: 255, a: 255 };
+/// let black   = Color { r: 0, g: 0, b: 0, a: 255 };
+/// let options = Options { size: 14.0, color: white, background: Some(Background::Solid(black)), ..Default::default() };
+///
+/// let srgb   = rasterize_with_options(&text, &font, &options)?;
+/// let linear = rasterize_with_options(&text, &font, &Options { blending: Blending::Linear, ..options })?;
+/// linear.save("rasterize_linear.png")?;
+///
+/// // Light text on a dark background is brighter at the edges of its strokes, rather than thinner
+/// let brightness = |image: &image::RgbaImage| image.pixels().map(|pixel| u64::from(pixel[0])).sum::<u64>();
+/// assert_eq!(linear.dimensions(), srgb.dimensions());
+/// assert!(brightness(&linear) > brightness(&srgb));
+/// // Fully covered pixels and the background are the same
+/// assert_eq!(linear.get_pixel(0, 0), srgb.get_pixel(0, 0));
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Blending {
+    /// Blend the sRGB values of colors, like most text renderers and browsers.
+    #[default]
+    Srgb,
+    /// Blend colors in linear light with 16 bits per channel, and convert the image to sRGB after it is drawn.
+    ///
+    /// The filters of [`Options::effects`] are also applied in linear light, before the image is converted.
+    Linear,
+}
+
+impl Display for Blending {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        let lowercase = format!("{:?}", self).to_lowercase();
+        write!(f, "{lowercase}")
+    }
+}
+
+impl FromStr for Blending {
+    type Err = OptionsError;
+
+    /// Returns a [`Blending`] converted from a [`str`].
+    fn from_str(blending: &str) -> Result<Self, Self::Err> {
+        let blending = match blending {
+            "srgb" => Blending::Srgb,
+            "linear" => Blending::Linear,
+            _ => Err(OptionsError::UnknownBlendingError(blending.to_string()))?,
+        };
+
+        Ok(blending)
+    }
+}
+
+/// How whitespace is trimmed and collapsed before text is laid out, with [`Options::whitespace`], like the
+/// `white-space` property of CSS.
+///
+/// Whitespace is any Unicode whitespace except the no-break spaces (ex. `U+00A0`), which are kept to hold words together.
+/// Runs of whitespace that are collapsed across [`Span`](crate::Span)s are kept in the span where the run starts.
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{Options, Whitespace, rasterize_with_options, EN_FONT, read_font_bytes};
+///
+/// let font    = read_font_bytes(EN_FONT)?;
+/// let text    = "  Hello,\t\n   World!  ";
+/// let options = |whitespace| Options { whitespace, ..Default::default() };
+///
+/// // Collapsing whitespace joins the lines with a single space
+/// let collapsed = rasterize_with_options(&text, &font, &options(Whitespace::Collapse))?;
+/// assert_eq!(collapsed, rasterize_with_options(&"Hello, World!", &font, &Options::default())?);
+///
+/// // Collapsing spaces keeps the line breaks
+/// let lines = rasterize_with_options(&text, &font, &options(Whitespace::CollapseSpaces))?;
+/// assert_eq!(lines, rasterize_with_options(&"Hello,\nWorld!", &font, &Options::default())?);
+///
+/// // Trimming only removes the whitespace around the text
+/// let trimmed = rasterize_with_options(&text, &font, &options(Whitespace::Trim))?;
+/// assert_eq!(trimmed, rasterize_with_options(&"Hello,\t\n   World!", &font, &Options::default())?);
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Whitespace {
+    /// Keep all whitespace.
+    #[default]
+    Preserve,
+    /// Remove the whitespace at the start and end of the text, and keep the whitespace within it.
+    Trim,
+    /// Trim the text, and replace each run of whitespace within it (including line breaks) with a single space.
+    Collapse,
+    /// Trim the text, and replace each run of whitespace within it with a single space, or with its line breaks
+    /// if it has any (ex. blank lines are kept, but the spaces around them are removed).
+    CollapseSpaces,
+}
+
+impl Display for Whitespace {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        let lowercase = format!("{:?}", self).to_lowercase();
+        write!(f, "{lowercase}")
+    }
+}
+
+impl FromStr for Whitespace {
+    type Err = OptionsError;
+
+    /// Returns a [`Whitespace`] converted from a [`str`].
+    fn from_str(whitespace: &str) -> Result<Self, Self::Err> {
+        let whitespace = match whitespace {
+            "preserve" => Whitespace::Preserve,
+            "trim" => Whitespace::Trim,
+            "collapse" => Whitespace::Collapse,
+            "collapsespaces" => Whitespace::CollapseSpaces,
+            _ => Err(OptionsError::UnknownWhitespaceError(whitespace.to_string()))?,
+        };
+
+        Ok(whitespace)
+    }
+}
+
+/// How the case of text is changed before it is laid out, with [`Options::text_transform`].
+///
+/// Case is mapped with the full Unicode case mappings (ex. `ß` is uppercased to `SS`), and the rules of
+/// [`Options::lang`] for languages that case letters differently: in Turkish and Azerbaijani `i` is uppercased to `İ`
+/// and `I` is lowercased to `ı`, and in Dutch the `ij` at the start of a word is titlecased to `IJ`.
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{LanguageTag, Options, TextTransform, rasterize_with_options, EN_FONT, read_font_bytes};
+/// use std::str::FromStr;
+///
+/// let font    = read_font_bytes(EN_FONT)?;
+/// let options = |text_transform, lang| Options { text_transform, lang, ..Default::default() };
+///
+/// // The same as rasterizing the transformed text
+/// let upper = options(TextTransform::Uppercase, None);
+/// assert_eq!(rasterize_with_options(&"straße", &font, &upper)?, rasterize_with_options(&"STRASSE", &font, &Options::default())?);
+/// let title = options(TextTransform::Titlecase, None);
+/// assert_eq!(rasterize_with_options(&"the end", &font, &title)?, rasterize_with_options(&"The End", &font, &Options::default())?);
+///
+/// // Turkish has a dotted and a dotless i
+/// let turkish = options(TextTransform::Uppercase, Some(LanguageTag::from_str("tr")?));
+/// assert_eq!(rasterize_with_options(&"istanbul", &font, &turkish)?, rasterize_with_options(&"İSTANBUL", &font, &Options::default())?);
+///
+/// // DejaVu Sans has no small capitals, so they are made from smaller capitals
+/// let small_caps = options(TextTransform::SmallCaps, None);
+/// let image = rasterize_with_options(&"Small Caps", &font, &small_caps)?;
+/// image.save("rasterize_small_caps.png")?;
+/// assert!(image.width() < rasterize_with_options(&"SMALL CAPS", &font, &Options::default())?.width());
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum TextTransform {
+    /// Keep the case of the text.
+    #[default]
+    None,
+    /// Change every letter to uppercase.
+    Uppercase,
+    /// Change every letter to lowercase.
+    Lowercase,
+    /// Change the first letter of each word to titlecase (usually uppercase), and keep the case of the other letters.
+    Titlecase,
+    /// Draw lowercase letters as small capitals, with the `smcp` feature of the main font when it has one.
+    ///
+    /// Otherwise, lowercase letters are uppercased and drawn at 70% of the font size.
+    SmallCaps,
+}
+
+impl Display for TextTransform {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        let lowercase = format!("{:?}", self).to_lowercase();
+        write!(f, "{lowercase}")
+    }
+}
+
+impl FromStr for TextTransform {
+    type Err = OptionsError;
+
+    /// Returns a [`TextTransform`] converted from a [`str`].
+    fn from_str(text_transform: &str) -> Result<Self, Self::Err> {
+        let text_transform = match text_transform {
+            "none" => TextTransform::None,
+            "uppercase" => TextTransform::Uppercase,
+            "lowercase" => TextTransform::Lowercase,
+            "titlecase" => TextTransform::Titlecase,
+            "smallcaps" => TextTransform::SmallCaps,
+            _ => Err(OptionsError::UnknownTextTransformError(
+                text_transform.to_string(),
+            ))?,
+        };
+
+        Ok(text_transform)
+    }
+}
+
+/// How text is hidden before it is laid out, with [`Options::mask`], so that images of secrets can be shared.
+///
+/// Hidden characters are replaced with a mask glyph before the text is laid out, so the pixels of the image
+/// only depend on the number of characters that are hidden, and not on the characters themselves.
+/// Line breaks are never hidden.
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{Mask, Options, rasterize_with_options, EN_FONT, read_font_bytes};
+///
+/// let font    = read_font_bytes(EN_FONT)?;
+/// let options = |mask| Options { mask: Some(mask), ..Default::default() };
+///
+/// // Masking replaces every character with the mask glyph
+/// let masked = rasterize_with_options(&"hunter2", &font, &options(Mask::All('•')))?;
+/// assert_eq!(masked, rasterize_with_options(&"•••••••", &font, &Options::default())?);
+///
+/// // Blurring hides only the byte ranges of the secret, and the image is the same for any secret of the same length
+/// let blurred = options(Mask::Blur(vec![10..17]));
+/// let image   = rasterize_with_options(&"password: hunter2", &font, &blurred)?;
+/// image.save("rasterize_mask.png")?;
+/// assert_eq!(image, rasterize_with_options(&"password: letmein", &font, &blurred)?);
+/// assert_ne!(image, rasterize_with_options(&"password: •••••••", &font, &Options::default())?);
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Mask {
+    /// Replace every character of the text with a glyph (ex. `•`).
+    All(char),
+    /// Replace the characters within byte ranges of the text with `•`, and blur the pixels of their glyphs.
+    ///
+    /// The ranges are relative to the text as given, before it is normalized, with the texts of
+    /// [`Span`](crate::Span)s joined together. A character is hidden if any of its bytes are within a range.
+    /// Text that the ranges don't refer to (ex. ruby, and the pieces of text between math with the `latex` feature) is not hidden.
+    Blur(Vec<Range<usize>>),
+}
+
+/// A procedural background that text is drawn over, with [`Options::background`].
+///
+/// The background fills the image, and is drawn before the [`Options::effects`] are applied.
+/// Random backgrounds are generated from a seed, so the same seed always draws the same background.
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{Background, Color, Options, rasterize_with_options, EN_FONT, read_font_bytes};
+///
+/// let font    = read_font_bytes(EN_FONT)?;
+/// let text    = "Less is more.";
+/// let cream   = Color { r: 244, g: 236, b: 216, a: 255 };
+/// let options = Options { background: Some(Background::Paper { color: cream, seed: 7 }), ..Default::default() };
+///
+/// let image = rasterize_with_options(&text, &font, &options)?;
+/// image.save("rasterize_paper.png")?;
+/// assert!(image.pixels().all(|pixel| pixel[3] == 255));
+/// assert_eq!(image, rasterize_with_options(&text, &font, &options)?);
+///
+/// // A gradient from left to right starts with the first color
+/// let gradient = Background::Gradient { start: cream, end: Color { r: 0, g: 0, b: 255, a: 255 }, angle: 0.0 };
+/// let image    = rasterize_with_options(&text, &font, &Options { background: Some(gradient), ..Default::default() })?;
+/// assert!(image.get_pixel(0, 0)[2] < image.get_pixel(image.width() - 1, 0)[2]);
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Background {
+    /// A single color.
+    Solid(Color),
+    /// A color with random grain, where `amount` is the most that each pixel is lightened or darkened,
+    /// as a fraction of the full range of each channel (ex. `0.1`).
+    Noise {
+        color: Color,
+        amount: f32,
+        seed: u64,
+    },
+    /// A color with a subtle texture of paper, mottled and streaked with fibres.
+    Paper { color: Color, seed: u64 },
+    /// A linear gradient from the `start` color to the `end` color across the image,
+    /// at an `angle` in degrees clockwise from left-to-right (ex. `90.0` is top-to-bottom).
+    Gradient {
+        start: Color,
+        end: Color,
+        angle: f32,
+    },
+}
+
+/// A border that is drawn around the outside of the image, with [`Options::border`].
+///
+/// The image is made larger by the width of the border on each side, so the border never covers the text.
+/// Borders are drawn after the [`Options::background`] and [`Options::effects`], and follow the [`Options::corner_radius`].
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{Background, Border, Color, Options, rasterize_with_options, EN_FONT, read_font_bytes};
+///
+/// let font    = read_font_bytes(EN_FONT)?;
+/// let text    = "New";
+/// let white   = Color { r: 255, g: 255, b: 255, a: 255 };
+/// let options = Options { background: Some(Background::Solid(white)), ..Default::default() };
+/// let plain   = rasterize_with_options(&text, &font, &options)?;
+///
+/// let border  = Border { width: 2.0, color: Color { r: 0, g: 128, b: 0, a: 255 } };
+/// let options = Options { corner_radius: 8.0, border: Some(border), ..options };
+/// let label   = rasterize_with_options(&text, &font, &options)?;
+/// label.save("rasterize_label.png")?;
+///
+/// assert_eq!(label.dimensions(), (plain.width() + 4, plain.height() + 4));
+/// // The corners are cut out, and the edges are drawn in the color of the border
+/// assert_eq!(label.get_pixel(0, 0)[3], 0);
+/// assert_eq!(label.get_pixel(label.width() / 2, 0), &image::Rgba([0, 128, 0, 255]));
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Border {
+    /// Width of the border in pixels.
+    pub width: f32,
+    pub color: Color,
+}
+
+/// Byte ranges of the text that are drawn over a color, like a selection in a text editor, with [`Options::selection`].
+///
+/// The rectangle behind each selected glyph spans the height of its line, like [`Style::background`](crate::Style::background),
+/// so a selection that wraps across lines is highlighted on each of them. The byte ranges are relative to the texts of
+/// the spans joined together, and a character is selected if any of its bytes are.
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{Options, Selection, rasterize_with_options, EN_FONT, read_font_bytes};
+///
+/// let font      = read_font_bytes(EN_FONT)?;
+/// let text      = "Select part of this text, across lines.";
+/// let options   = Options { wrap_width: Some(200.0), ..Default::default() };
+/// let selection = Selection { ranges: vec![7..30], ..Default::default() };
+///
+/// let plain     = rasterize_with_options(&text, &font, &options)?;
+/// let selected  = rasterize_with_options(&text, &font, &Options { selection: Some(selection.clone()), ..options })?;
+/// selected.save("rasterize_selection.png")?;
+///
+/// // The selection is drawn behind the glyphs, without changing the lines
+/// assert_eq!(selected.height(), plain.height());
+/// let color = selection.color;
+/// let highlighted = selected.pixels().filter(|pixel| pixel.0 == [color.r, color.g, color.b, color.a]).count();
+/// assert!(highlighted > 1000);
+/// assert_eq!(selected.get_pixel(0, 0)[3], 0);
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Selection {
+    /// Byte ranges of the text that are selected.
+    pub ranges: Vec<Range<usize>>,
+    /// The color behind the selected text.
+    pub color: Color,
+}
+
+impl Default for Selection {
+    fn default() -> Self {
+        // The light blue of selections in most text editors
+        let color = Color {
+            r: 179,
+            g: 215,
+            b: 255,
+            a: 255,
+        };
+        Selection {
+            ranges: Vec::new(),
+            color,
+        }
+    }
+}
+
+/// A box ("tofu") that is drawn for each character that no font supports, instead of the missing glyph of the main
+/// font, with [`Options::tofu`].
+///
+/// The box spans the advance of the missing glyph, from the baseline up to about the height of capitals, in the color
+/// of the text. Its codepoint is drawn inside of it in two rows of hexadecimal digits, like the boxes of Chrome and
+/// Firefox, when the box is large enough for the digits to be read.
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{Options, Tofu, TofuStyle, rasterize_with_options, EN_FONT, read_font_bytes};
+///
+/// let font   = read_font_bytes(EN_FONT)?;
+/// let text   = "Crab: 🦀";
+/// let notdef = rasterize_with_options(&text, &font, &Options::default())?;
+/// let tofu   = Options { tofu: Some(Tofu::default()), ..Default::default() };
+/// let image  = rasterize_with_options(&text, &font, &tofu)?;
+/// image.save("rasterize_tofu.png")?;
+/// assert_eq!(image.width(), notdef.width());
+/// assert_ne!(image, notdef);
+///
+/// // A filled box without the codepoint covers more pixels than the outline
+/// let filled = Tofu { style: TofuStyle::Filled, codepoint: false };
+/// let filled = rasterize_with_options(&text, &font, &Options { tofu: Some(filled), ..Default::default() })?;
+/// let inked  = |image: &image::RgbaImage| image.pixels().filter(|pixel| pixel[3] > 0).count();
+/// assert!(inked(&filled) > inked(&image));
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Tofu {
+    /// Whether the outline of the box is drawn, or the box is filled.
+    pub style: TofuStyle,
+    /// Whether the codepoint of the character is drawn inside of the box.
+    pub codepoint: bool,
+}
+
+impl Default for Tofu {
+    fn default() -> Self {
+        Tofu {
+            style: TofuStyle::default(),
+            codepoint: true,
+        }
+    }
+}
+
+/// How the box of a [`Tofu`] is drawn.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum TofuStyle {
+    /// Draw the outline of the box.
+    #[default]
+    Outline,
+    /// Fill the box with the color of the text at a quarter of its alpha, behind the codepoint.
+    Filled,
+}
+
+impl Display for TofuStyle {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        let lowercase = format!("{:?}", self).to_lowercase();
+        write!(f, "{lowercase}")
+    }
+}
+
+impl FromStr for TofuStyle {
+    type Err = OptionsError;
+
+    /// Returns a [`TofuStyle`] converted from a [`str`].
+    fn from_str(tofu_style: &str) -> Result<Self, Self::Err> {
+        let tofu_style = match tofu_style {
+            "outline" => TofuStyle::Outline,
+            "filled" => TofuStyle::Filled,
+            _ => Err(OptionsError::UnknownTofuStyleError(tofu_style.to_string()))?,
+        };
+
+        Ok(tofu_style)
+    }
+}
+
+/// The colors of color glyphs, which are drawn as layers of other glyphs in the colors of a palette, with
+/// [`Options::color_palette`].
+///
+/// Color glyphs are read from the COLR (version 0) and CPAL tables of a font (ex. a branded icon font). Layers in the
+/// foreground entry are drawn in the color of the text, and the other layers are faded with the alpha of the text.
+/// Layers are composited over each other from the bottom layer up, so translucent entries show the layers below them.
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{rasterize_with_options, Color, ColorPalette, Options, EN_FONT, read_font_bytes};
+/// # // Returns a copy of a font with more tables, whose tags sort before the tags of its tables
+/// # fn with_tables(font: &[u8], tables: &[(&[u8; 4], Vec<u8>)]) -> Vec<u8> {
+/// #     let count       = u16::from_be_bytes([font[4], font[5]]) as usize;
+/// #     let shift       = 16 * tables.len() as u32;
+/// #     let mut data    = font[12 + 16 * count..].to_vec();
+/// #     let mut records = Vec::new();
+/// #     for (tag, table) in tables {
+/// #         let offset = 12 + 16 * count as u32 + shift + data.len() as u32;
+/// #         records.extend([&tag[..], &[0; 4], &offset.to_be_bytes(), &(table.len() as u32).to_be_bytes()].concat());
+/// #         data.extend(table);
+/// #         data.resize(data.len().next_multiple_of(4), 0);
+/// #     }
+/// #     for record in font[12..12 + 16 * count].chunks(16) {
+/// #         let offset = u32::from_be_bytes(record[8..12].try_into().unwrap()) + shift;
+/// #         records.extend([&record[..8], &offset.to_be_bytes(), &record[12..]].concat());
+/// #     }
+/// #     let mut header = font[..12].to_vec();
+/// #     header[4..6].copy_from_slice(&((count + tables.len()) as u16).to_be_bytes());
+/// #     [header, records, data].concat()
+/// # }
+///
+/// // A copy of the font where "A" is a color glyph of two layers: an "O" in the first entry of the palette, under an
+/// // "A" in the color of the text. The entry is red in the first palette, and blue in the second palette.
+/// let font   = read_font_bytes(EN_FONT)?;
+/// let (a, o) = (font.glyph('A').id().0, font.glyph('O').id().0);
+/// let colr   = [0, 1, 0, 14, 0, 20, 2, a, 0, 2, o, 0, a, 0xFFFF].map(u16::to_be_bytes).concat();
+/// let cpal   = [[0, 1, 2, 2, 0, 16, 0, 1].map(u16::to_be_bytes).concat(), vec![0, 0, 255, 255, 255, 0, 0, 255]].concat();
+/// let color  = read_font_bytes(&with_tables(EN_FONT, &[(b"COLR", colr), (b"CPAL", cpal)]))?;
+/// let has    = |image: &image::RgbaImage, pixel: [u8; 4]| image.pixels().any(|p| p.0 == pixel);
+///
+/// let red = rasterize_with_options(&"A", &color, &Options::default())?;
+/// red.save("color_palette.png")?;
+/// assert!(has(&red, [255, 0, 0, 255]) && has(&red, [0, 0, 0, 255]));
+///
+/// // The second palette of the font, with the first entry replaced by green, and without the layers
+/// let palette = |color_palette| Options { color_palette, ..Default::default() };
+/// let blue    = rasterize_with_options(&"A", &color, &palette(ColorPalette { index: 1, ..Default::default() }))?;
+/// assert!(has(&blue, [0, 0, 255, 255]) && !has(&blue, [255, 0, 0, 255]));
+/// let green   = ColorPalette { overrides: vec![(0, Color { r: 0, g: 255, b: 0, a: 255 })], ..Default::default() };
+/// assert!(has(&rasterize_with_options(&"A", &color, &palette(green))?, [0, 255, 0, 255]));
+/// let plain   = rasterize_with_options(&"A", &color, &palette(ColorPalette { monochrome: true, ..Default::default() }))?;
+/// assert_eq!(plain, rasterize_with_options(&"A", &font, &Options::default())?);
+///
+/// // Layers are composited from the bottom layer up, so a translucent layer shows the layers below it:
+/// // here a half transparent red "O" over a blue "A"
+/// let colr  = [0, 1, 0, 14, 0, 20, 2, a, 0, 2, a, 0xFFFF, o, 0].map(u16::to_be_bytes).concat();
+/// let cpal  = [[0, 1, 1, 1, 0, 14, 0].map(u16::to_be_bytes).concat(), vec![0, 0, 255, 128]].concat();
+/// let above = read_font_bytes(&with_tables(EN_FONT, &[(b"COLR", colr), (b"CPAL", cpal)]))?;
+/// let blue  = Options { color: Color { r: 0, g: 0, b: 255, a: 255 }, ..Default::default() };
+/// let both  = rasterize_with_options(&"A", &above, &blue)?;
+/// assert!(has(&both, [255, 0, 127, 255]) && has(&both, [0, 0, 255, 255]) && has(&both, [255, 0, 0, 128]));
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+///
+/// With the `svg` feature, glyphs that are SVG documents (in the SVG table of a font) are drawn by resvg, in font units
+/// from the origin of the glyph. The current color of a document (`currentColor`) is the color of the text. Documents
+/// are clipped to the image, which is measured from the outlines of the glyphs, so fonts are expected to have
+/// outlines that cover their documents (as the outlines of other color fonts are drawn without the feature).
+///
+/// ```rust
+/// use rasterize_text::{rasterize_with_options, ColorPalette, Options, EN_FONT, read_font_bytes};
+/// # // Returns a copy of a font with more tables
+/// # fn with_tables(font: &[u8], tables: &[(&[u8; 4], Vec<u8>)]) -> Vec<u8> {
+/// #     let count   = u16::from_be_bytes([font[4], font[5]]) as usize;
+/// #     let mut all = Vec::new();
+/// #     for record in font[12..12 + 16 * count].chunks(16) {
+/// #         let at = |i: usize| u32::from_be_bytes(record[i..i + 4].try_into().unwrap()) as usize;
+/// #         all.push((&record[..4], &font[at(8)..at(8) + at(12)]));
+/// #     }
+/// #     all.extend(tables.iter().map(|(tag, table)| (&tag[..], &table[..])));
+/// #     all.sort();
+/// #     let mut header = font[..12].to_vec();
+/// #     header[4..6].copy_from_slice(&(all.len() as u16).to_be_bytes());
+/// #     let (mut records, mut data) = (Vec::new(), Vec::new());
+/// #     for (tag, table) in &all {
+/// #         let offset = (12 + 16 * all.len() + data.len()) as u32;
+/// #         records.extend([tag, &[0; 4][..], &offset.to_be_bytes(), &(table.len() as u32).to_be_bytes()].concat());
+/// #         data.extend(*table);
+/// #         data.resize(data.len().next_multiple_of(4), 0);
+/// #     }
+/// #     [header, records, data].concat()
+/// # }
+///
+/// // A copy of the font where "A" is an SVG document of a red square, inside the outline of the "A"
+/// let font     = read_font_bytes(EN_FONT)?;
+/// let a        = font.glyph('A').id().0;
+/// let document = format!(r#"<svg xmlns="http://www.w3.org/2000/svg"><rect id="glyph{a}" x="300" y="-1000" width="800" height="800" fill="red"/></svg>"#);
+/// let svg      = [[0, 0, 10, 0, 0, 1, a, a, 0, 14, 0, document.len() as u16].map(u16::to_be_bytes).concat(), document.into_bytes()].concat();
+/// let svg_font = read_font_bytes(&with_tables(EN_FONT, &[(b"SVG ", svg)]))?;
+/// let has      = |image: &image::RgbaImage, pixel: [u8; 4]| image.pixels().any(|p| p.0 == pixel);
+///
+/// let image = rasterize_with_options(&"A", &svg_font, &Options::default())?;
+/// image.save("svg_glyph.png")?;
+/// assert_eq!(has(&image, [255, 0, 0, 255]), cfg!(feature = "svg"));
+/// assert_eq!(has(&image, [0, 0, 0, 255]), !cfg!(feature = "svg"));
+///
+/// // Monochrome glyphs are drawn from their outlines
+/// let monochrome = Options { color_palette: ColorPalette { monochrome: true, ..Default::default() }, ..Default::default() };
+/// assert_eq!(rasterize_with_options(&"A", &svg_font, &monochrome)?, rasterize_with_options(&"A", &font, &Options::default())?);
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ColorPalette {
+    /// The index of the palette of the font that color glyphs are drawn in (ex. a palette for dark backgrounds).
+    /// If the font doesn't have the palette, the first palette is used.
+    pub index: u16,
+    /// Colors that replace entries of the palette, by the index of the entry (ex. to match a brand color).
+    /// If an entry is replaced more than once, the last color is used.
+    pub overrides: Vec<(u16, Color)>,
+    /// Whether color glyphs (and glyphs that are SVG documents) are drawn in the color of the text like other glyphs,
+    /// from their own outlines.
+    pub monochrome: bool,
+}
+
+/// A post-processing [`Filter`] that is applied to the image after the text is drawn, with [`Options::effects`].
+///
+/// Effects are applied in order, after the backgrounds and underlines are drawn.
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{Effect, Filter, Options, rasterize_with_options, EN_FONT, read_font_bytes};
+///
+/// let font    = read_font_bytes(EN_FONT)?;
+/// let text    = "Hello, World!";
+/// let plain   = rasterize_with_options(&text, &font, &Options::default())?;
+///
+/// // Pixelate the glyphs of "World", and leave the rest of the text as is
+/// let effects = vec![Effect { filter: Filter::Pixelate(8), range: Some(7..12) }];
+/// let image   = rasterize_with_options(&text, &font, &Options { effects, ..Default::default() })?;
+/// image.save("rasterize_effects.png")?;
+/// assert_eq!(image.dimensions(), plain.dimensions());
+/// assert_eq!(image.get_pixel(20, 20), plain.get_pixel(20, 20));
+/// assert_ne!(image, plain);
+///
+/// // Filters without a range apply to the whole image
+/// let effects = vec![Effect { filter: Filter::Invert, range: None }];
+/// let image   = rasterize_with_options(&text, &font, &Options { effects, ..Default::default() })?;
+/// assert!(image.pixels().zip(plain.pixels()).all(|(inverted, pixel)| inverted[3] == pixel[3]));
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Effect {
+    pub filter: Filter,
+    /// Byte range of the text whose glyphs are filtered, relative to the text as given, like the ranges of [`Mask::Blur`].
+    /// The rectangle that is filtered spans the height of the line and the advance of the glyphs.
+    ///
+    /// If no range is provided, the whole image is filtered.
+    pub range: Option<Range<usize>>,
+}
+
+/// A filter that changes the pixels of an image, for an [`Effect`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Filter {
+    /// Blur with a Gaussian blur, with a standard deviation in pixels.
+    Blur(f32),
+    /// Replace each square of pixels with its average, with a width in pixels.
+    Pixelate(u32),
+    /// Invert the color of each pixel, and keep its alpha.
+    Invert,
+    /// Replace the color of each pixel with its luminance.
+    Desaturate,
+}
+
+impl Display for Filter {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        match self {
+            Filter::Blur(sigma) => write!(f, "blur:{sigma}"),
+            Filter::Pixelate(size) => write!(f, "pixelate:{size}"),
+            Filter::Invert => write!(f, "invert"),
+            Filter::Desaturate => write!(f, "desaturate"),
+        }
+    }
+}
+
+impl FromStr for Filter {
+    type Err = OptionsError;
+
+    /// Returns a [`Filter`] converted from a [`str`], as a name followed by its parameter after a colon (ex. `blur:4`).
+    fn from_str(filter: &str) -> Result<Self, Self::Err> {
+        let error = || OptionsError::UnknownFilterError(filter.to_string());
+        let filter = match filter.split_once(':') {
+            Some(("blur", sigma)) => match sigma.parse::<f32>() {
+                Ok(sigma) if sigma.is_finite() && sigma >= 0.0 => Filter::Blur(sigma),
+                _ => Err(error())?,
+            },
+            Some(("pixelate", size)) => Filter::Pixelate(size.parse().map_err(|_| error())?),
+            None if filter == "invert" => Filter::Invert,
+            None if filter == "desaturate" => Filter::Desaturate,
+            _ => Err(error())?,
+        };
+
+        Ok(filter)
+    }
+}
+
+/// A [BCP 47](https://www.rfc-editor.org/info/bcp47) language tag (ex. `en`, `sr-Cyrl`, `tr-TR`).
+///
+/// The tag is checked to be well-formed, and the case of each subtag is normalized
+/// (ex. `SR-latn-rs` becomes `sr-Latn-RS`), but it is not checked against the registry of known languages.
+///
+/// # Examples
+///
+/// The Cyrillic letter `б` has a different form in Serbian than in Russian, which the font provides with a language-specific substitution.
+///
+/// ```rust
+/// use rasterize_text::{LanguageTag, Options, rasterize_with_options, EN_FONT, read_font_bytes};
+/// use std::str::FromStr;
+///
+/// let font    = read_font_bytes(EN_FONT)?;
+/// let russian = Options { lang: Some(LanguageTag::from_str("ru")?), ..Default::default() };
+/// let serbian = Options { lang: Some(LanguageTag::from_str("sr")?), ..Default::default() };
+/// assert_ne!(
+///     rasterize_with_options(&"б", &font, &russian)?,
+///     rasterize_with_options(&"б", &font, &serbian)?,
+/// );
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct LanguageTag(String);
+
+impl LanguageTag {
+    /// Returns the full language tag as a [`str`].
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns the primary language subtag (ex. `sr` for `sr-Latn-RS`).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use rasterize_text::LanguageTag;
+    /// use std::str::FromStr;
+    ///
+    /// let lang = LanguageTag::from_str("SR-latn-rs")?;
+    /// assert_eq!(lang.as_str(), "sr-Latn-RS");
+    /// assert_eq!(lang.language(), "sr");
+    /// # Ok::<(), color_eyre::eyre::Report>(())
+    /// ```
+    pub fn language(&self) -> &str {
+        self.0.split('-').next().unwrap_or_default()
+    }
+}
+
+impl Display for LanguageTag {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for LanguageTag {
+    type Err = OptionsError;
+
+    /// Returns a [`LanguageTag`] converted from a [`str`].
+    fn from_str(lang: &str) -> Result<Self, Self::Err> {
+        let error = || OptionsError::InvalidLanguageTagError(lang.to_string());
+
+        // Accept underscores, as used in POSIX locales (ex. "tr_TR")
+        let subtags: Vec<&str> = lang.split(['-', '_']).collect();
+
+        // The primary language subtag is 2-8 letters
+        let primary = subtags[0];
+        if !(2..=8).contains(&primary.len()) || !primary.chars().all(|c| c.is_ascii_alphabetic()) {
+            return Err(error());
+        }
+
+        let mut normalized = vec![primary.to_ascii_lowercase()];
+        for subtag in &subtags[1..] {
+            if !(1..=8).contains(&subtag.len())
+                || !subtag.chars().all(|c| c.is_ascii_alphanumeric())
+            {
+                return Err(error());
+            }
+            let alphabetic = subtag.chars().all(|c| c.is_ascii_alphabetic());
+            let subtag = match subtag.len() {
+                // Script subtags are title case (ex. "Latn")
+                4 if alphabetic => {
+                    let (first, rest) = subtag.split_at(1);
+                    first.to_ascii_uppercase() + &rest.to_ascii_lowercase()
+                }
+                // Region subtags are upper case (ex. "RS")
+                2 if alphabetic => subtag.to_ascii_uppercase(),
+                _ => subtag.to_ascii_lowercase(),
+            };
+            normalized.push(subtag);
+        }
+
+        Ok(LanguageTag(normalized.join("-")))
+    }
+}
@@ -0,0 +1,129 @@
+//! Export the outlines of laid out glyphs as vector paths, rather than rasterizing them.
+
+use crate::span::position_spans;
+use crate::{Color, Options, RasterizeError, Span};
+use kurbo::{Affine, BezPath, Point};
+use rusttype::{Font, OutlineBuilder};
+
+/// The outline of a glyph, at its position in the laid out text.
+#[derive(Clone, Debug)]
+pub struct GlyphOutline {
+    /// The id of the glyph in its font.
+    pub id: u16,
+    /// The origin of the glyph on its baseline, in the pixel coordinates of the image that the text is rasterized to.
+    pub position: Point,
+    /// The color of the glyph.
+    pub color: Color,
+    /// The outline of the glyph in pixels, relative to its [`GlyphOutline::position`], with y increasing downwards.
+    pub path: BezPath,
+}
+
+impl GlyphOutline {
+    /// Returns the outline of the glyph moved to its position, in the pixel coordinates of the image.
+    pub fn positioned_path(&self) -> BezPath {
+        Affine::translate(self.position.to_vec2()) * &self.path
+    }
+}
+
+/// Lay out a string of text as configured by [`Options`], and return the outlines of its glyphs as vector paths.
+///
+/// The glyphs are positioned the same as with [`rasterize_with_options`](crate::rasterize_with_options), so the text
+/// can be fed into vector pipelines (ex. SVG editors, plotters or laser cutters). Glyphs without an outline
+/// (ex. spaces) are skipped. Only the glyphs are outlined, without their backgrounds, underlines or effects.
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{outline_text, Options, rasterize_with_options, EN_FONT, read_font_bytes};
+/// use kurbo::Shape;
+///
+/// let text     = "Hello, World!";
+/// let font     = read_font_bytes(EN_FONT)?;
+/// let options  = Options::default();
+///
+/// let outlines = outline_text(&text, &font, &options)?;
+/// // The space has no outline
+/// assert_eq!(outlines.len(), 12);
+/// // The outlines are within the image that the text is rasterized to
+/// let image    = rasterize_with_options(&text, &font, &options)?;
+/// for outline in &outlines {
+///     let bounds = outline.positioned_path().bounding_box();
+///     assert!(bounds.x0 >= -1.0 && bounds.x1 <= f64::from(image.width()) + 1.0);
+///     assert!(bounds.y0 >= -1.0 && bounds.y1 <= f64::from(image.height()) + 1.0);
+/// }
+/// // The paths can be written as SVG, and read back to the same shape
+/// let svg      = outlines[0].positioned_path().to_svg();
+/// assert!(svg.starts_with('M'));
+/// let read     = kurbo::BezPath::from_svg(&svg)?.bounding_box();
+/// let bounds   = outlines[0].positioned_path().bounding_box();
+/// assert!((read.x0 - bounds.x0).abs() < 1e-3 && (read.y1 - bounds.y1).abs() < 1e-3);
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+pub fn outline_text(
+    text: impl AsRef<str>,
+    font: &Font,
+    options: &Options,
+) -> Result<Vec<GlyphOutline>, RasterizeError> {
+    outline_spans(&[Span::from(text.as_ref())], font, options)
+}
+
+/// Lay out spans of styled text as configured by [`Options`], and return the outlines of their glyphs as vector paths.
+///
+/// This is the same as [`outline_text`], but the [`Style`](crate::Style) of each [`Span`] overrides the [`Options`]
+/// for its text, as with [`rasterize_spans`](crate::rasterize_spans). Bold glyphs are drawn again slightly to the right,
+/// so each of their copies has an outline.
+pub fn outline_spans(
+    spans: &[Span],
+    font: &Font,
+    options: &Options,
+) -> Result<Vec<GlyphOutline>, RasterizeError> {
+    let (glyphs, _, _) = position_spans(spans, font, options)?;
+    let outlines = glyphs
+        .iter()
+        .filter_map(|(glyph, color, _)| {
+            let mut builder = PathBuilder::default();
+            // Glyphs that have no outline (ex. spaces) return false
+            if !glyph.unpositioned().build_outline(&mut builder) {
+                return None;
+            }
+            let position = glyph.position();
+            Some(GlyphOutline {
+                id: glyph.id().0,
+                position: Point::new(f64::from(position.x), f64::from(position.y)),
+                color: *color,
+                path: builder.0,
+            })
+        })
+        .collect();
+    Ok(outlines)
+}
+
+/// Build a [`BezPath`] from the outline of a glyph.
+#[derive(Default)]
+struct PathBuilder(BezPath);
+
+impl OutlineBuilder for PathBuilder {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.0.move_to(point(x, y));
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        self.0.line_to(point(x, y));
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        self.0.quad_to(point(x1, y1), point(x, y));
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        self.0.curve_to(point(x1, y1), point(x2, y2), point(x, y));
+    }
+
+    fn close(&mut self) {
+        self.0.close_path();
+    }
+}
+
+fn point(x: f32, y: f32) -> Point {
+    Point::new(f64::from(x), f64::from(y))
+}
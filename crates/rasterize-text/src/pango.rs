@@ -0,0 +1,184 @@
+//! Compare rasterized text with the same text rendered by an external Pango and Cairo installation, to validate
+//! shaping and layout (ex. of complex scripts) against an established engine.
+
+use crate::compare::{compare_images, CompareStyle, Comparison};
+use crate::shape::font_data;
+use crate::{rasterize_with_options, Direction, Options, RasterizeError};
+use image::{imageops, ImageBuffer, Rgba, RgbaImage};
+use rusttype::Font;
+use std::path::Path;
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[derive(Debug, thiserror::Error)]
+pub enum PangoError {
+    #[error(
+        "Failed to run {1:?}. Is Pango with pango-view (ex. the pango1.0-tools package) installed?"
+    )]
+    CommandError(#[source] std::io::Error, String),
+    #[error("pango-view failed to render the text: {0}")]
+    RenderError(String),
+    #[error("The font has no data to pass to Pango. Fonts must be read with read_font_bytes or read_font_file.")]
+    FontDataError,
+    #[error("Failed to read the text rendered by Pango.")]
+    ImageReadError(#[source] image::ImageError),
+    #[error(transparent)]
+    RasterizeError(#[from] RasterizeError),
+}
+
+/// The differences between rasterized text and the text rendered by Pango, from [`compare_with_pango`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PangoComparison {
+    /// The comparison of the ink of the rasterized text (first) with the ink of the text rendered by Pango (second).
+    pub comparison: Comparison,
+    /// The width of the ink of the rasterized text minus the width of the ink of the text rendered by Pango, in pixels.
+    pub width_delta: i64,
+    /// The height of the ink of the rasterized text minus the height of the ink of the text rendered by Pango, in
+    /// pixels.
+    pub height_delta: i64,
+}
+
+/// Rasterize a text as configured by [`Options`], render it with Pango and Cairo (`pango-view`), and compare them.
+///
+/// Pango is given the same font data, size, color, language and direction, with hinting turned off. Both renderings
+/// are cropped to their ink (the pixels that aren't transparent) before they are compared with [`compare_images`],
+/// since the engines pad lines differently, so the deltas show differences in shaping and positioning rather than in
+/// line spacing. Options that Pango doesn't have (ex. [`Options::effects`], [`Options::background`] and wrapping) are
+/// not used.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use rasterize_text::{compare_with_pango, CompareStyle, Options, EN_FONT, read_font_bytes};
+///
+/// let font   = read_font_bytes(EN_FONT)?;
+/// let result = compare_with_pango("Hello, World!", &font, &Options::default(), &CompareStyle::default())?;
+/// result.comparison.image.save("compare_with_pango.png")?;
+/// println!("{} pixels differ, and the ink is {}px wider", result.comparison.changed_pixels, result.width_delta);
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+pub fn compare_with_pango(
+    text: &str,
+    font: &Font,
+    options: &Options,
+    style: &CompareStyle,
+) -> Result<PangoComparison, PangoError> {
+    let options = Options {
+        wrap_width: None,
+        line_numbers: None,
+        mask: None,
+        effects: Vec::new(),
+        selection: None,
+        background: None,
+        corner_radius: 0.0,
+        border: None,
+        ..options.clone()
+    };
+    let ours = ink(&rasterize_with_options(&text, font, &options)?);
+    let theirs = ink(&render_pango(text, font, &options)?);
+    Ok(PangoComparison {
+        comparison: compare_images(&ours, &theirs, style)?,
+        width_delta: i64::from(ours.width()) - i64::from(theirs.width()),
+        height_delta: i64::from(ours.height()) - i64::from(theirs.height()),
+    })
+}
+
+/// Render a text to an image with `pango-view`, with colors premultiplied by their alpha like rasterized text.
+fn render_pango(text: &str, font: &Font, options: &Options) -> Result<RgbaImage, PangoError> {
+    // Each render uses its own directory, so renders can run in parallel
+    static RENDERS: AtomicUsize = AtomicUsize::new(0);
+    let id = RENDERS.fetch_add(1, Ordering::Relaxed);
+    let dir =
+        std::env::temp_dir().join(format!("rasterize-text-pango-{}-{id}", std::process::id()));
+    std::fs::create_dir_all(&dir).map_err(|e| PangoError::CommandError(e, "pango-view".into()))?;
+    let result = render_pango_in(&dir, text, font, options);
+    if let Err(e) = std::fs::remove_dir_all(&dir) {
+        log::warn!("Failed to remove temporary directory {dir:?}: {e}");
+    }
+    result
+}
+
+fn render_pango_in(
+    dir: &Path,
+    text: &str,
+    font: &Font,
+    options: &Options,
+) -> Result<RgbaImage, PangoError> {
+    use rustybuzz::ttf_parser::{name_id, Face};
+
+    let data = font_data(font).ok_or(PangoError::FontDataError)?;
+    let face = Face::parse(data, 0).map_err(|_| PangoError::FontDataError)?;
+    let family = face
+        .names()
+        .into_iter()
+        .filter(|name| name.name_id == name_id::FAMILY && name.is_unicode())
+        .find_map(|name| name.to_string())
+        .ok_or(PangoError::FontDataError)?;
+
+    // Fontconfig only sees the font, so Pango can't substitute a system font for it
+    let error = |e| PangoError::CommandError(e, "pango-view".into());
+    std::fs::write(dir.join("font.ttf"), data).map_err(error)?;
+    let config = format!(
+        "<?xml version=\"1.0\"?>\n<fontconfig>\n<dir>{}</dir>\n<cachedir>{}</cachedir>\n</fontconfig>\n",
+        dir.display(),
+        dir.join("cache").display()
+    );
+    std::fs::write(dir.join("fonts.conf"), config).map_err(error)?;
+
+    // The size of text is its pixel height (ascent - descent), while Pango's size is the em size, which is in
+    // pixels at 72 DPI
+    let em = font.scale_for_pixel_height(options.size) * f32::from(face.units_per_em());
+    let color = options.color;
+    let foreground = format!(
+        "#{:02x}{:02x}{:02x}{:02x}",
+        color.r, color.g, color.b, color.a
+    );
+
+    let png = dir.join("pango.png");
+    let mut command = Command::new("pango-view");
+    command
+        .env("FONTCONFIG_FILE", dir.join("fonts.conf"))
+        .args(["--no-display", "--dpi=72", "--margin=0", "--hinting=none"])
+        .args(["--antialias=gray", "--background=transparent"])
+        .arg(format!("--foreground={foreground}"))
+        .arg(format!("--font={family}, {em}"))
+        .arg(format!("--text={text}"))
+        .arg(format!("--output={}", png.display()));
+    if let Some(lang) = &options.lang {
+        command.arg(format!("--language={lang}"));
+    }
+    if options.direction.resolve(text) == Direction::Rtl {
+        command.arg("--rtl");
+    }
+    let output = command.output().map_err(error)?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(PangoError::RenderError(stderr.trim().to_string()));
+    }
+
+    let mut image = image::open(&png)
+        .map_err(PangoError::ImageReadError)?
+        .to_rgba8();
+    for pixel in image.pixels_mut() {
+        let alpha = u32::from(pixel[3]);
+        for c in 0..3 {
+            pixel[c] = ((u32::from(pixel[c]) * alpha + 127) / 255) as u8;
+        }
+    }
+    Ok(image)
+}
+
+/// Returns an image cropped to its ink, which are the pixels that aren't transparent.
+fn ink(image: &ImageBuffer<Rgba<u8>, Vec<u8>>) -> RgbaImage {
+    let inked = || {
+        image
+            .enumerate_pixels()
+            .filter(|(_, _, pixel)| pixel[3] > 0)
+    };
+    let (Some(left), Some(right)) = (inked().map(|p| p.0).min(), inked().map(|p| p.0).max()) else {
+        return RgbaImage::new(0, 0);
+    };
+    let top = inked().map(|p| p.1).min().unwrap_or_default();
+    let bottom = inked().map(|p| p.1).max().unwrap_or_default();
+    imageops::crop_imm(image, left, top, right - left + 1, bottom - top + 1).to_image()
+}
@@ -0,0 +1,56 @@
+//! Rasterize text with 16 bits per channel, for compositing and color grading without banding.
+
+use crate::span::position_spans;
+use crate::{draw, strict, Options, RasterizeError, Span};
+use image::{ImageBuffer, Rgba};
+use rusttype::Font;
+
+/// Rasterize a string of text to an [`ImageBuffer`] with 16 bits per channel, as configured by [`Options`].
+///
+/// The text is laid out and drawn the same as with [`rasterize_with_options`](crate::rasterize_with_options),
+/// but the coverage of glyphs, backgrounds, effects and borders are kept at 16 bits per channel,
+/// rather than being rounded to 8 bits. Colors (ex. [`Options::color`]) are 8 bit, and are scaled to 16 bits.
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{Options, rasterize_rgba16, rasterize_with_options, save_png16, ColorTag, EN_FONT, read_font_bytes};
+///
+/// let text    = "Hello, World!";
+/// let font    = read_font_bytes(EN_FONT)?;
+/// let options = Options::default();
+///
+/// let image = rasterize_rgba16(&text, &font, &options)?;
+/// save_png16(&image, "rasterize_rgba16.png", ColorTag::Srgb)?;
+///
+/// // The glyphs are the same as with 8 bits per channel, but their edges have more levels of coverage
+/// let image8 = rasterize_with_options(&text, &font, &options)?;
+/// assert_eq!(image.dimensions(), image8.dimensions());
+/// for (pixel, pixel8) in image.pixels().zip(image8.pixels()) {
+///     assert!((pixel[3] / 257).abs_diff(u16::from(pixel8[3])) <= 1);
+/// }
+/// assert!(image.pixels().any(|pixel| pixel[3] % 257 != 0));
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+pub fn rasterize_rgba16(
+    text: impl AsRef<str>,
+    font: &Font,
+    options: &Options,
+) -> Result<ImageBuffer<Rgba<u16>, Vec<u16>>, RasterizeError> {
+    rasterize_rgba16_spans(&[Span::from(text.as_ref())], font, options)
+}
+
+/// Rasterize spans of styled text to an [`ImageBuffer`] with 16 bits per channel, as configured by [`Options`].
+///
+/// This is the same as [`rasterize_rgba16`], but the [`Style`](crate::Style) of each [`Span`] overrides the [`Options`]
+/// for its text, as with [`rasterize_spans`](crate::rasterize_spans).
+pub fn rasterize_rgba16_spans(
+    spans: &[Span],
+    font: &Font,
+    options: &Options,
+) -> Result<ImageBuffer<Rgba<u16>, Vec<u16>>, RasterizeError> {
+    let (glyphs, decorations, warnings) = position_spans(spans, font, options)?;
+    let image = draw(&glyphs, &decorations, options)?;
+    strict::check(image.dimensions(), &glyphs, &warnings, font, options)?;
+    Ok(image)
+}
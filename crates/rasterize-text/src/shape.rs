@@ -0,0 +1,312 @@
+//! Shape lines of text into positioned glyphs, following the Unicode Bidirectional Algorithm.
+
+use crate::itemize::{itemize, Run};
+use crate::span::Segment;
+use crate::{LanguageTag, Options, PixelSnap, TextTransform};
+use rusttype::{Font, Scale};
+use rustybuzz::ttf_parser::Tag;
+use std::ops::Range;
+use std::str::FromStr;
+use unicode_bidi::{BidiInfo, ParagraphInfo};
+
+/// A glyph that has been positioned by the shaper.
+///
+/// Coordinates are in pixels, relative to the start of the baseline.
+/// The x coordinate increases to the right, and the y coordinate increases downwards.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct ShapedGlyph {
+    /// The glyph index within the font.
+    pub id: u16,
+    /// Index of the font in the font stack, where 0 is the primary font and the rest are fallback fonts.
+    pub font: usize,
+    /// Index of the segment of text that produced this glyph, which sets its size and color.
+    pub segment: usize,
+    /// Byte index of the first character in the text that produced this glyph.
+    pub cluster: usize,
+    pub x: f32,
+    pub y: f32,
+    /// How far the pen moves after drawing this glyph, including kerning.
+    pub advance: f32,
+}
+
+/// Shapes lines of text with a stack of fonts.
+///
+/// The fonts are parsed for shaping once, when the shaper is created.
+pub(crate) struct Shaper<'a> {
+    fonts: &'a [&'a Font<'a>],
+    segments: &'a [Segment],
+    faces: Vec<Option<rustybuzz::Face<'a>>>,
+    options: &'a Options,
+    /// The glyph and advance in pixels of a space in the main font, which is the width of a character cell for tab stops.
+    space: (u16, f32),
+    /// The byte ranges of the inline images and emoji that are drawn as images, and their advances in pixels, which
+    /// replace their glyphs (see [`Span::image`](crate::Span::image)).
+    objects: Vec<(Range<usize>, f32)>,
+}
+
+impl<'a> Shaper<'a> {
+    pub fn new(fonts: &'a [&'a Font<'a>], segments: &'a [Segment], options: &'a Options) -> Self {
+        // Shaping needs the raw font data, which is only available if the font owns it.
+        // Fonts created with read_font_file and read_font_bytes always do.
+        let faces: Vec<Option<rustybuzz::Face>> = fonts
+            .iter()
+            .map(|font| font_data(font).and_then(|data| rustybuzz::Face::from_slice(data, 0)))
+            .collect();
+        if faces.iter().any(Option::is_none) {
+            log::debug!("Font data is unavailable for shaping, falling back to nominal glyphs.");
+        }
+
+        let space = fonts[0].glyph(' ').scaled(Scale::uniform(options.size));
+        let space = (space.id().0, space.h_metrics().advance_width);
+
+        Shaper {
+            fonts,
+            segments,
+            faces,
+            options,
+            space,
+            objects: segments
+                .iter()
+                .filter_map(|segment| Some((segment.range.clone(), segment.object?)))
+                .collect(),
+        }
+    }
+
+    /// Substitute the emoji of a text that have a Twemoji asset with a space that is as wide as the line is high,
+    /// where their images are drawn (see [`Options::twemoji`]).
+    #[cfg(feature = "twemoji")]
+    pub fn with_emoji(mut self, text: &str) -> Self {
+        for (range, _) in crate::twemoji::find(text, self.options) {
+            let Some(segment) = self
+                .segments
+                .iter()
+                .find(|segment| segment.range.contains(&range.start))
+            else {
+                continue;
+            };
+            let metrics = self.fonts[0].v_metrics(Scale::uniform(segment.size));
+            self.objects.push((range, metrics.ascent - metrics.descent));
+        }
+        self
+    }
+
+    /// Shape a line of a paragraph into glyphs, laid out horizontally in visual (left-to-right) order.
+    ///
+    /// The line is split into directional runs with the Unicode Bidirectional Algorithm, then at
+    /// the boundaries of segments, and then into runs of a single script and font (see [`itemize`]).
+    /// Each run is shaped independently with [`rustybuzz`] before being placed on the line.
+    pub fn shape_line(
+        &self,
+        text: &str,
+        bidi: &BidiInfo,
+        paragraph: &ParagraphInfo,
+        line: Range<usize>,
+    ) -> Vec<ShapedGlyph> {
+        let mut glyphs = Vec::new();
+        if line.is_empty() {
+            return glyphs;
+        }
+
+        let mut caret = 0.0;
+        // The start of each object that has an advance, since an object can be shaped into several glyphs
+        let mut substituted = Vec::new();
+        let (levels, directional_runs) = bidi.visual_runs(paragraph, line);
+        for directional_run in directional_runs {
+            let rtl = levels[directional_run.start].is_rtl();
+            log::debug!("Directional run: {directional_run:?}, right-to-left: {rtl}");
+
+            // Runs are itemized in logical order, which is reversed visually for right-to-left text
+            let mut runs: Vec<(usize, Run)> = Vec::new();
+            for (i, segment) in self.segments.iter().enumerate() {
+                let start = segment.range.start.max(directional_run.start);
+                let end = segment.range.end.min(directional_run.end);
+                if start < end {
+                    runs.extend(
+                        itemize(text, start..end, self.fonts)
+                            .into_iter()
+                            .map(|run| (i, run)),
+                    );
+                }
+            }
+            if rtl {
+                runs.reverse();
+            }
+
+            for (i, run) in runs {
+                log::debug!("Run: {run:?}");
+                let font = self.fonts[run.font];
+                let segment = &self.segments[i];
+                let run_glyphs = match &self.faces[run.font] {
+                    Some(face) => {
+                        shape_run(face, font, text, &run, rtl, segment.size, self.options)
+                    }
+                    None => shape_run_nominal(font, text, &run, rtl, segment.size),
+                };
+                for mut glyph in run_glyphs {
+                    // Marks and the other glyphs of a character have no advance, so each character is spaced once
+                    if glyph.advance != 0.0 {
+                        glyph.advance += self.options.letter_spacing;
+                    }
+                    // Tabs are drawn as a space, which advances to the next tab stop
+                    if let Some(tab_stops) = &self.options.tab_stops {
+                        if text[glyph.cluster..].starts_with('\t') {
+                            glyph.id = self.space.0;
+                            glyph.font = 0;
+                            glyph.advance = tab_stops.next(caret, self.space.1) - caret;
+                        }
+                    }
+                    // Inline images and emoji are drawn as images, so their glyphs are replaced by a space
+                    let object = self
+                        .objects
+                        .iter()
+                        .find(|(range, _)| range.contains(&glyph.cluster));
+                    if let Some((range, advance)) = object {
+                        glyph.id = self.space.0;
+                        glyph.font = 0;
+                        glyph.x = 0.0;
+                        glyph.y = 0.0;
+                        glyph.advance = match substituted.contains(&range.start) {
+                            true => 0.0,
+                            false => {
+                                substituted.push(range.start);
+                                *advance + self.options.letter_spacing
+                            }
+                        };
+                    }
+                    if self.options.pixel_snap == PixelSnap::Advances {
+                        glyph.advance = glyph.advance.round();
+                    }
+                    glyph.segment = i;
+                    glyph.x += caret;
+                    glyph.y -= segment.rise;
+                    caret += glyph.advance;
+                    glyphs.push(glyph);
+                }
+            }
+        }
+
+        glyphs
+    }
+}
+
+/// Returns the raw data of a font, if the font owns it.
+pub(crate) fn font_data<'a>(font: &'a Font) -> Option<&'a [u8]> {
+    match font {
+        Font::Owned(face) => Some(face.as_slice()),
+        Font::Ref(_) => None,
+    }
+}
+
+/// Whether a font has an OpenType layout feature (ex. `smcp` for small capitals) in its glyph substitution table.
+pub(crate) fn has_feature(font: &Font, tag: Tag) -> bool {
+    font_data(font)
+        .and_then(|data| rustybuzz::ttf_parser::Face::parse(data, 0).ok())
+        .and_then(|face| face.tables().gsub)
+        .is_some_and(|gsub| gsub.features.find(tag).is_some())
+}
+
+/// Convert a [`LanguageTag`] to the language type used by [`rustybuzz`].
+fn to_language(lang: &LanguageTag) -> Option<rustybuzz::Language> {
+    rustybuzz::Language::from_str(lang.as_str()).ok()
+}
+
+/// Convert a script to the script type used by [`rustybuzz`], using the ISO 15924 code (ex. `Latn`).
+fn to_script(script: unicode_script::Script) -> Option<rustybuzz::Script> {
+    let tag = rustybuzz::ttf_parser::Tag::from_bytes_lossy(script.short_name().as_bytes());
+    rustybuzz::Script::from_iso15924_tag(tag)
+}
+
+/// Shape a single run with [`rustybuzz`].
+///
+/// The x and y coordinates of each glyph are offsets from its pen position on the line.
+fn shape_run(
+    face: &rustybuzz::Face,
+    font: &Font,
+    text: &str,
+    run: &Run,
+    rtl: bool,
+    size: f32,
+    options: &Options,
+) -> Vec<ShapedGlyph> {
+    let mut buffer = rustybuzz::UnicodeBuffer::new();
+    buffer.push_str(&text[run.range.clone()]);
+    buffer.set_direction(match rtl {
+        true => rustybuzz::Direction::RightToLeft,
+        false => rustybuzz::Direction::LeftToRight,
+    });
+    if let Some(script) = to_script(run.script) {
+        buffer.set_script(script);
+    }
+    if let Some(lang) = options.lang.as_ref().and_then(to_language) {
+        buffer.set_language(lang);
+    }
+    buffer.guess_segment_properties();
+    let mut features = match options.text_transform {
+        TextTransform::SmallCaps => vec![rustybuzz::Feature::new(Tag::from_bytes(b"smcp"), 1, ..)],
+        _ => Vec::new(),
+    };
+    if options.tabular_figures {
+        features.push(rustybuzz::Feature::new(Tag::from_bytes(b"tnum"), 1, ..));
+    }
+    // Ligatures would keep the characters that they join together, so spaced text isn't joined
+    if options.letter_spacing != 0.0 {
+        for tag in [b"liga", b"clig"] {
+            features.push(rustybuzz::Feature::new(Tag::from_bytes(tag), 0, ..));
+        }
+    }
+    let output = rustybuzz::shape(face, &features, buffer);
+
+    // Convert from font units to pixels, using the same scale as rusttype
+    let scale = font.scale_for_pixel_height(size);
+    // Substitutions in a malformed font can produce glyphs that aren't in the font, which are drawn as missing glyphs
+    let glyph_count = font.glyph_count();
+    output
+        .glyph_infos()
+        .iter()
+        .zip(output.glyph_positions())
+        .map(|(info, position)| ShapedGlyph {
+            id: u16::try_from(info.glyph_id)
+                .ok()
+                .filter(|id| (*id as usize) < glyph_count)
+                .unwrap_or(0),
+            font: run.font,
+            segment: 0,
+            cluster: run.range.start + info.cluster as usize,
+            x: position.x_offset as f32 * scale,
+            y: -position.y_offset as f32 * scale,
+            advance: position.x_advance as f32 * scale,
+        })
+        .collect()
+}
+
+/// Place the nominal glyph for each character of a run, applying only pair kerning.
+///
+/// The x and y coordinates of each glyph are offsets from its pen position on the line.
+fn shape_run_nominal(font: &Font, text: &str, run: &Run, rtl: bool, size: f32) -> Vec<ShapedGlyph> {
+    let scale = Scale::uniform(size);
+    let mut chars: Vec<(usize, char)> = text[run.range.clone()].char_indices().collect();
+    if rtl {
+        chars.reverse();
+    }
+
+    let mut glyphs: Vec<ShapedGlyph> = Vec::new();
+    for (i, c) in chars {
+        let glyph = font.glyph(c).scaled(scale);
+        // Kerning adjusts the advance of the previous glyph
+        if let Some(previous) = glyphs.last_mut() {
+            previous.advance +=
+                font.pair_kerning(scale, rusttype::GlyphId(previous.id), glyph.id());
+        }
+        glyphs.push(ShapedGlyph {
+            id: glyph.id().0,
+            font: run.font,
+            segment: 0,
+            cluster: run.range.start + i,
+            x: 0.0,
+            y: 0.0,
+            advance: glyph.h_metrics().advance_width,
+        });
+    }
+
+    glyphs
+}
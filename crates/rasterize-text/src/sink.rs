@@ -0,0 +1,214 @@
+//! Write rendered images to where they are published, by a relative key (ex. `badges/ada.png`), so that batch jobs can
+//! write to a directory or an object store (ex. S3 or GCS, with the `object_store` feature) in the same way.
+
+use crate::{write_png, EncodeError, PngOptions};
+use image::{ImageBuffer, Rgba};
+use std::path::{Component, Path, PathBuf};
+
+/// Where rendered images are written, by a relative key whose parts are separated by `/` (ex. `badges/ada.png`).
+///
+/// Sinks are shared by the threads of a batch, so they are [`Sync`]. Keys are relative paths whose parts are names
+/// (ex. not `..`), so that a key can't refer to a file or object outside of the sink.
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{rasterize, Color, OutputSink, PngOptions, EN_FONT, read_font_bytes};
+/// use std::collections::HashMap;
+/// use std::sync::Mutex;
+///
+/// // A sink that keeps the objects in memory, like a bucket of an object store
+/// #[derive(Default)]
+/// struct Bucket(Mutex<HashMap<String, Vec<u8>>>);
+///
+/// impl OutputSink for Bucket {
+///     fn write(&self, key: &str, bytes: &[u8]) -> std::io::Result<()> {
+///         self.0.lock().unwrap().insert(key.to_string(), bytes.to_vec());
+///         Ok(())
+///     }
+/// }
+///
+/// let font   = read_font_bytes(EN_FONT)?;
+/// let image  = rasterize(&"Ada", &font, 50.0, &Color::default())?;
+/// let bucket = Bucket::default();
+/// bucket.write_png("badges/ada.png", &image, PngOptions::default())?;
+/// assert!(bucket.0.lock().unwrap()["badges/ada.png"].starts_with(b"\x89PNG"));
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+pub trait OutputSink: Sync {
+    /// Write the bytes of a key, replacing the bytes that were written to it before.
+    fn write(&self, key: &str, bytes: &[u8]) -> std::io::Result<()>;
+
+    /// Returns the bytes that were written to a key (ex. to check whether an output changed since it was written).
+    ///
+    /// Sinks that can't be read return an error of the kind [`Unsupported`](std::io::ErrorKind::Unsupported).
+    fn read(&self, key: &str) -> std::io::Result<Vec<u8>> {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            format!("The key {key:?} can't be read from the sink."),
+        ))
+    }
+
+    /// Encode an image as a PNG with [`write_png`], and write it to a key.
+    fn write_png(
+        &self,
+        key: &str,
+        image_buffer: &ImageBuffer<Rgba<u8>, Vec<u8>>,
+        options: impl Into<PngOptions>,
+    ) -> Result<(), EncodeError>
+    where
+        Self: Sized,
+    {
+        let mut bytes = Vec::new();
+        write_png(image_buffer, &mut bytes, options)?;
+        self.write(key, &bytes)
+            .map_err(|error| EncodeError::FileWriteError(error, PathBuf::from(key)))
+    }
+}
+
+/// An [`OutputSink`] that writes each key as a file of a directory, whose subdirectories are created as needed.
+///
+/// Files are written next to their path and then moved to it, so that readers never see a partial image.
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{rasterize, Color, DirectorySink, OutputSink, PngOptions, EN_FONT, read_font_bytes};
+///
+/// let font  = read_font_bytes(EN_FONT)?;
+/// let image = rasterize(&"Ada", &font, 50.0, &Color::default())?;
+/// let sink  = DirectorySink::new(std::env::temp_dir().join("rasterize_sink"));
+/// sink.write_png("badges/ada.png", &image, PngOptions::default())?;
+/// assert!(sink.path("badges/ada.png").is_file());
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DirectorySink {
+    /// The directory that the keys are relative to.
+    pub directory: PathBuf,
+}
+
+impl DirectorySink {
+    /// Returns a sink that writes files to a directory.
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        DirectorySink {
+            directory: directory.into(),
+        }
+    }
+
+    /// Returns the path of the file of a key.
+    pub fn path(&self, key: &str) -> PathBuf {
+        self.directory.join(Path::new(key))
+    }
+}
+
+impl OutputSink for DirectorySink {
+    fn write(&self, key: &str, bytes: &[u8]) -> std::io::Result<()> {
+        check_key(key)?;
+        let path = self.path(key);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut partial = path.clone().into_os_string();
+        partial.push(format!(".{}.partial", std::process::id()));
+        std::fs::write(&partial, bytes)?;
+        std::fs::rename(&partial, &path)
+    }
+
+    fn read(&self, key: &str) -> std::io::Result<Vec<u8>> {
+        check_key(key)?;
+        std::fs::read(self.path(key))
+    }
+}
+
+/// An [`OutputSink`] that puts each key as an object of an [`ObjectStore`](object_store::ObjectStore) (ex. an S3
+/// bucket, with [`AmazonS3Builder`](https://docs.rs/object_store/latest/object_store/aws/struct.AmazonS3Builder.html)),
+/// under a prefix. This requires the `object_store` feature.
+///
+/// Object stores are asynchronous, so the sink has its own runtime, which each write blocks on.
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{rasterize, Color, ObjectStoreSink, OutputSink, PngOptions, EN_FONT, read_font_bytes};
+/// use object_store::memory::InMemory;
+/// use std::sync::Arc;
+///
+/// let font  = read_font_bytes(EN_FONT)?;
+/// let image = rasterize(&"Ada", &font, 50.0, &Color::default())?;
+/// let store = Arc::new(InMemory::new());
+/// let sink  = ObjectStoreSink::new(store.clone(), "conference/2024")?;
+/// sink.write_png("badges/ada.png", &image, PngOptions::default())?;
+/// assert!(sink.read("badges/ada.png")?.starts_with(b"\x89PNG"));
+/// assert_eq!(sink.path("badges/ada.png").as_ref(), "conference/2024/badges/ada.png");
+/// assert!(sink.write("../ada.png", b"").is_err());
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+#[cfg(feature = "object_store")]
+#[derive(Debug)]
+pub struct ObjectStoreSink {
+    store: std::sync::Arc<dyn object_store::ObjectStore>,
+    prefix: String,
+    runtime: tokio::runtime::Runtime,
+}
+
+#[cfg(feature = "object_store")]
+impl ObjectStoreSink {
+    /// Returns a sink that puts objects in a store, with a prefix that is joined to each key with a `/`
+    /// (ex. `conference/2024`, or an empty prefix for the root of the store).
+    pub fn new(
+        store: std::sync::Arc<dyn object_store::ObjectStore>,
+        prefix: impl Into<String>,
+    ) -> std::io::Result<Self> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()?;
+        Ok(ObjectStoreSink {
+            store,
+            prefix: prefix.into(),
+            runtime,
+        })
+    }
+
+    /// Returns the path of the object of a key, which is the key after the prefix.
+    pub fn path(&self, key: &str) -> object_store::path::Path {
+        object_store::path::Path::from(format!("{}/{key}", self.prefix))
+    }
+}
+
+#[cfg(feature = "object_store")]
+impl OutputSink for ObjectStoreSink {
+    fn write(&self, key: &str, bytes: &[u8]) -> std::io::Result<()> {
+        check_key(key)?;
+        let path = self.path(key);
+        self.runtime
+            .block_on(self.store.put(&path, bytes.to_vec().into()))
+            .map(drop)
+            .map_err(std::io::Error::other)
+    }
+
+    fn read(&self, key: &str) -> std::io::Result<Vec<u8>> {
+        check_key(key)?;
+        let get = async { self.store.get(&self.path(key)).await?.bytes().await };
+        match self.runtime.block_on(get) {
+            Ok(bytes) => Ok(bytes.to_vec()),
+            Err(error @ object_store::Error::NotFound { .. }) => {
+                Err(std::io::Error::new(std::io::ErrorKind::NotFound, error))
+            }
+            Err(error) => Err(std::io::Error::other(error)),
+        }
+    }
+}
+
+/// Returns an error unless a key is a relative path whose parts are names, so that it can't refer to a file or object
+/// outside of a sink (ex. `../secret` or `/etc/passwd`).
+fn check_key(key: &str) -> std::io::Result<()> {
+    let mut components = Path::new(key).components();
+    if key.is_empty() || !components.all(|component| matches!(component, Component::Normal(_))) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("The key {key:?} isn't a relative path within the sink."),
+        ));
+    }
+    Ok(())
+}
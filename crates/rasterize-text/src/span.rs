@@ -0,0 +1,800 @@
+//! Style runs of text differently within a single layout.
+
+use crate::gutter::gutter;
+use crate::inline;
+use crate::mask::{hide, pieces, whole_text};
+use crate::metadata::{add_warnings, CoverageWarning};
+use crate::shape::{font_data, has_feature};
+use crate::strict;
+use crate::tofu;
+use crate::transform::{transform, uppercase};
+use crate::whitespace::collapse;
+use crate::{
+    draw, layout, position_glyphs, Align, Color, Filter, Ink, Options, RasterizeError,
+    RasterizedText, TextTransform,
+};
+use image::{DynamicImage, ImageBuffer, Rgba, RgbaImage};
+use rusttype::{point, Font, Point, PositionedGlyph, Rect, Scale};
+use rustybuzz::ttf_parser::Tag;
+use std::borrow::Cow;
+use std::ops::Range;
+use std::sync::Arc;
+use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization};
+use unicode_properties::{GeneralCategory, UnicodeGeneralCategory};
+
+/// A run of text with its own [`Style`], rasterized with [`rasterize_spans`].
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{Color, Position, Span, Style};
+///
+/// let footnote = Span {
+///     text: "1".to_string(),
+///     style: Style { position: Position::Superscript, ..Default::default() },
+///     ..Default::default()
+/// };
+/// let text = Span::from("Hello");
+/// assert_eq!(text.style.color, None);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Span {
+    pub text: String,
+    pub style: Style,
+    /// Ruby (ex. furigana) to annotate the text with, drawn at half the size and centered above the text.
+    pub ruby: Option<String>,
+    /// An image that is drawn instead of the text of the span, inline with the text of the other spans (see
+    /// [`Span::image`]). The text isn't drawn, and is the alternative text of the image.
+    pub image: Option<InlineImage>,
+}
+
+impl Span {
+    /// A span of an image (ex. a badge or an icon), which is laid out inline with the text of the other spans like a
+    /// glyph that is as wide as the image. Its text is the object replacement character (`U+FFFC`).
+    ///
+    /// Lines aren't spaced further apart for images that are taller than the line, which overlap the lines around them.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use image::{DynamicImage, Rgba, RgbaImage};
+    /// use rasterize_text::{ImageAlign, Span, rasterize_spans, EN_FONT, read_font_bytes};
+    ///
+    /// let badge = RgbaImage::from_pixel(30, 20, Rgba([255, 0, 0, 255]));
+    /// let spans = [
+    ///     Span::from("Build "),
+    ///     Span::image(DynamicImage::ImageRgba8(badge), ImageAlign::Middle),
+    ///     Span::from(" passed"),
+    /// ];
+    /// let font  = read_font_bytes(EN_FONT)?;
+    /// let image = rasterize_spans(&spans, &font, &Default::default())?;
+    /// image.save("rasterize_inline_image.png")?;
+    ///
+    /// let red = image.pixels().filter(|pixel| **pixel == Rgba([255, 0, 0, 255])).count();
+    /// assert_eq!(red, 30 * 20);
+    /// let text = rasterize_spans(&[Span::from("Build  passed")], &font, &Default::default())?;
+    /// assert!(image.width() >= text.width() + 30);
+    /// # Ok::<(), color_eyre::eyre::Report>(())
+    /// ```
+    pub fn image(image: DynamicImage, align: ImageAlign) -> Self {
+        Span {
+            image: Some(InlineImage {
+                image: image.into_rgba8(),
+                align,
+            }),
+            ..Span::from("\u{FFFC}")
+        }
+    }
+}
+
+impl From<&str> for Span {
+    fn from(text: &str) -> Self {
+        Span::from(text.to_string())
+    }
+}
+
+impl From<String> for Span {
+    fn from(text: String) -> Self {
+        Span {
+            text,
+            style: Style::default(),
+            ruby: None,
+            image: None,
+        }
+    }
+}
+
+/// The style of a [`Span`], which overrides the [`Options`] for the text of the span.
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{rasterize_spans, Color, Options, Span, Style, EN_FONT, read_font_bytes};
+/// use image::Rgba;
+///
+/// let font = read_font_bytes(EN_FONT)?;
+/// let red  = Style { color: Some(Color { r: 255, g: 0, b: 0, a: 255 }), ..Default::default() };
+/// let blue = Style { color: Some(Color { r: 0, g: 0, b: 255, a: 255 }), ..Default::default() };
+///
+/// // Glyphs that overlap keep the pixels of the first glyph, even where the next glyph covers them more
+/// let clear   = Style { color: Some(Color { r: 0, g: 0, b: 0, a: 0 }), ..Default::default() };
+/// let options = Options { letter_spacing: -12.0, ..Default::default() };
+/// let first   = rasterize_spans(&[Span { style: red, ..Span::from("l") }, Span { style: clear, ..Span::from("l") }], &font, &options)?;
+/// let second  = rasterize_spans(&[Span { style: clear, ..Span::from("l") }, Span { style: blue, ..Span::from("l") }], &font, &options)?;
+/// let both    = rasterize_spans(&[Span { style: red, ..Span::from("l") }, Span { style: blue, ..Span::from("l") }], &font, &options)?;
+/// assert!(first.enumerate_pixels().any(|(x, y, pixel)| pixel[3] > 0 && second.get_pixel(x, y)[3] > pixel[3]));
+/// assert!(first.enumerate_pixels().all(|(x, y, pixel)| pixel[3] == 0 || both.get_pixel(x, y) == pixel));
+///
+/// // Bold glyphs are drawn again to the right, and their copies keep the pixels that they cover most
+/// let bold   = Style { bold: true, ..red };
+/// let normal = rasterize_spans(&[Span { style: red, ..Span::from("l") }], &font, &Default::default())?;
+/// let bolder = rasterize_spans(&[Span { style: bold, ..Span::from("l") }], &font, &Default::default())?;
+/// assert!(bolder.width() > normal.width());
+/// assert!(normal.enumerate_pixels().all(|(x, y, pixel)| bolder.get_pixel(x, y)[3] >= pixel[3]));
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Style {
+    /// The color of the text. If no color is provided, [`Options::color`] is used.
+    pub color: Option<Color>,
+    /// The vertical position of the text, relative to the baseline.
+    pub position: Position,
+    /// The color of the rectangle behind the text, which spans the height of the line. If no color is provided, there is no background.
+    pub background: Option<Color>,
+    /// Draw the text thicker, by drawing each glyph again slightly to the right (the font is not changed to a bold font).
+    pub bold: bool,
+    /// Draw a line under the text, in the color of the text.
+    pub underline: bool,
+    /// Draw a line through the middle of the text, in the color of the text.
+    pub strikethrough: bool,
+    /// Hide the text behind a solid bar in the color of the text (ex. a spoiler in a chat message, or redacted text),
+    /// which spans the height of the line. The glyphs under the bar are not drawn, so the text can't be recovered from the image.
+    ///
+    /// With [`Options::reveal_spoilers`], the text is drawn over a faint bar instead.
+    pub spoiler: bool,
+}
+
+/// The vertical position of text, relative to the baseline.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Position {
+    /// Text sits on the baseline, at the font size.
+    #[default]
+    Normal,
+    /// Smaller text raised above the baseline (ex. exponents, footnote markers).
+    Superscript,
+    /// Smaller text lowered below the baseline (ex. chemical formulas).
+    Subscript,
+}
+
+/// An image that is laid out inline with the text of spans, with [`Span::image`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InlineImage {
+    /// The pixels of the image, which is drawn at its size in pixels, and faded with the alpha of the color of its span.
+    pub image: RgbaImage,
+    /// How the image is placed vertically on the line.
+    pub align: ImageAlign,
+}
+
+/// How an [`InlineImage`] is placed vertically on the line, relative to the baseline, and the ascent and descent of the
+/// main font at the size of its span.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ImageAlign {
+    /// The bottom of the image sits on the baseline, like a capital letter.
+    #[default]
+    Baseline,
+    /// The top of the image is at the ascent of the font.
+    Top,
+    /// The image is centered between the ascent and the descent of the font.
+    Middle,
+    /// The bottom of the image is at the descent of the font.
+    Bottom,
+}
+
+/// A span of text after its [`Style`] has been resolved against the [`Options`] and font.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Segment {
+    /// Byte range of the span within the text.
+    pub range: Range<usize>,
+    /// Index of the span, which is split into several segments when its small capitals are synthesized.
+    pub span: usize,
+    /// Font size in pixels.
+    pub size: f32,
+    /// Distance of the baseline above the line's baseline in pixels.
+    pub rise: f32,
+    pub color: Color,
+    pub background: Option<Color>,
+    pub bold: bool,
+    pub underline: bool,
+    pub strikethrough: bool,
+    /// The color of the bar that hides the text of a [`Style::spoiler`], whose glyphs are transparent.
+    pub spoiler: Option<Color>,
+    /// The advance in pixels of the [`InlineImage`] that is drawn instead of the glyphs of the segment, if any.
+    pub object: Option<f32>,
+    /// Filters that are applied to the glyphs, from [`Mask::Blur`](crate::Mask::Blur) and the byte ranges of [`Options::effects`].
+    pub filters: Vec<Filter>,
+}
+
+/// A rectangle of pixels that is drawn with the glyphs of a segment, for its background, underline, strikethrough or filters.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Decoration {
+    pub rect: Rect<i32>,
+    pub color: Color,
+    pub kind: DecorationKind,
+}
+
+/// How the rectangle of a [`Decoration`] is drawn.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum DecorationKind {
+    /// Filled in front of the glyphs (ex. an underline or a strikethrough).
+    Foreground,
+    /// The pixels of an image whose top left corner is at a point, which are composited over the glyphs, faded with the
+    /// alpha of the color (ex. an [`InlineImage`]). The rectangle spans the pixels that aren't transparent.
+    Image(Arc<RgbaImage>, Point<i32>),
+    /// Blended behind the glyphs.
+    Background,
+    /// The pixels within the rectangle are filtered, and the color is not used.
+    Filter(Filter),
+    /// Not drawn, but the image is measured to include it (ex. the line boxes of [`Options::fixed_ascent`]).
+    Extent,
+}
+
+/// Normalize, collapse the whitespace of, mask, transform and join the text of spans, and resolve their styles to segments of the joined text.
+///
+/// The text of a span is split into several segments where it is partly hidden by [`Mask::Blur`](crate::Mask::Blur) or filtered by [`Options::effects`].
+/// When the main font has no small capitals, the lowercase letters of [`TextTransform::SmallCaps`] text are
+/// uppercased into their own segments at 70% of the size.
+///
+/// Text that is already normalized and isn't changed is borrowed from the spans, so the text of a single span is only
+/// copied where it changes.
+pub(crate) fn segments<'s>(
+    spans: &'s [Span],
+    font: &Font,
+    options: &Options,
+) -> (Cow<'s, str>, Vec<Segment>) {
+    let synthesize = options.text_transform == TextTransform::SmallCaps
+        && !has_feature(font, Tag::from_bytes(b"smcp"));
+    let mut text = Cow::Borrowed("");
+    let mut segments = Vec::new();
+    // Byte ranges of the mask are relative to the text as given, so the spans are split before they are normalized
+    let pieces = pieces(spans, options);
+    let normalized = pieces
+        .iter()
+        .map(|piece| match is_nfc_quick(piece.text.chars()) {
+            IsNormalized::Yes => Cow::Borrowed(piece.text),
+            _ => Cow::Owned(piece.text.nfc().collect()),
+        })
+        .collect();
+    let collapsed = collapse(normalized, options.whitespace);
+    for (piece, collapsed) in pieces.iter().zip(collapsed) {
+        let (i, span) = (piece.span, &spans[piece.span]);
+        let transformed = match piece.hidden {
+            true => Cow::Owned(hide(&collapsed, options.mask.as_ref())),
+            false => {
+                // Shortcodes are expanded before the case of the text is changed, which would break their names
+                #[cfg(feature = "emoji")]
+                let collapsed = match crate::emoji::expand(&collapsed, options) {
+                    Cow::Owned(expanded) => Cow::Owned(expanded),
+                    Cow::Borrowed(_) => collapsed,
+                };
+                transform(
+                    collapsed,
+                    options.text_transform,
+                    options.lang.as_ref(),
+                    text.chars().next_back(),
+                )
+            }
+        };
+        let (size, rise) = position(font, span.style.position, options.size);
+        let color = span.style.color.unwrap_or(options.color);
+        // Hidden spoilers are drawn as a bar over transparent glyphs, and revealed spoilers over a faint bar
+        let (hidden, revealed) = (
+            span.style.spoiler && !options.reveal_spoilers,
+            span.style.spoiler && options.reveal_spoilers,
+        );
+        let segment = |range, size| Segment {
+            range,
+            span: i,
+            size,
+            rise,
+            color: match hidden {
+                true => Color { a: 0, ..color },
+                false => color,
+            },
+            background: match (piece.selected, &options.selection) {
+                (true, Some(selection)) => Some(selection.color),
+                _ if revealed => span.style.background.or(Some(Color {
+                    a: color.a / 4,
+                    ..color
+                })),
+                _ => span.style.background,
+            },
+            bold: span.style.bold,
+            underline: span.style.underline,
+            strikethrough: span.style.strikethrough,
+            spoiler: hidden.then_some(color),
+            object: span
+                .image
+                .as_ref()
+                .filter(|_| !piece.hidden)
+                .map(|inline| inline.image.width() as f32),
+            filters: piece.filters.clone(),
+        };
+        if !synthesize {
+            let start = text.len();
+            match text.is_empty() {
+                true => text = transformed,
+                false => text.to_mut().push_str(&transformed),
+            }
+            segments.push(segment(start..text.len(), size));
+            continue;
+        }
+        for (run, small) in case_runs(&transformed) {
+            let start = text.len();
+            match small {
+                true => text
+                    .to_mut()
+                    .extend(uppercase(run, options.lang.as_ref()).nfc()),
+                false => text.to_mut().push_str(run),
+            }
+            segments.push(segment(
+                start..text.len(),
+                if small { size * 0.7 } else { size },
+            ));
+        }
+    }
+    (text, segments)
+}
+
+/// Split text into runs of lowercase letters and runs of other characters, where marks join the run before them.
+fn case_runs(text: &str) -> Vec<(&str, bool)> {
+    let mut runs = Vec::new();
+    let mut start = 0;
+    let mut small = false;
+    for (i, c) in text.char_indices() {
+        let mark = matches!(
+            c.general_category(),
+            GeneralCategory::NonspacingMark
+                | GeneralCategory::SpacingMark
+                | GeneralCategory::EnclosingMark
+        );
+        if mark || c.is_lowercase() == small {
+            continue;
+        }
+        if i > start {
+            runs.push((&text[start..i], small));
+        }
+        start = i;
+        small = c.is_lowercase();
+    }
+    if start < text.len() {
+        runs.push((&text[start..], small));
+    }
+    runs
+}
+
+/// Returns the font size and rise in pixels of text at a [`Position`].
+///
+/// Superscripts and subscripts use the size and offset recommended by the font (in the OS/2 table) when available,
+/// and otherwise 60% of the font size, raised by a third or lowered by a seventh of the font size.
+fn position(font: &Font, position: Position, size: f32) -> (f32, f32) {
+    let face = font_data(font).and_then(|data| rustybuzz::ttf_parser::Face::parse(data, 0).ok());
+    let metrics = face.as_ref().and_then(|face| match position {
+        Position::Normal => None,
+        Position::Superscript => face.superscript_metrics(),
+        Position::Subscript => face.subscript_metrics(),
+    });
+    // Font units are converted to pixels with the same scale as rusttype
+    let units_per_em = face.as_ref().map_or(0.0, |face| face.units_per_em() as f32);
+    let scale = font.scale_for_pixel_height(size);
+
+    match (position, metrics) {
+        (Position::Normal, _) => (size, 0.0),
+        (_, Some(metrics)) if metrics.y_size > 0 && units_per_em > 0.0 => {
+            let script_size = size * metrics.y_size as f32 / units_per_em;
+            let offset = metrics.y_offset as f32 * scale;
+            // The subscript offset is measured downwards from the baseline
+            match position {
+                Position::Subscript => (script_size, -offset),
+                _ => (script_size, offset),
+            }
+        }
+        (Position::Superscript, _) => (size * 0.6, size / 3.0),
+        (Position::Subscript, _) => (size * 0.6, -size / 7.0),
+    }
+}
+
+/// Rasterize spans of styled text to an [`ImageBuffer`], as configured by [`Options`].
+///
+/// This is the same as [`rasterize_with_options`](crate::rasterize_with_options), but the [`Style`]
+/// of each [`Span`] overrides the [`Options`] for its text. The spans are laid out as one text,
+/// so they can be wrapped and aligned together.
+///
+/// Spans with [`Span::ruby`] are annotated with the ruby centered above their text, at half the size of the text.
+/// Ruby that is wider than its text overhangs the neighbouring text. When any span has ruby,
+/// every line is moved down by the height of the ruby, so that the ruby fits between the lines.
+///
+/// # Examples
+///
+/// This example shows how to rasterize a formula with a superscript and subscripts, and a red footnote marker.
+///
+/// ```rust
+/// use rasterize_text::{outline_spans, Color, Position, Span, Style, rasterize_spans, EN_FONT, read_font_bytes};
+///
+/// let superscript = Style { position: Position::Superscript, ..Default::default() };
+/// let subscript   = Style { position: Position::Subscript, ..Default::default() };
+/// let footnote    = Style { position: Position::Superscript, color: Some(Color { r: 255, g: 0, b: 0, a: 255 }), ..Default::default() };
+/// let spans = [
+///     Span::from("E = mc"),
+///     Span { text: "2".to_string(), style: superscript, ruby: None, image: None },
+///     Span::from(" and H"),
+///     Span { text: "2".to_string(), style: subscript, ruby: None, image: None },
+///     Span::from("O"),
+///     Span { text: "[1]".to_string(), style: footnote, ruby: None, image: None },
+/// ];
+/// let font  = read_font_bytes(EN_FONT)?;
+/// let image = rasterize_spans(&spans, &font, &Default::default())?;
+/// image.save("rasterize_spans.png")?;
+///
+/// // The glyphs are "E=mc2andH2O[1]", without the spaces
+/// let outlines = outline_spans(&spans, &font, &Default::default())?;
+/// assert_eq!(outlines.len(), 14);
+/// // The superscript is raised above the baseline, and the subscript is lowered below it
+/// assert!(outlines[4].position.y < outlines[3].position.y);
+/// assert!(outlines[9].position.y > outlines[8].position.y);
+/// // The footnote marker is red
+/// assert!(outlines[11..].iter().all(|outline| outline.color == footnote.color.unwrap()));
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+///
+/// This example shows how to annotate Japanese text with furigana, which makes room for the ruby above the line.
+///
+/// ```rust
+/// use rasterize_text::{Span, rasterize_spans, KR_FONT, read_font_bytes};
+///
+/// let spans = [
+///     Span { text: "東京".to_string(), ruby: Some("とうきょう".to_string()), ..Default::default() },
+///     Span::from("に"),
+///     Span { text: "行".to_string(), ruby: Some("い".to_string()), ..Default::default() },
+///     Span::from("く"),
+/// ];
+/// let font  = read_font_bytes(KR_FONT)?;
+/// let image = rasterize_spans(&spans, &font, &Default::default())?;
+/// image.save("rasterize_ruby.png")?;
+///
+/// let plain = rasterize_spans(&[Span::from("東京に行く")], &font, &Default::default())?;
+/// assert_eq!(image.width(), plain.width());
+/// assert!(image.height() > plain.height());
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+pub fn rasterize_spans(
+    spans: &[Span],
+    font: &Font,
+    options: &Options,
+) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>, RasterizeError> {
+    Ok(rasterize_spans_with_metadata(spans, font, options)?.image)
+}
+
+/// Rasterize spans of styled text to a [`RasterizedText`], as configured by [`Options`].
+///
+/// This is the same as [`rasterize_spans`], but also returns warnings for characters that the main font doesn't support
+/// (see [`rasterize_with_metadata`](crate::rasterize_with_metadata)), including the characters of ruby.
+pub fn rasterize_spans_with_metadata(
+    spans: &[Span],
+    font: &Font,
+    options: &Options,
+) -> Result<RasterizedText, RasterizeError> {
+    let (glyphs, decorations, warnings) = position_spans(spans, font, options)?;
+    let image = draw(&glyphs, &decorations, options)?;
+    strict::check(image.dimensions(), &glyphs, &warnings, font, options)?;
+    Ok(RasterizedText { image, warnings })
+}
+
+/// Positioned glyphs in their colors, the backgrounds, underlines and strikethroughs of their spans,
+/// and warnings for characters that the main font doesn't support.
+pub(crate) type PositionedSpans<'f> = (
+    Vec<(PositionedGlyph<'f>, Color, Ink)>,
+    Vec<Decoration>,
+    Vec<CoverageWarning>,
+);
+
+/// Lay out spans of styled text and their ruby, and position their glyphs in pixels without drawing them.
+pub(crate) fn position_spans<'f>(
+    spans: &[Span],
+    font: &Font<'f>,
+    options: &Options,
+) -> Result<PositionedSpans<'f>, RasterizeError> {
+    // Use uniform scaling of the text based on the size in pixels
+    let scale = Scale::uniform(options.size);
+    log::debug!("Font Size (pixels): {scale:?}");
+
+    // Configure spatial metrics based on the uniform scaling
+    let metrics = font.v_metrics(scale);
+    log::debug!("Font Metrics: {metrics:?}");
+
+    // The font stack, with fallback fonts for characters that are missing from the main font
+    let fonts: Vec<&Font> = std::iter::once(font)
+        .chain(options.fallback_fonts.iter())
+        .collect();
+
+    let (text, segments) = segments(spans, font, options);
+    let mut lines = layout::layout(&text, &segments, &fonts, options);
+
+    // Lines are spaced apart to make room for ruby above them, at half the font size
+    let ruby_metrics = font.v_metrics(Scale::uniform(options.size / 2.0));
+    let ruby_height = match spans.iter().any(|span| span.ruby.is_some()) {
+        true => ruby_metrics.ascent - ruby_metrics.descent,
+        false => 0.0,
+    };
+    for (i, line) in lines.iter_mut().enumerate() {
+        line.y += (i + 1) as f32 * ruby_height;
+    }
+
+    // Baselines are moved down to the baseline grid, at least a grid step below the previous baseline
+    let ascent = options.fixed_ascent.unwrap_or(metrics.ascent);
+    let grid = options.baseline_grid.filter(|grid| *grid > 0.0);
+    if let Some(grid) = grid {
+        let mut previous = f32::NEG_INFINITY;
+        for line in lines.iter_mut() {
+            let baseline = (((ascent + line.y) / grid).ceil() * grid).max(previous + grid);
+            line.y = baseline - ascent;
+            previous = baseline;
+        }
+    }
+
+    // Line numbers are drawn in a gutter, and the text is moved to the right of it
+    let gutter = match &options.line_numbers {
+        Some(line_numbers) => Some(gutter(&lines, line_numbers, font, &fonts, options)?),
+        None => None,
+    };
+    let origin = point(gutter.as_ref().map_or(0.0, |gutter| gutter.width), ascent);
+
+    let mut glyphs = position_glyphs(&lines, &segments, &fonts, origin, options)?;
+    // Backgrounds span the height of the line, so that the backgrounds of neighbouring lines meet
+    let mut decorations = decorations(
+        &lines,
+        &segments,
+        font,
+        origin,
+        (metrics.ascent, metrics.line_gap - metrics.descent),
+    );
+    // With a fixed ascent, the image spans the boxes of the lines rather than their ink
+    if options.fixed_ascent.is_some() {
+        let last = lines.last().map_or(0.0, |line| line.y);
+        let bottom = ascent + last - metrics.descent;
+        let bottom = grid.map_or(bottom, |grid| (bottom / grid).ceil() * grid);
+        decorations.push(Decoration {
+            rect: Rect {
+                min: point(0, 0),
+                max: point(0, bottom.ceil() as i32),
+            },
+            color: Color::default(),
+            kind: DecorationKind::Extent,
+        });
+    }
+    // Missing glyphs are drawn as boxes with their codepoints, and removed after the ruby is positioned
+    let mut tofu = Vec::new();
+    if let Some(style) = &options.tofu {
+        tofu.push(tofu::boxes(&text, &lines, &segments, font, origin, style));
+    }
+    // Inline images and emoji with a Twemoji asset are drawn in the advances that were reserved for them
+    decorations.extend(inline::images(&lines, &segments, spans, font, origin));
+    #[cfg(feature = "twemoji")]
+    decorations.extend(crate::twemoji::images(
+        &text, &lines, &segments, font, origin, options,
+    ));
+    if let Some(gutter) = gutter {
+        glyphs.extend(gutter.glyphs);
+        decorations.extend(gutter.rule);
+    }
+    let mut warnings = Vec::new();
+    add_warnings(&mut warnings, &text, &lines, &fonts);
+
+    for (i, span) in spans.iter().enumerate() {
+        let Some(ruby) = span.ruby.as_deref() else {
+            continue;
+        };
+        // The ruby is placed over the first line of its text, if the text is wrapped
+        let Some((line, start, end)) = lines.iter().find_map(|line| {
+            let base = line
+                .glyphs
+                .iter()
+                .filter(|glyph| segments[glyph.segment].span == i);
+            let start = base.clone().map(|glyph| glyph.x).reduce(f32::min)?;
+            let end = base.map(|glyph| glyph.x + glyph.advance).reduce(f32::max)?;
+            Some((line, start, end))
+        }) else {
+            continue;
+        };
+        let Some(segment) = segments.iter().find(|segment| segment.span == i) else {
+            continue;
+        };
+        let (ruby_text, ruby_lines, ruby_segments) =
+            layout_ruby(ruby, segment, font, &fonts, options);
+        add_warnings(&mut warnings, &ruby_text, &ruby_lines, &fonts);
+        let width = ruby_lines.first().map_or(0.0, |line| line.width);
+
+        // Center the ruby over its text, with the bottom of the ruby at the top of the text
+        let base_metrics = font.v_metrics(Scale::uniform(segment.size));
+        let ruby_metrics = font.v_metrics(Scale::uniform(segment.size / 2.0));
+        let x = origin.x + ((start + end - width) / 2.0).max(0.0);
+        let y = origin.y + line.y - segment.rise - base_metrics.ascent + ruby_metrics.descent;
+        glyphs.extend(position_glyphs(
+            &ruby_lines,
+            &ruby_segments,
+            &fonts,
+            point(x, y),
+            options,
+        )?);
+        if let Some(style) = &options.tofu {
+            tofu.push(tofu::boxes(
+                &ruby_text,
+                &ruby_lines,
+                &ruby_segments,
+                font,
+                point(x, y),
+                style,
+            ));
+        }
+        #[cfg(feature = "twemoji")]
+        decorations.extend(crate::twemoji::images(
+            &ruby_text,
+            &ruby_lines,
+            &ruby_segments,
+            font,
+            point(x, y),
+            options,
+        ));
+    }
+    if options.tofu.is_some() {
+        glyphs.retain(|(glyph, ..)| glyph.id().0 != 0);
+    }
+    for (codepoints, boxes) in tofu {
+        glyphs.extend(codepoints);
+        decorations.extend(boxes);
+    }
+
+    Ok((glyphs, decorations, warnings))
+}
+
+/// Returns the backgrounds, spoiler bars, underlines, strikethroughs and filters of the glyphs of lines, with the first line's baseline starting at `origin`.
+///
+/// Backgrounds, spoiler bars and filters extend `extent` pixels above and below the baseline of the line, as (above, below),
+/// and span the advance of each glyph. The filters of neighbouring glyphs are joined, so that they are filtered together. Underlines are placed as recommended by the font (in the post table) when available,
+/// and otherwise a tenth of the font size below the baseline, at least a pixel thick. Strikethroughs are placed as recommended
+/// by the font (in the OS/2 table) when available, and otherwise a quarter of the font size above the baseline.
+pub(crate) fn decorations(
+    lines: &[layout::Line],
+    segments: &[Segment],
+    font: &Font,
+    origin: Point<f32>,
+    extent: (f32, f32),
+) -> Vec<Decoration> {
+    if !segments.iter().any(|segment| {
+        segment.background.is_some()
+            || segment.spoiler.is_some()
+            || segment.underline
+            || segment.strikethrough
+            || !segment.filters.is_empty()
+    }) {
+        return Vec::new();
+    }
+    let face = font_data(font).and_then(|data| rustybuzz::ttf_parser::Face::parse(data, 0).ok());
+    let underline = face
+        .as_ref()
+        .and_then(|face| face.underline_metrics())
+        .filter(|metrics| metrics.thickness > 0);
+    let strikethrough = face
+        .as_ref()
+        .and_then(|face| face.strikeout_metrics())
+        .filter(|metrics| metrics.thickness > 0);
+
+    // Float to integer casts saturate, so glyphs that are far away are drawn at the edge
+    let rect = |x: (f32, f32), y: (f32, f32)| Rect {
+        min: point(x.0.round() as i32, y.0.round() as i32),
+        max: point(x.1.round() as i32, y.1.round() as i32),
+    };
+    let mut decorations = Vec::new();
+    for line in lines {
+        let baseline = origin.y + line.y;
+        for glyph in &line.glyphs {
+            let segment = &segments[glyph.segment];
+            let x = (origin.x + glyph.x, origin.x + glyph.x + glyph.advance);
+            if let Some(color) = segment.background {
+                decorations.push(Decoration {
+                    rect: rect(x, (baseline - extent.0, baseline + extent.1)),
+                    color,
+                    kind: DecorationKind::Background,
+                });
+            }
+            if segment.underline {
+                // Font units are converted to pixels with the same scale as rusttype
+                let scale = font.scale_for_pixel_height(segment.size);
+                let (offset, thickness) = match underline {
+                    Some(metrics) => (
+                        -metrics.position as f32 * scale,
+                        metrics.thickness as f32 * scale,
+                    ),
+                    None => (segment.size / 10.0, segment.size / 20.0),
+                };
+                let top = baseline + offset;
+                decorations.push(Decoration {
+                    rect: rect(x, (top, top + thickness.max(1.0))),
+                    color: segment.color,
+                    kind: DecorationKind::Foreground,
+                });
+            }
+            if segment.strikethrough {
+                // The strikeout position is measured upwards from the baseline of the text, which is raised for superscripts
+                let scale = font.scale_for_pixel_height(segment.size);
+                let (offset, thickness) = match strikethrough {
+                    Some(metrics) => (
+                        metrics.position as f32 * scale,
+                        metrics.thickness as f32 * scale,
+                    ),
+                    None => (segment.size / 4.0, segment.size / 20.0),
+                };
+                let top = baseline - segment.rise - offset;
+                decorations.push(Decoration {
+                    rect: rect(x, (top, top + thickness.max(1.0))),
+                    color: segment.color,
+                    kind: DecorationKind::Foreground,
+                });
+            }
+            if let Some(color) = segment.spoiler {
+                decorations.push(Decoration {
+                    rect: rect(x, (baseline - extent.0, baseline + extent.1)),
+                    color,
+                    kind: DecorationKind::Foreground,
+                });
+            }
+            for filter in &segment.filters {
+                let rect = rect(x, (baseline - extent.0, baseline + extent.1));
+                let kind = DecorationKind::Filter(*filter);
+                let joined = decorations.iter_mut().rev().find(|decoration| {
+                    decoration.kind == kind
+                        && decoration.rect.max.x == rect.min.x
+                        && (decoration.rect.min.y, decoration.rect.max.y)
+                            == (rect.min.y, rect.max.y)
+                });
+                match joined {
+                    Some(decoration) => decoration.rect.max.x = rect.max.x,
+                    None => decorations.push(Decoration {
+                        rect,
+                        color: segment.color,
+                        kind,
+                    }),
+                }
+            }
+        }
+    }
+    decorations.retain(|decoration| {
+        decoration.rect.min.x < decoration.rect.max.x
+            && decoration.rect.min.y < decoration.rect.max.y
+    });
+    decorations
+}
+
+/// Lay out ruby on a single line, at half the size and in the color of the text it annotates.
+fn layout_ruby(
+    ruby: &str,
+    segment: &Segment,
+    font: &Font,
+    fonts: &[&Font],
+    options: &Options,
+) -> (String, Vec<layout::Line>, Vec<Segment>) {
+    let options = Options {
+        size: segment.size / 2.0,
+        color: segment.color,
+        wrap_width: None,
+        align: Align::Left,
+        first_line_indent: 0.0,
+        hanging_indent: 0.0,
+        mask: whole_text(options.mask.as_ref()),
+        effects: Vec::new(),
+        ..options.clone()
+    };
+    let spans = [Span::from(ruby)];
+    let (text, segments) = segments(&spans, font, &options);
+    let lines = layout::layout(&text, &segments, fonts, &options);
+    (text.into_owned(), lines, segments)
+}
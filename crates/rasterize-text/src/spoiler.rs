@@ -0,0 +1,117 @@
+//! Hide spoilers and redacted text behind bars, from chat markup, for previews of moderated content.
+
+use crate::{rasterize_spans, Options, RasterizeError, Span, Style};
+use image::{ImageBuffer, Rgba};
+use rusttype::Font;
+
+/// The markers that open and close spoilers, as in Discord (`||`) and HTML-like markup (`<redact>`).
+const MARKERS: [(&str, &str); 2] = [("||", "||"), ("<redact>", "</redact>")];
+
+/// Parse text with spoiler markup into [`Span`]s, where the text of spoilers has [`Style::spoiler`].
+///
+/// Spoilers are surrounded by `||` (ex. `||Snape kills Dumbledore||`) or by `<redact>` and `</redact>`. Markers that
+/// aren't closed are drawn as text, and spoilers aren't nested.
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{parse_spoilers, rasterize_spans, EN_FONT, read_font_bytes};
+///
+/// let spans = parse_spoilers("The butler ||did it||, said <redact>Jane Doe</redact> || 2");
+/// assert_eq!(spans.len(), 5);
+/// assert_eq!(spans[1].text, "did it");
+/// assert!(spans[1].style.spoiler);
+/// assert_eq!(spans[3].text, "Jane Doe");
+/// assert!(spans[3].style.spoiler);
+/// assert_eq!(spans[4].text, " || 2");
+///
+/// let font  = read_font_bytes(EN_FONT)?;
+/// let image = rasterize_spans(&spans, &font, &Default::default())?;
+/// image.save("parse_spoilers.png")?;
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+pub fn parse_spoilers(text: &str) -> Vec<Span> {
+    let mut spans = Vec::new();
+    let mut text_start = 0;
+    let mut rest = 0;
+    while let Some((start, open, close)) = MARKERS
+        .iter()
+        .filter_map(|(open, close)| Some((rest + text[rest..].find(open)?, open, close)))
+        .min_by_key(|(start, _, _)| *start)
+    {
+        let inner = start + open.len();
+        let Some(end) = text[inner..].find(close).map(|end| inner + end) else {
+            // An unclosed marker is text, and a later marker can still open a spoiler
+            rest = inner;
+            continue;
+        };
+        push(&mut spans, &text[text_start..start], false);
+        push(&mut spans, &text[inner..end], true);
+        rest = end + close.len();
+        text_start = rest;
+    }
+    push(&mut spans, &text[text_start..], false);
+    spans
+}
+
+/// Add text to the spans, as a spoiler or as plain text.
+fn push(spans: &mut Vec<Span>, text: &str, spoiler: bool) {
+    if text.is_empty() {
+        return;
+    }
+    spans.push(Span {
+        style: Style {
+            spoiler,
+            ..Default::default()
+        },
+        ..Span::from(text)
+    });
+}
+
+/// The two layers of text with spoilers, which are the same size, so that the revealed layer can replace the hidden
+/// layer when it is clicked.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpoilerLayers {
+    /// The text with its spoilers hidden behind solid bars.
+    pub hidden: ImageBuffer<Rgba<u8>, Vec<u8>>,
+    /// The text with its spoilers drawn over faint bars.
+    pub revealed: ImageBuffer<Rgba<u8>, Vec<u8>>,
+}
+
+/// Rasterize spans of text with spoilers twice, with the spoilers hidden and revealed, as configured by [`Options`].
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{parse_spoilers, rasterize_spoilers, Options, EN_FONT, read_font_bytes};
+///
+/// let font   = read_font_bytes(EN_FONT)?;
+/// let spans  = parse_spoilers("The butler ||did it||");
+/// let layers = rasterize_spoilers(&spans, &font, &Options::default())?;
+/// layers.hidden.save("rasterize_spoilers_hidden.png")?;
+/// layers.revealed.save("rasterize_spoilers_revealed.png")?;
+///
+/// assert_eq!(layers.hidden.dimensions(), layers.revealed.dimensions());
+/// assert_ne!(layers.hidden, layers.revealed);
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+pub fn rasterize_spoilers(
+    spans: &[Span],
+    font: &Font,
+    options: &Options,
+) -> Result<SpoilerLayers, RasterizeError> {
+    let layer = |reveal_spoilers| {
+        rasterize_spans(
+            spans,
+            font,
+            &Options {
+                reveal_spoilers,
+                ..options.clone()
+            },
+        )
+    };
+    Ok(SpoilerLayers {
+        hidden: layer(false)?,
+        revealed: layer(true)?,
+    })
+}
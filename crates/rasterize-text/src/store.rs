@@ -0,0 +1,275 @@
+//! Cache the encoded images of rendered text, keyed by a hash of everything that they are rendered from, so that text
+//! that is rendered again with the same inputs (ex. by a build or a batch of templates) is read rather than drawn.
+
+use crate::metadata::CoverageWarning;
+use crate::shape::font_data;
+use crate::span::position_spans;
+use crate::{
+    rasterize_spans_with_metadata, write_png, EncodeError, Options, PngOptions, RasterizeError,
+    Span,
+};
+use rusttype::Font;
+use siphasher::sip128::{Hasher128, SipHasher13};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::hash::Hasher;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+#[derive(Debug, thiserror::Error)]
+pub enum RenderCacheError {
+    #[error(transparent)]
+    RasterizeError(#[from] RasterizeError),
+    #[error(transparent)]
+    EncodeError(#[from] EncodeError),
+}
+
+/// Where the encoded images of [`rasterize_cached`] are stored (ex. a [`DiskCache`], a [`MemoryCache`], or a key-value
+/// store that is shared by several machines).
+pub trait CacheStore {
+    /// Returns the encoded image of a key, if it is stored.
+    fn get(&self, key: &RenderKey) -> Option<Vec<u8>>;
+    /// Store the encoded image of a key.
+    fn put(&self, key: &RenderKey, bytes: &[u8]) -> std::io::Result<()>;
+}
+
+/// A hash of the text, fonts and options that an image is rendered from, which keys its encoded image in a
+/// [`CacheStore`]. It is displayed as 32 hexadecimal digits.
+///
+/// The key is the 128 bit SipHash-1-3 (with keys of zero) of the version of the crate, the spans of the text (with their
+/// styles, ruby and images), the data of the main and fallback fonts, and the [`Options`] and [`PngOptions`]. Files that the options refer to by path
+/// (ex. the Twemoji assets of `Options::twemoji`) are hashed by their path, so changing them doesn't change the key.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct RenderKey(pub u128);
+
+impl RenderKey {
+    /// Returns the key of text that is rendered with a [`Font`] and encoded as configured, or [`None`] if a font doesn't
+    /// own its data (so it can't be hashed).
+    pub fn new(
+        text: impl AsRef<str>,
+        font: &Font,
+        options: &Options,
+        png_options: &PngOptions,
+    ) -> Option<Self> {
+        RenderKey::from_spans(&[Span::from(text.as_ref())], font, options, png_options)
+    }
+
+    /// Returns the key of spans of styled text, as with [`RenderKey::new`].
+    pub fn from_spans(
+        spans: &[Span],
+        font: &Font,
+        options: &Options,
+        png_options: &PngOptions,
+    ) -> Option<Self> {
+        let mut hasher = SipHasher13::new();
+        let mut write = |bytes: &[u8]| {
+            // Each field is prefixed with its length, so that the bytes of one field can't be moved to the next
+            hasher.write(&(bytes.len() as u64).to_le_bytes());
+            hasher.write(bytes);
+        };
+        write(env!("CARGO_PKG_VERSION").as_bytes());
+        write(format!("{spans:?}").as_bytes());
+        for font in std::iter::once(font).chain(&options.fallback_fonts) {
+            write(font_data(font)?);
+        }
+        // Fonts are debugged as `Font`, so the options only add their other fields
+        write(format!("{options:?}").as_bytes());
+        write(format!("{png_options:?}").as_bytes());
+        Some(RenderKey(hasher.finish128().as_u128()))
+    }
+}
+
+impl Display for RenderKey {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "{:032x}", self.0)
+    }
+}
+
+/// A [`CacheStore`] that stores images as PNG files in a directory, which is created when the first image is stored.
+///
+/// Files are named by their [`RenderKey`] (ex. `5f1d….png`), and are written next to their path and then moved to
+/// it, so that several processes can share the directory. Files are never removed, so the directory can be cleared
+/// when it grows too large.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiskCache {
+    /// The directory that the images are stored in.
+    pub directory: PathBuf,
+}
+
+impl DiskCache {
+    /// Returns a cache that stores images in a directory.
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        DiskCache {
+            directory: directory.into(),
+        }
+    }
+
+    /// Returns the path of the image of a key.
+    pub fn path(&self, key: &RenderKey) -> PathBuf {
+        self.directory.join(format!("{key}.png"))
+    }
+}
+
+impl CacheStore for DiskCache {
+    fn get(&self, key: &RenderKey) -> Option<Vec<u8>> {
+        std::fs::read(self.path(key)).ok()
+    }
+
+    fn put(&self, key: &RenderKey, bytes: &[u8]) -> std::io::Result<()> {
+        std::fs::create_dir_all(&self.directory)?;
+        let path = self.path(key);
+        let partial = path.with_extension(format!("png.{}.partial", std::process::id()));
+        std::fs::write(&partial, bytes)?;
+        std::fs::rename(&partial, &path)
+    }
+}
+
+/// A [`CacheStore`] that keeps images in memory, for processes that render the same text many times.
+#[derive(Debug, Default)]
+pub struct MemoryCache {
+    images: Mutex<HashMap<RenderKey, Vec<u8>>>,
+}
+
+impl MemoryCache {
+    /// Returns the number of stored images.
+    pub fn len(&self) -> usize {
+        self.images.lock().map_or(0, |images| images.len())
+    }
+
+    /// Returns whether no images are stored.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl CacheStore for MemoryCache {
+    fn get(&self, key: &RenderKey) -> Option<Vec<u8>> {
+        self.images.lock().ok()?.get(key).cloned()
+    }
+
+    fn put(&self, key: &RenderKey, bytes: &[u8]) -> std::io::Result<()> {
+        if let Ok(mut images) = self.images.lock() {
+            images.insert(*key, bytes.to_vec());
+        }
+        Ok(())
+    }
+}
+
+/// The PNG of spans of text that were rendered with [`rasterize_spans_cached`], and the warnings for characters that the
+/// main font doesn't support (see [`RasterizedText`](crate::RasterizedText)).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CachedText {
+    /// The encoded image, as it was stored.
+    pub png: Vec<u8>,
+    /// Characters that the main font doesn't support, in the order that they first appear in the text.
+    pub warnings: Vec<CoverageWarning>,
+}
+
+/// Returns the PNG of text rendered with a [`Font`], as configured by [`Options`] and [`PngOptions`], from a
+/// [`CacheStore`] if it was rendered with the same inputs before.
+///
+/// Otherwise the text is rasterized with [`rasterize_with_options`](crate::rasterize_with_options), encoded with
+/// [`write_png`] and stored. Images that can't be stored are logged and returned anyway, and text with a font that
+/// doesn't own its data isn't cached.
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{rasterize_cached, MemoryCache, Options, PngOptions, EN_FONT, read_font_bytes};
+///
+/// let font  = read_font_bytes(EN_FONT)?;
+/// let cache = MemoryCache::default();
+/// let png   = rasterize_cached("Hello, World!", &font, &Options::default(), PngOptions::default(), &cache)?;
+/// assert_eq!(cache.len(), 1);
+///
+/// // The same text is read from the cache, and other options are rendered again
+/// let again = rasterize_cached("Hello, World!", &font, &Options::default(), PngOptions::default(), &cache)?;
+/// assert_eq!(png, again);
+/// assert_eq!(cache.len(), 1);
+/// let small = Options { size: 20.0, ..Default::default() };
+/// rasterize_cached("Hello, World!", &font, &small, PngOptions::default(), &cache)?;
+/// assert_eq!(cache.len(), 2);
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+pub fn rasterize_cached(
+    text: impl AsRef<str>,
+    font: &Font,
+    options: &Options,
+    png_options: impl Into<PngOptions>,
+    store: &dyn CacheStore,
+) -> Result<Vec<u8>, RenderCacheError> {
+    let spans = [Span::from(text.as_ref())];
+    let (png, _) = cached(&spans, font, options, png_options.into(), store)?;
+    Ok(png)
+}
+
+/// Returns the PNG of spans of styled text and their warnings, from a [`CacheStore`] if they were rendered with the same
+/// inputs before, as with [`rasterize_cached`].
+///
+/// The warnings aren't stored, so text that is read from the cache is laid out again to find them, but isn't drawn or
+/// encoded.
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{rasterize_spans_cached, CoverageWarning, MemoryCache, Options, PngOptions, Span, Style};
+/// use rasterize_text::{EN_FONT, read_font_bytes};
+///
+/// let font  = read_font_bytes(EN_FONT)?;
+/// let cache = MemoryCache::default();
+/// let spans = [Span::from("Hello, "), Span { style: Style { bold: true, ..Default::default() }, ..Span::from("🦀") }];
+/// let first = rasterize_spans_cached(&spans, &font, &Options::default(), PngOptions::default(), &cache)?;
+/// assert_eq!(first.warnings, vec![CoverageWarning::Missing { character: '🦀', count: 1 }]);
+///
+/// // The same spans are read from the cache with the same warnings, and other styles are rendered again
+/// let again = rasterize_spans_cached(&spans, &font, &Options::default(), PngOptions::default(), &cache)?;
+/// assert_eq!((again, cache.len()), (first, 1));
+/// rasterize_spans_cached(&[Span::from("Hello, 🦀")], &font, &Options::default(), PngOptions::default(), &cache)?;
+/// assert_eq!(cache.len(), 2);
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+pub fn rasterize_spans_cached(
+    spans: &[Span],
+    font: &Font,
+    options: &Options,
+    png_options: impl Into<PngOptions>,
+    store: &dyn CacheStore,
+) -> Result<CachedText, RenderCacheError> {
+    let (png, warnings) = cached(spans, font, options, png_options.into(), store)?;
+    let warnings = match warnings {
+        Some(warnings) => warnings,
+        None => position_spans(spans, font, options)?.2,
+    };
+    Ok(CachedText { png, warnings })
+}
+
+/// Returns the PNG of spans from a [`CacheStore`], or renders and stores it, with its warnings if it was rendered.
+fn cached(
+    spans: &[Span],
+    font: &Font,
+    options: &Options,
+    png_options: PngOptions,
+    store: &dyn CacheStore,
+) -> Result<(Vec<u8>, Option<Vec<CoverageWarning>>), RenderCacheError> {
+    let key = RenderKey::from_spans(spans, font, options, &png_options);
+    if let Some(key) = &key {
+        if let Some(bytes) = store.get(key) {
+            log::debug!("Read the rendered text {key} from the cache.");
+            return Ok((bytes, None));
+        }
+    }
+    let rasterized = rasterize_spans_with_metadata(spans, font, options)?;
+    let mut bytes = Vec::new();
+    write_png(&rasterized.image, &mut bytes, png_options)?;
+    match key {
+        Some(key) => {
+            if let Err(error) = store.put(&key, &bytes) {
+                log::warn!("Failed to store the rendered text in the cache: {error}");
+            }
+        }
+        None => {
+            log::debug!("Font data is unavailable for hashing, so the rendered text isn't cached.")
+        }
+    }
+    Ok((bytes, Some(rasterized.warnings)))
+}
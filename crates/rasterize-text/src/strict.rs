@@ -0,0 +1,188 @@
+//! Turn conditions that rasterizing text recovers from into errors, with [`Options::strict`].
+
+use crate::metadata::CoverageWarning;
+use crate::shape::font_data;
+use crate::{Color, Ink, Options};
+use image::{ImageBuffer, Rgba};
+use rusttype::{point, Font, PositionedGlyph, Rect};
+use rustybuzz::ttf_parser::{Face, Tag};
+
+/// A condition that rasterizing text recovers from, which is an error with [`Options::strict`].
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{Options, RasterizeError, StrictError, rasterize_with_options, EN_FONT, read_font_bytes};
+/// use rasterize_text::{rasterize_card, rasterize_glyph_with_options, rasterize_grid, rasterize_rgba16, Card, GridLayout};
+/// use image::DynamicImage;
+///
+/// let font   = read_font_bytes(EN_FONT)?;
+/// let strict = Options { strict: true, ..Default::default() };
+/// rasterize_with_options(&"Hello, World!", &font, &strict)?;
+///
+/// // Without strict mode, the crab is drawn as the missing glyph of the font
+/// rasterize_with_options(&"Hello 🦀", &font, &Options::default())?;
+/// let result = rasterize_with_options(&"Hello 🦀", &font, &strict);
+/// assert!(matches!(result, Err(RasterizeError::Strict(StrictError::MissingGlyph(_)))));
+///
+/// let result = rasterize_with_options(&"   ", &font, &strict);
+/// assert!(matches!(result, Err(RasterizeError::Strict(StrictError::EmptyImage { .. }))));
+///
+/// // Every function that takes options is strict, ex. with 16 bits per channel, or for an icon the font doesn't have
+/// let result = rasterize_rgba16(&"Hello 🦀", &font, &strict);
+/// assert!(matches!(result, Err(RasterizeError::Strict(StrictError::MissingGlyph(_)))));
+/// let result = rasterize_glyph_with_options(&font, '\u{F185}', &strict);
+/// assert!(matches!(result, Err(RasterizeError::Strict(StrictError::MissingIcon))));
+///
+/// // Glyphs that are wider than the cells of a grid, or text that is taller than a card, are clipped
+/// let grid   = GridLayout { cell_w: 4.0, ..GridLayout::new(2, 1, &font, strict.size) };
+/// let result = rasterize_grid(&"WW", &font, &grid, &strict);
+/// assert!(matches!(result, Err(RasterizeError::Strict(StrictError::ClippedGlyphs { left, right, .. })) if left > 0 && right > 0));
+/// let card   = Card { size: Some((200, 40)), ..Card::new(DynamicImage::new_rgba8(1, 1)) };
+/// let result = rasterize_card(&"Hello, World!", &font, &card, &strict);
+/// assert!(matches!(result, Err(RasterizeError::Strict(StrictError::ClippedGlyphs { top, bottom, .. })) if top > 0 && bottom > 0));
+/// let card   = Card { size: Some((600, 300)), ..card };
+/// rasterize_card(&"Hello, World!", &font, &card, &strict)?;
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum StrictError {
+    #[error("{0}")]
+    MissingGlyph(CoverageWarning),
+    #[error("The image is {width}x{height} pixels, so it has no pixels (ex. text that is only whitespace).")]
+    EmptyImage { width: u32, height: u32 },
+    #[error("The font has no kerning (a kern table or a kern feature in its GPOS table), so pairs of glyphs may be spaced unevenly.")]
+    NoKerning,
+    #[error("Glyphs extend {left}, {top}, {right} and {bottom} pixel(s) past the left, top, right and bottom edges of the image, so they are clipped.")]
+    ClippedGlyphs {
+        left: u32,
+        top: u32,
+        right: u32,
+        bottom: u32,
+    },
+    #[error("The font has no glyph for the icon, so its missing glyph is drawn.")]
+    MissingIcon,
+}
+
+/// Returns the first condition of rasterized text that is an error with [`Options::strict`], if any.
+///
+/// The glyphs are drawn on an image of `width` by `height` pixels, whose origin is its top left corner, so the pixels
+/// of glyphs that extend to its left (ex. the descender of a `j`, which hooks to the left of its origin), above it
+/// (ex. stacked diacritics above the ascent), or past its right or bottom edge (ex. in a cell of a grid) are clipped.
+pub(crate) fn check(
+    (width, height): (u32, u32),
+    glyphs: &[(PositionedGlyph, Color, Ink)],
+    warnings: &[CoverageWarning],
+    font: &Font,
+    options: &Options,
+) -> Result<(), StrictError> {
+    if !options.strict {
+        return Ok(());
+    }
+    if let Some(warning) = warnings
+        .iter()
+        .find(|warning| matches!(warning, CoverageWarning::Missing { .. }))
+    {
+        return Err(StrictError::MissingGlyph(warning.clone()));
+    }
+    if width == 0 || height == 0 {
+        return Err(StrictError::EmptyImage { width, height });
+    }
+    if !has_kerning(font) {
+        return Err(StrictError::NoKerning);
+    }
+    let bounds = glyphs
+        .iter()
+        .filter_map(|(glyph, ..)| glyph.pixel_bounding_box());
+    clipped(bounds, (width, height))
+}
+
+/// Returns the first condition of a glyph that is drawn on its own that is an error with [`Options::strict`], if any.
+///
+/// A single glyph isn't laid out, so it doesn't need kerning, and a glyph that the font doesn't have is the missing
+/// glyph (`.notdef`).
+pub(crate) fn check_glyph(
+    (width, height): (u32, u32),
+    glyphs: &[(PositionedGlyph, Color, Ink)],
+    options: &Options,
+) -> Result<(), StrictError> {
+    if !options.strict {
+        return Ok(());
+    }
+    if glyphs.iter().any(|(glyph, ..)| glyph.id().0 == 0) {
+        return Err(StrictError::MissingIcon);
+    }
+    if width == 0 || height == 0 {
+        return Err(StrictError::EmptyImage { width, height });
+    }
+    let bounds = glyphs
+        .iter()
+        .filter_map(|(glyph, ..)| glyph.pixel_bounding_box());
+    clipped(bounds, (width, height))
+}
+
+/// Returns an error with [`Options::strict`] if the pixels of rasterized text that is composited over an image of
+/// `width` by `height` pixels, with its top left corner at `x` and `y`, are clipped at the edges of the image.
+pub(crate) fn check_placed(
+    text: &ImageBuffer<Rgba<u8>, Vec<u8>>,
+    (x, y): (i64, i64),
+    (width, height): (u32, u32),
+    options: &Options,
+) -> Result<(), StrictError> {
+    if !options.strict {
+        return Ok(());
+    }
+    // Only the pixels that are drawn are clipped, rather than the transparent pixels around them
+    let drawn = text.enumerate_pixels().filter(|(.., pixel)| pixel[3] > 0);
+    let bounds = drawn.map(|(tx, ty, _)| {
+        let at = |origin: i64, offset: u32| {
+            let coordinate = (origin + i64::from(offset)).clamp(i32::MIN.into(), i32::MAX.into());
+            coordinate as i32
+        };
+        let min = point(at(x, tx), at(y, ty));
+        Rect {
+            min,
+            max: point(min.x.saturating_add(1), min.y.saturating_add(1)),
+        }
+    });
+    clipped(bounds, (width, height))
+}
+
+/// Returns an error if the pixel bounding boxes of glyphs extend past the edges of an image of `width` by `height`
+/// pixels.
+fn clipped(
+    bounds: impl Iterator<Item = Rect<i32>>,
+    (width, height): (u32, u32),
+) -> Result<(), StrictError> {
+    let (mut left, mut top, mut right, mut bottom) = (0, 0, 0, 0);
+    for bounds in bounds {
+        left = left.max(-i64::from(bounds.min.x));
+        top = top.max(-i64::from(bounds.min.y));
+        right = right.max(i64::from(bounds.max.x) - i64::from(width));
+        bottom = bottom.max(i64::from(bounds.max.y) - i64::from(height));
+    }
+    let past = |distance: i64| u32::try_from(distance).unwrap_or(u32::MAX);
+    match (left, top, right, bottom) {
+        (0, 0, 0, 0) => Ok(()),
+        _ => Err(StrictError::ClippedGlyphs {
+            left: past(left),
+            top: past(top),
+            right: past(right),
+            bottom: past(bottom),
+        }),
+    }
+}
+
+/// Whether a font has kerning, in a kern table or as a kern feature of its GPOS table.
+///
+/// Fonts without data (from [`Font::try_from_bytes`]) can't be checked, so they are assumed to have kerning.
+fn has_kerning(font: &Font) -> bool {
+    let Some(face) = font_data(font).and_then(|data| Face::parse(data, 0).ok()) else {
+        return true;
+    };
+    let tables = face.tables();
+    tables.kern.is_some()
+        || tables
+            .gpos
+            .is_some_and(|gpos| gpos.features.find(Tag::from_bytes(b"kern")).is_some())
+}
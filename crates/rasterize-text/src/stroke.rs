@@ -0,0 +1,111 @@
+//! Draw only the outlines of glyphs, as hollow text.
+
+use crate::Options;
+use rusttype::{point, OutlineBuilder, Point, PositionedGlyph};
+
+/// Number of line segments that each curve of an outline is split into.
+const CURVE_STEPS: usize = 8;
+
+/// Draw the coverage of the pixels of a positioned glyph with a function, in the same way as [`PositionedGlyph::draw`].
+///
+/// If the [`Options::stroke_width`] is provided, pixels are only covered within the stroke width inside the outline
+/// of the glyph, so hollow glyphs cover the same pixels as filled glyphs.
+pub(crate) fn draw<O: FnMut(u32, u32, f32)>(glyph: &PositionedGlyph, options: &Options, mut o: O) {
+    let Some(width) = options.stroke_width else {
+        return glyph.draw(o);
+    };
+    // The outline is relative to the pixel bounding box of the glyph, like the pixels that are drawn
+    let mut outline = Outline::default();
+    glyph.build_outline(&mut outline);
+    let width = width.max(0.0);
+    glyph.draw(|x, y, v| {
+        let center = point(x as f32 + 0.5, y as f32 + 0.5);
+        // The inner edge of the stroke is anti-aliased, and the outer edge keeps the coverage of the glyph
+        let inner = (width + 0.5 - outline.distance(center)).clamp(0.0, 1.0);
+        o(x, y, v * inner);
+    });
+}
+
+/// Returns the distance in pixels from a point to the nearest point of a line segment.
+pub(crate) fn distance_to_line(p: Point<f32>, [a, b]: [Point<f32>; 2]) -> f32 {
+    let (ab, ap) = (b - a, p - a);
+    let length = ab.x * ab.x + ab.y * ab.y;
+    let t = match length > 0.0 {
+        true => ((ap.x * ab.x + ap.y * ab.y) / length).clamp(0.0, 1.0),
+        false => 0.0,
+    };
+    let (dx, dy) = (ap.x - ab.x * t, ap.y - ab.y * t);
+    (dx * dx + dy * dy).sqrt()
+}
+
+/// The outline of a glyph, with its curves split into line segments.
+#[derive(Default)]
+struct Outline {
+    lines: Vec<[Point<f32>; 2]>,
+    /// The end of the last segment, and the start of the current contour.
+    last: Point<f32>,
+    start: Point<f32>,
+}
+
+impl Outline {
+    /// Returns the distance in pixels from a point to the nearest line of the outline.
+    fn distance(&self, p: Point<f32>) -> f32 {
+        self.lines
+            .iter()
+            .map(|&line| distance_to_line(p, line))
+            .fold(f32::INFINITY, f32::min)
+    }
+
+    /// Add a curve to the outline, as line segments through the points that a function returns from 0 to 1.
+    fn curve(&mut self, at: impl Fn(f32) -> Point<f32>) {
+        for step in 1..=CURVE_STEPS {
+            self.line_to_point(at(step as f32 / CURVE_STEPS as f32));
+        }
+    }
+
+    fn line_to_point(&mut self, p: Point<f32>) {
+        self.lines.push([self.last, p]);
+        self.last = p;
+    }
+}
+
+impl OutlineBuilder for Outline {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.last = point(x, y);
+        self.start = self.last;
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        self.line_to_point(point(x, y));
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let (p0, p1, p2) = (self.last, point(x1, y1), point(x, y));
+        self.curve(|t| {
+            let u = 1.0 - t;
+            let (a, b, c) = (u * u, 2.0 * u * t, t * t);
+            point(
+                a * p0.x + b * p1.x + c * p2.x,
+                a * p0.y + b * p1.y + c * p2.y,
+            )
+        });
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        let (p0, p1, p2, p3) = (self.last, point(x1, y1), point(x2, y2), point(x, y));
+        self.curve(|t| {
+            let u = 1.0 - t;
+            let (a, b, c, d) = (u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t);
+            point(
+                a * p0.x + b * p1.x + c * p2.x + d * p3.x,
+                a * p0.y + b * p1.y + c * p2.y + d * p3.y,
+            )
+        });
+    }
+
+    fn close(&mut self) {
+        if self.last != self.start {
+            self.line_to_point(self.start);
+        }
+    }
+}
@@ -0,0 +1,330 @@
+//! Subset a font to the glyphs of a text, so that it can be served as a smaller webfont next to the rasterized text.
+
+use crate::shape::font_data;
+use rusttype::Font;
+use rustybuzz::ttf_parser::{loca, Face, GlyphId, Tag};
+use rustybuzz::UnicodeBuffer;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Tables that are not copied to a subset, since they are no longer valid (ex. the digital signature).
+const DROPPED_TABLES: [&[u8; 4]; 1] = [b"DSIG"];
+
+#[derive(Debug, thiserror::Error)]
+pub enum SubsetError {
+    #[error("Failed to read the font data. Fonts are subset from the bytes they were read from (ex. with read_font_file).")]
+    FontDataError,
+    #[error("Only fonts with TrueType outlines (a glyf table) can be subset, not fonts with CFF outlines (ex. most .otf files).")]
+    UnsupportedFontError,
+    #[error("The {0} table of the font is malformed.")]
+    MalformedTableError(String),
+}
+
+/// Returns a subset of a font with only the glyphs that are needed to draw a text, as the bytes of a TrueType font.
+///
+/// The text (or a charset, such as `"0123456789:"` for a clock) is shaped with the font, so that the ligatures and
+/// other substitutions that it is drawn with are kept, along with the glyphs that they are built from. The glyph
+/// ids are kept too, so the layout and hinting tables still apply, but the outlines of the other glyphs are emptied,
+/// the character map only has the characters of the text, and the glyph names are removed. The subset can be
+/// written to a `.ttf` file, and served as a webfont that matches an image of the text.
+///
+/// The subset isn't minimal: since glyphs aren't renumbered, every glyph of the font keeps its (empty) entry in the
+/// `loca` and `hmtx` tables, and the layout tables (ex. `GSUB`, `GPOS` and `kern`) are copied as is.
+///
+/// Returns [`SubsetError::UnsupportedFontError`] for fonts with CFF outlines.
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{rasterize, subset_font, Color, EN_FONT, read_font_bytes};
+///
+/// let text  = "Hello, World!";
+/// let font  = read_font_bytes(EN_FONT)?;
+/// let bytes = subset_font(&font, text)?;
+/// std::fs::write("subset_font.ttf", &bytes)?;
+/// assert!(bytes.len() < EN_FONT.len() / 2);
+///
+/// // The text is drawn the same with the subset, but other characters are missing
+/// let subset = read_font_bytes(&bytes)?;
+/// let color  = Color::default();
+/// assert_eq!(rasterize(&text, &subset, 50.0, &color)?, rasterize(&text, &font, 50.0, &color)?);
+/// assert_eq!(subset.glyph('x').id().0, 0);
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+pub fn subset_font(font: &Font, text: &str) -> Result<Vec<u8>, SubsetError> {
+    let data = font_data(font).ok_or(SubsetError::FontDataError)?;
+    let face = Face::parse(data, 0).map_err(|_| SubsetError::FontDataError)?;
+    let face_tables = face.tables();
+    let raw = face.raw_face();
+    let glyf = raw.table(Tag::from_bytes(b"glyf"));
+    let loca = raw.table(Tag::from_bytes(b"loca")).and_then(|loca| {
+        loca::Table::parse(
+            face_tables.maxp.number_of_glyphs,
+            face_tables.head.index_to_location_format,
+            loca,
+        )
+    });
+    let (Some(glyf), Some(loca)) = (glyf, loca) else {
+        return Err(SubsetError::UnsupportedFontError);
+    };
+    let malformed = |table: &str| SubsetError::MalformedTableError(table.to_string());
+
+    // The characters of the text, and the glyphs that the text is shaped to, including the missing glyph
+    let characters: BTreeMap<u32, u16> = text
+        .chars()
+        .filter_map(|c| face.glyph_index(c).map(|id| (u32::from(c), id.0)))
+        .collect();
+    let mut glyphs: BTreeSet<u16> = characters.values().copied().collect();
+    glyphs.insert(0);
+    if let Some(shaper) = rustybuzz::Face::from_slice(data, 0) {
+        let mut buffer = UnicodeBuffer::new();
+        buffer.push_str(text);
+        let shaped = rustybuzz::shape(&shaper, &[], buffer);
+        glyphs.extend(shaped.glyph_infos().iter().map(|info| info.glyph_id as u16));
+    }
+    // Composite glyphs are built from other glyphs, which are kept too
+    let mut pending: Vec<u16> = glyphs.iter().copied().collect();
+    while let Some(id) = pending.pop() {
+        let outline = loca
+            .glyph_range(GlyphId(id))
+            .and_then(|range| glyf.get(range))
+            .unwrap_or_default();
+        for component in components(outline).ok_or_else(|| malformed("glyf"))? {
+            if glyphs.insert(component) {
+                pending.push(component);
+            }
+        }
+    }
+
+    // The outlines of the glyphs that aren't kept are empty, with long offsets
+    let mut new_glyf = Vec::new();
+    let mut new_loca = Vec::new();
+    for id in 0..face.number_of_glyphs() {
+        new_loca.extend((new_glyf.len() as u32).to_be_bytes());
+        if glyphs.contains(&id) {
+            if let Some(outline) = loca
+                .glyph_range(GlyphId(id))
+                .and_then(|range| glyf.get(range))
+            {
+                new_glyf.extend(outline);
+                new_glyf.resize(new_glyf.len().next_multiple_of(4), 0);
+            }
+        }
+    }
+    new_loca.extend((new_glyf.len() as u32).to_be_bytes());
+
+    let mut tables = Vec::new();
+    for record in raw.table_records {
+        let tag = record.tag.to_bytes();
+        let table = raw
+            .table(record.tag)
+            .ok_or_else(|| malformed(&record.tag.to_string()))?;
+        let table = match &tag {
+            b"glyf" => std::mem::take(&mut new_glyf),
+            b"loca" => std::mem::take(&mut new_loca),
+            b"cmap" => cmap(&characters),
+            b"head" if table.len() >= 54 => {
+                let mut head = table.to_vec();
+                // The checksum adjustment is set once the font is written, and offsets are long
+                head[8..12].fill(0);
+                head[50..52].copy_from_slice(&1_i16.to_be_bytes());
+                head
+            }
+            b"head" => return Err(malformed("head")),
+            // Version 3 of the post table has no glyph names
+            b"post" if table.len() >= 32 => {
+                let mut post = table[..32].to_vec();
+                post[..4].copy_from_slice(&0x0003_0000_u32.to_be_bytes());
+                post
+            }
+            _ if DROPPED_TABLES.contains(&&tag) => continue,
+            _ => table.to_vec(),
+        };
+        tables.push((tag, table));
+    }
+    tables.sort_by_key(|(tag, _)| *tag);
+
+    Ok(write_font(&tables))
+}
+
+/// Returns the glyphs that a composite glyph is built from, which is none for a simple glyph, or [`None`] if the
+/// glyph is malformed.
+fn components(outline: &[u8]) -> Option<Vec<u16>> {
+    const ARGS_ARE_WORDS: u16 = 0x0001;
+    const HAS_SCALE: u16 = 0x0008;
+    const MORE_COMPONENTS: u16 = 0x0020;
+    const HAS_X_AND_Y_SCALE: u16 = 0x0040;
+    const HAS_TWO_BY_TWO: u16 = 0x0080;
+
+    let read = |offset: usize| -> Option<u16> {
+        let bytes = outline.get(offset..offset + 2)?;
+        Some(u16::from_be_bytes([bytes[0], bytes[1]]))
+    };
+    let mut ids = Vec::new();
+    // Simple glyphs (and empty ones) have a number of contours that isn't negative
+    if outline.is_empty() || (read(0)? as i16) >= 0 {
+        return Some(ids);
+    }
+    let mut offset = 10;
+    loop {
+        let flags = read(offset)?;
+        ids.push(read(offset + 2)?);
+        offset += 4 + if flags & ARGS_ARE_WORDS != 0 { 4 } else { 2 };
+        if flags & HAS_SCALE != 0 {
+            offset += 2;
+        } else if flags & HAS_X_AND_Y_SCALE != 0 {
+            offset += 4;
+        } else if flags & HAS_TWO_BY_TWO != 0 {
+            offset += 8;
+        }
+        if flags & MORE_COMPONENTS == 0 {
+            return Some(ids);
+        }
+    }
+}
+
+/// Returns a character map (cmap table) from characters to glyphs, with a format 4 subtable for the characters of
+/// the Basic Multilingual Plane, and a format 12 subtable for all characters if some are outside of it (or if there
+/// are too many characters for format 4).
+fn cmap(characters: &BTreeMap<u32, u16>) -> Vec<u8> {
+    // Consecutive characters that are mapped to consecutive glyphs are grouped together
+    let mut groups: Vec<(u32, u32, u16)> = Vec::new();
+    for (&c, &id) in characters {
+        match groups.last_mut() {
+            Some((start, end, start_id))
+                if *end + 1 == c && u32::from(*start_id) + (c - *start) == u32::from(id) =>
+            {
+                *end = c
+            }
+            _ => groups.push((c, c, id)),
+        }
+    }
+
+    let mut subtables: Vec<(u16, Vec<u8>)> = Vec::new();
+    // Format 4 ends with a segment for the character 0xFFFF, and its length is at most 65535 bytes
+    let mut segments: Vec<(u16, u16, u16)> = groups
+        .iter()
+        .filter(|(start, _, _)| *start < 0xFFFF)
+        .map(|&(start, end, id)| (start as u16, end.min(0xFFFE) as u16, id))
+        .collect();
+    segments.push((0xFFFF, 0xFFFF, 0));
+    if let Some(count) = u16::try_from(segments.len())
+        .ok()
+        .filter(|count| *count <= 8190)
+    {
+        subtables.push((1, format_4(&segments, count)));
+    }
+    if subtables.is_empty() || groups.iter().any(|(_, end, _)| *end > 0xFFFF) {
+        let mut format_12 = Vec::new();
+        format_12.extend(12_u16.to_be_bytes());
+        format_12.extend(0_u16.to_be_bytes());
+        for value in [16 + 12 * groups.len() as u32, 0, groups.len() as u32] {
+            format_12.extend(value.to_be_bytes());
+        }
+        for (start, end, id) in &groups {
+            for value in [*start, *end, u32::from(*id)] {
+                format_12.extend(value.to_be_bytes());
+            }
+        }
+        subtables.push((10, format_12));
+    }
+
+    // The subtables are for the Windows platform (3), with Unicode BMP (1) and full Unicode (10) encodings
+    let mut table = Vec::new();
+    table.extend(0_u16.to_be_bytes());
+    table.extend((subtables.len() as u16).to_be_bytes());
+    let mut offset = 4 + 8 * subtables.len() as u32;
+    for (encoding, subtable) in &subtables {
+        table.extend(3_u16.to_be_bytes());
+        table.extend(encoding.to_be_bytes());
+        table.extend(offset.to_be_bytes());
+        offset += subtable.len() as u32;
+    }
+    for (_, subtable) in subtables {
+        table.extend(subtable);
+    }
+    table
+}
+
+/// Returns a format 4 subtable of a character map, with a number of segments of characters that are mapped to
+/// consecutive glyphs.
+fn format_4(segments: &[(u16, u16, u16)], count: u16) -> Vec<u8> {
+    let search_range = 2 * 2_u16.pow(count.ilog2());
+    let mut subtable = Vec::new();
+    for value in [
+        4,
+        16 + 8 * count,
+        0,
+        2 * count,
+        search_range,
+        count.ilog2() as u16,
+        2 * count - search_range,
+    ] {
+        subtable.extend(value.to_be_bytes());
+    }
+    for (_, end, _) in segments {
+        subtable.extend(end.to_be_bytes());
+    }
+    subtable.extend(0_u16.to_be_bytes());
+    for (start, _, _) in segments {
+        subtable.extend(start.to_be_bytes());
+    }
+    for (start, _, id) in segments {
+        // The glyph of each character is the character plus the delta, modulo 65536
+        let delta = match start {
+            0xFFFF => 1,
+            _ => id.wrapping_sub(*start),
+        };
+        subtable.extend(delta.to_be_bytes());
+    }
+    subtable.extend(vec![0; 2 * segments.len()]);
+    subtable
+}
+
+/// Returns the bytes of a TrueType font with tables that are sorted by their tags, and sets the checksum
+/// adjustment of its head table.
+fn write_font(tables: &[([u8; 4], Vec<u8>)]) -> Vec<u8> {
+    let count = tables.len() as u16;
+    let search_range = 16 * 2_u16.pow(count.ilog2());
+    let mut font = Vec::new();
+    font.extend(0x0001_0000_u32.to_be_bytes());
+    for value in [
+        count,
+        search_range,
+        count.ilog2() as u16,
+        16 * count - search_range,
+    ] {
+        font.extend(value.to_be_bytes());
+    }
+
+    // Tables start after the table records, and are padded to 4 bytes
+    let mut offset = 12 + 16 * tables.len();
+    let mut head = None;
+    for (tag, table) in tables {
+        if tag == b"head" {
+            head = Some(offset);
+        }
+        font.extend(tag);
+        font.extend(checksum(table).to_be_bytes());
+        font.extend((offset as u32).to_be_bytes());
+        font.extend((table.len() as u32).to_be_bytes());
+        offset += table.len().next_multiple_of(4);
+    }
+    for (_, table) in tables {
+        font.extend(table);
+        font.resize(font.len().next_multiple_of(4), 0);
+    }
+    if let Some(head) = head {
+        let adjustment = 0xB1B0_AFBA_u32.wrapping_sub(checksum(&font));
+        font[head + 8..head + 12].copy_from_slice(&adjustment.to_be_bytes());
+    }
+    font
+}
+
+/// Returns the checksum of a table, which is the sum of its bytes as big-endian 32-bit integers.
+fn checksum(table: &[u8]) -> u32 {
+    table.chunks(4).fold(0_u32, |sum, chunk| {
+        let mut bytes = [0; 4];
+        bytes[..chunk.len()].copy_from_slice(chunk);
+        sum.wrapping_add(u32::from_be_bytes(bytes))
+    })
+}
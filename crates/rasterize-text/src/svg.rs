@@ -0,0 +1,181 @@
+//! Draw the glyphs of fonts that are SVG documents (the SVG table of OpenType fonts) with resvg.
+
+use crate::channel::Channel;
+use crate::shape::font_data;
+use crate::{Color, Options};
+use image::{ImageBuffer, Pixel, Rgba};
+use resvg::tiny_skia::{Pixmap, Transform};
+use resvg::usvg::Tree;
+use rusttype::PositionedGlyph;
+use rustybuzz::ttf_parser::{Face, GlyphId};
+use std::collections::HashMap;
+
+/// The SVG documents of the glyphs that are drawn to an image, which are parsed once for each font, document and
+/// color of the text.
+#[derive(Default)]
+pub(crate) struct SvgGlyphs<'a> {
+    faces: HashMap<*const u8, Option<Face<'a>>>,
+    trees: HashMap<(*const u8, [u8; 3]), Option<Tree>>,
+}
+
+impl<'a> SvgGlyphs<'a> {
+    /// Draw a positioned glyph from its SVG document in a color to an [`ImageBuffer`], blending it in the color space
+    /// of the [`Options::blending`]. Returns false if the font has no SVG document for the glyph (or the document is
+    /// malformed), and the glyph is drawn from its outline instead.
+    ///
+    /// The current color of the document (ex. `fill="currentColor"`) is the color of the text, and the document is
+    /// faded with the alpha of the text.
+    pub(crate) fn draw<C: Channel>(
+        &mut self,
+        image_buffer: &mut ImageBuffer<Rgba<C>, Vec<C>>,
+        glyph: &'a PositionedGlyph,
+        color: &Color,
+        options: &Options,
+    ) -> bool
+    where
+        Rgba<C>: Pixel<Subpixel = C>,
+    {
+        let font = glyph.unpositioned().font();
+        let Some(data) = font_data(font) else {
+            return false;
+        };
+        let face = self.faces.entry(data.as_ptr()).or_insert_with(|| {
+            Face::parse(data, 0)
+                .ok()
+                .filter(|face| face.tables().svg.is_some())
+        });
+        let id = glyph.id().0;
+        let Some(document) = face
+            .as_ref()
+            .and_then(|face| face.glyph_svg_image(GlyphId(id)))
+        else {
+            return false;
+        };
+        let key = (document.data.as_ptr(), [color.r, color.g, color.b]);
+        let tree = self
+            .trees
+            .entry(key)
+            .or_insert_with(|| parse(document.data, color));
+        let Some(tree) = tree else {
+            return false;
+        };
+
+        // Documents are in font units, which are scaled like the outlines, to the height from the ascent to the descent
+        let metrics = font.v_metrics_unscaled();
+        let scale = glyph.scale().y / (metrics.ascent - metrics.descent);
+        // A document can have several glyphs, which are the elements with the ids of the glyphs
+        let node = tree.node_by_id(&format!("glyph{id}"));
+        let bounds = match node {
+            Some(node) => node.abs_layer_bounding_box(),
+            None => Some(tree.root().abs_layer_bounding_box()),
+        };
+        let Some(bounds) = bounds else {
+            return true;
+        };
+        let position = glyph.position();
+        let (x, y) = (
+            position.x + bounds.x() * scale,
+            position.y + bounds.y() * scale,
+        );
+        let (left, top) = (x.floor(), y.floor());
+        let width = (bounds.width() * scale + x - left).ceil() as u32;
+        let height = (bounds.height() * scale + y - top).ceil() as u32;
+        if u64::from(width) * u64::from(height) > options.max_pixels {
+            return true;
+        }
+        let Some(mut pixmap) = Pixmap::new(width, height) else {
+            return true;
+        };
+        let transform = Transform::from_translate(x - left, y - top).pre_scale(scale, scale);
+        match node {
+            Some(node) => {
+                resvg::render_node(node, transform, &mut pixmap.as_mut());
+            }
+            None => {
+                let transform = transform.pre_translate(-bounds.x(), -bounds.y());
+                resvg::render(tree, transform, &mut pixmap.as_mut());
+            }
+        }
+        over(
+            image_buffer,
+            &pixmap,
+            (left as i64, top as i64),
+            color,
+            options,
+        );
+        true
+    }
+}
+
+/// Returns the tree of an SVG document (or a gzip compressed document), with its current color replaced by the color
+/// of the text.
+fn parse(data: &[u8], color: &Color) -> Option<Tree> {
+    let hex = format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b);
+    let svg = match std::str::from_utf8(data) {
+        Ok(svg) => svg.replace("currentColor", &hex).into_bytes(),
+        Err(_) => data.to_vec(),
+    };
+    Tree::from_data(&svg, &resvg::usvg::Options::default())
+        .inspect_err(|error| log::warn!("Failed to parse the SVG document of a glyph: {error}"))
+        .ok()
+}
+
+/// Composite a rendered SVG document over an [`ImageBuffer`], with its top left corner at a position, like a glyph
+/// whose coverage is the alpha of the document.
+fn over<C: Channel>(
+    image_buffer: &mut ImageBuffer<Rgba<C>, Vec<C>>,
+    pixmap: &Pixmap,
+    (left, top): (i64, i64),
+    color: &Color,
+    options: &Options,
+) where
+    Rgba<C>: Pixel<Subpixel = C>,
+{
+    for (i, pixel) in pixmap.pixels().iter().enumerate() {
+        let x = left + i64::from(i as u32 % pixmap.width());
+        let y = top + i64::from(i as u32 / pixmap.width());
+        let (Ok(x), Ok(y)) = (u32::try_from(x), u32::try_from(y)) else {
+            continue;
+        };
+        let Some(existing) = image_buffer.get_pixel_mut_checked(x, y) else {
+            continue;
+        };
+        let pixel = pixel.demultiply();
+        let coverage = f32::from(pixel.alpha()) / 255.0;
+        let straight = Color {
+            r: pixel.red(),
+            g: pixel.green(),
+            b: pixel.blue(),
+            a: color.a,
+        };
+        let source = C::color(&straight, options.blending).map(|channel| channel * coverage);
+        let behind = 1.0 - source[3] / C::MAX;
+        for (channel, source) in existing.0.iter_mut().zip(source) {
+            *channel = C::from_f32((source + channel.to_f32() * behind).round());
+        }
+    }
+}
+
+/// Returns the image of an SVG file (ex. an emoji asset of [`Options::twemoji`]), scaled to fit a square of a size in
+/// pixels.
+#[cfg(feature = "twemoji")]
+pub(crate) fn render_file(path: &std::path::Path, size: u32) -> Option<image::RgbaImage> {
+    let data = std::fs::read(path)
+        .inspect_err(|error| log::warn!("Failed to read the SVG file {path:?}: {error}"))
+        .ok()?;
+    let tree = Tree::from_data(&data, &resvg::usvg::Options::default())
+        .inspect_err(|error| log::warn!("Failed to parse the SVG file {path:?}: {error}"))
+        .ok()?;
+    let mut pixmap = Pixmap::new(size, size)?;
+    let scale = size as f32 / tree.size().width().max(tree.size().height());
+    resvg::render(
+        &tree,
+        Transform::from_scale(scale, scale),
+        &mut pixmap.as_mut(),
+    );
+    let pixels = pixmap.pixels().iter().flat_map(|pixel| {
+        let pixel = pixel.demultiply();
+        [pixel.red(), pixel.green(), pixel.blue(), pixel.alpha()]
+    });
+    image::RgbaImage::from_raw(size, size, pixels.collect())
+}
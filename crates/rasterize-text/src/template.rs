@@ -0,0 +1,380 @@
+//! Render layouts of text described in TOML or JSON templates, with `{placeholders}` filled in by variables (ex. for cards that are edited without code changes).
+
+use crate::card::{fit, over, Anchor, Fit};
+use crate::{
+    check_size, rasterize_with_options, read_font_file, Align, Color, ColorError, FontError,
+    Options, OptionsError, RasterizeError,
+};
+use image::{imageops, ImageBuffer, Rgba, RgbaImage};
+use rusttype::Font;
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+
+#[derive(Debug, thiserror::Error)]
+pub enum TemplateError {
+    #[error("Failed to parse the TOML template: {0}")]
+    TomlParseError(#[from] toml::de::Error),
+    #[error("Failed to parse the JSON template: {0}")]
+    JsonParseError(#[from] serde_json::Error),
+    #[error("Missing variable {0:?} for a placeholder of the template.")]
+    MissingVariableError(String),
+    #[error("Unclosed placeholder in {0:?}. Placeholders are names in braces (ex. '{{title}}'), and braces are escaped by doubling them ('{{{{').")]
+    UnclosedPlaceholderError(String),
+    #[error("Unknown font {0:?}. Expected one of the fonts of the template.")]
+    UnknownFontError(String),
+    #[cfg(feature = "tera")]
+    #[error("Failed to render the Tera template {1:?}: {0}")]
+    TeraError(#[source] tera::Error, String),
+    #[error("Failed to read the background image: {1:?}.")]
+    ImageReadError(#[source] image::ImageError, PathBuf),
+    #[error(transparent)]
+    FontError(#[from] FontError),
+    #[error(transparent)]
+    ColorError(#[from] ColorError),
+    #[error(transparent)]
+    OptionsError(#[from] OptionsError),
+    #[error(transparent)]
+    RasterizeError(#[from] RasterizeError),
+}
+
+/// A layout of text on a canvas, read from TOML with [`Template::from_toml`] or from JSON with
+/// [`Template::from_json`], and rendered with [`render_template`].
+///
+/// Colors, anchors and alignments are written as they are on the command line (ex. `"#1e1e2e"`, `"bottomright"`,
+/// `"center"`), and paths are relative to the current working directory. Fields that aren't part of the template
+/// (ex. misspelled fields) are errors, so that mistakes don't go unnoticed.
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::Template;
+///
+/// let template = Template::from_toml(r##"
+///     width      = 1200
+///     height     = 630
+///     background = "#1e1e2e"
+///
+///     [[elements]]
+///     text   = "{title}"
+///     x      = 600
+///     y      = 315
+///     anchor = "center"
+///     size   = 72
+///     color  = "white"
+/// "##)?;
+/// assert_eq!(template.elements[0].text, "{title}");
+///
+/// let json = Template::from_json(r#"{ "width": 1200, "height": 630, "elements": [{ "text": "{title}", "x": 600, "y": 315 }] }"#)?;
+/// assert_eq!(json.elements[0].x, 600.0);
+/// assert!(Template::from_toml("width = 1200\nheight = 630\nbackround = 'white'").is_err());
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+#[derive(Clone, Debug, PartialEq, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Template {
+    /// The width of the canvas in pixels.
+    pub width: u32,
+    /// The height of the canvas in pixels.
+    pub height: u32,
+    /// How the text and colors of the elements are filled in with variables.
+    #[serde(default)]
+    pub engine: TemplateEngine,
+    /// The color that the canvas is filled with. If no color is provided, the canvas is transparent.
+    #[serde(default)]
+    pub background: Option<String>,
+    /// An image that covers the canvas, over the background color, cropped to the canvas and centered (as with [`Fit::Cover`]).
+    #[serde(default)]
+    pub background_image: Option<PathBuf>,
+    /// Font files by name, which elements use by their names.
+    #[serde(default)]
+    pub fonts: BTreeMap<String, PathBuf>,
+    /// The texts drawn on the canvas, in order, so that later elements are drawn over earlier elements.
+    #[serde(default)]
+    pub elements: Vec<TemplateElement>,
+}
+
+/// How the text and colors of the elements of a [`Template`] are filled in with variables.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TemplateEngine {
+    /// Placeholders in braces (ex. `{title}`) are replaced by the values of variables.
+    #[default]
+    Placeholders,
+    /// The fields are [Tera](https://keats.github.io/tera/docs/) templates, with expressions (ex. `{{ title | upper }}`),
+    /// conditionals and loops over arrays (ex. the rows of a leaderboard), with the `tera` feature.
+    ///
+    /// Trailing newlines (ex. of the last iteration of a loop) are removed, so that they don't add an empty line.
+    #[cfg(feature = "tera")]
+    Tera,
+}
+
+/// A text of a [`Template`], placed at a point of the canvas.
+///
+/// Fields that are not provided are taken from the [`Options`] that the template is rendered with.
+#[derive(Clone, Debug, Default, PartialEq, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TemplateElement {
+    /// The text, with placeholders in braces (ex. `"{title}"`) that are replaced by the variables of the same names.
+    /// Elements whose text is empty once it is filled in (ex. by a conditional of [`TemplateEngine::Tera`]) aren't drawn.
+    pub text: String,
+    /// The horizontal position of the anchor of the text in pixels, from the left of the canvas.
+    pub x: f32,
+    /// The vertical position of the anchor of the text in pixels, from the top of the canvas.
+    pub y: f32,
+    /// The point of the text that is placed at the position (ex. `"center"`), which is the top left corner by default.
+    pub anchor: Option<String>,
+    /// The font size in pixels.
+    pub size: Option<f32>,
+    /// The color of the text, which can be a placeholder (ex. `"{accent}"`).
+    pub color: Option<String>,
+    /// The name of a font of the template. If no font is provided, the font that the template is rendered with is used.
+    pub font: Option<String>,
+    /// The width in pixels beyond which the text wraps onto the next line.
+    pub wrap_width: Option<f32>,
+    /// How the lines of the text are aligned (ex. `"right"`).
+    pub align: Option<String>,
+}
+
+impl Template {
+    /// Returns a template read from TOML.
+    pub fn from_toml(toml: &str) -> Result<Self, TemplateError> {
+        Ok(toml::from_str(toml)?)
+    }
+
+    /// Returns a template read from JSON.
+    pub fn from_json(json: &str) -> Result<Self, TemplateError> {
+        Ok(serde_json::from_str(json)?)
+    }
+}
+
+/// Returns a field of a template filled in with variables by the engine of the template.
+fn fill(
+    text: &str,
+    engine: TemplateEngine,
+    data: &Map<String, Value>,
+) -> Result<String, TemplateError> {
+    match engine {
+        TemplateEngine::Placeholders => substitute(text, data),
+        #[cfg(feature = "tera")]
+        TemplateEngine::Tera => {
+            let error = |e| TemplateError::TeraError(e, text.to_string());
+            let context = tera::Context::from_serialize(data).map_err(error)?;
+            let filled = tera::Tera::one_off(text, &context, false).map_err(error)?;
+            Ok(filled.trim_end_matches('\n').to_string())
+        }
+    }
+}
+
+/// Returns text with its placeholders (ex. `{title}`) replaced by the values of variables, where variables that
+/// aren't strings are replaced by their JSON (ex. `42`).
+///
+/// Doubled braces (`{{` and `}}`) are replaced by single braces, and a single closing brace is kept as is.
+fn substitute(text: &str, data: &Map<String, Value>) -> Result<String, TemplateError> {
+    let mut substituted = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(brace) = rest.find(['{', '}']) {
+        substituted.push_str(&rest[..brace]);
+        let after = &rest[brace + 1..];
+        if rest[brace..].starts_with("{{") || rest[brace..].starts_with("}}") {
+            substituted.push_str(&rest[brace..brace + 1]);
+            rest = &after[1..];
+            continue;
+        }
+        if rest[brace..].starts_with('}') {
+            substituted.push('}');
+            rest = after;
+            continue;
+        }
+        let close = after
+            .find('}')
+            .ok_or_else(|| TemplateError::UnclosedPlaceholderError(text.to_string()))?;
+        let name = after[..close].trim();
+        match data.get(name) {
+            Some(Value::String(value)) => substituted.push_str(value),
+            Some(value) => substituted.push_str(&value.to_string()),
+            None => Err(TemplateError::MissingVariableError(name.to_string()))?,
+        }
+        rest = &after[close + 1..];
+    }
+    substituted.push_str(rest);
+    Ok(substituted)
+}
+
+/// Render a [`Template`] to an [`ImageBuffer`] the size of its canvas, with its placeholders replaced by variables.
+///
+/// This is the same as [`render_template_with_data`], with variables that are strings.
+///
+/// Elements without a font are drawn with `font`, and the fields that an element doesn't have are taken from
+/// the [`Options`]. Text that extends past the edges of the canvas is cropped.
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{render_template, Options, Template, TemplateError, EN_FONT, read_font_bytes};
+/// use std::collections::HashMap;
+///
+/// let template = Template::from_toml(r##"
+///     width      = 600
+///     height     = 315
+///     background = "#1e1e2e"
+///
+///     [[elements]]
+///     text   = "{title}"
+///     x      = 300
+///     y      = 150
+///     anchor = "center"
+///     size   = 48
+///     color  = "{accent}"
+///
+///     [[elements]]
+///     text   = "by {author}"
+///     x      = 580
+///     y      = 295
+///     anchor = "bottomright"
+///     size   = 20
+///     color  = "#cdd6f4"
+/// "##)?;
+/// let vars = HashMap::from([
+///     ("title".to_string(), "Release 1.2".to_string()),
+///     ("author".to_string(), "Ferris".to_string()),
+///     ("accent".to_string(), "#f38ba8".to_string()),
+/// ]);
+/// let font  = read_font_bytes(EN_FONT)?;
+/// let image = render_template(&template, &vars, &font, &Options::default())?;
+/// image.save("render_template.png")?;
+/// assert_eq!(image.dimensions(), (600, 315));
+///
+/// let missing = render_template(&template, &HashMap::new(), &font, &Options::default());
+/// assert!(matches!(missing, Err(TemplateError::MissingVariableError(name)) if name == "title"));
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+pub fn render_template(
+    template: &Template,
+    vars: &HashMap<String, String>,
+    font: &Font,
+    options: &Options,
+) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>, TemplateError> {
+    let data = vars
+        .iter()
+        .map(|(name, value)| (name.clone(), Value::String(value.clone())))
+        .collect();
+    render_template_with_data(template, &data, font, options)
+}
+
+/// Render a [`Template`] to an [`ImageBuffer`] the size of its canvas, with its fields filled in with variables of
+/// any JSON type (ex. arrays of rows for the loops of [`TemplateEngine::Tera`]).
+///
+/// # Examples
+///
+/// ```rust
+/// # #[cfg(feature = "tera")]
+/// # {
+/// use rasterize_text::{render_template_with_data, Options, Template, EN_FONT, read_font_bytes};
+/// use serde_json::json;
+///
+/// let template = Template::from_toml(r##"
+///     width  = 400
+///     height = 300
+///     engine = "tera"
+///
+///     [[elements]]
+///     text = "{{ title | upper }}"
+///     size = 32
+///
+///     [[elements]]
+///     text = "{% for player in players %}{{ loop.index }}. {{ player.name }} ({{ player.score }})\n{% endfor %}"
+///     y    = 50
+///     size = 24
+///
+///     [[elements]]
+///     text  = "{% if players | length == 0 %}No players yet{% endif %}"
+///     y     = 250
+///     size  = 20
+/// "##)?;
+/// let data = json!({
+///     "title": "Leaderboard",
+///     "players": [{ "name": "Ferris", "score": 42 }, { "name": "Corro", "score": 37 }],
+/// });
+/// let font  = read_font_bytes(EN_FONT)?;
+/// let image = render_template_with_data(&template, data.as_object().unwrap(), &font, &Options::default())?;
+/// image.save("render_template_with_data.png")?;
+/// assert_eq!(image.dimensions(), (400, 300));
+/// # }
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+pub fn render_template_with_data(
+    template: &Template,
+    data: &Map<String, Value>,
+    font: &Font,
+    options: &Options,
+) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>, TemplateError> {
+    let engine = template.engine;
+    let (width, height) = check_size(template.width.into(), template.height.into(), options)?;
+    let mut image = RgbaImage::new(width, height);
+    if let Some(background) = &template.background {
+        let color: Color = fill(background, engine, data)?.parse()?;
+        let alpha = f32::from(color.a) / 255.0;
+        let premultiplied =
+            [color.r, color.g, color.b].map(|c| (f32::from(c) * alpha).round() as u8);
+        let [r, g, b] = premultiplied;
+        image
+            .pixels_mut()
+            .for_each(|pixel| *pixel = Rgba([r, g, b, color.a]));
+    }
+    if let Some(path) = &template.background_image {
+        let background =
+            image::open(path).map_err(|e| TemplateError::ImageReadError(e, path.clone()))?;
+        imageops::overlay(
+            &mut image,
+            &fit(&background, Fit::Cover, width, height),
+            0,
+            0,
+        );
+    }
+
+    let fonts = template
+        .fonts
+        .iter()
+        .map(|(name, path)| Ok((name.as_str(), read_font_file(path)?)))
+        .collect::<Result<HashMap<_, _>, TemplateError>>()?;
+    for element in &template.elements {
+        let text = fill(&element.text, engine, data)?;
+        if text.is_empty() {
+            continue;
+        }
+        let element_font = match &element.font {
+            Some(name) => fonts
+                .get(name.as_str())
+                .ok_or_else(|| TemplateError::UnknownFontError(name.clone()))?,
+            None => font,
+        };
+        let color = match &element.color {
+            Some(color) => fill(color, engine, data)?.parse()?,
+            None => options.color,
+        };
+        let align = match &element.align {
+            Some(align) => align.parse::<Align>()?,
+            None => options.align,
+        };
+        let anchor = match &element.anchor {
+            Some(anchor) => anchor.parse()?,
+            None => Anchor::TopLeft,
+        };
+        let element_options = Options {
+            size: element.size.unwrap_or(options.size),
+            color,
+            align,
+            wrap_width: element.wrap_width.or(options.wrap_width),
+            ..options.clone()
+        };
+        let rasterized = rasterize_with_options(&text, element_font, &element_options)?;
+
+        // The anchor of the text is placed at the position of the element
+        let (fx, fy) = anchor.fractions();
+        let x = element.x - rasterized.width() as f32 * fx;
+        let y = element.y - rasterized.height() as f32 * fy;
+        over(&mut image, &rasterized, x.round() as i64, y.round() as i64);
+    }
+    Ok(image)
+}
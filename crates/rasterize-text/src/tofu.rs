@@ -0,0 +1,131 @@
+//! Draw boxes ("tofu") with the codepoints of characters that no font supports, instead of their missing glyphs.
+
+use crate::layout::Line;
+use crate::span::{Decoration, DecorationKind, Segment};
+use crate::{Color, Ink, Tofu, TofuStyle};
+use rusttype::{point, Font, Point, PositionedGlyph, Rect, Scale};
+
+/// The smallest size in pixels of the codepoint in a box, below which the digits are unreadable and aren't drawn.
+const MIN_CODEPOINT_SIZE: f32 = 5.0;
+
+/// Returns the glyphs of the codepoints and the rectangles of the boxes of the missing glyphs (`.notdef`) of lines,
+/// with the first line's baseline starting at `origin`.
+///
+/// Each box spans the advance of its missing glyph, from the baseline up to the height of capitals, and the codepoint
+/// is drawn inside of it in two rows of hexadecimal digits in the main font, like Chrome and Firefox.
+pub(crate) fn boxes<'f>(
+    text: &str,
+    lines: &[Line],
+    segments: &[Segment],
+    font: &Font<'f>,
+    origin: Point<f32>,
+    tofu: &Tofu,
+) -> (Vec<(PositionedGlyph<'f>, Color, Ink)>, Vec<Decoration>) {
+    let mut glyphs = Vec::new();
+    let mut decorations = Vec::new();
+    for line in lines {
+        for glyph in line.glyphs.iter().filter(|glyph| glyph.id == 0) {
+            // Glyphs that were added during layout (ex. hyphens) are not part of the text
+            let Some(character) = text.get(glyph.cluster..).and_then(|s| s.chars().next()) else {
+                continue;
+            };
+            let segment = &segments[glyph.segment];
+            let color = segment.color;
+            let baseline = origin.y + line.y + glyph.y;
+            let ascent = font.v_metrics(Scale::uniform(segment.size)).ascent;
+            let thickness = (segment.size / 24.0).round().max(1.0);
+            let inset = glyph.advance * 0.08;
+            let (left, right) = (
+                origin.x + glyph.x + inset,
+                origin.x + glyph.x + glyph.advance - inset,
+            );
+            let (top, bottom) = (baseline - ascent * 0.8, baseline);
+
+            // Float to integer casts saturate, so boxes that are far away are drawn at the edge
+            let rect = |x: (f32, f32), y: (f32, f32)| Rect {
+                min: point(x.0.round() as i32, y.0.round() as i32),
+                max: point(x.1.round() as i32, y.1.round() as i32),
+            };
+            match tofu.style {
+                TofuStyle::Outline => {
+                    let sides = [
+                        rect((left, right), (top, top + thickness)),
+                        rect((left, right), (bottom - thickness, bottom)),
+                        rect((left, left + thickness), (top, bottom)),
+                        rect((right - thickness, right), (top, bottom)),
+                    ];
+                    decorations.extend(sides.into_iter().map(|rect| Decoration {
+                        rect,
+                        color,
+                        kind: DecorationKind::Foreground,
+                    }));
+                }
+                // The box is faint, so that the codepoint in front of it can be read
+                TofuStyle::Filled => decorations.push(Decoration {
+                    rect: rect((left, right), (top, bottom)),
+                    color: Color {
+                        a: color.a / 4,
+                        ..color
+                    },
+                    kind: DecorationKind::Background,
+                }),
+            }
+
+            if tofu.codepoint {
+                let padding = thickness * 2.0;
+                let inner = (right - left - padding * 2.0, bottom - top - padding * 2.0);
+                let center = point((left + right) / 2.0, top + padding + inner.1 / 4.0);
+                let digits = codepoint(character, font, center, inner);
+                glyphs.extend(digits.into_iter().map(|glyph| (glyph, color, Ink::First)));
+            }
+        }
+    }
+    (glyphs, decorations)
+}
+
+/// Returns the glyphs of the hexadecimal digits of the codepoint of a character in two rows, as large as fits in an
+/// area of (width, height), with the first row centered at `center` and the second row below it.
+fn codepoint<'f>(
+    character: char,
+    font: &Font<'f>,
+    center: Point<f32>,
+    (width, height): (f32, f32),
+) -> Vec<PositionedGlyph<'f>> {
+    let digits: Vec<char> = format!("{:04X}", u32::from(character)).chars().collect();
+    let (first, second) = digits.split_at(digits.len().div_ceil(2));
+    let row_width = |row: &[char], scale: Scale| {
+        row.iter()
+            .map(|&c| font.glyph(c).scaled(scale).h_metrics().advance_width)
+            .sum::<f32>()
+    };
+
+    // The rows are half of the height, and are made smaller to fit the width
+    let mut size = height / 2.0;
+    let widest = row_width(first, Scale::uniform(size));
+    if widest > width {
+        size *= width / widest;
+    }
+    if size < MIN_CODEPOINT_SIZE {
+        return Vec::new();
+    }
+    let scale = Scale::uniform(size);
+    // Digits are centered by their height, since they have no descenders
+    let digit_height = font
+        .glyph('0')
+        .scaled(scale)
+        .exact_bounding_box()
+        .map_or(size * 0.7, |bounds| -bounds.min.y);
+
+    let mut glyphs = Vec::new();
+    for (i, row) in [first, second].into_iter().enumerate() {
+        let baseline = (center.y + i as f32 * height / 2.0 + digit_height / 2.0).round();
+        let mut x = center.x - row_width(row, scale) / 2.0;
+        for &c in row {
+            let glyph = font.glyph(c).scaled(scale);
+            let advance = glyph.h_metrics().advance_width;
+            glyphs.push(glyph.positioned(point(x.round(), baseline)));
+            x += advance;
+        }
+    }
+    glyphs
+}
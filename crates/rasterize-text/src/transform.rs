@@ -0,0 +1,108 @@
+//! Change the case of text before it is laid out.
+
+use crate::{LanguageTag, TextTransform};
+use std::borrow::Cow;
+use unicode_normalization::UnicodeNormalization;
+use unicode_properties::{GeneralCategory, UnicodeGeneralCategory};
+
+/// Change the case of text with a [`TextTransform`], following the case rules of its language.
+///
+/// The `previous` character is the last character of the text before this text (ex. of the previous span),
+/// which decides whether the text starts a word when it is titlecased.
+///
+/// [`TextTransform::SmallCaps`] does not change the text, since small capitals are drawn by the shaper or
+/// synthesized from smaller capitals, so the text is returned as it is.
+pub(crate) fn transform<'t>(
+    text: Cow<'t, str>,
+    text_transform: TextTransform,
+    lang: Option<&LanguageTag>,
+    previous: Option<char>,
+) -> Cow<'t, str> {
+    let transformed = match text_transform {
+        TextTransform::None | TextTransform::SmallCaps => return text,
+        TextTransform::Uppercase => uppercase(&text, lang),
+        TextTransform::Lowercase => lowercase(&text, lang),
+        TextTransform::Titlecase => titlecase(&text, lang, previous),
+    };
+    // Case mapping can decompose characters (ex. `ǰ` is uppercased to `J` and a combining caron)
+    Cow::Owned(transformed.nfc().collect())
+}
+
+/// Whether the language has a dotted and a dotless i (Turkish and Azerbaijani).
+fn turkic(lang: Option<&LanguageTag>) -> bool {
+    lang.is_some_and(|lang| matches!(lang.language(), "tr" | "az"))
+}
+
+/// Uppercase text with the full Unicode case mappings (ex. `ß` is uppercased to `SS`).
+pub(crate) fn uppercase(text: &str, lang: Option<&LanguageTag>) -> String {
+    match turkic(lang) {
+        true => text.replace('i', "İ").to_uppercase(),
+        false => text.to_uppercase(),
+    }
+}
+
+/// Lowercase text with the full Unicode case mappings, including the final form of sigma at the end of a word.
+fn lowercase(text: &str, lang: Option<&LanguageTag>) -> String {
+    match turkic(lang) {
+        true => text.replace('I', "ı").replace('İ', "i").to_lowercase(),
+        false => text.to_lowercase(),
+    }
+}
+
+/// Titlecase the first letter of each word, and keep the case of the other letters.
+///
+/// A word starts at a letter or number that doesn't follow a letter, number, mark or apostrophe (ex. `don't` is one word).
+fn titlecase(text: &str, lang: Option<&LanguageTag>, mut previous: Option<char>) -> String {
+    let dutch = lang.is_some_and(|lang| lang.language() == "nl");
+    let mut titlecased = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let starts_word = c.is_alphanumeric() && !previous.is_some_and(joins_word);
+        previous = Some(c);
+        if !starts_word {
+            titlecased.push(c);
+            continue;
+        }
+        match c {
+            // Dutch capitalizes both letters of the digraph ij (ex. IJsselmeer)
+            'i' | 'I' if dutch && matches!(chars.peek(), Some('j' | 'J')) => {
+                titlecased.push_str("IJ");
+                previous = chars.next();
+            }
+            _ => titlecased.push_str(&titlecase_char(c, lang)),
+        }
+    }
+    titlecased
+}
+
+/// Whether a character continues the word before it, rather than separating it from the next word.
+fn joins_word(c: char) -> bool {
+    c.is_alphanumeric()
+        || matches!(c, '\'' | '’')
+        || matches!(
+            c.general_category(),
+            GeneralCategory::NonspacingMark
+                | GeneralCategory::SpacingMark
+                | GeneralCategory::EnclosingMark
+        )
+}
+
+/// Titlecase a character, which is the same as uppercasing it except for digraphs and ligatures.
+fn titlecase_char(c: char, lang: Option<&LanguageTag>) -> String {
+    match c {
+        // Digraphs have a titlecase form with only the first letter uppercased (ex. `ǆ` is titlecased to `ǅ`)
+        'Ǆ' | 'ǅ' | 'ǆ' => "ǅ".to_string(),
+        'Ǉ' | 'ǈ' | 'ǉ' => "ǈ".to_string(),
+        'Ǌ' | 'ǋ' | 'ǌ' => "ǋ".to_string(),
+        'Ǳ' | 'ǲ' | 'ǳ' => "ǲ".to_string(),
+        'i' if turkic(lang) => "İ".to_string(),
+        _ => {
+            // Characters that uppercase to several letters (ex. `ß` and `ﬁ`) only uppercase the first of them
+            let mut upper = c.to_uppercase();
+            let first = upper.next().unwrap_or(c);
+            std::iter::once(first)
+                .chain(upper.flat_map(char::to_lowercase))
+                .collect()
+        }
+    }
+}
@@ -0,0 +1,163 @@
+//! Substitute emoji with the images of Twemoji assets, which are scaled to the height of the line and drawn inline
+//! with the text, as a simpler alternative to color fonts.
+
+use crate::inline;
+use crate::layout::Line;
+use crate::span::{Decoration, Segment};
+use crate::Options;
+use image::imageops::FilterType;
+use image::RgbaImage;
+use rusttype::{point, Font, Point, Scale};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// The most characters of an emoji sequence (ex. the tag sequence of the flag of Scotland has 7).
+const MAX_SEQUENCE: usize = 10;
+
+/// The zero width joiner, which joins emoji into a sequence (ex. a family).
+const ZWJ: char = '\u{200D}';
+
+/// The emoji variation selector, which asks for a character to be drawn as an emoji rather than as text.
+const VS16: char = '\u{FE0F}';
+
+/// Returns the byte ranges of the emoji of text that have an asset in the directory of [`Options::twemoji`], and the
+/// paths of their assets.
+///
+/// The longest emoji sequence at each character is substituted (ex. a family, rather than its people). Characters that
+/// are drawn as text by default (ex. `©` or `™`) are only substituted when they are followed by the emoji variation
+/// selector (`U+FE0F`), like the parser of Twemoji.
+pub(crate) fn find(text: &str, options: &Options) -> Vec<(Range<usize>, PathBuf)> {
+    let Some(directory) = &options.twemoji else {
+        return Vec::new();
+    };
+    let starts: Vec<usize> = text
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain(std::iter::once(text.len()))
+        .collect();
+    let mut found = Vec::new();
+    let mut i = 0;
+    while i + 1 < starts.len() {
+        let last = (i + MAX_SEQUENCE).min(starts.len() - 1);
+        let longest = (i + 1..=last).rev().find_map(|j| {
+            let sequence = &text[starts[i]..starts[j]];
+            let mut chars = sequence.chars();
+            if let (Some(c), None) = (chars.next(), chars.next()) {
+                if c < '\u{2100}' {
+                    return None;
+                }
+            }
+            emojis::get(sequence)?;
+            asset(directory, sequence).map(|path| (j, path))
+        });
+        match longest {
+            Some((j, path)) => {
+                found.push((starts[i]..starts[j], path));
+                i = j;
+            }
+            None => i += 1,
+        }
+    }
+    found
+}
+
+/// Returns the path of the asset of an emoji in a directory, if it exists.
+///
+/// Assets are named by the lowercase hexadecimal codepoints of the emoji, joined by `-` (ex. `1f468-200d-1f4bb.png`).
+/// Twemoji leaves out the emoji variation selector of emoji that aren't joined, so names without it are tried first.
+fn asset(directory: &Path, emoji: &str) -> Option<PathBuf> {
+    let name = |skip: Option<char>| {
+        emoji
+            .chars()
+            .filter(|c| Some(*c) != skip)
+            .map(|c| format!("{:x}", c as u32))
+            .collect::<Vec<_>>()
+            .join("-")
+    };
+    let mut names = vec![name(None)];
+    if !emoji.contains(ZWJ) && emoji.contains(VS16) {
+        names.insert(0, name(Some(VS16)));
+    }
+    let extensions: &[&str] = match cfg!(feature = "svg") {
+        true => &["png", "svg"],
+        false => &["png"],
+    };
+    names
+        .iter()
+        .flat_map(|name| {
+            extensions
+                .iter()
+                .map(move |extension| format!("{name}.{extension}"))
+        })
+        .map(|file| directory.join(file))
+        .find(|path| path.is_file())
+}
+
+/// Returns the images of the emoji of lines, in the advances that were reserved for them by the shaper,
+/// with the first line's baseline starting at `origin`.
+///
+/// Each image is a square that spans the height of the line, from the ascent to the descent of the main font, and is
+/// faded with the alpha of the text. Images that can't be read are logged and left out.
+pub(crate) fn images(
+    text: &str,
+    lines: &[Line],
+    segments: &[Segment],
+    font: &Font,
+    origin: Point<f32>,
+    options: &Options,
+) -> Vec<Decoration> {
+    let emoji = find(text, options);
+    if emoji.is_empty() {
+        return Vec::new();
+    }
+    let mut images: HashMap<(&Path, u32), Option<Arc<RgbaImage>>> = HashMap::new();
+    let mut decorations = Vec::new();
+    for line in lines {
+        // The other glyphs of an emoji have no advance, so each emoji is drawn once
+        for glyph in line.glyphs.iter().filter(|glyph| glyph.advance > 0.0) {
+            let Some((_, path)) = emoji
+                .iter()
+                .find(|(range, _)| range.contains(&glyph.cluster))
+            else {
+                continue;
+            };
+            let segment = &segments[glyph.segment];
+            let metrics = font.v_metrics(Scale::uniform(segment.size));
+            let size = (metrics.ascent - metrics.descent).round();
+            if !(size >= 1.0 && size * size <= options.max_pixels as f32) {
+                continue;
+            }
+            let emoji = images
+                .entry((path, size as u32))
+                .or_insert_with(|| load(path, size as u32).map(Arc::new));
+            let Some(emoji) = emoji else {
+                continue;
+            };
+            let position = point(
+                origin.x + glyph.x,
+                origin.y + line.y + glyph.y - metrics.ascent,
+            );
+            decorations.extend(inline::image(Arc::clone(emoji), position, segment.color.a));
+        }
+    }
+    decorations
+}
+
+/// Returns the image of an asset, scaled to a square of a size in pixels.
+fn load(path: &Path, size: u32) -> Option<RgbaImage> {
+    #[cfg(feature = "svg")]
+    if path.extension().is_some_and(|extension| extension == "svg") {
+        return crate::svg::render_file(path, size);
+    }
+    let image = image::open(path)
+        .inspect_err(|error| log::warn!("Failed to read the emoji asset {path:?}: {error}"))
+        .ok()?;
+    Some(image::imageops::resize(
+        &image.to_rgba8(),
+        size,
+        size,
+        FilterType::Triangle,
+    ))
+}
@@ -0,0 +1,255 @@
+//! Read fonts from untrusted sources (ex. uploads to a service), with limits on their size, the time that they take
+//! to validate and the number that are validated at once, and sanity checks of their tables.
+
+use crate::{read_font_bytes, FontError};
+use rusttype::Font;
+use rustybuzz::ttf_parser::{Face, GlyphId, OutlineBuilder};
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// The tables that every font needs to be drawn: the character map, the header, the horizontal metrics and the
+/// maximum profile. Fonts also need outlines, in a `glyf` table (TrueType) or a `CFF ` or `CFF2` table (OpenType).
+const REQUIRED_TABLES: [&[u8; 4]; 5] = [b"cmap", b"head", b"hhea", b"hmtx", b"maxp"];
+
+/// The versions of single fonts, at the start of their files: TrueType (`1.0` or `true`) and OpenType with CFF outlines.
+const SFNT_VERSIONS: [&[u8; 4]; 3] = [&[0, 1, 0, 0], b"true", b"OTTO"];
+
+/// The number of fonts that are being validated, including those whose validation timed out but hasn't stopped yet.
+static VALIDATING: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Debug, thiserror::Error)]
+pub enum UntrustedFontError {
+    #[error("The font is {0} bytes, which is more than the limit of {1} bytes.")]
+    TooLargeError(usize, usize),
+    #[error("The font has {0} glyphs, which is more than the limit of {1} glyphs.")]
+    TooManyGlyphsError(usize, usize),
+    #[error("The font is not a single TrueType or OpenType font (ex. it is a font collection).")]
+    UnsupportedFormatError,
+    #[error("The table directory of the font is malformed: {0}.")]
+    MalformedTableError(String),
+    #[error("The font is missing the {0:?} table.")]
+    MissingTableError(String),
+    #[error("Validating the font took longer than {0:?}.")]
+    TimeoutError(Duration),
+    #[error("The limit of {0} fonts that are validated at once is reached.")]
+    BusyError(usize),
+    #[error("Validating the font panicked.")]
+    PanicError,
+    #[error(transparent)]
+    FontError(#[from] FontError),
+}
+
+/// Limits on a font from an untrusted source, with [`read_untrusted_font`].
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{read_untrusted_font, FontLimits, UntrustedFontError, EN_FONT};
+///
+/// let limits = FontLimits { max_bytes: 1 << 20, ..Default::default() };
+/// read_untrusted_font(EN_FONT, &limits)?;
+///
+/// // A font that is too large is refused before it is parsed
+/// let result = read_untrusted_font(&vec![0; 2 << 20], &limits);
+/// assert!(matches!(result, Err(UntrustedFontError::TooLargeError(_, _))));
+///
+/// // A table that extends past the end of the file is refused
+/// let truncated = &EN_FONT[..EN_FONT.len() / 2];
+/// let result = read_untrusted_font(truncated, &limits);
+/// assert!(matches!(result, Err(UntrustedFontError::MalformedTableError(_))));
+///
+/// // Fonts are refused while the limit of fonts are being validated
+/// let limits = FontLimits { max_concurrent: 0, ..Default::default() };
+/// let result = read_untrusted_font(EN_FONT, &limits);
+/// assert!(matches!(result, Err(UntrustedFontError::BusyError(0))));
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct FontLimits {
+    /// The most bytes of a font file.
+    pub max_bytes: usize,
+    /// The most glyphs of a font.
+    pub max_glyphs: usize,
+    /// The most time that validating a font takes. If a timeout is provided, the font is validated in another thread,
+    /// so that a font that makes the parser panic or take too long returns an error rather than wedging the caller.
+    pub timeout: Option<Duration>,
+    /// The most fonts that are validated at once, across threads. A font whose validation timed out is counted until
+    /// its thread stops, so fonts that take too long can't pile up threads.
+    pub max_concurrent: usize,
+}
+
+impl Default for FontLimits {
+    fn default() -> Self {
+        FontLimits {
+            // Large enough for the CJK fonts that are vendored (about 10 MiB)
+            max_bytes: 16 << 20,
+            max_glyphs: u16::MAX as usize,
+            timeout: Some(Duration::from_secs(5)),
+            max_concurrent: 8,
+        }
+    }
+}
+
+/// Read a font from an untrusted source (ex. a font that is uploaded to a service), within [`FontLimits`].
+///
+/// This is the same as [`read_font_bytes`], but the font is checked more carefully before it is used:
+///
+/// - The size of the font and its number of glyphs are limited.
+/// - Every table of the table directory must be within the file, and be listed once.
+/// - The tables that are needed to draw glyphs must be present.
+/// - The outline of every glyph is read, so that a font whose outlines make the parser panic or take too long is
+///   refused before text is drawn with it.
+///
+/// Validation returns [`UntrustedFontError::TimeoutError`] when it takes longer than the timeout of the limits, and its
+/// thread stops before it outlines the next glyph. A single glyph isn't interrupted, so the thread can outlive the
+/// timeout by the time that one glyph takes to outline. Fonts are refused with [`UntrustedFontError::BusyError`] while
+/// the most fonts of the limits are being validated.
+pub fn read_untrusted_font(
+    bytes: &[u8],
+    limits: &FontLimits,
+) -> Result<Font<'static>, UntrustedFontError> {
+    if bytes.len() > limits.max_bytes {
+        Err(UntrustedFontError::TooLargeError(
+            bytes.len(),
+            limits.max_bytes,
+        ))?;
+    }
+    check_tables(bytes)?;
+    let validating = Validating::start(limits.max_concurrent)?;
+    let Some(timeout) = limits.timeout else {
+        return validate(bytes, limits, None);
+    };
+    // The thread stops validating once the caller gives up, so a font that takes too long doesn't keep it running
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let (bytes, limits, cancel) = (bytes.to_vec(), *limits, cancelled.clone());
+    std::thread::spawn(move || {
+        let _validating = validating;
+        let result = validate(&bytes, &limits, Some(&cancel));
+        // The receiver is gone if it timed out
+        let _ = sender.send(result);
+    });
+    match receiver.recv_timeout(timeout) {
+        Ok(result) => result,
+        Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {
+            cancelled.store(true, Ordering::Relaxed);
+            Err(UntrustedFontError::TimeoutError(timeout))
+        }
+        // The sender is dropped without sending if the thread panicked
+        Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => Err(UntrustedFontError::PanicError),
+    }
+}
+
+/// A font that is being validated, which is counted in [`VALIDATING`] until it is dropped (even by a panic).
+struct Validating;
+
+impl Validating {
+    /// Count a font that is being validated, unless the most fonts are already being validated.
+    fn start(max_concurrent: usize) -> Result<Self, UntrustedFontError> {
+        VALIDATING
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |count| {
+                (count < max_concurrent).then_some(count + 1)
+            })
+            .map_err(|_| UntrustedFontError::BusyError(max_concurrent))?;
+        Ok(Validating)
+    }
+}
+
+impl Drop for Validating {
+    fn drop(&mut self) {
+        VALIDATING.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Check the table directory of a font: every table must be within the file and listed once, and the tables that
+/// are needed to draw glyphs must be present.
+fn check_tables(bytes: &[u8]) -> Result<(), UntrustedFontError> {
+    let malformed = |message: &str| UntrustedFontError::MalformedTableError(message.to_string());
+    let read_u32 = |offset: usize| {
+        let field = bytes.get(offset..offset + 4)?;
+        Some(u32::from_be_bytes(field.try_into().ok()?))
+    };
+    if !SFNT_VERSIONS
+        .iter()
+        .any(|version| bytes.get(0..4) == Some(&version[..]))
+    {
+        Err(UntrustedFontError::UnsupportedFormatError)?;
+    }
+    let count = bytes
+        .get(4..6)
+        .map(|field| u16::from_be_bytes([field[0], field[1]]) as usize)
+        .ok_or_else(|| malformed("the header is truncated"))?;
+
+    let mut tags = HashSet::new();
+    for record in (0..count).map(|i| 12 + 16 * i) {
+        let tag: [u8; 4] = bytes
+            .get(record..record + 4)
+            .and_then(|tag| tag.try_into().ok())
+            .ok_or_else(|| malformed("the table directory is truncated"))?;
+        let name = String::from_utf8_lossy(&tag).to_string();
+        let (Some(offset), Some(length)) = (read_u32(record + 8), read_u32(record + 12)) else {
+            Err(malformed("the table directory is truncated"))?
+        };
+        let end = (offset as usize).checked_add(length as usize);
+        if end.is_none_or(|end| end > bytes.len()) {
+            Err(malformed(&format!(
+                "the {name:?} table extends past the end of the file"
+            )))?;
+        }
+        if !tags.insert(tag) {
+            Err(malformed(&format!(
+                "the {name:?} table is listed more than once"
+            )))?;
+        }
+    }
+    if let Some(missing) = REQUIRED_TABLES.iter().find(|tag| !tags.contains(**tag)) {
+        Err(UntrustedFontError::MissingTableError(
+            String::from_utf8_lossy(*missing).to_string(),
+        ))?;
+    }
+    if ![b"glyf", b"CFF ", b"CFF2"]
+        .iter()
+        .any(|tag| tags.contains(*tag))
+    {
+        Err(UntrustedFontError::MissingTableError("glyf".to_string()))?;
+    }
+    Ok(())
+}
+
+/// Read a font, and the outline of every glyph, stopping before the next glyph once it is cancelled.
+fn validate(
+    bytes: &[u8],
+    limits: &FontLimits,
+    cancelled: Option<&AtomicBool>,
+) -> Result<Font<'static>, UntrustedFontError> {
+    let face = Face::parse(bytes, 0).map_err(|_| FontError::BytesReadError)?;
+    let glyphs = face.number_of_glyphs() as usize;
+    if glyphs > limits.max_glyphs {
+        Err(UntrustedFontError::TooManyGlyphsError(
+            glyphs,
+            limits.max_glyphs,
+        ))?;
+    }
+    for id in 0..face.number_of_glyphs() {
+        if cancelled.is_some_and(|cancelled| cancelled.load(Ordering::Relaxed)) {
+            Err(UntrustedFontError::TimeoutError(
+                limits.timeout.unwrap_or_default(),
+            ))?;
+        }
+        face.outline_glyph(GlyphId(id), &mut NoOutline);
+    }
+    Ok(read_font_bytes(bytes)?)
+}
+
+/// An outline builder that discards the outline, since glyphs are only outlined to check that they can be.
+struct NoOutline;
+
+impl OutlineBuilder for NoOutline {
+    fn move_to(&mut self, _: f32, _: f32) {}
+    fn line_to(&mut self, _: f32, _: f32) {}
+    fn quad_to(&mut self, _: f32, _: f32, _: f32, _: f32) {}
+    fn curve_to(&mut self, _: f32, _: f32, _: f32, _: f32, _: f32, _: f32) {}
+    fn close(&mut self) {}
+}
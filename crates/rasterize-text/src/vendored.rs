@@ -0,0 +1,122 @@
+//! The fonts that are provided within the application (vendored), with their names, versions, licenses and checksums.
+
+use crate::{read_font_bytes, FontError};
+use rusttype::Font;
+use siphasher::sip128::SipHasher13;
+use std::fmt::{Display, Formatter};
+
+/// A font that is provided within the application (vendored), so that there is a universal fallback.
+///
+/// The fonts are embedded at compile time, and each one carries the version and license of the font files in
+/// `assets/fonts`, and a checksum of its bytes, so that a changed font file is noticed.
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{VendoredFont, EN_FONT};
+///
+/// for vendored in VendoredFont::ALL {
+///     assert!(vendored.verify(), "{vendored} doesn't match its checksum");
+///     vendored.read()?;
+/// }
+/// assert_eq!(VendoredFont::DejaVuSans.bytes(), EN_FONT);
+/// assert_eq!(VendoredFont::NotoSansKR.license(), "OFL-1.1");
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum VendoredFont {
+    /// The English font DejaVu Sans, which is the default font ([`EN_FONT`](crate::EN_FONT)).
+    DejaVuSans,
+    /// The English bold font DejaVu Sans Bold ([`EN_BOLD_FONT`](crate::EN_BOLD_FONT)).
+    DejaVuSansBold,
+    /// The Korean font Noto Sans KR ([`KR_FONT`](crate::KR_FONT)).
+    NotoSansKR,
+    /// The Arabic font Amiri ([`AR_FONT`](crate::AR_FONT)), when the `arabic` feature is enabled.
+    #[cfg(feature = "arabic")]
+    Amiri,
+}
+
+impl VendoredFont {
+    /// Every vendored font, in the order that they are listed.
+    pub const ALL: &'static [VendoredFont] = &[
+        VendoredFont::DejaVuSans,
+        VendoredFont::DejaVuSansBold,
+        VendoredFont::NotoSansKR,
+        #[cfg(feature = "arabic")]
+        VendoredFont::Amiri,
+    ];
+
+    /// Returns the bytes of the font file.
+    pub const fn bytes(&self) -> &'static [u8] {
+        match self {
+            VendoredFont::DejaVuSans => {
+                include_bytes!("../../../assets/fonts/dejavu/DejaVuSans.ttf")
+            }
+            VendoredFont::DejaVuSansBold => {
+                include_bytes!("../../../assets/fonts/dejavu/DejaVuSans-Bold.ttf")
+            }
+            VendoredFont::NotoSansKR => include_bytes!("../../../assets/fonts/noto/NotoSansKR.ttf"),
+            #[cfg(feature = "arabic")]
+            VendoredFont::Amiri => include_bytes!("../../../assets/fonts/amiri/Amiri-Regular.ttf"),
+        }
+    }
+
+    /// Returns the full name of the font (ex. "DejaVu Sans Bold").
+    pub const fn name(&self) -> &'static str {
+        match self {
+            VendoredFont::DejaVuSans => "DejaVu Sans",
+            VendoredFont::DejaVuSansBold => "DejaVu Sans Bold",
+            VendoredFont::NotoSansKR => "Noto Sans KR",
+            #[cfg(feature = "arabic")]
+            VendoredFont::Amiri => "Amiri",
+        }
+    }
+
+    /// Returns the version of the font.
+    pub const fn version(&self) -> &'static str {
+        match self {
+            VendoredFont::DejaVuSans | VendoredFont::DejaVuSansBold => "2.37",
+            VendoredFont::NotoSansKR => "2.004",
+            #[cfg(feature = "arabic")]
+            VendoredFont::Amiri => "0.111",
+        }
+    }
+
+    /// Returns the license of the font, as an [SPDX](https://spdx.org/licenses/) identifier. The full license is in the
+    /// `LICENSE` file next to the font file.
+    pub const fn license(&self) -> &'static str {
+        match self {
+            VendoredFont::DejaVuSans | VendoredFont::DejaVuSansBold => "Bitstream-Vera",
+            VendoredFont::NotoSansKR => "OFL-1.1",
+            #[cfg(feature = "arabic")]
+            VendoredFont::Amiri => "OFL-1.1",
+        }
+    }
+
+    /// Returns the expected checksum of the font file, which is a 128-bit SipHash of its bytes.
+    pub const fn checksum(&self) -> u128 {
+        match self {
+            VendoredFont::DejaVuSans => 0xfbbce0bf9e5a6aa275976891a5c65c10,
+            VendoredFont::DejaVuSansBold => 0x3de8b441a24e16ec07bd297103b3265f,
+            VendoredFont::NotoSansKR => 0x5dea3315cefdde7fdd9e5d5e49bcbe66,
+            #[cfg(feature = "arabic")]
+            VendoredFont::Amiri => 0x2fd7b798ce88060da9029b5e4a2c4770,
+        }
+    }
+
+    /// Returns true if the bytes of the font file match its [`checksum`](VendoredFont::checksum).
+    pub fn verify(&self) -> bool {
+        SipHasher13::new().hash(self.bytes()).as_u128() == self.checksum()
+    }
+
+    /// Returns the font, read from its bytes with [`read_font_bytes`].
+    pub fn read(&self) -> Result<Font<'static>, FontError> {
+        read_font_bytes(self.bytes())
+    }
+}
+
+impl Display for VendoredFont {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
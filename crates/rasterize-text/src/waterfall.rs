@@ -0,0 +1,184 @@
+//! Draw a text at a list of sizes, in one or more fonts, to compare fonts and their hinting.
+
+use crate::span::{position_spans, Decoration};
+use crate::{draw, measure, strict, Color, Ink, Options, RasterizeError, Span, Style};
+use image::{ImageBuffer, Rgba};
+use rusttype::{point, Font, PositionedGlyph, Scale};
+
+/// The sizes of a waterfall, drawn by [`rasterize_waterfall`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Waterfall {
+    /// The sizes of the text in pixels, from the top row to the bottom row.
+    pub sizes: Vec<f32>,
+    /// The space between the rows and columns in pixels.
+    pub gap: u32,
+    /// The size of the labels of the sizes in pixels.
+    pub label_size: f32,
+    /// The color of the labels of the sizes, which are drawn to the left of the rows, if any.
+    pub label_color: Option<Color>,
+}
+
+impl Default for Waterfall {
+    fn default() -> Self {
+        Waterfall {
+            sizes: vec![
+                8.0, 9.0, 10.0, 11.0, 12.0, 14.0, 16.0, 18.0, 24.0, 36.0, 48.0, 72.0,
+            ],
+            gap: 16,
+            label_size: 12.0,
+            label_color: Some(Color {
+                r: 128,
+                g: 128,
+                b: 128,
+                a: 255,
+            }),
+        }
+    }
+}
+
+/// Rasterize a text at each of the sizes of a [`Waterfall`], stacked vertically, to an [`ImageBuffer`], as configured
+/// by [`Options`].
+///
+/// Each font is drawn in its own column, so that two (or more) fonts are compared side by side, and the text in each
+/// row is aligned on the same baseline. Small sizes show how the fonts are hinted and snapped to the pixel grid
+/// (see [`Options::pixel_snap`]), and the label of each size is drawn to the left of its row in the first font.
+/// The [`Options::size`] is not used, and the other options apply as with [`rasterize_spans`](crate::rasterize_spans),
+/// except for wrapping.
+///
+/// # Examples
+///
+/// ```rust
+/// use rasterize_text::{rasterize_waterfall, Options, Waterfall, EN_FONT, read_font_bytes};
+///
+/// let font      = read_font_bytes(EN_FONT)?;
+/// let waterfall = Waterfall::default();
+/// let image     = rasterize_waterfall("Hamburgefonstiv", &[&font], &waterfall, &Options::default())?;
+/// image.save("rasterize_waterfall.png")?;
+///
+/// // A second font is drawn in a column to the right of the first
+/// let side_by_side = rasterize_waterfall("Hamburgefonstiv", &[&font, &font], &waterfall, &Options::default())?;
+/// side_by_side.save("rasterize_waterfall_side_by_side.png")?;
+/// assert!(side_by_side.width() > image.width());
+/// assert_eq!(side_by_side.height(), image.height());
+/// # Ok::<(), color_eyre::eyre::Report>(())
+/// ```
+pub fn rasterize_waterfall(
+    text: &str,
+    fonts: &[&Font],
+    waterfall: &Waterfall,
+    options: &Options,
+) -> Result<ImageBuffer<Rgba<u8>, Vec<u8>>, RasterizeError> {
+    let options = Options {
+        wrap_width: None,
+        ..options.clone()
+    };
+    // The cells of each row, which are the label (if any) and then the text in each font, with the ascent of its font
+    let mut rows = Vec::new();
+    for size in &waterfall.sizes {
+        let mut cells = Vec::new();
+        if let (Some(color), Some(font)) = (waterfall.label_color, fonts.first()) {
+            let label = Span {
+                text: format!("{size}px"),
+                style: Style {
+                    color: Some(color),
+                    ..Default::default()
+                },
+                ruby: None,
+                image: None,
+            };
+            let label_options = Options {
+                size: waterfall.label_size,
+                line_numbers: None,
+                ..options.clone()
+            };
+            cells.push(cell(&label, font, &label_options)?);
+        }
+        for font in fonts {
+            let options = Options {
+                size: *size,
+                ..options.clone()
+            };
+            cells.push(cell(&Span::from(text), font, &options)?);
+        }
+        rows.push(cells);
+    }
+
+    // Columns are as wide as their widest cell, and rows are as tall as the cells on their shared baseline
+    let gap = i64::from(waterfall.gap);
+    let columns = rows.iter().map(Vec::len).max().unwrap_or_default();
+    let widths: Vec<i64> = (0..columns)
+        .map(|i| {
+            rows.iter()
+                .filter_map(|cells| cells.get(i))
+                .map(|cell| cell.width)
+                .max()
+                .unwrap_or_default()
+        })
+        .collect();
+    let mut glyphs = Vec::new();
+    let mut decorations = Vec::new();
+    let mut y = 0;
+    for cells in rows {
+        let baseline = cells
+            .iter()
+            .map(|cell| cell.ascent)
+            .max()
+            .unwrap_or_default();
+        let mut height = 0;
+        let mut x = 0;
+        for (cell, width) in cells.into_iter().zip(&widths) {
+            // Cells are moved by whole pixels, to keep their glyphs snapped
+            let (dx, dy) = (x, y + baseline - cell.ascent);
+            height = height.max(baseline - cell.ascent + cell.height);
+            glyphs.extend(cell.glyphs.into_iter().map(|(glyph, color, ink)| {
+                let position = glyph.position();
+                let position = point(position.x + dx as f32, position.y + dy as f32);
+                (glyph.into_unpositioned().positioned(position), color, ink)
+            }));
+            decorations.extend(cell.decorations.into_iter().map(|mut decoration| {
+                let (dx, dy) = (clamp(dx), clamp(dy));
+                decoration.rect.min.x = decoration.rect.min.x.saturating_add(dx);
+                decoration.rect.max.x = decoration.rect.max.x.saturating_add(dx);
+                decoration.rect.min.y = decoration.rect.min.y.saturating_add(dy);
+                decoration.rect.max.y = decoration.rect.max.y.saturating_add(dy);
+                decoration
+            }));
+            x += width + gap;
+        }
+        y += height + gap;
+    }
+    draw(&glyphs, &decorations, &options)
+}
+
+/// The positioned glyphs and decorations of a cell of a waterfall, with its size and the ascent of its font.
+struct Cell<'f> {
+    glyphs: Vec<(PositionedGlyph<'f>, Color, Ink)>,
+    decorations: Vec<Decoration>,
+    width: i64,
+    height: i64,
+    ascent: i64,
+}
+
+/// Returns the cell of a span in a font, as configured by [`Options`].
+fn cell<'f>(span: &Span, font: &Font<'f>, options: &Options) -> Result<Cell<'f>, RasterizeError> {
+    let spans = std::slice::from_ref(span);
+    let (glyphs, decorations, warnings) = position_spans(spans, font, options)?;
+    let (width, height) = measure(&glyphs, &decorations);
+    // Each cell is checked with its own font, since the fonts of a waterfall are compared rather than fallbacks
+    let size = |length: u64| length.min(u32::MAX.into()) as u32;
+    let size = (size(width), size(height));
+    strict::check(size, &glyphs, &warnings, font, options)?;
+    let ascent = font.v_metrics(Scale::uniform(options.size)).ascent;
+    Ok(Cell {
+        glyphs,
+        decorations,
+        width: clamp(width as i64).into(),
+        height: clamp(height as i64).into(),
+        ascent: ascent.round() as i64,
+    })
+}
+
+/// Returns a coordinate clamped to the range of an [`i32`].
+fn clamp(coordinate: i64) -> i32 {
+    coordinate.clamp(i64::from(i32::MIN), i64::from(i32::MAX)) as i32
+}
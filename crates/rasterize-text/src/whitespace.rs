@@ -0,0 +1,57 @@
+//! Trim and collapse the whitespace of text before it is laid out.
+
+use crate::Whitespace;
+use std::borrow::Cow;
+
+/// Trim and collapse the whitespace of the texts of spans as if they were one text, with a [`Whitespace`].
+///
+/// A run of whitespace that spans several texts is replaced in the text where it starts, and removed from the others.
+/// Preserved texts are returned as they are, without copying them.
+pub(crate) fn collapse(texts: Vec<Cow<str>>, whitespace: Whitespace) -> Vec<Cow<str>> {
+    if whitespace == Whitespace::Preserve {
+        return texts;
+    }
+    let mut collapsed = vec![String::new(); texts.len()];
+    // The run of whitespace since the last other character, and the text it starts in
+    let mut pending: Option<(usize, String)> = None;
+    let mut start = true;
+    for (i, text) in texts.iter().enumerate() {
+        for c in text.chars() {
+            if is_whitespace(c) {
+                pending.get_or_insert_with(|| (i, String::new())).1.push(c);
+                continue;
+            }
+            // Whitespace at the start of the text is trimmed
+            if let Some((j, run)) = pending.take().filter(|_| !start) {
+                collapsed[j].push_str(&replace(&run, whitespace));
+            }
+            collapsed[i].push(c);
+            start = false;
+        }
+    }
+    // Whitespace at the end of the text is trimmed, since it is still pending
+    collapsed.into_iter().map(Cow::Owned).collect()
+}
+
+/// Whether a character is whitespace that can be trimmed or collapsed, which excludes the no-break spaces.
+fn is_whitespace(c: char) -> bool {
+    c.is_whitespace() && !matches!(c, '\u{A0}' | '\u{2007}' | '\u{202F}')
+}
+
+/// Whether a character breaks the line (ex. a newline or a paragraph separator).
+pub(crate) fn is_line_break(c: char) -> bool {
+    matches!(
+        c,
+        '\n' | '\r' | '\u{B}' | '\u{C}' | '\u{85}' | '\u{2028}' | '\u{2029}'
+    )
+}
+
+/// Returns the replacement for a run of whitespace within the text.
+fn replace(run: &str, whitespace: Whitespace) -> String {
+    let breaks: String = run.chars().filter(|c| is_line_break(*c)).collect();
+    match whitespace {
+        Whitespace::Preserve | Whitespace::Trim => run.to_string(),
+        Whitespace::CollapseSpaces if !breaks.is_empty() => breaks,
+        Whitespace::Collapse | Whitespace::CollapseSpaces => " ".to_string(),
+    }
+}